@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::api::middleware::AppError;
+use crate::api::responses::{ApiKeyListResponse, PolicyListResponse, UserListResponse};
+use crate::api::handlers::connection::AppState;
+use crate::models::{
+    ConnectionPolicy, CreateApiKeyRequest, CreateApiKeyResponse, CreateConnectionPolicyRequest, CreateUserRequest, User,
+};
+use crate::services::{ApiKeyService, AuthService, ConnectionPolicyService};
+
+/// List API keys (secrets are never returned)
+///
+/// GET /api/admin/api-keys
+#[utoipa::path(
+    get,
+    path = "/api/admin/api-keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = ApiKeyListResponse),
+    ),
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+) -> Result<Json<ApiKeyListResponse>, AppError> {
+    let keys = ApiKeyService::new(state.storage.clone(), state.config.auth.clone())
+        .list()
+        .await?;
+
+    Ok(Json(ApiKeyListResponse { api_keys: keys }))
+}
+
+/// Issue a new API key with a scope (read, execute or admin)
+///
+/// POST /api/admin/api-keys
+///
+/// The plaintext key is only included in this response.
+#[utoipa::path(
+    post,
+    path = "/api/admin/api-keys",
+    tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Created", body = CreateApiKeyResponse),
+    ),
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), AppError> {
+    let created = ApiKeyService::new(state.storage.clone(), state.config.auth.clone())
+        .issue(payload)
+        .await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Revoke an API key
+///
+/// POST /api/admin/api-keys/{id}/revoke
+#[utoipa::path(
+    post,
+    path = "/api/admin/api-keys/{id}/revoke",
+    tag = "admin",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    ApiKeyService::new(state.storage.clone(), state.config.auth.clone())
+        .revoke(&id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List user accounts
+///
+/// GET /api/admin/users
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = UserListResponse),
+    ),
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+) -> Result<Json<UserListResponse>, AppError> {
+    let users = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .list_users()
+        .await?;
+
+    Ok(Json(UserListResponse { users }))
+}
+
+/// Create a user account
+///
+/// POST /api/admin/users
+#[utoipa::path(
+    post,
+    path = "/api/admin/users",
+    tag = "admin",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, description = "Created", body = User),
+    ),
+)]
+pub async fn create_user(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<User>), AppError> {
+    let user = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .create_user(payload)
+        .await?;
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+/// List connection policies
+///
+/// GET /api/admin/policies
+#[utoipa::path(
+    get,
+    path = "/api/admin/policies",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = PolicyListResponse),
+    ),
+)]
+pub async fn list_policies(
+    State(state): State<AppState>,
+) -> Result<Json<PolicyListResponse>, AppError> {
+    let policies = ConnectionPolicyService::new(state.storage.clone()).list().await?;
+
+    Ok(Json(PolicyListResponse { policies }))
+}
+
+/// Create a policy bound to a connection label selector
+///
+/// POST /api/admin/policies
+#[utoipa::path(
+    post,
+    path = "/api/admin/policies",
+    tag = "admin",
+    request_body = CreateConnectionPolicyRequest,
+    responses(
+        (status = 201, description = "Created", body = ConnectionPolicy),
+    ),
+)]
+pub async fn create_policy(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateConnectionPolicyRequest>,
+) -> Result<(StatusCode, Json<ConnectionPolicy>), AppError> {
+    let policy = ConnectionPolicyService::new(state.storage.clone())
+        .create(payload)
+        .await?;
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+/// Delete a connection policy
+///
+/// DELETE /api/admin/policies/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn delete_policy(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    ConnectionPolicyService::new(state.storage.clone())
+        .delete(&id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,48 @@
+use axum::{extract::State, Extension, Json};
+
+use crate::api::middleware::AppError;
+use crate::api::responses::WhoAmIResponse;
+use crate::api::handlers::connection::AppState;
+use crate::models::{LoginRequest, LoginResponse, Principal};
+use crate::services::AuthService;
+
+/// Log in with username and password and receive a JWT
+///
+/// POST /api/auth/login
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "OK", body = LoginResponse),
+    ),
+    security(()),
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let response = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .login(payload)
+        .await?;
+    Ok(Json(response))
+}
+
+/// Describe the authenticated caller
+///
+/// GET /api/auth/me
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "OK", body = WhoAmIResponse),
+    ),
+)]
+pub async fn me(
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<WhoAmIResponse>, AppError> {
+    let principal = principal.map(|Extension(principal)| principal);
+    Ok(Json(WhoAmIResponse::from_principal(principal.as_ref())))
+}
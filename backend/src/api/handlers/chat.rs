@@ -0,0 +1,236 @@
+// Chat Session Handlers
+//
+// Multi-turn natural language queries. Each turn keeps the question, the SQL
+// the LLM generated and a summary of the result (row count and columns), and
+// the latest turns of a session go into the prompt of the next question, so
+// follow-ups refine the previous query instead of starting from scratch.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Extension, Json,
+};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query::{execute_with_repair, natural_language_context};
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::api::responses::{ChatResponse, ChatSessionDeletedResponse, ChatSessionResponse};
+use crate::models::{ChatRequest, ChatSession, ChatTurn, DomainRole, Principal};
+use crate::services::ApiUsageService;
+
+/// Latest turns of a session given to the LLM as context
+const CONTEXT_TURNS: usize = 8;
+
+/// Characters of the first question kept as the session title
+const TITLE_MAX_CHARS: usize = 100;
+
+/// Ask a question in a chat session, starting a new session without `session_id`
+///
+/// POST /api/connections/{id}/chat
+///
+/// The question is answered with the earlier turns of the session as context,
+/// and the turn keeps the SQL that ran last (after any automatic repairs).
+/// A turn whose SQL fails is still recorded (with its error), so the next
+/// question can correct it; a failing first question starts no session.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/chat",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = ChatRequest,
+    responses(
+        (status = 200, description = "OK", body = ChatResponse),
+    ),
+)]
+pub async fn chat(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ChatRequest>,
+) -> Result<Json<ChatResponse>, AppError> {
+    let question = payload.question.trim();
+    let principal = principal.as_deref();
+
+    // Continue a session of this connection
+    let session = match payload.session_id.as_deref() {
+        Some(session_id) => {
+            let session = chat_session(&state, session_id, principal, DomainRole::Editor).await?;
+            if session.connection_id != id {
+                return Err(AppError::Validation(format!(
+                    "Chat session {} belongs to connection {}",
+                    session.id, session.connection_id
+                )));
+            }
+            Some(session)
+        }
+        None => None,
+    };
+    let mut history = match &session {
+        Some(session) => state
+            .storage
+            .list_chat_turns(&session.id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?,
+        None => Vec::new(),
+    };
+    history.drain(..history.len().saturating_sub(CONTEXT_TURNS));
+
+    let context = natural_language_context(&state, &id, question, &history, principal).await?;
+    if context.degraded {
+        // Over the LLM token budget: only the latest turn goes into the prompt
+        history.drain(..history.len().saturating_sub(1));
+    }
+
+    tracing::info!(
+        connection_id = %id,
+        question = %crate::logging::redactor().text(question),
+        turns = history.len(),
+        "Generating SQL for chat question"
+    );
+    let llm_service = context.llm_service(&state, principal).with_examples(context.examples.clone());
+    let generated = llm_service
+        .generate_sql_in_conversation(question, &history, &context.metadata, &context.connection.database_type)
+        .await;
+    ApiUsageService::new(state.storage.clone())
+        .record_llm_request(
+            context.connection.domain_id.as_deref(),
+            principal,
+            llm_service.token_usage(),
+            generated.is_err(),
+        )
+        .await;
+    let (generated_sql, executed) = execute_with_repair(
+        &state,
+        &context,
+        question,
+        &history,
+        generated?,
+        &headers,
+        principal,
+        payload.confirm_large_result,
+        None,
+    )
+    .await;
+
+    let (mut session, executed) = match (session, executed) {
+        (Some(session), executed) => (session, executed),
+        (None, Err(e)) => return Err(e),
+        (None, Ok(query)) => {
+            let title: String = question.chars().take(TITLE_MAX_CHARS).collect();
+            let session = ChatSession::new(
+                context.connection.id.clone(),
+                context.connection.domain_id.clone(),
+                principal.map(|p| p.subject()),
+                title,
+            );
+            state
+                .storage
+                .create_chat_session(&session)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            (session, Ok(query))
+        }
+    };
+
+    let turn = ChatTurn::new(session.id.clone(), question.to_string(), generated_sql);
+    let turn = match &executed {
+        Ok(query) => turn.with_result(query),
+        Err(e) => turn.with_error(e.to_string()),
+    };
+    state
+        .storage
+        .add_chat_turn(&turn)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    session.updated_at = turn.created_at;
+
+    Ok(Json(ChatResponse {
+        session,
+        turn,
+        query: executed?,
+    }))
+}
+
+/// Get a chat session with its turns
+///
+/// GET /api/chat-sessions/{id}
+///
+/// Sessions started by another principal need the Admin role.
+#[utoipa::path(
+    get,
+    path = "/api/chat-sessions/{id}",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = ChatSessionResponse),
+    ),
+)]
+pub async fn get_chat_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<ChatSessionResponse>, AppError> {
+    let session = chat_session(&state, &id, principal.as_deref(), DomainRole::Viewer).await?;
+    let turns = state
+        .storage
+        .list_chat_turns(&session.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(ChatSessionResponse { session, turns }))
+}
+
+/// Delete a chat session with its turns
+///
+/// DELETE /api/chat-sessions/{id}
+///
+/// Sessions started by another principal need the Admin role.
+#[utoipa::path(
+    delete,
+    path = "/api/chat-sessions/{id}",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Deleted", body = ChatSessionDeletedResponse),
+    ),
+)]
+pub async fn delete_chat_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<ChatSessionDeletedResponse>, AppError> {
+    let session = chat_session(&state, &id, principal.as_deref(), DomainRole::Editor).await?;
+    state
+        .storage
+        .delete_chat_session(&session.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(ChatSessionDeletedResponse {
+        message: "Chat session deleted".to_string(),
+        session_id: session.id,
+    }))
+}
+
+/// Load a chat session, requiring `owner_role` in its domain from the principal
+/// that started it and the Admin role from anyone else
+async fn chat_session(
+    state: &AppState,
+    id: &str,
+    principal: Option<&Principal>,
+    owner_role: DomainRole,
+) -> Result<ChatSession, AppError> {
+    let session = state
+        .storage
+        .get_chat_session(id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Chat session {} not found", id)))?;
+
+    let role = if session.created_by == principal.map(|p| p.subject()) {
+        owner_role
+    } else {
+        DomainRole::Admin
+    };
+    require_domain_role(state, principal, session.domain_id.as_deref(), role).await?;
+    Ok(session)
+}
@@ -3,17 +3,37 @@
 // API endpoint for executing cross-database JOIN and UNION queries using DataFusion's
 // federated execution engine.
 
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
 use std::collections::HashMap;
 
 use crate::api::handlers::connection::AppState;
-use crate::api::middleware::AppError;
-use crate::models::CrossDatabaseQueryRequest;
+use crate::api::handlers::query::{columnar_result, log_query_history, log_write_history};
+use crate::api::middleware::{ensure_max_length, require_domain_role, sql_audit_context, AppError};
+use crate::api::responses::{CrossDatabaseNaturalLanguageResponse, CrossDatabaseQueryEnvelope, QueryJobResponse};
+use crate::models::{
+    CrossDatabaseInsertRequest, CrossDatabaseNaturalLanguageRequest, CrossDatabaseQueryRequest, CrossDatabaseQueryResponse,
+    DomainRole, FederatedCatalog, LlmAuditContext,
+    Principal, Query, QueryHistory, QueryJob, ResultFormatParams, WriteResult,
+};
 use crate::services::database::{create_adapter, DatabaseAdapter, DatabaseType};
-use crate::services::datafusion::{CrossDatabaseQueryPlanner, DataFusionFederatedExecutor};
+use crate::services::datafusion::{
+    CrossDatabaseCostModel, CrossDatabaseQueryPlanner, DataFusionFederatedExecutor, VirtualView,
+};
+use crate::services::{
+    ApiUsageService, AuthService, ConnectionPolicyService, DataTransferService, DomainSettingsService, EmbeddingService,
+    FederatedCatalogService, LlmService, LlmUsageService, MetadataCacheService, PromptRedactor, QueryJobService,
+    QueryService, degraded_llm_config,
+};
 
 /// Execute cross-database query (JOIN or UNION across multiple databases)
 ///
+/// Without `connection_ids` and `database_aliases`, tables are read as
+/// `alias.schema.table` from the federated catalog (`GET /api/cross-database/catalog`).
+///
 /// # Request Body
 ///
 /// ```json
@@ -37,10 +57,199 @@ use crate::services::datafusion::{CrossDatabaseQueryPlanner, DataFusionFederated
 /// - Sub-queries executed per database
 /// - Merged results as JSON
 /// - Execution time and row count
+#[utoipa::path(
+    post,
+    path = "/api/cross-database/query",
+    tag = "queries",
+    params(ResultFormatParams),
+    request_body = CrossDatabaseQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = CrossDatabaseQueryEnvelope),
+    ),
+)]
 pub async fn execute_cross_database_query(
     State(state): State<AppState>,
-    Json(payload): Json<CrossDatabaseQueryRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    principal: Option<Extension<Principal>>,
+    Json(mut payload): Json<CrossDatabaseQueryRequest>,
+) -> Result<Json<CrossDatabaseQueryEnvelope>, AppError> {
+    // Without connections, the query's `alias.schema.table` references pick them from the catalog
+    if payload.connection_ids.is_empty() && payload.database_aliases.is_none() {
+        resolve_catalog_connections(&state, &mut payload).await?;
+    }
+
+    let mut result = run_cross_database_query(&state, &payload, principal.as_deref(), false).await?;
+    result.columnar = columnar_result(format.result_format, &mut result.results)?;
+    Ok(Json(CrossDatabaseQueryEnvelope { query: result }))
+}
+
+/// Answer a natural language question with one query over the connections of a domain
+///
+/// POST /api/domains/{id}/nl-query
+///
+/// The LLM gets the cached schema of each connected connection of the domain
+/// (or of the listed ones) under its catalog alias, and writes a query over
+/// `alias.schema.table` tables, which runs like `/api/cross-database/query`.
+/// Large schemas are narrowed to the tables most relevant to the question.
+/// Needs a configured LLM gateway.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/nl-query",
+    tag = "queries",
+    params(("id" = String, Path), ResultFormatParams),
+    request_body = CrossDatabaseNaturalLanguageRequest,
+    responses(
+        (status = 200, description = "OK", body = CrossDatabaseNaturalLanguageResponse),
+    ),
+)]
+pub async fn execute_cross_database_natural_language_query(
+    State(state): State<AppState>,
+    Path(domain_id): Path<String>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<CrossDatabaseNaturalLanguageRequest>,
+) -> Result<Json<CrossDatabaseNaturalLanguageResponse>, AppError> {
+    let question = payload.question.trim();
+    if question.is_empty() {
+        return Err(AppError::Validation("Question cannot be empty".to_string()));
+    }
+    ensure_max_length("Question", question, state.config.limits.max_question_length)?;
+
+    state
+        .storage
+        .get_domain(&domain_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    // Monthly LLM token budget: over it, reject or send fewer tables
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(Some(&domain_id))
+        .await?;
+    let degraded = LlmUsageService::new(state.storage.clone(), &state.config.llm)
+        .ensure_within_budget(Some(&domain_id), &settings)
+        .await?;
+    let llm_config = if degraded {
+        degraded_llm_config(&state.config.llm)
+    } else {
+        state.config.llm.clone()
+    };
+
+    // Connected connections of the domain with cached metadata, by catalog alias
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    let embeddings = EmbeddingService::from_config(&llm_config)
+        .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&settings))
+        .with_audit(
+            state.storage.clone(),
+            LlmAuditContext {
+                domain_id: Some(domain_id.clone()),
+                connection_id: None,
+                principal: principal.as_deref().map(|p| p.subject()),
+            },
+        );
+    let mut aliases: HashMap<String, String> = HashMap::new();
+    let mut catalogs = Vec::new();
+    let catalog_service = FederatedCatalogService::new(state.storage.clone(), state.config.metadata.clone());
+    for (alias, connection) in catalog_service.aliases().await? {
+        if connection.domain_id.as_deref() != Some(domain_id.as_str())
+            || !(payload.connection_ids.is_empty() || payload.connection_ids.contains(&connection.id))
+            || !matches!(connection.status, crate::models::ConnectionStatus::Connected)
+        {
+            continue;
+        }
+        let Some(metadata) = cache_service.get_cached_metadata(&connection.id).await? else {
+            continue;
+        };
+        let metadata = state
+            .schema_indexes
+            .relevant_metadata(&embeddings, &llm_config, question, metadata)
+            .await?;
+        aliases.insert(alias.clone(), connection.id);
+        catalogs.push((alias, connection.database_type, metadata));
+    }
+    if let Some(missing) = payload.connection_ids.iter().find(|id| !aliases.values().any(|c| c == *id)) {
+        return Err(AppError::Validation(format!(
+            "Connection {} is not a connected connection of domain {} with cached metadata",
+            missing, domain_id
+        )));
+    }
+    if catalogs.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Domain {} has no connected connections with cached metadata",
+            domain_id
+        )));
+    }
+
+    tracing::info!(
+        domain_id = %domain_id,
+        question = %crate::logging::redactor().text(question),
+        connections = catalogs.len(),
+        "Generating cross-database SQL from natural language question"
+    );
+    let llm_service = LlmService::new(&state.config)
+        .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&settings))
+        .with_audit(
+            state.storage.clone(),
+            LlmAuditContext {
+                domain_id: Some(domain_id.clone()),
+                connection_id: None,
+                principal: principal.as_deref().map(|p| p.subject()),
+            },
+        );
+    let generated = llm_service.generate_cross_database_sql(question, &catalogs).await;
+    if llm_service.is_configured() {
+        ApiUsageService::new(state.storage.clone())
+            .record_llm_request(
+                Some(&domain_id),
+                principal.as_deref(),
+                llm_service.token_usage(),
+                generated.is_err(),
+            )
+            .await;
+    }
+    let generated_sql = generated?;
+
+    // Only the connections shown to the LLM may be read
+    let mut database_aliases = HashMap::new();
+    for qualifier in CrossDatabaseQueryPlanner::catalog_qualifiers(&generated_sql)? {
+        let connection_id = aliases.get(&qualifier).ok_or_else(|| {
+            AppError::InvalidSql(format!("Generated SQL references unknown connection '{}'", qualifier))
+        })?;
+        database_aliases.insert(qualifier, connection_id.clone());
+    }
+    if database_aliases.is_empty() {
+        return Err(AppError::InvalidSql(
+            "Generated SQL references no `alias.schema.table` tables".to_string(),
+        ));
+    }
+    let mut connection_ids: Vec<String> = database_aliases.values().cloned().collect();
+    connection_ids.sort();
+    connection_ids.dedup();
+    let request = CrossDatabaseQueryRequest {
+        query: generated_sql.clone(),
+        connection_ids,
+        database_aliases: Some(database_aliases),
+        timeout_secs: payload.timeout_secs,
+        apply_limit: payload.apply_limit,
+        limit_value: payload.limit_value,
+    };
+
+    let mut result = run_cross_database_query(&state, &request, principal.as_deref(), true).await?;
+    result.columnar = columnar_result(format.result_format, &mut result.results)?;
+    Ok(Json(CrossDatabaseNaturalLanguageResponse {
+        generated_sql,
+        query: result,
+    }))
+}
+
+/// Plan and run a cross-database query whose connections are known, recording it in history
+async fn run_cross_database_query(
+    state: &AppState,
+    payload: &CrossDatabaseQueryRequest,
+    principal: Option<&Principal>,
+    is_llm_generated: bool,
+) -> Result<CrossDatabaseQueryResponse, AppError> {
     tracing::info!(
         "Executing cross-database query across {} databases",
         payload.connection_ids.len()
@@ -50,9 +259,12 @@ pub async fn execute_cross_database_query(
     payload
         .validate()
         .map_err(|e| AppError::Validation(e))?;
+    ensure_max_length("SQL query", &payload.query, state.config.limits.max_sql_length)?;
 
     // Get all connections and create adapters
+    let settings_service = DomainSettingsService::new(state.storage.clone());
     let mut adapters: HashMap<String, Box<dyn DatabaseAdapter>> = HashMap::new();
+    let mut domain_ids: Vec<String> = Vec::new();
 
     for conn_id in &payload.connection_ids {
         tracing::debug!("Loading connection: {}", conn_id);
@@ -64,6 +276,12 @@ pub async fn execute_cross_database_query(
             .await
             .map_err(|e| AppError::Database(e.to_string()))?
             .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", conn_id)))?;
+        require_domain_role(state, principal, connection.domain_id.as_deref(), DomainRole::Editor).await?;
+        if let Some(domain_id) = &connection.domain_id {
+            if !domain_ids.contains(domain_id) {
+                domain_ids.push(domain_id.clone());
+            }
+        }
 
         // Verify connection is active
         if !matches!(connection.status, crate::models::ConnectionStatus::Connected) {
@@ -73,11 +291,15 @@ pub async fn execute_cross_database_query(
             )));
         }
 
+        let settings = settings_service.for_domain(connection.domain_id.as_deref()).await?;
+        DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
         // Create database adapter
         let db_type = DatabaseType::from_str(&connection.database_type)?;
         let adapter = create_adapter(
             db_type,
             &connection.connection_url,
+            connection.read_only,
             state.pool_manager.clone(),
         )
         .await?;
@@ -87,44 +309,436 @@ pub async fn execute_cross_database_query(
 
     tracing::info!("Created {} database adapters", adapters.len());
 
-    // Create query planner
-    let planner = CrossDatabaseQueryPlanner::from_request(&payload);
+    let outcome = async {
+        // Create query planner
+        let planner = planner_for_request(state, payload).await?;
 
-    // Generate execution plan
-    let plan = planner
-        .plan_query(&payload)
-        .map_err(|e| {
-            tracing::error!("Query planning failed: {}", e);
-            e
-        })?;
+        // Generate execution plan
+        let plan = planner
+            .plan_query(payload)
+            .map_err(|e| {
+                tracing::error!(error = %crate::logging::redactor().text(&e.to_string()), "Query planning failed");
+                e
+            })?;
 
-    tracing::info!(
-        "Generated execution plan: {} sub-queries, merge strategy: {:?}",
-        plan.sub_queries.len(),
-        plan.merge_strategy
-    );
+        tracing::info!(
+            "Generated execution plan: {} sub-queries, merge strategy: {:?}",
+            plan.sub_queries.len(),
+            plan.merge_strategy
+        );
 
-    // Create federated executor
-    let executor = DataFusionFederatedExecutor::new();
+        // Views may run on connections of their domain the request does not list
+        for sub_query in &plan.sub_queries {
+            if !adapters.contains_key(&sub_query.connection_id) {
+                let adapter = view_adapter(state, &sub_query.connection_id).await?;
+                adapters.insert(sub_query.connection_id.clone(), adapter);
+            }
+        }
 
-    // Execute cross-database query
-    let result = executor
-        .execute_cross_database_query(plan, adapters)
-        .await
-        .map_err(|e| {
-            tracing::error!("Cross-database query execution failed: {}", e);
-            e
-        })?;
+        // Create federated executor
+        let executor = DataFusionFederatedExecutor::new()
+            .with_cache(state.config.cross_database.subquery_cache_enabled.then(|| state.subquery_cache.clone()))
+            .with_sessions(state.sessions.clone());
+
+        // Execute cross-database query
+        executor
+            .execute_cross_database_query(plan, adapters)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    error = %crate::logging::redactor().text(&e.to_string()),
+                    sql = %crate::logging::redactor().sql(&payload.query),
+                    "Cross-database query execution failed"
+                );
+                e
+            })
+    }
+    .await;
+
+    let executed_by = principal.map(|p| p.subject());
+    log_cross_database_history(state, &domain_ids, payload, outcome.as_ref(), executed_by, is_llm_generated).await;
+    let result = outcome?;
 
     tracing::info!(
         "Cross-database query completed: {} rows in {}ms",
         result.row_count,
         result.execution_time_ms
     );
+    Ok(result)
+}
+
+/// Browse the federated catalog: the connections' aliases and cached tables
+///
+/// GET /api/cross-database/catalog
+///
+/// Registers every connection's cached tables as `alias.schema.table` into the
+/// pooled DataFusion sessions, and lists those of the connections the caller can
+/// see. Cross-database queries that list no connections resolve these aliases.
+#[utoipa::path(
+    get,
+    path = "/api/cross-database/catalog",
+    tag = "queries",
+    responses(
+        (status = 200, description = "OK", body = FederatedCatalog),
+    ),
+)]
+pub async fn get_federated_catalog(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<FederatedCatalog>, AppError> {
+    let visible = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .visible_domain_ids(principal.as_deref())
+        .await?;
+
+    let catalog = FederatedCatalogService::new(state.storage.clone(), state.config.metadata.clone())
+        .sync(&state.sessions, visible.as_deref())
+        .await?;
+    Ok(Json(catalog))
+}
+
+/// Fill a request's connections and aliases from the catalog aliases its query references
+///
+/// Access to each connection is checked when the query loads it.
+pub(crate) async fn resolve_catalog_connections(
+    state: &AppState,
+    payload: &mut CrossDatabaseQueryRequest,
+) -> Result<(), AppError> {
+    let aliases = FederatedCatalogService::new(state.storage.clone(), state.config.metadata.clone())
+        .resolve(&payload.query)
+        .await?;
+
+    let mut connection_ids: Vec<String> = Vec::new();
+    for connection_id in aliases.values() {
+        if !connection_ids.contains(connection_id) {
+            connection_ids.push(connection_id.clone());
+        }
+    }
+    connection_ids.sort();
+    payload.connection_ids = connection_ids;
+    payload.database_aliases = Some(aliases);
+    Ok(())
+}
+
+/// Record a cross-database query in the history of each domain it touched
+///
+/// Entries carry the connection ids and aliases instead of a single connection,
+/// so they can be re-run and saved from the history.
+async fn log_cross_database_history(
+    state: &AppState,
+    domain_ids: &[String],
+    request: &CrossDatabaseQueryRequest,
+    result: Result<&CrossDatabaseQueryResponse, &AppError>,
+    executed_by: Option<String>,
+    is_llm_generated: bool,
+) {
+    for domain_id in domain_ids {
+        let history = match result {
+            Ok(response) => QueryHistory::new(
+                domain_id.clone(),
+                None,
+                request.query.clone(),
+                response.row_count,
+                response.execution_time_ms as u64,
+                is_llm_generated,
+            ),
+            Err(e) => QueryHistory::new_failed(domain_id.clone(), None, request.query.clone(), e.to_string(), is_llm_generated),
+        }
+        .with_connections(request.connection_ids.clone(), request.database_aliases.clone())
+        .with_executed_by(executed_by.clone());
+
+        // Log to history (ignore errors to not block query response)
+        if let Err(e) = state.storage.add_query_history(&history).await {
+            tracing::warn!(
+                error = %crate::logging::redactor().text(&e.to_string()),
+                "Failed to log cross-database query history"
+            );
+        }
+    }
+}
 
-    Ok(Json(serde_json::json!({
-        "query": result,
-    })))
+/// Copy the result of a query on one connection into a table of another
+///
+/// Cross-database `INSERT INTO ... SELECT`, run as a query job: returns the
+/// pending job right away. The SELECT runs on the source connection (Editor
+/// role, masking policies applied) limited to `CROSS_DB_INSERT_SELECT_MAX_ROWS`
+/// rows; its rows are streamed into the existing target table in batches
+/// (PostgreSQL `COPY`, multi-row INSERTs elsewhere). The target needs
+/// `allow_writes` and the Admin role on its domain. Poll
+/// `GET /api/query-jobs/{id}` for `rows_processed` while the job runs; batches
+/// are committed as they load, so a failed or cancelled job keeps the rows
+/// loaded so far. The load is recorded as a write in the target's history.
+///
+/// # Request Body
+///
+/// ```json
+/// {
+///   "source_connection_id": "mysql-conn-id",
+///   "query": "SELECT id, email AS contact FROM users WHERE active = 1",
+///   "target_connection_id": "pg-conn-id",
+///   "target_table": "crm.contacts",
+///   "batch_size": 1000
+/// }
+/// ```
+#[utoipa::path(
+    post,
+    path = "/api/cross-database/insert-select",
+    tag = "query-jobs",
+    request_body = CrossDatabaseInsertRequest,
+    responses(
+        (status = 202, description = "Accepted", body = QueryJobResponse),
+    ),
+)]
+pub async fn submit_cross_database_insert(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<CrossDatabaseInsertRequest>,
+) -> Result<(StatusCode, Json<QueryJobResponse>), AppError> {
+    tracing::info!(
+        "Submitting cross-database insert from {} into {}",
+        payload.source_connection_id,
+        payload.target_connection_id
+    );
+
+    payload.validate().map_err(AppError::Validation)?;
+    ensure_max_length("SQL query", &payload.query, state.config.limits.max_sql_length)?;
+
+    let load_connection = |id: String| {
+        let storage = state.storage.clone();
+        async move {
+            storage
+                .get_connection(&id)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+                .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))
+        }
+    };
+    let source = load_connection(payload.source_connection_id.clone()).await?;
+    let target = load_connection(payload.target_connection_id.clone()).await?;
+    require_domain_role(&state, principal.as_deref(), source.domain_id.as_deref(), DomainRole::Editor).await?;
+    require_domain_role(&state, principal.as_deref(), target.domain_id.as_deref(), DomainRole::Admin).await?;
+    if !target.allow_writes {
+        return Err(AppError::Forbidden(format!(
+            "Connection {} does not allow writes; enable allow_writes on the connection first",
+            target.id
+        )));
+    }
+    crate::logging::redactor().register_connection(&source.id, &source.connection_url);
+    crate::logging::redactor().register_connection(&target.id, &target.connection_url);
+
+    let settings_service = DomainSettingsService::new(state.storage.clone());
+    let source_settings = settings_service.for_domain(source.domain_id.as_deref()).await?;
+    DomainSettingsService::ensure_database_type_allowed(&source_settings, &source.database_type)?;
+    let target_settings = settings_service.for_domain(target.domain_id.as_deref()).await?;
+    DomainSettingsService::ensure_database_type_allowed(&target_settings, &target.database_type)?;
+
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&source).await?;
+    policy_service.ensure_within_quota(&source, &policy).await?;
+    let target_policy = policy_service.for_connection(&target).await?;
+    policy_service.ensure_within_quota(&target, &target_policy).await?;
+
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let executed_by = principal.as_deref().map(|p| p.subject());
+    let statement = format!("INSERT INTO {} {}", payload.target_table.trim(), payload.query.trim());
+    let job = QueryJob::new(
+        target.id.clone(),
+        target.domain_id.clone(),
+        statement.clone(),
+        executed_by.clone(),
+    );
+
+    let job_id = job.id.clone();
+    let work_state = state.clone();
+    let work = async move {
+        let config = &work_state.config.cross_database;
+        let source_adapter = create_adapter(
+            DatabaseType::from_str(&source.database_type)?,
+            &source.connection_url,
+            source.read_only,
+            work_state.pool_manager.clone(),
+        ).await?;
+        let target_adapter = create_adapter(
+            DatabaseType::from_str(&target.database_type)?,
+            &target.connection_url,
+            target.read_only,
+            work_state.pool_manager.clone(),
+        ).await?;
+
+        let start_time = std::time::Instant::now();
+        let batch_size = payload.batch_size.unwrap_or(config.insert_select_batch_rows).max(1);
+        let (mut query, batches) = QueryService::new()
+            .with_audit(audit)
+            .stream_query_with_limits(
+                Query::new(source.id.clone(), payload.query.trim().to_string(), false),
+                source_adapter,
+                config.insert_select_max_rows,
+                work_state.config.jobs.timeout_secs,
+                batch_size,
+            )
+            .await?;
+
+        let transfer = DataTransferService::new(
+            target_adapter,
+            &payload.target_table_parts(),
+            payload.columns.clone(),
+            target_settings.default_timeout_secs,
+        );
+        let outcome = transfer
+            .load(
+                batches,
+                |rows| policy.mask_rows(rows),
+                |rows_processed| {
+                    let storage = work_state.storage.clone();
+                    let job_id = job_id.clone();
+                    async move {
+                        if let Err(e) = storage.update_query_job_progress(&job_id, rows_processed).await {
+                            tracing::warn!(job_id = %job_id, "Failed to record transfer progress: {}", e);
+                        }
+                    }
+                },
+            )
+            .await;
+        let execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+        let write = outcome.map(|loaded| WriteResult {
+            statement_kind: "INSERT".to_string(),
+            affected_rows: loaded,
+            execution_time_ms,
+        });
+        log_write_history(&work_state, &target, &statement, write.as_ref(), executed_by.clone()).await;
+        let write = write?;
+
+        query.mark_streamed(write.affected_rows as usize, execution_time_ms);
+        log_query_history(&work_state, &source, &query.query_text, &query, executed_by, false, None).await;
+
+        let mut result = Query::new(target.id.clone(), statement.clone(), false);
+        result.mark_streamed(write.affected_rows as usize, execution_time_ms);
+        result.limit_applied = query.limit_applied;
+        Ok(result)
+    };
+
+    let job = QueryJobService::new(state.storage.clone(), state.query_jobs.clone())
+        .submit(job, work)
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(QueryJobResponse { job })))
+}
+
+/// Planner for a request, estimating rows from the connections' cached table statistics
+///
+/// Connections without cached metadata are planned without estimates. The
+/// saved query views of the connections' domains can be read as `domain.view_name`
+/// (materialized views from their snapshot). The plan's timeout and row limit
+/// stay within the strictest settings of those domains.
+pub(crate) async fn planner_for_request(
+    state: &AppState,
+    payload: &CrossDatabaseQueryRequest,
+) -> Result<CrossDatabaseQueryPlanner, AppError> {
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    let settings_service = DomainSettingsService::new(state.storage.clone());
+    let mut cost_model = CrossDatabaseCostModel::default();
+    let mut domain_settings = Vec::new();
+    for conn_id in &payload.connection_ids {
+        if let Some(metadata) = cache_service.get_cached_metadata(conn_id).await? {
+            cost_model.add_connection(conn_id.clone(), metadata.tables);
+        }
+        let connection = state
+            .storage
+            .get_connection(conn_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let domain_id = connection.and_then(|c| c.domain_id);
+        domain_settings.push(settings_service.for_domain(domain_id.as_deref()).await?);
+    }
+
+    Ok(CrossDatabaseQueryPlanner::from_request(payload)
+        .with_cost_model(cost_model, &state.config.cross_database)
+        .with_views(domain_views(state, &payload.connection_ids).await?)
+        .with_domain_settings(domain_settings))
+}
+
+/// Views of the domains the connections belong to, named after their domain
+async fn domain_views(state: &AppState, connection_ids: &[String]) -> Result<Vec<VirtualView>, AppError> {
+    let mut domain_ids: Vec<String> = Vec::new();
+    for conn_id in connection_ids {
+        let connection = state
+            .storage
+            .get_connection(conn_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if let Some(domain_id) = connection.and_then(|c| c.domain_id) {
+            if !domain_ids.contains(&domain_id) {
+                domain_ids.push(domain_id);
+            }
+        }
+    }
+
+    let mut views = Vec::new();
+    for domain_id in &domain_ids {
+        let Some(domain) = state
+            .storage
+            .get_domain(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+        else {
+            continue;
+        };
+        let saved_views = state
+            .storage
+            .list_saved_views(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        // Materialized views are read from their snapshot once one was taken
+        let snapshots: Vec<String> = state
+            .storage
+            .list_view_materializations(Some(domain_id))
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .into_iter()
+            .filter(|materialization| materialization.has_snapshot())
+            .map(|materialization| materialization.saved_query_id)
+            .collect();
+        views.extend(saved_views.iter().filter_map(|saved| {
+            let view = VirtualView::from_saved_query(&domain.name, saved)?;
+            Some(if snapshots.contains(&saved.id) { view.materialized(&saved.id) } else { view })
+        }));
+    }
+    Ok(views)
+}
+
+/// Adapter of a view's connection, or of a materialized view's snapshot
+///
+/// Views belong to the domain of a connection the request already checked
+/// access to, so only the connection's status and the domain's allowed
+/// database types are verified.
+pub(crate) async fn view_adapter(state: &AppState, conn_id: &str) -> Result<Box<dyn DatabaseAdapter>, AppError> {
+    if let Some(saved_query_id) = VirtualView::snapshot_of(conn_id) {
+        return Ok(state.view_materializations.snapshot_adapter(saved_query_id));
+    }
+    let connection = state
+        .storage
+        .get_connection(conn_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} of a view not found", conn_id)))?;
+    if !matches!(connection.status, crate::models::ConnectionStatus::Connected) {
+        return Err(AppError::Connection(format!(
+            "Connection {} of a view is not active (status: {:?})",
+            conn_id, connection.status
+        )));
+    }
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+    create_adapter(
+        DatabaseType::from_str(&connection.database_type)?,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    )
+    .await
 }
 
 #[cfg(test)]
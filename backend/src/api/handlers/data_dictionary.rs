@@ -0,0 +1,169 @@
+// Data Dictionary Handlers
+//
+// Human descriptions and tags of the tables, views and columns of a
+// connection. They are merged into the connection's metadata, so metadata
+// responses carry them and natural language queries see them in the schema
+// context of their prompts.
+
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::api::responses::{DictionaryEntryDeletedResponse, DictionaryListResponse};
+use crate::models::{DatabaseConnection, DictionaryEntry, DictionaryEntryRequest, DomainRole, Principal};
+
+async fn find_connection(
+    state: &AppState,
+    principal: Option<&Principal>,
+    id: &str,
+    role: DomainRole,
+) -> Result<DatabaseConnection, AppError> {
+    let connection = state
+        .storage
+        .get_connection(id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(state, principal, connection.domain_id.as_deref(), role).await?;
+    Ok(connection)
+}
+
+/// Prompts and schema retrieval of the connection use the changed dictionary
+/// from the next question on
+fn invalidate_llm_caches(state: &AppState, connection_id: &str) {
+    state.generation_cache.invalidate_connection(connection_id);
+    state.schema_indexes.invalidate(connection_id);
+}
+
+/// List the data dictionary of a connection
+///
+/// GET /api/connections/{id}/dictionary
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/dictionary",
+    tag = "metadata",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = DictionaryListResponse),
+    ),
+)]
+pub async fn list_dictionary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<DictionaryListResponse>, AppError> {
+    let connection = find_connection(&state, principal.as_deref(), &id, DomainRole::Viewer).await?;
+    let entries = state
+        .storage
+        .list_dictionary_entries(&connection.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(DictionaryListResponse { entries }))
+}
+
+/// Describe or tag a table, view or column of a connection
+///
+/// PUT /api/connections/{id}/dictionary
+///
+/// Replaces the entry of the same schema, table and column. Without `schema`
+/// the entry applies to the table in any schema; without `column` it
+/// describes the table itself. When the connection's schema is cached, the
+/// table and column must exist in it.
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/dictionary",
+    tag = "metadata",
+    params(("id" = String, Path)),
+    request_body = DictionaryEntryRequest,
+    responses(
+        (status = 200, description = "OK", body = DictionaryEntry),
+    ),
+)]
+pub async fn upsert_dictionary_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<DictionaryEntryRequest>,
+) -> Result<Json<DictionaryEntry>, AppError> {
+    let connection = find_connection(&state, principal.as_deref(), &id, DomainRole::Editor).await?;
+    let entry = DictionaryEntry::new(connection.id.clone(), payload, principal.as_deref().map(|p| p.subject()))
+        .map_err(AppError::Validation)?;
+
+    let metadata = state
+        .storage
+        .get_metadata_cache(&connection.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if metadata.is_some_and(|metadata| !entry.found_in(&metadata)) {
+        return Err(AppError::Validation(format!(
+            "{}{}{} not found in the metadata of connection {}",
+            entry.schema.as_deref().map(|schema| format!("{}.", schema)).unwrap_or_default(),
+            entry.table,
+            entry.column.as_deref().map(|column| format!(".{}", column)).unwrap_or_default(),
+            connection.id
+        )));
+    }
+
+    state
+        .storage
+        .upsert_dictionary_entry(&entry)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    invalidate_llm_caches(&state, &connection.id);
+    Ok(Json(entry))
+}
+
+/// Query parameters naming a data dictionary entry
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DictionaryEntryParams {
+    /// Schema of the entry; omitted for entries that apply to any schema
+    pub schema: Option<String>,
+    pub table: String,
+    /// Column of the entry; omitted for the table's own entry
+    pub column: Option<String>,
+}
+
+/// Remove a data dictionary entry
+///
+/// DELETE /api/connections/{id}/dictionary?schema=sales&table=orders&column=status
+#[utoipa::path(
+    delete,
+    path = "/api/connections/{id}/dictionary",
+    tag = "metadata",
+    params(("id" = String, Path), DictionaryEntryParams),
+    responses(
+        (status = 200, description = "Deleted", body = DictionaryEntryDeletedResponse),
+    ),
+)]
+pub async fn delete_dictionary_entry(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Query(params): Query<DictionaryEntryParams>,
+) -> Result<Json<DictionaryEntryDeletedResponse>, AppError> {
+    let connection = find_connection(&state, principal.as_deref(), &id, DomainRole::Editor).await?;
+    let non_empty = |name: &Option<String>| name.as_deref().map(str::trim).filter(|name| !name.is_empty());
+
+    let deleted = state
+        .storage
+        .delete_dictionary_entry(&connection.id, non_empty(&params.schema), params.table.trim(), non_empty(&params.column))
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if !deleted {
+        return Err(AppError::NotFound(format!(
+            "No data dictionary entry for {} on connection {}",
+            params.table.trim(),
+            connection.id
+        )));
+    }
+    invalidate_llm_caches(&state, &connection.id);
+    Ok(Json(DictionaryEntryDeletedResponse {
+        message: "Data dictionary entry deleted".to_string(),
+    }))
+}
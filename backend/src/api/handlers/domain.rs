@@ -1,33 +1,82 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 
-use crate::api::middleware::AppError;
-use crate::api::handlers::connection::AppState;
-use crate::models::{Domain, CreateDomainRequest, UpdateDomainRequest};
+use crate::api::middleware::{expected_version, require_domain_role, AppError};
+use crate::api::handlers::connection::{AppState, ConnectionListFilter};
+use crate::api::responses::{DomainConnectionsResponse, DomainEnvelope, DomainListResponse, LlmAuditListResponse};
+use crate::models::{
+    Domain, CreateDomainRequest, UpdateDomainRequest, DomainUsageReport, DomainStats, DomainSettings,
+    UpdateDomainSettingsRequest, ImportDomainRequest, ImportDomainResponse, ImportConnectionsRequest,
+    ImportConnectionsResponse, DomainRole, Principal,
+    AssignDomainRoleRequest, DomainRoleAssignment, DomainResponse, DomainBundle, ListParams, ApiUsageReport,
+    Webhook, CreateWebhookRequest, WebhookDelivery, LlmUsageReport, LabelSelector, SlowQueryReport, SlowQuerySort,
+};
+use crate::services::{
+    ApiUsageService, AuthService, DomainBundleService, DomainSettingsService, LlmUsageService, NotificationService,
+    SlowQueryService, TrashService,
+};
+use crate::storage::SqliteStorage;
+use std::collections::HashMap;
 
-/// List all domains with resource counts
+/// List domains with resource counts
+///
+/// GET /api/domains?page=1&page_size=50&sort=name&filter=sales
+#[utoipa::path(
+    get,
+    path = "/api/domains",
+    tag = "domains",
+    params(ListParams),
+    responses(
+        (status = 200, description = "OK", body = DomainListResponse),
+    ),
+)]
 pub async fn list_domains(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, AppError> {
-    let domains = state
+    principal: Option<Extension<Principal>>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<DomainListResponse>, AppError> {
+    let list_query = params
+        .resolve(SqliteStorage::DOMAIN_SORT_FIELDS, "-created_at")
+        .map_err(AppError::Validation)?;
+
+    // Users only see domains they have a role in
+    let visible = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .visible_domain_ids(principal.as_deref())
+        .await?;
+
+    let (domains, total) = state
         .storage
-        .list_domains()
+        .list_domains_page(&list_query, visible.as_deref())
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
-    Ok(Json(serde_json::json!({
-        "domains": domains
-    })))
+    Ok(Json(DomainListResponse {
+        domains,
+        page: list_query.page_info(total),
+    }))
 }
 
 /// Get a domain by ID
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}",
+    tag = "domains",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = DomainEnvelope),
+    ),
+)]
 pub async fn get_domain(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(id): Path<String>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<DomainEnvelope>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
     let domain = state
         .storage
         .get_domain(&id)
@@ -42,28 +91,42 @@ pub async fn get_domain(
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
-    Ok(Json(serde_json::json!({
-        "domain": {
-            "id": domain.id,
-            "name": domain.name,
-            "description": domain.description,
-            "created_at": domain.created_at,
-            "updated_at": domain.updated_at,
-            "connection_count": connection_count,
-            "saved_query_count": 0,
-            "query_history_count": 0,
-        }
-    })))
+    Ok(Json(DomainEnvelope {
+        domain: DomainResponse::with_counts(domain, connection_count, 0, 0),
+    }))
+}
+
+/// Names of trashed domains stay taken until they are restored or purged
+async fn ensure_domain_name_not_trashed(state: &AppState, name: &str) -> Result<(), AppError> {
+    let trashed = state
+        .storage
+        .trashed_domain_named(name)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    match trashed {
+        Some(item) => Err(TrashService::name_held_by(&item)),
+        None => Ok(()),
+    }
 }
 
 /// Create a new domain
+#[utoipa::path(
+    post,
+    path = "/api/domains",
+    tag = "domains",
+    request_body = CreateDomainRequest,
+    responses(
+        (status = 201, description = "Created", body = DomainEnvelope),
+    ),
+)]
 pub async fn create_domain(
     State(state): State<AppState>,
     Json(payload): Json<CreateDomainRequest>,
-) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+) -> Result<(StatusCode, Json<DomainEnvelope>), AppError> {
     // Create domain with validation
     let domain = Domain::new(payload.name, payload.description)
         .map_err(|e| AppError::Validation(e))?;
+    ensure_domain_name_not_trashed(&state, &domain.name).await?;
 
     // Save to storage
     state
@@ -74,27 +137,40 @@ pub async fn create_domain(
 
     Ok((
         StatusCode::CREATED,
-        Json(serde_json::json!({
-            "domain": {
-                "id": domain.id,
-                "name": domain.name,
-                "description": domain.description,
-                "created_at": domain.created_at,
-                "updated_at": domain.updated_at,
-                "connection_count": 0,
-                "saved_query_count": 0,
-                "query_history_count": 0,
-            }
-        })),
+        Json(DomainEnvelope {
+            domain: DomainResponse::with_counts(domain, 0, 0, 0),
+        }),
     ))
 }
 
 /// Update an existing domain
+///
+/// Requires the current version via `If-Match` or `expected_version`;
+/// returns 409 if the domain was modified concurrently.
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("If-Match" = Option<String>, Header, description = "Current version (alternative to `expected_version`)"),
+    ),
+    request_body = UpdateDomainRequest,
+    responses(
+        (status = 200, description = "OK", body = DomainEnvelope),
+    ),
+)]
 pub async fn update_domain(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateDomainRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<DomainEnvelope>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let expected_version = expected_version(&headers, payload.expected_version)?;
+
     // Get existing domain
     let mut domain = state
         .storage
@@ -106,6 +182,7 @@ pub async fn update_domain(
     // Update fields with validation
     if let Some(name) = payload.name {
         Domain::validate_name(&name).map_err(|e| AppError::Validation(e))?;
+        ensure_domain_name_not_trashed(&state, &name).await?;
         domain.name = name;
     }
 
@@ -117,13 +194,22 @@ pub async fn update_domain(
     // Touch updated_at timestamp
     domain.touch();
 
-    // Save changes
-    state
+    // Save changes (only applies if nobody updated the domain in the meantime)
+    domain.version = expected_version;
+    let updated = state
         .storage
         .update_domain(&domain)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+    if !updated {
+        return Err(AppError::Conflict(format!(
+            "Domain {} was modified by another request (expected version {}). Reload and retry.",
+            id, expected_version
+        )));
+    }
+    domain.version += 1;
+
     // Get connection count for response
     let connection_count = state
         .storage
@@ -131,21 +217,21 @@ pub async fn update_domain(
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
-    Ok(Json(serde_json::json!({
-        "domain": {
-            "id": domain.id,
-            "name": domain.name,
-            "description": domain.description,
-            "created_at": domain.created_at,
-            "updated_at": domain.updated_at,
-            "connection_count": connection_count,
-            "saved_query_count": 0,
-            "query_history_count": 0,
-        }
-    })))
+    Ok(Json(DomainEnvelope {
+        domain: DomainResponse::with_counts(domain, connection_count, 0, 0),
+    }))
 }
 
 /// Delete a domain (CASCADE will delete associated connections)
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{id}",
+    tag = "domains",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
 pub async fn delete_domain(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -171,10 +257,32 @@ pub async fn delete_domain(
 }
 
 /// List connections for a specific domain
+///
+/// GET /api/domains/{id}/connections?labels=env=prod&tag=pii
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/connections",
+    tag = "domains",
+    params(("id" = String, Path), ConnectionListFilter),
+    responses(
+        (status = 200, description = "OK", body = DomainConnectionsResponse),
+    ),
+)]
 pub async fn list_domain_connections(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(id): Path<String>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    Query(filter): Query<ConnectionListFilter>,
+) -> Result<Json<DomainConnectionsResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+    let labels = filter
+        .labels
+        .as_deref()
+        .map(LabelSelector::parse)
+        .transpose()
+        .map_err(AppError::Validation)?;
+    let tags = filter.tags()?;
+
     // Verify domain exists
     state
         .storage
@@ -184,13 +292,710 @@ pub async fn list_domain_connections(
         .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
 
     // Get connections for domain
-    let connections = state
+    let mut connections = state
         .storage
         .list_connections_by_domain(&id)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
+    connections.retain(|connection| {
+        labels.as_ref().is_none_or(|selector| selector.matches(&connection.labels))
+            && tags.iter().all(|tag| connection.tags.contains(tag))
+    });
+
+    Ok(Json(DomainConnectionsResponse { connections }))
+}
+
+/// Get aggregated usage (heatmap, daily trend, per-connection distribution, LLM ratio)
+///
+/// GET /api/domains/{id}/usage?days=30
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/usage",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("days" = Option<u32>, Query, description = "Window in days (1-365, default 30)"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = DomainUsageReport),
+    ),
+)]
+pub async fn get_domain_usage(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<DomainUsageReport>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let days = params
+        .get("days")
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(30);
+    if !(1..=365).contains(&days) {
+        return Err(AppError::Validation("days must be between 1 and 365".to_string()));
+    }
+
+    let since = chrono::Utc::now() - chrono::Duration::days(days);
+    let buckets = state
+        .storage
+        .list_usage_buckets(&id, since)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(Json(DomainUsageReport::from_buckets(id, since, &buckets)))
+}
+
+/// Get resource usage of a domain: query counts, failure rate, average
+/// execution time, rows scanned, most-used connections, top users and the
+/// most-run query shapes
+///
+/// GET /api/domains/{id}/stats?days=7&top=10
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/stats",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("days" = Option<u32>, Query, description = "Window in days (1-365, default 30)"),
+        ("top" = Option<u32>, Query, description = "Connections, users and query shapes listed (1-100, default 10)"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = DomainStats),
+    ),
+)]
+pub async fn get_domain_stats(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<DomainStats>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let days = params
+        .get("days")
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(30);
+    if !(1..=365).contains(&days) {
+        return Err(AppError::Validation("days must be between 1 and 365".to_string()));
+    }
+    let top = params
+        .get("top")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(10);
+    if !(1..=100).contains(&top) {
+        return Err(AppError::Validation("top must be between 1 and 100".to_string()));
+    }
+
+    let until = chrono::Utc::now();
+    let since = until - chrono::Duration::days(days);
+    let stats = state
+        .storage
+        .domain_stats(&id, since, until, top)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(Json(stats))
+}
+
+/// Get the queries of a domain flagged as slow, grouped by SQL fingerprint
+/// and ranked by total or average execution time
+///
+/// GET /api/domains/{id}/slow-queries?days=7&top=20&sort=total|avg
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/slow-queries",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("days" = Option<u32>, Query, description = "Window in days (1-365, default 7)"),
+        ("top" = Option<u32>, Query, description = "Query shapes listed (1-100, default 20)"),
+        ("sort" = Option<SlowQuerySort>, Query, description = "`total` (default) or `avg` execution time"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = SlowQueryReport),
+    ),
+)]
+pub async fn get_domain_slow_queries(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<SlowQueryReport>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let days = params
+        .get("days")
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(7);
+    if !(1..=365).contains(&days) {
+        return Err(AppError::Validation("days must be between 1 and 365".to_string()));
+    }
+    let top = params
+        .get("top")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(20);
+    if !(1..=100).contains(&top) {
+        return Err(AppError::Validation("top must be between 1 and 100".to_string()));
+    }
+    let sort = params
+        .get("sort")
+        .map(|s| SlowQuerySort::from_str(s))
+        .transpose()
+        .map_err(AppError::Validation)?
+        .unwrap_or_default();
+
+    let report = SlowQueryService::new(state.storage.clone())
+        .report(&id, days, top, sort)
+        .await?;
+    Ok(Json(report))
+}
+
+/// Get the monthly API usage report of a domain (daily rollups and totals)
+///
+/// GET /api/domains/{id}/reports?month=2024-06&format=json|csv
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/reports",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("month" = Option<String>, Query, description = "`YYYY-MM` (default: current month, UTC)"),
+        ("format" = Option<String>, Query, description = "`json` (default) or `csv`"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ApiUsageReport),
+    ),
+)]
+pub async fn get_domain_reports(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let report = ApiUsageService::new(state.storage.clone())
+        .monthly_report(&id, params.get("month").map(String::as_str))
+        .await?;
+
+    let format = params.get("format").map(|f| f.to_lowercase()).unwrap_or_else(|| "json".to_string());
+    match format.as_str() {
+        "json" => Ok(Json(report).into_response()),
+        "csv" => {
+            let disposition = format!("attachment; filename=\"usage-{}-{}.csv\"", id, report.month);
+            Ok((
+                [(header::CONTENT_TYPE, "text/csv".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+                report.to_csv(),
+            )
+                .into_response())
+        }
+        other => Err(AppError::Validation(format!(
+            "Unsupported report format '{}' (expected json or csv)",
+            other
+        ))),
+    }
+}
+
+/// Get the monthly LLM usage of a domain: requests and tokens per day and
+/// principal, with the domain's token budget
+///
+/// GET /api/domains/{id}/llm-usage?month=2024-06
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/llm-usage",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("month" = Option<String>, Query, description = "`YYYY-MM` (default: current month, UTC)"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = LlmUsageReport),
+    ),
+)]
+pub async fn get_domain_llm_usage(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<LlmUsageReport>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let settings = DomainSettingsService::new(state.storage.clone()).get(&id).await?;
+    let report = LlmUsageService::new(state.storage.clone(), &state.config.llm)
+        .monthly_report(&settings, params.get("month").map(String::as_str))
+        .await?;
+    Ok(Json(report))
+}
+
+/// List the prompts sent to the LLM for a domain, exactly as sent, with what
+/// was redacted from them
+///
+/// GET /api/domains/{id}/llm-audit?page=1&page_size=50&sort=-created_at&filter=summarize
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/llm-audit",
+    tag = "domains",
+    params(("id" = String, Path), ListParams),
+    responses(
+        (status = 200, description = "OK", body = LlmAuditListResponse),
+    ),
+)]
+pub async fn list_domain_llm_audit(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<LlmAuditListResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+    let list_query = params
+        .resolve(SqliteStorage::LLM_AUDIT_SORT_FIELDS, "-created_at")
+        .map_err(AppError::Validation)?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let (entries, total) = state
+        .storage
+        .list_llm_audit_page(&id, &list_query)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(LlmAuditListResponse {
+        entries,
+        page: list_query.page_info(total),
+    }))
+}
+
+/// Get domain settings (defaults when never customized)
+///
+/// GET /api/domains/{id}/settings
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/settings",
+    tag = "domains",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = DomainSettings),
+    ),
+)]
+pub async fn get_domain_settings(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<DomainSettings>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let settings = DomainSettingsService::new(state.storage.clone()).get(&id).await?;
+    Ok(Json(settings))
+}
+
+/// Update domain settings (omitted fields are unchanged)
+///
+/// PUT /api/domains/{id}/settings
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}/settings",
+    tag = "domains",
+    params(("id" = String, Path)),
+    request_body = UpdateDomainSettingsRequest,
+    responses(
+        (status = 200, description = "OK", body = DomainSettings),
+    ),
+)]
+pub async fn update_domain_settings(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateDomainSettingsRequest>,
+) -> Result<Json<DomainSettings>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    tracing::info!("Updating settings for domain: {}", id);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .update(&id, payload)
+        .await?;
+    Ok(Json(settings))
+}
+
+/// Export a domain (settings, connections, saved queries) as a portable bundle
+///
+/// GET /api/domains/{id}/export?format=json|yaml
+///
+/// Connection URLs are templated (`${SALES_DB_PASSWORD}`); no credentials are exported.
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/export",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("format" = Option<String>, Query, description = "`json` (default) or `yaml`"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = DomainBundle),
+    ),
+)]
+pub async fn export_domain(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let bundle = DomainBundleService::new(state.storage.clone()).export(&id).await?;
+    let format = params.get("format").map(|f| f.to_lowercase()).unwrap_or_else(|| "json".to_string());
+
+    let (content_type, extension, body) = match format.as_str() {
+        "json" => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&bundle)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize bundle: {}", e)))?,
+        ),
+        "yaml" | "yml" => (
+            "application/yaml",
+            "yaml",
+            serde_yaml::to_string(&bundle)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize bundle: {}", e)))?,
+        ),
+        other => {
+            return Err(AppError::Validation(format!(
+                "Unsupported export format '{}' (expected json or yaml)",
+                other
+            )))
+        }
+    };
+
+    let disposition = format!("attachment; filename=\"domain-{}.{}\"", id, extension);
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response())
+}
+
+/// Import a domain bundle as a new domain
+///
+/// POST /api/domains/import
+///
+/// Accepts JSON or YAML (`Content-Type: application/yaml`) with the bundle,
+/// values for its URL variables and an optional new domain name.
+#[utoipa::path(
+    post,
+    path = "/api/domains/import",
+    tag = "domains",
+    request_body = ImportDomainRequest,
+    responses(
+        (status = 201, description = "Created", body = ImportDomainResponse),
+    ),
+)]
+pub async fn import_domain(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<ImportDomainResponse>), AppError> {
+    let is_yaml = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("yaml"));
+
+    let request: ImportDomainRequest = if is_yaml {
+        serde_yaml::from_str(&body)
+            .map_err(|e| AppError::Validation(format!("Invalid YAML bundle: {}", e)))?
+    } else {
+        serde_json::from_str(&body)
+            .map_err(|e| AppError::Validation(format!("Invalid JSON bundle: {}", e)))?
+    };
+
+    let imported = DomainBundleService::new(state.storage.clone()).import(request).await?;
+    Ok((StatusCode::CREATED, Json(imported)))
+}
+
+/// Import connection definitions into a domain
+///
+/// POST /api/domains/{id}/connections/import
+///
+/// Accepts JSON or YAML (`Content-Type: application/yaml`): the request
+/// object, or a bare list of definitions. Secrets are left as `${VARIABLE}`
+/// placeholders in the URLs and passed in `variables`. Connections are not
+/// connected to; their metadata is loaded on the first refresh.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/connections/import",
+    tag = "domains",
+    params(("id" = String, Path)),
+    request_body = ImportConnectionsRequest,
+    responses(
+        (status = 200, description = "OK", body = ImportConnectionsResponse),
+    ),
+)]
+pub async fn import_connections(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportConnectionsResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    state
+        .storage
+        .get_domain(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain with ID {} not found", id)))?;
+
+    let is_yaml = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("yaml"));
+    let request = ImportConnectionsRequest::parse(&body, is_yaml).map_err(AppError::Validation)?;
+
+    let imported = DomainBundleService::new(state.storage.clone())
+        .import_connections(&id, request, &state.pool_manager)
+        .await?;
+    Ok(Json(imported))
+}
+
+/// List role assignments of a domain
+///
+/// GET /api/domains/{id}/roles
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/roles",
+    tag = "domains",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = Vec<DomainRoleAssignment>),
+    ),
+)]
+pub async fn list_domain_roles(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DomainRoleAssignment>>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let roles = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .list_roles(&id)
+        .await?;
+    Ok(Json(roles))
+}
+
+/// Assign (or change) a user's role in a domain
+///
+/// PUT /api/domains/{id}/roles
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}/roles",
+    tag = "domains",
+    params(("id" = String, Path)),
+    request_body = AssignDomainRoleRequest,
+    responses(
+        (status = 200, description = "OK", body = DomainRoleAssignment),
+    ),
+)]
+pub async fn assign_domain_role(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Json(payload): Json<AssignDomainRoleRequest>,
+) -> Result<Json<DomainRoleAssignment>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+    tracing::info!("Assigning role {} to user {} in domain {}", payload.role.as_str(), payload.user_id, id);
+
+    let assignment = AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .assign_role(&id, payload)
+        .await?;
+    Ok(Json(assignment))
+}
+
+/// Remove a user's role in a domain
+///
+/// DELETE /api/domains/{id}/roles/{user_id}
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{id}/roles/{user_id}",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("user_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn remove_domain_role(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .remove_role(&id, &user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the webhooks of a domain
+///
+/// GET /api/domains/{id}/webhooks
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/webhooks",
+    tag = "domains",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = Vec<Webhook>),
+    ),
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Webhook>>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let webhooks = NotificationService::new(state.storage.clone()).list_webhooks(&id).await?;
+    Ok(Json(webhooks))
+}
+
+/// Register a webhook for events of a domain
+///
+/// POST /api/domains/{id}/webhooks
+///
+/// Events are POSTed as JSON and signed with the secret: `X-DbQuery-Signature`
+/// is `sha256=` plus the hex HMAC-SHA256 of `{X-DbQuery-Timestamp}.{body}`.
+/// Failed deliveries are retried with exponential backoff.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/webhooks",
+    tag = "domains",
+    params(("id" = String, Path)),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Created", body = Webhook),
+    ),
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<Webhook>), AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let webhook = NotificationService::new(state.storage.clone())
+        .create_webhook(&id, payload)
+        .await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// Delete a webhook and its delivery log
+///
+/// DELETE /api/domains/{id}/webhooks/{webhook_id}
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{id}/webhooks/{webhook_id}",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("webhook_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, webhook_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    NotificationService::new(state.storage.clone())
+        .delete_webhook(&id, &webhook_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Recent deliveries of a webhook, newest first
+///
+/// GET /api/domains/{id}/webhooks/{webhook_id}/deliveries
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "domains",
+    params(
+        ("id" = String, Path),
+        ("webhook_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = Vec<WebhookDelivery>),
+    ),
+)]
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, webhook_id)): Path<(String, String)>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
 
-    Ok(Json(serde_json::json!({
-        "connections": connections
-    })))
+    let deliveries = NotificationService::new(state.storage.clone())
+        .list_deliveries(&id, &webhook_id)
+        .await?;
+    Ok(Json(deliveries))
 }
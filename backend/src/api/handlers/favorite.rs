@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use std::collections::HashMap;
+
+use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::api::responses::{FavoriteListResponse, RecentQueryListResponse};
+use crate::models::{DomainRole, FavoriteType, Principal};
+
+/// Owner of the caller's favorites (empty while authentication is disabled)
+fn favorite_owner(principal: Option<&Principal>) -> String {
+    principal.map(|p| p.subject()).unwrap_or_default()
+}
+
+/// List the caller's favorite connections and saved queries in a domain
+///
+/// GET /api/domains/{domain_id}/favorites
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/favorites",
+    tag = "favorites",
+    params(("domain_id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = FavoriteListResponse),
+    ),
+)]
+pub async fn list_favorites(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(domain_id): Path<String>,
+) -> Result<Json<FavoriteListResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    let favorites = state
+        .storage
+        .list_favorites(&favorite_owner(principal.as_deref()), &domain_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(FavoriteListResponse { favorites }))
+}
+
+/// Mark a connection or saved query of the domain as a favorite of the caller
+///
+/// PUT /api/domains/{domain_id}/favorites/{resource_type}/{resource_id}
+#[utoipa::path(
+    put,
+    path = "/api/domains/{domain_id}/favorites/{resource_type}/{resource_id}",
+    tag = "favorites",
+    params(
+        ("domain_id" = String, Path),
+        ("resource_type" = FavoriteType, Path),
+        ("resource_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn add_favorite(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, resource_type, resource_id)): Path<(String, String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    let resource_type = FavoriteType::from_str(&resource_type).map_err(AppError::Validation)?;
+
+    let resource_domain = match resource_type {
+        FavoriteType::Connection => state
+            .storage
+            .get_connection(&resource_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .map(|connection| connection.domain_id),
+        FavoriteType::SavedQuery => state
+            .storage
+            .get_saved_query(&resource_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .map(|saved| Some(saved.domain_id)),
+    };
+    if resource_domain.flatten().as_deref() != Some(domain_id.as_str()) {
+        return Err(AppError::NotFound(format!(
+            "{} {} not found in domain {}",
+            resource_type.as_str(),
+            resource_id,
+            domain_id
+        )));
+    }
+
+    state
+        .storage
+        .add_favorite(&favorite_owner(principal.as_deref()), resource_type, &resource_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a favorite of the caller
+///
+/// DELETE /api/domains/{domain_id}/favorites/{resource_type}/{resource_id}
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{domain_id}/favorites/{resource_type}/{resource_id}",
+    tag = "favorites",
+    params(
+        ("domain_id" = String, Path),
+        ("resource_type" = FavoriteType, Path),
+        ("resource_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn remove_favorite(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, resource_type, resource_id)): Path<(String, String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    let resource_type = FavoriteType::from_str(&resource_type).map_err(AppError::Validation)?;
+
+    let removed = state
+        .storage
+        .remove_favorite(&favorite_owner(principal.as_deref()), resource_type, &resource_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if !removed {
+        return Err(AppError::NotFound(format!(
+            "{} {} is not a favorite",
+            resource_type.as_str(),
+            resource_id
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the queries the caller ran recently in a domain, latest first
+///
+/// GET /api/domains/{domain_id}/recent?limit=20
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/recent",
+    tag = "favorites",
+    params(
+        ("domain_id" = String, Path),
+        ("limit" = Option<u32>, Query, description = "Entries listed (1-100, default 20)"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = RecentQueryListResponse),
+    ),
+)]
+pub async fn list_recent_queries(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(domain_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<RecentQueryListResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    let limit = params
+        .get("limit")
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(20);
+    if !(1..=100).contains(&limit) {
+        return Err(AppError::Validation("limit must be between 1 and 100".to_string()));
+    }
+
+    let executed_by = principal.as_deref().map(|p| p.subject());
+    let recent = state
+        .storage
+        .list_recent_queries(&domain_id, executed_by.as_deref(), limit)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(RecentQueryListResponse { recent }))
+}
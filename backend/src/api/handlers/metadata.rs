@@ -1,19 +1,45 @@
 use axum::{
     extract::{Path, Query, State},
-    Json,
+    Extension, Json,
 };
+use serde::Deserialize;
+use utoipa::IntoParams;
 use std::collections::HashMap;
 
-use crate::api::middleware::AppError;
-use crate::services::{DbService, MetadataCacheService};
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::api::responses::{
+    AutocompleteResponse, MetadataRefreshResponse, MetadataResponse, TableCountResponse, TableProfileResponse,
+    TableSampleResponse,
+};
+use crate::models::{
+    AutocompleteRequest, DatabaseConnection, DomainRole, DomainSettings, MetadataDiff, MetadataVersionSummary, Principal,
+};
+use crate::services::database::{create_adapter, DatabaseAdapter, DatabaseType};
+use crate::services::{
+    ConnectionPolicyService, DomainSettingsService, MetadataCacheService, SqlCompleter, TablePreviewService,
+    TableProfileService, DEFAULT_SAMPLE_ROWS,
+};
 use crate::api::handlers::connection::AppState;
 
 /// Get database metadata
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/metadata",
+    tag = "metadata",
+    params(
+        ("id" = String, Path),
+        ("refresh" = Option<bool>, Query, description = "Bypass the cache and re-read the schema"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = MetadataResponse),
+    ),
+)]
 pub async fn get_metadata(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(id): Path<String>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<MetadataResponse>, AppError> {
     tracing::info!("Getting metadata for connection: {}", id);
     
     // Check if connection exists
@@ -23,6 +49,7 @@ pub async fn get_metadata(
         .await
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Viewer).await?;
 
     // Check if refresh is requested
     let refresh = params
@@ -30,69 +57,384 @@ pub async fn get_metadata(
         .map(|v| v == "true")
         .unwrap_or(false);
 
-    let cache_service = MetadataCacheService::new(state.storage.clone());
-
     if refresh {
         tracing::info!("Force refreshing metadata for connection: {}", id);
-        // Force refresh: retrieve fresh metadata from database
-        let (_, metadata) = DbService::connect_and_get_metadata(
-            id.clone(),
-            &connection.connection_url,
-            &connection.database_type,
-            state.pool_manager.clone(),
-        )
-        .await?;
-        
-        tracing::info!("Metadata refreshed. Found {} tables and {} views", metadata.tables.len(), metadata.views.len());
-
-        // Convert to JSON using LLM service
-        let llm_service = crate::services::LlmService::new(&state.config);
-        let metadata_json = llm_service.convert_metadata_to_json(&metadata).await?;
-        
-        let mut metadata_with_json = metadata;
-        metadata_with_json.metadata_json = metadata_json;
-        metadata_with_json.increment_version();
-
-        // Save to cache
-        cache_service.save_metadata(&metadata_with_json).await?;
-
-        Ok(Json(serde_json::json!({
-            "metadata": metadata_with_json,
-            "cached": false
-        })))
-    } else {
-        // Try to get from cache
-        match cache_service.get_cached_metadata(&id).await? {
-            Some(metadata) => Ok(Json(serde_json::json!({
-                "metadata": metadata,
-                "cached": true
-            }))),
-            None => {
-                // No cache, retrieve fresh
-                let (_, metadata) = DbService::connect_and_get_metadata(
-                    id.clone(),
-                    &connection.connection_url,
-                    &connection.database_type,
-                    state.pool_manager.clone(),
-                )
-                .await?;
-
-                // Convert to JSON
-                let llm_service = crate::services::LlmService::new(&state.config);
-                let metadata_json = llm_service.convert_metadata_to_json(&metadata).await?;
-                
-                let mut metadata_with_json = metadata;
-                metadata_with_json.metadata_json = metadata_json;
-
-                // Save to cache
-                cache_service.save_metadata(&metadata_with_json).await?;
-
-                Ok(Json(serde_json::json!({
-                    "metadata": metadata_with_json,
-                    "cached": false
-                })))
-            }
+        let refreshed = state.metadata_refresh.refresh(&connection, false).await?;
+        return Ok(Json(MetadataResponse {
+            metadata: refreshed.metadata,
+            cached: false,
+        }));
+    }
+
+    // Try to get from cache; without one, the schema is read now
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    match cache_service.get_cached_metadata(&id).await? {
+        Some(metadata) => Ok(Json(MetadataResponse {
+            metadata,
+            cached: true,
+        })),
+        None => {
+            let refreshed = state.metadata_refresh.refresh(&connection, false).await?;
+            Ok(Json(MetadataResponse {
+                metadata: refreshed.metadata,
+                cached: false,
+            }))
         }
     }
 }
 
+/// Query parameters for metadata refreshes
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MetadataRefreshParams {
+    /// Read the columns of every table, not only of new or changed ones
+    #[serde(default)]
+    pub full: bool,
+}
+
+/// Re-read a connection's schema and save it as a new metadata version
+///
+/// POST /api/connections/{id}/metadata/refresh
+///
+/// Only the columns of tables that are new or changed since the previous
+/// version are read, unless `full=true`. `diff` lists the tables added,
+/// removed or changed (null on the first read); a non-empty diff drops cached
+/// query results of the connection and raises the `metadata.changed` webhook
+/// event.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/metadata/refresh",
+    tag = "metadata",
+    params(("id" = String, Path), MetadataRefreshParams),
+    responses(
+        (status = 200, description = "OK", body = MetadataRefreshResponse),
+    ),
+)]
+pub async fn refresh_metadata(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<MetadataRefreshParams>,
+) -> Result<Json<MetadataRefreshResponse>, AppError> {
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    let refreshed = state.metadata_refresh.refresh(&connection, params.full).await?;
+    Ok(Json(MetadataRefreshResponse {
+        changed: refreshed.diff.as_ref().is_some_and(|diff| !diff.is_empty()),
+        metadata: refreshed.metadata,
+        diff: refreshed.diff,
+    }))
+}
+
+/// Query parameters for metadata diffs
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MetadataDiffParams {
+    /// Base version (defaults to the previous retained version)
+    pub from: Option<i32>,
+    /// Target version (defaults to the latest)
+    pub to: Option<i32>,
+}
+
+/// List retained metadata versions
+///
+/// GET /api/connections/{id}/metadata/versions
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/metadata/versions",
+    tag = "metadata",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = Vec<MetadataVersionSummary>),
+    ),
+)]
+pub async fn list_metadata_versions(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<MetadataVersionSummary>>, AppError> {
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    Ok(Json(cache_service.list_versions(&id).await?))
+}
+
+/// Diff two metadata versions (added/removed/changed tables and columns)
+///
+/// GET /api/connections/{id}/metadata/diff?from=&to=
+///
+/// `to` defaults to the latest version, `from` to the previous retained version.
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/metadata/diff",
+    tag = "metadata",
+    params(
+        ("id" = String, Path),
+        MetadataDiffParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = MetadataDiff),
+    ),
+)]
+pub async fn get_metadata_diff(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<MetadataDiffParams>,
+) -> Result<Json<MetadataDiff>, AppError> {
+    tracing::info!("Diffing metadata for connection {} ({:?} -> {:?})", id, params.from, params.to);
+
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    let diff = cache_service.diff(&id, params.from, params.to).await?;
+    Ok(Json(diff))
+}
+
+/// Complete partial SQL from the cached schema
+///
+/// POST /api/connections/{id}/autocomplete
+///
+/// Never reads the schema from the database: without cached metadata only
+/// keywords are suggested and `schema_cached` is false.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/autocomplete",
+    tag = "metadata",
+    params(("id" = String, Path)),
+    request_body = AutocompleteRequest,
+    responses(
+        (status = 200, description = "OK", body = AutocompleteResponse),
+    ),
+)]
+pub async fn autocomplete(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Json(request): Json<AutocompleteRequest>,
+) -> Result<Json<AutocompleteResponse>, AppError> {
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    let metadata = cache_service.get_cached_metadata(&id).await?;
+
+    let cursor = request.cursor.unwrap_or(usize::MAX);
+    let limit = request.limit.unwrap_or(50).clamp(1, 500);
+    let completions = SqlCompleter::new(metadata.as_ref()).complete(&request.sql, cursor, limit);
+
+    Ok(Json(AutocompleteResponse {
+        context: completions.context,
+        prefix: completions.prefix,
+        suggestions: completions.suggestions,
+        schema_cached: metadata.is_some(),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TableSampleParams {
+    /// Rows to return (default 100, capped by the domain's `max_row_limit`)
+    pub limit: Option<u64>,
+}
+
+/// Preview a table's rows without writing SQL
+///
+/// GET /api/connections/{id}/tables/{schema}/{table}/sample?limit=100
+///
+/// Runs a generated `SELECT *` with the identifiers quoted for the database.
+/// Needs the Editor role like other queries; masking policies and the hourly
+/// query quota apply. Not recorded in the query history.
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/tables/{schema}/{table}/sample",
+    tag = "metadata",
+    params(("id" = String, Path), ("schema" = String, Path), ("table" = String, Path), TableSampleParams),
+    responses(
+        (status = 200, description = "OK", body = TableSampleResponse),
+    ),
+)]
+pub async fn sample_table(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, schema, table)): Path<(String, String, String)>,
+    Query(params): Query<TableSampleParams>,
+) -> Result<Json<TableSampleResponse>, AppError> {
+    let (connection, settings, adapter) = preview_target(&state, principal.as_deref(), &id, DomainRole::Editor).await?;
+
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_SAMPLE_ROWS).clamp(1, settings.max_row_limit);
+    let mut result =
+        TablePreviewService::sample(adapter.as_ref(), &schema, &table, limit, settings.default_timeout_secs).await?;
+    policy.mask_rows(&mut result.rows);
+
+    Ok(Json(TableSampleResponse {
+        schema,
+        table,
+        row_count: result.row_count,
+        rows: result.rows,
+        execution_time_ms: result.execution_time_ms,
+    }))
+}
+
+/// Count a table's rows exactly
+///
+/// GET /api/connections/{id}/tables/{schema}/{table}/count
+///
+/// Runs a generated `COUNT(*)`; the `row_count` in metadata is the database's
+/// (usually estimated) statistic instead. Needs the Viewer role.
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/tables/{schema}/{table}/count",
+    tag = "metadata",
+    params(("id" = String, Path), ("schema" = String, Path), ("table" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = TableCountResponse),
+    ),
+)]
+pub async fn count_table(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, schema, table)): Path<(String, String, String)>,
+) -> Result<Json<TableCountResponse>, AppError> {
+    let (_, settings, adapter) = preview_target(&state, principal.as_deref(), &id, DomainRole::Viewer).await?;
+
+    let row_count = TablePreviewService::count(adapter.as_ref(), &schema, &table, settings.default_timeout_secs).await?;
+    Ok(Json(TableCountResponse { schema, table, row_count }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TableProfileParams {
+    /// Schema of the table; may be omitted when the name is unique
+    pub schema: Option<String>,
+    /// Recompute even when a profile of the current metadata version is cached
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// Profile a table's columns
+///
+/// POST /api/connections/{id}/tables/{table}/profile?schema=sales
+///
+/// Runs generated profiling SQL for null ratios, distinct counts, min/max,
+/// most frequent values and histograms of numeric columns. Columns come from
+/// the cached metadata, which must have been read. The profile is cached per
+/// table until the metadata changes version; `refresh=true` recomputes it.
+/// Needs the Editor role like table samples; masked columns keep their counts
+/// but not their values, and the hourly query quota applies to recomputes.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/tables/{table}/profile",
+    tag = "metadata",
+    params(("id" = String, Path), ("table" = String, Path), TableProfileParams),
+    responses(
+        (status = 200, description = "OK", body = TableProfileResponse),
+    ),
+)]
+pub async fn profile_table(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((id, table)): Path<(String, String)>,
+    Query(params): Query<TableProfileParams>,
+) -> Result<Json<TableProfileResponse>, AppError> {
+    let (connection, settings, adapter) = preview_target(&state, principal.as_deref(), &id, DomainRole::Editor).await?;
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+
+    let metadata = state
+        .storage
+        .get_metadata_cache(&connection.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("No metadata cached for connection {}; read it first", connection.id))
+        })?;
+    let relation = TableProfileService::find_relation(&metadata, params.schema.as_deref(), &table)?;
+
+    let cached = if params.refresh {
+        None
+    } else {
+        state
+            .storage
+            .get_table_profile(&connection.id, &relation.schema, &relation.name)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|profile| profile.metadata_version == metadata.version)
+    };
+    let (mut profile, cached) = match cached {
+        Some(profile) => (profile, true),
+        None => {
+            policy_service.ensure_within_quota(&connection, &policy).await?;
+            let profile =
+                TableProfileService::profile(adapter.as_ref(), &metadata, &relation, settings.default_timeout_secs)
+                    .await?;
+            state
+                .storage
+                .save_table_profile(&profile)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            (profile, false)
+        }
+    };
+    profile.mask(&policy);
+
+    Ok(Json(TableProfileResponse { profile, cached }))
+}
+
+/// Connection, domain settings and adapter for a table preview
+async fn preview_target(
+    state: &AppState,
+    principal: Option<&Principal>,
+    id: &str,
+    role: DomainRole,
+) -> Result<(DatabaseConnection, DomainSettings, Box<dyn DatabaseAdapter>), AppError> {
+    let connection = state
+        .storage
+        .get_connection(id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(state, principal, connection.domain_id.as_deref(), role).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    )
+    .await?;
+    Ok((connection, settings, adapter))
+}
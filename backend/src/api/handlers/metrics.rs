@@ -0,0 +1,126 @@
+// Semantic Metrics Handlers
+//
+// A domain's metrics definition (entities, dimensions, measures and default
+// joins) is written as YAML or JSON. Metric requests compile to SQL in the
+// dialect of one of the domain's connections; nothing is executed.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use std::collections::HashMap;
+
+use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::models::{CompileMetricRequest, CompiledMetric, DomainMetrics, DomainRole, MetricsDefinition, Principal};
+use crate::services::MetricsService;
+
+/// Get the metrics definition of a domain
+///
+/// GET /api/domains/{id}/metrics?format=json|yaml
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/metrics",
+    tag = "metrics",
+    params(
+        ("id" = String, Path),
+        ("format" = Option<String>, Query, description = "`json` (default) or `yaml`"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = DomainMetrics),
+    ),
+)]
+pub async fn get_domain_metrics(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    let metrics = MetricsService::new(state.storage.clone()).get(&id).await?;
+    match params.get("format").map(|f| f.to_lowercase()).as_deref() {
+        None | Some("json") => Ok(Json(metrics).into_response()),
+        Some("yaml") | Some("yml") => {
+            let body = serde_yaml::to_string(&metrics)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize metrics: {}", e)))?;
+            Ok(([(header::CONTENT_TYPE, "application/yaml")], body).into_response())
+        }
+        Some(other) => Err(AppError::Validation(format!(
+            "Unsupported format '{}' (expected json or yaml)",
+            other
+        ))),
+    }
+}
+
+/// Replace the metrics definition of a domain
+///
+/// PUT /api/domains/{id}/metrics
+///
+/// Accepts JSON or YAML (`Content-Type: application/yaml`).
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}/metrics",
+    tag = "metrics",
+    params(("id" = String, Path)),
+    request_body = MetricsDefinition,
+    responses(
+        (status = 200, description = "OK", body = DomainMetrics),
+    ),
+)]
+pub async fn update_domain_metrics(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<DomainMetrics>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Admin).await?;
+
+    let is_yaml = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("yaml"));
+
+    let definition: MetricsDefinition = if is_yaml {
+        serde_yaml::from_str(&body)
+            .map_err(|e| AppError::Validation(format!("Invalid YAML metrics definition: {}", e)))?
+    } else {
+        serde_json::from_str(&body)
+            .map_err(|e| AppError::Validation(format!("Invalid JSON metrics definition: {}", e)))?
+    };
+
+    let metrics = MetricsService::new(state.storage.clone()).save(&id, definition).await?;
+    Ok(Json(metrics))
+}
+
+/// Compile a metric request into SQL for one of the domain's connections
+///
+/// POST /api/domains/{id}/metrics/compile
+///
+/// `{"metric": "revenue", "group_by": ["region"], "grain": "day"}` compiles
+/// to the measure aggregated per truncated day and region, joining entities
+/// over the default joins, in the connection's dialect. Nothing is executed.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/metrics/compile",
+    tag = "metrics",
+    params(("id" = String, Path)),
+    request_body = CompileMetricRequest,
+    responses(
+        (status = 200, description = "OK", body = CompiledMetric),
+    ),
+)]
+pub async fn compile_metric(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+    Json(payload): Json<CompileMetricRequest>,
+) -> Result<Json<CompiledMetric>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&id), DomainRole::Viewer).await?;
+
+    let compiled = MetricsService::new(state.storage.clone()).compile(&id, &payload).await?;
+    Ok(Json(compiled))
+}
@@ -1,6 +1,19 @@
+pub mod chat;
 pub mod connection;
+pub mod data_dictionary;
 pub mod domain;
 pub mod metadata;
+pub mod metrics;
 pub mod query;
+pub mod result_contract;
+pub mod query_job;
+pub mod query_session;
+pub mod sql;
+pub mod sql_feedback;
+pub mod transaction;
 pub mod cross_database_query;
-
+pub mod trash;
+pub mod admin;
+pub mod auth;
+pub mod share;
+pub mod favorite;
@@ -1,188 +1,1339 @@
 use axum::{
+    body::Body,
     extract::{Path, State},
-    Json,
+    http::{header, HeaderMap},
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use utoipa::IntoParams;
+
+use crate::api::middleware::{
+    current_request_id, ensure_max_length, expected_version, in_request, require_domain_role, sql_audit_context, AppError,
 };
-
-use crate::api::middleware::AppError;
 use crate::api::handlers::connection::AppState;
+use crate::api::handlers::cross_database_query::{planner_for_request, view_adapter};
+use crate::api::responses::{
+    AdviseResponse, ArchiveQueryResponse, CancelledQueryResponse, ExplainResponse, QueryHistoryDailyResponse, QueryHistoryListResponse, QueryResponse, SavedQueryDeletedResponse,
+    SavedQueryListResponse, ViewDematerializedResponse,
+};
+use crate::config::LlmConfig;
 use crate::models::{
-    Query, QueryRequest, NaturalLanguageQueryRequest, UnifiedQueryRequest,
-    DatabaseType as ModelDatabaseType, SavedQuery, QueryHistory,
-    CreateSavedQueryRequest, UpdateSavedQueryRequest,
+    Query, QueryRequest, NaturalLanguageQueryRequest, NaturalLanguageQueryEvent, UnifiedQueryRequest,
+    DatabaseType as ModelDatabaseType, DatabaseConnection, SavedQuery, QueryHistory, Principal,
+    DomainRole, CreateSavedQueryRequest, UpdateSavedQueryRequest, ListParams, UnifiedQueryResponse,
+    CrossDatabaseQueryResponse, TransformStep, validate_transform, ExportFormat, ColumnarResult, ResultFormat,
+    ResultFormatParams, QueryParameters, HistoryResultFormat, HistoryResult, ExecuteRequest, ExecuteSavedQueryRequest, WriteResult, QueryKind,
+    RunningQuery, QueryHistoryStatus, WebhookEvent, ExplainRequest, EffectivePolicy, MaterializeViewRequest,
+    ViewMaterialization, DomainSettings, DatabaseMetadata, ChatTurn, QueryPlan, SummarizeResultRequest, ResultSummary,
+    SqlExample, CacheStatus, LlmAuditContext, QueryShare, RerunHistoryRequest, PromoteHistoryRequest,
+    HistoryFilterParams, HistoryAggregation, ListQuery,
 };
-use crate::services::{QueryService, LlmService, MetadataCacheService};
-use crate::services::database::{DatabaseType, create_adapter};
+use crate::services::{
+    ApiUsageService, ChartRecommendationService, ConnectionPolicyService, DomainSettingsService, EmbeddingService, HistoryArchiveService, HistoryResultService, CachedGeneration, LlmService, LlmUsageService, MetadataCacheService, NotificationService, PruneSummary, PromptRedactor, QueryAdvisorService, QueryReplayService,
+    QueryService, ResultExportService, ResultInsightService, SqlFeedbackService, profile_columns, degraded_llm_config, ResultTransformService, TrashService, EXPORT_BATCH_ROWS,
+};
+use crate::services::database::{DatabaseType, RowBatchStream, create_adapter};
+use crate::services::datafusion::VirtualView;
+use crate::storage::SqliteStorage;
+use crate::validation::{BindParams, PlaceholderStyle, SqlValidator};
 
 /// Execute SQL query using connection pooling
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/query",
+    tag = "queries",
+    params(("id" = String, Path), ResultFormatParams),
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "OK", body = QueryResponse),
+    ),
+)]
 pub async fn execute_query(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<QueryRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<QueryResponse>, AppError> {
     tracing::info!("Executing SQL query for connection: {}", id);
+    let caller = QueryCaller::Principal(principal.as_deref());
+    run_query(&state, &id, payload, format.result_format, &headers, caller, None)
+        .await
+        .map(Json)
+}
+
+/// Who a query runs for
+#[derive(Clone, Copy)]
+pub(crate) enum QueryCaller<'a> {
+    /// The request's principal, whose domain role is checked
+    Principal(Option<&'a Principal>),
+    /// Visitor of a resolved share link of a saved query in the share's domain
+    Share(&'a QueryShare),
+}
+
+impl QueryCaller<'_> {
+    fn principal(&self) -> Option<&Principal> {
+        match self {
+            QueryCaller::Principal(principal) => *principal,
+            QueryCaller::Share(_) => None,
+        }
+    }
+
+    /// Identifier recorded in query history and the running query registry
+    fn subject(&self) -> Option<String> {
+        match self {
+            QueryCaller::Principal(principal) => principal.map(|p| p.subject()),
+            QueryCaller::Share(share) => Some(share.subject()),
+        }
+    }
+}
 
+/// Run a query on a connection, as `POST /api/connections/{id}/query` does;
+/// the history entry is linked to `saved_query_id` when given
+pub(crate) async fn run_query(
+    state: &AppState,
+    id: &str,
+    payload: QueryRequest,
+    result_format: ResultFormat,
+    headers: &HeaderMap,
+    caller: QueryCaller<'_>,
+    saved_query_id: Option<&str>,
+) -> Result<QueryResponse, AppError> {
     // Sanitize SQL query input
     let sanitized_query = payload.query.trim();
     if sanitized_query.is_empty() {
         return Err(AppError::Validation("SQL query cannot be empty".to_string()));
     }
+    ensure_max_length("SQL query", sanitized_query, state.config.limits.max_sql_length)?;
 
     // Get connection from storage
     let connection = state
         .storage
-        .get_connection(&id)
+        .get_connection(id)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    match caller {
+        QueryCaller::Principal(principal) => {
+            require_domain_role(state, principal, connection.domain_id.as_deref(), DomainRole::Editor).await?
+        }
+        QueryCaller::Share(share) => {
+            if connection.domain_id.as_deref() != Some(share.domain_id.as_str()) {
+                return Err(AppError::NotFound(format!("Connection {} not found", id)));
+            }
+        }
+    }
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    // Per-domain row limit, timeout (and caps on the request's overrides) and allowed database types
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    // Label-bound policies: hourly quota now, column masking on the result
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    // Result transformation: the saved query's steps, then the request's
+    let transform = ResultTransformService::new(state.storage.clone())
+        .steps_for_request(&connection.id, payload.saved_query_id.as_deref(), &payload.transform)
+        .await?;
+    let parameters = request_parameters(state, payload.saved_query_id.as_deref(), &payload.parameters).await?;
 
     // Create database adapter with connection pool
     let db_type = DatabaseType::from_str(&connection.database_type)?;
     let adapter = create_adapter(
         db_type,
         &connection.connection_url,
+        connection.read_only,
         state.pool_manager.clone(),
     ).await?;
 
     // Execute query using QueryService (validation will happen there)
-    let query_service = QueryService::new();
-    let query = Query::new(id.clone(), sanitized_query.to_string(), false);
-    let result = query_service.execute_query_with_adapter(query, adapter).await?;
+    let audit = sql_audit_context(&state.config.audit, headers, caller.principal());
+    let query_service = QueryService::new()
+        .with_audit(audit)
+        .with_large_result_gate(state.config.limits.large_result_threshold, payload.confirm_large_result)
+        .with_parameters(parameters)
+        .with_result_cache(state.config.query_cache.enabled.then(|| state.query_cache.clone()));
+    let query = Query::new(id.to_string(), sanitized_query.to_string(), false);
+    let mut result = state
+        .running_queries
+        .run(
+            RunningQuery::new(
+                current_request_id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                connection.id.clone(),
+                connection.domain_id.clone(),
+                caller.subject(),
+            ),
+            query_service.execute_query_with_limits(
+                query,
+                adapter,
+                settings.row_limit(payload.limit_value),
+                settings.timeout_secs(payload.timeout_secs),
+            ),
+        )
+        .await?;
+    if let Some(rows) = result.results.as_mut() {
+        policy.mask_rows(rows);
+    }
 
     // Log query history (if connection has domain_id)
-    if let Some(domain_id) = &connection.domain_id {
-        let history = match &result.status {
-            crate::models::QueryStatus::Completed => {
-                QueryHistory::new(
-                    domain_id.clone(),
-                    id.clone(),
-                    sanitized_query.to_string(),
-                    result.row_count.unwrap_or(0),
-                    result.execution_time_ms.unwrap_or(0),
-                    false,
-                )
-            }
-            crate::models::QueryStatus::Failed => {
-                QueryHistory::new_failed(
-                    domain_id.clone(),
-                    id.clone(),
-                    sanitized_query.to_string(),
-                    result.error_message.clone().unwrap_or_else(|| "Unknown error".to_string()),
-                    false,
-                )
-            }
-            _ => {
-                // Don't log pending/executing states
-                return Ok(Json(serde_json::json!({
-                    "query": result,
-                })));
-            }
+    if let Some(history) = history_entry(&connection, sanitized_query, &result, caller.subject(), false) {
+        let history = history.with_saved_query(saved_query_id.map(str::to_string));
+        record_query_history(state, &connection, history, &result, payload.save_result).await;
+    }
+
+    // Transform after masking, so renamed or derived columns cannot expose masked values
+    apply_transform(state, &transform, &mut result).await?;
+
+    let chart = result.results.as_deref().and_then(ChartRecommendationService::recommend);
+    let columnar = query_columnar_result(result_format, &mut result)?;
+    Ok(QueryResponse { query: result, generated_sql: None, generation_cache: None, columnar, chart })
+}
+
+/// Response header telling whether an export was cut off at `EXPORT_MAX_ROWS`
+pub const EXPORT_TRUNCATED_HEADER: &str = "x-export-truncated";
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportParams {
+    /// File format: csv, ndjson, parquet, xlsx or arrow (Arrow IPC stream)
+    pub format: ExportFormat,
+}
+
+/// Execute a SQL query and download the result as a file
+///
+/// Runs like `POST /api/connections/{id}/query` (masking, history,
+/// transformation), but returns the rows as an attachment instead of JSON.
+/// The domain row limit does not apply; results are capped at
+/// `EXPORT_MAX_ROWS` rows instead (`X-Export-Truncated: true` when the cap
+/// was hit). CSV and NDJSON bodies are streamed: without a transformation
+/// they are encoded while the database returns the rows, and as the response
+/// starts before the last row is read, `X-Export-Truncated` is left out.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/query/export",
+    tag = "queries",
+    params(("id" = String, Path), ExportParams),
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Result file", content_type = "application/octet-stream"),
+    ),
+)]
+pub async fn export_query(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<ExportParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<QueryRequest>,
+) -> Result<Response, AppError> {
+    tracing::info!("Exporting SQL query result for connection: {} as {}", id, params.format.extension());
+
+    let sanitized_query = payload.query.trim();
+    if sanitized_query.is_empty() {
+        return Err(AppError::Validation("SQL query cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL query", sanitized_query, state.config.limits.max_sql_length)?;
+
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    let transform = ResultTransformService::new(state.storage.clone())
+        .steps_for_request(&connection.id, payload.saved_query_id.as_deref(), &payload.transform)
+        .await?;
+    let parameters = request_parameters(&state, payload.saved_query_id.as_deref(), &payload.parameters).await?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    ).await?;
+
+    // Exports are deliberate, so there is no large result confirmation; the export cap bounds them
+    // (and a requested `limit_value`, which may only lower it)
+    let max_rows = state.config.limits.max_export_rows.max(1);
+    let max_rows = payload.limit_value.map_or(max_rows, |limit| limit.clamp(1, max_rows));
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let query_service = QueryService::new().with_audit(audit).with_parameters(parameters);
+    let query = Query::new(id.clone(), sanitized_query.to_string(), false);
+    let timeout_secs = settings.timeout_secs(payload.timeout_secs);
+    let executed_by = principal.as_deref().map(|p| p.subject());
+
+    // CSV and NDJSON files are written while the rows arrive; transformations need the whole result
+    if params.format.is_streamable() && transform.is_empty() {
+        let started = Instant::now();
+        let running = running_query(&connection, principal.as_deref());
+        let (query, mut batches) = state
+            .running_queries
+            .run(
+                running.clone(),
+                query_service.stream_query_with_limits(query, adapter, max_rows, timeout_secs, EXPORT_BATCH_ROWS),
+            )
+            .await?;
+        // Query errors surface before the response starts
+        let first = match batches.next().await {
+            Some(Err(e)) => return Err(e),
+            first => first,
         };
+        let export = StreamedExport { state, connection, running, query, policy, executed_by, format: params.format, started };
+        let body = export.spawn(stream::iter(first).chain(batches).boxed());
+        return export_response(params.format, None, Body::from_stream(body));
+    }
+
+    let mut result = state
+        .running_queries
+        .run(
+            running_query(&connection, principal.as_deref()),
+            query_service.execute_query_with_limits(query, adapter, max_rows, timeout_secs),
+        )
+        .await?;
+    if let Some(rows) = result.results.as_mut() {
+        policy.mask_rows(rows);
+    }
+
+    log_query_history(&state, &connection, sanitized_query, &result, executed_by, false, None).await;
+    apply_transform(&state, &transform, &mut result).await?;
+
+    // The injected LIMIT was reached, so rows beyond the export cap were left out
+    let truncated = result.limit_applied && result.row_count.unwrap_or(0) as u64 >= max_rows;
+    let rows = result.results.unwrap_or_default();
+    let body = ResultExportService::encode(params.format, &rows)?;
+    export_response(params.format, Some(truncated), Body::from_stream(body))
+}
+
+/// Attachment response for an export (`truncated` is unknown for streamed exports)
+fn export_response(format: ExportFormat, truncated: Option<bool>, body: Body) -> Result<Response, AppError> {
+    let filename = format!(
+        "query-{}.{}",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename));
+    if let Some(truncated) = truncated {
+        response = response.header(EXPORT_TRUNCATED_HEADER, truncated.to_string());
+    }
+    response
+        .body(body)
+        .map_err(|e| AppError::Internal(format!("Failed to build export response: {}", e)))
+}
+
+/// CSV or NDJSON export whose rows are encoded while the query streams them
+struct StreamedExport {
+    state: AppState,
+    connection: DatabaseConnection,
+    running: RunningQuery,
+    query: Query,
+    policy: EffectivePolicy,
+    executed_by: Option<String>,
+    format: ExportFormat,
+    started: Instant,
+}
+
+impl StreamedExport {
+    /// Encode `batches` in a task of its own and return the file chunks
+    ///
+    /// The query stays cancellable by its request id until the last row is
+    /// written, and is logged to the history once done. A client that goes
+    /// away ends the query.
+    fn spawn(self, batches: RowBatchStream) -> ExportChunkStream {
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        tokio::spawn(async move {
+            let StreamedExport { state, connection, running, mut query, policy, executed_by, format, started } = self;
+            let mut row_count = 0;
+            let outcome = state
+                .running_queries
+                .run(running, async {
+                    let masked = batches.map(|batch| {
+                        batch.map(|mut rows| {
+                            policy.mask_rows(&mut rows);
+                            row_count += rows.len();
+                            rows
+                        })
+                    });
+                    let mut chunks = ResultExportService::encode_stream(format, masked)?.boxed();
+                    while let Some(chunk) = chunks.next().await {
+                        if tx.send(Ok(chunk?)).await.is_err() {
+                            return Err(AppError::Cancelled("Export download was closed by the client".to_string()));
+                        }
+                    }
+                    Ok(())
+                })
+                .await;
+
+            match outcome {
+                Ok(()) => query.mark_streamed(row_count, started.elapsed().as_millis() as u64),
+                Err(e) => {
+                    query.mark_failed(e.to_string());
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+            log_query_history(&state, &connection, &query.query_text, &query, executed_by, false, None).await;
+        });
+
+        stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) }).boxed()
+    }
+}
+
+/// File chunks of a streamed export
+type ExportChunkStream = futures::stream::BoxStream<'static, Result<axum::body::Bytes, AppError>>;
+
+/// Explain a query without executing it
+///
+/// Runs the database's native EXPLAIN (`FORMAT JSON` on PostgreSQL and MySQL,
+/// `EXPLAIN PLAN FOR` on Druid, the textual plan on Doris) and returns it as
+/// a normalized plan tree. With `unified: true` the query is in DataFusion
+/// syntax and the response also carries its translation to the connection's
+/// dialect. The query is explained as written, without the domain row limit.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/query/explain",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = ExplainRequest,
+    responses(
+        (status = 200, description = "OK", body = ExplainResponse),
+    ),
+)]
+pub async fn explain_query(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ExplainRequest>,
+) -> Result<Json<ExplainResponse>, AppError> {
+    let (_, translated_sql, plan) = explain_plan(&state, &id, &headers, principal.as_deref(), &payload).await?;
+
+    Ok(Json(ExplainResponse {
+        query: payload.query.trim().to_string(),
+        translated_sql,
+        estimated_rows: plan.estimated_rows(),
+        plan,
+    }))
+}
+
+/// `llm_error` of advice and summaries left to the rules or statistics because
+/// the domain is over its monthly LLM token budget
+const OVER_LLM_BUDGET: &str = "Domain is over its monthly LLM token budget";
 
-        // Log to history (ignore errors to not block query response)
-        if let Err(e) = state.storage.add_query_history(&history).await {
-            tracing::warn!("Failed to log query history: {}", e);
+/// Suggest optimizations for a query from its plan, schema and the LLM
+///
+/// Explains the query like `/query/explain`, then asks the LLM for advice on
+/// the plan and the cached schema of the tables involved, returned as
+/// structured suggestions (missing indexes with their `CREATE INDEX`
+/// statement, rewrites with the rewritten query). Rules over the plan add
+/// suggestions the LLM did not cover, and are the only source when no LLM
+/// gateway is configured, it fails or the domain is over its monthly LLM
+/// token budget with the `degrade` action (the `reject` action fails the
+/// request with QUOTA_EXCEEDED). Nothing is executed.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/query/advise",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = ExplainRequest,
+    responses(
+        (status = 200, description = "OK", body = AdviseResponse),
+    ),
+)]
+pub async fn advise_query(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ExplainRequest>,
+) -> Result<Json<AdviseResponse>, AppError> {
+    let (connection, translated_sql, plan) = explain_plan(&state, &id, &headers, principal.as_deref(), &payload).await?;
+    let sql = translated_sql.as_deref().unwrap_or(payload.query.trim());
+
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
+    let metadata = cache_service
+        .get_cached_metadata(&connection.id)
+        .await?
+        .unwrap_or_else(|| DatabaseMetadata::new(connection.id.clone(), Vec::new(), Vec::new(), Vec::new()));
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+
+    // Monthly LLM token budget: over it, reject or advise from the rules alone
+    let over_budget = LlmUsageService::new(state.storage.clone(), &state.config.llm)
+        .ensure_within_budget(connection.domain_id.as_deref(), &settings)
+        .await?;
+
+    let heuristics = QueryAdvisorService::heuristic_suggestions(sql, &plan, &metadata);
+    if over_budget {
+        return Ok(Json(AdviseResponse {
+            query: payload.query.trim().to_string(),
+            translated_sql,
+            estimated_rows: plan.estimated_rows(),
+            plan,
+            suggestions: heuristics,
+            llm_error: Some(OVER_LLM_BUDGET.to_string()),
+        }));
+    }
+    let advisor = QueryAdvisorService::new(&state.config)
+        .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&settings))
+        .with_audit(state.storage.clone(), llm_audit_context(&connection, principal.as_deref()));
+    let advised = advisor
+        .llm_suggestions(sql, &plan, &metadata, &connection.database_type)
+        .await;
+    if !matches!(advised, Ok(None)) {
+        ApiUsageService::new(state.storage.clone())
+            .record_llm_request(
+                connection.domain_id.as_deref(),
+                principal.as_deref(),
+                advisor.token_usage(),
+                advised.is_err(),
+            )
+            .await;
+    }
+    let (suggestions, llm_error) = match advised {
+        Ok(Some(llm)) => (QueryAdvisorService::merge(llm, heuristics), None),
+        Ok(None) => (heuristics, Some("LLM gateway is not configured".to_string())),
+        Err(e) => {
+            tracing::warn!(connection_id = %connection.id, "LLM query advice failed: {}", e);
+            (heuristics, Some(e.to_string()))
         }
+    };
+
+    Ok(Json(AdviseResponse {
+        query: payload.query.trim().to_string(),
+        translated_sql,
+        estimated_rows: plan.estimated_rows(),
+        plan,
+        suggestions,
+        llm_error,
+    }))
+}
+
+/// Summarize a query result with insights and follow-up questions
+///
+/// POST /api/connections/{id}/query/summarize
+///
+/// Takes the rows the client already holds; nothing is executed. Column
+/// statistics cover every submitted row, and the LLM sees them with an evenly
+/// spaced sample of the rows. Without an LLM gateway, when it fails or when
+/// the domain is over its monthly LLM token budget with the `degrade` action,
+/// the summary is phrased from the statistics alone (the `reject` action fails
+/// the request with QUOTA_EXCEEDED).
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/query/summarize",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = SummarizeResultRequest,
+    responses(
+        (status = 200, description = "OK", body = ResultSummary),
+    ),
+)]
+pub async fn summarize_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<SummarizeResultRequest>,
+) -> Result<Json<ResultSummary>, AppError> {
+    ensure_max_length("SQL query", payload.query.trim(), state.config.limits.max_sql_length)?;
+    if let Some(question) = &payload.question {
+        ensure_max_length("Question", question, state.config.limits.max_question_length)?;
     }
 
-    Ok(Json(serde_json::json!({
-        "query": result,
-    })))
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+
+    // Monthly LLM token budget: over it, reject or summarize from the statistics alone
+    let over_budget = LlmUsageService::new(state.storage.clone(), &state.config.llm)
+        .ensure_within_budget(connection.domain_id.as_deref(), &settings)
+        .await?;
+
+    let profiles = profile_columns(&payload.results);
+    if over_budget {
+        return Ok(Json(ResultSummary {
+            llm_error: Some(OVER_LLM_BUDGET.to_string()),
+            ..ResultInsightService::statistics_summary(&payload, &profiles)
+        }));
+    }
+    let insights = ResultInsightService::new(&state.config)
+        .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&settings))
+        .with_audit(state.storage.clone(), llm_audit_context(&connection, principal.as_deref()));
+    let summarized = insights.llm_summary(&payload, &profiles).await;
+    if !matches!(summarized, Ok(None)) {
+        ApiUsageService::new(state.storage.clone())
+            .record_llm_request(
+                connection.domain_id.as_deref(),
+                principal.as_deref(),
+                insights.token_usage(),
+                summarized.is_err(),
+            )
+            .await;
+    }
+    let summary = match summarized {
+        Ok(Some(summary)) => summary,
+        Ok(None) => ResultSummary {
+            llm_error: Some("LLM gateway is not configured".to_string()),
+            ..ResultInsightService::statistics_summary(&payload, &profiles)
+        },
+        Err(e) => {
+            tracing::warn!(connection_id = %connection.id, "LLM result summary failed: {}", e);
+            ResultSummary {
+                llm_error: Some(e.to_string()),
+                ..ResultInsightService::statistics_summary(&payload, &profiles)
+            }
+        }
+    };
+
+    Ok(Json(summary))
+}
+
+/// Explain a query on a connection, returning the connection, the dialect
+/// translation of a unified query and the plan
+async fn explain_plan(
+    state: &AppState,
+    id: &str,
+    headers: &HeaderMap,
+    principal: Option<&Principal>,
+    payload: &ExplainRequest,
+) -> Result<(DatabaseConnection, Option<String>, QueryPlan), AppError> {
+    let sanitized_query = payload.query.trim();
+    if sanitized_query.is_empty() {
+        return Err(AppError::Validation("SQL query cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL query", sanitized_query, state.config.limits.max_sql_length)?;
+
+    let connection = state
+        .storage
+        .get_connection(id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(state, principal, connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let unified = if payload.unified {
+        let database_type = ModelDatabaseType::from_str(&connection.database_type).map_err(AppError::Validation)?;
+        Some(database_type)
+    } else {
+        None
+    };
+    let parameters = request_parameters(state, None, &payload.parameters).await?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    ).await?;
+
+    let audit = sql_audit_context(&state.config.audit, headers, principal);
+    let query_service = QueryService::new().with_audit(audit).with_parameters(parameters);
+    let (translated_sql, plan) = query_service
+        .explain(sanitized_query, adapter.as_ref(), unified, settings.default_timeout_secs)
+        .await?;
+    Ok((connection, translated_sql, plan))
+}
+
+/// Execute a write statement (INSERT, UPDATE, DELETE, MERGE, TRUNCATE or DDL)
+///
+/// Only for connections with `allow_writes`; needs the Admin role on the
+/// connection's domain. One statement per request, no row limit; the response
+/// carries the affected row count. Successes and failures are recorded in the
+/// query history with kind `write`.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/execute",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = ExecuteRequest,
+    responses(
+        (status = 200, description = "OK", body = WriteResult),
+    ),
+)]
+pub async fn execute_write(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ExecuteRequest>,
+) -> Result<Json<WriteResult>, AppError> {
+    tracing::info!("Executing write statement for connection: {}", id);
+
+    let statement = payload.statement.trim();
+    if statement.is_empty() {
+        return Err(AppError::Validation("SQL statement cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL statement", statement, state.config.limits.max_sql_length)?;
+
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Admin).await?;
+    if !connection.allow_writes {
+        return Err(AppError::Forbidden(format!(
+            "Connection {} does not allow writes; enable allow_writes on the connection first",
+            id
+        )));
+    }
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    let parameters = request_parameters(&state, None, &payload.parameters).await?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    ).await?;
+
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let query_service = QueryService::new().with_audit(audit).with_parameters(parameters);
+    let result = state
+        .running_queries
+        .run(
+            running_query(&connection, principal.as_deref()),
+            query_service.execute_write(statement, adapter, settings.default_timeout_secs),
+        )
+        .await;
+
+    let executed_by = principal.as_deref().map(|p| p.subject());
+    log_write_history(&state, &connection, statement, result.as_ref(), executed_by).await;
+    Ok(Json(result?))
+}
+
+/// Record a write statement in the domain's history, including failures
+///
+/// Writes get no execution snapshot, as they are never replayed.
+pub(crate) async fn log_write_history(
+    state: &AppState,
+    connection: &DatabaseConnection,
+    statement: &str,
+    result: Result<&WriteResult, &AppError>,
+    executed_by: Option<String>,
+) {
+    let Some(domain_id) = &connection.domain_id else {
+        return;
+    };
+
+    let history = match result {
+        Ok(write) => QueryHistory::new(
+            domain_id.clone(),
+            connection.id.clone(),
+            statement.to_string(),
+            write.affected_rows as usize,
+            write.execution_time_ms,
+            false,
+        ),
+        Err(e) => QueryHistory::new_failed(
+            domain_id.clone(),
+            connection.id.clone(),
+            statement.to_string(),
+            e.to_string(),
+            false,
+        ),
+    }
+    .with_executed_by(executed_by)
+    .with_kind(QueryKind::Write);
+
+    if let Err(e) = state.storage.add_query_history(&history).await {
+        tracing::warn!("Failed to log write history: {}", e);
+    }
+    notify_query_failed(state, &history).await;
+}
+
+/// Raise the `query.failed` webhook event for a failed history entry
+async fn notify_query_failed(state: &AppState, history: &QueryHistory) {
+    if history.status != QueryHistoryStatus::Failed {
+        return;
+    }
+    let data = serde_json::json!({
+        "history_id": history.id,
+        "connection_id": history.connection_id,
+        "kind": history.kind.as_str(),
+        "executed_by": history.executed_by,
+        "error": history.error_message,
+    });
+    NotificationService::new(state.storage.clone())
+        .notify(Some(&history.domain_id), WebhookEvent::QueryFailed, data)
+        .await;
+}
+
+/// Cancel a running synchronous query
+///
+/// The query id is the request id of the executing request: send your own
+/// `X-Request-Id` with the query to cancel it while it runs. Covers the query,
+/// export, natural language, unified query and execute endpoints; the
+/// executing request fails with `QUERY_CANCELLED`. PostgreSQL and MySQL also
+/// cancel the statement on the server. Cancelling another principal's query
+/// needs the Admin role.
+#[utoipa::path(
+    post,
+    path = "/api/queries/{id}/cancel",
+    tag = "queries",
+    params(("id" = String, Path, description = "Request id of the running query")),
+    responses(
+        (status = 200, description = "Cancelled", body = CancelledQueryResponse),
+    ),
+)]
+pub async fn cancel_query(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<CancelledQueryResponse>, AppError> {
+    let not_running = || AppError::NotFound(format!("No query with id {} is running", id));
+    let query = state.running_queries.get(&id).ok_or_else(not_running)?;
+
+    let role = if query.started_by == principal.as_deref().map(|p| p.subject()) {
+        DomainRole::Editor
+    } else {
+        DomainRole::Admin
+    };
+    require_domain_role(&state, principal.as_deref(), query.domain_id.as_deref(), role).await?;
+
+    let cancelled = state.running_queries.cancel(&id).ok_or_else(not_running)?;
+    Ok(Json(CancelledQueryResponse { cancelled }))
 }
 
 /// Execute natural language query using connection pooling
+///
+/// SQL that ran for the same question on the connection's current metadata is
+/// reused for `llm.generation_cache_ttl_secs` without asking the LLM, unless
+/// `force_regenerate` is set.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/nl-query",
+    tag = "queries",
+    params(("id" = String, Path), ResultFormatParams),
+    request_body = NaturalLanguageQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = QueryResponse),
+    ),
+)]
 pub async fn execute_natural_language_query(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<NaturalLanguageQueryRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<QueryResponse>, AppError> {
     tracing::info!("Executing natural language query for connection: {}", id);
 
-    // Validate question
     let question = payload.question.trim();
+    let context = natural_language_context(&state, &id, question, &[], principal.as_deref()).await?;
+
+    let (generated, generation_cache) = match cached_generation(&state, &context, question, payload.force_regenerate) {
+        Some(cached) => (Ok(cached.sql), CacheStatus { hit: true, cached_at: Some(cached.generated_at) }),
+        None => {
+            // Generate SQL from natural language using LLM
+            tracing::info!(
+                connection_id = %id,
+                question = %crate::logging::redactor().text(question),
+                "Generating SQL from natural language question"
+            );
+            let llm_service = context
+                .llm_service(&state, principal.as_deref())
+                .with_examples(context.examples.clone());
+            let generated = llm_service
+                .generate_sql_from_natural_language(question, &context.metadata, &context.connection.database_type)
+                .await;
+            ApiUsageService::new(state.storage.clone())
+                .record_llm_request(
+                    context.connection.domain_id.as_deref(),
+                    principal.as_deref(),
+                    llm_service.token_usage(),
+                    generated.is_err(),
+                )
+                .await;
+            (generated, CacheStatus { hit: false, cached_at: None })
+        }
+    };
+    let (generated_sql, executed) = execute_with_repair(
+        &state,
+        &context,
+        question,
+        &[],
+        generated?,
+        &headers,
+        principal.as_deref(),
+        payload.confirm_large_result,
+        None,
+    )
+    .await;
+    update_generation_cache(&state, &context, question, &generated_sql, &executed);
+    let mut result = executed?;
+
+    let chart = result.results.as_deref().and_then(ChartRecommendationService::recommend);
+    let columnar = query_columnar_result(format.result_format, &mut result)?;
+    Ok(Json(QueryResponse {
+        query: result,
+        generated_sql: Some(generated_sql),
+        generation_cache: Some(generation_cache),
+        columnar,
+        chart,
+    }))
+}
+
+/// Execute a natural language query, streaming the generated SQL as Server-Sent Events
+///
+/// Emits `sql-token` events while the LLM writes the SQL (none when the SQL is
+/// cached for the question), then `sql-generated` with the complete SQL, `execution-started`, `sql-repaired` for each rewrite
+/// of SQL the database rejected, and a final `completed` (the query with its
+/// results) or `failed` event, after which the stream ends.
+/// Errors before generation starts (unknown connection, missing metadata,
+/// quota) are returned as regular error responses.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/nl-query/stream",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = NaturalLanguageQueryRequest,
+    responses(
+        (status = 200, description = "Event stream", content_type = "text/event-stream", body = NaturalLanguageQueryEvent),
+    ),
+)]
+pub async fn stream_natural_language_query(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<NaturalLanguageQueryRequest>,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, Infallible>>>, AppError> {
+    tracing::info!("Streaming natural language query for connection: {}", id);
+
+    let question = payload.question.trim().to_string();
+    let principal = principal.map(|Extension(principal)| principal);
+    let context = natural_language_context(&state, &id, &question, &[], principal.as_ref()).await?;
+
+    // The query stays cancellable under the request's id after the response starts
+    let (events, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(in_request(current_request_id(), async move {
+        let (generated, cache) = match cached_generation(&state, &context, &question, payload.force_regenerate) {
+            Some(cached) => (Ok(cached.sql), CacheStatus { hit: true, cached_at: Some(cached.generated_at) }),
+            None => {
+                // Forward the LLM's tokens as they arrive
+                let (tokens, mut token_receiver) = mpsc::unbounded_channel();
+                let forward = {
+                    let events = events.clone();
+                    tokio::spawn(async move {
+                        while let Some(token) = token_receiver.recv().await {
+                            let _ = events.send(NaturalLanguageQueryEvent::SqlToken { token });
+                        }
+                    })
+                };
+
+                tracing::info!(
+                    connection_id = %context.connection.id,
+                    question = %crate::logging::redactor().text(&question),
+                    "Generating SQL from natural language question"
+                );
+                let llm_service = context
+                    .llm_service(&state, principal.as_ref())
+                    .with_examples(context.examples.clone());
+                let generated = llm_service
+                    .stream_sql_from_natural_language(&question, &context.metadata, &context.connection.database_type, tokens)
+                    .await;
+                let _ = forward.await;
+                ApiUsageService::new(state.storage.clone())
+                    .record_llm_request(
+                        context.connection.domain_id.as_deref(),
+                        principal.as_ref(),
+                        llm_service.token_usage(),
+                        generated.is_err(),
+                    )
+                    .await;
+                (generated, CacheStatus { hit: false, cached_at: None })
+            }
+        };
+
+        let event = match generated {
+            Err(e) => NaturalLanguageQueryEvent::Failed { error_message: e.to_string() },
+            // Nobody is listening for the results any more
+            Ok(_) if events.is_closed() => return,
+            Ok(generated_sql) => {
+                let _ = events.send(NaturalLanguageQueryEvent::SqlGenerated { sql: generated_sql.clone(), cache });
+                let _ = events.send(NaturalLanguageQueryEvent::ExecutionStarted);
+                let (sql, executed) = execute_with_repair(
+                    &state,
+                    &context,
+                    &question,
+                    &[],
+                    generated_sql,
+                    &headers,
+                    principal.as_ref(),
+                    payload.confirm_large_result,
+                    Some(&events),
+                )
+                .await;
+                update_generation_cache(&state, &context, &question, &sql, &executed);
+                match executed {
+                    Ok(query) => NaturalLanguageQueryEvent::Completed { query },
+                    Err(e) => NaturalLanguageQueryEvent::Failed { error_message: e.to_string() },
+                }
+            }
+        };
+        let _ = events.send(event);
+    }));
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        let sse = Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+        Some((Ok(sse), receiver))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// What a natural language question on a connection is answered with
+pub(crate) struct NaturalLanguageContext {
+    pub(crate) connection: DatabaseConnection,
+    settings: DomainSettings,
+    policy: EffectivePolicy,
+    /// Cached schema given to the LLM (narrowed to the relevant tables on large schemas)
+    pub(crate) metadata: DatabaseMetadata,
+    /// Answered questions of the domain closest to this one, for the prompt
+    pub(crate) examples: Vec<SqlExample>,
+    /// LLM settings of the query (smaller prompts once the domain is over budget)
+    llm_config: LlmConfig,
+    /// Whether the domain is over its LLM token budget and the prompt is reduced
+    pub(crate) degraded: bool,
+}
+
+impl NaturalLanguageContext {
+    /// LLM service of the question: prompts are redacted with the domain's
+    /// deny-list and recorded in the LLM audit log
+    pub(crate) fn llm_service(&self, state: &AppState, principal: Option<&Principal>) -> LlmService {
+        LlmService::new(&state.config)
+            .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&self.settings))
+            .with_audit(state.storage.clone(), llm_audit_context(&self.connection, principal))
+    }
+}
+
+/// Who LLM prompts about a connection are sent for, as recorded in the LLM audit log
+pub(crate) fn llm_audit_context(connection: &DatabaseConnection, principal: Option<&Principal>) -> LlmAuditContext {
+    LlmAuditContext {
+        domain_id: connection.domain_id.clone(),
+        connection_id: Some(connection.id.clone()),
+        principal: principal.map(|p| p.subject()),
+    }
+}
+
+/// Validate a natural language question and load its connection's settings, policy and metadata
+///
+/// The earlier turns of a chat session (`history`) take part in picking the
+/// tables relevant to the question.
+pub(crate) async fn natural_language_context(
+    state: &AppState,
+    id: &str,
+    question: &str,
+    history: &[ChatTurn],
+    principal: Option<&Principal>,
+) -> Result<NaturalLanguageContext, AppError> {
+    // Validate question
     if question.is_empty() {
         return Err(AppError::Validation("Question cannot be empty".to_string()));
     }
+    ensure_max_length("Question", question, state.config.limits.max_question_length)?;
 
     // Get connection
     let connection = state
         .storage
-        .get_connection(&id)
+        .get_connection(id)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(state, principal, connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    // Per-domain row limit, timeout and allowed database types
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    // Label-bound policies: hourly quota now, column masking on the result
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    // Monthly LLM token budget: over it, reject or send a smaller prompt
+    let degraded = LlmUsageService::new(state.storage.clone(), &state.config.llm)
+        .ensure_within_budget(connection.domain_id.as_deref(), &settings)
+        .await?;
+    let llm_config = if degraded {
+        degraded_llm_config(&state.config.llm)
+    } else {
+        state.config.llm.clone()
+    };
 
     // Get metadata for LLM context
-    let cache_service = MetadataCacheService::new(state.storage.clone());
+    let cache_service = MetadataCacheService::new(state.storage.clone(), state.config.metadata.clone());
     let metadata = cache_service
-        .get_cached_metadata(&id)
+        .get_cached_metadata(id)
         .await?
         .ok_or_else(|| AppError::NotFound("Database metadata not found. Please connect to the database first.".to_string()))?;
 
-    // Generate SQL from natural language using LLM
-    tracing::info!("Generating SQL from natural language question: {}", question);
-    let llm_service = LlmService::new(&state.config);
-    let generated_sql = llm_service
-        .generate_sql_from_natural_language(question, &metadata, &connection.database_type)
+    // Large schemas: only the tables most relevant to the question go into the prompt
+    let retrieval_text = history
+        .iter()
+        .map(|turn| turn.question.as_str())
+        .chain(std::iter::once(question))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let embeddings = EmbeddingService::from_config(&llm_config)
+        .with_redactor(PromptRedactor::new(&state.config.llm).with_domain(&settings))
+        .with_audit(state.storage.clone(), llm_audit_context(&connection, principal));
+    let metadata = state
+        .schema_indexes
+        .relevant_metadata(&embeddings, &llm_config, &retrieval_text, metadata)
         .await?;
 
-    tracing::info!("Generated SQL from natural language: {}", generated_sql);
+    // Rated or corrected past questions as few-shot examples
+    let examples = SqlFeedbackService::new(state.storage.clone(), &llm_config)
+        .similar_examples(connection.domain_id.as_deref(), &connection.database_type, question)
+        .await?;
+
+    Ok(NaturalLanguageContext {
+        connection,
+        settings,
+        policy,
+        metadata,
+        examples,
+        llm_config,
+        degraded,
+    })
+}
+
+/// SQL cached for a question on the context's connection and metadata version,
+/// unless the request forces a new generation
+fn cached_generation(
+    state: &AppState,
+    context: &NaturalLanguageContext,
+    question: &str,
+    force_regenerate: bool,
+) -> Option<CachedGeneration> {
+    if force_regenerate {
+        return None;
+    }
+    state.generation_cache.get(
+        &context.connection.id,
+        question,
+        context.metadata.version,
+        &context.connection.database_type,
+    )
+}
+
+/// Cache SQL that answered a question, or drop the cached SQL when the database rejected it
+fn update_generation_cache(
+    state: &AppState,
+    context: &NaturalLanguageContext,
+    question: &str,
+    sql: &str,
+    executed: &Result<Query, AppError>,
+) {
+    let connection = &context.connection;
+    match executed {
+        Ok(_) => state.generation_cache.put(
+            &connection.id,
+            question,
+            context.metadata.version,
+            &connection.database_type,
+            sql,
+        ),
+        Err(e) if e.is_sql_error() => {
+            state.generation_cache.invalidate_question(&connection.id, question);
+        }
+        Err(_) => {}
+    }
+}
+
+/// Run generated SQL, sending it back to the LLM with the database error while it fails
+///
+/// Up to `llm.max_repair_attempts` rewrites (none for degraded queries) are
+/// tried before the failure is returned; each is announced on `repairs` (streamed queries). Returns the SQL
+/// that ran last with its outcome.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn execute_with_repair(
+    state: &AppState,
+    context: &NaturalLanguageContext,
+    question: &str,
+    history: &[ChatTurn],
+    generated_sql: String,
+    headers: &HeaderMap,
+    principal: Option<&Principal>,
+    confirm_large_result: bool,
+    repairs: Option<&mpsc::UnboundedSender<NaturalLanguageQueryEvent>>,
+) -> (String, Result<Query, AppError>) {
+    let connection = &context.connection;
+    let mut sql = generated_sql;
+    let mut attempt = 0;
+    loop {
+        let executed =
+            execute_generated_sql(state, context, &sql, headers, principal, confirm_large_result, attempt > 0).await;
+        let error_message = match &executed {
+            Err(e) if e.is_sql_error() && attempt < context.llm_config.max_repair_attempts => e.to_string(),
+            _ => return (sql, executed),
+        };
+        attempt += 1;
+
+        tracing::info!(
+            connection_id = %connection.id,
+            attempt,
+            error = %error_message,
+            "Asking the LLM to repair failed generated SQL"
+        );
+        let llm_service = context.llm_service(state, principal);
+        let repaired = llm_service
+            .repair_sql(question, history, &sql, &error_message, &context.metadata, &connection.database_type)
+            .await;
+        ApiUsageService::new(state.storage.clone())
+            .record_llm_request(connection.domain_id.as_deref(), principal, llm_service.token_usage(), repaired.is_err())
+            .await;
+        match repaired {
+            Ok(repaired) if repaired.trim() != sql.trim() => {
+                if let Some(repairs) = repairs {
+                    let _ = repairs.send(NaturalLanguageQueryEvent::SqlRepaired {
+                        attempt,
+                        sql: repaired.clone(),
+                        error_message,
+                    });
+                }
+                sql = repaired;
+            }
+            // No different SQL to try: the failure stands
+            _ => return (sql, executed),
+        }
+    }
+}
+
+/// Run SQL the LLM generated, masking the result and logging it as LLM-generated
+///
+/// Statements the database rejects are logged as failed too, so every repair
+/// attempt shows in the history; `auto_repaired` marks the rewrites.
+async fn execute_generated_sql(
+    state: &AppState,
+    context: &NaturalLanguageContext,
+    generated_sql: &str,
+    headers: &HeaderMap,
+    principal: Option<&Principal>,
+    confirm_large_result: bool,
+    auto_repaired: bool,
+) -> Result<Query, AppError> {
+    let connection = &context.connection;
+    tracing::info!(
+        connection_id = %connection.id,
+        sql = %crate::logging::redactor().sql(generated_sql),
+        auto_repaired,
+        "Generated SQL from natural language"
+    );
 
     // Create database adapter with connection pool
     let db_type = DatabaseType::from_str(&connection.database_type)?;
     let adapter = create_adapter(
         db_type,
         &connection.connection_url,
+        connection.read_only,
         state.pool_manager.clone(),
     ).await?;
 
     // Create query object (marked as LLM-generated)
-    let query = Query::new(id.clone(), generated_sql.clone(), true);
+    let query = Query::new(connection.id.clone(), generated_sql.to_string(), true);
 
     // Execute query using QueryService
-    let query_service = QueryService::new();
-    let result = query_service.execute_query_with_adapter(query, adapter).await?;
-
-    // Log query history (if connection has domain_id)
-    if let Some(domain_id) = &connection.domain_id {
-        let history = match &result.status {
-            crate::models::QueryStatus::Completed => {
-                QueryHistory::new(
-                    domain_id.clone(),
-                    id.clone(),
-                    generated_sql.clone(),
-                    result.row_count.unwrap_or(0),
-                    result.execution_time_ms.unwrap_or(0),
-                    true, // LLM-generated
-                )
-            }
-            crate::models::QueryStatus::Failed => {
-                QueryHistory::new_failed(
-                    domain_id.clone(),
-                    id.clone(),
-                    generated_sql.clone(),
-                    result.error_message.clone().unwrap_or_else(|| "Unknown error".to_string()),
-                    true, // LLM-generated
-                )
-            }
-            _ => {
-                // Don't log pending/executing states
-                return Ok(Json(serde_json::json!({
-                    "query": result,
-                    "generated_sql": generated_sql,
-                })));
+    let audit = sql_audit_context(&state.config.audit, headers, principal);
+    let query_service = QueryService::new()
+        .with_audit(audit)
+        .with_large_result_gate(state.config.limits.large_result_threshold, confirm_large_result);
+    let settings = &context.settings;
+    let executed_by = principal.map(|p| p.subject());
+    let executed = state
+        .running_queries
+        .run(
+            running_query(connection, principal),
+            query_service.execute_query_with_limits(query, adapter, settings.default_row_limit, settings.default_timeout_secs),
+        )
+        .await;
+    let mut result = match executed {
+        Ok(result) => result,
+        Err(e) => {
+            if e.is_sql_error() {
+                let mut failed = Query::new(connection.id.clone(), generated_sql.to_string(), true);
+                failed.mark_failed(e.to_string());
+                if let Some(history) = history_entry(connection, generated_sql, &failed, executed_by, true) {
+                    record_query_history(state, connection, history.with_auto_repaired(auto_repaired), &failed, None).await;
+                }
             }
-        };
-
-        // Log to history (ignore errors to not block query response)
-        if let Err(e) = state.storage.add_query_history(&history).await {
-            tracing::warn!("Failed to log query history: {}", e);
+            return Err(e);
         }
+    };
+    if let Some(rows) = result.results.as_mut() {
+        context.policy.mask_rows(rows);
+    }
+
+    // Log query history (if connection has domain_id)
+    if let Some(history) = history_entry(connection, generated_sql, &result, executed_by, true) {
+        record_query_history(state, connection, history.with_auto_repaired(auto_repaired), &result, None).await;
     }
 
-    Ok(Json(serde_json::json!({
-        "query": result,
-        "generated_sql": generated_sql,
-    })))
+    Ok(result)
 }
 
 /// Execute unified SQL query using DataFusion semantic layer
@@ -206,11 +1357,24 @@ pub async fn execute_natural_language_query(
 ///
 /// # Response
 /// Returns UnifiedQueryResponse with original query, translated query, and results
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/unified-query",
+    tag = "queries",
+    params(("id" = String, Path), ResultFormatParams),
+    request_body = UnifiedQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = UnifiedQueryResponse),
+    ),
+)]
 pub async fn execute_unified_query(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<UnifiedQueryRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<UnifiedQueryResponse>, AppError> {
     tracing::info!(
         "Executing unified SQL query for connection {} with database type {:?}",
         id,
@@ -222,6 +1386,7 @@ pub async fn execute_unified_query(
     if sanitized_query.is_empty() {
         return Err(AppError::Validation("SQL query cannot be empty".to_string()));
     }
+    ensure_max_length("SQL query", sanitized_query, state.config.limits.max_sql_length)?;
 
     // Get connection from storage
     let connection = state
@@ -230,6 +1395,17 @@ pub async fn execute_unified_query(
         .await
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    // Label-bound policies: hourly quota now, column masking on the result
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
 
     // Verify database type matches
     let expected_db_type = DatabaseType::from_str(&connection.database_type)?;
@@ -243,29 +1419,189 @@ pub async fn execute_unified_query(
         )));
     }
 
-    // Create database adapter with connection pool
-    let adapter = create_adapter(
-        expected_db_type,
-        &connection.connection_url,
-        state.pool_manager.clone(),
-    ).await?;
+    // Create database adapter with connection pool
+    let adapter = create_adapter(
+        expected_db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    ).await?;
+
+    // Create unified query request, within the domain's timeout and row limits
+    let unified_request = UnifiedQueryRequest {
+        query: sanitized_query.to_string(),
+        database_type: payload.database_type,
+        timeout_secs: Some(settings.timeout_secs(payload.timeout_secs)),
+        apply_limit: payload.apply_limit,
+        limit_value: Some(settings.row_limit(payload.limit_value.map(|limit| limit as u64)) as usize),
+    };
+
+    // Execute unified query using QueryService
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let query_service = QueryService::new().with_audit(audit);
+    let mut result = state
+        .running_queries
+        .run(
+            running_query(&connection, principal.as_deref()),
+            query_service.execute_unified_query(unified_request, adapter),
+        )
+        .await?;
+    policy.mask_rows(&mut result.results);
+    result.columnar = columnar_result(format.result_format, &mut result.results)?;
+
+    Ok(Json(result))
+}
+
+/// Record a finished query in the domain's history and capture its execution snapshot
+/// (and its rows, with `save_result`)
+///
+/// Connections outside a domain and unfinished queries are not recorded.
+/// Failures are logged and never affect the query response.
+pub(crate) async fn log_query_history(
+    state: &AppState,
+    connection: &DatabaseConnection,
+    query_text: &str,
+    result: &Query,
+    executed_by: Option<String>,
+    is_llm_generated: bool,
+    save_result: Option<HistoryResultFormat>,
+) {
+    let Some(history) = history_entry(connection, query_text, result, executed_by, is_llm_generated) else {
+        return;
+    };
+    record_query_history(state, connection, history, result, save_result).await;
+}
+
+/// History entry of a completed or failed query on a domain's connection
+fn history_entry(
+    connection: &DatabaseConnection,
+    query_text: &str,
+    result: &Query,
+    executed_by: Option<String>,
+    is_llm_generated: bool,
+) -> Option<QueryHistory> {
+    let domain_id = connection.domain_id.as_ref()?;
+    let history = match &result.status {
+        crate::models::QueryStatus::Completed => QueryHistory::new(
+            domain_id.clone(),
+            connection.id.clone(),
+            query_text.to_string(),
+            result.row_count.unwrap_or(0),
+            result.execution_time_ms.unwrap_or(0),
+            is_llm_generated,
+        ),
+        crate::models::QueryStatus::Failed => QueryHistory::new_failed(
+            domain_id.clone(),
+            connection.id.clone(),
+            query_text.to_string(),
+            result.error_message.clone().unwrap_or_else(|| "Unknown error".to_string()),
+            is_llm_generated,
+        ),
+        // Don't log pending/executing states
+        _ => return None,
+    }
+    .with_executed_by(executed_by);
+    Some(history)
+}
+
+/// Store a history entry with its execution snapshot and notify on failure
+///
+/// With `save_result`, the (masked) rows of a completed query are kept with the entry.
+async fn record_query_history(
+    state: &AppState,
+    connection: &DatabaseConnection,
+    history: QueryHistory,
+    result: &Query,
+    save_result: Option<HistoryResultFormat>,
+) {
+    // Log to history (ignore errors to not block query response)
+    if let Err(e) = state.storage.add_query_history(&history).await {
+        tracing::warn!("Failed to log query history: {}", e);
+    } else {
+        let replay_service = QueryReplayService::new(state.storage.clone(), state.pool_manager.clone());
+        if let Err(e) = replay_service.capture_snapshot(&history, connection, result).await {
+            tracing::warn!("Failed to capture execution snapshot: {}", e);
+        }
+        if let (Some(format), Some(rows)) = (save_result, result.results.as_deref()) {
+            let result_service = HistoryResultService::new(state.storage.clone(), state.config.history.clone());
+            if let Err(e) = result_service.capture(&history.id, format, rows).await {
+                tracing::warn!("Failed to save query result with history: {}", e);
+            }
+        }
+    }
+    notify_query_failed(state, &history).await;
+}
+
+/// Bind parameter values of a request: the saved query's defaults, overridden by the request's
+pub(crate) async fn request_parameters(
+    state: &AppState,
+    saved_query_id: Option<&str>,
+    request_parameters: &QueryParameters,
+) -> Result<QueryParameters, AppError> {
+    let mut parameters = QueryParameters::new();
+    if let Some(saved_query_id) = saved_query_id {
+        if let Some(saved) = state
+            .storage
+            .get_saved_query(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+        {
+            parameters = saved.parameters;
+        }
+    }
+    parameters.extend(request_parameters.iter().map(|(name, value)| (name.clone(), value.clone())));
+    BindParams::validate(&parameters)?;
+    Ok(parameters)
+}
+
+/// Registry entry of a synchronous query, keyed by the request id
+fn running_query(connection: &DatabaseConnection, principal: Option<&Principal>) -> RunningQuery {
+    RunningQuery::new(
+        current_request_id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        connection.id.clone(),
+        connection.domain_id.clone(),
+        principal.map(|p| p.subject()),
+    )
+}
 
-    // Create unified query request
-    let unified_request = UnifiedQueryRequest {
-        query: sanitized_query.to_string(),
-        database_type: payload.database_type,
-        timeout_secs: payload.timeout_secs,
-        apply_limit: payload.apply_limit,
-        limit_value: payload.limit_value,
-    };
+/// Run a result transformation over a completed query's rows
+pub(crate) async fn apply_transform(state: &AppState, steps: &[TransformStep], result: &mut Query) -> Result<(), AppError> {
+    if steps.is_empty() {
+        return Ok(());
+    }
+    if let Some(rows) = result.results.take() {
+        let rows = ResultTransformService::apply(steps, &state.sessions, rows).await?;
+        result.row_count = Some(rows.len());
+        result.results = Some(rows);
+    }
+    Ok(())
+}
 
-    // Execute unified query using QueryService
-    let query_service = QueryService::new();
-    let result = query_service
-        .execute_unified_query(unified_request, adapter)
-        .await?;
+/// Column-oriented form of result rows for `result_format=columnar`
+///
+/// Returns None for `rows`; otherwise the rows are moved out of `rows`.
+pub(crate) fn columnar_result(
+    format: ResultFormat,
+    rows: &mut Vec<serde_json::Value>,
+) -> Result<Option<ColumnarResult>, AppError> {
+    if format != ResultFormat::Columnar {
+        return Ok(None);
+    }
+    let columnar = ResultExportService::columnar(rows)?;
+    rows.clear();
+    Ok(Some(columnar))
+}
 
-    Ok(Json(serde_json::json!(result)))
+/// [`columnar_result`] for a query; its `results` become null when converted
+fn query_columnar_result(format: ResultFormat, query: &mut Query) -> Result<Option<ColumnarResult>, AppError> {
+    let Some(rows) = query.results.as_mut() else {
+        return Ok(None);
+    };
+    let columnar = columnar_result(format, rows)?;
+    if columnar.is_some() {
+        query.results = None;
+    }
+    Ok(columnar)
 }
 
 /// Helper function to convert model DatabaseType to service DatabaseType
@@ -283,8 +1619,9 @@ fn convert_model_db_type_to_service(db_type: ModelDatabaseType) -> Result<Databa
 /// Allows querying across multiple databases with JOINs and UNIONs
 pub async fn execute_cross_database_query(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<crate::models::CrossDatabaseQueryRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<CrossDatabaseQueryResponse>, AppError> {
     tracing::info!(
         "Executing cross-database query across {} connections",
         payload.connection_ids.len()
@@ -294,10 +1631,10 @@ pub async fn execute_cross_database_query(
     payload
         .validate()
         .map_err(|e| AppError::Validation(e))?;
+    ensure_max_length("SQL query", &payload.query, state.config.limits.max_sql_length)?;
 
     // Create planner (automatically uses aliases if provided)
-    use crate::services::datafusion::CrossDatabaseQueryPlanner;
-    let planner = CrossDatabaseQueryPlanner::from_request(&payload);
+    let planner = planner_for_request(&state, &payload).await?;
 
     // Generate execution plan
     let plan = planner.plan_query(&payload)?;
@@ -313,27 +1650,40 @@ pub async fn execute_cross_database_query(
             .await
             .map_err(|e| AppError::Database(e.to_string()))?
             .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", conn_id)))?;
+        require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+        let settings = DomainSettingsService::new(state.storage.clone())
+            .for_domain(connection.domain_id.as_deref())
+            .await?;
+        DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
 
         // Create adapter
         let db_type = DatabaseType::from_str(&connection.database_type)?;
         let adapter = create_adapter(
             db_type,
             &connection.connection_url,
+            connection.read_only,
             state.pool_manager.clone(),
         )
         .await?;
 
         adapters.insert(conn_id.clone(), adapter);
     }
+    for sub_query in &plan.sub_queries {
+        if !adapters.contains_key(&sub_query.connection_id) {
+            adapters.insert(sub_query.connection_id.clone(), view_adapter(&state, &sub_query.connection_id).await?);
+        }
+    }
 
     // Execute cross-database query
     use crate::services::datafusion::DataFusionFederatedExecutor;
-    let executor = DataFusionFederatedExecutor::new();
+    let executor = DataFusionFederatedExecutor::new()
+        .with_cache(state.config.cross_database.subquery_cache_enabled.then(|| state.subquery_cache.clone()))
+        .with_sessions(state.sessions.clone());
     let result = executor
         .execute_cross_database_query(plan, adapters)
         .await?;
 
-    Ok(Json(serde_json::json!(result)))
+    Ok(Json(result))
 }
 
 // ============================================================================
@@ -343,13 +1693,31 @@ pub async fn execute_cross_database_query(
 /// Create a saved query for a domain
 ///
 /// POST /api/domains/{domain_id}/queries/saved
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/saved",
+    tag = "saved-queries",
+    params(("domain_id" = String, Path)),
+    request_body = CreateSavedQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = SavedQuery),
+    ),
+)]
 pub async fn create_saved_query(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(domain_id): Path<String>,
     Json(payload): Json<CreateSavedQueryRequest>,
 ) -> Result<Json<SavedQuery>, AppError> {
     tracing::info!("Creating saved query '{}' for domain {}", payload.name, domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    save_new_query(&state, &domain_id, payload).await.map(Json)
+}
+
+/// Validate and store a new saved query of a domain
+async fn save_new_query(state: &AppState, domain_id: &str, payload: CreateSavedQueryRequest) -> Result<SavedQuery, AppError> {
     // Validate inputs
     if payload.name.trim().is_empty() {
         return Err(AppError::Validation("Query name cannot be empty".to_string()));
@@ -357,38 +1725,65 @@ pub async fn create_saved_query(
     if payload.query_text.trim().is_empty() {
         return Err(AppError::Validation("Query text cannot be empty".to_string()));
     }
+    ensure_max_length("Query text", &payload.query_text, state.config.limits.max_sql_length)?;
+    validate_transform(&payload.transform).map_err(AppError::Validation)?;
+    BindParams::validate(&payload.parameters)?;
 
     // Verify domain exists
     let domain = state
         .storage
-        .get_domain(&domain_id)
+        .get_domain(domain_id)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
 
-    // Verify connection exists and belongs to domain
-    let connection = state
-        .storage
-        .get_connection(&payload.connection_id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
-        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", payload.connection_id)))?;
+    // A single connection of the domain, or the connections of a cross-database query
+    let connection_ids = match (&payload.connection_id, payload.connection_ids.is_empty()) {
+        (Some(connection_id), true) => vec![connection_id.clone()],
+        (None, false) => payload.connection_ids.clone(),
+        _ => {
+            return Err(AppError::Validation(
+                "Provide either connection_id or connection_ids (for a cross-database query)".to_string(),
+            ))
+        }
+    };
+    for connection_id in &connection_ids {
+        // Verify connection exists and belongs to domain
+        let connection = state
+            .storage
+            .get_connection(connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", connection_id)))?;
 
-    if connection.domain_id.as_ref() != Some(&domain_id) {
-        return Err(AppError::Validation(format!(
-            "Connection {} does not belong to domain {}",
-            payload.connection_id, domain_id
-        )));
+        if connection.domain_id.as_deref() != Some(domain_id) {
+            return Err(AppError::Validation(format!(
+                "Connection {} does not belong to domain {}",
+                connection_id, domain_id
+            )));
+        }
+    }
+
+    let view_name = payload.view_name.as_deref().map(str::trim).filter(|name| !name.is_empty());
+    if let Some(view_name) = view_name {
+        validate_view(state, domain_id, None, view_name, payload.connection_id.is_some(), &payload.query_text).await?;
     }
+    ensure_saved_query_name_not_trashed(state, domain_id, &payload.name).await?;
 
     // Create saved query
-    let saved_query = SavedQuery::new(
+    let mut saved_query = SavedQuery::new(
         domain.id,
         payload.connection_id,
         payload.name,
         payload.query_text,
         payload.description,
-    );
+    )
+    .with_transform(payload.transform)
+    .with_parameters(payload.parameters)
+    .with_view_name(view_name.map(str::to_string));
+    if saved_query.connection_id.is_none() {
+        saved_query = saved_query.with_connections(connection_ids, payload.database_aliases);
+    }
 
     // Save to storage
     state
@@ -398,18 +1793,92 @@ pub async fn create_saved_query(
         .map_err(|e| AppError::Database(e.to_string()))?;
 
     tracing::info!("Saved query created with ID: {}", saved_query.id);
-    Ok(Json(saved_query))
+    Ok(saved_query)
+}
+
+/// Names of trashed saved queries stay taken in their domain until they are
+/// restored or purged
+async fn ensure_saved_query_name_not_trashed(state: &AppState, domain_id: &str, name: &str) -> Result<(), AppError> {
+    let trashed = state
+        .storage
+        .trashed_saved_query_named(domain_id, name)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    match trashed {
+        Some(item) => Err(TrashService::name_held_by(&item)),
+        None => Ok(()),
+    }
+}
+
+/// Check that a saved query can be exposed as the view `domain.view_name`
+///
+/// Views are single SELECT queries on one connection without `:name`
+/// placeholders, and their names are unique within the domain.
+async fn validate_view(
+    state: &AppState,
+    domain_id: &str,
+    query_id: Option<&str>,
+    view_name: &str,
+    single_connection: bool,
+    query_text: &str,
+) -> Result<(), AppError> {
+    if !VirtualView::is_valid_name(view_name) {
+        return Err(AppError::Validation(format!(
+            "Invalid view name '{}': use letters, digits and underscores, not starting with a digit",
+            view_name
+        )));
+    }
+    if !single_connection {
+        return Err(AppError::Validation("Only single-connection saved queries can be views".to_string()));
+    }
+    SqlValidator::validate_select_only(query_text)?;
+    if BindParams::bind(query_text, &QueryParameters::new(), PlaceholderStyle::Numbered).is_err() {
+        return Err(AppError::Validation("A view cannot use :name placeholders".to_string()));
+    }
+
+    let views = state
+        .storage
+        .list_saved_views(domain_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if views
+        .iter()
+        .any(|view| view.view_name.as_deref() == Some(view_name) && Some(view.id.as_str()) != query_id)
+    {
+        return Err(AppError::Conflict(format!("Domain {} already has a view named {}", domain_id, view_name)));
+    }
+    Ok(())
 }
 
-/// List all saved queries for a domain
+/// List saved queries for a domain
 ///
-/// GET /api/domains/{domain_id}/queries/saved
+/// GET /api/domains/{domain_id}/queries/saved?page=1&page_size=50&sort=name&filter=revenue
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/saved",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ListParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = SavedQueryListResponse),
+    ),
+)]
 pub async fn list_saved_queries(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(domain_id): Path<String>,
-) -> Result<Json<Vec<SavedQuery>>, AppError> {
+    axum::extract::Query(params): axum::extract::Query<ListParams>,
+) -> Result<Json<SavedQueryListResponse>, AppError> {
     tracing::info!("Listing saved queries for domain {}", domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    let list_query = params
+        .resolve(SqliteStorage::SAVED_QUERY_SORT_FIELDS, "-created_at")
+        .map_err(AppError::Validation)?;
+
     // Verify domain exists
     state
         .storage
@@ -419,25 +1888,43 @@ pub async fn list_saved_queries(
         .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
 
     // Get saved queries
-    let queries = state
+    let (queries, total) = state
         .storage
-        .list_saved_queries(&domain_id)
+        .list_saved_queries_page(&domain_id, &list_query)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
-    tracing::info!("Found {} saved queries for domain {}", queries.len(), domain_id);
-    Ok(Json(queries))
+    tracing::info!("Found {} saved queries for domain {}", total, domain_id);
+    Ok(Json(SavedQueryListResponse {
+        saved_queries: queries,
+        page: list_query.page_info(total),
+    }))
 }
 
 /// Get a specific saved query
 ///
 /// GET /api/domains/{domain_id}/queries/saved/{query_id}
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = SavedQuery),
+    ),
+)]
 pub async fn get_saved_query(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path((domain_id, query_id)): Path<(String, String)>,
 ) -> Result<Json<SavedQuery>, AppError> {
     tracing::info!("Getting saved query {} for domain {}", query_id, domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
     // Get saved query
     let query = state
         .storage
@@ -454,16 +1941,108 @@ pub async fn get_saved_query(
     Ok(Json(query))
 }
 
+/// Run a saved query on its connection
+///
+/// POST /api/domains/{domain_id}/queries/saved/{query_id}/execute
+///
+/// Runs like `POST /api/connections/{id}/query` with the stored SQL: the
+/// request's parameter values override the saved defaults, and the history
+/// entry records the saved query's ID.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/execute",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+        ResultFormatParams,
+    ),
+    request_body = ExecuteSavedQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = QueryResponse),
+    ),
+)]
+pub async fn execute_saved_query(
+    State(state): State<AppState>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ExecuteSavedQueryRequest>,
+) -> Result<Json<QueryResponse>, AppError> {
+    tracing::info!("Executing saved query {} in domain {}", query_id, domain_id);
+
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let saved = state
+        .storage
+        .get_saved_query(&query_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found", query_id)))?;
+    if saved.domain_id != domain_id {
+        return Err(AppError::NotFound(format!("Saved query {} not found in domain {}", query_id, domain_id)));
+    }
+    let Some(connection_id) = saved.connection_id.clone() else {
+        return Err(AppError::Validation(format!(
+            "Saved query {} is a cross-database query; run it through POST /api/cross-database/query",
+            query_id
+        )));
+    };
+
+    let request = payload.into_query_request(&saved);
+    run_query(
+        &state,
+        &connection_id,
+        request,
+        format.result_format,
+        &headers,
+        QueryCaller::Principal(principal.as_deref()),
+        Some(&saved.id),
+    )
+    .await
+    .map(Json)
+}
+
 /// Update a saved query
 ///
 /// PUT /api/domains/{domain_id}/queries/saved/{query_id}
+#[utoipa::path(
+    put,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+        ("If-Match" = Option<String>, Header, description = "Current version (alternative to `expected_version`)"),
+    ),
+    request_body = UpdateSavedQueryRequest,
+    responses(
+        (status = 200, description = "OK", body = SavedQuery),
+    ),
+)]
 pub async fn update_saved_query(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path((domain_id, query_id)): Path<(String, String)>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateSavedQueryRequest>,
 ) -> Result<Json<SavedQuery>, AppError> {
     tracing::info!("Updating saved query {} for domain {}", query_id, domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let expected_version = expected_version(&headers, payload.expected_version)?;
+    if let Some(query_text) = &payload.query_text {
+        ensure_max_length("Query text", query_text, state.config.limits.max_sql_length)?;
+    }
+    if let Some(transform) = &payload.transform {
+        validate_transform(transform).map_err(AppError::Validation)?;
+    }
+    if let Some(parameters) = &payload.parameters {
+        BindParams::validate(parameters)?;
+    }
+
     // Get existing query
     let query = state
         .storage
@@ -476,19 +2055,40 @@ pub async fn update_saved_query(
     if query.domain_id != domain_id {
         return Err(AppError::NotFound(format!("Saved query {} not found in domain {}", query_id, domain_id)));
     }
+    if let Some(name) = payload.name.as_deref().filter(|name| *name != query.name) {
+        ensure_saved_query_name_not_trashed(&state, &domain_id, name).await?;
+    }
 
-    // Update query
-    state
+    // An empty view name stops exposing the query as a view
+    let view_name = payload.view_name.as_deref().map(str::trim).map(|name| (!name.is_empty()).then_some(name));
+    if let Some(view_name) = view_name.unwrap_or(query.view_name.as_deref()) {
+        let query_text = payload.query_text.as_deref().unwrap_or(&query.query_text);
+        validate_view(&state, &domain_id, Some(&query_id), view_name, !query.is_cross_database(), query_text).await?;
+    }
+
+    // Update query (only applies if nobody updated it in the meantime)
+    let updated = state
         .storage
         .update_saved_query(
             &query_id,
             payload.name.map(|s| s.to_string()),
             payload.query_text.map(|s| s.to_string()),
             payload.description.map(|s| s.to_string()),
+            payload.transform.as_deref(),
+            payload.parameters.as_ref(),
+            view_name,
+            expected_version,
         )
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+    if !updated {
+        return Err(AppError::Conflict(format!(
+            "Saved query {} was modified by another request (expected version {}, current version {}). Reload and retry.",
+            query_id, expected_version, query.version
+        )));
+    }
+
     // Get updated query
     let updated_query = state
         .storage
@@ -504,12 +2104,27 @@ pub async fn update_saved_query(
 /// Delete a saved query
 ///
 /// DELETE /api/domains/{domain_id}/queries/saved/{query_id}
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = SavedQueryDeletedResponse),
+    ),
+)]
 pub async fn delete_saved_query(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path((domain_id, query_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<SavedQueryDeletedResponse>, AppError> {
     tracing::info!("Deleting saved query {} from domain {}", query_id, domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
     // Get existing query
     let query = state
         .storage
@@ -531,10 +2146,151 @@ pub async fn delete_saved_query(
         .map_err(|e| AppError::Database(e.to_string()))?;
 
     tracing::info!("Saved query {} deleted successfully", query_id);
-    Ok(Json(serde_json::json!({
-        "message": "Saved query deleted successfully",
-        "query_id": query_id
-    })))
+    Ok(Json(SavedQueryDeletedResponse {
+        message: "Saved query deleted successfully".to_string(),
+        query_id,
+    }))
+}
+
+/// Saved query of a domain
+async fn domain_saved_query(state: &AppState, domain_id: &str, query_id: &str) -> Result<SavedQuery, AppError> {
+    state
+        .storage
+        .get_saved_query(query_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .filter(|query| query.domain_id == domain_id)
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found in domain {}", query_id, domain_id)))
+}
+
+/// Get the materialization of a saved query view
+///
+/// GET /api/domains/{domain_id}/queries/saved/{query_id}/materialization
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/materialization",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ViewMaterialization),
+    ),
+)]
+pub async fn get_view_materialization(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<Json<ViewMaterialization>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    domain_saved_query(&state, &domain_id, &query_id).await?;
+
+    let materialization = state
+        .view_materializations
+        .get(&query_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} is not materialized", query_id)))?;
+    Ok(Json(materialization))
+}
+
+/// Materialize a saved query view
+///
+/// PUT /api/domains/{domain_id}/queries/saved/{query_id}/materialization
+///
+/// Cross-database queries then read the view from its latest Parquet snapshot.
+/// With `refresh_interval_secs` the snapshot is refreshed on that schedule
+/// (the first one right away), otherwise only through the refresh endpoint.
+/// Calling it again changes the interval.
+#[utoipa::path(
+    put,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/materialization",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    request_body = MaterializeViewRequest,
+    responses(
+        (status = 200, description = "OK", body = ViewMaterialization),
+    ),
+)]
+pub async fn materialize_view(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+    Json(payload): Json<MaterializeViewRequest>,
+) -> Result<Json<ViewMaterialization>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    let query = domain_saved_query(&state, &domain_id, &query_id).await?;
+
+    let materialization = state
+        .view_materializations
+        .materialize(&query, payload.refresh_interval_secs)
+        .await?;
+    Ok(Json(materialization))
+}
+
+/// Refresh the snapshot of a materialized view now
+///
+/// POST /api/domains/{domain_id}/queries/saved/{query_id}/materialization/refresh
+///
+/// Runs the view's query on its connection and replaces the snapshot once it
+/// is written; queries read the previous snapshot until then.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/materialization/refresh",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ViewMaterialization),
+    ),
+)]
+pub async fn refresh_view_materialization(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<Json<ViewMaterialization>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    domain_saved_query(&state, &domain_id, &query_id).await?;
+
+    let materialization = state.view_materializations.refresh(&query_id).await?;
+    Ok(Json(materialization))
+}
+
+/// Stop materializing a saved query view and delete its snapshot
+///
+/// DELETE /api/domains/{domain_id}/queries/saved/{query_id}/materialization
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/materialization",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ViewDematerializedResponse),
+    ),
+)]
+pub async fn dematerialize_view(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<Json<ViewDematerializedResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    domain_saved_query(&state, &domain_id, &query_id).await?;
+
+    if !state.view_materializations.dematerialize(&query_id).await? {
+        return Err(AppError::NotFound(format!("Saved query {} is not materialized", query_id)));
+    }
+    Ok(Json(ViewDematerializedResponse {
+        message: "View materialization deleted successfully".to_string(),
+        query_id,
+    }))
 }
 
 // ============================================================================
@@ -543,14 +2299,38 @@ pub async fn delete_saved_query(
 
 /// List query history for a domain
 ///
-/// GET /api/domains/{domain_id}/queries/history?limit=50
+/// GET /api/domains/{domain_id}/queries/history?page=1&page_size=50&sort=-executed_at&filter=orders&status=failed
+///
+/// `limit` is still accepted as an alias of `page_size`. With
+/// `aggregate=daily` the matching entries are summarized per day instead.
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/history",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ListParams,
+        HistoryFilterParams,
+    ),
+    responses(
+        (status = 200, description = "Entries, or per-day statistics with `aggregate=daily`", body = QueryHistoryListResponse),
+    ),
+)]
 pub async fn list_query_history(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path(domain_id): Path<String>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<QueryHistory>>, AppError> {
+    axum::extract::Query(params): axum::extract::Query<ListParams>,
+    axum::extract::Query(filter): axum::extract::Query<HistoryFilterParams>,
+) -> Result<Response, AppError> {
     tracing::info!("Listing query history for domain {}", domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    let list_query = params
+        .resolve(SqliteStorage::HISTORY_SORT_FIELDS, "-executed_at")
+        .map_err(AppError::Validation)?;
+
     // Verify domain exists
     state
         .storage
@@ -559,33 +2339,43 @@ pub async fn list_query_history(
         .map_err(|e| AppError::Database(e.to_string()))?
         .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
 
-    // Parse limit parameter
-    let limit = params
-        .get("limit")
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(50);
-
-    // Get query history
-    let history = state
-        .storage
-        .list_query_history(&domain_id, limit)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
-
-    tracing::info!("Found {} query history entries for domain {}", history.len(), domain_id);
-    Ok(Json(history))
+    history_listing(&state, &domain_id, None, &list_query, &filter).await
 }
 
 /// List query history for a specific connection
 ///
-/// GET /api/domains/{domain_id}/connections/{connection_id}/history?limit=50
+/// GET /api/domains/{domain_id}/connections/{connection_id}/history?page=1&page_size=50
+///
+/// Accepts the filters and `aggregate=daily` of the domain history listing.
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/connections/{connection_id}/history",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ("connection_id" = String, Path),
+        ListParams,
+        HistoryFilterParams,
+    ),
+    responses(
+        (status = 200, description = "Entries, or per-day statistics with `aggregate=daily`", body = QueryHistoryListResponse),
+    ),
+)]
 pub async fn list_connection_query_history(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Path((domain_id, connection_id)): Path<(String, String)>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<QueryHistory>>, AppError> {
+    axum::extract::Query(params): axum::extract::Query<ListParams>,
+    axum::extract::Query(filter): axum::extract::Query<HistoryFilterParams>,
+) -> Result<Response, AppError> {
     tracing::info!("Listing query history for connection {} in domain {}", connection_id, domain_id);
 
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    let list_query = params
+        .resolve(SqliteStorage::HISTORY_SORT_FIELDS, "-executed_at")
+        .map_err(AppError::Validation)?;
+
     // Verify domain exists
     state
         .storage
@@ -609,19 +2399,391 @@ pub async fn list_connection_query_history(
         )));
     }
 
-    // Parse limit parameter
-    let limit = params
-        .get("limit")
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(50);
+    history_listing(&state, &domain_id, Some(&connection_id), &list_query, &filter).await
+}
+
+/// One page of the matching history entries, or their per-day statistics
+/// (`aggregate=daily`), computed in SQLite
+async fn history_listing(
+    state: &AppState,
+    domain_id: &str,
+    connection_id: Option<&str>,
+    list_query: &ListQuery,
+    filter: &HistoryFilterParams,
+) -> Result<Response, AppError> {
+    filter.validate().map_err(AppError::Validation)?;
+
+    if filter.aggregate == Some(HistoryAggregation::Daily) {
+        let days = state
+            .storage
+            .history_daily_stats(domain_id, connection_id, list_query, filter)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        return Ok(Json(QueryHistoryDailyResponse { days }).into_response());
+    }
+
+    let (history, total) = state
+        .storage
+        .list_query_history_page(domain_id, connection_id, list_query, filter)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    tracing::info!("Found {} query history entries for domain {}", total, domain_id);
+    Ok(Json(QueryHistoryListResponse {
+        history,
+        page: list_query.page_info(total),
+    })
+    .into_response())
+}
+
+/// Replay a historic query against the current database and explain differences
+///
+/// POST /api/domains/{domain_id}/queries/history/{history_id}/replay
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/history/{history_id}/replay",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ("history_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = crate::models::ReplayReport),
+    ),
+)]
+pub async fn replay_query_history(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, history_id)): Path<(String, String)>,
+) -> Result<Json<crate::models::ReplayReport>, AppError> {
+    tracing::info!("Replaying query history {} in domain {}", history_id, domain_id);
+
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let replay_service = QueryReplayService::new(state.storage.clone(), state.pool_manager.clone());
+    let report = replay_service.replay(&domain_id, &history_id).await?;
+
+    Ok(Json(report))
+}
+
+/// Load a history entry of a domain
+async fn history_in_domain(state: &AppState, domain_id: &str, history_id: &str) -> Result<QueryHistory, AppError> {
+    state
+        .storage
+        .get_query_history(history_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .filter(|history| history.domain_id == domain_id)
+        .ok_or_else(|| AppError::NotFound(format!("Query history {} not found in domain {}", history_id, domain_id)))
+}
+
+/// Saved query a history entry references, if it still exists in the domain
+async fn linked_saved_query(state: &AppState, history: &QueryHistory) -> Result<Option<SavedQuery>, AppError> {
+    let Some(saved_query_id) = history.saved_query_id.as_deref() else {
+        return Ok(None);
+    };
+    Ok(state
+        .storage
+        .get_saved_query(saved_query_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .filter(|saved| saved.domain_id == history.domain_id))
+}
+
+/// Run the SQL of a history entry again on its connection
+///
+/// POST /api/domains/{domain_id}/queries/history/{history_id}/rerun
+///
+/// Entries of a saved query run with its default parameters and
+/// transformation while it exists, and the new entry references it too.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/history/{history_id}/rerun",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ("history_id" = String, Path),
+        ResultFormatParams,
+    ),
+    request_body = RerunHistoryRequest,
+    responses(
+        (status = 200, description = "OK", body = QueryResponse),
+    ),
+)]
+pub async fn rerun_query_history(
+    State(state): State<AppState>,
+    Path((domain_id, history_id)): Path<(String, String)>,
+    axum::extract::Query(format): axum::extract::Query<ResultFormatParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<RerunHistoryRequest>,
+) -> Result<Json<QueryResponse>, AppError> {
+    tracing::info!("Rerunning query history {} in domain {}", history_id, domain_id);
+
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let history = history_in_domain(&state, &domain_id, &history_id).await?;
+    if history.kind == QueryKind::Write {
+        return Err(AppError::Validation(format!(
+            "Query history {} is a write statement; run it through POST /api/connections/{{id}}/execute",
+            history_id
+        )));
+    }
+    let Some(connection_id) = history.connection_id.clone() else {
+        return Err(AppError::Validation(format!(
+            "Query history {} is a cross-database query; run it through POST /api/cross-database/query",
+            history_id
+        )));
+    };
+
+    let saved_query_id = linked_saved_query(&state, &history).await?.map(|saved| saved.id);
+    let request = payload.into_query_request(&history, saved_query_id.clone());
+    run_query(
+        &state,
+        &connection_id,
+        request,
+        format.result_format,
+        &headers,
+        QueryCaller::Principal(principal.as_deref()),
+        saved_query_id.as_deref(),
+    )
+    .await
+    .map(Json)
+}
+
+/// Get the result kept with a history entry
+///
+/// GET /api/domains/{domain_id}/queries/history/{history_id}/result?format=json|parquet
+///
+/// Only queries run with `save_result` have one. `format=parquet` downloads
+/// the file of results kept as Parquet. Reading results takes the editor role,
+/// like running the query again would.
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/history/{history_id}/result",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ("history_id" = String, Path),
+        ("format" = Option<String>, Query, description = "`json` (default) or `parquet`"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = HistoryResult),
+        (status = 404, description = "No result was saved with the entry"),
+    ),
+)]
+pub async fn get_query_history_result(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, history_id)): Path<(String, String)>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let history = history_in_domain(&state, &domain_id, &history_id).await?;
+    let (result, parquet) = HistoryResultService::new(state.storage.clone(), state.config.history.clone())
+        .get(&history.id)
+        .await?;
+
+    let format = params.get("format").map(|f| f.to_lowercase()).unwrap_or_else(|| "json".to_string());
+    match format.as_str() {
+        "json" => Ok(Json(result).into_response()),
+        "parquet" => {
+            let Some(parquet) = parquet else {
+                return Err(AppError::Validation(format!(
+                    "The result of query history {} was saved as rows; read it with format=json",
+                    history_id
+                )));
+            };
+            let disposition = format!("attachment; filename=\"history-{}.parquet\"", history_id);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, ExportFormat::Parquet.content_type().to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                parquet,
+            )
+                .into_response())
+        }
+        other => Err(AppError::Validation(format!(
+            "Unsupported result format '{}' (expected json or parquet)",
+            other
+        ))),
+    }
+}
+
+/// Save the SQL of a history entry as a saved query
+///
+/// POST /api/domains/{domain_id}/queries/history/{history_id}/promote
+///
+/// The saved query keeps the entry's connection (or the connections and
+/// aliases of a cross-database query), and the entry then references it.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/history/{history_id}/promote",
+    tag = "history",
+    params(
+        ("domain_id" = String, Path),
+        ("history_id" = String, Path),
+    ),
+    request_body = PromoteHistoryRequest,
+    responses(
+        (status = 200, description = "OK", body = SavedQuery),
+        (status = 409, description = "The entry already references a saved query"),
+    ),
+)]
+pub async fn promote_query_history(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, history_id)): Path<(String, String)>,
+    Json(payload): Json<PromoteHistoryRequest>,
+) -> Result<Json<SavedQuery>, AppError> {
+    tracing::info!("Promoting query history {} in domain {}", history_id, domain_id);
+
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+
+    let history = history_in_domain(&state, &domain_id, &history_id).await?;
+    if history.kind == QueryKind::Write {
+        return Err(AppError::Validation(format!(
+            "Query history {} is a write statement and cannot be saved",
+            history_id
+        )));
+    }
+    if history.connection_id.is_none() && history.connection_ids.is_empty() {
+        return Err(AppError::Validation(format!(
+            "The connection of query history {} no longer exists",
+            history_id
+        )));
+    }
+    if let Some(saved) = linked_saved_query(&state, &history).await? {
+        return Err(AppError::Conflict(format!(
+            "Query history {} already references saved query {} ('{}')",
+            history_id, saved.id, saved.name
+        )));
+    }
 
-    // Get query history
-    let history = state
+    let saved_query = save_new_query(&state, &domain_id, payload.into_create_request(&history)).await?;
+    state
         .storage
-        .list_query_history_by_connection(&connection_id, limit)
+        .set_history_saved_query(&history.id, &saved_query.id)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
 
-    tracing::info!("Found {} query history entries for connection {}", history.len(), connection_id);
-    Ok(Json(history))
+    Ok(Json(saved_query))
+}
+
+/// Query archived (pruned) history for a domain through DataFusion
+///
+/// POST /api/domains/{domain_id}/queries/history/archive
+///
+/// The query runs against the `query_history_archive` table, which is scoped
+/// to the domain and exposes a `month` partition column (YYYY-MM).
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/history/archive",
+    tag = "history",
+    params(("domain_id" = String, Path)),
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "OK", body = ArchiveQueryResponse),
+    ),
+)]
+pub async fn query_history_archive(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(domain_id): Path<String>,
+    Json(payload): Json<QueryRequest>,
+) -> Result<Json<ArchiveQueryResponse>, AppError> {
+    tracing::info!("Querying archived history for domain {}", domain_id);
+
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+
+    // Verify domain exists
+    state
+        .storage
+        .get_domain(&domain_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+
+    let sql = payload.query.trim();
+    if sql.is_empty() {
+        return Err(AppError::Validation("SQL query cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL query", sql, state.config.limits.max_sql_length)?;
+
+    let archive_service = HistoryArchiveService::new(state.storage.clone(), state.config.history.clone());
+    let result = archive_service.query_archive(&domain_id, sql).await?;
+
+    Ok(Json(ArchiveQueryResponse {
+        rows: result.rows,
+        row_count: result.row_count,
+        execution_time_ms: result.execution_time_ms,
+    }))
+}
+
+/// Prune query history past the retention window, archiving if enabled
+///
+/// POST /api/queries/history/prune
+#[utoipa::path(
+    post,
+    path = "/api/queries/history/prune",
+    tag = "history",
+    responses(
+        (status = 200, description = "OK", body = PruneSummary),
+    ),
+)]
+pub async fn prune_query_history(
+    State(state): State<AppState>,
+) -> Result<Json<PruneSummary>, AppError> {
+    if state.config.history.retention_days == 0 {
+        return Err(AppError::Validation(
+            "History retention is disabled (HISTORY_RETENTION_DAYS=0)".to_string(),
+        ));
+    }
+
+    let archive_service = HistoryArchiveService::new(state.storage.clone(), state.config.history.clone());
+    let summary = archive_service.prune().await?;
+
+    Ok(Json(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_summarize_rejected_over_llm_budget() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let connection = DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            Some("default-domain-id".to_string()),
+        );
+        storage.save_connection(&connection).await.unwrap();
+        let tokens = crate::models::LlmTokenUsage { prompt_tokens: 90, completion_tokens: 10 };
+        storage.record_llm_request("default-domain-id", Some("alice"), tokens, false).await.unwrap();
+
+        let mut config = crate::config::Config::from_env().unwrap();
+        config.llm.monthly_token_budget = 100;
+        config.llm.budget_exceeded_action = "reject".to_string();
+        let app = crate::api::routes::create_router_with_state(storage, config);
+
+        let request = Request::post(format!("/api/connections/{}/query/summarize", connection.id))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"query": "SELECT 1 AS one", "results": [{"one": 1}]}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "QUOTA_EXCEEDED");
+    }
 }
@@ -0,0 +1,204 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures::stream::{self, Stream};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query::{apply_transform, log_query_history, request_parameters};
+use crate::api::middleware::{ensure_max_length, require_domain_role, sql_audit_context, AppError};
+use crate::api::responses::QueryJobResponse;
+use crate::models::{DomainRole, Principal, Query, QueryJob, QueryJobEvent, QueryRequest};
+use crate::services::database::{create_adapter, DatabaseType};
+use crate::services::{ConnectionPolicyService, DomainSettingsService, QueryJobService, QueryService, ResultTransformService};
+
+/// Submit a SQL query to run in the background
+///
+/// Returns the pending job right away; poll `GET /api/query-jobs/{id}` for
+/// its status and results. The query runs with the domain's row limit and
+/// the job timeout (`QUERY_JOB_TIMEOUT_SECS`) instead of the request timeout.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/queries/async",
+    tag = "query-jobs",
+    params(("id" = String, Path)),
+    request_body = QueryRequest,
+    responses(
+        (status = 202, description = "Accepted", body = QueryJobResponse),
+    ),
+)]
+pub async fn submit_query_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<QueryRequest>,
+) -> Result<(StatusCode, Json<QueryJobResponse>), AppError> {
+    tracing::info!("Submitting query job for connection: {}", id);
+
+    let sanitized_query = payload.query.trim().to_string();
+    if sanitized_query.is_empty() {
+        return Err(AppError::Validation("SQL query cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL query", &sanitized_query, state.config.limits.max_sql_length)?;
+
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    let transform = ResultTransformService::new(state.storage.clone())
+        .steps_for_request(&connection.id, payload.saved_query_id.as_deref(), &payload.transform)
+        .await?;
+    let parameters = request_parameters(&state, payload.saved_query_id.as_deref(), &payload.parameters).await?;
+
+    // The audit context is captured now, while the request id is still in scope
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let executed_by = principal.as_deref().map(|p| p.subject());
+    let job = QueryJob::new(
+        connection.id.clone(),
+        connection.domain_id.clone(),
+        sanitized_query.clone(),
+        executed_by.clone(),
+    );
+
+    let work_state = state.clone();
+    let work = async move {
+        let db_type = DatabaseType::from_str(&connection.database_type)?;
+        let adapter = create_adapter(
+            db_type,
+            &connection.connection_url,
+            connection.read_only,
+            work_state.pool_manager.clone(),
+        ).await?;
+
+        let query_service = QueryService::new()
+            .with_audit(audit)
+            .with_large_result_gate(work_state.config.limits.large_result_threshold, payload.confirm_large_result)
+            .with_parameters(parameters)
+            .with_result_cache(work_state.config.query_cache.enabled.then(|| work_state.query_cache.clone()));
+        let query = Query::new(connection.id.clone(), sanitized_query.clone(), false);
+        // Jobs default to the longer job timeout; an explicit override is capped like a synchronous query's
+        let timeout_secs = match payload.timeout_secs {
+            Some(requested) => settings.timeout_secs(Some(requested)),
+            None => work_state.config.jobs.timeout_secs,
+        };
+        let mut result = query_service
+            .execute_query_with_limits(query, adapter, settings.row_limit(payload.limit_value), timeout_secs)
+            .await?;
+        if let Some(rows) = result.results.as_mut() {
+            policy.mask_rows(rows);
+        }
+
+        log_query_history(&work_state, &connection, &sanitized_query, &result, executed_by, false, payload.save_result).await;
+        apply_transform(&work_state, &transform, &mut result).await?;
+        Ok(result)
+    };
+
+    let job = QueryJobService::new(state.storage.clone(), state.query_jobs.clone())
+        .submit(job, work)
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(QueryJobResponse { job })))
+}
+
+/// Get the status of a query job, with its results once completed
+#[utoipa::path(
+    get,
+    path = "/api/query-jobs/{id}",
+    tag = "query-jobs",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = QueryJobResponse),
+    ),
+)]
+pub async fn get_query_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<QueryJobResponse>, AppError> {
+    let job = QueryJobService::new(state.storage.clone(), state.query_jobs.clone())
+        .get(&id)
+        .await?;
+    require_domain_role(&state, principal.as_deref(), job.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    Ok(Json(QueryJobResponse { job }))
+}
+
+/// Cancel a pending or running query job
+///
+/// Aborts the query; PostgreSQL and MySQL also cancel the statement on the
+/// database server. Finished jobs return 409.
+#[utoipa::path(
+    delete,
+    path = "/api/query-jobs/{id}",
+    tag = "query-jobs",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Cancelled", body = QueryJobResponse),
+    ),
+)]
+pub async fn cancel_query_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<QueryJobResponse>, AppError> {
+    let service = QueryJobService::new(state.storage.clone(), state.query_jobs.clone());
+    let job = service.get(&id).await?;
+    require_domain_role(&state, principal.as_deref(), job.domain_id.as_deref(), DomainRole::Editor).await?;
+
+    let job = service.cancel(&id).await?;
+    Ok(Json(QueryJobResponse { job }))
+}
+
+/// Follow a query job as Server-Sent Events
+///
+/// Emits `execution-started` when the query starts, then `row-batch` events
+/// (`offset` and up to 500 `rows` each) and a final `completed`, `failed` or
+/// `cancelled` event, after which the stream ends. Finished jobs are replayed.
+#[utoipa::path(
+    get,
+    path = "/api/query-jobs/{id}/stream",
+    tag = "query-jobs",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Event stream", content_type = "text/event-stream", body = QueryJobEvent),
+    ),
+)]
+pub async fn stream_query_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let service = QueryJobService::new(state.storage.clone(), state.query_jobs.clone());
+    let job = service.get(&id).await?;
+    require_domain_role(&state, principal.as_deref(), job.domain_id.as_deref(), DomainRole::Viewer).await?;
+
+    let events = stream::unfold(service.events(job), |mut events| async move {
+        let event = events.recv().await?;
+        let sse = Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+        Some((Ok(sse), events))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
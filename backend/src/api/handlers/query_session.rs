@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::HeaderMap,
+    response::Response,
+    Extension,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use utoipa::IntoParams;
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query::{apply_transform, log_query_history};
+use crate::api::middleware::{ensure_max_length, require_domain_role, sql_audit_context, AppError};
+use crate::models::{
+    DatabaseConnection, DomainRole, DomainSettings, EffectivePolicy, Principal, Query, QueryParameters,
+    QuerySessionFrame, QuerySessionRequest, TransformStep,
+};
+use crate::services::database::{create_adapter, DatabaseType};
+use crate::services::{ConnectionPolicyService, DomainSettingsService, QueryService, ResultTransformService, ROW_BATCH_SIZE};
+use crate::validation::SqlAuditContext;
+
+/// Path of the interactive query session endpoint
+pub const QUERY_SESSION_PATH: &str = "/ws";
+
+/// Most statements a session may run at the same time
+const MAX_RUNNING_STATEMENTS: usize = 4;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct QuerySessionParams {
+    /// Connection the session is bound to
+    pub connection_id: String,
+    /// Bearer token, for clients that cannot set the `Authorization` header (browsers)
+    pub access_token: Option<String>,
+}
+
+/// Everything a session needs to run statements, resolved once when it opens
+struct Session {
+    state: AppState,
+    connection: DatabaseConnection,
+    settings: DomainSettings,
+    policy: EffectivePolicy,
+    audit: Option<SqlAuditContext>,
+    executed_by: Option<String>,
+}
+
+/// Open an interactive query session (WebSocket)
+///
+/// The session is bound to one connection and needs the Editor role on its
+/// domain. Clients send JSON `query` (`id`, `query`, optional
+/// `confirm_large_result`, `transform` and `parameters`) and `cancel` (`id`)
+/// messages; the server answers with `started`, `row-batch` (up to 500 rows), `completed`,
+/// `failed`, `cancelled` and `error` frames tagged with the statement `id`.
+/// Up to 4 statements run at once. Cancelling, or closing the socket, aborts
+/// running statements; PostgreSQL and MySQL also cancel them on the server.
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "queries",
+    params(QuerySessionParams),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol", body = QuerySessionFrame),
+    ),
+)]
+pub async fn open_query_session(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<QuerySessionParams>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let connection = state
+        .storage
+        .get_connection(&params.connection_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", params.connection_id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+    let policy = ConnectionPolicyService::new(state.storage.clone())
+        .for_connection(&connection)
+        .await?;
+
+    tracing::info!("Opening query session for connection: {}", connection.id);
+    let session = Arc::new(Session {
+        audit: sql_audit_context(&state.config.audit, &headers, principal.as_deref()),
+        executed_by: principal.as_deref().map(|p| p.subject()),
+        state,
+        connection,
+        settings,
+        policy,
+    });
+
+    Ok(upgrade.on_upgrade(move |socket| run_session(socket, session)))
+}
+
+async fn run_session(socket: WebSocket, session: Arc<Session>) {
+    let (mut sink, mut incoming) = socket.split();
+
+    // Statements send their frames through a channel; one task writes them to the socket
+    let (frames, mut outgoing) = mpsc::channel::<QuerySessionFrame>(64);
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = outgoing.recv().await {
+            let text = match serde_json::to_string(&frame) {
+                Ok(text) => text,
+                Err(e) => {
+                    tracing::warn!("Failed to serialize query session frame: {}", e);
+                    continue;
+                }
+            };
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let _ = frames
+        .send(QuerySessionFrame::Ready {
+            session_id: session_id.clone(),
+            connection_id: session.connection.id.clone(),
+        })
+        .await;
+
+    let mut running: HashMap<String, AbortHandle> = HashMap::new();
+    while let Some(Ok(message)) = incoming.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            // Pings are answered by axum; binary frames are not part of the protocol
+            _ => continue,
+        };
+        running.retain(|_, handle| !handle.is_finished());
+
+        let frame = match serde_json::from_str::<QuerySessionRequest>(text.as_str()) {
+            Ok(QuerySessionRequest::Query { id, query, confirm_large_result, transform, parameters }) => {
+                if running.contains_key(&id) {
+                    Some(QuerySessionFrame::Error {
+                        message: format!("Statement {} is already running", id),
+                        id: Some(id),
+                    })
+                } else if running.len() >= MAX_RUNNING_STATEMENTS {
+                    Some(QuerySessionFrame::Error {
+                        id: Some(id),
+                        message: format!("At most {} statements can run at once in a session", MAX_RUNNING_STATEMENTS),
+                    })
+                } else {
+                    let task = tokio::spawn(run_statement(
+                        session.clone(),
+                        frames.clone(),
+                        id.clone(),
+                        query,
+                        confirm_large_result,
+                        transform,
+                        parameters,
+                    ));
+                    running.insert(id, task.abort_handle());
+                    None
+                }
+            }
+            Ok(QuerySessionRequest::Cancel { id }) => match running.remove(&id) {
+                Some(handle) => {
+                    handle.abort();
+                    Some(QuerySessionFrame::Cancelled { id })
+                }
+                None => Some(QuerySessionFrame::Error {
+                    message: format!("Statement {} is not running", id),
+                    id: Some(id),
+                }),
+            },
+            Err(e) => Some(QuerySessionFrame::Error {
+                id: None,
+                message: format!("Invalid message: {}", e),
+            }),
+        };
+        if let Some(frame) = frame {
+            if frames.send(frame).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    // Dropping the statements' adapters cancels them on the database server
+    for handle in running.values() {
+        handle.abort();
+    }
+    drop(frames);
+    let _ = writer.await;
+    tracing::info!("Closed query session {} for connection: {}", session_id, session.connection.id);
+}
+
+/// Run one statement and send its frames
+async fn run_statement(
+    session: Arc<Session>,
+    frames: mpsc::Sender<QuerySessionFrame>,
+    id: String,
+    query: String,
+    confirm_large_result: bool,
+    transform: Vec<TransformStep>,
+    parameters: QueryParameters,
+) {
+    let _ = frames.send(QuerySessionFrame::Started { id: id.clone() }).await;
+
+    let outcome = match execute_statement(&session, &frames, &id, &query, confirm_large_result, &transform, parameters).await {
+        Ok(result) => QuerySessionFrame::outcome(&id, &result, ROW_BATCH_SIZE),
+        Err(e) => vec![QuerySessionFrame::Failed {
+            id: id.clone(),
+            error_message: Some(e.to_string()),
+        }],
+    };
+    for frame in outcome {
+        if frames.send(frame).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Run one statement, sending its rows as `row-batch` frames while they
+/// arrive unless a transformation needs the whole result first
+///
+/// The returned query holds the rows only if they were not streamed.
+async fn execute_statement(
+    session: &Session,
+    frames: &mpsc::Sender<QuerySessionFrame>,
+    id: &str,
+    query: &str,
+    confirm_large_result: bool,
+    transform: &[TransformStep],
+    parameters: QueryParameters,
+) -> Result<Query, AppError> {
+    let state = &session.state;
+    let connection = &session.connection;
+
+    let sanitized_query = query.trim();
+    if sanitized_query.is_empty() {
+        return Err(AppError::Validation("SQL query cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL query", sanitized_query, state.config.limits.max_sql_length)?;
+
+    ConnectionPolicyService::new(state.storage.clone())
+        .ensure_within_quota(connection, &session.policy)
+        .await?;
+    let transform = ResultTransformService::new(state.storage.clone())
+        .steps_for_request(&connection.id, None, transform)
+        .await?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    )
+    .await?;
+
+    // Every statement gets its own audit request id within the session's
+    let audit = session.audit.clone().map(|audit| SqlAuditContext {
+        request_id: format!("{}/{}", audit.request_id, id),
+        ..audit
+    });
+    let query_service = QueryService::new()
+        .with_audit(audit)
+        .with_large_result_gate(state.config.limits.large_result_threshold, confirm_large_result)
+        .with_parameters(parameters);
+    let query = Query::new(connection.id.clone(), sanitized_query.to_string(), false);
+
+    if transform.is_empty() {
+        let started = Instant::now();
+        let (mut result, mut batches) = query_service
+            .stream_query_with_limits(
+                query,
+                adapter,
+                session.settings.default_row_limit,
+                session.settings.default_timeout_secs,
+                ROW_BATCH_SIZE,
+            )
+            .await?;
+        let mut offset = 0;
+        while let Some(batch) = batches.next().await {
+            let mut rows = batch?;
+            session.policy.mask_rows(&mut rows);
+            let batch_rows = rows.len();
+            let frame = QuerySessionFrame::RowBatch { id: id.to_string(), offset, rows };
+            if frames.send(frame).await.is_err() {
+                return Err(AppError::Cancelled(format!("Session closed while statement {} was running", id)));
+            }
+            offset += batch_rows;
+        }
+        result.mark_streamed(offset, started.elapsed().as_millis() as u64);
+
+        log_query_history(state, connection, sanitized_query, &result, session.executed_by.clone(), false, None).await;
+        return Ok(result);
+    }
+
+    let mut result = query_service
+        .execute_query_with_limits(
+            query,
+            adapter,
+            session.settings.default_row_limit,
+            session.settings.default_timeout_secs,
+        )
+        .await?;
+    if let Some(rows) = result.results.as_mut() {
+        session.policy.mask_rows(rows);
+    }
+
+    log_query_history(state, connection, sanitized_query, &result, session.executed_by.clone(), false, None).await;
+    apply_transform(state, &transform, &mut result).await?;
+    Ok(result)
+}
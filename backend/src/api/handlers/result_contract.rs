@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::models::{DomainRole, Principal, ResultContract, SavedQuery, SetResultContractRequest};
+use crate::services::ResultContractService;
+
+/// Saved query of a domain
+async fn saved_query_in_domain(state: &AppState, domain_id: &str, query_id: &str) -> Result<SavedQuery, AppError> {
+    state
+        .storage
+        .get_saved_query(query_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .filter(|saved| saved.domain_id == domain_id)
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found in domain {}", query_id, domain_id)))
+}
+
+/// Get the result contract of a saved query, with the drift its latest check found
+///
+/// GET /api/domains/{domain_id}/queries/saved/{query_id}/contract
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/contract",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = ResultContract),
+        (status = 404, description = "The saved query declares no contract"),
+    ),
+)]
+pub async fn get_result_contract(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<Json<ResultContract>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+
+    let contract = ResultContractService::new(state.storage.clone())
+        .get(&saved.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} declares no result contract", saved.id)))?;
+    Ok(Json(contract))
+}
+
+/// Declare the columns and types a saved query's result must have
+///
+/// PUT /api/domains/{domain_id}/queries/saved/{query_id}/contract
+///
+/// Replaces the previous contract and clears the drift found against it.
+#[utoipa::path(
+    put,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/contract",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    request_body = SetResultContractRequest,
+    responses(
+        (status = 200, description = "OK", body = ResultContract),
+    ),
+)]
+pub async fn set_result_contract(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+    Json(payload): Json<SetResultContractRequest>,
+) -> Result<Json<ResultContract>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+
+    let contract = ResultContractService::new(state.storage.clone())
+        .set(&saved, payload)
+        .await?;
+    Ok(Json(contract))
+}
+
+/// Remove the result contract of a saved query
+///
+/// DELETE /api/domains/{domain_id}/queries/saved/{query_id}/contract
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/contract",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn delete_result_contract(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+
+    if !ResultContractService::new(state.storage.clone()).delete(&saved.id).await? {
+        return Err(AppError::NotFound(format!("Saved query {} declares no result contract", saved.id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
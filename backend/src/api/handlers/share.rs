@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query::{run_query, QueryCaller};
+use crate::api::middleware::{require_domain_role, AppError};
+use crate::api::responses::QueryShareListResponse;
+use crate::models::{
+    CreateQueryShareRequest, CreateQueryShareResponse, DomainRole, ExecuteSavedQueryRequest, Principal, QueryStatus,
+    ResultFormat, SavedQuery, SharedResult,
+};
+use crate::services::{ChartRecommendationService, QueryShareService};
+
+/// Saved query of a domain (soft-deleted queries are not found)
+async fn saved_query_in_domain(state: &AppState, domain_id: &str, query_id: &str) -> Result<SavedQuery, AppError> {
+    state
+        .storage
+        .get_saved_query(query_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .filter(|saved| saved.domain_id == domain_id)
+        .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found in domain {}", query_id, domain_id)))
+}
+
+/// Share a saved query's result through a link readable without authentication
+///
+/// POST /api/domains/{domain_id}/queries/saved/{query_id}/shares
+///
+/// With `snapshot: true` the query runs now and the link serves this result;
+/// otherwise every visit runs it with the parameter values given here, until
+/// the saved query's SQL or connection is changed. The token is only part of
+/// this response.
+#[utoipa::path(
+    post,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/shares",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    request_body = CreateQueryShareRequest,
+    responses(
+        (status = 201, description = "Created", body = CreateQueryShareResponse),
+    ),
+)]
+pub async fn create_query_share(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateQueryShareRequest>,
+) -> Result<(StatusCode, Json<CreateQueryShareResponse>), AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+    QueryShareService::validate(&saved, &payload)?;
+
+    let snapshot = if payload.snapshot {
+        let connection_id = saved.connection_id.clone().unwrap_or_default();
+        let request = ExecuteSavedQueryRequest {
+            parameters: payload.parameters.clone(),
+            // Visitors cannot confirm; the domain row limit still caps the result
+            confirm_large_result: true,
+            ..Default::default()
+        }
+        .into_query_request(&saved);
+        let response = run_query(
+            &state,
+            &connection_id,
+            request,
+            ResultFormat::Rows,
+            &headers,
+            QueryCaller::Principal(principal.as_deref()),
+            Some(&saved.id),
+        )
+        .await?;
+        Some(response.query)
+    } else {
+        None
+    };
+
+    let created_by = principal.as_deref().map(|p| p.subject());
+    let created = QueryShareService::new(state.storage.clone())
+        .create(&saved, payload, created_by, snapshot)
+        .await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// List the share links of a saved query
+///
+/// GET /api/domains/{domain_id}/queries/saved/{query_id}/shares
+#[utoipa::path(
+    get,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/shares",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = QueryShareListResponse),
+    ),
+)]
+pub async fn list_query_shares(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id)): Path<(String, String)>,
+) -> Result<Json<QueryShareListResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+
+    let shares = QueryShareService::new(state.storage.clone()).list(&saved.id).await?;
+    Ok(Json(QueryShareListResponse { shares }))
+}
+
+/// Revoke a share link
+///
+/// DELETE /api/domains/{domain_id}/queries/saved/{query_id}/shares/{share_id}
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{domain_id}/queries/saved/{query_id}/shares/{share_id}",
+    tag = "saved-queries",
+    params(
+        ("domain_id" = String, Path),
+        ("query_id" = String, Path),
+        ("share_id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn revoke_query_share(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path((domain_id, query_id, share_id)): Path<(String, String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Editor).await?;
+    let saved = saved_query_in_domain(&state, &domain_id, &query_id).await?;
+
+    QueryShareService::new(state.storage.clone())
+        .revoke(&saved.id, &share_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Read the result behind a share link (no authentication)
+///
+/// GET /api/share/{token}
+///
+/// Serves the frozen result of snapshot shares and runs the saved query for
+/// the others, as long as it is unchanged since the link was created (404
+/// otherwise). The SQL and the connection are not disclosed.
+#[utoipa::path(
+    get,
+    path = "/api/share/{token}",
+    tag = "saved-queries",
+    params(("token" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = SharedResult),
+        (status = 404, description = "Unknown, revoked or expired link"),
+    ),
+    security(()),
+)]
+pub async fn get_shared_result(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SharedResult>, AppError> {
+    let (share, snapshot) = QueryShareService::new(state.storage.clone()).resolve(&token).await?;
+    let saved = saved_query_in_domain(&state, &share.domain_id, &share.saved_query_id)
+        .await
+        .map_err(|_| AppError::NotFound("Share link not found, revoked or expired".to_string()))?;
+
+    let is_snapshot = snapshot.is_some();
+    let query = match snapshot {
+        Some(query) => query,
+        None => {
+            QueryShareService::ensure_unchanged(&share, &saved)?;
+            let connection_id = saved
+                .connection_id
+                .clone()
+                .ok_or_else(|| AppError::NotFound("Share link not found, revoked or expired".to_string()))?;
+            let request = ExecuteSavedQueryRequest {
+                parameters: share.parameters.clone(),
+                confirm_large_result: true,
+                ..Default::default()
+            }
+            .into_query_request(&saved);
+            run_query(
+                &state,
+                &connection_id,
+                request,
+                ResultFormat::Rows,
+                &headers,
+                QueryCaller::Share(&share),
+                Some(&saved.id),
+            )
+            .await?
+            .query
+        }
+    };
+    if query.status != QueryStatus::Completed {
+        // The database error may describe the schema, so it is not passed on
+        tracing::warn!(
+            share_id = %share.id,
+            error = %crate::logging::redactor().text(query.error_message.as_deref().unwrap_or_default()),
+            "Shared query failed"
+        );
+        return Err(AppError::Database("The shared query failed to run".to_string()));
+    }
+
+    let results = query.results.unwrap_or_default();
+    Ok(Json(SharedResult {
+        name: saved.name,
+        description: saved.description,
+        snapshot: is_snapshot,
+        executed_at: query.executed_at,
+        expires_at: share.expires_at,
+        row_count: query.row_count.unwrap_or(results.len()),
+        limit_applied: query.limit_applied,
+        chart: ChartRecommendationService::recommend(&results),
+        results,
+    }))
+}
@@ -0,0 +1,77 @@
+// SQL Tool Handlers
+//
+// Connection-independent formatting, linting and dialect translation for the
+// SQL editor. Nothing is executed, so a read-scoped API key is enough.
+
+use axum::Json;
+
+use crate::api::middleware::AppError;
+use crate::api::responses::{FormattedSqlResponse, LintSqlResponse, TranslatedSqlResponse};
+use crate::models::{FormatSqlRequest, LintSqlRequest, TranslateSqlRequest};
+use crate::services::datafusion::{DatabaseType, DialectTranslationService};
+use crate::validation::{SqlFormatter, SqlLinter};
+
+/// Pretty-print SQL
+///
+/// POST /api/sql/format
+#[utoipa::path(
+    post,
+    path = "/api/sql/format",
+    tag = "sql",
+    request_body = FormatSqlRequest,
+    responses(
+        (status = 200, description = "OK", body = FormattedSqlResponse),
+    ),
+)]
+pub async fn format_sql(Json(request): Json<FormatSqlRequest>) -> Result<Json<FormattedSqlResponse>, AppError> {
+    let dialect = SqlFormatter::dialect(request.dialect.as_deref())?;
+    let sql = SqlFormatter::format(&request.sql, dialect.as_deref(), &request.style)?;
+    Ok(Json(FormattedSqlResponse { sql }))
+}
+
+/// Check SQL for common performance and correctness pitfalls
+///
+/// POST /api/sql/lint
+#[utoipa::path(
+    post,
+    path = "/api/sql/lint",
+    tag = "sql",
+    request_body = LintSqlRequest,
+    responses(
+        (status = 200, description = "OK", body = LintSqlResponse),
+    ),
+)]
+pub async fn lint_sql(Json(request): Json<LintSqlRequest>) -> Result<Json<LintSqlResponse>, AppError> {
+    let dialect = SqlFormatter::dialect(request.dialect.as_deref())?;
+    let issues = SqlLinter::lint(&request.sql, dialect.as_deref())?;
+    Ok(Json(LintSqlResponse { issues }))
+}
+
+/// Preview the translation of unified (DataFusion) SQL to a database dialect
+///
+/// POST /api/sql/translate
+///
+/// Returns the SQL a unified query would run on the target, with the rewrites
+/// applied (identifier quoting, function substitutions, CAST types, INTERVAL
+/// syntax) and their positions in the submitted SQL. Nothing is executed.
+#[utoipa::path(
+    post,
+    path = "/api/sql/translate",
+    tag = "sql",
+    request_body = TranslateSqlRequest,
+    responses(
+        (status = 200, description = "OK", body = TranslatedSqlResponse),
+    ),
+)]
+pub async fn translate_sql(Json(request): Json<TranslateSqlRequest>) -> Result<Json<TranslatedSqlResponse>, AppError> {
+    let target = DatabaseType::from_str(&request.dialect).map_err(|e| AppError::Validation(e.to_string()))?;
+    let (translated_sql, rewrites) = DialectTranslationService::new()
+        .preview_translation(&request.sql, target)
+        .await
+        .map_err(|e| AppError::InvalidSql(format!("{:#}", e)))?;
+    Ok(Json(TranslatedSqlResponse {
+        dialect: target.as_str().to_string(),
+        translated_sql,
+        rewrites,
+    }))
+}
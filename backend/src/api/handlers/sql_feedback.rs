@@ -0,0 +1,166 @@
+// SQL Feedback Handlers
+//
+// Ratings and corrections of LLM-generated SQL. Questions rated up or
+// corrected become few-shot examples of their domain: the closest ones go into
+// the prompts of later natural language queries on the same database type.
+
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{ensure_max_length, require_domain_role, AppError};
+use crate::api::responses::{SqlFeedbackDeletedResponse, SqlFeedbackListResponse};
+use crate::models::{DomainRole, ListParams, Principal, SqlFeedback, SqlFeedbackRequest};
+use crate::storage::SqliteStorage;
+use crate::validation::SqlValidator;
+
+/// Rate or correct SQL generated for a question on a connection
+///
+/// POST /api/connections/{id}/nl-feedback
+///
+/// Feedback is kept in the connection's domain. A rating up makes the
+/// generated SQL an example for similar questions; `corrected_sql` (a single
+/// SELECT, never executed here) makes the correction the example instead.
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/nl-feedback",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = SqlFeedbackRequest,
+    responses(
+        (status = 200, description = "OK", body = SqlFeedback),
+    ),
+)]
+pub async fn submit_sql_feedback(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<SqlFeedbackRequest>,
+) -> Result<Json<SqlFeedback>, AppError> {
+    let limits = &state.config.limits;
+    if payload.question.trim().is_empty() || payload.generated_sql.trim().is_empty() {
+        return Err(AppError::Validation("Question and generated SQL cannot be empty".to_string()));
+    }
+    ensure_max_length("Question", payload.question.trim(), limits.max_question_length)?;
+    ensure_max_length("SQL query", payload.generated_sql.trim(), limits.max_sql_length)?;
+    if let Some(corrected) = payload.corrected_sql.as_deref().map(str::trim).filter(|sql| !sql.is_empty()) {
+        ensure_max_length("SQL query", corrected, limits.max_sql_length)?;
+        SqlValidator::validate_select_only(corrected)?;
+    }
+
+    let connection = state
+        .storage
+        .get_connection(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(&state, principal.as_deref(), connection.domain_id.as_deref(), DomainRole::Editor).await?;
+    let domain_id = connection.domain_id.clone().ok_or_else(|| {
+        AppError::Validation(format!("Connection {} belongs to no domain; feedback is kept per domain", id))
+    })?;
+
+    // Cached SQL for the question is generated again, with this feedback as an example
+    state.generation_cache.invalidate_question(&connection.id, &payload.question);
+
+    let feedback = SqlFeedback::new(
+        domain_id,
+        connection.id,
+        connection.database_type,
+        payload,
+        principal.as_deref().map(|p| p.subject()),
+    );
+    state
+        .storage
+        .add_sql_feedback(&feedback)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(feedback))
+}
+
+/// List the SQL feedback of a domain
+///
+/// GET /api/domains/{id}/nl-feedback?page=1&page_size=50&sort=-created_at&filter=orders
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/nl-feedback",
+    tag = "queries",
+    params(("id" = String, Path), ListParams),
+    responses(
+        (status = 200, description = "OK", body = SqlFeedbackListResponse),
+    ),
+)]
+pub async fn list_sql_feedback(
+    State(state): State<AppState>,
+    Path(domain_id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    axum::extract::Query(params): axum::extract::Query<ListParams>,
+) -> Result<Json<SqlFeedbackListResponse>, AppError> {
+    require_domain_role(&state, principal.as_deref(), Some(&domain_id), DomainRole::Viewer).await?;
+    let list_query = params
+        .resolve(SqliteStorage::SQL_FEEDBACK_SORT_FIELDS, "-created_at")
+        .map_err(AppError::Validation)?;
+
+    state
+        .storage
+        .get_domain(&domain_id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+
+    let (feedback, total) = state
+        .storage
+        .list_sql_feedback_page(&domain_id, &list_query)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(SqlFeedbackListResponse {
+        feedback,
+        page: list_query.page_info(total),
+    }))
+}
+
+/// Delete SQL feedback, removing its example from later prompts
+///
+/// DELETE /api/nl-feedback/{id}
+///
+/// Feedback given by another principal needs the Admin role.
+#[utoipa::path(
+    delete,
+    path = "/api/nl-feedback/{id}",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Deleted", body = SqlFeedbackDeletedResponse),
+    ),
+)]
+pub async fn delete_sql_feedback(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<SqlFeedbackDeletedResponse>, AppError> {
+    let principal = principal.as_deref();
+    let feedback = state
+        .storage
+        .get_sql_feedback(&id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("SQL feedback {} not found", id)))?;
+
+    let role = if feedback.created_by == principal.map(|p| p.subject()) {
+        DomainRole::Editor
+    } else {
+        DomainRole::Admin
+    };
+    require_domain_role(&state, principal, Some(&feedback.domain_id), role).await?;
+
+    state
+        .storage
+        .delete_sql_feedback(&feedback.id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Json(SqlFeedbackDeletedResponse {
+        message: "SQL feedback deleted".to_string(),
+        feedback_id: feedback.id,
+    }))
+}
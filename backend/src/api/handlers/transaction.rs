@@ -0,0 +1,209 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query::{log_write_history, request_parameters};
+use crate::api::middleware::{ensure_max_length, require_domain_role, sql_audit_context, AppError};
+use crate::api::responses::{TransactionSessionResponse, TransactionStatementResponse};
+use crate::models::{DatabaseConnection, DomainRole, ExecuteRequest, Principal, TransactionSession};
+use crate::services::database::{create_adapter, DatabaseType};
+use crate::services::{ConnectionPolicyService, DomainSettingsService, QueryService};
+
+/// Open a transaction session on a write-mode connection
+///
+/// Takes a connection out of the pool and starts a transaction on it. Run
+/// statements with `POST /api/sessions/{id}/execute`, then commit or roll
+/// back. Sessions idle longer than `TRANSACTION_IDLE_TIMEOUT_SECS` are rolled
+/// back. Needs the Admin role and a connection with `allow_writes`
+/// (PostgreSQL and MySQL only).
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/sessions",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 201, description = "Created", body = TransactionSessionResponse),
+    ),
+)]
+pub async fn open_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<(StatusCode, Json<TransactionSessionResponse>), AppError> {
+    let connection = writable_connection(&state, principal.as_deref(), &id).await?;
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    DomainSettingsService::ensure_database_type_allowed(&settings, &connection.database_type)?;
+
+    let db_type = DatabaseType::from_str(&connection.database_type)?;
+    let adapter = create_adapter(
+        db_type,
+        &connection.connection_url,
+        connection.read_only,
+        state.pool_manager.clone(),
+    ).await?;
+    let transaction = adapter.begin_transaction().await?;
+
+    let session = state.transactions.open(
+        connection.id.clone(),
+        connection.domain_id.clone(),
+        principal.as_deref().map(|p| p.subject()),
+        transaction,
+    )?;
+    Ok((StatusCode::CREATED, Json(TransactionSessionResponse { session })))
+}
+
+/// Get an open transaction session
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "OK", body = TransactionSessionResponse),
+    ),
+)]
+pub async fn get_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<TransactionSessionResponse>, AppError> {
+    let session = owned_session(&state, principal.as_deref(), &id).await?;
+    Ok(Json(TransactionSessionResponse { session }))
+}
+
+/// Execute a write statement in a transaction session
+///
+/// Accepts the statements of `POST /api/connections/{id}/execute`; the
+/// changes become visible to others on commit. Statements are recorded in
+/// the query history with kind `write`.
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/execute",
+    tag = "queries",
+    params(("id" = String, Path)),
+    request_body = ExecuteRequest,
+    responses(
+        (status = 200, description = "OK", body = TransactionStatementResponse),
+    ),
+)]
+pub async fn execute_in_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ExecuteRequest>,
+) -> Result<Json<TransactionStatementResponse>, AppError> {
+    let statement = payload.statement.trim();
+    if statement.is_empty() {
+        return Err(AppError::Validation("SQL statement cannot be empty".to_string()));
+    }
+    ensure_max_length("SQL statement", statement, state.config.limits.max_sql_length)?;
+
+    let session = owned_session(&state, principal.as_deref(), &id).await?;
+    let connection = writable_connection(&state, principal.as_deref(), &session.connection_id).await?;
+
+    let settings = DomainSettingsService::new(state.storage.clone())
+        .for_domain(connection.domain_id.as_deref())
+        .await?;
+    let policy_service = ConnectionPolicyService::new(state.storage.clone());
+    let policy = policy_service.for_connection(&connection).await?;
+    policy_service.ensure_within_quota(&connection, &policy).await?;
+
+    let parameters = request_parameters(&state, None, &payload.parameters).await?;
+    let audit = sql_audit_context(&state.config.audit, &headers, principal.as_deref());
+    let query_service = QueryService::new().with_audit(audit).with_parameters(parameters);
+
+    let outcome = state
+        .transactions
+        .execute(&id, &query_service, statement, settings.default_timeout_secs)
+        .await;
+    let executed_by = principal.as_deref().map(|p| p.subject());
+    let result = outcome.as_ref().map(|(_, write)| write);
+    log_write_history(&state, &connection, statement, result, executed_by).await;
+
+    let (session, result) = outcome?;
+    Ok(Json(TransactionStatementResponse { session, result }))
+}
+
+/// Commit a transaction session and close it
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/commit",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Committed", body = TransactionSessionResponse),
+    ),
+)]
+pub async fn commit_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<TransactionSessionResponse>, AppError> {
+    owned_session(&state, principal.as_deref(), &id).await?;
+    let session = state.transactions.finish(&id, true).await?;
+    Ok(Json(TransactionSessionResponse { session }))
+}
+
+/// Roll back a transaction session and close it
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/rollback",
+    tag = "queries",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, description = "Rolled back", body = TransactionSessionResponse),
+    ),
+)]
+pub async fn rollback_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    principal: Option<Extension<Principal>>,
+) -> Result<Json<TransactionSessionResponse>, AppError> {
+    owned_session(&state, principal.as_deref(), &id).await?;
+    let session = state.transactions.finish(&id, false).await?;
+    Ok(Json(TransactionSessionResponse { session }))
+}
+
+/// Connection that allows writes, for a caller with the Admin role on its domain
+async fn writable_connection(
+    state: &AppState,
+    principal: Option<&Principal>,
+    id: &str,
+) -> Result<DatabaseConnection, AppError> {
+    let connection = state
+        .storage
+        .get_connection(id)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", id)))?;
+    require_domain_role(state, principal, connection.domain_id.as_deref(), DomainRole::Admin).await?;
+    if !connection.allow_writes {
+        return Err(AppError::Forbidden(format!(
+            "Connection {} does not allow writes; enable allow_writes on the connection first",
+            id
+        )));
+    }
+    crate::logging::redactor().register_connection(&connection.id, &connection.connection_url);
+    Ok(connection)
+}
+
+/// Open session of the caller; other principals' sessions are reported as missing
+async fn owned_session(
+    state: &AppState,
+    principal: Option<&Principal>,
+    id: &str,
+) -> Result<TransactionSession, AppError> {
+    let session = state.transactions.get(id).await?;
+    if session.opened_by != principal.map(|p| p.subject()) {
+        return Err(AppError::NotFound(format!("Transaction session {} not found", id)));
+    }
+    require_domain_role(state, principal, session.domain_id.as_deref(), DomainRole::Admin).await?;
+    Ok(session)
+}
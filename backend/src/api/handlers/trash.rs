@@ -0,0 +1,100 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::collections::HashMap;
+
+use crate::api::middleware::AppError;
+use crate::api::responses::{TrashRestoredResponse, TrashListResponse};
+use crate::api::handlers::connection::AppState;
+use crate::models::TrashResourceType;
+use crate::services::TrashService;
+
+/// List soft-deleted domains, connections and saved queries
+///
+/// GET /api/trash?type=connection
+#[utoipa::path(
+    get,
+    path = "/api/trash",
+    tag = "trash",
+    params(("type" = Option<TrashResourceType>, Query, description = "Only list one resource type")),
+    responses(
+        (status = 200, description = "OK", body = TrashListResponse),
+    ),
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<TrashListResponse>, AppError> {
+    let resource_type = params
+        .get("type")
+        .map(|t| TrashResourceType::from_str(t).map_err(AppError::Validation))
+        .transpose()?;
+
+    let trash_service = TrashService::new(state.storage.clone(), state.config.trash.clone());
+    let items = trash_service.list(resource_type).await?;
+
+    Ok(Json(TrashListResponse {
+        items,
+        grace_period_days: state.config.trash.grace_period_days,
+    }))
+}
+
+/// Restore a soft-deleted resource
+///
+/// POST /api/trash/{resource_type}/{id}/restore
+#[utoipa::path(
+    post,
+    path = "/api/trash/{resource_type}/{id}/restore",
+    tag = "trash",
+    params(
+        ("resource_type" = TrashResourceType, Path),
+        ("id" = String, Path),
+    ),
+    responses(
+        (status = 200, description = "OK", body = TrashRestoredResponse),
+    ),
+)]
+pub async fn restore_trash_item(
+    State(state): State<AppState>,
+    Path((resource_type, id)): Path<(String, String)>,
+) -> Result<Json<TrashRestoredResponse>, AppError> {
+    let resource_type = TrashResourceType::from_str(&resource_type).map_err(AppError::Validation)?;
+
+    let trash_service = TrashService::new(state.storage.clone(), state.config.trash.clone());
+    trash_service.restore(resource_type, &id).await?;
+
+    Ok(Json(TrashRestoredResponse {
+        message: format!("{} restored successfully", resource_type.as_str()),
+        resource_type,
+        id,
+    }))
+}
+
+/// Permanently delete a soft-deleted resource
+///
+/// DELETE /api/trash/{resource_type}/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/trash/{resource_type}/{id}",
+    tag = "trash",
+    params(
+        ("resource_type" = TrashResourceType, Path),
+        ("id" = String, Path),
+    ),
+    responses(
+        (status = 204, description = "No Content"),
+    ),
+)]
+pub async fn purge_trash_item(
+    State(state): State<AppState>,
+    Path((resource_type, id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let resource_type = TrashResourceType::from_str(&resource_type).map_err(AppError::Validation)?;
+
+    let trash_service = TrashService::new(state.storage.clone(), state.config.trash.clone());
+    trash_service.purge(resource_type, &id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
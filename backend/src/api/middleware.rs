@@ -1,10 +1,22 @@
 use axum::{
-    http::StatusCode,
+    body::HttpBody,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::api::handlers::connection::AppState;
+use crate::api::handlers::query_session::QUERY_SESSION_PATH;
+use crate::api::openapi::{OPENAPI_PATH, SWAGGER_UI_PATH};
+use crate::config::AuditConfig;
+use crate::models::{ApiKeyScope, DomainRole, Principal, RowEstimate, API_KEY_PREFIX};
+use crate::validation::SqlAuditContext;
+use crate::services::{ApiKeyService, ApiUsageService, AuthService};
 
 /// Application error types
 #[derive(Debug, Error)]
@@ -30,22 +42,184 @@ pub enum AppError {
     #[error("Not implemented: {0}")]
     NotImplemented(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String, RowEstimate),
+
+    #[error("Precondition required: {0}")]
+    PreconditionRequired(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Input too long: {0}")]
+    InputTooLong(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Query cancelled: {0}")]
+    Cancelled(String),
+
+    /// A connection's circuit breaker is open; retry after the given seconds
+    #[error("Connection unavailable: {0}")]
+    CircuitOpen(String, u64),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 }
 
-/// Error response format
-#[derive(Debug, Serialize, Deserialize)]
+/// Machine-readable error codes (`error.code` in every error response)
+///
+/// Clients should branch on the code; `message` is meant for people and may
+/// change wording. Database failures are classified further (TABLE_NOT_FOUND,
+/// COLUMN_NOT_FOUND, ...) from the SQLSTATE or engine error text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    DatabaseError,
+    TableNotFound,
+    ColumnNotFound,
+    SqlSyntaxError,
+    PermissionDenied,
+    QueryTimeout,
+    /// Cancelled through `POST /api/queries/{id}/cancel`
+    QueryCancelled,
+    ConnectionError,
+    InvalidSql,
+    ValidationError,
+    LlmServiceError,
+    NotFound,
+    NotImplemented,
+    Conflict,
+    ConfirmationRequired,
+    PreconditionRequired,
+    PayloadTooLarge,
+    InputTooLong,
+    Unauthorized,
+    Forbidden,
+    QuotaExceeded,
+    /// The database failed repeatedly; requests fail fast until it recovers
+    CircuitOpen,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::DatabaseError => "DATABASE_ERROR",
+            ErrorCode::TableNotFound => "TABLE_NOT_FOUND",
+            ErrorCode::ColumnNotFound => "COLUMN_NOT_FOUND",
+            ErrorCode::SqlSyntaxError => "SQL_SYNTAX_ERROR",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::QueryTimeout => "QUERY_TIMEOUT",
+            ErrorCode::QueryCancelled => "QUERY_CANCELLED",
+            ErrorCode::ConnectionError => "CONNECTION_ERROR",
+            ErrorCode::InvalidSql => "INVALID_SQL",
+            ErrorCode::ValidationError => "VALIDATION_ERROR",
+            ErrorCode::LlmServiceError => "LLM_SERVICE_ERROR",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::NotImplemented => "NOT_IMPLEMENTED",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::ConfirmationRequired => "CONFIRMATION_REQUIRED",
+            ErrorCode::PreconditionRequired => "PRECONDITION_REQUIRED",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::InputTooLong => "INPUT_TOO_LONG",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::QuotaExceeded => "QUOTA_EXCEEDED",
+            ErrorCode::CircuitOpen => "CIRCUIT_OPEN",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+        }
+    }
+
+    /// Classify a database error message by SQLSTATE or engine wording
+    pub fn classify_database_error(msg: &str) -> Self {
+        // Codes already assigned by the query service (`TABLE_NOT_FOUND: ...`)
+        for code in [
+            ErrorCode::TableNotFound,
+            ErrorCode::ColumnNotFound,
+            ErrorCode::SqlSyntaxError,
+            ErrorCode::PermissionDenied,
+        ] {
+            if msg.starts_with(code.as_str()) {
+                return code;
+            }
+        }
+
+        let lower = msg.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+
+        // Column checks come first: "column x of relation y does not exist"
+        if has(&["42703", "42s22", "unknown column"])
+            || (lower.contains("column") && has(&["does not exist", "not found"]))
+        {
+            ErrorCode::ColumnNotFound
+        } else if has(&["42p01", "42s02", "table or view not found"])
+            || (has(&["relation", "table"]) && has(&["does not exist", "doesn't exist"]))
+        {
+            ErrorCode::TableNotFound
+        } else if has(&["42601", "syntax error", "error in your sql syntax"]) {
+            ErrorCode::SqlSyntaxError
+        } else if has(&["42501", "permission denied", "command denied", "access denied"]) {
+            ErrorCode::PermissionDenied
+        } else if has(&["timeout", "timed out"]) {
+            ErrorCode::QueryTimeout
+        } else {
+            ErrorCode::DatabaseError
+        }
+    }
+}
+
+impl From<ErrorCode> for String {
+    fn from(code: ErrorCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+/// Error response body
+///
+/// ```json
+/// {
+///   "error": {
+///     "code": "TABLE_NOT_FOUND",
+///     "message": "...",
+///     "details": "...",
+///     "data": { ... },
+///     "request_id": "3f6c..."
+///   }
+/// }
+/// ```
+///
+/// `code` is always present and is one of [`ErrorCode`]; `details`, `data`
+/// and `request_id` are omitted when empty. `request_id` matches the
+/// `X-Request-Id` response header.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: ErrorDetail,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorDetail {
+    /// One of [`ErrorCode`]
+    #[schema(value_type = ErrorCode)]
     pub code: String,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// Structured context for the client (e.g. a result size estimate)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl ErrorDetail {
@@ -54,6 +228,8 @@ impl ErrorDetail {
             code: code.into(),
             message: message.into(),
             details: None,
+            data: None,
+            request_id: current_request_id(),
         }
     }
 
@@ -61,42 +237,71 @@ impl ErrorDetail {
         self.details = Some(details.into());
         self
     }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl AppError {
+    /// Whether the statement itself was rejected (invalid SQL, unknown table or
+    /// column, syntax or other engine errors), so corrected SQL may succeed
+    ///
+    /// Permission errors and timeouts are not, nor is anything outside the database.
+    pub fn is_sql_error(&self) -> bool {
+        match self {
+            AppError::InvalidSql(_) => true,
+            AppError::Database(msg) => matches!(
+                ErrorCode::classify_database_error(msg),
+                ErrorCode::TableNotFound | ErrorCode::ColumnNotFound | ErrorCode::SqlSyntaxError | ErrorCode::DatabaseError
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::CircuitOpen(_, secs) => Some(*secs),
+            _ => None,
+        };
         let (status, error_detail) = match self {
             AppError::Database(msg) => {
                 // Provide actionable suggestions for database errors
-                let enhanced_msg = if msg.contains("TABLE_NOT_FOUND") || msg.contains("does not exist") {
-                    format!("{} Try refreshing the metadata or check if the table name is correct.", msg)
-                } else if msg.contains("timeout") {
-                    format!("{} Consider simplifying your query or checking database performance.", msg)
-                } else {
-                    msg
+                let code = ErrorCode::classify_database_error(&msg);
+                let enhanced_msg = match code {
+                    ErrorCode::TableNotFound | ErrorCode::ColumnNotFound => {
+                        format!("{} Try refreshing the metadata or check if the table name is correct.", msg)
+                    }
+                    ErrorCode::QueryTimeout => {
+                        format!("{} Consider simplifying your query or checking database performance.", msg)
+                    }
+                    _ => msg,
                 };
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    ErrorDetail::new("DATABASE_ERROR", enhanced_msg),
+                    ErrorDetail::new(code, enhanced_msg),
                 )
             },
             AppError::Connection(msg) => {
                 // Connection errors already include suggestions
                 (
                     StatusCode::BAD_REQUEST,
-                    ErrorDetail::new("CONNECTION_ERROR", msg),
+                    ErrorDetail::new(ErrorCode::ConnectionError, msg),
                 )
             },
             AppError::InvalidSql(msg) => {
                 let enhanced_msg = format!("{} Only SELECT queries are allowed. Please check your SQL syntax.", msg);
                 (
                     StatusCode::BAD_REQUEST,
-                    ErrorDetail::new("INVALID_SQL", enhanced_msg),
+                    ErrorDetail::new(ErrorCode::InvalidSql, enhanced_msg),
                 )
             },
             AppError::Validation(msg) => (
                 StatusCode::BAD_REQUEST,
-                ErrorDetail::new("VALIDATION_ERROR", msg),
+                ErrorDetail::new(ErrorCode::ValidationError, msg),
             ),
             AppError::LlmService(msg) => {
                 let enhanced_msg = if msg.contains("not yet implemented") || msg.contains("not configured") {
@@ -106,20 +311,62 @@ impl IntoResponse for AppError {
                 };
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    ErrorDetail::new("LLM_SERVICE_ERROR", enhanced_msg),
+                    ErrorDetail::new(ErrorCode::LlmServiceError, enhanced_msg),
                 )
             },
             AppError::NotFound(msg) => (
                 StatusCode::NOT_FOUND,
-                ErrorDetail::new("NOT_FOUND", msg),
+                ErrorDetail::new(ErrorCode::NotFound, msg),
             ),
             AppError::NotImplemented(msg) => (
                 StatusCode::NOT_IMPLEMENTED,
-                ErrorDetail::new("NOT_IMPLEMENTED", msg),
+                ErrorDetail::new(ErrorCode::NotImplemented, msg),
+            ),
+            AppError::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                ErrorDetail::new(ErrorCode::Conflict, msg),
+            ),
+            AppError::ConfirmationRequired(msg, estimate) => (
+                StatusCode::CONFLICT,
+                ErrorDetail::new(ErrorCode::ConfirmationRequired, msg)
+                    .with_data(serde_json::json!({ "estimate": estimate })),
+            ),
+            AppError::PreconditionRequired(msg) => (
+                StatusCode::PRECONDITION_REQUIRED,
+                ErrorDetail::new(ErrorCode::PreconditionRequired, msg),
+            ),
+            AppError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorDetail::new(ErrorCode::PayloadTooLarge, msg),
+            ),
+            AppError::InputTooLong(msg) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorDetail::new(ErrorCode::InputTooLong, msg),
+            ),
+            AppError::Unauthorized(msg) => (
+                StatusCode::UNAUTHORIZED,
+                ErrorDetail::new(ErrorCode::Unauthorized, msg),
+            ),
+            AppError::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                ErrorDetail::new(ErrorCode::Forbidden, msg),
+            ),
+            AppError::QuotaExceeded(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorDetail::new(ErrorCode::QuotaExceeded, msg),
+            ),
+            AppError::Cancelled(msg) => (
+                StatusCode::CONFLICT,
+                ErrorDetail::new(ErrorCode::QueryCancelled, msg),
+            ),
+            AppError::CircuitOpen(msg, retry_after_secs) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorDetail::new(ErrorCode::CircuitOpen, msg)
+                    .with_data(serde_json::json!({ "retry_after_secs": retry_after_secs })),
             ),
             AppError::Internal(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ErrorDetail::new("INTERNAL_ERROR", msg),
+                ErrorDetail::new(ErrorCode::InternalError, msg),
             ),
         };
 
@@ -127,8 +374,448 @@ impl IntoResponse for AppError {
             error: error_detail,
         });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+/// Resolve the version a client expects to update (optimistic concurrency)
+///
+/// The `If-Match` header takes precedence (accepts `3`, `"3"` or `W/"3"`),
+/// falling back to an `expected_version` field in the request body.
+pub fn expected_version(headers: &HeaderMap, body_version: Option<i64>) -> Result<i64, AppError> {
+    if let Some(value) = headers.get(header::IF_MATCH) {
+        let raw = value
+            .to_str()
+            .map_err(|_| AppError::Validation("Invalid If-Match header".to_string()))?;
+        let version = raw
+            .trim()
+            .trim_start_matches("W/")
+            .trim_matches('"');
+        return version.parse::<i64>().map_err(|_| {
+            AppError::Validation(format!("Invalid If-Match header: expected a version number, got {}", raw))
+        });
+    }
+
+    body_version.ok_or_else(|| {
+        AppError::PreconditionRequired(
+            "Updates require an If-Match header or expected_version field with the current version".to_string(),
+        )
+    })
+}
+
+/// Reject requests whose declared body size exceeds the route's limit
+///
+/// Used with `axum::middleware::from_fn_with_state(limit, limit_body_size)`
+/// alongside `DefaultBodyLimit`, which still caps chunked bodies without a
+/// `Content-Length`. Rejecting up front avoids buffering the body at all.
+pub async fn limit_body_size(
+    State(max_bytes): State<usize>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if max_bytes > 0 {
+        let content_length = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+
+        if let Some(length) = content_length.filter(|length| *length > max_bytes) {
+            return Err(AppError::PayloadTooLarge(format!(
+                "Request body is {} bytes; the maximum for this endpoint is {} bytes",
+                length, max_bytes
+            )));
+        }
     }
+
+    Ok(next.run(request).await)
+}
+
+/// Count every API call in its domain's daily usage rollup
+///
+/// Used with `axum::middleware::from_fn_with_state(state, track_api_usage)`.
+/// Responses with a 4xx/5xx status count as errors; the response body size is
+/// the data volume. Recording happens in the background and never delays or
+/// fails the response.
+pub async fn track_api_usage(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+
+    let failed = response.status().is_client_error() || response.status().is_server_error();
+    let size_hint = response.body().size_hint();
+    let bytes_out = size_hint.exact().unwrap_or(size_hint.lower());
+    let storage = state.storage.clone();
+    tokio::spawn(async move {
+        ApiUsageService::new(storage).record_call(&path, failed, bytes_out).await;
+    });
+
+    response
+}
+
+/// Require a valid `Authorization: Bearer <token>` (API key or login JWT)
+///
+/// Used with `axum::middleware::from_fn_with_state(state, require_auth)`.
+/// A no-op unless `auth.enabled` is set. `/health`, login, share links, the API
+/// docs and CORS preflight requests are always allowed. API keys must have the scope the request
+/// needs; users must be global admins for administrative routes, and their
+/// domain roles are checked by the handlers. The authenticated `Principal`
+/// is added to the request extensions.
+pub async fn require_auth(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let path = request.uri().path();
+    if !state.config.auth.enabled
+        || request.method() == Method::OPTIONS
+        || path == "/health"
+        || path == "/api/auth/login"
+        || path.starts_with("/api/share/")
+        || path == OPENAPI_PATH
+        || path.starts_with(SWAGGER_UI_PATH)
+    {
+        return Ok(next.run(request).await);
+    }
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer ").or_else(|| v.strip_prefix("bearer ")))
+        .map(|v| v.trim().to_string())
+        .or_else(|| query_session_token(&request))
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization: Bearer <token> header".to_string()))?;
+
+    let principal = if is_jwt(&token) {
+        let principal = AuthService::new(state.storage.clone(), state.config.auth.clone())
+            .verify_token(&token)
+            .await?;
+        if !principal.is_admin() && user_requires_admin(request.method(), request.uri().path()) {
+            return Err(AppError::Forbidden(format!(
+                "User '{}' is not an administrator",
+                principal.name()
+            )));
+        }
+        principal
+    } else {
+        let api_key = ApiKeyService::new(state.storage.clone(), state.config.auth.clone())
+            .authenticate(&token)
+            .await?;
+
+        let required = required_scope(request.method(), request.uri().path());
+        if !api_key.scope.allows(required) {
+            return Err(AppError::Forbidden(format!(
+                "API key '{}' has scope '{}' but this request requires '{}'",
+                api_key.name,
+                api_key.scope.as_str(),
+                required.as_str()
+            )));
+        }
+        Principal::from_api_key(&api_key)
+    };
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+/// `access_token` query parameter of a query session request
+///
+/// Browsers cannot set headers on WebSocket requests, so only the session
+/// endpoint accepts the token in the URL.
+fn query_session_token(request: &Request) -> Option<String> {
+    if request.uri().path() != QUERY_SESSION_PATH {
+        return None;
+    }
+    url::form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find(|(key, _)| key == "access_token")
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// Login tokens are JWTs (`header.payload.signature`); API keys never contain dots
+fn is_jwt(token: &str) -> bool {
+    !token.starts_with(API_KEY_PREFIX) && token.matches('.').count() == 2
+}
+
+/// Routes that are not scoped to a single domain and need a global admin user
+pub fn user_requires_admin(method: &Method, path: &str) -> bool {
+    let domain_lifecycle = path == "/api/domains/import"
+        || (path == "/api/domains" && method == Method::POST)
+        || (path.starts_with("/api/domains/") && path.matches('/').count() == 3 && method == Method::DELETE);
+
+    path.starts_with("/api/admin/")
+        || path == "/api/admin"
+        || path.starts_with("/api/trash")
+        || path == "/api/queries/history/prune"
+        || domain_lifecycle
+}
+
+/// Require a domain role of the caller (see `AuthService::require_domain_role`)
+pub async fn require_domain_role(
+    state: &AppState,
+    principal: Option<&Principal>,
+    domain_id: Option<&str>,
+    role: DomainRole,
+) -> Result<(), AppError> {
+    AuthService::new(state.storage.clone(), state.config.auth.clone())
+        .require_domain_role(principal, domain_id, role)
+        .await
+}
+
+/// Scope an API key needs for each route: method, path (`{param}` matches any
+/// single segment) and scope. Routes not listed need admin, so a route is never
+/// reachable with a weaker key because it was left out.
+const ROUTE_SCOPES: &[(&str, &str, ApiKeyScope)] = &[
+    // Queries and statements
+    ("POST", "/api/connections/{id}/query", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/query/export", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/query/explain", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/query/advise", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/query/summarize", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/execute", ApiKeyScope::Admin),
+    ("POST", "/api/sessions/{id}/execute", ApiKeyScope::Admin),
+    ("POST", "/api/connections/{id}/nl-query", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/nl-query/stream", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/chat", ApiKeyScope::Execute),
+    ("GET", "/api/chat-sessions/{id}", ApiKeyScope::Read),
+    ("DELETE", "/api/chat-sessions/{id}", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/nl-feedback", ApiKeyScope::Execute),
+    ("DELETE", "/api/nl-feedback/{id}", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/unified-query", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/queries/async", ApiKeyScope::Execute),
+    // SQL formatting, linting, completion and metric compilation never touch a database
+    ("POST", "/api/connections/{id}/autocomplete", ApiKeyScope::Read),
+    ("POST", "/api/sql/format", ApiKeyScope::Read),
+    ("POST", "/api/sql/lint", ApiKeyScope::Read),
+    ("POST", "/api/sql/translate", ApiKeyScope::Read),
+    ("POST", "/api/cross-database/query", ApiKeyScope::Execute),
+    ("POST", "/api/cross-database/insert-select", ApiKeyScope::Execute),
+    ("GET", "/api/cross-database/catalog", ApiKeyScope::Read),
+    // Saved queries
+    ("GET", "/api/domains/{domain_id}/queries/saved", ApiKeyScope::Read),
+    ("POST", "/api/domains/{domain_id}/queries/saved", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/queries/saved/{query_id}", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{domain_id}/queries/saved/{query_id}", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{domain_id}/queries/saved/{query_id}", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/queries/saved/{query_id}/contract", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{domain_id}/queries/saved/{query_id}/contract", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{domain_id}/queries/saved/{query_id}/contract", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{domain_id}/queries/saved/{query_id}/execute", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/queries/saved/{query_id}/shares", ApiKeyScope::Read),
+    ("POST", "/api/domains/{domain_id}/queries/saved/{query_id}/shares", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{domain_id}/queries/saved/{query_id}/shares/{share_id}", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/queries/saved/{query_id}/materialization", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{domain_id}/queries/saved/{query_id}/materialization", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{domain_id}/queries/saved/{query_id}/materialization", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{domain_id}/queries/saved/{query_id}/materialization/refresh", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{domain_id}/queries/history/archive", ApiKeyScope::Execute),
+    // Domains; role assignments and webhooks need admin
+    ("GET", "/api/auth/me", ApiKeyScope::Read),
+    ("GET", "/api/domains", ApiKeyScope::Read),
+    ("POST", "/api/domains", ApiKeyScope::Execute),
+    ("POST", "/api/domains/import", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{id}/connections/import", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{id}", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{id}", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{id}", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{id}/connections", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/usage", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/stats", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/slow-queries", ApiKeyScope::Read),
+    ("GET", "/api/domains/{domain_id}/favorites", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{domain_id}/favorites/{resource_type}/{resource_id}", ApiKeyScope::Execute),
+    ("DELETE", "/api/domains/{domain_id}/favorites/{resource_type}/{resource_id}", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/recent", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/reports", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/llm-usage", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/llm-audit", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/settings", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{id}/settings", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{id}/nl-query", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{id}/nl-feedback", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/metrics", ApiKeyScope::Read),
+    ("PUT", "/api/domains/{id}/metrics", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{id}/metrics/compile", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/export", ApiKeyScope::Read),
+    ("GET", "/api/domains/{id}/roles", ApiKeyScope::Admin),
+    ("PUT", "/api/domains/{id}/roles", ApiKeyScope::Admin),
+    ("DELETE", "/api/domains/{id}/roles/{user_id}", ApiKeyScope::Admin),
+    ("GET", "/api/domains/{id}/webhooks", ApiKeyScope::Admin),
+    ("POST", "/api/domains/{id}/webhooks", ApiKeyScope::Admin),
+    ("DELETE", "/api/domains/{id}/webhooks/{webhook_id}", ApiKeyScope::Admin),
+    ("GET", "/api/domains/{id}/webhooks/{webhook_id}/deliveries", ApiKeyScope::Admin),
+    // Connections; write statements and transaction sessions need admin
+    ("GET", "/api/connections", ApiKeyScope::Read),
+    ("POST", "/api/connections", ApiKeyScope::Execute),
+    ("GET", "/api/connections/templates", ApiKeyScope::Read),
+    ("POST", "/api/connections/url", ApiKeyScope::Execute),
+    ("GET", "/api/connections/{id}", ApiKeyScope::Read),
+    ("PUT", "/api/connections/{id}", ApiKeyScope::Execute),
+    ("DELETE", "/api/connections/{id}", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/duplicate", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/move", ApiKeyScope::Execute),
+    ("GET", "/api/connections/{id}/statement-cache", ApiKeyScope::Read),
+    ("GET", "/api/connections/{id}/pool-stats", ApiKeyScope::Read),
+    ("DELETE", "/api/connections/{id}/cache", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/sessions", ApiKeyScope::Admin),
+    ("POST", "/api/queries/{id}/cancel", ApiKeyScope::Execute),
+    ("GET", "/api/sessions/{id}", ApiKeyScope::Admin),
+    ("POST", "/api/sessions/{id}/commit", ApiKeyScope::Admin),
+    ("POST", "/api/sessions/{id}/rollback", ApiKeyScope::Admin),
+    // Metadata; table samples and exact counts run a query on the database
+    ("GET", "/api/connections/{id}/metadata", ApiKeyScope::Read),
+    ("POST", "/api/connections/{id}/metadata/refresh", ApiKeyScope::Execute),
+    ("GET", "/api/connections/{id}/dictionary", ApiKeyScope::Read),
+    ("PUT", "/api/connections/{id}/dictionary", ApiKeyScope::Execute),
+    ("DELETE", "/api/connections/{id}/dictionary", ApiKeyScope::Execute),
+    ("GET", "/api/connections/{id}/metadata/versions", ApiKeyScope::Read),
+    ("GET", "/api/connections/{id}/metadata/diff", ApiKeyScope::Read),
+    ("GET", "/api/connections/{id}/tables/{schema}/{table}/sample", ApiKeyScope::Execute),
+    ("GET", "/api/connections/{id}/tables/{schema}/{table}/count", ApiKeyScope::Execute),
+    ("POST", "/api/connections/{id}/tables/{table}/profile", ApiKeyScope::Execute),
+    // Query jobs and sessions; opening a query session is a GET, but the session executes queries
+    ("GET", "/api/query-jobs/{id}", ApiKeyScope::Read),
+    ("DELETE", "/api/query-jobs/{id}", ApiKeyScope::Execute),
+    ("GET", "/api/query-jobs/{id}/stream", ApiKeyScope::Read),
+    ("GET", QUERY_SESSION_PATH, ApiKeyScope::Execute),
+    // Query history and trash
+    ("GET", "/api/domains/{domain_id}/queries/history", ApiKeyScope::Read),
+    ("GET", "/api/domains/{domain_id}/connections/{connection_id}/history", ApiKeyScope::Read),
+    ("POST", "/api/domains/{domain_id}/queries/history/{history_id}/replay", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{domain_id}/queries/history/{history_id}/rerun", ApiKeyScope::Execute),
+    ("POST", "/api/domains/{domain_id}/queries/history/{history_id}/promote", ApiKeyScope::Execute),
+    ("GET", "/api/domains/{domain_id}/queries/history/{history_id}/result", ApiKeyScope::Read),
+    ("POST", "/api/queries/history/prune", ApiKeyScope::Execute),
+    ("GET", "/api/trash", ApiKeyScope::Read),
+    ("POST", "/api/trash/{resource_type}/{id}/restore", ApiKeyScope::Execute),
+    ("DELETE", "/api/trash/{resource_type}/{id}", ApiKeyScope::Execute),
+    // Administration
+    ("GET", "/api/admin/api-keys", ApiKeyScope::Admin),
+    ("POST", "/api/admin/api-keys", ApiKeyScope::Admin),
+    ("POST", "/api/admin/api-keys/{id}/revoke", ApiKeyScope::Admin),
+    ("GET", "/api/admin/users", ApiKeyScope::Admin),
+    ("POST", "/api/admin/users", ApiKeyScope::Admin),
+    ("GET", "/api/admin/policies", ApiKeyScope::Admin),
+    ("POST", "/api/admin/policies", ApiKeyScope::Admin),
+    ("DELETE", "/api/admin/policies/{id}", ApiKeyScope::Admin),
+];
+
+/// Scope needed for a request, looked up in [`ROUTE_SCOPES`] (HEAD as GET)
+pub fn required_scope(method: &Method, path: &str) -> ApiKeyScope {
+    let method = if method == Method::HEAD { "GET" } else { method.as_str() };
+    ROUTE_SCOPES
+        .iter()
+        .find(|(route_method, route, _)| *route_method == method && route_matches(route, path))
+        .map(|(_, _, scope)| *scope)
+        .unwrap_or(ApiKeyScope::Admin)
+}
+
+/// Whether a request path matches a route path, `{param}` segments matching any
+/// non-empty segment
+fn route_matches(route: &str, path: &str) -> bool {
+    let mut route_segments = route.split('/');
+    let mut path_segments = path.split('/');
+    loop {
+        match (route_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(actual)) => {
+                let param = expected.starts_with('{') && expected.ends_with('}');
+                if expected != actual && !(param && !actual.is_empty()) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Header carrying the request id (accepted from clients, echoed on responses)
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Give every request an id and echo it in the `X-Request-Id` response header
+///
+/// A client-supplied id (up to 128 visible ASCII characters) is kept,
+/// otherwise a UUID is generated. Error bodies and SQL audit comments carry
+/// the same id, so a failure can be traced from the client to the database.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && v.len() <= 128 && v.chars().all(|c| c.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Id of the request being handled (None outside the `request_id` middleware)
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run a task spawned by a handler under the request id `id`, so its queries can
+/// be cancelled and are audited like the request's own
+pub async fn in_request<F: std::future::Future>(id: Option<String>, future: F) -> F::Output {
+    match id {
+        Some(id) => REQUEST_ID.scope(id, future).await,
+        None => future.await,
+    }
+}
+
+/// Audit context for outgoing SQL (None unless `audit.sql_comment` is enabled)
+///
+/// Uses the request id (see [`request_id`]), falling back to the caller's
+/// `X-Request-Id` or a generated id, and the name of the authenticated user
+/// or API key as the user.
+pub fn sql_audit_context(
+    config: &AuditConfig,
+    headers: &HeaderMap,
+    principal: Option<&Principal>,
+) -> Option<SqlAuditContext> {
+    if !config.sql_comment {
+        return None;
+    }
+
+    let request_id = current_request_id()
+        .or_else(|| {
+            headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        })
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    Some(SqlAuditContext {
+        request_id,
+        user: principal.map(|p| p.name().to_string()),
+    })
+}
+
+/// Check the length of a text field such as SQL or a question (0 disables the check)
+pub fn ensure_max_length(field: &str, value: &str, max_length: usize) -> Result<(), AppError> {
+    if max_length > 0 && value.len() > max_length {
+        return Err(AppError::InputTooLong(format!(
+            "{} is {} bytes long; the maximum is {} bytes",
+            field,
+            value.len(),
+            max_length
+        )));
+    }
+    Ok(())
 }
 
 /// Convert anyhow::Error to AppError
@@ -163,5 +850,118 @@ mod tests {
         assert_eq!(detail.message, "Test message");
         assert!(detail.details.is_none());
     }
+
+    #[test]
+    fn test_expected_version() {
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            expected_version(&headers, None),
+            Err(AppError::PreconditionRequired(_))
+        ));
+        assert_eq!(expected_version(&headers, Some(4)).unwrap(), 4);
+
+        headers.insert(header::IF_MATCH, "W/\"7\"".parse().unwrap());
+        assert_eq!(expected_version(&headers, Some(4)).unwrap(), 7);
+
+        headers.insert(header::IF_MATCH, "abc".parse().unwrap());
+        assert!(expected_version(&headers, None).is_err());
+    }
+
+    #[test]
+    fn test_ensure_max_length() {
+        assert!(ensure_max_length("SQL query", "SELECT 1", 8).is_ok());
+        assert!(ensure_max_length("SQL query", "SELECT 1", 0).is_ok());
+
+        let err = ensure_max_length("SQL query", "SELECT 10", 8).unwrap_err();
+        assert!(err.to_string().contains("9 bytes"));
+        assert_eq!(err.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope(&Method::GET, "/api/connections"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/api/connections/1/query"), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::DELETE, "/api/domains/1"), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::GET, "/api/admin/api-keys"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::PUT, "/api/domains/d1/roles"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, QUERY_SESSION_PATH), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::POST, "/api/connections/1/execute"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::POST, "/api/connections/1/sessions"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, "/api/sessions/s1"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, "/api/domains/d1/webhooks"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::POST, "/api/sql/lint"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/api/connections/1/autocomplete"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/api/domains/d1/metrics/compile"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::GET, "/api/connections/1/tables/public/orders/sample"), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::GET, "/api/connections/1/tables/public/orders/count"), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::GET, "/api/connections/1/metadata"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::HEAD, "/api/connections/1/metadata"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::GET, "/api/domains/d1/queries/saved/q1/contract"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::PUT, "/api/domains/d1/queries/saved/q1/contract"), ApiKeyScope::Execute);
+        assert_eq!(required_scope(&Method::DELETE, "/api/domains/d1/queries/saved/q1/contract"), ApiKeyScope::Execute);
+        // Unlisted routes and methods need admin
+        assert_eq!(required_scope(&Method::GET, "/api/unknown"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::PATCH, "/api/connections/1"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, "/api/connections/1/tables/orders/count"), ApiKeyScope::Admin);
+
+        assert!(user_requires_admin(&Method::POST, "/api/domains"));
+        assert!(user_requires_admin(&Method::DELETE, "/api/domains/d1"));
+        assert!(!user_requires_admin(&Method::DELETE, "/api/domains/d1/queries/saved/q1"));
+        assert!(!user_requires_admin(&Method::GET, "/api/domains"));
+        assert!(is_jwt("aaa.bbb.ccc"));
+        assert!(!is_jwt("dbq_0123456789abcdef"));
+
+        let response = AppError::Forbidden("scope".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_error_codes_and_request_id() {
+        assert_eq!(
+            ErrorCode::classify_database_error("TABLE_NOT_FOUND: Code: 42P01, Message: relation \"x\" does not exist"),
+            ErrorCode::TableNotFound
+        );
+        assert_eq!(
+            ErrorCode::classify_database_error("column \"y\" of relation \"x\" does not exist"),
+            ErrorCode::ColumnNotFound
+        );
+        assert_eq!(
+            ErrorCode::classify_database_error("ERROR 1146 (42S02): Table 'shop.x' doesn't exist"),
+            ErrorCode::TableNotFound
+        );
+        assert_eq!(
+            ErrorCode::classify_database_error("Query timeout after 30 seconds"),
+            ErrorCode::QueryTimeout
+        );
+        assert_eq!(ErrorCode::classify_database_error("disk full"), ErrorCode::DatabaseError);
+        assert!(AppError::Database("column \"y\" does not exist".to_string()).is_sql_error());
+        assert!(AppError::InvalidSql("Only SELECT queries are allowed".to_string()).is_sql_error());
+        assert!(!AppError::Database("Query timeout after 30 seconds".to_string()).is_sql_error());
+        assert!(!AppError::Cancelled("cancelled".to_string()).is_sql_error());
+        assert_eq!(serde_json::to_value(ErrorCode::TableNotFound).unwrap(), "TABLE_NOT_FOUND");
+
+        assert!(ErrorDetail::new(ErrorCode::NotFound, "outside").request_id.is_none());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let detail = rt.block_on(REQUEST_ID.scope("req-42".to_string(), async {
+            ErrorDetail::new(ErrorCode::NotFound, "inside")
+        }));
+        assert_eq!(detail.code, "NOT_FOUND");
+        assert_eq!(detail.request_id.as_deref(), Some("req-42"));
+    }
+
+    #[test]
+    fn test_conflict_status() {
+        let response = AppError::Conflict("stale".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = AppError::Cancelled("by request".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_circuit_open_status() {
+        let response = AppError::CircuitOpen("down".to_string(), 12).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "12");
+    }
 }
 
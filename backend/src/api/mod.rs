@@ -1,5 +1,7 @@
 pub mod middleware;
 pub mod routes;
 pub mod handlers;
+pub mod openapi;
+pub mod responses;
 
 
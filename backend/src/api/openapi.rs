@@ -0,0 +1,262 @@
+// OpenAPI Document
+//
+// OpenAPI 3 description of the HTTP API, generated from the `#[utoipa::path]`
+// annotations on the handlers and the `ToSchema` derives on the models.
+// Served at `/api/openapi.json`, with Swagger UI at `/api/docs`.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{RefOr, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::api::handlers::{admin, auth, chat, connection, cross_database_query, data_dictionary, domain, favorite, metadata, metrics, query, query_job, query_session, result_contract, share, sql, sql_feedback, transaction, trash};
+use crate::api::middleware::{ErrorCode, ErrorResponse};
+use crate::api::responses::QueryHistoryDailyResponse;
+
+/// Path of the generated OpenAPI document
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Path of the Swagger UI page
+pub const SWAGGER_UI_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "DB Query API",
+        description = "Query databases with SQL or natural language, organised into domains"
+    ),
+    paths(
+        auth::login,
+        auth::me,
+        domain::list_domains,
+        domain::create_domain,
+        domain::import_domain,
+        domain::import_connections,
+        domain::get_domain,
+        domain::update_domain,
+        domain::delete_domain,
+        domain::list_domain_connections,
+        domain::get_domain_usage,
+        domain::get_domain_stats,
+        domain::get_domain_slow_queries,
+        domain::get_domain_reports,
+        domain::get_domain_llm_usage,
+        domain::list_domain_llm_audit,
+        domain::get_domain_settings,
+        domain::update_domain_settings,
+        metrics::get_domain_metrics,
+        metrics::update_domain_metrics,
+        metrics::compile_metric,
+        domain::export_domain,
+        domain::list_domain_roles,
+        domain::assign_domain_role,
+        domain::remove_domain_role,
+        domain::list_webhooks,
+        domain::create_webhook,
+        domain::delete_webhook,
+        domain::list_webhook_deliveries,
+        connection::list_connections,
+        connection::create_connection,
+        connection::get_connection,
+        connection::get_statement_cache_stats,
+        connection::get_pool_stats,
+        connection::invalidate_query_cache,
+        connection::update_connection,
+        connection::delete_connection,
+        connection::duplicate_connection,
+        connection::list_connection_templates,
+        connection::build_url,
+        connection::move_connection,
+        metadata::get_metadata,
+        metadata::refresh_metadata,
+        metadata::list_metadata_versions,
+        metadata::get_metadata_diff,
+        metadata::autocomplete,
+        metadata::sample_table,
+        metadata::count_table,
+        metadata::profile_table,
+        data_dictionary::list_dictionary,
+        data_dictionary::upsert_dictionary_entry,
+        data_dictionary::delete_dictionary_entry,
+        query::execute_query,
+        query::export_query,
+        query::explain_query,
+        query::advise_query,
+        query::summarize_result,
+        query::execute_write,
+        query::cancel_query,
+        sql::format_sql,
+        sql::lint_sql,
+        sql::translate_sql,
+        transaction::open_transaction,
+        transaction::get_transaction,
+        transaction::execute_in_transaction,
+        transaction::commit_transaction,
+        transaction::rollback_transaction,
+        query::execute_natural_language_query,
+        query::stream_natural_language_query,
+        chat::chat,
+        chat::get_chat_session,
+        chat::delete_chat_session,
+        sql_feedback::submit_sql_feedback,
+        sql_feedback::list_sql_feedback,
+        sql_feedback::delete_sql_feedback,
+        query::execute_unified_query,
+        cross_database_query::execute_cross_database_query,
+        cross_database_query::submit_cross_database_insert,
+        cross_database_query::get_federated_catalog,
+        cross_database_query::execute_cross_database_natural_language_query,
+        query_job::submit_query_job,
+        query_job::get_query_job,
+        query_job::cancel_query_job,
+        query_job::stream_query_job,
+        query_session::open_query_session,
+        favorite::list_favorites,
+        favorite::add_favorite,
+        favorite::remove_favorite,
+        favorite::list_recent_queries,
+        query::list_saved_queries,
+        query::create_saved_query,
+        query::get_saved_query,
+        query::execute_saved_query,
+        share::create_query_share,
+        share::list_query_shares,
+        share::revoke_query_share,
+        share::get_shared_result,
+        query::update_saved_query,
+        query::delete_saved_query,
+        result_contract::get_result_contract,
+        result_contract::set_result_contract,
+        result_contract::delete_result_contract,
+        query::get_view_materialization,
+        query::materialize_view,
+        query::refresh_view_materialization,
+        query::dematerialize_view,
+        query::list_query_history,
+        query::list_connection_query_history,
+        query::replay_query_history,
+        query::rerun_query_history,
+        query::promote_query_history,
+        query::get_query_history_result,
+        query::query_history_archive,
+        query::prune_query_history,
+        trash::list_trash,
+        trash::restore_trash_item,
+        trash::purge_trash_item,
+        admin::list_api_keys,
+        admin::create_api_key,
+        admin::revoke_api_key,
+        admin::list_users,
+        admin::create_user,
+        admin::list_policies,
+        admin::create_policy,
+        admin::delete_policy,
+    ),
+    // The history listings answer `aggregate=daily` with QueryHistoryDailyResponse
+    components(schemas(ErrorResponse, ErrorCode, QueryHistoryDailyResponse)),
+    modifiers(&BearerAuth, &ErrorResponses),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "auth", description = "Login and caller identity"),
+        (name = "domains", description = "Domains, their settings, roles and bundles"),
+        (name = "connections", description = "Database connections"),
+        (name = "metadata", description = "Cached database schemas"),
+        (name = "metrics", description = "Semantic metrics definitions compiled to SQL"),
+        (name = "queries", description = "SQL, natural language and cross-database queries"),
+        (name = "sql", description = "SQL formatting and linting"),
+        (name = "query-jobs", description = "Asynchronous queries with polling and cancellation"),
+        (name = "saved-queries", description = "Saved queries of a domain"),
+        (name = "favorites", description = "Per-user favorites and recently run queries"),
+        (name = "history", description = "Query history, replay and archive"),
+        (name = "trash", description = "Soft-deleted resources"),
+        (name = "admin", description = "API keys, users and connection policies"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Bearer token scheme (login JWT or API key)
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Login JWT or API key"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Document the common error body as the default response of every operation
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let error = ResponseBuilder::new()
+            .description("Error; branch on `error.code`")
+            .content(
+                "application/json",
+                utoipa::openapi::ContentBuilder::new()
+                    .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name("ErrorResponse"))))
+                    .build(),
+            )
+            .build();
+
+        for path_item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut path_item.get,
+                &mut path_item.put,
+                &mut path_item.post,
+                &mut path_item.delete,
+                &mut path_item.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| RefOr::T(error.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_document_covers_api() {
+        let doc = ApiDoc::openapi();
+        let json = serde_json::to_value(&doc).unwrap();
+
+        for path in [
+            "/api/connections",
+            "/api/connections/{id}/query",
+            "/api/domains/{domain_id}/queries/history",
+            "/api/admin/policies/{id}",
+            "/api/query-jobs/{id}",
+        ] {
+            assert!(json["paths"].get(path).is_some(), "missing {}", path);
+        }
+
+        let list = &json["paths"]["/api/connections"]["get"];
+        let params: Vec<&str> = list["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert!(params.contains(&"page_size") && params.contains(&"labels"));
+        assert_eq!(
+            list["responses"]["default"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ErrorResponse"
+        );
+        assert!(json["components"]["schemas"].get("ErrorCode").is_some());
+        assert!(json["components"]["securitySchemes"].get("bearer_auth").is_some());
+    }
+}
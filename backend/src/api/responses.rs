@@ -0,0 +1,467 @@
+// API Response Bodies
+//
+// Typed bodies of successful responses, so every endpoint has a fixed shape.
+// Errors use `middleware::ErrorResponse` (`{"error": {"code", "message",
+// "details", "request_id"}}`). List endpoints flatten `PageInfo` (`total`,
+// `page`, `page_size`) next to their items.
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::{
+    ApiKey, ApiKeyScope, CacheStatus, ChartSpec, ChatSession, ChatTurn, ColumnarResult, Completion, CompletionContext, ConnectionPolicy, ConnectionTemplate, CrossDatabaseQueryResponse, DatabaseConnection, DatabaseMetadata, DictionaryEntry,
+    DomainResponse, Favorite, HistoryDailyStats, LintIssue, LlmAuditEntry, MetadataDiff, OptimizationSuggestion, PageInfo, Principal, Query, QueryHistory, QueryJob, QueryShare, RecentQuery, QueryPlan, RunningQuery, SavedQuery, SqlFeedback, TableProfile, TransactionSession,
+    TrashItem, TrashResourceType, User, WriteResult,
+};
+use crate::services::database::WriteAccess;
+use crate::services::PoolStats;
+
+/// `GET /api/connections`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectionListResponse {
+    pub connections: Vec<DatabaseConnection>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// `POST /api/connections`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateConnectionResponse {
+    pub connection: DatabaseConnection,
+    pub metadata: DatabaseMetadata,
+    /// Result of `verify_read_only`, if requested
+    pub read_only_check: Option<WriteAccess>,
+    pub warnings: Vec<String>,
+}
+
+/// `GET /api/domains`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DomainListResponse {
+    pub domains: Vec<DomainResponse>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// Single domain (`GET`/`POST`/`PUT /api/domains...`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DomainEnvelope {
+    pub domain: DomainResponse,
+}
+
+/// `GET /api/domains/{id}/connections`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DomainConnectionsResponse {
+    pub connections: Vec<DatabaseConnection>,
+}
+
+/// `GET /api/domains/{domain_id}/queries/saved`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedQueryListResponse {
+    pub saved_queries: Vec<SavedQuery>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// `GET /api/domains/{domain_id}/queries/history?aggregate=daily` and
+/// per-connection history
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryHistoryDailyResponse {
+    pub days: Vec<HistoryDailyStats>,
+}
+
+/// `GET /api/domains/{domain_id}/queries/history` and per-connection history
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryHistoryListResponse {
+    pub history: Vec<QueryHistory>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// Result of a single-connection SQL or natural language query
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryResponse {
+    pub query: Query,
+    /// SQL produced by the LLM (natural language queries only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_sql: Option<String>,
+    /// Whether `generated_sql` was cached for the same question instead of
+    /// generated (natural language queries only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_cache: Option<CacheStatus>,
+    /// Results by column (`result_format=columnar`; `query.results` is then null)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columnar: Option<ColumnarResult>,
+    /// Suggested chart of the result (absent when no chart fits)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chart: Option<ChartSpec>,
+}
+
+/// `POST /api/connections/{id}/chat`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatResponse {
+    pub session: ChatSession,
+    /// The turn just added (its `generated_sql` produced `query`)
+    pub turn: ChatTurn,
+    pub query: Query,
+}
+
+/// `GET /api/chat-sessions/{id}`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatSessionResponse {
+    pub session: ChatSession,
+    /// Oldest first
+    pub turns: Vec<ChatTurn>,
+}
+
+/// `GET /api/domains/{id}/llm-audit`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LlmAuditListResponse {
+    pub entries: Vec<LlmAuditEntry>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// `DELETE /api/chat-sessions/{id}`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChatSessionDeletedResponse {
+    pub message: String,
+    pub session_id: String,
+}
+
+/// `GET /api/domains/{id}/nl-feedback`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SqlFeedbackListResponse {
+    pub feedback: Vec<SqlFeedback>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// `DELETE /api/nl-feedback/{id}`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SqlFeedbackDeletedResponse {
+    pub message: String,
+    pub feedback_id: String,
+}
+
+/// Asynchronous query job (`/api/query-jobs/{id}`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryJobResponse {
+    pub job: QueryJob,
+}
+
+/// `POST /api/connections/{id}/query/explain`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExplainResponse {
+    /// The query as submitted
+    pub query: String,
+    /// Dialect translation of a unified (DataFusion) query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translated_sql: Option<String>,
+    /// Largest row estimate in the plan
+    pub estimated_rows: Option<f64>,
+    pub plan: QueryPlan,
+}
+
+/// `POST /api/connections/{id}/query/advise`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdviseResponse {
+    /// The query as submitted
+    pub query: String,
+    /// Dialect translation of a unified (DataFusion) query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translated_sql: Option<String>,
+    /// Largest row estimate in the plan
+    pub estimated_rows: Option<f64>,
+    pub plan: QueryPlan,
+    /// LLM suggestions first, then heuristic ones
+    pub suggestions: Vec<OptimizationSuggestion>,
+    /// Why the suggestions are heuristic only (no LLM gateway, or the LLM failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_error: Option<String>,
+}
+
+/// `POST /api/sql/format`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FormattedSqlResponse {
+    pub sql: String,
+}
+
+/// `POST /api/sql/lint`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LintSqlResponse {
+    pub issues: Vec<LintIssue>,
+}
+
+/// `POST /api/sql/translate`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TranslatedSqlResponse {
+    /// Target dialect name
+    pub dialect: String,
+    /// The query in the target dialect (not executed)
+    pub translated_sql: String,
+    /// Rewrites applied, in query order
+    pub rewrites: Vec<SqlRewrite>,
+}
+
+/// `DELETE /api/connections/{id}/cache`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryCacheInvalidatedResponse {
+    pub connection_id: String,
+    /// Cached results removed
+    pub removed: usize,
+}
+
+/// `POST /api/queries/{id}/cancel`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CancelledQueryResponse {
+    pub cancelled: RunningQuery,
+}
+
+/// Transaction session (`/api/sessions/{id}`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionSessionResponse {
+    pub session: TransactionSession,
+}
+
+/// `POST /api/sessions/{id}/execute`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionStatementResponse {
+    pub session: TransactionSession,
+    pub result: WriteResult,
+}
+
+/// `POST /api/cross-database/query`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CrossDatabaseQueryEnvelope {
+    pub query: CrossDatabaseQueryResponse,
+}
+
+/// `POST /api/domains/{id}/nl-query`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CrossDatabaseNaturalLanguageResponse {
+    /// SQL produced by the LLM, over `alias.schema.table` tables
+    pub generated_sql: String,
+    pub query: CrossDatabaseQueryResponse,
+}
+
+/// `POST /api/domains/{domain_id}/queries/history/archive`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArchiveQueryResponse {
+    pub rows: Vec<serde_json::Value>,
+    pub row_count: usize,
+    pub execution_time_ms: u64,
+}
+
+/// `GET /api/connections/{id}/metadata`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetadataResponse {
+    pub metadata: DatabaseMetadata,
+    /// Whether the metadata came from the cache rather than the database
+    pub cached: bool,
+}
+
+/// `GET /api/connections/{id}/dictionary`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DictionaryListResponse {
+    pub entries: Vec<DictionaryEntry>,
+}
+
+/// `DELETE /api/connections/{id}/dictionary`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DictionaryEntryDeletedResponse {
+    pub message: String,
+}
+
+/// `POST /api/connections/{id}/metadata/refresh`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetadataRefreshResponse {
+    pub metadata: DatabaseMetadata,
+    /// Whether tables were added, removed or changed since the previous version
+    pub changed: bool,
+    /// Changes since the previous version, null on the first read
+    pub diff: Option<MetadataDiff>,
+}
+
+/// `POST /api/connections/{id}/autocomplete`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AutocompleteResponse {
+    pub context: CompletionContext,
+    /// Partial identifier before the cursor that suggestions replace
+    pub prefix: String,
+    pub suggestions: Vec<Completion>,
+    /// Whether schema metadata was cached; fetch `/metadata` to enable table and column suggestions
+    pub schema_cached: bool,
+}
+
+/// `GET /api/connections/{id}/tables/{schema}/{table}/sample`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TableSampleResponse {
+    pub schema: String,
+    pub table: String,
+    pub rows: Vec<serde_json::Value>,
+    pub row_count: usize,
+    pub execution_time_ms: u64,
+}
+
+/// `GET /api/connections/{id}/tables/{schema}/{table}/count`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TableCountResponse {
+    pub schema: String,
+    pub table: String,
+    /// Exact row count (`COUNT(*)`)
+    pub row_count: u64,
+}
+
+/// `GET /api/connections/templates`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectionTemplateListResponse {
+    pub templates: Vec<ConnectionTemplate>,
+}
+
+/// `POST /api/connections/url`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectionUrlResponse {
+    pub connection_url: String,
+    /// The URL with its password masked, for display
+    pub redacted_url: String,
+}
+
+/// `GET /api/connections/{id}/pool-stats`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolStatsResponse {
+    pub connection_id: String,
+    pub database_type: String,
+    #[serde(flatten)]
+    pub stats: PoolStats,
+}
+
+/// `POST /api/connections/{id}/tables/{table}/profile`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TableProfileResponse {
+    #[serde(flatten)]
+    pub profile: TableProfile,
+    /// Whether the profile was computed by an earlier request
+    pub cached: bool,
+}
+
+/// `DELETE /api/domains/{domain_id}/queries/saved/{query_id}`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedQueryDeletedResponse {
+    pub message: String,
+    pub query_id: String,
+}
+
+/// `DELETE /api/domains/{domain_id}/queries/saved/{query_id}/materialization`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ViewDematerializedResponse {
+    pub message: String,
+    pub query_id: String,
+}
+
+/// `POST /api/trash/{resource_type}/{id}/restore`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashRestoredResponse {
+    pub message: String,
+    pub resource_type: TrashResourceType,
+    pub id: String,
+}
+
+/// `GET /api/trash`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashListResponse {
+    pub items: Vec<TrashItem>,
+    pub grace_period_days: u32,
+}
+
+/// `GET /api/admin/api-keys`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyListResponse {
+    pub api_keys: Vec<ApiKey>,
+}
+
+/// `GET /api/admin/users`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserListResponse {
+    pub users: Vec<User>,
+}
+
+/// `GET /api/admin/policies`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PolicyListResponse {
+    pub policies: Vec<ConnectionPolicy>,
+}
+
+/// `GET /api/domains/{domain_id}/queries/saved/{query_id}/shares`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryShareListResponse {
+    pub shares: Vec<QueryShare>,
+}
+
+/// `GET /api/domains/{domain_id}/favorites`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FavoriteListResponse {
+    pub favorites: Vec<Favorite>,
+}
+
+/// `GET /api/domains/{domain_id}/recent`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecentQueryListResponse {
+    pub recent: Vec<RecentQuery>,
+}
+
+/// `GET /api/auth/me`
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct WhoAmIResponse {
+    pub authenticated: bool,
+    /// `user` or `api_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub kind: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<ApiKeyScope>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_admin: Option<bool>,
+}
+
+impl WhoAmIResponse {
+    pub fn from_principal(principal: Option<&Principal>) -> Self {
+        let Some(principal) = principal else {
+            return Self::default();
+        };
+
+        let (kind, id, scope) = match principal {
+            Principal::User { id, .. } => ("user", id.clone(), None),
+            Principal::ApiKey { id, scope, .. } => ("api_key", id.clone(), Some(*scope)),
+        };
+        Self {
+            authenticated: true,
+            kind: Some(kind),
+            id: Some(id),
+            name: Some(principal.name().to_string()),
+            scope,
+            is_admin: Some(principal.is_admin()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_response_flattens_page_info() {
+        let body = serde_json::to_value(QueryHistoryListResponse {
+            history: Vec::new(),
+            page: PageInfo { total: 120, page: 2, page_size: 50 },
+        })
+        .unwrap();
+        assert_eq!(body, serde_json::json!({"history": [], "total": 120, "page": 2, "page_size": 50}));
+
+        let anonymous = serde_json::to_value(WhoAmIResponse::from_principal(None)).unwrap();
+        assert_eq!(anonymous, serde_json::json!({"authenticated": false}));
+    }
+}
@@ -1,15 +1,25 @@
 use axum::{
-    routing::{get, post},
+    extract::{DefaultBodyLimit, State},
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::cors::CorsLayer;
 use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
-use crate::api::handlers::{connection, domain, metadata, query, cross_database_query};
+use crate::api::handlers::{admin, auth, chat, connection, data_dictionary, domain, favorite, metadata, metrics, query, query_job, query_session, sql, sql_feedback, cross_database_query, result_contract, share, transaction, trash};
 use crate::api::handlers::connection::AppState;
+use crate::api::middleware::{limit_body_size, request_id, require_auth, track_api_usage};
+use crate::api::openapi::{ApiDoc, OPENAPI_PATH, SWAGGER_UI_PATH};
 use crate::storage::SqliteStorage;
 use crate::config::Config;
-use crate::services::ConnectionPoolManager;
+use crate::services::datafusion::{DataFusionSessionManager, DataFusionSessionPool, ScalarUdfRegistry};
+use crate::services::{
+    ConnectionPoolManager, QueryJobRegistry, QueryResultCache, RunningQueryRegistry, SchemaIndexCache,
+    MetadataRefreshService, SqlGenerationCache, SubQueryResultCache, TransactionRegistry, ViewMaterializationService,
+};
 
 /// Create the main application router (deprecated - use create_router_with_state)
 /// This is kept for backward compatibility but requires state to work properly
@@ -22,21 +32,219 @@ pub fn create_router() -> Router<()> {
 /// Create router with application state
 pub fn create_router_with_state(storage: Arc<SqliteStorage>, config: Config) -> Router {
     // Initialize connection pool manager
-    let pool_manager = Arc::new(ConnectionPoolManager::new());
+    let pool_manager = Arc::new(ConnectionPoolManager::from_config(&config.pool));
+    // Pool settings and read-only sessions of the stored connections apply
+    // from their first query
+    {
+        let pool_manager = pool_manager.clone();
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            match storage.list_connections().await {
+                Ok(connections) => {
+                    for connection in &connections {
+                        pool_manager
+                            .configure(&connection.connection_url, &connection.pool_settings, connection.read_only)
+                            .await;
+                    }
+                }
+                Err(e) => tracing::error!("Failed to load connection pool settings: {}", e),
+            }
+        });
+    }
+    let limits = config.limits.clone();
+    let transactions = Arc::new(TransactionRegistry::new(config.transactions.idle_timeout_secs));
+    transactions.clone().spawn_reaper();
 
+    let query_cache = Arc::new(QueryResultCache::from_config(&config.query_cache, storage.clone()));
+    let subquery_cache = Arc::new(SubQueryResultCache::from_config(&config.cross_database));
+    let udfs = Arc::new(ScalarUdfRegistry::from_config(&config.datafusion).unwrap_or_else(|e| {
+        tracing::warn!("DataFusion UDFs disabled: {:#}", e);
+        ScalarUdfRegistry::new()
+    }));
+    let sessions = Arc::new(DataFusionSessionPool::from_config(&config.datafusion, udfs.clone()).unwrap_or_else(|e| {
+        tracing::warn!("DataFusion memory limit disabled: {:#}", e);
+        DataFusionSessionPool::new(
+            DataFusionSessionManager::default_config().with_udfs(Some(udfs)),
+            None,
+            config.datafusion.session_pool_size,
+        )
+        .expect("default DataFusion runtime")
+    }));
+    let view_materializations = Arc::new(
+        ViewMaterializationService::new(
+            storage.clone(),
+            pool_manager.clone(),
+            subquery_cache.clone(),
+            config.materialization.clone(),
+        )
+        .with_sessions(sessions.clone()),
+    );
+    view_materializations.clone().spawn_scheduler();
+    let generation_cache = Arc::new(SqlGenerationCache::from_config(&config.llm));
+    let metadata_refresh = Arc::new(MetadataRefreshService::new(
+        storage.clone(),
+        pool_manager.clone(),
+        query_cache.clone(),
+        subquery_cache.clone(),
+        &config,
+    ));
+    metadata_refresh.clone().spawn_scheduler();
     let state = AppState {
         storage,
         config,
         pool_manager,
+        query_jobs: Arc::new(QueryJobRegistry::new()),
+        transactions,
+        running_queries: Arc::new(RunningQueryRegistry::new()),
+        query_cache,
+        subquery_cache,
+        sessions,
+        view_materializations,
+        schema_indexes: Arc::new(SchemaIndexCache::new()),
+        generation_cache,
+        metadata_refresh,
     };
 
-    Router::new()
-        .route("/health", get(health_check))
+    // Routes that accept SQL or questions get their own (usually tighter) body limit
+    let query_routes = Router::new()
+        .route(
+            "/api/connections/{id}/query",
+            post(query::execute_query),
+        )
+        .route(
+            "/api/connections/{id}/query/export",
+            post(query::export_query),
+        )
+        .route(
+            "/api/connections/{id}/query/explain",
+            post(query::explain_query),
+        )
+        .route(
+            "/api/connections/{id}/query/advise",
+            post(query::advise_query),
+        )
+        .route(
+            "/api/connections/{id}/query/summarize",
+            post(query::summarize_result),
+        )
+        .route(
+            "/api/connections/{id}/execute",
+            post(query::execute_write),
+        )
+        .route(
+            "/api/sessions/{id}/execute",
+            post(transaction::execute_in_transaction),
+        )
+        .route(
+            "/api/connections/{id}/nl-query",
+            post(query::execute_natural_language_query),
+        )
+        .route(
+            "/api/connections/{id}/nl-query/stream",
+            post(query::stream_natural_language_query),
+        )
+        .route(
+            "/api/connections/{id}/chat",
+            post(chat::chat),
+        )
+        .route(
+            "/api/chat-sessions/{id}",
+            get(chat::get_chat_session).delete(chat::delete_chat_session),
+        )
+        .route(
+            "/api/connections/{id}/nl-feedback",
+            post(sql_feedback::submit_sql_feedback),
+        )
+        .route(
+            "/api/nl-feedback/{id}",
+            delete(sql_feedback::delete_sql_feedback),
+        )
+        .route(
+            "/api/connections/{id}/unified-query",
+            post(query::execute_unified_query),
+        )
+        .route(
+            "/api/connections/{id}/queries/async",
+            post(query_job::submit_query_job),
+        )
+        .route(
+            "/api/connections/{id}/autocomplete",
+            post(metadata::autocomplete),
+        )
+        .route("/api/sql/format", post(sql::format_sql))
+        .route("/api/sql/lint", post(sql::lint_sql))
+        .route("/api/sql/translate", post(sql::translate_sql))
+        .route(
+            "/api/cross-database/query",
+            post(cross_database_query::execute_cross_database_query),
+        )
+        .route(
+            "/api/cross-database/insert-select",
+            post(cross_database_query::submit_cross_database_insert),
+        )
+        .route(
+            "/api/cross-database/catalog",
+            get(cross_database_query::get_federated_catalog),
+        )
+        // Saved query routes (domain-scoped)
+        .route(
+            "/api/domains/{domain_id}/queries/saved",
+            get(query::list_saved_queries).post(query::create_saved_query),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}",
+            get(query::get_saved_query)
+                .put(query::update_saved_query)
+                .delete(query::delete_saved_query),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/contract",
+            get(result_contract::get_result_contract)
+                .put(result_contract::set_result_contract)
+                .delete(result_contract::delete_result_contract),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/execute",
+            post(query::execute_saved_query),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/shares",
+            get(share::list_query_shares).post(share::create_query_share),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/shares/{share_id}",
+            delete(share::revoke_query_share),
+        )
+        .route("/api/share/{token}", get(share::get_shared_result))
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/materialization",
+            get(query::get_view_materialization)
+                .put(query::materialize_view)
+                .delete(query::dematerialize_view),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/saved/{query_id}/materialization/refresh",
+            post(query::refresh_view_materialization),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/history/archive",
+            post(query::query_history_archive),
+        )
+        .layer(from_fn_with_state(limits.max_query_body_bytes, limit_body_size))
+        .layer(body_limit(limits.max_query_body_bytes));
+
+    let api_routes = Router::new()
+        .route("/health", get(health_status))
+        // Auth routes
+        .route("/api/auth/login", post(auth::login))
+        .route("/api/auth/me", get(auth::me))
         // Domain routes
         .route(
             "/api/domains",
             get(domain::list_domains).post(domain::create_domain),
         )
+        .route("/api/domains/import", post(domain::import_domain))
+        .route("/api/domains/{id}/connections/import", post(domain::import_connections))
         .route(
             "/api/domains/{id}",
             get(domain::get_domain)
@@ -47,6 +255,86 @@ pub fn create_router_with_state(storage: Arc<SqliteStorage>, config: Config) ->
             "/api/domains/{id}/connections",
             get(domain::list_domain_connections),
         )
+        .route(
+            "/api/domains/{id}/usage",
+            get(domain::get_domain_usage),
+        )
+        .route(
+            "/api/domains/{id}/stats",
+            get(domain::get_domain_stats),
+        )
+        .route(
+            "/api/domains/{id}/slow-queries",
+            get(domain::get_domain_slow_queries),
+        )
+        .route(
+            "/api/domains/{domain_id}/favorites",
+            get(favorite::list_favorites),
+        )
+        .route(
+            "/api/domains/{domain_id}/favorites/{resource_type}/{resource_id}",
+            put(favorite::add_favorite).delete(favorite::remove_favorite),
+        )
+        .route(
+            "/api/domains/{domain_id}/recent",
+            get(favorite::list_recent_queries),
+        )
+        .route(
+            "/api/domains/{id}/reports",
+            get(domain::get_domain_reports),
+        )
+        .route(
+            "/api/domains/{id}/llm-usage",
+            get(domain::get_domain_llm_usage),
+        )
+        .route(
+            "/api/domains/{id}/llm-audit",
+            get(domain::list_domain_llm_audit),
+        )
+        .route(
+            "/api/domains/{id}/settings",
+            get(domain::get_domain_settings).put(domain::update_domain_settings),
+        )
+        .route(
+            "/api/domains/{id}/nl-query",
+            post(cross_database_query::execute_cross_database_natural_language_query),
+        )
+        .route(
+            "/api/domains/{id}/nl-feedback",
+            get(sql_feedback::list_sql_feedback),
+        )
+        .route(
+            "/api/domains/{id}/metrics",
+            get(metrics::get_domain_metrics).put(metrics::update_domain_metrics),
+        )
+        .route(
+            "/api/domains/{id}/metrics/compile",
+            post(metrics::compile_metric),
+        )
+        .route(
+            "/api/domains/{id}/export",
+            get(domain::export_domain),
+        )
+        .route(
+            "/api/domains/{id}/roles",
+            get(domain::list_domain_roles).put(domain::assign_domain_role),
+        )
+        .route(
+            "/api/domains/{id}/roles/{user_id}",
+            delete(domain::remove_domain_role),
+        )
+        .route(
+            "/api/domains/{id}/webhooks",
+            get(domain::list_webhooks).post(domain::create_webhook),
+        )
+        .route(
+            "/api/domains/{id}/webhooks/{webhook_id}",
+            delete(domain::delete_webhook),
+        )
+        .route(
+            "/api/domains/{id}/webhooks/{webhook_id}/deliveries",
+            get(domain::list_webhook_deliveries),
+        )
         // Connection routes
         .route(
             "/api/connections",
@@ -54,38 +342,93 @@ pub fn create_router_with_state(storage: Arc<SqliteStorage>, config: Config) ->
         )
         .route(
             "/api/connections/{id}",
-            get(connection::get_connection).delete(connection::delete_connection),
+            get(connection::get_connection)
+                .put(connection::update_connection)
+                .delete(connection::delete_connection),
         )
+        .route(
+            "/api/connections/templates",
+            get(connection::list_connection_templates),
+        )
+        .route(
+            "/api/connections/url",
+            post(connection::build_url),
+        )
+        .route(
+            "/api/connections/{id}/duplicate",
+            post(connection::duplicate_connection),
+        )
+        .route(
+            "/api/connections/{id}/move",
+            post(connection::move_connection),
+        )
+        .route(
+            "/api/connections/{id}/statement-cache",
+            get(connection::get_statement_cache_stats),
+        )
+        .route(
+            "/api/connections/{id}/pool-stats",
+            get(connection::get_pool_stats),
+        )
+        .route(
+            "/api/connections/{id}/cache",
+            delete(connection::invalidate_query_cache),
+        )
+        .route(
+            "/api/connections/{id}/sessions",
+            post(transaction::open_transaction),
+        )
+        .route("/api/queries/{id}/cancel", post(query::cancel_query))
+        .route("/api/sessions/{id}", get(transaction::get_transaction))
+        .route("/api/sessions/{id}/commit", post(transaction::commit_transaction))
+        .route("/api/sessions/{id}/rollback", post(transaction::rollback_transaction))
         .route(
             "/api/connections/{id}/metadata",
             get(metadata::get_metadata),
         )
         .route(
-            "/api/connections/{id}/query",
-            post(query::execute_query),
+            "/api/connections/{id}/metadata/refresh",
+            post(metadata::refresh_metadata),
         )
         .route(
-            "/api/connections/{id}/nl-query",
-            post(query::execute_natural_language_query),
+            "/api/connections/{id}/dictionary",
+            get(data_dictionary::list_dictionary)
+                .put(data_dictionary::upsert_dictionary_entry)
+                .delete(data_dictionary::delete_dictionary_entry),
         )
         .route(
-            "/api/connections/{id}/unified-query",
-            post(query::execute_unified_query),
+            "/api/connections/{id}/metadata/versions",
+            get(metadata::list_metadata_versions),
         )
         .route(
-            "/api/cross-database/query",
-            post(cross_database_query::execute_cross_database_query),
+            "/api/connections/{id}/metadata/diff",
+            get(metadata::get_metadata_diff),
         )
-        // Saved query routes (domain-scoped)
         .route(
-            "/api/domains/{domain_id}/queries/saved",
-            get(query::list_saved_queries).post(query::create_saved_query),
+            "/api/connections/{id}/tables/{schema}/{table}/sample",
+            get(metadata::sample_table),
         )
         .route(
-            "/api/domains/{domain_id}/queries/saved/{query_id}",
-            get(query::get_saved_query)
-                .put(query::update_saved_query)
-                .delete(query::delete_saved_query),
+            "/api/connections/{id}/tables/{schema}/{table}/count",
+            get(metadata::count_table),
+        )
+        .route(
+            "/api/connections/{id}/tables/{table}/profile",
+            post(metadata::profile_table),
+        )
+        // Asynchronous query jobs
+        .route(
+            "/api/query-jobs/{id}",
+            get(query_job::get_query_job).delete(query_job::cancel_query_job),
+        )
+        .route(
+            "/api/query-jobs/{id}/stream",
+            get(query_job::stream_query_job),
+        )
+        // Interactive query sessions (WebSocket)
+        .route(
+            query_session::QUERY_SESSION_PATH,
+            get(query_session::open_query_session),
         )
         // Query history routes (domain-scoped)
         .route(
@@ -96,13 +439,95 @@ pub fn create_router_with_state(storage: Arc<SqliteStorage>, config: Config) ->
             "/api/domains/{domain_id}/connections/{connection_id}/history",
             get(query::list_connection_query_history),
         )
+        .route(
+            "/api/domains/{domain_id}/queries/history/{history_id}/replay",
+            post(query::replay_query_history),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/history/{history_id}/rerun",
+            post(query::rerun_query_history),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/history/{history_id}/promote",
+            post(query::promote_query_history),
+        )
+        .route(
+            "/api/domains/{domain_id}/queries/history/{history_id}/result",
+            get(query::get_query_history_result),
+        )
+        .route(
+            "/api/queries/history/prune",
+            post(query::prune_query_history),
+        )
+        // Trash routes (soft-deleted resources)
+        .route("/api/trash", get(trash::list_trash))
+        .route(
+            "/api/trash/{resource_type}/{id}/restore",
+            post(trash::restore_trash_item),
+        )
+        .route(
+            "/api/trash/{resource_type}/{id}",
+            delete(trash::purge_trash_item),
+        )
+        // Admin routes (admin scope)
+        .route(
+            "/api/admin/api-keys",
+            get(admin::list_api_keys).post(admin::create_api_key),
+        )
+        .route(
+            "/api/admin/api-keys/{id}/revoke",
+            post(admin::revoke_api_key),
+        )
+        .route(
+            "/api/admin/users",
+            get(admin::list_users).post(admin::create_user),
+        )
+        .route(
+            "/api/admin/policies",
+            get(admin::list_policies).post(admin::create_policy),
+        )
+        .route(
+            "/api/admin/policies/{id}",
+            delete(admin::delete_policy),
+        )
+        .layer(from_fn_with_state(limits.max_body_bytes, limit_body_size))
+        .layer(body_limit(limits.max_body_bytes));
+
+    Router::new()
+        .merge(api_routes)
+        .merge(query_routes)
+        .merge(SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_PATH, ApiDoc::openapi()))
+        .layer(from_fn_with_state(state.clone(), track_api_usage))
+        .layer(from_fn_with_state(state.clone(), require_auth))
+        .layer(from_fn(request_id))
         .layer(CorsLayer::permissive())
         .with_state(state)
 }
 
+/// Body limit for extractors (covers chunked bodies without Content-Length)
+fn body_limit(max_bytes: usize) -> DefaultBodyLimit {
+    if max_bytes == 0 {
+        DefaultBodyLimit::disable()
+    } else {
+        DefaultBodyLimit::max(max_bytes)
+    }
+}
+
 /// Health check endpoint
 async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Health check: `OK`, or `DEGRADED` while any connection's circuit breaker is not closed
+///
+/// Needs no authentication, so it says nothing about which connections are
+/// affected; `GET /api/connections/{id}/pool-stats` gives each one's circuit.
+async fn health_status(State(state): State<AppState>) -> &'static str {
+    if state.pool_manager.open_circuits().await.is_empty() {
+        "OK"
+    } else {
+        "DEGRADED"
+    }
+}
+
 
@@ -1,12 +1,30 @@
 use serde::Deserialize;
 use std::env;
 
+/// Column name fragments that mark personal data by default
+pub const DEFAULT_REDACTED_COLUMN_PATTERNS: &str =
+    "email,phone,mobile,ssn,passport,password,secret,token,credit_card,card_number,iban,address,birth,salary,tax_id,national_id";
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
     pub llm: LlmConfig,
     pub logging: LoggingConfig,
+    pub history: HistoryConfig,
+    pub trash: TrashConfig,
+    pub limits: LimitsConfig,
+    pub metadata: MetadataConfig,
+    pub auth: AuthConfig,
+    pub audit: AuditConfig,
+    pub jobs: JobsConfig,
+    pub pool: PoolConfig,
+    pub transactions: TransactionsConfig,
+    pub webhooks: WebhooksConfig,
+    pub query_cache: QueryCacheConfig,
+    pub cross_database: CrossDatabaseConfig,
+    pub materialization: MaterializationConfig,
+    pub datafusion: DataFusionConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -24,12 +42,219 @@ pub struct ServerConfig {
 pub struct LlmConfig {
     pub gateway_url: String,
     pub api_key: Option<String>,
+    /// OpenAI-compatible embeddings endpoint (unset uses the local hashing model)
+    pub embedding_url: Option<String>,
+    /// Model sent to the embeddings endpoint
+    pub embedding_model: Option<String>,
+    /// Tables above which only the most relevant ones go into the prompt (0 disables retrieval)
+    pub schema_retrieval_threshold: usize,
+    /// Tables retrieved for a question
+    pub schema_top_k: usize,
+    /// Times failing generated SQL is sent back to the LLM with the error (0 disables repair)
+    pub max_repair_attempts: u32,
+    /// Rated or corrected past questions of the domain given to the LLM as examples (0 disables)
+    pub few_shot_examples: usize,
+    /// Tokens a domain may spend on natural language queries per month (0 is unlimited)
+    pub monthly_token_budget: u64,
+    /// Natural language queries over budget: "reject" or "degrade" (smaller prompts)
+    pub budget_exceeded_action: String,
+    /// Tables retrieved for a question once a domain is over budget and degraded
+    pub degraded_schema_top_k: usize,
+    /// Lifetime of SQL generated for a question, reused when it is asked again (0 disables)
+    pub generation_cache_ttl_secs: u64,
+    /// Generated SQL entries kept (oldest are evicted)
+    pub generation_cache_max_entries: usize,
+    /// Comma-separated fragments of column names whose values never reach the LLM
+    /// (empty disables the heuristic; domain deny-lists still apply)
+    pub redacted_column_patterns: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct LoggingConfig {
     pub level: String,
     pub style: String,
+    /// Output format: "text" or "json"
+    pub format: String,
+    /// Replace literal values in logged SQL with placeholders
+    pub mask_sql_literals: bool,
+}
+
+/// Query history retention and cold-storage archival settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryConfig {
+    /// Rows older than this many days are pruned (0 disables pruning)
+    pub retention_days: u32,
+    /// Archive pruned rows to Parquet instead of discarding them
+    pub archive_enabled: bool,
+    /// Root directory for month-partitioned Parquet archives
+    pub archive_dir: String,
+    /// Interval between background prune runs
+    pub prune_interval_secs: u64,
+    /// Rows kept with a history entry when a query asks to save its result
+    pub result_max_rows: usize,
+    /// Largest Parquet result kept with a history entry
+    pub result_max_bytes: u64,
+}
+
+/// Soft-delete trash settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashConfig {
+    /// Days a trashed item is kept before being purged (0 keeps items forever)
+    pub grace_period_days: u32,
+    /// Interval between background purge runs
+    pub purge_interval_secs: u64,
+}
+
+/// Request size limits (0 disables a limit)
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    /// Maximum request body size for general API routes
+    pub max_body_bytes: usize,
+    /// Maximum request body size for query execution routes
+    pub max_query_body_bytes: usize,
+    /// Maximum length of SQL text accepted for execution or saving
+    pub max_sql_length: usize,
+    /// Maximum length of a natural language question
+    pub max_question_length: usize,
+    /// Estimated rows above which queries without LIMIT need `confirm_large_result`
+    pub large_result_threshold: u64,
+    /// Row cap of result exports, which are not bound by the domain row limit (cannot be disabled)
+    pub max_export_rows: u64,
+}
+
+/// Metadata cache settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetadataConfig {
+    /// Metadata versions kept per connection for diffing (0 keeps all)
+    pub retained_versions: usize,
+    /// Age after which a connection's metadata is re-read in the background
+    /// (0 disables scheduled refresh; domain settings may override it)
+    pub refresh_interval_secs: u64,
+    /// How often the scheduler looks for connections due for a refresh
+    pub refresh_check_interval_secs: u64,
+}
+
+/// API key authentication settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// Require `Authorization: Bearer <api key>` on every API route
+    pub enabled: bool,
+    /// Admin key accepted in addition to stored keys (used to issue the first keys)
+    pub bootstrap_key: Option<String>,
+    /// HMAC secret for signing login JWTs (login is disabled without it)
+    pub jwt_secret: Option<String>,
+    /// Lifetime of login JWTs
+    pub jwt_ttl_secs: u64,
+}
+
+/// Audit settings for SQL sent to target databases
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditConfig {
+    /// Append `/* db-query request_id=... user=... */` to outgoing SQL
+    pub sql_comment: bool,
+}
+
+/// Asynchronous query job settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobsConfig {
+    /// Timeout of a background query (replaces the domain's request timeout)
+    pub timeout_secs: u64,
+}
+
+/// Connection pool settings for target databases
+#[derive(Debug, Clone, Deserialize)]
+pub struct PoolConfig {
+    /// Prepared statements cached per pooled PostgreSQL/MySQL connection (0 disables)
+    pub statement_cache_size: usize,
+    /// Default most connections per pool (connections may override the pool settings)
+    pub max_size: usize,
+    /// Default connections kept open while idle
+    pub min_idle: usize,
+    /// Default longest wait for a free pooled connection
+    pub acquire_timeout_secs: u64,
+    /// Default age after which pooled connections are closed (0 = no limit)
+    pub max_lifetime_secs: u64,
+    /// Consecutive checkout failures that open a pool's circuit breaker (0 disables it)
+    pub circuit_failure_threshold: u32,
+    /// How long an open circuit fails fast before probing the database again
+    pub circuit_open_secs: u64,
+}
+
+/// Write-mode transaction session settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionsConfig {
+    /// Idle sessions are rolled back after this many seconds
+    pub idle_timeout_secs: u64,
+}
+
+/// Domain webhook delivery settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhooksConfig {
+    /// Attempts per event before a delivery is marked failed
+    pub max_attempts: u32,
+    /// Timeout of each delivery request
+    pub timeout_secs: u64,
+}
+
+/// Query result cache settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryCacheConfig {
+    /// Serve repeated queries from the cache
+    pub enabled: bool,
+    /// Entries kept in memory (least recently used are evicted)
+    pub max_entries: usize,
+    /// Lifetime of a cached result
+    pub ttl_secs: u64,
+    /// Hits after which an entry is persisted to SQLite and survives restarts (0 never persists)
+    pub persist_after_hits: u64,
+}
+
+/// Federated (cross-database) query planning settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct CrossDatabaseConfig {
+    /// Estimated rows fetched from the databases above which a plan carries a warning (0 disables)
+    pub transfer_warning_rows: u64,
+    /// Largest estimated table whose join keys are sent to the other side as an `IN` filter (0 disables)
+    pub broadcast_max_rows: u64,
+    /// Serve unchanged sub-queries from the sub-query result cache
+    pub subquery_cache_enabled: bool,
+    /// Lifetime of a cached sub-query result
+    pub subquery_cache_ttl_secs: u64,
+    /// Arrow IPC bytes kept in the sub-query result cache (oldest entries are evicted)
+    pub subquery_cache_max_bytes: usize,
+    /// Most rows one cross-database INSERT ... SELECT copies (the source query is limited to it)
+    pub insert_select_max_rows: u64,
+    /// Rows read from the source and inserted into the target per batch
+    pub insert_select_batch_rows: usize,
+}
+
+/// Materialized view snapshot settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaterializationConfig {
+    /// Directory of the Parquet snapshots of materialized views
+    pub dir: String,
+    /// Interval between checks for materializations due for a scheduled refresh
+    pub check_interval_secs: u64,
+    /// Timeout of the query that refreshes a snapshot
+    pub refresh_timeout_secs: u64,
+}
+
+/// DataFusion session settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataFusionConfig {
+    /// Comma-separated built-in scalar UDFs registered into DataFusion sessions
+    /// (`parse_user_agent`, `ip_to_country`)
+    pub udfs: String,
+    /// MaxMind country (or city) MMDB file used by `ip_to_country`
+    pub geoip_mmdb_path: Option<String>,
+    /// Rows per record batch
+    pub batch_size: usize,
+    /// Partitions of parallel execution (0 uses the CPU count)
+    pub target_partitions: usize,
+    /// Memory shared by all sessions, in MiB (0 is unlimited)
+    pub memory_limit_mb: u64,
+    /// Idle sessions kept for reuse
+    pub session_pool_size: usize,
 }
 
 impl Config {
@@ -39,8 +264,70 @@ impl Config {
             .set_default("server.host", "0.0.0.0")?
             .set_default("server.port", 3000)?
             .set_default("llm.gateway_url", "http://localhost:8080")?
+            .set_default("llm.schema_retrieval_threshold", 50)?
+            .set_default("llm.schema_top_k", 15)?
+            .set_default("llm.max_repair_attempts", 2)?
+            .set_default("llm.few_shot_examples", 3)?
+            .set_default("llm.monthly_token_budget", 0)?
+            .set_default("llm.budget_exceeded_action", "reject")?
+            .set_default("llm.degraded_schema_top_k", 5)?
+            .set_default("llm.generation_cache_ttl_secs", 3600)?
+            .set_default("llm.generation_cache_max_entries", 1000)?
+            .set_default("llm.redacted_column_patterns", DEFAULT_REDACTED_COLUMN_PATTERNS)?
             .set_default("logging.level", "info")?
-            .set_default("logging.style", "auto")?;
+            .set_default("logging.style", "auto")?
+            .set_default("logging.format", "text")?
+            .set_default("logging.mask_sql_literals", false)?
+            .set_default("history.retention_days", 0)?
+            .set_default("history.archive_enabled", false)?
+            .set_default("history.archive_dir", "./history_archive")?
+            .set_default("history.prune_interval_secs", 3600)?
+            .set_default("history.result_max_rows", 100)?
+            .set_default("history.result_max_bytes", 10 * 1024 * 1024)?
+            .set_default("trash.grace_period_days", 30)?
+            .set_default("trash.purge_interval_secs", 3600)?
+            .set_default("limits.max_body_bytes", 1024 * 1024)?
+            .set_default("limits.max_query_body_bytes", 512 * 1024)?
+            .set_default("limits.max_sql_length", 100_000)?
+            .set_default("limits.max_question_length", 4_000)?
+            .set_default("limits.large_result_threshold", 1_000_000)?
+            .set_default("limits.max_export_rows", 1_000_000)?
+            .set_default("metadata.retained_versions", 10)?
+            .set_default("metadata.refresh_interval_secs", 0)?
+            .set_default("metadata.refresh_check_interval_secs", 60)?
+            .set_default("auth.enabled", false)?
+            .set_default("auth.jwt_ttl_secs", 8 * 3600)?
+            .set_default("audit.sql_comment", false)?
+            .set_default("jobs.timeout_secs", 3600)?
+            .set_default("pool.statement_cache_size", 64)?
+            .set_default("pool.max_size", 16)?
+            .set_default("pool.min_idle", 2)?
+            .set_default("pool.acquire_timeout_secs", 30)?
+            .set_default("pool.max_lifetime_secs", 1800)?
+            .set_default("pool.circuit_failure_threshold", 5)?
+            .set_default("pool.circuit_open_secs", 30)?
+            .set_default("transactions.idle_timeout_secs", 300)?
+            .set_default("webhooks.max_attempts", 5)?
+            .set_default("webhooks.timeout_secs", 10)?
+            .set_default("query_cache.enabled", false)?
+            .set_default("query_cache.max_entries", 1000)?
+            .set_default("query_cache.ttl_secs", 300)?
+            .set_default("query_cache.persist_after_hits", 2)?
+            .set_default("cross_database.transfer_warning_rows", 1_000_000)?
+            .set_default("cross_database.broadcast_max_rows", 10_000)?
+            .set_default("cross_database.subquery_cache_enabled", false)?
+            .set_default("cross_database.subquery_cache_ttl_secs", 300)?
+            .set_default("cross_database.subquery_cache_max_bytes", 256 * 1024 * 1024)?
+            .set_default("cross_database.insert_select_max_rows", 1_000_000)?
+            .set_default("cross_database.insert_select_batch_rows", 1000)?
+            .set_default("materialization.dir", "./materialized_views")?
+            .set_default("materialization.check_interval_secs", 60)?
+            .set_default("materialization.refresh_timeout_secs", 600)?
+            .set_default("datafusion.udfs", "")?
+            .set_default("datafusion.batch_size", 8192)?
+            .set_default("datafusion.target_partitions", 0)?
+            .set_default("datafusion.memory_limit_mb", 0)?
+            .set_default("datafusion.session_pool_size", 8)?;
 
         // Load from environment variables
         if let Ok(database_url) = env::var("DATABASE_URL") {
@@ -63,6 +350,54 @@ impl Config {
             builder = builder.set_override("llm.api_key", Some(api_key))?;
         }
 
+        if let Ok(url) = env::var("LLM_EMBEDDING_URL") {
+            builder = builder.set_override("llm.embedding_url", Some(url))?;
+        }
+
+        if let Ok(model) = env::var("LLM_EMBEDDING_MODEL") {
+            builder = builder.set_override("llm.embedding_model", Some(model))?;
+        }
+
+        if let Ok(threshold) = env::var("LLM_SCHEMA_RETRIEVAL_THRESHOLD") {
+            builder = builder.set_override("llm.schema_retrieval_threshold", threshold.parse::<u64>().unwrap_or(50))?;
+        }
+
+        if let Ok(top_k) = env::var("LLM_SCHEMA_TOP_K") {
+            builder = builder.set_override("llm.schema_top_k", top_k.parse::<u64>().unwrap_or(15))?;
+        }
+
+        if let Ok(attempts) = env::var("LLM_MAX_REPAIR_ATTEMPTS") {
+            builder = builder.set_override("llm.max_repair_attempts", attempts.parse::<u64>().unwrap_or(2))?;
+        }
+
+        if let Ok(examples) = env::var("LLM_FEW_SHOT_EXAMPLES") {
+            builder = builder.set_override("llm.few_shot_examples", examples.parse::<u64>().unwrap_or(3))?;
+        }
+
+        if let Ok(budget) = env::var("LLM_MONTHLY_TOKEN_BUDGET") {
+            builder = builder.set_override("llm.monthly_token_budget", budget.parse::<u64>().unwrap_or(0))?;
+        }
+
+        if let Ok(action) = env::var("LLM_BUDGET_EXCEEDED_ACTION") {
+            builder = builder.set_override("llm.budget_exceeded_action", action)?;
+        }
+
+        if let Ok(top_k) = env::var("LLM_DEGRADED_SCHEMA_TOP_K") {
+            builder = builder.set_override("llm.degraded_schema_top_k", top_k.parse::<u64>().unwrap_or(5))?;
+        }
+
+        if let Ok(ttl) = env::var("LLM_GENERATION_CACHE_TTL_SECS") {
+            builder = builder.set_override("llm.generation_cache_ttl_secs", ttl.parse::<u64>().unwrap_or(3600))?;
+        }
+
+        if let Ok(entries) = env::var("LLM_GENERATION_CACHE_MAX_ENTRIES") {
+            builder = builder.set_override("llm.generation_cache_max_entries", entries.parse::<u64>().unwrap_or(1000))?;
+        }
+
+        if let Ok(patterns) = env::var("LLM_REDACTED_COLUMN_PATTERNS") {
+            builder = builder.set_override("llm.redacted_column_patterns", patterns)?;
+        }
+
         if let Ok(log_level) = env::var("RUST_LOG") {
             builder = builder.set_override("logging.level", log_level)?;
         }
@@ -71,6 +406,232 @@ impl Config {
             builder = builder.set_override("logging.style", log_style)?;
         }
 
+        if let Ok(log_format) = env::var("LOG_FORMAT") {
+            builder = builder.set_override("logging.format", log_format)?;
+        }
+
+        if let Ok(mask_literals) = env::var("LOG_MASK_SQL_LITERALS") {
+            builder = builder.set_override("logging.mask_sql_literals", mask_literals.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(retention_days) = env::var("HISTORY_RETENTION_DAYS") {
+            builder = builder.set_override("history.retention_days", retention_days.parse::<u32>().unwrap_or(0))?;
+        }
+
+        if let Ok(archive_enabled) = env::var("HISTORY_ARCHIVE_ENABLED") {
+            builder = builder.set_override("history.archive_enabled", archive_enabled.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(archive_dir) = env::var("HISTORY_ARCHIVE_DIR") {
+            builder = builder.set_override("history.archive_dir", archive_dir)?;
+        }
+
+        if let Ok(interval) = env::var("HISTORY_PRUNE_INTERVAL_SECS") {
+            builder = builder.set_override("history.prune_interval_secs", interval.parse::<u64>().unwrap_or(3600))?;
+        }
+
+        if let Ok(max_rows) = env::var("HISTORY_RESULT_MAX_ROWS") {
+            builder = builder.set_override("history.result_max_rows", max_rows.parse::<u64>().unwrap_or(100))?;
+        }
+
+        if let Ok(max_bytes) = env::var("HISTORY_RESULT_MAX_BYTES") {
+            builder = builder.set_override("history.result_max_bytes", max_bytes.parse::<u64>().unwrap_or(10 * 1024 * 1024))?;
+        }
+
+        if let Ok(grace_period) = env::var("TRASH_GRACE_PERIOD_DAYS") {
+            builder = builder.set_override("trash.grace_period_days", grace_period.parse::<u32>().unwrap_or(30))?;
+        }
+
+        if let Ok(interval) = env::var("TRASH_PURGE_INTERVAL_SECS") {
+            builder = builder.set_override("trash.purge_interval_secs", interval.parse::<u64>().unwrap_or(3600))?;
+        }
+
+        if let Ok(max_body) = env::var("MAX_REQUEST_BODY_BYTES") {
+            builder = builder.set_override("limits.max_body_bytes", max_body.parse::<u64>().unwrap_or(1024 * 1024))?;
+        }
+
+        if let Ok(max_query_body) = env::var("MAX_QUERY_BODY_BYTES") {
+            builder = builder.set_override("limits.max_query_body_bytes", max_query_body.parse::<u64>().unwrap_or(512 * 1024))?;
+        }
+
+        if let Ok(max_sql) = env::var("MAX_SQL_LENGTH") {
+            builder = builder.set_override("limits.max_sql_length", max_sql.parse::<u64>().unwrap_or(100_000))?;
+        }
+
+        if let Ok(max_question) = env::var("MAX_QUESTION_LENGTH") {
+            builder = builder.set_override("limits.max_question_length", max_question.parse::<u64>().unwrap_or(4_000))?;
+        }
+
+        if let Ok(threshold) = env::var("LARGE_RESULT_THRESHOLD") {
+            builder = builder.set_override("limits.large_result_threshold", threshold.parse::<u64>().unwrap_or(1_000_000))?;
+        }
+
+        if let Ok(max_export) = env::var("EXPORT_MAX_ROWS") {
+            builder = builder.set_override("limits.max_export_rows", max_export.parse::<u64>().unwrap_or(1_000_000))?;
+        }
+
+        if let Ok(retained) = env::var("METADATA_RETAINED_VERSIONS") {
+            builder = builder.set_override("metadata.retained_versions", retained.parse::<u64>().unwrap_or(10))?;
+        }
+
+        if let Ok(interval) = env::var("METADATA_REFRESH_INTERVAL_SECS") {
+            builder = builder.set_override("metadata.refresh_interval_secs", interval.parse::<u64>().unwrap_or(0))?;
+        }
+
+        if let Ok(interval) = env::var("METADATA_REFRESH_CHECK_INTERVAL_SECS") {
+            builder = builder.set_override("metadata.refresh_check_interval_secs", interval.parse::<u64>().unwrap_or(60))?;
+        }
+
+        if let Ok(auth_enabled) = env::var("AUTH_ENABLED") {
+            builder = builder.set_override("auth.enabled", auth_enabled.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(bootstrap_key) = env::var("AUTH_BOOTSTRAP_KEY") {
+            builder = builder.set_override("auth.bootstrap_key", Some(bootstrap_key))?;
+        }
+
+        if let Ok(jwt_secret) = env::var("AUTH_JWT_SECRET") {
+            builder = builder.set_override("auth.jwt_secret", Some(jwt_secret))?;
+        }
+
+        if let Ok(jwt_ttl) = env::var("AUTH_JWT_TTL_SECS") {
+            builder = builder.set_override("auth.jwt_ttl_secs", jwt_ttl.parse::<u64>().unwrap_or(8 * 3600))?;
+        }
+
+        if let Ok(sql_comment) = env::var("SQL_AUDIT_COMMENT") {
+            builder = builder.set_override("audit.sql_comment", sql_comment.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(timeout) = env::var("QUERY_JOB_TIMEOUT_SECS") {
+            builder = builder.set_override("jobs.timeout_secs", timeout.parse::<u64>().unwrap_or(3600))?;
+        }
+
+        if let Ok(cache_size) = env::var("STATEMENT_CACHE_SIZE") {
+            builder = builder.set_override("pool.statement_cache_size", cache_size.parse::<u64>().unwrap_or(64))?;
+        }
+
+        if let Ok(max_size) = env::var("POOL_MAX_SIZE") {
+            builder = builder.set_override("pool.max_size", max_size.parse::<u64>().unwrap_or(16))?;
+        }
+
+        if let Ok(min_idle) = env::var("POOL_MIN_IDLE") {
+            builder = builder.set_override("pool.min_idle", min_idle.parse::<u64>().unwrap_or(2))?;
+        }
+
+        if let Ok(timeout) = env::var("POOL_ACQUIRE_TIMEOUT_SECS") {
+            builder = builder.set_override("pool.acquire_timeout_secs", timeout.parse::<u64>().unwrap_or(30))?;
+        }
+
+        if let Ok(lifetime) = env::var("POOL_MAX_LIFETIME_SECS") {
+            builder = builder.set_override("pool.max_lifetime_secs", lifetime.parse::<u64>().unwrap_or(1800))?;
+        }
+
+        if let Ok(threshold) = env::var("POOL_CIRCUIT_FAILURE_THRESHOLD") {
+            builder = builder.set_override("pool.circuit_failure_threshold", threshold.parse::<u32>().unwrap_or(5))?;
+        }
+
+        if let Ok(open_secs) = env::var("POOL_CIRCUIT_OPEN_SECS") {
+            builder = builder.set_override("pool.circuit_open_secs", open_secs.parse::<u64>().unwrap_or(30))?;
+        }
+
+        if let Ok(idle_timeout) = env::var("TRANSACTION_IDLE_TIMEOUT_SECS") {
+            builder = builder.set_override(
+                "transactions.idle_timeout_secs",
+                idle_timeout.parse::<u64>().unwrap_or(300),
+            )?;
+        }
+
+        if let Ok(max_attempts) = env::var("WEBHOOK_MAX_ATTEMPTS") {
+            builder = builder.set_override("webhooks.max_attempts", max_attempts.parse::<u64>().unwrap_or(5))?;
+        }
+
+        if let Ok(timeout) = env::var("WEBHOOK_TIMEOUT_SECS") {
+            builder = builder.set_override("webhooks.timeout_secs", timeout.parse::<u64>().unwrap_or(10))?;
+        }
+
+        if let Ok(enabled) = env::var("QUERY_CACHE_ENABLED") {
+            builder = builder.set_override("query_cache.enabled", enabled.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(max_entries) = env::var("QUERY_CACHE_MAX_ENTRIES") {
+            builder = builder.set_override("query_cache.max_entries", max_entries.parse::<u64>().unwrap_or(1000))?;
+        }
+
+        if let Ok(ttl) = env::var("QUERY_CACHE_TTL_SECS") {
+            builder = builder.set_override("query_cache.ttl_secs", ttl.parse::<u64>().unwrap_or(300))?;
+        }
+
+        if let Ok(hits) = env::var("QUERY_CACHE_PERSIST_AFTER_HITS") {
+            builder = builder.set_override("query_cache.persist_after_hits", hits.parse::<u64>().unwrap_or(2))?;
+        }
+
+        if let Ok(rows) = env::var("CROSS_DB_TRANSFER_WARNING_ROWS") {
+            builder = builder.set_override("cross_database.transfer_warning_rows", rows.parse::<u64>().unwrap_or(1_000_000))?;
+        }
+
+        if let Ok(rows) = env::var("CROSS_DB_BROADCAST_MAX_ROWS") {
+            builder = builder.set_override("cross_database.broadcast_max_rows", rows.parse::<u64>().unwrap_or(10_000))?;
+        }
+
+        if let Ok(enabled) = env::var("CROSS_DB_SUBQUERY_CACHE_ENABLED") {
+            builder = builder.set_override("cross_database.subquery_cache_enabled", enabled.parse::<bool>().unwrap_or(false))?;
+        }
+
+        if let Ok(ttl) = env::var("CROSS_DB_SUBQUERY_CACHE_TTL_SECS") {
+            builder = builder.set_override("cross_database.subquery_cache_ttl_secs", ttl.parse::<u64>().unwrap_or(300))?;
+        }
+
+        if let Ok(max_bytes) = env::var("CROSS_DB_SUBQUERY_CACHE_MAX_BYTES") {
+            builder = builder.set_override(
+                "cross_database.subquery_cache_max_bytes",
+                max_bytes.parse::<u64>().unwrap_or(256 * 1024 * 1024),
+            )?;
+        }
+
+        if let Ok(rows) = env::var("CROSS_DB_INSERT_SELECT_MAX_ROWS") {
+            builder = builder.set_override("cross_database.insert_select_max_rows", rows.parse::<u64>().unwrap_or(1_000_000))?;
+        }
+
+        if let Ok(rows) = env::var("CROSS_DB_INSERT_SELECT_BATCH_ROWS") {
+            builder = builder.set_override("cross_database.insert_select_batch_rows", rows.parse::<u64>().unwrap_or(1000))?;
+        }
+
+        if let Ok(dir) = env::var("MATERIALIZATION_DIR") {
+            builder = builder.set_override("materialization.dir", dir)?;
+        }
+
+        if let Ok(interval) = env::var("MATERIALIZATION_CHECK_INTERVAL_SECS") {
+            builder = builder.set_override("materialization.check_interval_secs", interval.parse::<u64>().unwrap_or(60))?;
+        }
+
+        if let Ok(timeout) = env::var("MATERIALIZATION_REFRESH_TIMEOUT_SECS") {
+            builder = builder.set_override("materialization.refresh_timeout_secs", timeout.parse::<u64>().unwrap_or(600))?;
+        }
+
+        if let Ok(udfs) = env::var("DATAFUSION_UDFS") {
+            builder = builder.set_override("datafusion.udfs", udfs)?;
+        }
+
+        if let Ok(path) = env::var("DATAFUSION_GEOIP_MMDB_PATH") {
+            builder = builder.set_override("datafusion.geoip_mmdb_path", Some(path))?;
+        }
+
+        if let Ok(size) = env::var("DATAFUSION_BATCH_SIZE") {
+            builder = builder.set_override("datafusion.batch_size", size.parse::<u64>().unwrap_or(8192))?;
+        }
+
+        if let Ok(partitions) = env::var("DATAFUSION_TARGET_PARTITIONS") {
+            builder = builder.set_override("datafusion.target_partitions", partitions.parse::<u64>().unwrap_or(0))?;
+        }
+
+        if let Ok(limit) = env::var("DATAFUSION_MEMORY_LIMIT_MB") {
+            builder = builder.set_override("datafusion.memory_limit_mb", limit.parse::<u64>().unwrap_or(0))?;
+        }
+
+        if let Ok(size) = env::var("DATAFUSION_SESSION_POOL_SIZE") {
+            builder = builder.set_override("datafusion.session_pool_size", size.parse::<u64>().unwrap_or(8))?;
+        }
+
         // Try to load from .env file
         let _ = dotenv::dotenv();
 
@@ -99,6 +660,64 @@ mod tests {
         let config = config.unwrap();
         assert_eq!(config.server.port, 3000);
         assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.history.retention_days, 0);
+        assert!(!config.history.archive_enabled);
+        assert_eq!(config.history.result_max_rows, 100);
+        assert_eq!(config.trash.grace_period_days, 30);
+        assert_eq!(config.logging.format, "text");
+        assert_eq!(config.limits.max_body_bytes, 1024 * 1024);
+        assert_eq!(config.limits.max_sql_length, 100_000);
+        assert_eq!(config.limits.large_result_threshold, 1_000_000);
+        assert_eq!(config.limits.max_export_rows, 1_000_000);
+        assert_eq!(config.metadata.retained_versions, 10);
+        assert_eq!(config.metadata.refresh_interval_secs, 0);
+        assert_eq!(config.metadata.refresh_check_interval_secs, 60);
+        assert!(!config.logging.mask_sql_literals);
+        assert!(!config.auth.enabled);
+        assert_eq!(config.auth.jwt_ttl_secs, 8 * 3600);
+        assert!(!config.audit.sql_comment);
+        assert_eq!(config.jobs.timeout_secs, 3600);
+        assert_eq!(config.pool.statement_cache_size, 64);
+        assert_eq!(config.pool.max_size, 16);
+        assert_eq!(config.pool.min_idle, 2);
+        assert_eq!(config.pool.acquire_timeout_secs, 30);
+        assert_eq!(config.pool.max_lifetime_secs, 1800);
+        assert_eq!(config.pool.circuit_failure_threshold, 5);
+        assert_eq!(config.pool.circuit_open_secs, 30);
+        assert_eq!(config.transactions.idle_timeout_secs, 300);
+        assert_eq!(config.webhooks.max_attempts, 5);
+        assert_eq!(config.webhooks.timeout_secs, 10);
+        assert!(!config.query_cache.enabled);
+        assert_eq!(config.query_cache.max_entries, 1000);
+        assert_eq!(config.query_cache.ttl_secs, 300);
+        assert_eq!(config.query_cache.persist_after_hits, 2);
+        assert_eq!(config.cross_database.transfer_warning_rows, 1_000_000);
+        assert_eq!(config.cross_database.broadcast_max_rows, 10_000);
+        assert!(!config.cross_database.subquery_cache_enabled);
+        assert_eq!(config.cross_database.subquery_cache_ttl_secs, 300);
+        assert_eq!(config.cross_database.subquery_cache_max_bytes, 256 * 1024 * 1024);
+        assert_eq!(config.cross_database.insert_select_max_rows, 1_000_000);
+        assert_eq!(config.cross_database.insert_select_batch_rows, 1000);
+        assert_eq!(config.materialization.dir, "./materialized_views");
+        assert_eq!(config.materialization.check_interval_secs, 60);
+        assert_eq!(config.materialization.refresh_timeout_secs, 600);
+        assert_eq!(config.datafusion.udfs, "");
+        assert!(config.datafusion.geoip_mmdb_path.is_none());
+        assert_eq!(config.datafusion.batch_size, 8192);
+        assert_eq!(config.datafusion.target_partitions, 0);
+        assert_eq!(config.datafusion.memory_limit_mb, 0);
+        assert_eq!(config.datafusion.session_pool_size, 8);
+        assert!(config.llm.embedding_url.is_none());
+        assert_eq!(config.llm.schema_retrieval_threshold, 50);
+        assert_eq!(config.llm.schema_top_k, 15);
+        assert_eq!(config.llm.max_repair_attempts, 2);
+        assert_eq!(config.llm.few_shot_examples, 3);
+        assert_eq!(config.llm.monthly_token_budget, 0);
+        assert_eq!(config.llm.budget_exceeded_action, "reject");
+        assert_eq!(config.llm.degraded_schema_top_k, 5);
+        assert_eq!(config.llm.generation_cache_ttl_secs, 3600);
+        assert_eq!(config.llm.generation_cache_max_entries, 1000);
+        assert_eq!(config.llm.redacted_column_patterns, DEFAULT_REDACTED_COLUMN_PATTERNS);
     }
 }
 
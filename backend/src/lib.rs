@@ -1,5 +1,6 @@
 pub mod api;
 pub mod config;
+pub mod logging;
 pub mod models;
 pub mod services;
 pub mod storage;
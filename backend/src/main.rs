@@ -1,10 +1,10 @@
 use axum::Router;
 use std::net::SocketAddr;
 use tracing::{info, error};
-use tracing_subscriber;
 
 mod api;
 mod config;
+mod logging;
 mod models;
 mod services;
 mod storage;
@@ -14,20 +14,15 @@ use config::Config;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
-
     // Load configuration
     let config = Config::from_env().map_err(|e| {
-        error!("Failed to load configuration: {}", e);
+        eprintln!("Failed to load configuration: {}", e);
         e
     })?;
 
+    // Initialize logging (text or JSON output, with redaction policy)
+    logging::init(&config.logging);
+
     info!("Starting server on {}", config.server_address());
 
     // Initialize SQLite storage
@@ -40,6 +35,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             })?
     );
 
+    // Register stored connection secrets with the log redaction policy
+    match storage.list_connections().await {
+        Ok(connections) => {
+            for connection in &connections {
+                logging::redactor().register_connection(&connection.id, &connection.connection_url);
+            }
+        }
+        Err(e) => error!("Failed to load connections for log redaction: {}", e),
+    }
+
+    // Query jobs do not survive a restart; mark the interrupted ones as failed
+    match storage.fail_unfinished_query_jobs().await {
+        Ok(0) => {}
+        Ok(count) => info!("Marked {} interrupted query jobs as failed", count),
+        Err(e) => error!("Failed to clean up interrupted query jobs: {}", e),
+    }
+
+    // Start background query history pruning/archival (server-wide and per-domain retention)
+    let archive_service = std::sync::Arc::new(services::HistoryArchiveService::new(
+        storage.clone(),
+        config.history.clone(),
+    ));
+    archive_service.spawn_scheduler();
+    info!(
+        "Query history retention: {} days (0 = per-domain settings only, archive: {})",
+        config.history.retention_days, config.history.archive_enabled
+    );
+
+    // Start background trash purge (no-op when the grace period is 0)
+    let trash_service = std::sync::Arc::new(services::TrashService::new(
+        storage.clone(),
+        config.trash.clone(),
+    ));
+    if trash_service.spawn_scheduler().is_some() {
+        info!("Trash purge enabled: grace period {} days", config.trash.grace_period_days);
+    }
+
+    // Start background webhook delivery
+    let notification_service = std::sync::Arc::new(services::NotificationService::new(storage.clone()));
+    notification_service.spawn_worker(config.webhooks.clone());
+    info!("Webhook delivery: up to {} attempts per event", config.webhooks.max_attempts);
+
     // Create router with state
     let app: Router = api::routes::create_router_with_state(storage, config.clone());
 
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Prefix of every issued API key (makes keys recognizable in configs and secret scanners)
+pub const API_KEY_PREFIX: &str = "dbq_";
+
+/// Permission level of an API key; each scope includes the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    /// Read-only access (GET requests)
+    Read,
+    /// Read access plus query execution and resource changes
+    Execute,
+    /// Everything, including API key management
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Read => "read",
+            ApiKeyScope::Execute => "execute",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "read" | "read_only" | "readonly" => Ok(ApiKeyScope::Read),
+            "execute" => Ok(ApiKeyScope::Execute),
+            "admin" => Ok(ApiKeyScope::Admin),
+            _ => Err(format!("Invalid API key scope: {} (expected read, execute or admin)", s)),
+        }
+    }
+
+    /// Whether this scope grants the required scope
+    pub fn allows(&self, required: ApiKeyScope) -> bool {
+        *self >= required
+    }
+}
+
+/// Stored API key (the secret itself is only kept as a SHA-256 hash)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key, shown to help identify it
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scope: ApiKeyScope,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Generate a new key; returns the stored record and the plaintext secret
+    pub fn generate(name: String, scope: ApiKeyScope) -> (Self, String) {
+        let secret = format!(
+            "{}{}{}",
+            API_KEY_PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let key = Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            key_prefix: secret[..API_KEY_PREFIX.len() + 8].to_string(),
+            key_hash: Self::hash(&secret),
+            scope,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        (key, secret)
+    }
+
+    /// Hex-encoded SHA-256 of a plaintext key
+    pub fn hash(secret: &str) -> String {
+        use sha2::{Digest, Sha256};
+        Sha256::digest(secret.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Request payload for issuing an API key
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scope: ApiKeyScope,
+}
+
+/// Response for a newly issued key; the plaintext key is only returned once
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_hierarchy() {
+        assert!(ApiKeyScope::Admin.allows(ApiKeyScope::Execute));
+        assert!(ApiKeyScope::Execute.allows(ApiKeyScope::Read));
+        assert!(!ApiKeyScope::Read.allows(ApiKeyScope::Execute));
+        assert!(!ApiKeyScope::Execute.allows(ApiKeyScope::Admin));
+        assert_eq!(ApiKeyScope::from_str("read_only").unwrap(), ApiKeyScope::Read);
+        assert!(ApiKeyScope::from_str("owner").is_err());
+    }
+
+    #[test]
+    fn test_generate_key() {
+        let (key, secret) = ApiKey::generate("ci".to_string(), ApiKeyScope::Execute);
+        assert!(secret.starts_with(API_KEY_PREFIX));
+        assert!(secret.starts_with(&key.key_prefix));
+        assert_eq!(key.key_hash, ApiKey::hash(&secret));
+        assert_ne!(key.key_hash, ApiKey::hash("dbq_other"));
+
+        // The hash is never serialized
+        let json = serde_json::to_string(&key).unwrap();
+        assert!(!json.contains(&key.key_hash));
+    }
+}
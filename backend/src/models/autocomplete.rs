@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// `POST /api/connections/{id}/autocomplete`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AutocompleteRequest {
+    /// The editor content, possibly incomplete SQL
+    pub sql: String,
+    /// Cursor position as a character offset into `sql` (default: end)
+    #[serde(default)]
+    pub cursor: Option<usize>,
+    /// Maximum number of suggestions (default 50)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Column,
+    Table,
+    View,
+    Schema,
+    Keyword,
+}
+
+/// What the cursor position expects
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionContext {
+    /// After FROM, JOIN, INTO or UPDATE
+    Table,
+    /// In a select list or a condition
+    Column,
+    /// After `qualifier.`
+    Qualified,
+    /// Anywhere else, e.g. after a table name
+    Keyword,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Column type, or the schema of a table or view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Visualization suited to a query result
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartType {
+    /// Measures over a date or time axis
+    Line,
+    /// Measures per category
+    Bar,
+    /// Shares of a whole over a few categories
+    Pie,
+    /// One measure against another
+    Scatter,
+}
+
+/// How a column is read on a chart axis
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    /// Dates and timestamps
+    Temporal,
+    /// Numbers
+    Quantitative,
+    /// Categories
+    Nominal,
+}
+
+/// Aggregation of the y values of rows sharing an x value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartAggregation {
+    Sum,
+    Avg,
+    /// Rows per x value (the chart has no y column)
+    Count,
+}
+
+/// A result column placed on a chart axis
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ChartEncoding {
+    pub field: String,
+    pub kind: FieldKind,
+}
+
+/// Suggested chart of a query result, to render without asking the user
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ChartSpec {
+    pub chart_type: ChartType,
+    pub x: ChartEncoding,
+    /// One series per column; empty for count charts
+    pub y: Vec<ChartEncoding>,
+    /// Set when x values repeat, or for count charts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<ChartAggregation>,
+    /// Why this chart fits the result
+    pub reason: String,
+}
@@ -0,0 +1,138 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::query::Query;
+
+/// Columns named in a result summary before the rest are counted
+const SUMMARY_MAX_COLUMNS: usize = 20;
+
+/// Conversation of natural language questions about one connection
+///
+/// Earlier turns are given to the LLM as context, so a follow-up such as
+/// "now only for last month" refines the previous query.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChatSession {
+    pub id: String,
+    pub connection_id: String,
+    pub domain_id: Option<String>,
+    /// Principal subject that started the session
+    pub created_by: Option<String>,
+    /// First question of the session
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    /// When the latest turn was added
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ChatSession {
+    pub fn new(connection_id: String, domain_id: Option<String>, created_by: Option<String>, title: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connection_id,
+            domain_id,
+            created_by,
+            title,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// One question of a chat session with the SQL it produced
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChatTurn {
+    pub id: String,
+    pub session_id: String,
+    pub question: String,
+    pub generated_sql: Option<String>,
+    /// Row count and columns of the result (no row values)
+    pub result_summary: Option<String>,
+    /// Why the generated SQL failed, if it did
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ChatTurn {
+    pub fn new(session_id: String, question: String, generated_sql: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            session_id,
+            question,
+            generated_sql: Some(generated_sql),
+            result_summary: None,
+            error_message: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Record the result of the turn's SQL
+    pub fn with_result(mut self, query: &Query) -> Self {
+        self.result_summary = Some(summarize_result(query));
+        self
+    }
+
+    /// Record the failure of the turn's SQL
+    pub fn with_error(mut self, error_message: String) -> Self {
+        self.error_message = Some(error_message);
+        self
+    }
+}
+
+/// Summary of a query result given to the LLM: row count and column names
+///
+/// Row values are left out, so result data is never sent to the LLM.
+pub fn summarize_result(query: &Query) -> String {
+    let row_count = query.row_count.unwrap_or(0);
+    let mut summary = format!("{} row{}", row_count, if row_count == 1 { "" } else { "s" });
+    if query.limit_applied {
+        summary.push_str(" (row limit reached)");
+    }
+
+    let columns: Vec<&str> = query
+        .results
+        .as_ref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.as_object())
+        .map(|row| row.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    if !columns.is_empty() {
+        summary.push_str("; columns: ");
+        summary.push_str(&columns[..columns.len().min(SUMMARY_MAX_COLUMNS)].join(", "));
+        if columns.len() > SUMMARY_MAX_COLUMNS {
+            summary.push_str(&format!(" and {} more", columns.len() - SUMMARY_MAX_COLUMNS));
+        }
+    }
+    summary
+}
+
+/// Request body of `POST /api/connections/{id}/chat`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChatRequest {
+    /// Session to continue (omit to start a new one)
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub question: String,
+    /// Execute even if the estimated result exceeds the large result threshold
+    #[serde(default)]
+    pub confirm_large_result: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summarize_result() {
+        let mut query = Query::new("conn".to_string(), "SELECT region, total FROM sales".to_string(), true);
+        query.mark_completed(vec![json!({"region": "EU", "total": 3}), json!({"region": "US", "total": 5})], 4);
+        assert_eq!(summarize_result(&query), "2 rows; columns: region, total");
+
+        query.mark_completed(vec![], 1);
+        query.limit_applied = true;
+        assert_eq!(summarize_result(&query), "0 rows (row limit reached)");
+    }
+}
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::policy::Labels;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DatabaseConnection {
     pub id: String,
     pub name: Option<String>,
@@ -13,9 +16,94 @@ pub struct DatabaseConnection {
     pub created_at: DateTime<Utc>,
     pub last_connected_at: Option<DateTime<Utc>>,
     pub metadata_cache_id: Option<String>,
+    /// Incremented on every update (optimistic concurrency control)
+    pub version: i64,
+    /// Connection is meant for reads only (see `verify_read_only` on creation)
+    #[serde(default)]
+    pub read_only: bool,
+    /// Write statements may run through `POST /api/connections/{id}/execute`
+    #[serde(default)]
+    pub allow_writes: bool,
+    /// Free-form labels (`env=prod`) used for filtering and policy selectors
+    #[serde(default)]
+    #[schema(value_type = std::collections::BTreeMap<String, String>)]
+    pub labels: Labels,
+    /// Pool sizes and timeouts overriding the server defaults
+    #[serde(default)]
+    pub pool_settings: PoolSettings,
+    /// Free-form tags (`pii`, `warehouse`) for grouping and list filters
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Most tags a connection may carry
+pub const MAX_CONNECTION_TAGS: usize = 32;
+
+/// Tags trimmed, lowercased, listed once and sorted
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    if normalized.len() > MAX_CONNECTION_TAGS {
+        return Err(format!("A connection cannot have more than {} tags", MAX_CONNECTION_TAGS));
+    }
+    if let Some(tag) = normalized.iter().find(|tag| {
+        tag.len() > 63 || !tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-' | ':'))
+    }) {
+        return Err(format!(
+            "Invalid tag '{}': use up to 63 letters, digits, '.', '_', '/', '-' or ':'",
+            tag
+        ));
+    }
+    Ok(normalized)
+}
+
+/// Largest pool a connection may ask for
+pub const MAX_POOL_SIZE: usize = 256;
+
+/// Connection pool settings of a connection; unset fields use the server's
+/// `pool.*` defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct PoolSettings {
+    /// Most connections open at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<usize>,
+    /// Connections opened ahead of use and kept idle (PostgreSQL, MySQL, Doris)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_idle: Option<usize>,
+    /// Longest wait for a free connection before a request fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquire_timeout_secs: Option<u64>,
+    /// Connections older than this are closed instead of reused (0 = no limit;
+    /// PostgreSQL, MySQL, Doris)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lifetime_secs: Option<u64>,
+}
+
+impl PoolSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(max_size) = self.max_size {
+            if max_size == 0 || max_size > MAX_POOL_SIZE {
+                return Err(format!("Pool max_size must be between 1 and {}", MAX_POOL_SIZE));
+            }
+        }
+        if let Some(min_idle) = self.min_idle {
+            if min_idle > self.max_size.unwrap_or(MAX_POOL_SIZE) {
+                return Err("Pool min_idle cannot exceed max_size".to_string());
+            }
+        }
+        if self.acquire_timeout_secs == Some(0) {
+            return Err("Pool acquire_timeout_secs must be positive".to_string());
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ConnectionStatus {
     Connected,
@@ -40,9 +128,43 @@ impl DatabaseConnection {
             created_at: Utc::now(),
             last_connected_at: None,
             metadata_cache_id: None,
+            version: 1,
+            read_only: false,
+            allow_writes: false,
+            labels: Labels::new(),
+            pool_settings: PoolSettings::default(),
+            tags: Vec::new(),
         }
     }
 
+    /// Reject settings that contradict each other
+    pub fn validate_write_mode(&self) -> Result<(), String> {
+        if self.read_only && self.allow_writes {
+            return Err("A read-only connection cannot allow writes".to_string());
+        }
+        Ok(())
+    }
+
+    /// Copy of the connection's target and settings under a new id, not yet connected
+    pub fn duplicate(&self, name: Option<String>, domain_id: Option<String>) -> Self {
+        let name = name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.name.as_ref().map(|name| format!("{} (copy)", name)));
+        let mut copy = Self::new(
+            name,
+            self.connection_url.clone(),
+            self.database_type.clone(),
+            domain_id.or_else(|| self.domain_id.clone()),
+        );
+        copy.read_only = self.read_only;
+        copy.allow_writes = self.allow_writes;
+        copy.labels = self.labels.clone();
+        copy.pool_settings = self.pool_settings.clone();
+        copy.tags = self.tags.clone();
+        copy
+    }
+
     pub fn mark_connected(&mut self) {
         self.status = ConnectionStatus::Connected;
         self.last_connected_at = Some(Utc::now());
@@ -57,16 +179,136 @@ impl DatabaseConnection {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateConnectionRequest {
     pub name: Option<String>,
+    /// Required unless `url_fields` is given
+    #[serde(default)]
     pub connection_url: String,
+    /// The URL as separate fields, assembled by the server (see `GET /api/connections/templates`)
+    pub url_fields: Option<super::ConnectionUrlFields>,
     #[serde(default = "default_database_type")]
     pub database_type: String,
     pub domain_id: Option<String>,
+    #[serde(default)]
+    pub read_only: bool,
+    /// Check at creation that the credentials of a read-only connection
+    /// really lack write privileges (a warning is returned otherwise)
+    #[serde(default)]
+    pub verify_read_only: bool,
+    /// Opt in to write statements (not allowed together with `read_only`)
+    #[serde(default)]
+    pub allow_writes: bool,
+    #[serde(default)]
+    #[schema(value_type = std::collections::BTreeMap<String, String>)]
+    pub labels: Labels,
+    #[serde(default)]
+    pub pool_settings: PoolSettings,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Request payload for updating an existing connection
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateConnectionRequest {
+    pub name: Option<String>,
+    pub connection_url: Option<String>,
+    /// New URL as separate fields (instead of `connection_url`)
+    pub url_fields: Option<super::ConnectionUrlFields>,
+    pub read_only: Option<bool>,
+    pub allow_writes: Option<bool>,
+    /// Replaces all labels when present
+    #[schema(value_type = Option<std::collections::BTreeMap<String, String>>)]
+    pub labels: Option<Labels>,
+    /// Replaces all pool settings when present
+    pub pool_settings: Option<PoolSettings>,
+    /// Replaces all tags when present
+    pub tags: Option<Vec<String>>,
+    /// Current version, required unless an If-Match header is sent
+    pub expected_version: Option<i64>,
+}
+
+/// `POST /api/connections/{id}/duplicate`
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DuplicateConnectionRequest {
+    /// Name of the copy (defaults to the original's name with " (copy)")
+    pub name: Option<String>,
+    /// Domain of the copy (defaults to the original's domain)
+    pub domain_id: Option<String>,
+}
+
+/// `POST /api/connections/{id}/move`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveConnectionRequest {
+    /// Domain the connection moves to
+    pub domain_id: String,
+    /// Current version, required unless an If-Match header is sent
+    pub expected_version: Option<i64>,
 }
 
-fn default_database_type() -> String {
+pub(crate) fn default_database_type() -> String {
     "postgresql".to_string()
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_connection() {
+        let mut original = DatabaseConnection::new(
+            Some("Orders".to_string()),
+            "postgresql://localhost/orders".to_string(),
+            "postgresql".to_string(),
+            Some("staging".to_string()),
+        );
+        original.read_only = true;
+        original.labels.insert("env".to_string(), "staging".to_string());
+        original.tags = vec!["pii".to_string()];
+        original.mark_connected();
+
+        let copy = original.duplicate(None, None);
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.name.as_deref(), Some("Orders (copy)"));
+        assert_eq!(copy.domain_id.as_deref(), Some("staging"));
+        assert_eq!(copy.status, ConnectionStatus::Disconnected);
+        assert!(copy.read_only);
+        assert_eq!(copy.labels, original.labels);
+        assert_eq!(copy.tags, original.tags);
+        assert_eq!(copy.metadata_cache_id, None);
+
+        let copy = original.duplicate(Some(" Orders (prod) ".to_string()), Some("prod".to_string()));
+        assert_eq!(copy.name.as_deref(), Some("Orders (prod)"));
+        assert_eq!(copy.domain_id.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn test_validate_pool_settings() {
+        assert!(PoolSettings::default().validate().is_ok());
+        let settings = |max_size, min_idle, acquire_timeout_secs| PoolSettings {
+            max_size,
+            min_idle,
+            acquire_timeout_secs,
+            max_lifetime_secs: Some(0),
+        };
+        assert!(settings(Some(4), Some(4), Some(5)).validate().is_ok());
+        assert!(settings(Some(0), None, None).validate().is_err());
+        assert!(settings(Some(MAX_POOL_SIZE + 1), None, None).validate().is_err());
+        assert!(settings(Some(4), Some(5), None).validate().is_err());
+        assert!(settings(None, None, Some(0)).validate().is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            normalize_tags(&tags(&[" Warehouse", "pii", "", "PII", "team:finance"])).unwrap(),
+            tags(&["pii", "team:finance", "warehouse"])
+        );
+        assert!(normalize_tags(&tags(&["has space"])).is_err());
+        assert!(normalize_tags(&tags(&[&"x".repeat(64)])).is_err());
+        let many: Vec<String> = (0..=MAX_CONNECTION_TAGS).map(|i| format!("tag{}", i)).collect();
+        assert!(normalize_tags(&many).is_err());
+    }
+}
@@ -3,9 +3,12 @@
 // Models for cross-database JOIN and UNION queries using DataFusion's federated execution.
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+use super::query::{CacheStatus, ColumnarResult};
+
 /// Request for cross-database query execution
 ///
 /// Allows querying multiple databases in a single SQL statement using qualified table names.
@@ -27,17 +30,21 @@ use std::collections::HashMap;
 ///     limit_value: Some(100),
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CrossDatabaseQueryRequest {
     /// SQL query with qualified table names (e.g., db1.table1, db2.table2)
     ///
-    /// Table qualification format: `<database_identifier>.<table_name>`
+    /// Table qualification format: `<database_identifier>.<table_name>` or
+    /// `<database_identifier>.<schema>.<table_name>`
     /// The database identifier must map to one of the connection_ids or aliases
     pub query: String,
 
     /// List of connection IDs involved in the query
     ///
-    /// Each connection ID must exist and be accessible by the user
+    /// Each connection ID must exist and be accessible by the user. When empty
+    /// (and no aliases are given), the query's `alias.schema.table` references
+    /// are resolved through the federated catalog (`GET /api/cross-database/catalog`).
+    #[serde(default)]
     pub connection_ids: Vec<String>,
 
     /// Optional database aliases mapping (e.g., {"db1": "uuid-1", "db2": "uuid-2"})
@@ -47,7 +54,8 @@ pub struct CrossDatabaseQueryRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub database_aliases: Option<HashMap<String, String>>,
 
-    /// Query timeout in seconds (default: 60)
+    /// Query timeout in seconds (default: the strictest default of the
+    /// connections' domains, capped by their maximum timeouts)
     ///
     /// This is the total timeout for the entire cross-database operation,
     /// including all sub-queries and result merging
@@ -61,7 +69,8 @@ pub struct CrossDatabaseQueryRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub apply_limit: Option<bool>,
 
-    /// LIMIT value to apply (default: 1000)
+    /// LIMIT value to apply (default: the strictest default row limit of the
+    /// connections' domains, capped by their maximum row limits)
     ///
     /// This limit is applied to the final result set after merging
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -72,7 +81,7 @@ pub struct CrossDatabaseQueryRequest {
 ///
 /// Contains the original query, sub-queries executed per database,
 /// and the merged results
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CrossDatabaseQueryResponse {
     /// Original cross-database SQL query
     pub original_query: String,
@@ -99,10 +108,16 @@ pub struct CrossDatabaseQueryResponse {
     /// Timestamp when query was executed
     #[serde(with = "chrono::serde::ts_milliseconds")]
     pub executed_at: DateTime<Utc>,
+    /// Results by column (`result_format=columnar`, `results` is then empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columnar: Option<ColumnarResult>,
+    /// Planner warnings, e.g. an estimated transfer above the configured row threshold
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 /// Information about a sub-query executed against a specific database
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SubQueryExecution {
     /// Connection ID where this sub-query was executed
     pub connection_id: String,
@@ -120,6 +135,19 @@ pub struct SubQueryExecution {
 
     /// Execution time for this specific sub-query in milliseconds
     pub execution_time_ms: u128,
+
+    /// WHERE predicates of the original query evaluated by this database
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pushed_predicates: Vec<String>,
+    /// Rows the planner expected from the table statistics of the metadata cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_rows: Option<u64>,
+    /// Join keys of a smaller table this sub-query was filtered by (`IN` list size)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_keys: Option<usize>,
+    /// Sub-query cache outcome (absent when the sub-query cache is disabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
 }
 
 /// Internal: Cross-database execution plan
@@ -136,6 +164,13 @@ pub struct CrossDatabaseExecutionPlan {
     /// How to merge the sub-query results
     pub merge_strategy: MergeStrategy,
 
+    /// Clauses of the original query applied to the merged rows
+    pub merge_clauses: MergeClauses,
+    /// Join whose smaller side's keys filter the larger side's sub-query
+    pub broadcast: Option<BroadcastJoin>,
+    /// Cost warnings returned with the results
+    pub warnings: Vec<String>,
+
     /// Timeout for the entire operation
     pub timeout_secs: u64,
 
@@ -165,6 +200,20 @@ pub struct SubQuery {
     ///
     /// Used when registering as in-memory table in DataFusion
     pub result_alias: String,
+
+    /// WHERE predicates pushed down into `query`
+    ///
+    /// Single-table conjuncts of the original WHERE clause, filtered here
+    /// instead of after the rows were fetched.
+    pub pushed_predicates: Vec<String>,
+
+    /// Whether the result is a table of the merge's FROM clause
+    ///
+    /// Results of scalar and IN subqueries are registered for the merge SQL to read
+    /// but are not joined.
+    pub join_input: bool,
+    /// Rows expected from table statistics and pushed predicates, when known
+    pub estimated_rows: Option<u64>,
 }
 
 /// Internal: Strategy for merging sub-query results
@@ -186,6 +235,11 @@ pub enum MergeStrategy {
         conditions: Vec<JoinCondition>,
     },
 
+    /// Full JOIN (FULL OUTER JOIN)
+    FullOuterJoin {
+        conditions: Vec<JoinCondition>,
+    },
+
     /// UNION (or UNION ALL) results
     Union {
         /// Whether to use UNION ALL (keep duplicates)
@@ -196,6 +250,163 @@ pub enum MergeStrategy {
     None,
 }
 
+/// Internal: Clauses of the original SELECT evaluated by DataFusion after merging
+///
+/// Rendered SQL fragments with database qualifiers removed and identifiers quoted,
+/// so they resolve against the in-memory tables registered under each alias.
+#[derive(Debug, Clone, Default)]
+pub struct MergeClauses {
+    /// `DISTINCT` or `DISTINCT ON (...)`
+    pub distinct: Option<String>,
+
+    /// Select list items; empty means `*`
+    pub projection: Vec<String>,
+
+    /// WHERE condition
+    pub selection: Option<String>,
+
+    /// Complete `GROUP BY ...` clause
+    pub group_by: Option<String>,
+
+    /// HAVING condition
+    pub having: Option<String>,
+
+    /// Complete `ORDER BY ...` clause
+    pub order_by: Option<String>,
+}
+
+/// Request to copy the result of a query on one connection into a table of
+/// another (cross-database `INSERT INTO ... SELECT`)
+///
+/// Result columns are inserted into the target columns of the same name; rename
+/// them with aliases in the query.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CrossDatabaseInsertRequest {
+    /// Connection the query runs on
+    pub source_connection_id: String,
+
+    /// SELECT query in the source database's dialect
+    pub query: String,
+
+    /// Connection the rows are inserted into (needs `allow_writes`)
+    pub target_connection_id: String,
+
+    /// Existing target table, optionally schema-qualified (`schema.table`)
+    pub target_table: String,
+
+    /// Result columns to insert (default: all columns of the first row)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+
+    /// Rows per INSERT batch (default: `CROSS_DB_INSERT_SELECT_BATCH_ROWS`, at most 10000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+}
+
+impl CrossDatabaseInsertRequest {
+    /// Validate request parameters
+    pub fn validate(&self) -> Result<(), String> {
+        if self.query.trim().is_empty() {
+            return Err("Query cannot be empty".to_string());
+        }
+
+        if self.source_connection_id.is_empty() || self.target_connection_id.is_empty() {
+            return Err("Source and target connection IDs are required".to_string());
+        }
+
+        let parts = self.target_table_parts();
+        if parts.is_empty() || parts.len() > 2 || parts.iter().any(|part| part.is_empty()) {
+            return Err("Target table must be `table` or `schema.table`".to_string());
+        }
+
+        if let Some(columns) = &self.columns {
+            if columns.is_empty() || columns.iter().any(|column| column.trim().is_empty()) {
+                return Err("Columns must be non-empty names".to_string());
+            }
+        }
+
+        if let Some(batch_size) = self.batch_size {
+            if batch_size == 0 || batch_size > 10000 {
+                return Err("Batch size must be between 1 and 10000".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Schema (if any) and table name of the target table
+    pub fn target_table_parts(&self) -> Vec<&str> {
+        self.target_table.trim().split('.').map(str::trim).collect()
+    }
+}
+
+/// Natural language question over the connections of a domain
+/// (`POST /api/domains/{id}/nl-query`)
+///
+/// The LLM sees the cached schema of each connection under its catalog alias
+/// and answers with one cross-database query over `alias.schema.table` tables.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CrossDatabaseNaturalLanguageRequest {
+    pub question: String,
+
+    /// Connections of the domain the question is about (default: all connected ones)
+    #[serde(default)]
+    pub connection_ids: Vec<String>,
+
+    /// Query timeout in seconds (default and cap from the domain's settings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// Whether to automatically apply LIMIT if not present (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_limit: Option<bool>,
+
+    /// LIMIT value to apply (default and cap from the domain's settings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_value: Option<u32>,
+}
+
+/// Tables of a connection in the federated catalog
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CatalogConnection {
+    /// Catalog name queries use as `alias.schema.table`
+    pub alias: String,
+    pub connection_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub database_type: String,
+    /// Schemas of the cached metadata (empty when the connection has none cached)
+    pub schemas: Vec<CatalogSchema>,
+}
+
+/// Schema of a connection catalog
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CatalogSchema {
+    pub name: String,
+    pub tables: Vec<String>,
+}
+
+/// `GET /api/cross-database/catalog`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FederatedCatalog {
+    pub connections: Vec<CatalogConnection>,
+}
+
+/// Internal: Semi-join reduction of a cross-database JOIN
+///
+/// The build sub-query runs first. Its distinct join keys are then sent to the
+/// probe sub-query's database as `probe_column IN (...)`, so only rows that
+/// can match are transferred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BroadcastJoin {
+    /// Result alias of the smaller table
+    pub build_alias: String,
+    pub build_column: String,
+    /// Result alias of the table filtered by the keys
+    pub probe_alias: String,
+    pub probe_column: String,
+}
+
 /// Internal: JOIN condition between tables from different databases
 #[derive(Debug, Clone)]
 pub struct JoinCondition {
@@ -292,6 +503,8 @@ impl CrossDatabaseQueryResponse {
             execution_time_ms,
             limit_applied,
             executed_at: Utc::now(),
+            columnar: None,
+            warnings: vec![],
         }
     }
 }
@@ -300,6 +513,31 @@ impl CrossDatabaseQueryResponse {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_insert_request_validation() {
+        let request = CrossDatabaseInsertRequest {
+            source_connection_id: "conn1".to_string(),
+            query: "SELECT id, name FROM users".to_string(),
+            target_connection_id: "conn2".to_string(),
+            target_table: "archive.users".to_string(),
+            columns: None,
+            batch_size: Some(500),
+        };
+        assert!(request.validate().is_ok());
+        assert_eq!(request.target_table_parts(), vec!["archive", "users"]);
+
+        let invalid = |change: fn(&mut CrossDatabaseInsertRequest)| {
+            let mut request = request.clone();
+            change(&mut request);
+            request.validate().is_err()
+        };
+        assert!(invalid(|r| r.query = " ".to_string()));
+        assert!(invalid(|r| r.target_table = "a.b.c".to_string()));
+        assert!(invalid(|r| r.target_table = "archive.".to_string()));
+        assert!(invalid(|r| r.columns = Some(vec![])));
+        assert!(invalid(|r| r.batch_size = Some(0)));
+    }
+
     #[test]
     fn test_cross_database_request_validation() {
         // Valid request with 2 connections
@@ -350,6 +588,10 @@ mod tests {
                     query: "SELECT id, username FROM users".to_string(),
                     row_count: 10,
                     execution_time_ms: 5,
+                    pushed_predicates: vec![],
+                    estimated_rows: Some(10),
+                    broadcast_keys: None,
+                    cache: None,
                 },
                 SubQueryExecution {
                     connection_id: "conn2".to_string(),
@@ -357,6 +599,10 @@ mod tests {
                     query: "SELECT id, user_id, total FROM orders".to_string(),
                     row_count: 25,
                     execution_time_ms: 8,
+                    pushed_predicates: vec![],
+                    estimated_rows: None,
+                    broadcast_keys: Some(10),
+                    cache: None,
                 },
             ],
             vec![serde_json::json!({"username": "alice", "total": 100})],
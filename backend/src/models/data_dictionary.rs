@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{Column, DatabaseMetadata};
+
+/// Longest description a data dictionary entry may carry
+pub const MAX_DICTIONARY_DESCRIPTION_LENGTH: usize = 4000;
+
+/// Most tags a data dictionary entry may carry
+pub const MAX_DICTIONARY_TAGS: usize = 20;
+
+/// Description and tags of a table or view, or of one of its columns, kept
+/// per connection and merged into its metadata
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DictionaryEntry {
+    pub connection_id: String,
+    /// Schema of the table; None matches the table in any schema
+    pub schema: Option<String>,
+    pub table: String,
+    /// Column described; None for the table itself
+    pub column: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub updated_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `PUT /api/connections/{id}/dictionary`
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DictionaryEntryRequest {
+    pub schema: Option<String>,
+    pub table: String,
+    pub column: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl DictionaryEntry {
+    /// Entry of a request, with names and description trimmed and tags
+    /// trimmed, lowercased and listed once
+    pub fn new(connection_id: String, request: DictionaryEntryRequest, updated_by: Option<String>) -> Result<Self, String> {
+        let table = request.table.trim().to_string();
+        if table.is_empty() {
+            return Err("Table name cannot be empty".to_string());
+        }
+        let description = request
+            .description
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty());
+        if description
+            .as_ref()
+            .is_some_and(|description| description.chars().count() > MAX_DICTIONARY_DESCRIPTION_LENGTH)
+        {
+            return Err(format!(
+                "Description exceeds {} characters",
+                MAX_DICTIONARY_DESCRIPTION_LENGTH
+            ));
+        }
+
+        let mut tags: Vec<String> = request
+            .tags
+            .iter()
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        if tags.len() > MAX_DICTIONARY_TAGS {
+            return Err(format!("At most {} tags are allowed", MAX_DICTIONARY_TAGS));
+        }
+        if description.is_none() && tags.is_empty() {
+            return Err("A description or at least one tag is required".to_string());
+        }
+
+        Ok(Self {
+            connection_id,
+            schema: non_empty(request.schema),
+            table,
+            column: non_empty(request.column),
+            description,
+            tags,
+            updated_by,
+            updated_at: Utc::now(),
+        })
+    }
+
+    /// Whether the table or view (and column) the entry describes exists in `metadata`
+    pub fn found_in(&self, metadata: &DatabaseMetadata) -> bool {
+        let has_column = |columns: &[Column]| {
+            self.column
+                .as_deref()
+                .is_none_or(|name| columns.iter().any(|column| column.name.eq_ignore_ascii_case(name)))
+        };
+        metadata
+            .tables
+            .iter()
+            .any(|table| self.describes(table.schema.as_deref(), &table.name) && has_column(&table.columns))
+            || metadata
+                .views
+                .iter()
+                .any(|view| self.describes(view.schema.as_deref(), &view.name) && has_column(&view.columns))
+    }
+
+    fn describes(&self, schema: Option<&str>, table: &str) -> bool {
+        self.table.eq_ignore_ascii_case(table)
+            && self
+                .schema
+                .as_deref()
+                .is_none_or(|entry_schema| schema.is_some_and(|schema| schema.eq_ignore_ascii_case(entry_schema)))
+    }
+}
+
+fn non_empty(name: Option<String>) -> Option<String> {
+    name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+impl DatabaseMetadata {
+    /// Merge the descriptions and tags of a data dictionary into the tables,
+    /// views and columns they describe
+    ///
+    /// Dictionary descriptions replace those read from the database; an entry
+    /// for a specific schema wins over one for any schema.
+    pub fn annotate(&mut self, entries: &[DictionaryEntry]) {
+        if entries.is_empty() {
+            return;
+        }
+        // Entries for any schema first, so schema-specific ones are applied last
+        let mut ordered: Vec<&DictionaryEntry> = entries.iter().collect();
+        ordered.sort_by_key(|entry| entry.schema.is_some());
+
+        for entry in ordered {
+            for table in &mut self.tables {
+                if entry.describes(table.schema.as_deref(), &table.name) {
+                    match &entry.column {
+                        Some(column) => annotate_column(&mut table.columns, column, entry),
+                        None => {
+                            if entry.description.is_some() {
+                                table.description = entry.description.clone();
+                            }
+                            table.tags = entry.tags.clone();
+                        }
+                    }
+                }
+            }
+            for view in &mut self.views {
+                if entry.describes(view.schema.as_deref(), &view.name) {
+                    match &entry.column {
+                        Some(column) => annotate_column(&mut view.columns, column, entry),
+                        None => {
+                            if entry.description.is_some() {
+                                view.description = entry.description.clone();
+                            }
+                            view.tags = entry.tags.clone();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn annotate_column(columns: &mut [Column], name: &str, entry: &DictionaryEntry) {
+    if let Some(column) = columns.iter_mut().find(|column| column.name.eq_ignore_ascii_case(name)) {
+        if entry.description.is_some() {
+            column.description = entry.description.clone();
+        }
+        column.tags = entry.tags.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Table;
+
+    fn request(schema: Option<&str>, table: &str, column: Option<&str>, description: &str, tags: &[&str]) -> DictionaryEntryRequest {
+        DictionaryEntryRequest {
+            schema: schema.map(str::to_string),
+            table: table.to_string(),
+            column: column.map(str::to_string),
+            description: Some(description.to_string()),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn entry(schema: Option<&str>, table: &str, column: Option<&str>, description: &str, tags: &[&str]) -> DictionaryEntry {
+        DictionaryEntry::new("c".to_string(), request(schema, table, column, description, tags), None).unwrap()
+    }
+
+    #[test]
+    fn test_new_normalizes_entry() {
+        let entry = entry(Some(" "), " orders ", Some(""), "  Customer orders ", &["Finance", " finance", ""]);
+        assert_eq!(entry.schema, None);
+        assert_eq!(entry.table, "orders");
+        assert_eq!(entry.column, None);
+        assert_eq!(entry.description.as_deref(), Some("Customer orders"));
+        assert_eq!(entry.tags, vec!["finance"]);
+
+        assert!(DictionaryEntry::new("c".to_string(), request(None, "orders", None, " ", &[]), None).is_err());
+        assert!(DictionaryEntry::new("c".to_string(), request(None, "", None, "x", &[]), None).is_err());
+    }
+
+    #[test]
+    fn test_annotate_metadata() {
+        let column = |name: &str| Column {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        };
+        let table = |schema: &str| Table {
+            name: "orders".to_string(),
+            schema: Some(schema.to_string()),
+            columns: vec![column("id"), column("status")],
+            row_count: None,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        };
+        let mut metadata = DatabaseMetadata::new(
+            "c".to_string(),
+            vec![table("sales"), table("archive")],
+            vec![],
+            vec!["sales".to_string(), "archive".to_string()],
+        );
+
+        assert!(entry(None, "orders", Some("status"), "x", &[]).found_in(&metadata));
+        assert!(!entry(Some("public"), "orders", None, "x", &[]).found_in(&metadata));
+        assert!(!entry(None, "orders", Some("total"), "x", &[]).found_in(&metadata));
+
+        metadata.annotate(&[
+            entry(Some("sales"), "orders", None, "Current orders", &["finance"]),
+            entry(None, "ORDERS", None, "Orders", &[]),
+            entry(None, "orders", Some("Status"), "Fulfilment state", &["enum"]),
+            entry(None, "missing", None, "Ignored", &[]),
+        ]);
+
+        assert_eq!(metadata.tables[0].description.as_deref(), Some("Current orders"));
+        assert_eq!(metadata.tables[0].tags, vec!["finance"]);
+        assert_eq!(metadata.tables[1].description.as_deref(), Some("Orders"));
+        assert_eq!(metadata.tables[1].columns[1].description.as_deref(), Some("Fulfilment state"));
+        assert_eq!(metadata.tables[1].columns[1].tags, vec!["enum"]);
+        assert_eq!(metadata.tables[1].columns[0].description, None);
+    }
+}
@@ -1,16 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// Domain represents an organizational unit for grouping database connections, queries, and history.
 /// Provides complete data isolation between different domains (e.g., Production, Development, Analytics).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Domain {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Incremented on every update (optimistic concurrency control)
+    pub version: i64,
 }
 
 impl Domain {
@@ -26,6 +29,7 @@ impl Domain {
             description,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            version: 1,
         })
     }
 
@@ -72,27 +76,30 @@ impl Domain {
 }
 
 /// Request payload for creating a new domain
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDomainRequest {
     pub name: String,
     pub description: Option<String>,
 }
 
 /// Request payload for updating an existing domain
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateDomainRequest {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// Current version, required unless an If-Match header is sent
+    pub expected_version: Option<i64>,
 }
 
 /// Response payload for domain with resource counts
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DomainResponse {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
     pub connection_count: usize,
     pub saved_query_count: usize,
     pub query_history_count: usize,
@@ -106,6 +113,7 @@ impl From<Domain> for DomainResponse {
             description: domain.description,
             created_at: domain.created_at,
             updated_at: domain.updated_at,
+            version: domain.version,
             connection_count: 0,
             saved_query_count: 0,
             query_history_count: 0,
@@ -127,6 +135,7 @@ impl DomainResponse {
             description: domain.description,
             created_at: domain.created_at,
             updated_at: domain.updated_at,
+            version: domain.version,
             connection_count,
             saved_query_count,
             query_history_count,
@@ -134,6 +143,208 @@ impl DomainResponse {
     }
 }
 
+// ============================================================================
+// Domain Settings
+// ============================================================================
+
+/// Row limit applied to queries without an explicit LIMIT
+pub const DEFAULT_ROW_LIMIT: u64 = 1000;
+/// Query timeout used when a domain does not override it
+pub const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 30;
+/// Upper bound for a domain's row limits
+pub const MAX_ROW_LIMIT: u64 = 100_000;
+/// Upper bound for a domain's query timeouts
+pub const MAX_QUERY_TIMEOUT_SECS: u64 = 3600;
+/// Shortest scheduled metadata refresh interval a domain may set
+pub const MIN_METADATA_REFRESH_INTERVAL_SECS: u64 = 60;
+/// Execution time from which a query is flagged as slow in history
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 1000;
+
+/// Per-domain query defaults and restrictions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DomainSettings {
+    pub domain_id: String,
+    /// LIMIT applied to queries that do not specify one
+    pub default_row_limit: u64,
+    /// Query timeout in seconds
+    pub default_timeout_secs: u64,
+    /// Cap on the `limit_value` a request may ask for
+    pub max_row_limit: u64,
+    /// Cap on the `timeout_secs` a request may ask for
+    pub max_timeout_secs: u64,
+    /// History retention override in days (None uses the server setting, 0 keeps forever)
+    pub history_retention_days: Option<u32>,
+    /// Database types connections in this domain may use (empty allows all)
+    pub allowed_database_types: Vec<String>,
+    /// Monthly LLM token budget override (None uses the server setting, 0 is unlimited)
+    pub llm_monthly_token_budget: Option<u64>,
+    /// Tables and columns (`table`, `column` or `table.column`, lowercase) never
+    /// described to the LLM; their values are masked in sample data
+    pub llm_denied_identifiers: Vec<String>,
+    /// Scheduled metadata refresh interval override in seconds (None uses the
+    /// server setting, 0 disables)
+    pub metadata_refresh_interval_secs: Option<u64>,
+    /// Execution time in milliseconds from which a query is flagged as slow
+    /// (0 disables the flag)
+    #[serde(default = "default_slow_query_threshold_ms")]
+    pub slow_query_threshold_ms: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_slow_query_threshold_ms() -> u64 {
+    DEFAULT_SLOW_QUERY_THRESHOLD_MS
+}
+
+impl DomainSettings {
+    /// Settings used for domains that never customized them
+    pub fn defaults(domain_id: String) -> Self {
+        Self {
+            domain_id,
+            default_row_limit: DEFAULT_ROW_LIMIT,
+            default_timeout_secs: DEFAULT_QUERY_TIMEOUT_SECS,
+            max_row_limit: MAX_ROW_LIMIT,
+            max_timeout_secs: MAX_QUERY_TIMEOUT_SECS,
+            history_retention_days: None,
+            allowed_database_types: Vec::new(),
+            llm_monthly_token_budget: None,
+            llm_denied_identifiers: Vec::new(),
+            metadata_refresh_interval_secs: None,
+            slow_query_threshold_ms: DEFAULT_SLOW_QUERY_THRESHOLD_MS,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Whether connections of the given database type may be queried in this domain
+    pub fn allows_database_type(&self, database_type: &str) -> bool {
+        self.allowed_database_types.is_empty()
+            || self
+                .allowed_database_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(database_type))
+    }
+
+    /// Row limit of a request: its `limit_value` or the default, capped at `max_row_limit`
+    pub fn row_limit(&self, requested: Option<u64>) -> u64 {
+        requested.unwrap_or(self.default_row_limit).clamp(1, self.max_row_limit.max(1))
+    }
+
+    /// Timeout of a request: its `timeout_secs` or the default, capped at `max_timeout_secs`
+    pub fn timeout_secs(&self, requested: Option<u64>) -> u64 {
+        requested.unwrap_or(self.default_timeout_secs).clamp(1, self.max_timeout_secs.max(1))
+    }
+
+    /// Whether a query that ran for `execution_time_ms` counts as slow
+    pub fn is_slow(&self, execution_time_ms: u64) -> bool {
+        self.slow_query_threshold_ms > 0 && execution_time_ms >= self.slow_query_threshold_ms
+    }
+
+    /// Apply a partial update, validating the resulting values
+    pub fn apply(&mut self, update: UpdateDomainSettingsRequest) -> Result<(), String> {
+        for (field, value) in [("default_row_limit", update.default_row_limit), ("max_row_limit", update.max_row_limit)] {
+            if value.is_some_and(|limit| limit == 0 || limit > MAX_ROW_LIMIT) {
+                return Err(format!("{} must be between 1 and {}", field, MAX_ROW_LIMIT));
+            }
+        }
+        for (field, value) in [
+            ("default_timeout_secs", update.default_timeout_secs),
+            ("max_timeout_secs", update.max_timeout_secs),
+        ] {
+            if value.is_some_and(|timeout| timeout == 0 || timeout > MAX_QUERY_TIMEOUT_SECS) {
+                return Err(format!("{} must be between 1 and {}", field, MAX_QUERY_TIMEOUT_SECS));
+            }
+        }
+        if let Some(Some(interval)) = update.metadata_refresh_interval_secs {
+            if interval > 0 && interval < MIN_METADATA_REFRESH_INTERVAL_SECS {
+                return Err(format!(
+                    "metadata_refresh_interval_secs must be 0 or at least {}",
+                    MIN_METADATA_REFRESH_INTERVAL_SECS
+                ));
+            }
+        }
+        let row_limit = update.default_row_limit.unwrap_or(self.default_row_limit);
+        let max_row_limit = update.max_row_limit.unwrap_or(self.max_row_limit);
+        if row_limit > max_row_limit {
+            return Err("default_row_limit must not exceed max_row_limit".to_string());
+        }
+        let timeout = update.default_timeout_secs.unwrap_or(self.default_timeout_secs);
+        let max_timeout = update.max_timeout_secs.unwrap_or(self.max_timeout_secs);
+        if timeout > max_timeout {
+            return Err("default_timeout_secs must not exceed max_timeout_secs".to_string());
+        }
+
+        self.default_row_limit = row_limit;
+        self.max_row_limit = max_row_limit;
+        self.default_timeout_secs = timeout;
+        self.max_timeout_secs = max_timeout;
+        if let Some(retention) = update.history_retention_days {
+            self.history_retention_days = retention;
+        }
+        if let Some(types) = update.allowed_database_types {
+            let mut normalized: Vec<String> = types.iter().map(|t| t.trim().to_lowercase()).collect();
+            normalized.sort();
+            normalized.dedup();
+            self.allowed_database_types = normalized;
+        }
+        if let Some(budget) = update.llm_monthly_token_budget {
+            self.llm_monthly_token_budget = budget;
+        }
+        if let Some(identifiers) = update.llm_denied_identifiers {
+            let mut normalized: Vec<String> = identifiers
+                .iter()
+                .map(|i| i.trim().to_lowercase())
+                .filter(|i| !i.is_empty())
+                .collect();
+            normalized.sort();
+            normalized.dedup();
+            self.llm_denied_identifiers = normalized;
+        }
+        if let Some(interval) = update.metadata_refresh_interval_secs {
+            self.metadata_refresh_interval_secs = interval;
+        }
+        if let Some(threshold) = update.slow_query_threshold_ms {
+            self.slow_query_threshold_ms = threshold;
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+}
+
+/// Request payload for updating domain settings (omitted fields are unchanged)
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdateDomainSettingsRequest {
+    pub default_row_limit: Option<u64>,
+    pub default_timeout_secs: Option<u64>,
+    pub max_row_limit: Option<u64>,
+    pub max_timeout_secs: Option<u64>,
+    /// `null` resets to the server-wide retention
+    #[serde(default, with = "double_option")]
+    pub history_retention_days: Option<Option<u32>>,
+    pub allowed_database_types: Option<Vec<String>>,
+    /// `null` resets to the server-wide LLM token budget
+    #[serde(default, with = "double_option")]
+    pub llm_monthly_token_budget: Option<Option<u64>>,
+    /// Replaces the deny-list of tables and columns kept out of LLM prompts
+    pub llm_denied_identifiers: Option<Vec<String>>,
+    /// `null` resets to the server-wide metadata refresh interval
+    #[serde(default, with = "double_option")]
+    pub metadata_refresh_interval_secs: Option<Option<u64>>,
+    /// 0 stops flagging slow queries
+    pub slow_query_threshold_ms: Option<u64>,
+}
+
+/// Distinguishes an omitted field (`None`) from an explicit `null` (`Some(None)`)
+mod double_option {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,4 +407,67 @@ mod tests {
 
         assert!(domain.updated_at > original_updated);
     }
+
+    #[test]
+    fn test_domain_settings_apply() {
+        let mut settings = DomainSettings::defaults("d1".to_string());
+        assert!(settings.allows_database_type("mysql"));
+
+        let update: UpdateDomainSettingsRequest = serde_json::from_str(
+            r#"{"default_row_limit": 500, "history_retention_days": 7, "allowed_database_types": ["PostgreSQL", "postgresql"]}"#,
+        )
+        .unwrap();
+        settings.apply(update).unwrap();
+        assert_eq!(settings.default_row_limit, 500);
+        assert_eq!(settings.default_timeout_secs, DEFAULT_QUERY_TIMEOUT_SECS);
+        assert_eq!(settings.history_retention_days, Some(7));
+        assert_eq!(settings.allowed_database_types, vec!["postgresql"]);
+        assert!(!settings.allows_database_type("mysql"));
+
+        let reset: UpdateDomainSettingsRequest = serde_json::from_str(r#"{"history_retention_days": null}"#).unwrap();
+        settings.apply(reset).unwrap();
+        assert_eq!(settings.history_retention_days, None);
+
+        let budget: UpdateDomainSettingsRequest = serde_json::from_str(r#"{"llm_monthly_token_budget": 0}"#).unwrap();
+        settings.apply(budget).unwrap();
+        assert_eq!(settings.llm_monthly_token_budget, Some(0));
+
+        let denied: UpdateDomainSettingsRequest =
+            serde_json::from_str(r#"{"llm_denied_identifiers": [" Customers.Email", "salaries", "", "salaries"]}"#).unwrap();
+        settings.apply(denied).unwrap();
+        assert_eq!(settings.llm_denied_identifiers, vec!["customers.email", "salaries"]);
+
+        let refresh: UpdateDomainSettingsRequest =
+            serde_json::from_str(r#"{"metadata_refresh_interval_secs": 3600}"#).unwrap();
+        settings.apply(refresh).unwrap();
+        assert_eq!(settings.metadata_refresh_interval_secs, Some(3600));
+        let too_often: UpdateDomainSettingsRequest =
+            serde_json::from_str(r#"{"metadata_refresh_interval_secs": 5}"#).unwrap();
+        assert!(settings.apply(too_often).is_err());
+
+        assert!(settings.is_slow(DEFAULT_SLOW_QUERY_THRESHOLD_MS));
+        let no_slow: UpdateDomainSettingsRequest = serde_json::from_str(r#"{"slow_query_threshold_ms": 0}"#).unwrap();
+        settings.apply(no_slow).unwrap();
+        assert!(!settings.is_slow(u64::MAX));
+
+        let invalid = UpdateDomainSettingsRequest {
+            default_timeout_secs: Some(0),
+            ..Default::default()
+        };
+        assert!(settings.apply(invalid).is_err());
+
+        // Request overrides fall back to the defaults and are capped
+        let caps: UpdateDomainSettingsRequest =
+            serde_json::from_str(r#"{"max_row_limit": 2000, "max_timeout_secs": 60}"#).unwrap();
+        settings.apply(caps).unwrap();
+        assert_eq!(settings.row_limit(None), 500);
+        assert_eq!(settings.row_limit(Some(50_000)), 2000);
+        assert_eq!(settings.timeout_secs(Some(5)), 5);
+        assert_eq!(settings.timeout_secs(Some(600)), 60);
+        let below_default = UpdateDomainSettingsRequest {
+            max_row_limit: Some(100),
+            ..Default::default()
+        };
+        assert!(settings.apply(below_default).is_err());
+    }
 }
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// File format of a query result export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+    Xlsx,
+    /// Arrow IPC stream, for clients that read Arrow directly (pyarrow, arrow for R)
+    Arrow,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Arrow => "arrows",
+        }
+    }
+
+    /// Whether the file can be written batch by batch (Parquet and Excel files
+    /// are only complete once their footer / archive directory is written, and
+    /// an Arrow stream is written by one writer that owns the schema message)
+    pub fn is_streamable(&self) -> bool {
+        matches!(self, ExportFormat::Csv | ExportFormat::Ndjson)
+    }
+}
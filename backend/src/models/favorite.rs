@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::query::QueryHistoryStatus;
+
+/// Kind of resource a user can mark as a favorite
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FavoriteType {
+    Connection,
+    SavedQuery,
+}
+
+impl FavoriteType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FavoriteType::Connection => "connection",
+            FavoriteType::SavedQuery => "saved_query",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "connection" | "connections" => Ok(FavoriteType::Connection),
+            "saved_query" | "saved_queries" => Ok(FavoriteType::SavedQuery),
+            _ => Err(format!(
+                "Unknown favorite type: {}. Expected connection or saved_query",
+                s
+            )),
+        }
+    }
+}
+
+/// A connection or saved query a user marked as a favorite
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Favorite {
+    pub resource_type: FavoriteType,
+    pub resource_id: String,
+    pub domain_id: String,
+    /// Current name of the connection or saved query
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A query the caller ran recently, derived from query history
+///
+/// Runs of a saved query are grouped by the saved query, other runs by
+/// connection and SQL text.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecentQuery {
+    /// Connection of the latest run (None for cross-database queries)
+    pub connection_id: Option<String>,
+    pub saved_query_id: Option<String>,
+    /// Name of the saved query, if it still exists
+    pub saved_query_name: Option<String>,
+    /// SQL of the latest run
+    pub query_text: String,
+    /// Status of the latest run
+    pub last_status: QueryHistoryStatus,
+    pub last_executed_at: DateTime<Utc>,
+    /// Runs in the history
+    pub executions: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_favorite_type_round_trip() {
+        for kind in [FavoriteType::Connection, FavoriteType::SavedQuery] {
+            assert_eq!(FavoriteType::from_str(kind.as_str()).unwrap(), kind);
+        }
+        assert_eq!(FavoriteType::from_str("saved_queries").unwrap(), FavoriteType::SavedQuery);
+        assert!(FavoriteType::from_str("domain").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Verdict on SQL the LLM generated for a question
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackRating {
+    /// The generated SQL answers the question
+    Up,
+    /// It doesn't; `corrected_sql` may hold SQL that does
+    Down,
+}
+
+impl FeedbackRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "up" => Self::Up,
+            _ => Self::Down,
+        }
+    }
+}
+
+/// Rating or correction of generated SQL, kept per domain
+///
+/// Questions rated up or corrected become examples: the ones closest to a new
+/// question of the domain go into its prompt.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SqlFeedback {
+    pub id: String,
+    pub domain_id: String,
+    /// Connection the SQL was generated for (null once it is deleted)
+    pub connection_id: Option<String>,
+    /// Examples are only given to prompts for the same database type
+    pub database_type: String,
+    pub question: String,
+    pub generated_sql: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corrected_sql: Option<String>,
+    pub rating: FeedbackRating,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Principal subject that gave the feedback
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SqlFeedback {
+    pub fn new(
+        domain_id: String,
+        connection_id: String,
+        database_type: String,
+        request: SqlFeedbackRequest,
+        created_by: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            domain_id,
+            connection_id: Some(connection_id),
+            database_type,
+            question: request.question.trim().to_string(),
+            generated_sql: request.generated_sql.trim().to_string(),
+            corrected_sql: request
+                .corrected_sql
+                .map(|sql| sql.trim().to_string())
+                .filter(|sql| !sql.is_empty()),
+            rating: request.rating,
+            comment: request.comment.filter(|comment| !comment.trim().is_empty()),
+            created_by,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// The example this feedback makes, if any: the corrected SQL, or the generated
+    /// SQL when rated up
+    pub fn example(&self) -> Option<SqlExample> {
+        let sql = match (&self.corrected_sql, self.rating) {
+            (Some(corrected), _) => corrected,
+            (None, FeedbackRating::Up) => &self.generated_sql,
+            (None, FeedbackRating::Down) => return None,
+        };
+        Some(SqlExample {
+            question: self.question.clone(),
+            sql: sql.clone(),
+        })
+    }
+}
+
+/// `POST /api/connections/{id}/nl-feedback`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SqlFeedbackRequest {
+    pub question: String,
+    /// SQL the LLM generated for the question (`generated_sql` of the response)
+    pub generated_sql: String,
+    pub rating: FeedbackRating,
+    /// SQL that answers the question, when the generated SQL doesn't
+    #[serde(default)]
+    pub corrected_sql: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// A question with the SQL that answers it, given to the LLM as an example
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlExample {
+    pub question: String,
+    pub sql: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_example() {
+        let request = |rating, corrected_sql: Option<&str>| SqlFeedbackRequest {
+            question: " Orders per month ".to_string(),
+            generated_sql: "SELECT COUNT(*) FROM orders".to_string(),
+            rating,
+            corrected_sql: corrected_sql.map(str::to_string),
+            comment: Some(" ".to_string()),
+        };
+        let feedback = |rating, corrected_sql| {
+            SqlFeedback::new("d".to_string(), "c".to_string(), "postgresql".to_string(), request(rating, corrected_sql), None)
+        };
+
+        let up = feedback(FeedbackRating::Up, None);
+        assert_eq!(up.question, "Orders per month");
+        assert_eq!(up.comment, None);
+        assert_eq!(up.example().unwrap().sql, "SELECT COUNT(*) FROM orders");
+
+        assert!(feedback(FeedbackRating::Down, None).example().is_none());
+        assert!(feedback(FeedbackRating::Down, Some("  ")).example().is_none());
+        let corrected = feedback(
+            FeedbackRating::Down,
+            Some("SELECT date_trunc('month', created_at), COUNT(*) FROM orders GROUP BY 1"),
+        );
+        assert!(corrected.example().unwrap().sql.starts_with("SELECT date_trunc"));
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// `POST /api/connections/{id}/query/summarize`
+///
+/// The rows are the result the client already holds (masked like any result);
+/// nothing runs on the database.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SummarizeResultRequest {
+    /// SQL that produced the rows
+    pub query: String,
+    /// Natural language question the query answers, if any
+    #[serde(default)]
+    pub question: Option<String>,
+    /// Result rows (objects keyed by column)
+    #[schema(value_type = Vec<Object>)]
+    pub results: Vec<serde_json::Value>,
+    /// Rows of the full result, when `results` holds only part of it
+    #[serde(default)]
+    pub row_count: Option<usize>,
+}
+
+/// Where a result summary came from
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SummarySource {
+    Llm,
+    /// Column statistics, without an LLM gateway or when the LLM failed
+    Statistics,
+}
+
+/// Takeaways of a query result for people who don't read result grids
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResultSummary {
+    /// A few sentences on what the result shows
+    pub summary: String,
+    /// Notable facts (extremes, concentrations, gaps), one per entry
+    pub insights: Vec<String>,
+    /// Questions worth asking next, usable as natural language queries
+    pub follow_up_questions: Vec<String>,
+    /// Rows given to the LLM
+    pub sampled_rows: usize,
+    pub row_count: usize,
+    pub source: SummarySource,
+    /// Why the summary comes from column statistics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_error: Option<String>,
+}
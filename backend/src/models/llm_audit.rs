@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// What was held back from a prompt before it was sent to the LLM
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PromptRedactions {
+    /// Tables and columns of the domain's deny-list left out of the schema
+    pub withheld: Vec<String>,
+    /// Columns whose values were masked in sample data
+    pub masked_columns: Vec<String>,
+}
+
+impl PromptRedactions {
+    pub fn is_empty(&self) -> bool {
+        self.withheld.is_empty() && self.masked_columns.is_empty()
+    }
+
+    /// Add the redactions of another part of the prompt, each listed once
+    pub fn merge(&mut self, other: PromptRedactions) {
+        for (target, items) in [(&mut self.withheld, other.withheld), (&mut self.masked_columns, other.masked_columns)] {
+            for item in items {
+                if !target.contains(&item) {
+                    target.push(item);
+                }
+            }
+        }
+    }
+}
+
+/// Who a prompt is sent for, recorded with it in the audit log
+#[derive(Debug, Clone, Default)]
+pub struct LlmAuditContext {
+    pub domain_id: Option<String>,
+    pub connection_id: Option<String>,
+    /// Principal subject that asked
+    pub principal: Option<String>,
+}
+
+/// A prompt as it left for the LLM gateway, with what was redacted from it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LlmAuditEntry {
+    pub id: String,
+    pub domain_id: Option<String>,
+    pub connection_id: Option<String>,
+    pub principal: Option<String>,
+    /// What the prompt asks for, e.g. `generate_sql` or `summarize_result`
+    pub purpose: String,
+    /// The prompt exactly as sent
+    pub prompt: String,
+    pub redactions: PromptRedactions,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LlmAuditEntry {
+    pub fn new(context: &LlmAuditContext, purpose: &str, prompt: &str, redactions: PromptRedactions) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            domain_id: context.domain_id.clone(),
+            connection_id: context.connection_id.clone(),
+            principal: context.principal.clone(),
+            purpose: purpose.to_string(),
+            prompt: prompt.to_string(),
+            redactions,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// State of a materialized view's latest refresh
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MaterializationStatus {
+    /// Not refreshed yet
+    Pending,
+    Refreshing,
+    Ready,
+    /// The latest refresh failed (an earlier snapshot, if any, is still read)
+    Failed,
+}
+
+impl MaterializationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaterializationStatus::Pending => "pending",
+            MaterializationStatus::Refreshing => "refreshing",
+            MaterializationStatus::Ready => "ready",
+            MaterializationStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(MaterializationStatus::Pending),
+            "refreshing" => Ok(MaterializationStatus::Refreshing),
+            "ready" => Ok(MaterializationStatus::Ready),
+            "failed" => Ok(MaterializationStatus::Failed),
+            _ => Err(format!("Unknown materialization status: {}", s)),
+        }
+    }
+}
+
+/// Parquet snapshot of a saved query view's result
+///
+/// Cross-database queries read a materialized view from its latest snapshot
+/// instead of running the view's query on its connection.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ViewMaterialization {
+    pub saved_query_id: String,
+    pub domain_id: String,
+    /// Seconds between scheduled refreshes (None refreshes on demand only)
+    pub refresh_interval_secs: Option<u64>,
+    pub status: MaterializationStatus,
+    /// Rows of the latest snapshot
+    pub row_count: Option<u64>,
+    /// Parquet file size of the latest snapshot
+    pub size_bytes: Option<u64>,
+    /// When the latest snapshot was taken
+    pub refreshed_at: Option<DateTime<Utc>>,
+    /// When the latest refresh started, whether or not it succeeded
+    pub attempted_at: Option<DateTime<Utc>>,
+    /// Error of the latest refresh, if it failed
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ViewMaterialization {
+    pub fn new(saved_query_id: String, domain_id: String, refresh_interval_secs: Option<u64>) -> Self {
+        Self {
+            saved_query_id,
+            domain_id,
+            refresh_interval_secs,
+            status: MaterializationStatus::Pending,
+            row_count: None,
+            size_bytes: None,
+            refreshed_at: None,
+            attempted_at: None,
+            last_error: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether a snapshot was taken, so queries can read the view from it
+    pub fn has_snapshot(&self) -> bool {
+        self.refreshed_at.is_some()
+    }
+
+    /// Whether a scheduled refresh is due at `now`
+    ///
+    /// The interval counts from the latest attempt, so failing refreshes are
+    /// retried once per interval. Materializations never refreshed are due right away.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let Some(interval) = self.refresh_interval_secs else {
+            return false;
+        };
+        match self.attempted_at {
+            Some(attempted_at) => now >= attempted_at + chrono::Duration::seconds(interval as i64),
+            None => true,
+        }
+    }
+}
+
+/// Request to materialize a saved query view
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct MaterializeViewRequest {
+    /// Seconds between scheduled refreshes (omit to refresh on demand only)
+    pub refresh_interval_secs: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_materialization_is_due() {
+        let now = Utc::now();
+        let mut materialization = ViewMaterialization::new("query-1".to_string(), "domain-1".to_string(), None);
+        assert!(!materialization.is_due(now));
+
+        materialization.refresh_interval_secs = Some(3600);
+        assert!(materialization.is_due(now));
+
+        materialization.attempted_at = Some(now - chrono::Duration::minutes(30));
+        assert!(!materialization.is_due(now));
+        assert!(materialization.is_due(now + chrono::Duration::minutes(30)));
+
+        // A failed attempt waits for the next interval too
+        materialization.refreshed_at = Some(now - chrono::Duration::days(1));
+        materialization.status = MaterializationStatus::Failed;
+        assert!(!materialization.is_due(now));
+    }
+
+    #[test]
+    fn test_status_round_trips() {
+        for status in [
+            MaterializationStatus::Pending,
+            MaterializationStatus::Refreshing,
+            MaterializationStatus::Ready,
+            MaterializationStatus::Failed,
+        ] {
+            assert_eq!(MaterializationStatus::from_str(status.as_str()), Ok(status));
+        }
+        assert!(MaterializationStatus::from_str("stale").is_err());
+    }
+}
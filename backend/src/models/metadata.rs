@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DatabaseMetadata {
     pub id: String,
     pub connection_id: String,
@@ -33,39 +35,162 @@ impl DatabaseMetadata {
             version: 1,
         }
     }
-
-    pub fn increment_version(&mut self) {
-        self.version += 1;
-        self.retrieved_at = chrono::Utc::now();
-        // Update metadata_json as well
-        let json_value = serde_json::json!({
-            "tables": &self.tables,
-            "views": &self.views,
-            "schemas": &self.schemas,
-        });
-        self.metadata_json = serde_json::to_string(&json_value).unwrap_or_default();
-    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Table {
     pub name: String,
     pub schema: Option<String>,
     pub columns: Vec<Column>,
+    /// Row count from the database's statistics, an estimate on most
+    /// databases (`GET .../tables/{schema}/{table}/count` counts exactly)
     pub row_count: Option<i64>,
+    /// Storage size in bytes, when the database reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
     pub description: Option<String>,
+    /// Tags from the connection's data dictionary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Partitioning and distribution of Druid datasources and Doris tables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_metadata: Option<EngineMetadata>,
+}
+
+/// Partitions listed in [`EngineMetadata::partitions`]: the first one and
+/// the most recent ones
+pub const MAX_ENGINE_PARTITIONS: usize = 10;
+
+/// How an analytical engine lays out a table's data
+///
+/// Queries that filter on the partition keys read only the matching
+/// partitions (Doris) or segments (Druid), so the layout matters for
+/// writing efficient SQL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct EngineMetadata {
+    /// `druid` or `doris`
+    pub engine: String,
+    /// `RANGE` or `LIST` for Doris, `TIME` for Druid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_method: Option<String>,
+    /// Columns the data is partitioned on (`__time` for Druid)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partition_keys: Vec<String>,
+    /// Doris partitions or published Druid segments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_count: Option<u64>,
+    /// Start of the earliest segment interval (Druid)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_start: Option<String>,
+    /// End of the latest segment interval (Druid)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_end: Option<String>,
+    /// Partitions in declaration order, at most [`MAX_ENGINE_PARTITIONS`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partitions: Vec<PartitionInfo>,
+    /// `HASH` or `RANDOM` bucketing (Doris)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distribution_keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buckets: Option<u64>,
+    /// Data model (`DUP`, `AGG`, `UNI`) of a Doris table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_model: Option<String>,
+    /// Key columns of the data model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_columns: Vec<String>,
+}
+
+/// A partition and the values it holds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PartitionInfo {
+    pub name: String,
+    /// Range or list of partition key values, as the engine describes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_count: Option<i64>,
+}
+
+impl EngineMetadata {
+    /// Keep the first of `partitions` and the most recent ones, up to
+    /// [`MAX_ENGINE_PARTITIONS`]
+    pub fn with_partitions(mut self, mut partitions: Vec<PartitionInfo>) -> Self {
+        self.partition_count = Some(partitions.len() as u64);
+        if partitions.len() > MAX_ENGINE_PARTITIONS {
+            partitions.drain(1..partitions.len() + 1 - MAX_ENGINE_PARTITIONS);
+        }
+        self.partitions = partitions;
+        self
+    }
+
+    /// One-line description for schema context in prompts
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.partition_keys.is_empty() {
+            let mut part = match &self.partition_method {
+                Some(method) => format!("{} partitioned on {}", method, self.partition_keys.join(", ")),
+                None => format!("partitioned on {}", self.partition_keys.join(", ")),
+            };
+            let mut details = Vec::new();
+            if let Some(count) = self.partition_count {
+                details.push(format!("{} {}", count, if self.engine == "druid" { "segments" } else { "partitions" }));
+            }
+            if let (Some(start), Some(end)) = (&self.range_start, &self.range_end) {
+                details.push(format!("{} to {}", start, end));
+            }
+            if let (Some(first), Some(last)) = (self.partitions.first(), self.partitions.last()) {
+                let describe = |p: &PartitionInfo| match &p.values {
+                    Some(values) => format!("{} {}", p.name, values),
+                    None => p.name.clone(),
+                };
+                if self.partitions.len() == 1 {
+                    details.push(describe(first));
+                } else {
+                    details.push(format!("first {}, last {}", describe(first), describe(last)));
+                }
+            }
+            if !details.is_empty() {
+                part.push_str(&format!(" ({})", details.join("; ")));
+            }
+            parts.push(part);
+        }
+        if self.distribution_type.is_some() || !self.distribution_keys.is_empty() {
+            let mut part = format!("{} distributed", self.distribution_type.as_deref().unwrap_or("HASH"));
+            if !self.distribution_keys.is_empty() {
+                part.push_str(&format!(" on {}", self.distribution_keys.join(", ")));
+            }
+            if let Some(buckets) = self.buckets {
+                part.push_str(&format!(" into {} buckets", buckets));
+            }
+            parts.push(part);
+        }
+        if let Some(model) = &self.table_model {
+            if self.key_columns.is_empty() {
+                parts.push(format!("{} model", model));
+            } else {
+                parts.push(format!("{} model keyed on {}", model, self.key_columns.join(", ")));
+            }
+        }
+        parts.join("; ")
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct View {
     pub name: String,
     pub schema: Option<String>,
     pub columns: Vec<Column>,
     pub definition: Option<String>,
     pub description: Option<String>,
+    /// Tags from the connection's data dictionary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Column {
     pub name: String,
     pub data_type: String,
@@ -75,5 +200,292 @@ pub struct Column {
     pub default_value: Option<String>,
     pub max_length: Option<i32>,
     pub description: Option<String>,
+    /// Distinct values from the database's statistics, when it keeps them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distinct_count: Option<i64>,
+    /// Tags from the connection's data dictionary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+// ============================================================================
+// Metadata Version History
+// ============================================================================
+
+/// Summary of a retained metadata cache version
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MetadataVersionSummary {
+    pub id: String,
+    pub version: i32,
+    pub retrieved_at: chrono::DateTime<chrono::Utc>,
+    pub table_count: usize,
+    pub view_count: usize,
+}
+
+/// A column whose definition changed between two metadata versions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ColumnChange {
+    pub name: String,
+    pub before: Column,
+    pub after: Column,
 }
 
+/// Column-level changes of a table (or view) present in both versions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableDiff {
+    /// Qualified name (`schema.table` when a schema is known)
+    pub name: String,
+    pub added_columns: Vec<Column>,
+    pub removed_columns: Vec<Column>,
+    pub changed_columns: Vec<ColumnChange>,
+}
+
+/// Differences between two metadata versions of a connection
+///
+/// Tables and views are compared together by qualified name. Descriptions
+/// are ignored since they are regenerated on every refresh.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MetadataDiff {
+    pub connection_id: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub added_tables: Vec<String>,
+    pub removed_tables: Vec<String>,
+    pub changed_tables: Vec<TableDiff>,
+}
+
+impl MetadataDiff {
+    /// Compute the differences from `from` to `to`
+    pub fn between(from: &DatabaseMetadata, to: &DatabaseMetadata) -> Self {
+        let before = relations(from);
+        let after = relations(to);
+
+        let added_tables = after
+            .keys()
+            .filter(|name| !before.contains_key(*name))
+            .cloned()
+            .collect();
+        let removed_tables = before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let changed_tables = before
+            .iter()
+            .filter_map(|(name, old_columns)| {
+                let new_columns = after.get(name)?;
+                let diff = diff_columns(name, old_columns, new_columns);
+                let changed = !diff.added_columns.is_empty()
+                    || !diff.removed_columns.is_empty()
+                    || !diff.changed_columns.is_empty();
+                changed.then_some(diff)
+            })
+            .collect();
+
+        Self {
+            connection_id: to.connection_id.clone(),
+            from_version: from.version,
+            to_version: to.version,
+            added_tables,
+            removed_tables,
+            changed_tables,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty() && self.removed_tables.is_empty() && self.changed_tables.is_empty()
+    }
+}
+
+fn qualified_name(schema: &Option<String>, name: &str) -> String {
+    match schema {
+        Some(schema) if !schema.is_empty() => format!("{}.{}", schema, name),
+        _ => name.to_string(),
+    }
+}
+
+fn relations(metadata: &DatabaseMetadata) -> BTreeMap<String, &[Column]> {
+    metadata
+        .tables
+        .iter()
+        .map(|t| (qualified_name(&t.schema, &t.name), t.columns.as_slice()))
+        .chain(
+            metadata
+                .views
+                .iter()
+                .map(|v| (qualified_name(&v.schema, &v.name), v.columns.as_slice())),
+        )
+        .collect()
+}
+
+fn column_definition_changed(before: &Column, after: &Column) -> bool {
+    before.data_type != after.data_type
+        || before.is_nullable != after.is_nullable
+        || before.is_primary_key != after.is_primary_key
+        || before.is_foreign_key != after.is_foreign_key
+        || before.default_value != after.default_value
+        || before.max_length != after.max_length
+}
+
+fn diff_columns(name: &str, before: &[Column], after: &[Column]) -> TableDiff {
+    let before_by_name: BTreeMap<&str, &Column> = before.iter().map(|c| (c.name.as_str(), c)).collect();
+    let after_by_name: BTreeMap<&str, &Column> = after.iter().map(|c| (c.name.as_str(), c)).collect();
+
+    TableDiff {
+        name: name.to_string(),
+        added_columns: after
+            .iter()
+            .filter(|c| !before_by_name.contains_key(c.name.as_str()))
+            .cloned()
+            .collect(),
+        removed_columns: before
+            .iter()
+            .filter(|c| !after_by_name.contains_key(c.name.as_str()))
+            .cloned()
+            .collect(),
+        changed_columns: before
+            .iter()
+            .filter_map(|old| {
+                let new = after_by_name.get(old.name.as_str())?;
+                column_definition_changed(old, new).then(|| ColumnChange {
+                    name: old.name.clone(),
+                    before: old.clone(),
+                    after: (*new).clone(),
+                })
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn table(name: &str, columns: Vec<Column>) -> Table {
+        Table {
+            name: name.to_string(),
+            schema: Some("public".to_string()),
+            columns,
+            row_count: None,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_metadata_diff() {
+        let mut from = DatabaseMetadata::new(
+            "conn-1".to_string(),
+            vec![
+                table("users", vec![column("id", "integer"), column("email", "text")]),
+                table("legacy", vec![column("id", "integer")]),
+            ],
+            vec![],
+            vec!["public".to_string()],
+        );
+        from.version = 1;
+
+        let mut retyped_email = column("email", "varchar");
+        retyped_email.description = Some("ignored".to_string());
+        let mut to = DatabaseMetadata::new(
+            "conn-1".to_string(),
+            vec![
+                table("users", vec![column("id", "integer"), retyped_email, column("name", "text")]),
+                table("orders", vec![column("id", "integer")]),
+            ],
+            vec![],
+            vec!["public".to_string()],
+        );
+        to.version = 2;
+
+        let diff = MetadataDiff::between(&from, &to);
+        assert_eq!(diff.from_version, 1);
+        assert_eq!(diff.to_version, 2);
+        assert_eq!(diff.added_tables, vec!["public.orders"]);
+        assert_eq!(diff.removed_tables, vec!["public.legacy"]);
+        assert_eq!(diff.changed_tables.len(), 1);
+
+        let users = &diff.changed_tables[0];
+        assert_eq!(users.name, "public.users");
+        assert_eq!(users.added_columns[0].name, "name");
+        assert!(users.removed_columns.is_empty());
+        assert_eq!(users.changed_columns[0].name, "email");
+        assert_eq!(users.changed_columns[0].after.data_type, "varchar");
+    }
+
+    #[test]
+    fn test_metadata_diff_identical() {
+        let metadata = DatabaseMetadata::new(
+            "conn-1".to_string(),
+            vec![table("users", vec![column("id", "integer")])],
+            vec![],
+            vec![],
+        );
+        assert!(MetadataDiff::between(&metadata, &metadata).is_empty());
+    }
+
+    #[test]
+    fn test_engine_metadata_summary() {
+        let partitions = (1..=12)
+            .map(|day| PartitionInfo {
+                name: format!("p202401{:02}", day),
+                values: None,
+                row_count: None,
+            })
+            .collect();
+        let doris = EngineMetadata {
+            engine: "doris".to_string(),
+            partition_method: Some("RANGE".to_string()),
+            partition_keys: vec!["dt".to_string()],
+            distribution_type: Some("HASH".to_string()),
+            distribution_keys: vec!["user_id".to_string()],
+            buckets: Some(16),
+            table_model: Some("DUP".to_string()),
+            key_columns: vec!["dt".to_string(), "user_id".to_string()],
+            ..Default::default()
+        }
+        .with_partitions(partitions);
+
+        assert_eq!(doris.partition_count, Some(12));
+        assert_eq!(doris.partitions.len(), MAX_ENGINE_PARTITIONS);
+        assert_eq!(doris.partitions[0].name, "p20240101");
+        assert_eq!(doris.partitions[1].name, "p20240104");
+        assert_eq!(
+            doris.summary(),
+            "RANGE partitioned on dt (12 partitions; first p20240101, last p20240112); \
+             HASH distributed on user_id into 16 buckets; DUP model keyed on dt, user_id"
+        );
+
+        let druid = EngineMetadata {
+            engine: "druid".to_string(),
+            partition_method: Some("TIME".to_string()),
+            partition_keys: vec!["__time".to_string()],
+            partition_count: Some(3),
+            range_start: Some("2024-01-01T00:00:00.000Z".to_string()),
+            range_end: Some("2024-01-04T00:00:00.000Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            druid.summary(),
+            "TIME partitioned on __time (3 segments; 2024-01-01T00:00:00.000Z to 2024-01-04T00:00:00.000Z)"
+        );
+    }
+}
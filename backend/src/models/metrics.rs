@@ -0,0 +1,267 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+/// Aggregation of a measure
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasureAggregation {
+    Sum,
+    Count,
+    CountDistinct,
+    Avg,
+    Min,
+    Max,
+}
+
+/// Time grain a metric is bucketed by
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeGrain {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl TimeGrain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeGrain::Day => "day",
+            TimeGrain::Week => "week",
+            TimeGrain::Month => "month",
+            TimeGrain::Quarter => "quarter",
+            TimeGrain::Year => "year",
+        }
+    }
+}
+
+/// Attribute of an entity that metrics can be grouped by
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricDimension {
+    pub name: String,
+    /// SQL expression (default: the column named like the dimension)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Aggregated value of an entity, the metric requests ask for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricMeasure {
+    pub name: String,
+    pub agg: MeasureAggregation,
+    /// SQL expression aggregated (default: `*` for counts, otherwise the column named like the measure)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Table of a connection described by dimensions and measures
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricEntity {
+    /// Name metrics and joins refer to (also the table alias in compiled SQL)
+    pub name: String,
+    /// Table, optionally schema-qualified (`public.orders`)
+    pub table: String,
+    /// Timestamp expression time grains truncate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_dimension: Option<String>,
+    #[serde(default)]
+    pub dimensions: Vec<MetricDimension>,
+    #[serde(default)]
+    pub measures: Vec<MetricMeasure>,
+}
+
+/// Column pair of a join condition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricJoinColumns {
+    /// Column of the `from` entity
+    pub from: String,
+    /// Column of the `to` entity
+    pub to: String,
+}
+
+/// Default join between two entities, used when a metric is grouped by
+/// dimensions of another entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricJoin {
+    pub from: String,
+    pub to: String,
+    pub on: Vec<MetricJoinColumns>,
+}
+
+/// Semantic metrics definition of a domain (YAML or JSON)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MetricsDefinition {
+    #[serde(default)]
+    pub entities: Vec<MetricEntity>,
+    #[serde(default)]
+    pub joins: Vec<MetricJoin>,
+}
+
+impl MetricsDefinition {
+    /// Validate names and join references
+    pub fn validate(&self) -> Result<(), String> {
+        let mut entities = HashSet::new();
+        for entity in &self.entities {
+            validate_name("Entity", &entity.name)?;
+            if !entities.insert(entity.name.as_str()) {
+                return Err(format!("Duplicate entity '{}'", entity.name));
+            }
+            if entity.table.trim().is_empty() {
+                return Err(format!("Entity '{}' has no table", entity.name));
+            }
+
+            let mut fields = HashSet::new();
+            let names = entity
+                .dimensions
+                .iter()
+                .map(|d| ("Dimension", &d.name))
+                .chain(entity.measures.iter().map(|m| ("Measure", &m.name)));
+            for (kind, name) in names {
+                validate_name(kind, name)?;
+                if !fields.insert(name.as_str()) {
+                    return Err(format!("Duplicate dimension or measure '{}' in entity '{}'", name, entity.name));
+                }
+            }
+        }
+
+        for join in &self.joins {
+            for entity in [&join.from, &join.to] {
+                if !entities.contains(entity.as_str()) {
+                    return Err(format!("Join references unknown entity '{}'", entity));
+                }
+            }
+            if join.from == join.to {
+                return Err(format!("Entity '{}' cannot be joined to itself", join.from));
+            }
+            if join.on.is_empty() {
+                return Err(format!("Join of '{}' and '{}' has no columns", join.from, join.to));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn entity(&self, name: &str) -> Option<&MetricEntity> {
+        self.entities.iter().find(|e| e.name == name)
+    }
+}
+
+/// Dimension and measure names become SQL aliases, so they are plain identifiers
+fn validate_name(kind: &str, name: &str) -> Result<(), String> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} name '{}' must start with a letter or underscore and contain only letters, digits and underscores",
+            kind, name
+        ))
+    }
+}
+
+/// Stored metrics definition of a domain
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainMetrics {
+    pub domain_id: String,
+    #[serde(flatten)]
+    pub definition: MetricsDefinition,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `POST /api/domains/{id}/metrics/compile`
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CompileMetricRequest {
+    /// Measure to compute (`revenue` or `orders.revenue` when ambiguous)
+    pub metric: String,
+    /// Dimensions to group by (`region` or `customers.region`)
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    /// Time grain of the metric entity's time dimension
+    #[serde(default)]
+    pub grain: Option<TimeGrain>,
+    /// Connection whose dialect the SQL is compiled to
+    pub connection_id: String,
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+/// SQL a metric request compiles to (not executed)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CompiledMetric {
+    pub connection_id: String,
+    /// Target dialect name
+    pub dialect: String,
+    /// The query in the target dialect
+    pub sql: String,
+    /// Result columns: the time grain, the group-by dimensions, then the metric
+    pub columns: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = r#"
+entities:
+  - name: orders
+    table: public.orders
+    time_dimension: created_at
+    dimensions:
+      - name: status
+    measures:
+      - name: revenue
+        agg: sum
+        expr: amount
+      - name: order_count
+        agg: count
+  - name: customers
+    table: public.customers
+    dimensions:
+      - name: region
+joins:
+  - from: orders
+    to: customers
+    on:
+      - from: customer_id
+        to: id
+"#;
+
+    #[test]
+    fn test_definition_parses_from_yaml() {
+        let definition: MetricsDefinition = serde_yaml::from_str(DEFINITION).unwrap();
+        definition.validate().unwrap();
+
+        let orders = definition.entity("orders").unwrap();
+        assert_eq!(orders.measures[0].agg, MeasureAggregation::Sum);
+        assert_eq!(orders.measures[1].expr, None);
+        assert_eq!(definition.joins[0].on[0].to, "id");
+    }
+
+    #[test]
+    fn test_definition_validation() {
+        let definition: MetricsDefinition = serde_yaml::from_str(DEFINITION).unwrap();
+
+        let mut duplicate = definition.clone();
+        duplicate.entities[0].dimensions.push(MetricDimension {
+            name: "revenue".to_string(),
+            expr: None,
+            description: None,
+        });
+        assert!(duplicate.validate().unwrap_err().contains("Duplicate"));
+
+        let mut unknown = definition.clone();
+        unknown.joins[0].to = "products".to_string();
+        assert!(unknown.validate().unwrap_err().contains("unknown entity"));
+
+        let mut invalid = definition;
+        invalid.entities[1].name = "customer table".to_string();
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -1,14 +1,74 @@
+pub mod api_key;
+pub mod autocomplete;
+pub mod chart;
+pub mod chat;
 pub mod connection;
+pub mod connection_template;
+pub mod data_dictionary;
 pub mod domain;
+pub mod domain_bundle;
+pub mod export;
+pub mod favorite;
+pub mod feedback;
+pub mod insight;
+pub mod llm_audit;
+pub mod materialization;
 pub mod metadata;
+pub mod metrics;
+pub mod pagination;
+pub mod policy;
 pub mod query;
+pub mod result_contract;
+pub mod query_job;
+pub mod query_plan;
+pub mod query_session;
+pub mod transform;
 pub mod unified_query;
 pub mod cross_database_query;
+pub mod trash;
+pub mod usage;
+pub mod user;
+pub mod replay;
+pub mod share;
+pub mod sql_tools;
+pub mod table_profile;
+pub mod transaction;
+pub mod webhook;
 
+pub use api_key::*;
+pub use autocomplete::*;
+pub use chart::*;
+pub use chat::*;
 pub use connection::*;
+pub use connection_template::*;
+pub use data_dictionary::*;
 pub use domain::*;
+pub use domain_bundle::*;
+pub use export::*;
+pub use favorite::*;
+pub use feedback::*;
+pub use llm_audit::*;
+pub use insight::*;
+pub use materialization::*;
 pub use metadata::*;
+pub use metrics::*;
+pub use pagination::*;
+pub use policy::*;
 pub use query::*;
+pub use result_contract::*;
+pub use query_job::*;
+pub use query_plan::*;
+pub use query_session::*;
+pub use transform::*;
 pub use unified_query::*;
 pub use cross_database_query::*;
+pub use trash::*;
+pub use usage::*;
+pub use user::*;
+pub use replay::*;
+pub use share::*;
+pub use sql_tools::*;
+pub use table_profile::*;
+pub use transaction::*;
+pub use webhook::*;
 
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Page size used when a list request does not specify one
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Largest page size a list request may ask for
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Query parameters accepted by list endpoints: `?page=&page_size=&sort=&filter=`
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListParams {
+    /// 1-based page number
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    /// Sort field, prefixed with `-` for descending order (e.g. `-created_at`)
+    pub sort: Option<String>,
+    /// Case-insensitive substring match on the resource's name or text
+    pub filter: Option<String>,
+    /// Older alias of `page_size` (query history endpoints)
+    pub limit: Option<u32>,
+}
+
+/// Sortable field of a resource: API name and the SQL column it orders by
+pub type SortField = (&'static str, &'static str);
+
+/// Validated list request, ready to be turned into ORDER BY / LIMIT / OFFSET
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListQuery {
+    pub page: u32,
+    pub page_size: u32,
+    /// Whitelisted SQL column (never user input)
+    pub sort_column: &'static str,
+    pub descending: bool,
+    pub filter: Option<String>,
+}
+
+impl ListParams {
+    /// Validate the parameters against a resource's sortable fields
+    ///
+    /// `default_sort` uses the same syntax as `sort` (e.g. `-created_at`).
+    pub fn resolve(&self, fields: &[SortField], default_sort: &str) -> Result<ListQuery, String> {
+        let page = self.page.unwrap_or(1);
+        if page == 0 {
+            return Err("page must be 1 or greater".to_string());
+        }
+
+        let page_size = self.page_size.or(self.limit).unwrap_or(DEFAULT_PAGE_SIZE);
+        if page_size == 0 || page_size > MAX_PAGE_SIZE {
+            return Err(format!("page_size must be between 1 and {}", MAX_PAGE_SIZE));
+        }
+
+        let sort = self
+            .sort
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(default_sort);
+        let (descending, name) = match sort.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, sort.strip_prefix('+').unwrap_or(sort)),
+        };
+        let sort_column = fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, column)| *column)
+            .ok_or_else(|| {
+                let names: Vec<&str> = fields.iter().map(|(field, _)| *field).collect();
+                format!("Cannot sort by '{}' (sortable fields: {})", name, names.join(", "))
+            })?;
+
+        let filter = self
+            .filter
+            .as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::to_string);
+
+        Ok(ListQuery {
+            page,
+            page_size,
+            sort_column,
+            descending,
+            filter,
+        })
+    }
+}
+
+impl ListQuery {
+    pub fn offset(&self) -> u64 {
+        (self.page as u64 - 1) * self.page_size as u64
+    }
+
+    /// `ORDER BY ... LIMIT ... OFFSET ...` clause (all parts are validated)
+    ///
+    /// Ties are broken by `id` so pages never overlap.
+    pub fn page_clause(&self) -> String {
+        format!(
+            " ORDER BY {} {}, id LIMIT {} OFFSET {}",
+            self.sort_column,
+            if self.descending { "DESC" } else { "ASC" },
+            self.page_size,
+            self.offset()
+        )
+    }
+
+    /// LIKE pattern for the filter, with `%`, `_` and `\` escaped (use `ESCAPE '\'`)
+    pub fn filter_pattern(&self) -> Option<String> {
+        self.filter.as_ref().map(|filter| {
+            let escaped = filter
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+
+    /// Paging fields of the response for `total` matching items
+    pub fn page_info(&self, total: u64) -> PageInfo {
+        PageInfo {
+            total,
+            page: self.page,
+            page_size: self.page_size,
+        }
+    }
+}
+
+/// Paging fields flattened into list responses next to the items
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PageInfo {
+    pub total: u64,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[SortField] = &[("name", "name"), ("created_at", "created_at")];
+
+    #[test]
+    fn test_resolve_list_params() {
+        let query = ListParams::default().resolve(FIELDS, "-created_at").unwrap();
+        assert_eq!(query.page, 1);
+        assert_eq!(query.page_size, DEFAULT_PAGE_SIZE);
+        assert_eq!(query.page_clause(), " ORDER BY created_at DESC, id LIMIT 50 OFFSET 0");
+
+        let params = ListParams {
+            page: Some(3),
+            page_size: Some(20),
+            sort: Some("Name".to_string()),
+            filter: Some(" 50%_off ".to_string()),
+            limit: None,
+        };
+        let query = params.resolve(FIELDS, "-created_at").unwrap();
+        assert_eq!(query.page_clause(), " ORDER BY name ASC, id LIMIT 20 OFFSET 40");
+        assert_eq!(query.filter_pattern().unwrap(), "%50\\%\\_off%");
+
+        assert_eq!(query.page_info(41), PageInfo { total: 41, page: 3, page_size: 20 });
+
+        // Legacy `limit` still sets the page size
+        let legacy = ListParams { limit: Some(10), ..Default::default() };
+        assert_eq!(legacy.resolve(FIELDS, "name").unwrap().page_size, 10);
+    }
+
+    #[test]
+    fn test_reject_invalid_list_params() {
+        let bad_sort = ListParams { sort: Some("password; DROP TABLE".to_string()), ..Default::default() };
+        assert!(bad_sort.resolve(FIELDS, "name").is_err());
+        let bad_page = ListParams { page: Some(0), ..Default::default() };
+        assert!(bad_page.resolve(FIELDS, "name").is_err());
+        let too_big = ListParams { page_size: Some(MAX_PAGE_SIZE + 1), ..Default::default() };
+        assert!(too_big.resolve(FIELDS, "name").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Labels attached to a connection (`env=prod`, `team=payments`, `pii=true`)
+pub type Labels = BTreeMap<String, String>;
+
+/// Validate label keys and values: 1-63 characters of `[A-Za-z0-9._/-]` (values may be empty)
+pub fn validate_labels(labels: &Labels) -> Result<(), String> {
+    if labels.len() > 32 {
+        return Err("A connection cannot have more than 32 labels".to_string());
+    }
+    let valid = |s: &str| {
+        s.len() <= 63 && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-'))
+    };
+    for (key, value) in labels {
+        if key.is_empty() || !valid(key) {
+            return Err(format!(
+                "Invalid label key '{}': use 1-63 letters, digits, '.', '_', '/' or '-'",
+                key
+            ));
+        }
+        if !valid(value) {
+            return Err(format!(
+                "Invalid value for label '{}': use at most 63 letters, digits, '.', '_', '/' or '-'",
+                key
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Equality-based label selector; matches connections carrying every listed label
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+#[schema(value_type = std::collections::BTreeMap<String, String>)]
+pub struct LabelSelector(pub Labels);
+
+impl LabelSelector {
+    /// Parse `key=value,key=value` (as used in `?labels=` query parameters)
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut labels = Labels::new();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid label selector '{}': expected key=value", pair))?;
+            labels.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        validate_labels(&labels)?;
+        Ok(Self(labels))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn matches(&self, labels: &Labels) -> bool {
+        self.0.iter().all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
+/// Query policy applied to every connection whose labels match its selector
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionPolicy {
+    pub id: String,
+    pub name: String,
+    pub selector: LabelSelector,
+    /// Result columns whose values are replaced by a mask (case-insensitive)
+    pub masked_columns: Vec<String>,
+    /// Maximum queries per connection in any rolling hour
+    pub max_queries_per_hour: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request payload for creating a connection policy
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateConnectionPolicyRequest {
+    pub name: String,
+    pub selector: LabelSelector,
+    #[serde(default)]
+    pub masked_columns: Vec<String>,
+    pub max_queries_per_hour: Option<u32>,
+}
+
+impl ConnectionPolicy {
+    pub fn from_request(request: CreateConnectionPolicyRequest) -> Result<Self, String> {
+        let name = request.name.trim().to_string();
+        if name.is_empty() || name.len() > 100 {
+            return Err("Policy name must be between 1 and 100 characters".to_string());
+        }
+        // An empty selector would silently apply to every connection
+        if request.selector.is_empty() {
+            return Err("Policy selector must contain at least one label".to_string());
+        }
+        validate_labels(&request.selector.0)?;
+        if request.masked_columns.is_empty() && request.max_queries_per_hour.is_none() {
+            return Err("Policy must set masked_columns or max_queries_per_hour".to_string());
+        }
+        if request.max_queries_per_hour == Some(0) {
+            return Err("max_queries_per_hour must be greater than 0".to_string());
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            selector: request.selector,
+            masked_columns: request
+                .masked_columns
+                .into_iter()
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect(),
+            max_queries_per_hour: request.max_queries_per_hour,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// Combined effect of all policies matching a connection
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectivePolicy {
+    pub policy_ids: Vec<String>,
+    /// Lowercased column names to mask
+    pub masked_columns: BTreeSet<String>,
+    /// Strictest quota of the matching policies
+    pub max_queries_per_hour: Option<u32>,
+}
+
+/// Replacement for masked values
+pub const MASKED_VALUE: &str = "****";
+
+impl EffectivePolicy {
+    /// Merge the policies whose selector matches `labels`
+    pub fn resolve(policies: &[ConnectionPolicy], labels: &Labels) -> Self {
+        let mut effective = Self::default();
+        for policy in policies.iter().filter(|p| p.selector.matches(labels)) {
+            effective.policy_ids.push(policy.id.clone());
+            effective
+                .masked_columns
+                .extend(policy.masked_columns.iter().map(|c| c.to_lowercase()));
+            effective.max_queries_per_hour = match (effective.max_queries_per_hour, policy.max_queries_per_hour) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        effective
+    }
+
+    /// Whether values of a column are masked
+    pub fn masks_column(&self, column: &str) -> bool {
+        self.masked_columns.contains(&column.to_lowercase())
+    }
+
+    /// Mask non-null values of masked columns in result rows (JSON objects)
+    pub fn mask_rows(&self, rows: &mut [serde_json::Value]) {
+        if self.masked_columns.is_empty() {
+            return;
+        }
+        for row in rows {
+            if let Some(object) = row.as_object_mut() {
+                for (column, value) in object.iter_mut() {
+                    if !value.is_null() && self.masks_column(column) {
+                        *value = serde_json::Value::String(MASKED_VALUE.to_string());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(selector: &str, masked: &[&str], quota: Option<u32>) -> ConnectionPolicy {
+        ConnectionPolicy::from_request(CreateConnectionPolicyRequest {
+            name: "test".to_string(),
+            selector: LabelSelector::parse(selector).unwrap(),
+            masked_columns: masked.iter().map(|c| c.to_string()).collect(),
+            max_queries_per_hour: quota,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let selector = LabelSelector::parse("env=prod, pii=true").unwrap();
+        let mut labels = Labels::new();
+        labels.insert("env".to_string(), "prod".to_string());
+        assert!(!selector.matches(&labels));
+        labels.insert("pii".to_string(), "true".to_string());
+        labels.insert("team".to_string(), "payments".to_string());
+        assert!(selector.matches(&labels));
+
+        assert!(LabelSelector::parse("env").is_err());
+        assert!(LabelSelector::parse("env=prod;drop").is_err());
+        assert!(LabelSelector::parse("").unwrap().matches(&Labels::new()));
+    }
+
+    #[test]
+    fn test_effective_policy_merges_matching_policies() {
+        let policies = vec![
+            policy("env=prod", &["Email"], Some(100)),
+            policy("pii=true", &["ssn"], Some(10)),
+            policy("env=dev", &["name"], Some(1)),
+        ];
+        let labels: Labels = [("env", "prod"), ("pii", "true")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let effective = EffectivePolicy::resolve(&policies, &labels);
+        assert_eq!(effective.policy_ids.len(), 2);
+        assert_eq!(effective.max_queries_per_hour, Some(10));
+
+        let mut rows = vec![serde_json::json!({"EMAIL": "a@b.c", "ssn": null, "name": "Ann"})];
+        effective.mask_rows(&mut rows);
+        assert_eq!(
+            rows[0],
+            serde_json::json!({"EMAIL": MASKED_VALUE, "ssn": null, "name": "Ann"})
+        );
+    }
+}
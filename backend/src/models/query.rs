@@ -1,10 +1,17 @@
+use std::collections::{BTreeMap, HashMap};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use super::transform::TransformStep;
 use super::unified_query::DatabaseType;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Values for the `:name` placeholders of a query, keyed by name
+pub type QueryParameters = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Query {
     pub id: String,
     pub connection_id: String,
@@ -26,9 +33,21 @@ pub struct Query {
     /// Translated query in target dialect (if using unified query execution)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub translated_query: Option<String>,
+    /// Result cache outcome (absent when the result cache is disabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
+}
+
+/// Whether a query result was served from the result cache
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CacheStatus {
+    pub hit: bool,
+    /// When the served result was computed (hits only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum QueryStatus {
     Pending,
@@ -54,6 +73,7 @@ impl Query {
             database_type: None,
             original_query: None,
             translated_query: None,
+            cache: None,
         }
     }
 
@@ -69,6 +89,15 @@ impl Query {
         self.executed_at = Some(Utc::now());
     }
 
+    /// Mark a query whose rows were streamed to the client instead of kept
+    pub fn mark_streamed(&mut self, row_count: usize, execution_time_ms: u64) {
+        self.status = QueryStatus::Completed;
+        self.results = None;
+        self.row_count = Some(row_count);
+        self.execution_time_ms = Some(execution_time_ms);
+        self.executed_at = Some(Utc::now());
+    }
+
     pub fn mark_failed(&mut self, error_message: String) {
         self.status = QueryStatus::Failed;
         self.error_message = Some(error_message);
@@ -76,14 +105,264 @@ impl Query {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Synchronous query in progress, cancellable with `POST /api/queries/{id}/cancel`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RunningQuery {
+    /// Request id of the executing request (`X-Request-Id`)
+    pub id: String,
+    pub connection_id: String,
+    pub domain_id: Option<String>,
+    /// Principal subject that started the query
+    pub started_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+impl RunningQuery {
+    pub fn new(id: String, connection_id: String, domain_id: Option<String>, started_by: Option<String>) -> Self {
+        Self {
+            id,
+            connection_id,
+            domain_id,
+            started_by,
+            started_at: Utc::now(),
+        }
+    }
+}
+
+/// Write statement for `POST /api/connections/{id}/execute`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExecuteRequest {
+    /// One INSERT, UPDATE, DELETE, MERGE, TRUNCATE or DDL statement
+    pub statement: String,
+    /// Values for `:name` placeholders
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+}
+
+/// Outcome of a write statement
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WriteResult {
+    /// Kind of statement, e.g. `INSERT` or `CREATE TABLE`
+    pub statement_kind: String,
+    /// Rows inserted, updated or deleted (0 for DDL)
+    pub affected_rows: u64,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct QueryRequest {
     pub query: String,
+    /// Execute even if the estimated result exceeds the large result threshold
+    #[serde(default)]
+    pub confirm_large_result: bool,
+    /// Apply the transformation of this saved query to the result
+    #[serde(default)]
+    pub saved_query_id: Option<String>,
+    /// Post-processing steps applied to the result (after the saved query's)
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+    /// Values for `:name` placeholders (override the saved query's defaults)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// Query timeout override, capped at the domain's `max_timeout_secs`
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Row limit override for queries without a LIMIT, capped at the domain's `max_row_limit`
+    #[serde(default)]
+    pub limit_value: Option<u64>,
+    /// Keep the result with the history entry (`GET .../queries/history/{history_id}/result`)
+    #[serde(default)]
+    pub save_result: Option<HistoryResultFormat>,
+}
+
+/// Run of a saved query (`POST /api/domains/{id}/queries/saved/{query_id}/execute`)
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ExecuteSavedQueryRequest {
+    /// Values for `:name` placeholders (override the saved query's defaults)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// Execute even if the estimated result exceeds the large result threshold
+    #[serde(default)]
+    pub confirm_large_result: bool,
+    /// Post-processing steps applied after the saved query's transformation
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+    /// Query timeout override, capped at the domain's `max_timeout_secs`
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Row limit override for queries without a LIMIT, capped at the domain's `max_row_limit`
+    #[serde(default)]
+    pub limit_value: Option<u64>,
+    /// Keep the result with the history entry
+    #[serde(default)]
+    pub save_result: Option<HistoryResultFormat>,
+}
+
+impl ExecuteSavedQueryRequest {
+    /// The equivalent request of `POST /api/connections/{id}/query`
+    pub fn into_query_request(self, saved: &SavedQuery) -> QueryRequest {
+        QueryRequest {
+            query: saved.query_text.clone(),
+            confirm_large_result: self.confirm_large_result,
+            saved_query_id: Some(saved.id.clone()),
+            transform: self.transform,
+            parameters: self.parameters,
+            timeout_secs: self.timeout_secs,
+            limit_value: self.limit_value,
+            save_result: self.save_result,
+        }
+    }
+}
+
+/// Rerun of a history entry (`POST /api/domains/{id}/queries/history/{history_id}/rerun`)
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RerunHistoryRequest {
+    /// Values for `:name` placeholders (override the saved query's defaults)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// Execute even if the estimated result exceeds the large result threshold
+    #[serde(default)]
+    pub confirm_large_result: bool,
+    /// Query timeout override, capped at the domain's `max_timeout_secs`
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Row limit override for queries without a LIMIT, capped at the domain's `max_row_limit`
+    #[serde(default)]
+    pub limit_value: Option<u64>,
+    /// Keep the result with the new history entry
+    #[serde(default)]
+    pub save_result: Option<HistoryResultFormat>,
+}
+
+impl RerunHistoryRequest {
+    /// The request running the entry's SQL again; `saved_query_id` applies
+    /// the defaults and transformation of the saved query the entry ran
+    pub fn into_query_request(self, history: &QueryHistory, saved_query_id: Option<String>) -> QueryRequest {
+        QueryRequest {
+            query: history.query_text.clone(),
+            confirm_large_result: self.confirm_large_result,
+            saved_query_id,
+            transform: Vec::new(),
+            parameters: self.parameters,
+            timeout_secs: self.timeout_secs,
+            limit_value: self.limit_value,
+            save_result: self.save_result,
+        }
+    }
+}
+
+/// Promotion of a history entry to a saved query
+/// (`POST /api/domains/{id}/queries/history/{history_id}/promote`)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PromoteHistoryRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Default values for `:name` placeholders
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+}
+
+impl PromoteHistoryRequest {
+    /// The saved query keeping the entry's SQL and connection(s)
+    pub fn into_create_request(self, history: &QueryHistory) -> CreateSavedQueryRequest {
+        CreateSavedQueryRequest {
+            connection_id: history.connection_id.clone(),
+            connection_ids: history.connection_ids.clone(),
+            database_aliases: history.database_aliases.clone(),
+            name: self.name,
+            query_text: history.query_text.clone(),
+            description: self.description,
+            transform: Vec::new(),
+            parameters: self.parameters,
+            view_name: None,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct NaturalLanguageQueryRequest {
     pub question: String,
+    /// Execute even if the estimated result exceeds the large result threshold
+    #[serde(default)]
+    pub confirm_large_result: bool,
+    /// Ask the LLM even if SQL for the same question is cached
+    #[serde(default)]
+    pub force_regenerate: bool,
+}
+
+/// Event of a streamed natural language query (`POST /api/connections/{id}/nl-query/stream`)
+///
+/// The SSE event name equals the `event` field of the JSON data.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum NaturalLanguageQueryEvent {
+    /// Next piece of the SQL as the LLM produces it
+    SqlToken { token: String },
+    /// Complete SQL, with markdown code fences removed (`cache.hit` when it was
+    /// cached for the same question rather than generated)
+    SqlGenerated { sql: String, cache: CacheStatus },
+    ExecutionStarted,
+    /// The LLM rewrote SQL the database rejected with `error_message`; `sql` runs next
+    SqlRepaired { attempt: u32, sql: String, error_message: String },
+    /// The executed query with its results
+    Completed { query: Query },
+    Failed { error_message: String },
+}
+
+impl NaturalLanguageQueryEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            NaturalLanguageQueryEvent::SqlToken { .. } => "sql-token",
+            NaturalLanguageQueryEvent::SqlGenerated { .. } => "sql-generated",
+            NaturalLanguageQueryEvent::ExecutionStarted => "execution-started",
+            NaturalLanguageQueryEvent::SqlRepaired { .. } => "sql-repaired",
+            NaturalLanguageQueryEvent::Completed { .. } => "completed",
+            NaturalLanguageQueryEvent::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// Estimated result size of a query without LIMIT (from the database's EXPLAIN output)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RowEstimate {
+    pub estimated_rows: u64,
+    /// Estimates above this require `confirm_large_result=true`
+    pub threshold: u64,
+}
+
+/// Shape of the result rows in a query response
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    /// One JSON object per row (`results`)
+    #[default]
+    Rows,
+    /// One array per column (`columnar`), without repeating column names
+    Columnar,
+}
+
+/// Query string of query endpoints selecting the result shape
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct ResultFormatParams {
+    /// `rows` (default) or `columnar`
+    #[serde(default)]
+    pub result_format: ResultFormat,
+}
+
+/// Column-oriented query result (`result_format=columnar`)
+///
+/// `data[i]` holds the values of `columns[i]`, whose Arrow type is `types[i]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ColumnarResult {
+    pub columns: Vec<String>,
+    pub types: Vec<String>,
+    pub data: Vec<Vec<serde_json::Value>>,
 }
 
 // ============================================================================
@@ -91,22 +370,47 @@ pub struct NaturalLanguageQueryRequest {
 // ============================================================================
 
 /// SavedQuery - User-saved queries scoped to a domain
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SavedQuery {
     pub id: String,
     pub domain_id: String,
-    pub connection_id: String,
+    /// Connection the query runs on (None for cross-database queries)
+    pub connection_id: Option<String>,
+    /// Connections of a cross-database query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connection_ids: Vec<String>,
+    /// Aliases a cross-database query uses for its connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_aliases: Option<HashMap<String, String>>,
     pub name: String,
     pub query_text: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Incremented on every update (optimistic concurrency control)
+    pub version: i64,
+    /// Post-processing steps applied to the result when the query is run
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+    /// Default values for the query's `:name` placeholders
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// Name the query is exposed under as a view (`domain.view_name`) in cross-database queries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_name: Option<String>,
+    /// Set when a metadata refresh found a table or column the query uses
+    /// dropped or renamed; cleared when the query text is edited
+    #[serde(default)]
+    pub is_broken: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breakage_reason: Option<String>,
 }
 
 impl SavedQuery {
     pub fn new(
         domain_id: String,
-        connection_id: String,
+        connection_id: impl Into<Option<String>>,
         name: String,
         query_text: String,
         description: Option<String>,
@@ -115,29 +419,92 @@ impl SavedQuery {
         Self {
             id: Uuid::new_v4().to_string(),
             domain_id,
-            connection_id,
+            connection_id: connection_id.into(),
+            connection_ids: Vec::new(),
+            database_aliases: None,
             name,
             query_text,
             description,
             created_at: now,
             updated_at: now,
+            version: 1,
+            transform: Vec::new(),
+            parameters: QueryParameters::new(),
+            view_name: None,
+            is_broken: false,
+            breakage_reason: None,
         }
     }
+
+    /// Set the result transformation
+    pub fn with_transform(mut self, transform: Vec<TransformStep>) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Set the default parameter values
+    pub fn with_parameters(mut self, parameters: QueryParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Make this a cross-database query over `connection_ids`
+    pub fn with_connections(mut self, connection_ids: Vec<String>, database_aliases: Option<HashMap<String, String>>) -> Self {
+        self.connection_id = None;
+        self.connection_ids = connection_ids;
+        self.database_aliases = database_aliases;
+        self
+    }
+
+    /// Expose the query as a view named `view_name`
+    pub fn with_view_name(mut self, view_name: Option<String>) -> Self {
+        self.view_name = view_name;
+        self
+    }
+
+    /// Whether the query runs across several connections
+    pub fn is_cross_database(&self) -> bool {
+        self.connection_id.is_none()
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// Either `connection_id`, or `connection_ids` (and optionally
+/// `database_aliases`) for a cross-database query
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateSavedQueryRequest {
-    pub connection_id: String,
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    #[serde(default)]
+    pub connection_ids: Vec<String>,
+    #[serde(default)]
+    pub database_aliases: Option<HashMap<String, String>>,
     pub name: String,
     pub query_text: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// Expose the query as the view `domain.view_name` (single-connection queries only)
+    #[serde(default)]
+    pub view_name: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateSavedQueryRequest {
     pub name: Option<String>,
     pub query_text: Option<String>,
     pub description: Option<String>,
+    /// Replaces the whole transformation (an empty list removes it)
+    pub transform: Option<Vec<TransformStep>>,
+    /// Replaces the default parameter values
+    #[schema(value_type = Option<Object>)]
+    pub parameters: Option<QueryParameters>,
+    /// Renames the view (an empty string stops exposing the query as a view)
+    pub view_name: Option<String>,
+    /// Current version, required unless an If-Match header is sent
+    pub expected_version: Option<i64>,
 }
 
 // ============================================================================
@@ -145,11 +512,18 @@ pub struct UpdateSavedQueryRequest {
 // ============================================================================
 
 /// QueryHistory - Execution history of queries scoped to a domain
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryHistory {
     pub id: String,
     pub domain_id: String,
-    pub connection_id: String,
+    /// Connection the query ran on (None for cross-database queries)
+    pub connection_id: Option<String>,
+    /// Connections of a cross-database query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connection_ids: Vec<String>,
+    /// Aliases a cross-database query used for its connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_aliases: Option<HashMap<String, String>>,
     pub query_text: String,
     pub row_count: usize,
     pub execution_time_ms: u64,
@@ -157,19 +531,68 @@ pub struct QueryHistory {
     pub error_message: Option<String>,
     pub executed_at: DateTime<Utc>,
     pub is_llm_generated: bool,
+    /// Who executed the query (user id or `api_key:<id>`; None without auth)
+    #[serde(default)]
+    pub executed_by: Option<String>,
+    /// Read query or write statement (`POST /api/connections/{id}/execute`)
+    #[serde(default)]
+    pub kind: QueryKind,
+    /// SQL the LLM rewrote after an earlier generated statement failed
+    #[serde(default)]
+    pub auto_repaired: bool,
+    /// Saved query the entry ran, or the one it was promoted to
+    /// (`POST .../queries/history/{history_id}/promote`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_query_id: Option<String>,
+    /// Execution time reached the domain's `slow_query_threshold_ms`
+    #[serde(default)]
+    pub is_slow: bool,
+    /// SQL with its literals replaced by `?`, shared by runs of the same
+    /// query shape (computed when the entry is recorded)
+    #[serde(default)]
+    pub fingerprint: String,
+    /// The result was kept with the entry (`GET .../queries/history/{history_id}/result`)
+    #[serde(default)]
+    pub has_result: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum QueryHistoryStatus {
     Success,
     Failed,
 }
 
+/// Whether a history entry was a read query or a write statement
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryKind {
+    #[default]
+    Read,
+    /// For writes, `row_count` is the number of affected rows
+    Write,
+}
+
+impl QueryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryKind::Read => "read",
+            QueryKind::Write => "write",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "write" => QueryKind::Write,
+            _ => QueryKind::Read,
+        }
+    }
+}
+
 impl QueryHistory {
     pub fn new(
         domain_id: String,
-        connection_id: String,
+        connection_id: impl Into<Option<String>>,
         query_text: String,
         row_count: usize,
         execution_time_ms: u64,
@@ -178,7 +601,9 @@ impl QueryHistory {
         Self {
             id: Uuid::new_v4().to_string(),
             domain_id,
-            connection_id,
+            connection_id: connection_id.into(),
+            connection_ids: Vec::new(),
+            database_aliases: None,
             query_text,
             row_count,
             execution_time_ms,
@@ -186,12 +611,19 @@ impl QueryHistory {
             error_message: None,
             executed_at: Utc::now(),
             is_llm_generated,
+            executed_by: None,
+            kind: QueryKind::Read,
+            auto_repaired: false,
+            saved_query_id: None,
+            is_slow: false,
+            fingerprint: String::new(),
+            has_result: false,
         }
     }
 
     pub fn new_failed(
         domain_id: String,
-        connection_id: String,
+        connection_id: impl Into<Option<String>>,
         query_text: String,
         error_message: String,
         is_llm_generated: bool,
@@ -199,7 +631,9 @@ impl QueryHistory {
         Self {
             id: Uuid::new_v4().to_string(),
             domain_id,
-            connection_id,
+            connection_id: connection_id.into(),
+            connection_ids: Vec::new(),
+            database_aliases: None,
             query_text,
             row_count: 0,
             execution_time_ms: 0,
@@ -207,7 +641,149 @@ impl QueryHistory {
             error_message: Some(error_message),
             executed_at: Utc::now(),
             is_llm_generated,
+            executed_by: None,
+            kind: QueryKind::Read,
+            auto_repaired: false,
+            saved_query_id: None,
+            is_slow: false,
+            fingerprint: String::new(),
+            has_result: false,
+        }
+    }
+
+    /// Record the executing user
+    pub fn with_executed_by(mut self, executed_by: Option<String>) -> Self {
+        self.executed_by = executed_by;
+        self
+    }
+
+    /// Record the kind of statement
+    pub fn with_kind(mut self, kind: QueryKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Mark the entry as a repair attempt of failing generated SQL
+    pub fn with_auto_repaired(mut self, auto_repaired: bool) -> Self {
+        self.auto_repaired = auto_repaired;
+        self
+    }
+
+    /// Link the entry to the saved query it ran
+    pub fn with_saved_query(mut self, saved_query_id: Option<String>) -> Self {
+        self.saved_query_id = saved_query_id;
+        self
+    }
+
+    /// Record a cross-database query over `connection_ids`
+    pub fn with_connections(mut self, connection_ids: Vec<String>, database_aliases: Option<HashMap<String, String>>) -> Self {
+        self.connection_id = None;
+        self.connection_ids = connection_ids;
+        self.database_aliases = database_aliases;
+        self
+    }
+}
+
+/// Query string of the history endpoints narrowing the entries
+/// (`?status=failed&since=...&min_execution_time_ms=500`)
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryFilterParams {
+    /// `success` or `failed`
+    pub status: Option<QueryHistoryStatus>,
+    pub is_llm_generated: Option<bool>,
+    /// Entries executed at or after this time (RFC 3339)
+    pub since: Option<DateTime<Utc>>,
+    /// Entries executed at or before this time (RFC 3339)
+    pub until: Option<DateTime<Utc>>,
+    pub min_execution_time_ms: Option<u64>,
+    /// Only entries flagged (or not flagged) as slow
+    pub is_slow: Option<bool>,
+    /// Only runs of this query shape (the `fingerprint` of an entry)
+    pub fingerprint: Option<String>,
+    /// `daily`: return per-day counts and latency percentiles instead of entries
+    pub aggregate: Option<HistoryAggregation>,
+}
+
+impl HistoryFilterParams {
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.since, self.until) {
+            (Some(since), Some(until)) if since > until => Err("since must not be after until".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Aggregation mode of the history endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAggregation {
+    Daily,
+}
+
+/// Matching history entries of one day (UTC)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HistoryDailyStats {
+    pub day: chrono::NaiveDate,
+    pub queries: u64,
+    pub failed: u64,
+    pub avg_execution_time_ms: f64,
+    /// Median execution time (nearest rank)
+    pub p50_execution_time_ms: u64,
+    /// 95th percentile execution time (nearest rank)
+    pub p95_execution_time_ms: u64,
+}
+
+/// How a query result is kept with its history entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryResultFormat {
+    /// The first `HISTORY_RESULT_MAX_ROWS` rows
+    Rows,
+    /// The whole result as a Parquet file of at most `HISTORY_RESULT_MAX_BYTES`
+    Parquet,
+}
+
+impl HistoryResultFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryResultFormat::Rows => "rows",
+            HistoryResultFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "parquet" => HistoryResultFormat::Parquet,
+            _ => HistoryResultFormat::Rows,
         }
     }
 }
 
+/// Result of a query kept with its history entry
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryResult {
+    pub history_id: String,
+    /// `rows` when the Parquet file would have been empty or too large
+    pub format: HistoryResultFormat,
+    /// Rows the query returned
+    pub total_row_count: usize,
+    /// Rows kept
+    pub row_count: usize,
+    /// Rows were left out
+    pub truncated: bool,
+    /// Size of the stored rows (JSON) or Parquet file
+    pub size_bytes: u64,
+    pub captured_at: DateTime<Utc>,
+    #[serde(default)]
+    pub results: Vec<serde_json::Value>,
+}
+
+/// Domains a history retention run applies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryScope {
+    All,
+    Domain(String),
+    /// Every domain except those with their own retention setting
+    AllExcept(Vec<String>),
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::query::{Query, QueryStatus};
+
+/// Lifecycle of an asynchronous query job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl QueryJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryJobStatus::Pending => "pending",
+            QueryJobStatus::Running => "running",
+            QueryJobStatus::Completed => "completed",
+            QueryJobStatus::Failed => "failed",
+            QueryJobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(QueryJobStatus::Pending),
+            "running" => Ok(QueryJobStatus::Running),
+            "completed" => Ok(QueryJobStatus::Completed),
+            "failed" => Ok(QueryJobStatus::Failed),
+            "cancelled" => Ok(QueryJobStatus::Cancelled),
+            _ => Err(format!("Unknown query job status: {}", s)),
+        }
+    }
+
+    /// Whether the job can no longer change state
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            QueryJobStatus::Completed | QueryJobStatus::Failed | QueryJobStatus::Cancelled
+        )
+    }
+}
+
+/// SQL query executed in the background; clients poll it by id
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueryJob {
+    pub id: String,
+    pub connection_id: String,
+    pub domain_id: Option<String>,
+    pub query_text: String,
+    pub status: QueryJobStatus,
+    /// Principal subject that submitted the job
+    pub submitted_by: Option<String>,
+    /// Result rows (completed jobs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Vec<serde_json::Value>>,
+    pub row_count: Option<usize>,
+    /// Rows handled so far by a job that reports progress (e.g. rows loaded by
+    /// a cross-database INSERT ... SELECT)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows_processed: Option<u64>,
+    pub execution_time_ms: Option<u64>,
+    pub limit_applied: bool,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl QueryJob {
+    pub fn new(connection_id: String, domain_id: Option<String>, query_text: String, submitted_by: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connection_id,
+            domain_id,
+            query_text,
+            status: QueryJobStatus::Pending,
+            submitted_by,
+            results: None,
+            row_count: None,
+            rows_processed: None,
+            execution_time_ms: None,
+            limit_applied: false,
+            error_message: None,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    /// Take over the outcome of the executed query
+    pub fn complete_with(&mut self, query: Query) {
+        self.status = match query.status {
+            QueryStatus::Completed => QueryJobStatus::Completed,
+            _ => QueryJobStatus::Failed,
+        };
+        self.results = query.results;
+        self.row_count = query.row_count;
+        self.execution_time_ms = query.execution_time_ms;
+        self.limit_applied = query.limit_applied;
+        self.error_message = query.error_message;
+        self.finished_at = Some(Utc::now());
+    }
+
+    pub fn fail(&mut self, error_message: String) {
+        self.status = QueryJobStatus::Failed;
+        self.error_message = Some(error_message);
+        self.finished_at = Some(Utc::now());
+    }
+}
+
+/// Event of a job's SSE stream (`GET /api/query-jobs/{id}/stream`)
+///
+/// The SSE event name equals the `event` field of the JSON data.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum QueryJobEvent {
+    ExecutionStarted {
+        job_id: String,
+        started_at: Option<DateTime<Utc>>,
+    },
+    /// Consecutive slice of the result rows, starting at row `offset`
+    RowBatch {
+        job_id: String,
+        offset: usize,
+        rows: Vec<serde_json::Value>,
+    },
+    Completed {
+        job_id: String,
+        row_count: Option<usize>,
+        execution_time_ms: Option<u64>,
+        limit_applied: bool,
+    },
+    Failed {
+        job_id: String,
+        error_message: Option<String>,
+    },
+    Cancelled {
+        job_id: String,
+        error_message: Option<String>,
+    },
+}
+
+impl QueryJobEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueryJobEvent::ExecutionStarted { .. } => "execution-started",
+            QueryJobEvent::RowBatch { .. } => "row-batch",
+            QueryJobEvent::Completed { .. } => "completed",
+            QueryJobEvent::Failed { .. } => "failed",
+            QueryJobEvent::Cancelled { .. } => "cancelled",
+        }
+    }
+
+    /// `execution-started` for a job that has started
+    pub fn started(job: &QueryJob) -> Option<Self> {
+        job.started_at.map(|started_at| QueryJobEvent::ExecutionStarted {
+            job_id: job.id.clone(),
+            started_at: Some(started_at),
+        })
+    }
+
+    /// Row batches and the final event of a finished job (nothing while unfinished)
+    pub fn outcome(job: &QueryJob, batch_size: usize) -> Vec<Self> {
+        let job_id = job.id.clone();
+        match job.status {
+            QueryJobStatus::Pending | QueryJobStatus::Running => Vec::new(),
+            QueryJobStatus::Completed => {
+                let rows = job.results.as_deref().unwrap_or_default();
+                let mut events: Vec<Self> = rows
+                    .chunks(batch_size.max(1))
+                    .enumerate()
+                    .map(|(index, batch)| QueryJobEvent::RowBatch {
+                        job_id: job_id.clone(),
+                        offset: index * batch_size.max(1),
+                        rows: batch.to_vec(),
+                    })
+                    .collect();
+                events.push(QueryJobEvent::Completed {
+                    job_id,
+                    row_count: job.row_count,
+                    execution_time_ms: job.execution_time_ms,
+                    limit_applied: job.limit_applied,
+                });
+                events
+            }
+            QueryJobStatus::Failed => vec![QueryJobEvent::Failed {
+                job_id,
+                error_message: job.error_message.clone(),
+            }],
+            QueryJobStatus::Cancelled => vec![QueryJobEvent::Cancelled {
+                job_id,
+                error_message: job.error_message.clone(),
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_takes_query_outcome() {
+        let mut job = QueryJob::new("conn".to_string(), None, "SELECT 1".to_string(), None);
+        assert_eq!(job.status, QueryJobStatus::Pending);
+        assert!(!job.status.is_finished());
+
+        let mut query = Query::new("conn".to_string(), "SELECT 1".to_string(), false);
+        query.mark_completed(vec![serde_json::json!({"?column?": 1})], 12);
+        job.complete_with(query);
+
+        assert_eq!(job.status, QueryJobStatus::Completed);
+        assert_eq!(job.row_count, Some(1));
+        assert!(job.finished_at.is_some());
+        assert_eq!(QueryJobStatus::from_str(job.status.as_str()).unwrap(), job.status);
+    }
+
+    #[test]
+    fn test_outcome_events_batch_rows() {
+        let mut job = QueryJob::new("conn".to_string(), None, "SELECT n".to_string(), None);
+        assert!(QueryJobEvent::outcome(&job, 2).is_empty());
+
+        let mut query = Query::new("conn".to_string(), "SELECT n".to_string(), false);
+        query.mark_completed((0..5).map(|n| serde_json::json!({"n": n})).collect(), 3);
+        job.complete_with(query);
+
+        let events = QueryJobEvent::outcome(&job, 2);
+        let names: Vec<&str> = events.iter().map(QueryJobEvent::name).collect();
+        assert_eq!(names, ["row-batch", "row-batch", "row-batch", "completed"]);
+        let data = serde_json::to_value(&events[2]).unwrap();
+        assert_eq!(data["event"], "row-batch");
+        assert_eq!(data["offset"], 4);
+        assert_eq!(data["rows"].as_array().unwrap().len(), 1);
+    }
+}
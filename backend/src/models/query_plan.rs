@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use super::query::QueryParameters;
+
+/// `POST /api/connections/{id}/query/explain`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExplainRequest {
+    pub query: String,
+    /// The query is in DataFusion syntax (as for unified queries) and is
+    /// translated to the connection's dialect before it is explained
+    #[serde(default)]
+    pub unified: bool,
+    /// Values for `:name` placeholders
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+}
+
+/// Format the database returned its plan in
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanFormat {
+    /// Structured plan (`EXPLAIN (FORMAT JSON)`, `EXPLAIN FORMAT=JSON`, Druid native queries)
+    Json,
+    /// Textual plan parsed line by line (Doris)
+    Text,
+}
+
+/// Node of a query plan, normalized across databases
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlanNode {
+    /// Plan operation, e.g. `Seq Scan`, `nested_loop` or `VOlapScanNode`
+    pub operation: String,
+    /// Table the node reads, if any
+    pub relation: Option<String>,
+    /// Rows the database expects the node to produce
+    pub estimated_rows: Option<f64>,
+    /// Cost estimate in the database's own units (not comparable across databases)
+    pub estimated_cost: Option<f64>,
+    /// Other attributes the database reported for the node
+    #[schema(value_type = Object)]
+    pub details: Map<String, Value>,
+    #[schema(no_recursion)]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    pub fn new(operation: impl Into<String>) -> Self {
+        Self {
+            operation: operation.into(),
+            relation: None,
+            estimated_rows: None,
+            estimated_cost: None,
+            details: Map::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Plan of a query as reported by the database's EXPLAIN, without executing it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueryPlan {
+    pub format: PlanFormat,
+    pub root: PlanNode,
+    /// The EXPLAIN output as the database returned it
+    #[schema(value_type = Object)]
+    pub raw: Value,
+}
+
+impl QueryPlan {
+    /// Largest row estimate of any node (the top node's for most plans)
+    pub fn estimated_rows(&self) -> Option<f64> {
+        fn walk(node: &PlanNode, max: &mut Option<f64>) {
+            if let Some(rows) = node.estimated_rows {
+                *max = Some(max.map_or(rows, |m: f64| m.max(rows)));
+            }
+            node.children.iter().for_each(|child| walk(child, max));
+        }
+        let mut max = None;
+        walk(&self.root, &mut max);
+        max
+    }
+}
+
+/// What an optimization suggestion changes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// Create an index (`sql` holds the `CREATE INDEX` statement)
+    MissingIndex,
+    /// Rewrite the query (`sql` holds the rewritten query)
+    Rewrite,
+    /// Refresh table statistics the planner relies on
+    Statistics,
+    Other,
+}
+
+/// Expected gain of applying a suggestion
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionImpact {
+    High,
+    Medium,
+    Low,
+}
+
+/// Where a suggestion came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionSource {
+    /// The LLM, given the plan and the schema
+    Llm,
+    /// Rules over the plan (full scans of large tables, `SELECT *`)
+    Heuristic,
+}
+
+/// Optimization suggestion for a query (`POST /api/connections/{id}/query/advise`)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OptimizationSuggestion {
+    pub kind: SuggestionKind,
+    pub impact: SuggestionImpact,
+    pub title: String,
+    pub description: String,
+    /// Table the suggestion applies to
+    pub table: Option<String>,
+    /// Columns of the suggested index, or the columns involved
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Statement implementing the suggestion; never executed by the server
+    pub sql: Option<String>,
+    pub source: SuggestionSource,
+}
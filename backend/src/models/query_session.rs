@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::query::{Query, QueryParameters, QueryStatus};
+use super::transform::TransformStep;
+
+/// Message sent by the client of an interactive query session (`GET /ws`)
+#[derive(Debug, Clone, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum QuerySessionRequest {
+    /// Run a statement on the session's connection
+    ///
+    /// `id` is chosen by the client and tags every frame about the statement.
+    Query {
+        id: String,
+        query: String,
+        #[serde(default)]
+        confirm_large_result: bool,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        /// Values for the statement's `:name` placeholders
+        #[serde(default)]
+        #[schema(value_type = Object)]
+        parameters: QueryParameters,
+    },
+    /// Cancel a running statement
+    Cancel { id: String },
+}
+
+/// Frame sent by the server of an interactive query session
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum QuerySessionFrame {
+    /// The session is open and bound to `connection_id`
+    Ready {
+        session_id: String,
+        connection_id: String,
+    },
+    Started {
+        id: String,
+    },
+    /// Consecutive slice of the result rows, starting at row `offset`
+    RowBatch {
+        id: String,
+        offset: usize,
+        rows: Vec<serde_json::Value>,
+    },
+    Completed {
+        id: String,
+        row_count: Option<usize>,
+        execution_time_ms: Option<u64>,
+        limit_applied: bool,
+    },
+    Failed {
+        id: String,
+        error_message: Option<String>,
+    },
+    Cancelled {
+        id: String,
+    },
+    /// A client message was rejected (`id` is set when it names a statement)
+    Error {
+        id: Option<String>,
+        message: String,
+    },
+}
+
+impl QuerySessionFrame {
+    /// Row batches and the final frame of an executed statement
+    ///
+    /// Only the final frame when the rows were already streamed.
+    pub fn outcome(id: &str, query: &Query, batch_size: usize) -> Vec<Self> {
+        if query.status != QueryStatus::Completed {
+            return vec![QuerySessionFrame::Failed {
+                id: id.to_string(),
+                error_message: query.error_message.clone(),
+            }];
+        }
+
+        let rows = query.results.as_deref().unwrap_or_default();
+        let mut frames: Vec<Self> = rows
+            .chunks(batch_size.max(1))
+            .enumerate()
+            .map(|(index, batch)| QuerySessionFrame::RowBatch {
+                id: id.to_string(),
+                offset: index * batch_size.max(1),
+                rows: batch.to_vec(),
+            })
+            .collect();
+        frames.push(QuerySessionFrame::Completed {
+            id: id.to_string(),
+            row_count: query.row_count,
+            execution_time_ms: query.execution_time_ms,
+            limit_applied: query.limit_applied,
+        });
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests_and_batch_outcome() {
+        let request: QuerySessionRequest =
+            serde_json::from_str(r#"{"type": "query", "id": "q1", "query": "SELECT n FROM t"}"#).unwrap();
+        assert_eq!(
+            request,
+            QuerySessionRequest::Query {
+                id: "q1".to_string(),
+                query: "SELECT n FROM t".to_string(),
+                confirm_large_result: false,
+                transform: Vec::new(),
+                parameters: QueryParameters::new(),
+            }
+        );
+        let cancel: QuerySessionRequest = serde_json::from_str(r#"{"type": "cancel", "id": "q1"}"#).unwrap();
+        assert_eq!(cancel, QuerySessionRequest::Cancel { id: "q1".to_string() });
+
+        let mut query = Query::new("conn".to_string(), "SELECT n FROM t".to_string(), false);
+        query.mark_completed((0..3).map(|n| serde_json::json!({"n": n})).collect(), 5);
+        let frames = QuerySessionFrame::outcome("q1", &query, 2);
+        assert_eq!(frames.len(), 3);
+        let data = serde_json::to_value(&frames[1]).unwrap();
+        assert_eq!(data["type"], "row-batch");
+        assert_eq!(data["offset"], 2);
+        assert_eq!(serde_json::to_value(&frames[2]).unwrap()["type"], "completed");
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Execution context captured alongside a query history entry
+///
+/// Used to explain why replaying a historic query yields different results.
+/// Connection settings are stored as a fingerprint so credentials never end
+/// up in history.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueryHistorySnapshot {
+    pub history_id: String,
+    pub connection_id: String,
+    pub connection_version: i64,
+    /// Hash of the database type and URL without credentials (empty for
+    /// snapshots captured before credentials were stripped)
+    pub connection_fingerprint: String,
+    pub database_type: String,
+    pub metadata_cache_id: Option<String>,
+    pub metadata_version: Option<i32>,
+    pub result_hash: Option<String>,
+    pub limit_applied: bool,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Result and context of one side of a replay comparison
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReplayExecution {
+    pub row_count: usize,
+    pub result_hash: Option<String>,
+    pub connection_version: Option<i64>,
+    pub metadata_version: Option<i32>,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Kind of difference detected between the original execution and a replay
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayDifferenceKind {
+    MissingSnapshot,
+    ResultChanged,
+    RowCountChanged,
+    MetadataChanged,
+    ConnectionSettingsChanged,
+    DatabaseTypeChanged,
+    LimitChanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReplayDifference {
+    pub kind: ReplayDifferenceKind,
+    pub message: String,
+}
+
+/// Outcome of replaying a historic query against the current database
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReplayReport {
+    pub history_id: String,
+    pub query_text: String,
+    pub original: ReplayExecution,
+    pub replay: ReplayExecution,
+    /// True when both executions produced an identical result hash
+    pub results_match: bool,
+    pub differences: Vec<ReplayDifference>,
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Type a contract column may declare
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContractType {
+    Integer,
+    /// Floating point and decimal columns
+    Float,
+    String,
+    Boolean,
+    Date,
+    Timestamp,
+}
+
+impl ContractType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContractType::Integer => "integer",
+            ContractType::Float => "float",
+            ContractType::String => "string",
+            ContractType::Boolean => "boolean",
+            ContractType::Date => "date",
+            ContractType::Timestamp => "timestamp",
+        }
+    }
+}
+
+/// Column a saved query's result is declared to have
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ContractColumn {
+    /// Column name, matched exactly
+    pub name: String,
+    /// Expected type (omit to only require the column)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<ContractType>,
+}
+
+/// Declared result schema of a saved query, with the outcome of the latest check
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResultContract {
+    pub saved_query_id: String,
+    pub domain_id: String,
+    /// Columns of the result, in order
+    pub columns: Vec<ContractColumn>,
+    /// How the latest checked result differed from `columns` (empty when it
+    /// matched or was never checked)
+    #[serde(default)]
+    pub drift: Vec<String>,
+    /// When a result was last checked against the contract
+    pub checked_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ResultContract {
+    pub fn new(saved_query_id: String, domain_id: String, columns: Vec<ContractColumn>) -> Self {
+        Self {
+            saved_query_id,
+            domain_id,
+            columns,
+            drift: Vec::new(),
+            checked_at: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Request to declare (or replace) a saved query's result contract
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetResultContractRequest {
+    /// Columns (in order) and types the query's result must have
+    pub columns: Vec<ContractColumn>,
+}
+
+impl SetResultContractRequest {
+    /// At least one column, names non-empty and unique
+    pub fn validate(&self) -> Result<(), String> {
+        if self.columns.is_empty() {
+            return Err("A result contract must declare at least one column".to_string());
+        }
+        let mut seen = std::collections::HashSet::new();
+        for column in &self.columns {
+            if column.name.trim().is_empty() {
+                return Err("Contract column names cannot be empty".to_string());
+            }
+            if !seen.insert(column.name.as_str()) {
+                return Err(format!("Column {} is declared twice", column.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_contract_request() {
+        let column = |name: &str| ContractColumn { name: name.to_string(), data_type: Some(ContractType::Integer) };
+        let request = |columns| SetResultContractRequest { columns };
+        assert!(request(vec![column("id"), column("total")]).validate().is_ok());
+        assert!(request(Vec::new()).validate().is_err());
+        assert!(request(vec![column("id"), column("id")]).validate().is_err());
+        assert!(request(vec![column(" ")]).validate().is_err());
+
+        let parsed: ContractColumn = serde_json::from_str(r#"{"name": "region"}"#).unwrap();
+        assert_eq!(parsed.data_type, None);
+        let parsed: ContractColumn = serde_json::from_str(r#"{"name": "total", "data_type": "float"}"#).unwrap();
+        assert_eq!(parsed.data_type, Some(ContractType::Float));
+    }
+}
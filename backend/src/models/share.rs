@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::api_key::ApiKey;
+use super::chart::ChartSpec;
+use super::query::{QueryParameters, SavedQuery};
+
+/// Prefix of every share token
+pub const SHARE_TOKEN_PREFIX: &str = "dbs_";
+
+/// Longest lifetime a share link may be given (one year)
+pub const MAX_SHARE_TTL_SECS: u64 = 365 * 24 * 3600;
+
+/// Link to the result of a saved query, readable without authentication
+///
+/// Only the SHA-256 hash of the token is stored; the plaintext is returned
+/// once when the share is created.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueryShare {
+    pub id: String,
+    pub domain_id: String,
+    pub saved_query_id: String,
+    /// First characters of the token, shown to help identify it
+    pub token_prefix: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    /// Parameter values the query runs with (visitors cannot change them)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+    /// When the frozen result was captured (null: every visit runs the query)
+    pub snapshot_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Null: the link does not expire
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// `saved_query_hash` of the saved query when the share was created
+    #[serde(skip_serializing, default)]
+    pub query_hash: String,
+}
+
+impl QueryShare {
+    /// Generate a share; returns the stored record and the plaintext token
+    pub fn generate(
+        domain_id: String,
+        saved_query_id: String,
+        parameters: QueryParameters,
+        expires_in_secs: Option<u64>,
+        created_by: Option<String>,
+    ) -> (Self, String) {
+        let token = format!("{}{}{}", SHARE_TOKEN_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let created_at = Utc::now();
+        let share = Self {
+            id: Uuid::new_v4().to_string(),
+            domain_id,
+            saved_query_id,
+            token_prefix: token[..SHARE_TOKEN_PREFIX.len() + 8].to_string(),
+            token_hash: Self::hash(&token),
+            parameters,
+            snapshot_at: None,
+            created_by,
+            created_at,
+            expires_at: expires_in_secs.map(|secs| created_at + chrono::Duration::seconds(secs as i64)),
+            revoked_at: None,
+            query_hash: String::new(),
+        };
+        (share, token)
+    }
+
+    /// Hex-encoded SHA-256 of a plaintext token
+    pub fn hash(token: &str) -> String {
+        ApiKey::hash(token)
+    }
+
+    /// Hash of the SQL and connection of a saved query
+    pub fn saved_query_hash(saved: &SavedQuery) -> String {
+        Self::hash(&format!("{}\n{}", saved.connection_id.as_deref().unwrap_or(""), saved.query_text))
+    }
+
+    /// Whether `saved` still runs the SQL on the connection the share was
+    /// created for (shares created before the hash was kept never match)
+    pub fn matches_saved_query(&self, saved: &SavedQuery) -> bool {
+        !self.query_hash.is_empty() && self.query_hash == Self::saved_query_hash(saved)
+    }
+
+    /// Identifier recorded in query history for runs of the share
+    pub fn subject(&self) -> String {
+        format!("share:{}", self.id)
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Request payload for sharing a saved query
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CreateQueryShareRequest {
+    /// Freeze the current result instead of running the query on every visit
+    #[serde(default)]
+    pub snapshot: bool,
+    /// Lifetime of the link (omit for a link that does not expire)
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+    /// Values for `:name` placeholders (override the saved query's defaults)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub parameters: QueryParameters,
+}
+
+impl CreateQueryShareRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.expires_in_secs {
+            Some(secs) if !(60..=MAX_SHARE_TTL_SECS).contains(&secs) => Err(format!(
+                "expires_in_secs must be between 60 and {}",
+                MAX_SHARE_TTL_SECS
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Response for a new share; the plaintext token is only returned once
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateQueryShareResponse {
+    pub share: QueryShare,
+    pub token: String,
+    /// Path serving the shared result (`/api/share/{token}`)
+    pub path: String,
+}
+
+/// Shared result as served to visitors of a share link
+///
+/// Leaves out the SQL and the connection; only the saved query's name and
+/// description describe the result.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SharedResult {
+    pub name: String,
+    pub description: Option<String>,
+    /// Whether the result is a snapshot frozen when the link was created
+    pub snapshot: bool,
+    pub executed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub results: Vec<serde_json::Value>,
+    pub row_count: usize,
+    /// Whether the domain row limit cut the result off
+    pub limit_applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chart: Option<ChartSpec>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_share() {
+        let (share, token) = QueryShare::generate(
+            "domain-1".to_string(),
+            "saved-1".to_string(),
+            QueryParameters::new(),
+            Some(3600),
+            Some("alice".to_string()),
+        );
+        assert!(token.starts_with(SHARE_TOKEN_PREFIX));
+        assert!(token.starts_with(&share.token_prefix));
+        assert_eq!(share.token_hash, QueryShare::hash(&token));
+        assert!(!share.is_expired(Utc::now()));
+        assert!(share.is_expired(Utc::now() + chrono::Duration::hours(2)));
+        assert_eq!(share.subject(), format!("share:{}", share.id));
+
+        // The hash is never serialized
+        let json = serde_json::to_string(&share).unwrap();
+        assert!(!json.contains(&share.token_hash));
+    }
+
+    #[test]
+    fn test_validate_share_request() {
+        assert!(CreateQueryShareRequest::default().validate().is_ok());
+        let request = CreateQueryShareRequest {
+            expires_in_secs: Some(10),
+            ..Default::default()
+        };
+        assert!(request.validate().is_err());
+    }
+}
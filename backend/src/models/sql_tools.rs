@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Layout of formatted SQL
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FormatStyle {
+    /// One clause per line with indented lists (single line when false)
+    #[serde(default = "default_true")]
+    pub pretty: bool,
+    /// Upper-case keywords (lower-case when false)
+    #[serde(default = "default_true")]
+    pub uppercase_keywords: bool,
+    /// Spaces per indentation level of pretty output
+    #[serde(default = "default_indent")]
+    pub indent: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_indent() -> usize {
+    2
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        Self {
+            pretty: true,
+            uppercase_keywords: true,
+            indent: default_indent(),
+        }
+    }
+}
+
+/// `POST /api/sql/format`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FormatSqlRequest {
+    pub sql: String,
+    /// Dialect to parse with: postgresql, mysql, doris, druid or generic
+    /// (default: PostgreSQL, falling back to generic)
+    #[serde(default)]
+    pub dialect: Option<String>,
+    #[serde(default)]
+    pub style: FormatStyle,
+}
+
+/// `POST /api/sql/lint`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LintSqlRequest {
+    pub sql: String,
+    /// Dialect to parse with, as for formatting
+    #[serde(default)]
+    pub dialect: Option<String>,
+}
+
+/// `POST /api/sql/translate`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TranslateSqlRequest {
+    /// Query in DataFusion (unified) SQL
+    pub sql: String,
+    /// Target dialect: postgresql, mysql, doris or druid
+    pub dialect: String,
+}
+
+/// Kind of change a dialect translation made
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteKind {
+    /// A quoted identifier re-quoted for the dialect (e.g. `"id"` to `` `id` ``)
+    IdentifierQuoting,
+    /// A function substituted (e.g. `CURRENT_DATE` to `CURDATE()`)
+    Function,
+    /// A CAST target type renamed (e.g. `TEXT` to `CHAR`)
+    TypeName,
+    /// An INTERVAL literal rewritten (e.g. `INTERVAL '7 days'` to `INTERVAL 7 DAY`)
+    Interval,
+}
+
+/// Change made while translating a query to a dialect
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct SqlRewrite {
+    pub kind: RewriteKind,
+    /// Fragment of the submitted SQL
+    pub original: String,
+    /// What the fragment became
+    pub translated: String,
+    /// 1-based position of the fragment in the submitted SQL
+    pub line: u64,
+    pub column: u64,
+}
+
+/// Check a lint issue comes from
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// `SELECT *` or `t.*`: fragile when columns change and reads unneeded data
+    SelectStar,
+    /// The outermost query returns an unbounded number of rows
+    MissingLimit,
+    /// Tables combined without a join condition
+    CartesianJoin,
+    /// A predicate wraps a column in a function, cast or arithmetic, or
+    /// starts a LIKE pattern with a wildcard, so indexes cannot be used
+    NonSargablePredicate,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Warning,
+    Info,
+}
+
+/// Issue found in a query
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub severity: LintSeverity,
+    pub message: String,
+    /// The offending SQL fragment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>,
+    /// 1-based position of the fragment in the submitted SQL, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+}
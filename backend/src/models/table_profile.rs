@@ -0,0 +1,195 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::models::{EffectivePolicy, MASKED_VALUE};
+
+/// How a column's values are profiled, judged from its database type
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnKind {
+    /// Numbers: min/max, top values and a histogram
+    Numeric,
+    /// Dates and timestamps: min/max and top values
+    Temporal,
+    /// Text and other ordered scalars: min/max and top values
+    Text,
+    /// Booleans: top values only, as not every database orders them
+    Boolean,
+    /// JSON, binary, arrays and spatial types: null counts only
+    Other,
+}
+
+impl ColumnKind {
+    pub fn of(data_type: &str) -> Self {
+        let data_type = data_type.to_lowercase();
+        let has = |fragments: &[&str]| fragments.iter().any(|fragment| data_type.contains(fragment));
+
+        if data_type.ends_with("[]")
+            || has(&["json", "blob", "binary", "bytea", "array", "xml", "geometry", "geography", "point", "polygon", "bitmap", "hll", "map", "struct", "interval"])
+        {
+            Self::Other
+        } else if has(&["bool"]) || data_type == "bit" || data_type == "tinyint(1)" {
+            Self::Boolean
+        } else if has(&["int", "numeric", "decimal", "float", "double", "real", "number", "money", "serial"]) {
+            Self::Numeric
+        } else if has(&["date", "time", "year"]) {
+            Self::Temporal
+        } else if has(&["char", "text", "string", "uuid", "enum", "citext", "name"]) {
+            Self::Text
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Whether the column has an order, so MIN and MAX apply
+    pub fn is_ordered(self) -> bool {
+        matches!(self, Self::Numeric | Self::Temporal | Self::Text)
+    }
+
+    /// Whether values can be counted distinct and grouped
+    pub fn is_groupable(self) -> bool {
+        self != Self::Other
+    }
+}
+
+/// A value of a column and the rows holding it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ValueFrequency {
+    #[schema(value_type = Object)]
+    pub value: Value,
+    pub count: u64,
+}
+
+/// Rows whose value lies in `[lower, upper)`, or `[lower, upper]` for the last bucket
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HistogramBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u64,
+}
+
+/// Statistics of one column of a profiled table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ColumnStatistics {
+    pub name: String,
+    pub data_type: String,
+    pub kind: ColumnKind,
+    pub null_count: u64,
+    /// Null rows as a share of all rows (0 for an empty table)
+    pub null_ratio: f64,
+    /// Distinct non-null values; None for columns of kind `other`
+    pub distinct_count: Option<u64>,
+    #[schema(value_type = Option<Object>)]
+    pub min: Option<Value>,
+    #[schema(value_type = Option<Object>)]
+    pub max: Option<Value>,
+    /// Most frequent non-null values, most frequent first
+    pub top_values: Vec<ValueFrequency>,
+    /// Equal-width buckets between min and max (numeric columns)
+    pub histogram: Vec<HistogramBucket>,
+    /// Values withheld by a connection policy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub masked: bool,
+}
+
+/// Profile of a table's columns, computed by generated SQL on the database
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct TableProfile {
+    pub connection_id: String,
+    pub schema: String,
+    pub table: String,
+    /// Metadata version the columns were taken from; a newer version makes
+    /// the cached profile stale
+    pub metadata_version: i32,
+    pub row_count: u64,
+    pub columns: Vec<ColumnStatistics>,
+    /// Columns left out beyond the profiling limit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_columns: Vec<String>,
+    /// Time spent on the profiling queries
+    pub execution_time_ms: u64,
+    pub profiled_at: DateTime<Utc>,
+}
+
+impl TableProfile {
+    /// Replace the values of columns masked by `policy`, keeping their counts
+    pub fn mask(&mut self, policy: &EffectivePolicy) {
+        for column in &mut self.columns {
+            if !policy.masks_column(&column.name) {
+                continue;
+            }
+            let masked = || Some(Value::String(MASKED_VALUE.to_string()));
+            column.min = column.min.as_ref().and(masked());
+            column.max = column.max.as_ref().and(masked());
+            column.top_values.clear();
+            column.histogram.clear();
+            column.masked = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_kind_of() {
+        assert_eq!(ColumnKind::of("integer"), ColumnKind::Numeric);
+        assert_eq!(ColumnKind::of("DECIMAL(10,2)"), ColumnKind::Numeric);
+        assert_eq!(ColumnKind::of("timestamp with time zone"), ColumnKind::Temporal);
+        assert_eq!(ColumnKind::of("varchar(255)"), ColumnKind::Text);
+        assert_eq!(ColumnKind::of("boolean"), ColumnKind::Boolean);
+        assert_eq!(ColumnKind::of("tinyint(1)"), ColumnKind::Boolean);
+        // Not integers, despite the fragment
+        assert_eq!(ColumnKind::of("interval"), ColumnKind::Other);
+        assert_eq!(ColumnKind::of("point"), ColumnKind::Other);
+        assert_eq!(ColumnKind::of("jsonb"), ColumnKind::Other);
+        assert_eq!(ColumnKind::of("integer[]"), ColumnKind::Other);
+    }
+
+    #[test]
+    fn test_mask_profile() {
+        let column = |name: &str| ColumnStatistics {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            kind: ColumnKind::Text,
+            null_count: 1,
+            null_ratio: 0.5,
+            distinct_count: Some(1),
+            min: Some(Value::String("a@example.com".to_string())),
+            max: None,
+            top_values: vec![ValueFrequency {
+                value: Value::String("a@example.com".to_string()),
+                count: 1,
+            }],
+            histogram: Vec::new(),
+            masked: false,
+        };
+        let mut profile = TableProfile {
+            connection_id: "c".to_string(),
+            schema: "public".to_string(),
+            table: "customers".to_string(),
+            metadata_version: 1,
+            row_count: 2,
+            columns: vec![column("Email"), column("name")],
+            skipped_columns: Vec::new(),
+            execution_time_ms: 0,
+            profiled_at: Utc::now(),
+        };
+        let policy = EffectivePolicy {
+            masked_columns: ["email".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+
+        profile.mask(&policy);
+        assert!(profile.columns[0].masked);
+        assert_eq!(profile.columns[0].min, Some(Value::String(MASKED_VALUE.to_string())));
+        assert_eq!(profile.columns[0].max, None);
+        assert!(profile.columns[0].top_values.is_empty());
+        assert_eq!(profile.columns[0].null_count, 1);
+        assert!(!profile.columns[1].masked);
+        assert_eq!(profile.columns[1].top_values.len(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Lifecycle of a transaction session
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Open,
+    Committed,
+    RolledBack,
+}
+
+/// Transaction pinned to one pooled connection (`/api/sessions/{id}`)
+///
+/// Sessions live in the server's memory only; a restart rolls them back.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransactionSession {
+    pub id: String,
+    pub connection_id: String,
+    pub domain_id: Option<String>,
+    /// Principal subject that opened the session; only it may use the session
+    pub opened_by: Option<String>,
+    pub status: TransactionStatus,
+    /// Statements executed in the session
+    pub statement_count: usize,
+    /// Rows affected by those statements
+    pub affected_rows: u64,
+    pub opened_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    /// Rolled back automatically if still idle at this time
+    pub expires_at: DateTime<Utc>,
+}
+
+impl TransactionSession {
+    pub fn new(
+        connection_id: String,
+        domain_id: Option<String>,
+        opened_by: Option<String>,
+        idle_timeout_secs: u64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connection_id,
+            domain_id,
+            opened_by,
+            status: TransactionStatus::Open,
+            statement_count: 0,
+            affected_rows: 0,
+            opened_at: now,
+            last_used_at: now,
+            expires_at: now + chrono::Duration::seconds(idle_timeout_secs as i64),
+        }
+    }
+
+    /// Count an executed statement and push the idle deadline back
+    pub fn record_statement(&mut self, affected_rows: u64, idle_timeout_secs: u64) {
+        self.statement_count += 1;
+        self.affected_rows += affected_rows;
+        self.last_used_at = Utc::now();
+        self.expires_at = self.last_used_at + chrono::Duration::seconds(idle_timeout_secs as i64);
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Most steps a single transformation pipeline may have
+pub const MAX_TRANSFORM_STEPS: usize = 50;
+
+/// Target type of a `cast` step
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CastType {
+    Integer,
+    Float,
+    String,
+    Boolean,
+    Date,
+    Timestamp,
+}
+
+/// One post-processing step applied to query results
+///
+/// Expressions and predicates are DataFusion SQL expressions over the
+/// result's columns; quote mixed-case column names (`"createdAt"`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum TransformStep {
+    /// Rename a column
+    Rename { column: String, to: String },
+    /// Convert a column to another type
+    Cast {
+        column: String,
+        #[serde(rename = "type")]
+        to: CastType,
+    },
+    /// Add (or replace) a column computed from an expression, e.g. `price * quantity`
+    Derive { column: String, expr: String },
+    /// Keep only rows matching a predicate, e.g. `status = 'active'`
+    Filter { predicate: String },
+}
+
+impl TransformStep {
+    /// Name of the step's operation, as used in the `op` field
+    pub fn op(&self) -> &'static str {
+        match self {
+            TransformStep::Rename { .. } => "rename",
+            TransformStep::Cast { .. } => "cast",
+            TransformStep::Derive { .. } => "derive",
+            TransformStep::Filter { .. } => "filter",
+        }
+    }
+
+    /// Check that all fields of the step are filled in
+    pub fn validate(&self) -> Result<(), String> {
+        let required: Vec<(&str, &str)> = match self {
+            TransformStep::Rename { column, to } => vec![("column", column.as_str()), ("to", to.as_str())],
+            TransformStep::Cast { column, .. } => vec![("column", column.as_str())],
+            TransformStep::Derive { column, expr } => vec![("column", column.as_str()), ("expr", expr.as_str())],
+            TransformStep::Filter { predicate } => vec![("predicate", predicate.as_str())],
+        };
+        match required.iter().find(|(_, value)| value.trim().is_empty()) {
+            Some((field, _)) => Err(format!("{} step needs a non-empty '{}'", self.op(), field)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Validate a whole pipeline (step count and every step)
+pub fn validate_transform(steps: &[TransformStep]) -> Result<(), String> {
+    if steps.len() > MAX_TRANSFORM_STEPS {
+        return Err(format!(
+            "Transformation has {} steps (at most {} allowed)",
+            steps.len(),
+            MAX_TRANSFORM_STEPS
+        ));
+    }
+    for (index, step) in steps.iter().enumerate() {
+        step.validate()
+            .map_err(|e| format!("Transformation step {}: {}", index + 1, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_validate_steps() {
+        let steps: Vec<TransformStep> = serde_json::from_value(serde_json::json!([
+            {"op": "rename", "column": "amt", "to": "amount"},
+            {"op": "cast", "column": "amount", "type": "float"},
+            {"op": "derive", "column": "total", "expr": "amount * 2"},
+            {"op": "filter", "predicate": "total > 10"},
+        ]))
+        .unwrap();
+        assert_eq!(steps[1], TransformStep::Cast { column: "amount".to_string(), to: CastType::Float });
+        assert!(validate_transform(&steps).is_ok());
+
+        let blank = vec![TransformStep::Filter { predicate: " ".to_string() }];
+        assert_eq!(
+            validate_transform(&blank).unwrap_err(),
+            "Transformation step 1: filter step needs a non-empty 'predicate'"
+        );
+    }
+}
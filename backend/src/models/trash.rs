@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Kind of resource that can be soft-deleted into the trash
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashResourceType {
+    Domain,
+    Connection,
+    SavedQuery,
+}
+
+impl TrashResourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrashResourceType::Domain => "domain",
+            TrashResourceType::Connection => "connection",
+            TrashResourceType::SavedQuery => "saved_query",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "domain" | "domains" => Ok(TrashResourceType::Domain),
+            "connection" | "connections" => Ok(TrashResourceType::Connection),
+            "saved_query" | "saved_queries" => Ok(TrashResourceType::SavedQuery),
+            _ => Err(format!(
+                "Unknown resource type: {}. Expected domain, connection or saved_query",
+                s
+            )),
+        }
+    }
+
+    /// Backing SQLite table for this resource type
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            TrashResourceType::Domain => "domains",
+            TrashResourceType::Connection => "connections",
+            TrashResourceType::SavedQuery => "saved_queries",
+        }
+    }
+}
+
+/// A soft-deleted resource awaiting restore or purge
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrashItem {
+    pub resource_type: TrashResourceType,
+    pub id: String,
+    pub name: Option<String>,
+    /// Owning domain (None for domains themselves)
+    pub domain_id: Option<String>,
+    pub deleted_at: DateTime<Utc>,
+    /// When the item becomes eligible for permanent purge (None if purging is disabled)
+    pub purge_after: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_resource_type_round_trip() {
+        for resource_type in [
+            TrashResourceType::Domain,
+            TrashResourceType::Connection,
+            TrashResourceType::SavedQuery,
+        ] {
+            assert_eq!(TrashResourceType::from_str(resource_type.as_str()).unwrap(), resource_type);
+        }
+
+        assert_eq!(TrashResourceType::from_str("saved_queries").unwrap(), TrashResourceType::SavedQuery);
+        assert!(TrashResourceType::from_str("history").is_err());
+    }
+}
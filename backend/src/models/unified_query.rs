@@ -5,10 +5,13 @@
 // to the target database's dialect.
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use chrono::{DateTime, Utc};
 
+use super::query::ColumnarResult;
+
 /// Database type enumeration for unified query execution
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum DatabaseType {
     /// PostgreSQL database
@@ -49,7 +52,7 @@ impl DatabaseType {
 /// This represents a query that will be executed using DataFusion's unified SQL semantics.
 /// The query is written in DataFusion SQL and will be automatically translated to the
 /// target database's dialect.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UnifiedQueryRequest {
     /// The SQL query in DataFusion syntax
     pub query: String,
@@ -57,53 +60,47 @@ pub struct UnifiedQueryRequest {
     /// The target database type for execution
     pub database_type: DatabaseType,
 
-    /// Optional timeout in seconds (defaults to 30)
-    #[serde(default = "default_timeout")]
-    pub timeout_secs: u64,
+    /// Optional timeout in seconds (defaults to the domain's default timeout,
+    /// capped by its maximum)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
 
     /// Whether to apply automatic LIMIT if not present (defaults to true)
     #[serde(default = "default_apply_limit")]
     pub apply_limit: bool,
 
-    /// The LIMIT value to apply if apply_limit is true (defaults to 1000)
-    #[serde(default = "default_limit_value")]
-    pub limit_value: usize,
-}
-
-fn default_timeout() -> u64 {
-    30
+    /// The LIMIT value to apply if apply_limit is true (defaults to the
+    /// domain's default row limit, capped by its maximum)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_value: Option<usize>,
 }
 
 fn default_apply_limit() -> bool {
     true
 }
 
-fn default_limit_value() -> usize {
-    1000
-}
-
 impl UnifiedQueryRequest {
     /// Create a new unified query request
     pub fn new(query: String, database_type: DatabaseType) -> Self {
         Self {
             query,
             database_type,
-            timeout_secs: default_timeout(),
+            timeout_secs: None,
             apply_limit: default_apply_limit(),
-            limit_value: default_limit_value(),
+            limit_value: None,
         }
     }
 
     /// Create a query request with custom timeout
     pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
-        self.timeout_secs = timeout_secs;
+        self.timeout_secs = Some(timeout_secs);
         self
     }
 
     /// Create a query request with custom limit settings
     pub fn with_limit(mut self, apply_limit: bool, limit_value: usize) -> Self {
         self.apply_limit = apply_limit;
-        self.limit_value = limit_value;
+        self.limit_value = Some(limit_value);
         self
     }
 }
@@ -111,7 +108,7 @@ impl UnifiedQueryRequest {
 /// Unified query response model
 ///
 /// This represents the result of executing a unified SQL query.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UnifiedQueryResponse {
     /// The original query in DataFusion SQL syntax
     pub original_query: String,
@@ -136,6 +133,9 @@ pub struct UnifiedQueryResponse {
 
     /// Timestamp when the query was executed
     pub executed_at: DateTime<Utc>,
+    /// Results by column (`result_format=columnar`, `results` is then empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columnar: Option<ColumnarResult>,
 }
 
 impl UnifiedQueryResponse {
@@ -159,12 +159,13 @@ impl UnifiedQueryResponse {
             execution_time_ms,
             limit_applied,
             executed_at: Utc::now(),
+            columnar: None,
         }
     }
 }
 
 /// Simplified request for backward compatibility
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SimpleUnifiedQueryRequest {
     /// The SQL query
     pub query: String,
@@ -217,9 +218,10 @@ mod tests {
             DatabaseType::PostgreSQL,
         );
 
-        assert_eq!(req.timeout_secs, 30);
+        // Left to the domain's settings
+        assert_eq!(req.timeout_secs, None);
         assert!(req.apply_limit);
-        assert_eq!(req.limit_value, 1000);
+        assert_eq!(req.limit_value, None);
     }
 
     #[test]
@@ -231,9 +233,9 @@ mod tests {
         .with_timeout(60)
         .with_limit(false, 0);
 
-        assert_eq!(req.timeout_secs, 60);
+        assert_eq!(req.timeout_secs, Some(60));
         assert!(!req.apply_limit);
-        assert_eq!(req.limit_value, 0);
+        assert_eq!(req.limit_value, Some(0));
     }
 
     #[test]
@@ -0,0 +1,654 @@
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::{BTreeMap, HashMap};
+
+/// Hourly usage aggregate for one connection in a domain
+///
+/// Maintained incrementally whenever a query history entry is recorded, so
+/// usage reports never need to scan raw history.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UsageBucket {
+    pub domain_id: String,
+    pub connection_id: String,
+    /// Start of the hour (UTC) this bucket covers
+    pub bucket_start: DateTime<Utc>,
+    pub total_count: u64,
+    pub success_count: u64,
+    pub failed_count: u64,
+    pub llm_count: u64,
+    pub total_execution_time_ms: u64,
+}
+
+/// Queries per day, split by outcome
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub total: u64,
+    pub success: u64,
+    pub failed: u64,
+}
+
+/// Query distribution for a single connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ConnectionUsage {
+    pub connection_id: String,
+    pub total: u64,
+    pub success: u64,
+    pub failed: u64,
+    pub avg_execution_time_ms: f64,
+}
+
+/// Aggregated usage report for a domain
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainUsageReport {
+    pub domain_id: String,
+    pub since: DateTime<Utc>,
+    /// Query counts indexed by `[day_of_week][hour]` (Monday = 0, UTC hours)
+    #[schema(value_type = Vec<Vec<u64>>)]
+    pub heatmap: [[u64; 24]; 7],
+    /// Daily success/error trend (oldest first)
+    pub daily: Vec<DailyUsage>,
+    /// Per-connection distribution (busiest first)
+    pub connections: Vec<ConnectionUsage>,
+    pub total_queries: u64,
+    pub llm_queries: u64,
+    pub manual_queries: u64,
+    /// Share of LLM-generated queries (0.0 to 1.0)
+    pub llm_ratio: f64,
+}
+
+impl DomainUsageReport {
+    /// Build a report from hourly buckets
+    pub fn from_buckets(domain_id: String, since: DateTime<Utc>, buckets: &[UsageBucket]) -> Self {
+        let mut heatmap = [[0u64; 24]; 7];
+        let mut daily: BTreeMap<NaiveDate, DailyUsage> = BTreeMap::new();
+        let mut connections: HashMap<String, (ConnectionUsage, u64)> = HashMap::new();
+        let mut total_queries = 0;
+        let mut llm_queries = 0;
+
+        for bucket in buckets {
+            let day = bucket.bucket_start.weekday().num_days_from_monday() as usize;
+            let hour = bucket.bucket_start.hour() as usize;
+            heatmap[day][hour] += bucket.total_count;
+
+            let date = bucket.bucket_start.date_naive();
+            let entry = daily.entry(date).or_insert_with(|| DailyUsage {
+                date,
+                total: 0,
+                success: 0,
+                failed: 0,
+            });
+            entry.total += bucket.total_count;
+            entry.success += bucket.success_count;
+            entry.failed += bucket.failed_count;
+
+            let (usage, execution_time_ms) = connections
+                .entry(bucket.connection_id.clone())
+                .or_insert_with(|| {
+                    (
+                        ConnectionUsage {
+                            connection_id: bucket.connection_id.clone(),
+                            total: 0,
+                            success: 0,
+                            failed: 0,
+                            avg_execution_time_ms: 0.0,
+                        },
+                        0,
+                    )
+                });
+            usage.total += bucket.total_count;
+            usage.success += bucket.success_count;
+            usage.failed += bucket.failed_count;
+            *execution_time_ms += bucket.total_execution_time_ms;
+
+            total_queries += bucket.total_count;
+            llm_queries += bucket.llm_count;
+        }
+
+        let mut connections: Vec<ConnectionUsage> = connections
+            .into_values()
+            .map(|(mut usage, execution_time_ms)| {
+                if usage.total > 0 {
+                    usage.avg_execution_time_ms = execution_time_ms as f64 / usage.total as f64;
+                }
+                usage
+            })
+            .collect();
+        connections.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.connection_id.cmp(&b.connection_id)));
+
+        let llm_ratio = if total_queries == 0 {
+            0.0
+        } else {
+            llm_queries as f64 / total_queries as f64
+        };
+
+        Self {
+            domain_id,
+            since,
+            heatmap,
+            daily: daily.into_values().collect(),
+            connections,
+            total_queries,
+            llm_queries,
+            manual_queries: total_queries - llm_queries,
+            llm_ratio,
+        }
+    }
+}
+
+/// Daily API usage rollup of a domain
+///
+/// Maintained incrementally: API calls and response bytes by the usage
+/// middleware, query counts with each history entry, LLM requests by the
+/// natural language endpoint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DailyApiUsage {
+    pub date: NaiveDate,
+    pub api_calls: u64,
+    /// Calls answered with a 4xx or 5xx status
+    pub api_errors: u64,
+    /// Response body bytes sent
+    pub bytes_out: u64,
+    pub queries: u64,
+    pub failed_queries: u64,
+    pub rows_returned: u64,
+    /// Natural language to SQL requests sent to the LLM gateway
+    pub llm_requests: u64,
+    pub llm_failures: u64,
+}
+
+impl DailyApiUsage {
+    /// Share of API calls that failed (0.0 to 1.0)
+    pub fn error_rate(&self) -> f64 {
+        if self.api_calls == 0 {
+            0.0
+        } else {
+            self.api_errors as f64 / self.api_calls as f64
+        }
+    }
+
+    fn add(&mut self, other: &DailyApiUsage) {
+        self.api_calls += other.api_calls;
+        self.api_errors += other.api_errors;
+        self.bytes_out += other.bytes_out;
+        self.queries += other.queries;
+        self.failed_queries += other.failed_queries;
+        self.rows_returned += other.rows_returned;
+        self.llm_requests += other.llm_requests;
+        self.llm_failures += other.llm_failures;
+    }
+}
+
+/// Monthly API usage report of a domain (`GET /api/domains/{id}/reports`)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiUsageReport {
+    pub domain_id: String,
+    /// Reported month (`YYYY-MM`)
+    pub month: String,
+    /// Days with any usage (oldest first)
+    pub days: Vec<DailyApiUsage>,
+    /// Sums over the month (`date` is the first day of the month)
+    pub totals: DailyApiUsage,
+    /// Share of API calls that failed over the month
+    pub error_rate: f64,
+}
+
+impl ApiUsageReport {
+    pub fn from_days(domain_id: String, month: NaiveDate, days: Vec<DailyApiUsage>) -> Self {
+        let mut totals = DailyApiUsage { date: month, ..Default::default() };
+        for day in &days {
+            totals.add(day);
+        }
+        Self {
+            domain_id,
+            month: month.format("%Y-%m").to_string(),
+            error_rate: totals.error_rate(),
+            days,
+            totals,
+        }
+    }
+
+    /// CSV export: one row per day plus a `total` row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "domain_id,date,api_calls,api_errors,error_rate,bytes_out,queries,failed_queries,rows_returned,llm_requests,llm_failures\n",
+        );
+        let rows = self
+            .days
+            .iter()
+            .map(|day| (day.date.to_string(), day))
+            .chain(std::iter::once(("total".to_string(), &self.totals)));
+        for (date, usage) in rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.4},{},{},{},{},{},{}\n",
+                self.domain_id,
+                date,
+                usage.api_calls,
+                usage.api_errors,
+                usage.error_rate(),
+                usage.bytes_out,
+                usage.queries,
+                usage.failed_queries,
+                usage.rows_returned,
+                usage.llm_requests,
+                usage.llm_failures,
+            ));
+        }
+        csv
+    }
+}
+
+/// Tokens of one or more LLM calls, as reported by the gateway or estimated
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct LlmTokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl LlmTokenUsage {
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn add(&mut self, other: LlmTokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+/// Daily LLM usage of one principal in a domain (`llm_usage_daily`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmUsageRecord {
+    /// Principal subject (None for unauthenticated requests)
+    pub principal: Option<String>,
+    pub date: NaiveDate,
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub tokens: LlmTokenUsage,
+}
+
+/// Requests and tokens sent to the LLM gateway
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct LlmUsageTotals {
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl LlmUsageTotals {
+    fn add(&mut self, record: &LlmUsageRecord) {
+        self.requests += record.requests;
+        self.failed_requests += record.failed_requests;
+        self.prompt_tokens += record.tokens.prompt_tokens;
+        self.completion_tokens += record.tokens.completion_tokens;
+        self.total_tokens += record.tokens.total();
+    }
+}
+
+/// LLM usage of a domain on one day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DailyLlmUsage {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub usage: LlmUsageTotals,
+}
+
+/// LLM usage of one principal over the reported month
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PrincipalLlmUsage {
+    /// Principal subject (null for unauthenticated requests)
+    pub principal: Option<String>,
+    #[serde(flatten)]
+    pub usage: LlmUsageTotals,
+}
+
+/// What happens to natural language queries of a domain over its token budget
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmBudgetAction {
+    /// Refuse them with `QUOTA_EXCEEDED`
+    Reject,
+    /// Send them with a smaller prompt: fewer tables, no examples, no repairs
+    Degrade,
+}
+
+impl LlmBudgetAction {
+    /// Parse the `llm.budget_exceeded_action` setting (anything but "degrade" rejects)
+    pub fn parse(value: &str) -> Self {
+        if value.trim().eq_ignore_ascii_case("degrade") {
+            Self::Degrade
+        } else {
+            Self::Reject
+        }
+    }
+}
+
+/// A domain's monthly LLM token budget and how much of it is spent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct LlmBudgetStatus {
+    /// Tokens allowed per month (null is unlimited)
+    pub monthly_token_budget: Option<u64>,
+    pub used_tokens: u64,
+    /// Tokens left this month (null is unlimited)
+    pub remaining_tokens: Option<u64>,
+    pub exceeded: bool,
+    pub exceeded_action: LlmBudgetAction,
+}
+
+impl LlmBudgetStatus {
+    /// Status of `budget` tokens (0 is unlimited) with `used_tokens` spent
+    pub fn new(budget: u64, used_tokens: u64, exceeded_action: LlmBudgetAction) -> Self {
+        let monthly_token_budget = (budget > 0).then_some(budget);
+        Self {
+            monthly_token_budget,
+            used_tokens,
+            remaining_tokens: monthly_token_budget.map(|budget| budget.saturating_sub(used_tokens)),
+            exceeded: monthly_token_budget.is_some_and(|budget| used_tokens >= budget),
+            exceeded_action,
+        }
+    }
+}
+
+/// Monthly LLM usage of a domain (`GET /api/domains/{id}/llm-usage`)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LlmUsageReport {
+    pub domain_id: String,
+    /// Reported month (`YYYY-MM`)
+    pub month: String,
+    /// Days with any usage (oldest first)
+    pub days: Vec<DailyLlmUsage>,
+    /// Usage per principal (most tokens first)
+    pub principals: Vec<PrincipalLlmUsage>,
+    pub totals: LlmUsageTotals,
+    /// Budget of the current month, whatever month is reported
+    pub budget: LlmBudgetStatus,
+}
+
+impl LlmUsageReport {
+    pub fn from_records(domain_id: String, month: NaiveDate, records: &[LlmUsageRecord], budget: LlmBudgetStatus) -> Self {
+        let mut days: BTreeMap<NaiveDate, LlmUsageTotals> = BTreeMap::new();
+        let mut principals: HashMap<Option<String>, LlmUsageTotals> = HashMap::new();
+        let mut totals = LlmUsageTotals::default();
+        for record in records {
+            days.entry(record.date).or_default().add(record);
+            principals.entry(record.principal.clone()).or_default().add(record);
+            totals.add(record);
+        }
+
+        let mut principals: Vec<PrincipalLlmUsage> = principals
+            .into_iter()
+            .map(|(principal, usage)| PrincipalLlmUsage { principal, usage })
+            .collect();
+        principals.sort_by(|a, b| {
+            b.usage
+                .total_tokens
+                .cmp(&a.usage.total_tokens)
+                .then_with(|| a.principal.cmp(&b.principal))
+        });
+
+        Self {
+            domain_id,
+            month: month.format("%Y-%m").to_string(),
+            days: days.into_iter().map(|(date, usage)| DailyLlmUsage { date, usage }).collect(),
+            principals,
+            totals,
+            budget,
+        }
+    }
+}
+
+/// Query counts and costs of a set of history entries
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct QueryTotals {
+    pub queries: u64,
+    pub failed: u64,
+    /// Share of failed queries (0.0 to 1.0)
+    pub failure_rate: f64,
+    pub avg_execution_time_ms: f64,
+    /// Rows the queries returned, or affected for write statements
+    pub rows_scanned: u64,
+}
+
+impl QueryTotals {
+    pub fn new(queries: u64, failed: u64, avg_execution_time_ms: f64, rows_scanned: u64) -> Self {
+        let failure_rate = if queries == 0 { 0.0 } else { failed as f64 / queries as f64 };
+        Self {
+            queries,
+            failed,
+            failure_rate,
+            avg_execution_time_ms,
+            rows_scanned,
+        }
+    }
+}
+
+/// Queries of a domain that used one connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ConnectionQueryStats {
+    pub connection_id: String,
+    /// Connection name (null once the connection is deleted)
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub totals: QueryTotals,
+}
+
+/// Queries of a domain one user or API key executed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct UserQueryStats {
+    /// User id or `api_key:<id>`
+    pub executed_by: String,
+    #[serde(flatten)]
+    pub totals: QueryTotals,
+}
+
+/// Runs of one query shape in a domain: the SQL with its literals replaced by `?`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct QueryShapeStats {
+    pub fingerprint: String,
+    #[serde(flatten)]
+    pub totals: QueryTotals,
+}
+
+/// Resource usage of a domain over a time window (`GET /api/domains/{id}/stats`)
+///
+/// Aggregated by SQLite from the query history. A cross-database query
+/// counts once for each connection it used.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainStats {
+    pub domain_id: String,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    #[serde(flatten)]
+    pub totals: QueryTotals,
+    /// Most-used connections (most queries first)
+    pub top_connections: Vec<ConnectionQueryStats>,
+    /// Users with the most queries (history without a user is left out)
+    pub top_users: Vec<UserQueryStats>,
+    /// Most-run query shapes (history grouped by SQL fingerprint)
+    pub top_queries: Vec<QueryShapeStats>,
+}
+
+/// Ranking of the slow query report
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowQuerySort {
+    /// Most time spent in total first
+    #[default]
+    Total,
+    /// Slowest on average first
+    Avg,
+}
+
+impl SlowQuerySort {
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "total" => Ok(SlowQuerySort::Total),
+            "avg" => Ok(SlowQuerySort::Avg),
+            _ => Err(format!("Unknown sort: {}. Expected total or avg", s)),
+        }
+    }
+}
+
+/// Slow runs of one query shape: the SQL with its literals replaced by `?`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SlowQueryGroup {
+    pub fingerprint: String,
+    /// SQL of the latest slow run
+    pub example_query: String,
+    pub executions: u64,
+    pub total_execution_time_ms: u64,
+    pub avg_execution_time_ms: f64,
+    pub max_execution_time_ms: u64,
+    pub last_executed_at: DateTime<Utc>,
+}
+
+/// Queries of a domain flagged as slow over a time window
+/// (`GET /api/domains/{id}/slow-queries`)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SlowQueryReport {
+    pub domain_id: String,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Current slow query threshold of the domain (0: flagging disabled)
+    pub threshold_ms: u64,
+    pub sort: SlowQuerySort,
+    pub queries: Vec<SlowQueryGroup>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_query_totals_failure_rate() {
+        let totals = QueryTotals::new(4, 1, 12.5, 100);
+        assert_eq!(totals.failure_rate, 0.25);
+        assert_eq!(QueryTotals::new(0, 0, 0.0, 0).failure_rate, 0.0);
+
+        let json = serde_json::to_value(ConnectionQueryStats {
+            connection_id: "c1".to_string(),
+            name: None,
+            totals,
+        })
+        .unwrap();
+        assert_eq!(json["queries"], 4);
+        assert_eq!(json["rows_scanned"], 100);
+    }
+
+    fn bucket(connection_id: &str, start: DateTime<Utc>, total: u64, failed: u64, llm: u64) -> UsageBucket {
+        UsageBucket {
+            domain_id: "domain-1".to_string(),
+            connection_id: connection_id.to_string(),
+            bucket_start: start,
+            total_count: total,
+            success_count: total - failed,
+            failed_count: failed,
+            llm_count: llm,
+            total_execution_time_ms: total * 10,
+        }
+    }
+
+    #[test]
+    fn test_report_from_buckets() {
+        // 2024-01-01 is a Monday
+        let monday_9 = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        let tuesday_14 = Utc.with_ymd_and_hms(2024, 1, 2, 14, 0, 0).unwrap();
+        let buckets = vec![
+            bucket("conn-a", monday_9, 4, 1, 2),
+            bucket("conn-b", monday_9, 1, 0, 0),
+            bucket("conn-a", tuesday_14, 3, 0, 1),
+        ];
+
+        let report = DomainUsageReport::from_buckets("domain-1".to_string(), monday_9, &buckets);
+
+        assert_eq!(report.heatmap[0][9], 5);
+        assert_eq!(report.heatmap[1][14], 3);
+        assert_eq!(report.daily.len(), 2);
+        assert_eq!(report.daily[0].failed, 1);
+        assert_eq!(report.connections[0].connection_id, "conn-a");
+        assert_eq!(report.connections[0].total, 7);
+        assert_eq!(report.connections[0].avg_execution_time_ms, 10.0);
+        assert_eq!(report.total_queries, 8);
+        assert_eq!(report.llm_queries, 3);
+        assert_eq!(report.manual_queries, 5);
+        assert!((report.llm_ratio - 0.375).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_report_empty() {
+        let report = DomainUsageReport::from_buckets("domain-1".to_string(), Utc::now(), &[]);
+        assert_eq!(report.total_queries, 0);
+        assert_eq!(report.llm_ratio, 0.0);
+        assert!(report.daily.is_empty());
+    }
+
+    #[test]
+    fn test_api_usage_report_totals_and_csv() {
+        let month = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let days = vec![
+            DailyApiUsage {
+                date: NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+                api_calls: 8,
+                api_errors: 2,
+                bytes_out: 1000,
+                queries: 5,
+                ..Default::default()
+            },
+            DailyApiUsage {
+                date: NaiveDate::from_ymd_opt(2024, 6, 4).unwrap(),
+                api_calls: 2,
+                llm_requests: 1,
+                ..Default::default()
+            },
+        ];
+
+        let report = ApiUsageReport::from_days("domain-1".to_string(), month, days);
+        assert_eq!(report.month, "2024-06");
+        assert_eq!(report.totals.api_calls, 10);
+        assert_eq!(report.totals.bytes_out, 1000);
+        assert!((report.error_rate - 0.2).abs() < f64::EPSILON);
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "domain-1,2024-06-03,8,2,0.2500,1000,5,0,0,0,0");
+        assert_eq!(lines[3], "domain-1,total,10,2,0.2000,1000,5,0,0,1,0");
+    }
+
+    #[test]
+    fn test_llm_usage_report_and_budget() {
+        let month = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let record = |principal: Option<&str>, day: u32, prompt_tokens: u64, failed_requests: u64| LlmUsageRecord {
+            principal: principal.map(str::to_string),
+            date: NaiveDate::from_ymd_opt(2024, 6, day).unwrap(),
+            requests: 1,
+            failed_requests,
+            tokens: LlmTokenUsage { prompt_tokens, completion_tokens: 10 },
+        };
+        let records = vec![
+            record(Some("alice"), 3, 100, 0),
+            record(None, 3, 20, 1),
+            record(Some("alice"), 4, 50, 0),
+        ];
+
+        let budget = LlmBudgetStatus::new(200, 210, LlmBudgetAction::Degrade);
+        let report = LlmUsageReport::from_records("domain-1".to_string(), month, &records, budget);
+        assert_eq!(report.days.len(), 2);
+        assert_eq!(report.days[0].usage.total_tokens, 140);
+        assert_eq!(report.principals[0].principal.as_deref(), Some("alice"));
+        assert_eq!(report.principals[0].usage.requests, 2);
+        assert_eq!(report.principals[1].usage.failed_requests, 1);
+        assert_eq!(report.totals.total_tokens, 200);
+        assert!(report.budget.exceeded);
+        assert_eq!(report.budget.remaining_tokens, Some(0));
+
+        let unlimited = LlmBudgetStatus::new(0, 5000, LlmBudgetAction::parse("reject"));
+        assert!(!unlimited.exceeded);
+        assert_eq!(unlimited.remaining_tokens, None);
+        assert_eq!(LlmBudgetAction::parse(" Degrade "), LlmBudgetAction::Degrade);
+    }
+}
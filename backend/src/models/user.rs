@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::api_key::{ApiKey, ApiKeyScope};
+
+/// User account (password stored as an Argon2 hash)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    /// Global administrators have every role in every domain
+    pub is_admin: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl User {
+    pub fn new(username: String, password_hash: String, is_admin: bool) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            username,
+            password_hash,
+            is_admin,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Validate username: 3-50 characters, alphanumeric with `.`, `-`, `_`, `@`
+    pub fn validate_username(username: &str) -> Result<(), String> {
+        if username.len() < 3 || username.len() > 50 {
+            return Err("Username must be between 3 and 50 characters".to_string());
+        }
+        if !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '@'))
+        {
+            return Err("Username may only contain letters, digits, '.', '-', '_' and '@'".to_string());
+        }
+        Ok(())
+    }
+
+    /// Validate password: at least 8 characters
+    pub fn validate_password(password: &str) -> Result<(), String> {
+        if password.chars().count() < 8 {
+            return Err("Password must be at least 8 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Role of a user within a domain; each role includes the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DomainRole {
+    /// Browse connections, metadata, saved queries and history
+    Viewer,
+    /// Viewer plus query execution and saved query changes
+    Editor,
+    /// Editor plus connection management, domain settings and role assignments
+    Admin,
+}
+
+impl DomainRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DomainRole::Viewer => "viewer",
+            DomainRole::Editor => "editor",
+            DomainRole::Admin => "admin",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "viewer" => Ok(DomainRole::Viewer),
+            "editor" => Ok(DomainRole::Editor),
+            "admin" => Ok(DomainRole::Admin),
+            _ => Err(format!("Invalid role: {} (expected viewer, editor or admin)", s)),
+        }
+    }
+}
+
+/// A user's role in a domain
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainRoleAssignment {
+    pub user_id: String,
+    pub domain_id: String,
+    pub role: DomainRole,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// Authenticated caller, added to request extensions by the auth middleware
+#[derive(Debug, Clone)]
+pub enum Principal {
+    /// Logged-in user (JWT); domain access is checked against role assignments
+    User { id: String, username: String, is_admin: bool },
+    /// Service credential; access is governed by the key's scope
+    ApiKey { id: String, name: String, scope: ApiKeyScope },
+}
+
+impl Principal {
+    pub fn from_api_key(key: &ApiKey) -> Self {
+        Principal::ApiKey {
+            id: key.id.clone(),
+            name: key.name.clone(),
+            scope: key.scope,
+        }
+    }
+
+    /// Display name (username or API key name)
+    pub fn name(&self) -> &str {
+        match self {
+            Principal::User { username, .. } => username,
+            Principal::ApiKey { name, .. } => name,
+        }
+    }
+
+    /// Identifier recorded in query history (`<user id>` or `api_key:<key id>`)
+    pub fn subject(&self) -> String {
+        match self {
+            Principal::User { id, .. } => id.clone(),
+            Principal::ApiKey { id, .. } => format!("api_key:{}", id),
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        match self {
+            Principal::User { is_admin, .. } => *is_admin,
+            Principal::ApiKey { scope, .. } => *scope == ApiKeyScope::Admin,
+        }
+    }
+}
+
+/// Request payload for creating a user
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub is_admin: bool,
+}
+
+/// Request payload for logging in
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Login response with a signed JWT
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub token: String,
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: User,
+}
+
+/// Request payload for assigning a domain role
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignDomainRoleRequest {
+    pub user_id: String,
+    pub role: DomainRole,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_ordering_and_validation() {
+        assert!(DomainRole::Admin > DomainRole::Editor);
+        assert!(DomainRole::Editor > DomainRole::Viewer);
+        assert_eq!(DomainRole::from_str("Editor").unwrap(), DomainRole::Editor);
+        assert!(DomainRole::from_str("owner").is_err());
+
+        assert!(User::validate_username("alice").is_ok());
+        assert!(User::validate_username("al").is_err());
+        assert!(User::validate_username("alice smith").is_err());
+        assert!(User::validate_password("short").is_err());
+
+        let user = User::new("alice".to_string(), "hash".to_string(), false);
+        let json = serde_json::to_string(&user).unwrap();
+        assert!(!json.contains("hash"));
+    }
+}
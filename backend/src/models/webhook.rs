@@ -0,0 +1,257 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Event a webhook can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum WebhookEvent {
+    /// An asynchronous query job completed
+    #[serde(rename = "query_job.completed")]
+    QueryJobCompleted,
+    /// An asynchronous query job failed
+    #[serde(rename = "query_job.failed")]
+    QueryJobFailed,
+    /// A query or write statement failed (recorded in the query history)
+    #[serde(rename = "query.failed")]
+    QueryFailed,
+    /// A connection became reachable or unreachable (seen on metadata reads)
+    #[serde(rename = "connection.status_changed")]
+    ConnectionStatusChanged,
+    /// A metadata refresh found added, removed or changed tables
+    #[serde(rename = "metadata.changed")]
+    MetadataChanged,
+    /// A metadata refresh found a table or column a saved query uses dropped or renamed
+    #[serde(rename = "saved_query.broken")]
+    SavedQueryBroken,
+    /// A checked result of a saved query drifted from its declared result contract
+    #[serde(rename = "saved_query.schema_drift")]
+    SchemaDrift,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::QueryJobCompleted => "query_job.completed",
+            WebhookEvent::QueryJobFailed => "query_job.failed",
+            WebhookEvent::QueryFailed => "query.failed",
+            WebhookEvent::ConnectionStatusChanged => "connection.status_changed",
+            WebhookEvent::MetadataChanged => "metadata.changed",
+            WebhookEvent::SavedQueryBroken => "saved_query.broken",
+            WebhookEvent::SchemaDrift => "saved_query.schema_drift",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "query_job.completed" => Ok(WebhookEvent::QueryJobCompleted),
+            "query_job.failed" => Ok(WebhookEvent::QueryJobFailed),
+            "query.failed" => Ok(WebhookEvent::QueryFailed),
+            "connection.status_changed" => Ok(WebhookEvent::ConnectionStatusChanged),
+            "metadata.changed" => Ok(WebhookEvent::MetadataChanged),
+            "saved_query.broken" => Ok(WebhookEvent::SavedQueryBroken),
+            "saved_query.schema_drift" => Ok(WebhookEvent::SchemaDrift),
+            _ => Err(format!("Unknown webhook event: {}", s)),
+        }
+    }
+}
+
+/// HTTP callback of a domain
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: String,
+    pub domain_id: String,
+    pub url: String,
+    /// Key of the `X-DbQuery-Signature` HMAC; never returned
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// Events to deliver; empty means all events
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn new(domain_id: String, url: String, secret: String, events: Vec<WebhookEvent>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            domain_id,
+            url,
+            secret,
+            events,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// `POST /api/domains/{id}/webhooks`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// http:// or https:// URL that receives the events as JSON POSTs
+    pub url: String,
+    /// Shared secret for verifying signatures (at least 16 characters)
+    pub secret: String,
+    /// Events to deliver; omit for all events
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+}
+
+impl CreateWebhookRequest {
+    /// Host names are resolved (and checked) when the webhook is created and
+    /// before every delivery; addresses given as the host are checked here
+    pub fn validate(&self) -> Result<(), String> {
+        let url = url::Url::parse(&self.url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err("Webhook URL must use http:// or https://".to_string());
+        }
+        let ip = match url.host() {
+            Some(url::Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(url::Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            Some(url::Host::Domain(_)) => None,
+            None => return Err("Webhook URL must have a host".to_string()),
+        };
+        if let Some(ip) = ip.filter(|ip| !is_deliverable_address(*ip)) {
+            return Err(format!("Webhook URL must not point to the internal address {}", ip));
+        }
+        if self.secret.chars().count() < 16 {
+            return Err("Webhook secret must be at least 16 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Whether webhooks may be delivered to an address
+///
+/// Loopback, private, link-local, unspecified and broadcast addresses are
+/// refused, so a webhook cannot reach services of the server's own network.
+pub fn is_deliverable_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_deliverable_address(IpAddr::V4(ip)),
+            None => {
+                let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+                let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Delivery state of one event to one webhook
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first attempt or a retry
+    Pending,
+    Delivered,
+    /// Gave up after the last attempt
+    Failed,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(WebhookDeliveryStatus::Pending),
+            "delivered" => Ok(WebhookDeliveryStatus::Delivered),
+            "failed" => Ok(WebhookDeliveryStatus::Failed),
+            _ => Err(format!("Unknown webhook delivery status: {}", s)),
+        }
+    }
+}
+
+/// Queued or finished delivery of an event
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    /// JSON body sent to the webhook
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook_id: String, event: WebhookEvent, payload: serde_json::Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            webhook_id,
+            event,
+            payload,
+            status: WebhookDeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_and_request_validation() {
+        let event: WebhookEvent = serde_json::from_str("\"query_job.failed\"").unwrap();
+        assert_eq!(event, WebhookEvent::QueryJobFailed);
+        assert_eq!(WebhookEvent::from_str(event.as_str()).unwrap(), event);
+        let drift: WebhookEvent = serde_json::from_str("\"saved_query.schema_drift\"").unwrap();
+        assert_eq!(WebhookEvent::from_str(drift.as_str()).unwrap(), WebhookEvent::SchemaDrift);
+
+        let webhook = Webhook::new("d".to_string(), "https://hooks.example.com".to_string(), "s".repeat(16), vec![event]);
+        assert!(webhook.subscribes_to(WebhookEvent::QueryJobFailed));
+        assert!(!webhook.subscribes_to(WebhookEvent::QueryFailed));
+        // The secret is never serialized
+        assert!(serde_json::to_value(&webhook).unwrap().get("secret").is_none());
+
+        let request = CreateWebhookRequest {
+            url: "ftp://hooks.example.com".to_string(),
+            secret: "s".repeat(16),
+            events: Vec::new(),
+        };
+        assert!(request.validate().is_err());
+        let request = CreateWebhookRequest { url: "https://hooks.example.com".to_string(), secret: "short".to_string(), ..request };
+        assert!(request.validate().is_err());
+        let request = CreateWebhookRequest { secret: "s".repeat(16), ..request };
+        assert!(request.validate().is_ok());
+        for url in ["http://127.0.0.1:8080/hook", "http://10.0.0.5/hook", "http://169.254.169.254/latest", "http://[::1]/hook"] {
+            let request = CreateWebhookRequest { url: url.to_string(), secret: "s".repeat(16), events: Vec::new() };
+            assert!(request.validate().is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_deliverable_addresses() {
+        for ip in ["203.0.113.7", "2001:db8::1"] {
+            assert!(is_deliverable_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "::1", "::", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(!is_deliverable_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
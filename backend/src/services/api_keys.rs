@@ -0,0 +1,145 @@
+// API Key Service
+//
+// Issues, lists and revokes API keys and authenticates bearer tokens. Only
+// SHA-256 hashes of the keys are stored; the plaintext is returned once on
+// issue. The optional bootstrap key from the configuration authenticates as
+// an admin so the first keys can be issued.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::api::middleware::AppError;
+use crate::config::AuthConfig;
+use crate::models::{ApiKey, ApiKeyScope, CreateApiKeyRequest, CreateApiKeyResponse};
+use crate::storage::SqliteStorage;
+
+/// Id reported for requests authenticated with the bootstrap key
+pub const BOOTSTRAP_KEY_ID: &str = "bootstrap";
+
+pub struct ApiKeyService {
+    storage: Arc<SqliteStorage>,
+    config: AuthConfig,
+}
+
+impl ApiKeyService {
+    pub fn new(storage: Arc<SqliteStorage>, config: AuthConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Issue a new key; the plaintext is only part of this response
+    pub async fn issue(&self, request: CreateApiKeyRequest) -> Result<CreateApiKeyResponse, AppError> {
+        let name = request.name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation("API key name cannot be empty".to_string()));
+        }
+        if name.len() > 100 {
+            return Err(AppError::Validation("API key name cannot exceed 100 characters".to_string()));
+        }
+
+        let (api_key, key) = ApiKey::generate(name, request.scope);
+        self.storage
+            .create_api_key(&api_key)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!(api_key_id = %api_key.id, scope = api_key.scope.as_str(), "Issued API key");
+        Ok(CreateApiKeyResponse { api_key, key })
+    }
+
+    /// List all keys (secrets are never returned)
+    pub async fn list(&self) -> Result<Vec<ApiKey>, AppError> {
+        self.storage
+            .list_api_keys()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Revoke a key; revoked keys are rejected immediately
+    pub async fn revoke(&self, id: &str) -> Result<(), AppError> {
+        let revoked = self
+            .storage
+            .revoke_api_key(id, Utc::now())
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if !revoked {
+            return Err(AppError::NotFound(format!("Active API key {} not found", id)));
+        }
+        tracing::info!(api_key_id = %id, "Revoked API key");
+        Ok(())
+    }
+
+    /// Resolve a bearer token to its key, rejecting unknown and revoked keys
+    pub async fn authenticate(&self, token: &str) -> Result<ApiKey, AppError> {
+        let token_hash = ApiKey::hash(token);
+
+        if let Some(bootstrap) = self.config.bootstrap_key.as_deref().filter(|k| !k.is_empty()) {
+            if ApiKey::hash(bootstrap) == token_hash {
+                return Ok(ApiKey {
+                    id: BOOTSTRAP_KEY_ID.to_string(),
+                    name: "Bootstrap key".to_string(),
+                    key_prefix: String::new(),
+                    key_hash: token_hash,
+                    scope: ApiKeyScope::Admin,
+                    created_at: Utc::now(),
+                    last_used_at: None,
+                    revoked_at: None,
+                });
+            }
+        }
+
+        let key = self
+            .storage
+            .get_api_key_by_hash(&token_hash)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|key| !key.is_revoked())
+            .ok_or_else(|| AppError::Unauthorized("Invalid or revoked API key".to_string()))?;
+
+        if let Err(e) = self.storage.touch_api_key(&key.id, Utc::now()).await {
+            tracing::warn!(api_key_id = %key.id, error = %e, "Failed to record API key usage");
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_issue_authenticate_revoke() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let service = ApiKeyService::new(
+                storage,
+                AuthConfig {
+                    enabled: true,
+                    bootstrap_key: Some("bootstrap-secret".to_string()),
+                    jwt_secret: None,
+                    jwt_ttl_secs: 3600,
+                },
+            );
+
+            let bootstrap = service.authenticate("bootstrap-secret").await.unwrap();
+            assert_eq!(bootstrap.scope, ApiKeyScope::Admin);
+
+            let issued = service
+                .issue(CreateApiKeyRequest { name: "ci".to_string(), scope: ApiKeyScope::Execute })
+                .await
+                .unwrap();
+            let key = service.authenticate(&issued.key).await.unwrap();
+            assert_eq!(key.id, issued.api_key.id);
+            assert_eq!(key.scope, ApiKeyScope::Execute);
+
+            service.revoke(&key.id).await.unwrap();
+            assert!(matches!(service.authenticate(&issued.key).await, Err(AppError::Unauthorized(_))));
+            assert!(service.revoke(&key.id).await.is_err());
+            assert!(service.authenticate("dbq_unknown").await.is_err());
+        });
+    }
+}
@@ -0,0 +1,150 @@
+// API Usage Service
+//
+// Attributes API calls to domains and builds monthly usage reports from the
+// daily rollups (`api_usage_daily`) for chargeback and capacity planning.
+// Calls are attributed by path: domain routes carry the domain id, connection
+// and query job routes are resolved through the stored connection or job.
+
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate, Utc};
+
+use crate::api::middleware::AppError;
+use crate::models::{ApiUsageReport, LlmTokenUsage, Principal};
+use crate::storage::SqliteStorage;
+
+pub struct ApiUsageService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl ApiUsageService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Record a finished API call against the domain its path belongs to
+    ///
+    /// Calls outside any domain (health, auth, admin, cross-database) are not counted.
+    pub async fn record_call(&self, path: &str, failed: bool, bytes_out: u64) {
+        let Some(domain_id) = self.domain_for_path(path).await else {
+            return;
+        };
+        if let Err(e) = self.storage.record_api_call(&domain_id, failed, bytes_out).await {
+            tracing::warn!(domain_id = %domain_id, "Failed to record API usage: {}", e);
+        }
+    }
+
+    /// Record a request sent to the LLM gateway with its tokens, against the
+    /// domain and the principal that made it
+    pub async fn record_llm_request(
+        &self,
+        domain_id: Option<&str>,
+        principal: Option<&Principal>,
+        tokens: LlmTokenUsage,
+        failed: bool,
+    ) {
+        let Some(domain_id) = domain_id else {
+            return;
+        };
+        let principal = principal.map(|p| p.subject());
+        if let Err(e) = self
+            .storage
+            .record_llm_request(domain_id, principal.as_deref(), tokens, failed)
+            .await
+        {
+            tracing::warn!(domain_id = %domain_id, "Failed to record LLM usage: {}", e);
+        }
+    }
+
+    /// Usage report of a domain for a month (`YYYY-MM`, default: current month)
+    pub async fn monthly_report(&self, domain_id: &str, month: Option<&str>) -> Result<ApiUsageReport, AppError> {
+        let (first_day, next_month) = month_range(month)?;
+        let days = self
+            .storage
+            .list_api_usage_days(domain_id, first_day, next_month)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(ApiUsageReport::from_days(domain_id.to_string(), first_day, days))
+    }
+
+    /// Domain an API path belongs to, if any
+    async fn domain_for_path(&self, path: &str) -> Option<String> {
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["api", "domains", id, ..] if *id != "import" => Some(id.to_string()),
+            ["api", "connections", id, ..] => self
+                .storage
+                .get_connection(id)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|connection| connection.domain_id),
+            ["api", "query-jobs", id, ..] => self
+                .storage
+                .get_query_job(id)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|job| job.domain_id),
+            _ => None,
+        }
+    }
+}
+
+/// First day of a month (`YYYY-MM`, default: current month) and of the month after
+pub(crate) fn month_range(month: Option<&str>) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let first_day = match month {
+        Some(month) => parse_month(month)?,
+        None => {
+            let today = Utc::now().date_naive();
+            NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+                .ok_or_else(|| AppError::Internal("Invalid current date".to_string()))?
+        }
+    };
+    let next_month = first_day
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| AppError::Validation(format!("month {} is out of range", first_day.format("%Y-%m"))))?;
+    Ok((first_day, next_month))
+}
+
+/// Parse `YYYY-MM` into the first day of that month
+fn parse_month(month: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("month must be formatted as YYYY-MM, got '{}'", month)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_and_report() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let service = ApiUsageService::new(storage.clone());
+
+            let connection = crate::models::DatabaseConnection::new(
+                None,
+                "postgresql://localhost/db".to_string(),
+                "postgresql".to_string(),
+                Some("default-domain-id".to_string()),
+            );
+            storage.save_connection(&connection).await.unwrap();
+
+            service.record_call("/api/domains/default-domain-id/usage", false, 100).await;
+            service.record_call(&format!("/api/connections/{}/query", connection.id), true, 50).await;
+            service.record_call("/api/admin/users", false, 10).await;
+
+            let report = service.monthly_report("default-domain-id", None).await.unwrap();
+            assert_eq!(report.totals.api_calls, 2);
+            assert_eq!(report.totals.api_errors, 1);
+            assert_eq!(report.totals.bytes_out, 150);
+
+            assert!(parse_month("2024-13").is_err());
+            assert_eq!(parse_month("2024-06").unwrap(), NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+        });
+    }
+}
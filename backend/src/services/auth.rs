@@ -0,0 +1,342 @@
+// Auth Service
+//
+// User accounts, password login with signed JWTs and per-domain role checks
+// (viewer < editor < admin). Global admins and admin-scoped API keys pass
+// every domain check; other API keys are governed by their scope alone.
+
+use std::sync::Arc;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::api::middleware::AppError;
+use crate::config::AuthConfig;
+use crate::models::{
+    AssignDomainRoleRequest, CreateUserRequest, DomainRole, DomainRoleAssignment, LoginRequest,
+    LoginResponse, Principal, User,
+};
+use crate::storage::SqliteStorage;
+
+/// JWT claims issued on login
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// User id
+    sub: String,
+    name: String,
+    admin: bool,
+    iat: i64,
+    exp: i64,
+}
+
+pub struct AuthService {
+    storage: Arc<SqliteStorage>,
+    config: AuthConfig,
+}
+
+impl AuthService {
+    pub fn new(storage: Arc<SqliteStorage>, config: AuthConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Create a user account
+    pub async fn create_user(&self, request: CreateUserRequest) -> Result<User, AppError> {
+        let username = request.username.trim().to_string();
+        User::validate_username(&username).map_err(AppError::Validation)?;
+        User::validate_password(&request.password).map_err(AppError::Validation)?;
+
+        let existing = self
+            .storage
+            .get_user_by_username(&username)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if existing.is_some() {
+            return Err(AppError::Conflict(format!("User {} already exists", username)));
+        }
+
+        let user = User::new(username, Self::hash_password(&request.password)?, request.is_admin);
+        self.storage
+            .create_user(&user)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!(user_id = %user.id, is_admin = user.is_admin, "Created user");
+        Ok(user)
+    }
+
+    /// Check credentials and issue a JWT
+    pub async fn login(&self, request: LoginRequest) -> Result<LoginResponse, AppError> {
+        let secret = self.jwt_secret()?;
+
+        let user = self
+            .storage
+            .get_user_by_username(request.username.trim())
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|user| Self::verify_password(&request.password, &user.password_hash))
+            .ok_or_else(|| AppError::Unauthorized("Invalid username or password".to_string()))?;
+
+        let issued_at = Utc::now();
+        let expires_at = issued_at + Duration::seconds(self.config.jwt_ttl_secs as i64);
+        let claims = Claims {
+            sub: user.id.clone(),
+            name: user.username.clone(),
+            admin: user.is_admin,
+            iat: issued_at.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
+            .map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
+
+        tracing::info!(user_id = %user.id, "User logged in");
+        Ok(LoginResponse {
+            token,
+            token_type: "Bearer".to_string(),
+            expires_at,
+            user,
+        })
+    }
+
+    /// Resolve a login JWT to its user (the account must still exist)
+    pub async fn verify_token(&self, token: &str) -> Result<Principal, AppError> {
+        let secret = self.jwt_secret()?;
+        let claims = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &Validation::default())
+            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?
+            .claims;
+
+        let user = self
+            .storage
+            .get_user(&claims.sub)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::Unauthorized("User no longer exists".to_string()))?;
+
+        // Admin status is read from storage so demotions apply to existing tokens
+        Ok(Principal::User {
+            id: user.id,
+            username: user.username,
+            is_admin: user.is_admin,
+        })
+    }
+
+    /// Require at least `required` in the domain owning a resource
+    ///
+    /// Passes when auth is disabled (no principal), for global admins and for
+    /// API keys (their scope was checked by the middleware). Resources outside
+    /// any domain are only accessible to admins.
+    pub async fn require_domain_role(
+        &self,
+        principal: Option<&Principal>,
+        domain_id: Option<&str>,
+        required: DomainRole,
+    ) -> Result<(), AppError> {
+        let Some(principal) = principal else {
+            return Ok(());
+        };
+
+        let user_id = match principal {
+            Principal::ApiKey { .. } => return Ok(()),
+            Principal::User { is_admin: true, .. } => return Ok(()),
+            Principal::User { id, .. } => id,
+        };
+
+        let Some(domain_id) = domain_id else {
+            return Err(AppError::Forbidden(
+                "Only administrators can access resources outside a domain".to_string(),
+            ));
+        };
+
+        let role = self
+            .storage
+            .get_domain_role(user_id, domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        match role {
+            Some(role) if role >= required => Ok(()),
+            Some(role) => Err(AppError::Forbidden(format!(
+                "Role '{}' in domain {} is not sufficient; '{}' is required",
+                role.as_str(),
+                domain_id,
+                required.as_str()
+            ))),
+            None => Err(AppError::Forbidden(format!("No access to domain {}", domain_id))),
+        }
+    }
+
+    /// Domains a principal may see; `None` means all of them
+    pub async fn visible_domain_ids(&self, principal: Option<&Principal>) -> Result<Option<Vec<String>>, AppError> {
+        match principal {
+            Some(Principal::User { id, is_admin: false, .. }) => self
+                .storage
+                .list_user_domain_ids(id)
+                .await
+                .map(Some)
+                .map_err(|e| AppError::Database(e.to_string())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Assign a user's role in a domain
+    pub async fn assign_role(
+        &self,
+        domain_id: &str,
+        request: AssignDomainRoleRequest,
+    ) -> Result<DomainRoleAssignment, AppError> {
+        self.storage
+            .get_domain(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+        self.storage
+            .get_user(&request.user_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("User {} not found", request.user_id)))?;
+
+        let assignment = DomainRoleAssignment {
+            user_id: request.user_id,
+            domain_id: domain_id.to_string(),
+            role: request.role,
+            assigned_at: Utc::now(),
+        };
+        self.storage
+            .set_domain_role(&assignment)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(assignment)
+    }
+
+    /// List role assignments of a domain
+    pub async fn list_roles(&self, domain_id: &str) -> Result<Vec<DomainRoleAssignment>, AppError> {
+        self.storage
+            .list_domain_roles(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Remove a user's role in a domain
+    pub async fn remove_role(&self, domain_id: &str, user_id: &str) -> Result<(), AppError> {
+        let removed = self
+            .storage
+            .remove_domain_role(user_id, domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if !removed {
+            return Err(AppError::NotFound(format!(
+                "User {} has no role in domain {}",
+                user_id, domain_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// List all user accounts
+    pub async fn list_users(&self) -> Result<Vec<User>, AppError> {
+        self.storage
+            .list_users()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    fn jwt_secret(&self) -> Result<&str, AppError> {
+        self.config
+            .jwt_secret
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| AppError::NotImplemented("User login requires AUTH_JWT_SECRET to be configured".to_string()))
+    }
+
+    fn hash_password(password: &str) -> Result<String, AppError> {
+        let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+            .map_err(|e| AppError::Internal(format!("Failed to generate salt: {}", e)))?;
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+    }
+
+    fn verify_password(password: &str, password_hash: &str) -> bool {
+        PasswordHash::new(password_hash)
+            .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Domain;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_login_and_domain_roles() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let service = AuthService::new(
+                storage.clone(),
+                AuthConfig {
+                    enabled: true,
+                    bootstrap_key: None,
+                    jwt_secret: Some("test-secret".to_string()),
+                    jwt_ttl_secs: 3600,
+                },
+            );
+
+            let user = service
+                .create_user(CreateUserRequest {
+                    username: "alice".to_string(),
+                    password: "correct horse".to_string(),
+                    is_admin: false,
+                })
+                .await
+                .unwrap();
+
+            assert!(service
+                .login(LoginRequest { username: "alice".to_string(), password: "wrong pass".to_string() })
+                .await
+                .is_err());
+            let login = service
+                .login(LoginRequest { username: "alice".to_string(), password: "correct horse".to_string() })
+                .await
+                .unwrap();
+            let principal = service.verify_token(&login.token).await.unwrap();
+            assert_eq!(principal.subject(), user.id);
+            assert!(service.verify_token("not.a.token").await.is_err());
+
+            let domain = Domain::new("Analytics".to_string(), None).unwrap();
+            storage.create_domain(&domain).await.unwrap();
+
+            // No role yet
+            assert!(service
+                .require_domain_role(Some(&principal), Some(&domain.id), DomainRole::Viewer)
+                .await
+                .is_err());
+
+            service
+                .assign_role(&domain.id, AssignDomainRoleRequest { user_id: user.id.clone(), role: DomainRole::Viewer })
+                .await
+                .unwrap();
+            assert!(service
+                .require_domain_role(Some(&principal), Some(&domain.id), DomainRole::Viewer)
+                .await
+                .is_ok());
+            assert!(matches!(
+                service.require_domain_role(Some(&principal), Some(&domain.id), DomainRole::Editor).await,
+                Err(AppError::Forbidden(_))
+            ));
+
+            assert_eq!(
+                service.visible_domain_ids(Some(&principal)).await.unwrap(),
+                Some(vec![domain.id.clone()])
+            );
+
+            // Auth disabled: no principal, no checks
+            assert!(service.require_domain_role(None, None, DomainRole::Admin).await.is_ok());
+        });
+    }
+}
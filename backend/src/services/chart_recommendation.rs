@@ -0,0 +1,262 @@
+// Chart Recommendation
+//
+// Picks a chart for a query result from the shape of its columns, so the
+// frontend can render one without asking. Columns are read as dates, numbers
+// or categories over the first rows; dates with numbers make a line chart,
+// categories with numbers a bar (or pie) chart and two numbers a scatter plot.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::Value;
+
+use crate::models::{ChartAggregation, ChartEncoding, ChartSpec, ChartType, FieldKind};
+use crate::services::{profile_columns, ColumnProfile};
+
+/// Rows read to classify columns
+const CHART_SAMPLE_ROWS: usize = 1000;
+
+/// Series of one chart
+const MAX_SERIES: usize = 5;
+
+/// Categories a bar chart stays readable with
+const MAX_BAR_CATEGORIES: usize = 50;
+
+/// Slices of a pie chart
+const MAX_PIE_SLICES: usize = 6;
+
+/// Column names of measures averaged rather than summed
+const AVERAGED_MEASURES: &[&str] = &["avg", "average", "mean", "rate", "ratio", "pct", "percent", "median"];
+
+/// A result column as a chart sees it
+struct ChartColumn {
+    profile: ColumnProfile,
+    kind: FieldKind,
+}
+
+impl ChartColumn {
+    /// Whether rows share values of this column
+    fn repeats(&self) -> bool {
+        self.profile.distinct < self.profile.non_null
+    }
+
+    fn encoding(&self) -> ChartEncoding {
+        ChartEncoding {
+            field: self.profile.name.clone(),
+            kind: self.kind,
+        }
+    }
+}
+
+pub struct ChartRecommendationService;
+
+impl ChartRecommendationService {
+    /// Chart for result rows, None when no chart fits (fewer than two rows,
+    /// only unique text, too many categories)
+    pub fn recommend(rows: &[Value]) -> Option<ChartSpec> {
+        let rows = &rows[..rows.len().min(CHART_SAMPLE_ROWS)];
+        if rows.len() < 2 {
+            return None;
+        }
+        let columns = classify_columns(rows);
+
+        let temporal = columns.iter().find(|c| c.kind == FieldKind::Temporal);
+        let measures: Vec<&ChartColumn> = columns
+            .iter()
+            .filter(|c| c.kind == FieldKind::Quantitative)
+            .take(MAX_SERIES)
+            .collect();
+        // Categories shared by rows first, before unique labels such as ids
+        let category = columns
+            .iter()
+            .filter(|c| c.kind == FieldKind::Nominal && c.profile.distinct > 1)
+            .min_by_key(|c| !c.repeats());
+
+        if let Some(time) = temporal {
+            if !measures.is_empty() {
+                return Some(chart(
+                    ChartType::Line,
+                    time,
+                    &measures,
+                    time.repeats().then(|| aggregation(&measures)),
+                    format!("{} over {}", names(&measures), time.profile.name),
+                ));
+            }
+            if time.repeats() {
+                return Some(chart(
+                    ChartType::Line,
+                    time,
+                    &[],
+                    Some(ChartAggregation::Count),
+                    format!("rows over {}", time.profile.name),
+                ));
+            }
+        }
+
+        if let Some(category) = category {
+            if category.profile.distinct > MAX_BAR_CATEGORIES {
+                return None;
+            }
+            if measures.is_empty() {
+                if !category.repeats() {
+                    return None;
+                }
+                return Some(chart(
+                    ChartType::Bar,
+                    category,
+                    &[],
+                    Some(ChartAggregation::Count),
+                    format!("rows per {}", category.profile.name),
+                ));
+            }
+            let aggregated = category.repeats().then(|| aggregation(&measures));
+            let share = measures.len() == 1
+                && category.profile.distinct <= MAX_PIE_SLICES
+                && aggregated != Some(ChartAggregation::Avg)
+                && measures[0].profile.numeric.is_some_and(|(min, _, _)| min >= 0.0);
+            let chart_type = if share { ChartType::Pie } else { ChartType::Bar };
+            let reason = if share {
+                format!("share of {} by {}", names(&measures), category.profile.name)
+            } else {
+                format!("{} per {}", names(&measures), category.profile.name)
+            };
+            return Some(chart(chart_type, category, &measures, aggregated, reason));
+        }
+
+        if measures.len() >= 2 {
+            return Some(chart(
+                ChartType::Scatter,
+                measures[0],
+                &measures[1..2],
+                None,
+                format!("{} against {}", measures[1].profile.name, measures[0].profile.name),
+            ));
+        }
+        None
+    }
+}
+
+fn chart(
+    chart_type: ChartType,
+    x: &ChartColumn,
+    y: &[&ChartColumn],
+    aggregation: Option<ChartAggregation>,
+    reason: String,
+) -> ChartSpec {
+    ChartSpec {
+        chart_type,
+        x: x.encoding(),
+        y: y.iter().map(|c| c.encoding()).collect(),
+        aggregation,
+        reason,
+    }
+}
+
+fn names(columns: &[&ChartColumn]) -> String {
+    columns.iter().map(|c| c.profile.name.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// Avg when every measure is a rate or an average already, otherwise Sum
+fn aggregation(measures: &[&ChartColumn]) -> ChartAggregation {
+    let averaged = measures.iter().all(|c| {
+        let name = c.profile.name.to_lowercase();
+        name.split(|ch: char| !ch.is_ascii_alphanumeric())
+            .any(|word| AVERAGED_MEASURES.contains(&word))
+    });
+    if averaged {
+        ChartAggregation::Avg
+    } else {
+        ChartAggregation::Sum
+    }
+}
+
+/// Columns with at least one value, read as dates, numbers or categories.
+/// Numeric identifiers (`id`, `*_id`) are categories, not measures.
+fn classify_columns(rows: &[Value]) -> Vec<ChartColumn> {
+    profile_columns(rows)
+        .into_iter()
+        .filter(|profile| profile.non_null > 0)
+        .map(|profile| {
+            let name = profile.name.to_lowercase();
+            let kind = if profile.numeric.is_some() && name != "id" && !name.ends_with("_id") {
+                FieldKind::Quantitative
+            } else if is_temporal(rows, &profile.name) {
+                FieldKind::Temporal
+            } else {
+                FieldKind::Nominal
+            };
+            ChartColumn { profile, kind }
+        })
+        .collect()
+}
+
+/// Whether every value of `column` is a date or timestamp string
+fn is_temporal(rows: &[Value], column: &str) -> bool {
+    rows.iter()
+        .filter_map(|row| row.get(column))
+        .filter(|value| !value.is_null())
+        .all(|value| value.as_str().is_some_and(is_date))
+}
+
+fn is_date(text: &str) -> bool {
+    DateTime::parse_from_rfc3339(text).is_ok()
+        || NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_recommend_line_and_bar() {
+        let daily = vec![
+            json!({"day": "2024-01-01", "orders": 10, "revenue": 120.5}),
+            json!({"day": "2024-01-02", "orders": 12, "revenue": 99.0}),
+        ];
+        let chart = ChartRecommendationService::recommend(&daily).unwrap();
+        assert_eq!(chart.chart_type, ChartType::Line);
+        assert_eq!(chart.x, ChartEncoding { field: "day".to_string(), kind: FieldKind::Temporal });
+        assert_eq!(chart.y.len(), 2);
+        assert_eq!(chart.aggregation, None);
+
+        let per_region: Vec<Value> = (0..10)
+            .map(|i| json!({"region": format!("r{}", i % 8), "avg_price": i}))
+            .collect();
+        let chart = ChartRecommendationService::recommend(&per_region).unwrap();
+        assert_eq!(chart.chart_type, ChartType::Bar);
+        assert_eq!(chart.aggregation, Some(ChartAggregation::Avg));
+    }
+
+    #[test]
+    fn test_recommend_pie_count_and_scatter() {
+        let shares = vec![
+            json!({"status": "open", "tickets": 4}),
+            json!({"status": "closed", "tickets": 9}),
+        ];
+        assert_eq!(ChartRecommendationService::recommend(&shares).unwrap().chart_type, ChartType::Pie);
+
+        let users = vec![
+            json!({"id": 1, "country": "DE"}),
+            json!({"id": 2, "country": "DE"}),
+            json!({"id": 3, "country": "FR"}),
+        ];
+        let chart = ChartRecommendationService::recommend(&users).unwrap();
+        assert_eq!(chart.chart_type, ChartType::Bar);
+        assert_eq!(chart.x.field, "country");
+        assert!(chart.y.is_empty());
+        assert_eq!(chart.aggregation, Some(ChartAggregation::Count));
+
+        let points = vec![json!({"height": 1.7, "weight": 60}), json!({"height": 1.8, "weight": 80})];
+        assert_eq!(ChartRecommendationService::recommend(&points).unwrap().chart_type, ChartType::Scatter);
+    }
+
+    #[test]
+    fn test_recommend_none() {
+        assert!(ChartRecommendationService::recommend(&[json!({"total": 3})]).is_none());
+        let names = vec![json!({"name": "a"}), json!({"name": "b"})];
+        assert!(ChartRecommendationService::recommend(&names).is_none());
+        let notes = vec![json!({"note": "x"}), json!({"note": "x"})];
+        assert!(ChartRecommendationService::recommend(&notes).is_none());
+    }
+}
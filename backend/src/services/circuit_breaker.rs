@@ -0,0 +1,287 @@
+// Circuit Breaker
+//
+// When a target database goes down, every request would otherwise wait the
+// full acquire timeout before failing. Each pool's breaker counts consecutive
+// checkout failures; past the threshold it opens and checkouts fail at once.
+// Once the open period has passed, the next checkout is let through as a
+// probe: success closes the circuit, failure opens it for another period.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::api::middleware::AppError;
+
+/// When a circuit opens and how long it stays open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitSettings {
+    /// Consecutive failures that open the circuit (0 disables the breaker)
+    pub failure_threshold: u32,
+    pub open_for: Duration,
+}
+
+impl CircuitSettings {
+    pub fn from_config(config: &crate::config::PoolConfig) -> Self {
+        Self {
+            failure_threshold: config.circuit_failure_threshold,
+            open_for: Duration::from_secs(config.circuit_open_secs.max(1)),
+        }
+    }
+}
+
+impl Default for CircuitSettings {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Checkouts go through
+    Closed,
+    /// Checkouts fail at once
+    Open,
+    /// The open period has passed; the next checkout probes the database
+    HalfOpen,
+}
+
+/// State of a pool's circuit breaker
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// When the circuit last opened
+    pub opened_at: Option<DateTime<Utc>>,
+    /// When an open circuit lets the next checkout probe the database
+    pub retry_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Times the circuit opened since the pool was created
+    pub trips: u64,
+}
+
+impl Default for CircuitStatus {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            retry_at: None,
+            last_error: None,
+            trips: 0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    /// Set while open: when it opened, on both clocks
+    opened: Option<(Instant, DateTime<Utc>)>,
+    /// Set while a probe checkout is running
+    probe_started: Option<Instant>,
+    last_error: Option<String>,
+    trips: u64,
+}
+
+/// Consecutive-failure circuit breaker of one pool
+///
+/// Each breaker lives in its pool's `PoolMetrics`, so breakers are keyed by
+/// (connection URL, read-only) like the pools themselves: the read-only and
+/// read-write pools of a database trip separately.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    settings: CircuitSettings,
+    /// Longest a checkout may take; a probe running longer was abandoned
+    probe_timeout: Duration,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: CircuitSettings, probe_timeout: Duration) -> Self {
+        Self {
+            settings,
+            probe_timeout,
+            circuit: Mutex::new(Circuit::default()),
+        }
+    }
+
+    /// Admit a checkout, returning whether it is the probe of an open circuit
+    ///
+    /// Fails fast while the circuit is open or another probe is running.
+    pub fn admit(&self) -> Result<bool, AppError> {
+        let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((opened, _)) = circuit.opened else {
+            return Ok(false);
+        };
+        let probe_running = circuit
+            .probe_started
+            .is_some_and(|started| started.elapsed() < self.probe_timeout);
+        let remaining = self.settings.open_for.saturating_sub(opened.elapsed());
+        if remaining.is_zero() && !probe_running {
+            circuit.probe_started = Some(Instant::now());
+            return Ok(true);
+        }
+
+        let retry_after_secs = remaining.as_secs_f64().ceil().max(1.0) as u64;
+        Err(AppError::CircuitOpen(
+            format!(
+                "The database is unavailable after {} consecutive connection failures, so requests fail fast until it \
+                 recovers (next attempt in {}s). Last error: {}",
+                circuit.consecutive_failures,
+                retry_after_secs,
+                circuit.last_error.as_deref().unwrap_or("unknown")
+            ),
+            retry_after_secs,
+        ))
+    }
+
+    /// Close the circuit after a successful checkout
+    pub fn record_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        if circuit.opened.is_some() {
+            tracing::info!("Connection circuit closed: the database is reachable again");
+        }
+        circuit.consecutive_failures = 0;
+        circuit.opened = None;
+        circuit.probe_started = None;
+    }
+
+    /// Count a failed checkout, opening the circuit past the threshold or
+    /// when a probe failed
+    pub fn record_failure(&self, error: &AppError, probe: bool) {
+        let mut circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+        circuit.last_error = Some(error.to_string());
+        if probe {
+            circuit.probe_started = None;
+        }
+        let threshold = self.settings.failure_threshold;
+        if threshold == 0 || circuit.consecutive_failures < threshold {
+            return;
+        }
+        if probe || circuit.opened.is_none() {
+            if circuit.opened.is_none() {
+                circuit.trips += 1;
+                tracing::warn!(
+                    "Connection circuit opened after {} consecutive failures: {}",
+                    circuit.consecutive_failures,
+                    error
+                );
+            }
+            circuit.opened = Some((Instant::now(), Utc::now()));
+        }
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        let circuit = self.circuit.lock().unwrap_or_else(|e| e.into_inner());
+        let (state, opened_at, retry_at) = match circuit.opened {
+            None => (CircuitState::Closed, None, None),
+            Some((opened, opened_at)) => {
+                let state = if opened.elapsed() >= self.settings.open_for {
+                    CircuitState::HalfOpen
+                } else {
+                    CircuitState::Open
+                };
+                let open_for = chrono::Duration::from_std(self.settings.open_for)
+                    .unwrap_or_else(|_| chrono::Duration::zero());
+                (state, Some(opened_at), Some(opened_at + open_for))
+            }
+        };
+        CircuitStatus {
+            state,
+            consecutive_failures: circuit.consecutive_failures,
+            opened_at,
+            retry_at,
+            last_error: circuit.last_error.clone(),
+            trips: circuit.trips,
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitSettings::default(), Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure() -> AppError {
+        AppError::Connection("connection refused".to_string())
+    }
+
+    #[test]
+    fn test_circuit_opens_and_recovers() {
+        let settings = CircuitSettings {
+            failure_threshold: 3,
+            open_for: Duration::from_millis(20),
+        };
+        let breaker = CircuitBreaker::new(settings, Duration::from_secs(5));
+
+        for _ in 0..2 {
+            assert!(!breaker.admit().unwrap());
+            breaker.record_failure(&failure(), false);
+        }
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+        breaker.record_failure(&failure(), false);
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.trips, 1);
+        assert!(status.last_error.unwrap().contains("refused"));
+        assert!(matches!(breaker.admit(), Err(AppError::CircuitOpen(_, _))));
+
+        // After the open period one checkout probes; others still fail fast
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(breaker.status().state, CircuitState::HalfOpen);
+        assert!(breaker.admit().unwrap());
+        assert!(breaker.admit().is_err());
+
+        // A failed probe opens the circuit again
+        breaker.record_failure(&failure(), true);
+        assert_eq!(breaker.status().state, CircuitState::Open);
+        assert_eq!(breaker.status().trips, 1);
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.admit().unwrap());
+        breaker.record_success();
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(!breaker.admit().unwrap());
+    }
+
+    #[test]
+    fn test_abandoned_probe_and_disabled_breaker() {
+        let settings = CircuitSettings {
+            failure_threshold: 1,
+            open_for: Duration::ZERO,
+        };
+        let breaker = CircuitBreaker::new(settings, Duration::ZERO);
+        breaker.record_failure(&failure(), false);
+        assert!(breaker.admit().unwrap());
+        // The probe's checkout was dropped without a result
+        assert!(breaker.admit().unwrap());
+
+        let disabled = CircuitBreaker::new(
+            CircuitSettings {
+                failure_threshold: 0,
+                ..settings
+            },
+            Duration::ZERO,
+        );
+        for _ in 0..10 {
+            disabled.record_failure(&failure(), false);
+        }
+        assert!(!disabled.admit().unwrap());
+        assert_eq!(disabled.status().consecutive_failures, 10);
+    }
+}
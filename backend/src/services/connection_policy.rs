@@ -0,0 +1,144 @@
+// Connection Policy Service
+//
+// Manages query policies bound to connection label selectors (e.g.
+// `env=prod,pii=true`) and resolves the policies in effect for a connection:
+// result column masking and hourly query quotas. Policies apply to every
+// matching connection, including ones created after the policy.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::api::middleware::AppError;
+use crate::models::{ConnectionPolicy, CreateConnectionPolicyRequest, DatabaseConnection, EffectivePolicy};
+use crate::storage::SqliteStorage;
+
+pub struct ConnectionPolicyService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl ConnectionPolicyService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Validate and store a new policy
+    pub async fn create(&self, request: CreateConnectionPolicyRequest) -> Result<ConnectionPolicy, AppError> {
+        let policy = ConnectionPolicy::from_request(request).map_err(AppError::Validation)?;
+        self.storage
+            .create_connection_policy(&policy)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!(policy_id = %policy.id, name = %policy.name, "Created connection policy");
+        Ok(policy)
+    }
+
+    /// List all policies
+    pub async fn list(&self) -> Result<Vec<ConnectionPolicy>, AppError> {
+        self.storage
+            .list_connection_policies()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Delete a policy
+    pub async fn delete(&self, id: &str) -> Result<(), AppError> {
+        let deleted = self
+            .storage
+            .delete_connection_policy(id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if !deleted {
+            return Err(AppError::NotFound(format!("Connection policy {} not found", id)));
+        }
+        tracing::info!(policy_id = %id, "Deleted connection policy");
+        Ok(())
+    }
+
+    /// Policies in effect for a connection, merged
+    pub async fn for_connection(&self, connection: &DatabaseConnection) -> Result<EffectivePolicy, AppError> {
+        let policies = self.list().await?;
+        Ok(EffectivePolicy::resolve(&policies, &connection.labels))
+    }
+
+    /// Reject the query if the connection has used up its hourly quota
+    pub async fn ensure_within_quota(
+        &self,
+        connection: &DatabaseConnection,
+        policy: &EffectivePolicy,
+    ) -> Result<(), AppError> {
+        let Some(limit) = policy.max_queries_per_hour else {
+            return Ok(());
+        };
+
+        let used = self
+            .storage
+            .count_connection_queries_since(&connection.id, Utc::now() - Duration::hours(1))
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if used >= limit as u64 {
+            return Err(AppError::QuotaExceeded(format!(
+                "Connection {} has reached its policy quota of {} queries per hour",
+                connection.id, limit
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{LabelSelector, QueryHistory};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_policy_quota_follows_labels() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let service = ConnectionPolicyService::new(storage.clone());
+
+            service
+                .create(CreateConnectionPolicyRequest {
+                    name: "prod quota".to_string(),
+                    selector: LabelSelector::parse("env=prod").unwrap(),
+                    masked_columns: vec!["email".to_string()],
+                    max_queries_per_hour: Some(1),
+                })
+                .await
+                .unwrap();
+
+            let mut prod = DatabaseConnection::new(None, "postgresql://localhost/db".to_string(), "postgresql".to_string(), None);
+            prod.labels.insert("env".to_string(), "prod".to_string());
+            let dev = DatabaseConnection::new(None, "postgresql://localhost/db".to_string(), "postgresql".to_string(), None);
+
+            let policy = service.for_connection(&prod).await.unwrap();
+            assert!(policy.masked_columns.contains("email"));
+            assert!(service.ensure_within_quota(&prod, &policy).await.is_ok());
+
+            storage.save_connection(&prod).await.unwrap();
+            storage
+                .add_query_history(&QueryHistory::new(
+                    "default-domain-id".to_string(),
+                    prod.id.clone(),
+                    "SELECT 1".to_string(),
+                    1,
+                    1,
+                    false,
+                ))
+                .await
+                .unwrap();
+            assert!(matches!(
+                service.ensure_within_quota(&prod, &policy).await,
+                Err(AppError::QuotaExceeded(_))
+            ));
+
+            // Unlabelled connections are not affected
+            assert_eq!(service.for_connection(&dev).await.unwrap(), EffectivePolicy::default());
+        });
+    }
+}
@@ -0,0 +1,128 @@
+// Data Transfer Service
+//
+// Cross-database INSERT ... SELECT: the rows of a query streamed from one
+// connection are inserted into a table of another, batch by batch, through the
+// target adapter's bulk load (COPY for PostgreSQL, multi-row INSERTs
+// elsewhere). Batches are committed as they are loaded, so a failed transfer
+// keeps the rows loaded before the failure; the progress callback reports them.
+
+use std::future::Future;
+
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::services::database::{DatabaseAdapter, RowBatchStream};
+
+/// Loads streamed rows into one table of a target connection
+pub struct DataTransferService {
+    target: Box<dyn DatabaseAdapter>,
+    /// Quoted (optionally schema-qualified) target table
+    table: String,
+    /// Result columns to insert; taken from the first row when not given
+    columns: Option<Vec<String>>,
+    timeout_secs: u64,
+}
+
+impl DataTransferService {
+    /// `table_parts` is the target table as `[table]` or `[schema, table]`;
+    /// `timeout_secs` bounds each batch insert
+    pub fn new(
+        target: Box<dyn DatabaseAdapter>,
+        table_parts: &[&str],
+        columns: Option<Vec<String>>,
+        timeout_secs: u64,
+    ) -> Self {
+        let table = table_parts
+            .iter()
+            .map(|part| target.quote_identifier(part))
+            .collect::<Vec<_>>()
+            .join(".");
+        Self { target, table, columns, timeout_secs }
+    }
+
+    /// Insert every batch of `batches` and return the number of rows loaded
+    ///
+    /// `prepare` runs on each batch before it is inserted (e.g. to mask
+    /// columns); `on_progress` receives the running total after each batch.
+    pub async fn load<F, Fut>(
+        &self,
+        mut batches: RowBatchStream,
+        prepare: impl Fn(&mut [Value]),
+        mut on_progress: F,
+    ) -> Result<u64, AppError>
+    where
+        F: FnMut(u64) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut columns = self.columns.clone();
+        let mut loaded = 0;
+        while let Some(batch) = batches.next().await {
+            let mut rows = batch?;
+            if rows.is_empty() {
+                continue;
+            }
+            prepare(rows.as_mut_slice());
+
+            if columns.is_none() {
+                columns = Some(Self::result_columns(&rows[0])?);
+            }
+            let insert_columns = columns.as_deref().unwrap_or_default();
+            Self::ensure_columns_present(insert_columns, &rows[0])?;
+
+            loaded += self
+                .target
+                .bulk_insert(&self.table, insert_columns, &rows, self.timeout_secs)
+                .await?;
+            on_progress(loaded).await;
+        }
+        Ok(loaded)
+    }
+
+    /// Column names of a result row
+    fn result_columns(row: &Value) -> Result<Vec<String>, AppError> {
+        let columns: Vec<String> = row
+            .as_object()
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default();
+        if columns.is_empty() {
+            return Err(AppError::Validation("The source query returned no columns".to_string()));
+        }
+        Ok(columns)
+    }
+
+    /// Reject requested columns the source query does not return
+    fn ensure_columns_present(columns: &[String], row: &Value) -> Result<(), AppError> {
+        let missing: Vec<&str> = columns
+            .iter()
+            .filter(|column| row.get(column.as_str()).is_none())
+            .map(String::as_str)
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(AppError::Validation(format!(
+            "The source query does not return the columns: {}",
+            missing.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_columns_come_from_the_result_row() {
+        let row = json!({"id": 1, "name": "Alice"});
+        assert_eq!(DataTransferService::result_columns(&row).unwrap(), vec!["id", "name"]);
+        assert!(DataTransferService::result_columns(&json!({})).is_err());
+
+        let requested = vec!["id".to_string(), "name".to_string()];
+        assert!(DataTransferService::ensure_columns_present(&requested, &row).is_ok());
+        let error = DataTransferService::ensure_columns_present(&["id".to_string(), "email".to_string()], &row)
+            .unwrap_err();
+        assert!(error.to_string().contains("email"));
+    }
+}
@@ -1,9 +1,17 @@
 // Database adapter trait for multi-database support
-use crate::models::{DatabaseConnection, DatabaseMetadata};
+use crate::models::{DatabaseConnection, DatabaseMetadata, QueryPlan};
 use crate::api::middleware::AppError;
+use crate::validation::{LimitRewriter, LimitStyle, PlaceholderStyle};
+use sqlparser::dialect::{Dialect, GenericDialect};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use serde_json::Value;
 use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::arrow::record_batch::RecordBatch;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 /// Database connection information
 #[derive(Debug, Clone)]
@@ -13,13 +21,47 @@ pub struct DatabaseConnectionInfo {
 }
 
 /// Query execution result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
     pub rows: Vec<Value>,
     pub row_count: usize,
     pub execution_time_ms: u64,
 }
 
+/// Rows of a query result, produced in batches while the query runs
+pub type RowBatchStream = BoxStream<'static, Result<Vec<Value>, AppError>>;
+
+/// Rows per batch when a result is collected rather than consumed as a stream
+pub const STREAM_BATCH_ROWS: usize = 1000;
+
+/// Whether a connection's credentials can modify data
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum WriteAccess {
+    /// No write privileges were found
+    ReadOnly,
+    /// The credentials can modify data (detail names the privilege found)
+    Writable(String),
+    /// The database does not expose enough information to tell
+    Unknown(String),
+}
+
+/// Transaction pinned to one pooled connection
+///
+/// Dropping it without [`Transaction::commit`] rolls it back.
+#[async_trait::async_trait]
+pub trait Transaction: Send {
+    /// Execute a write statement within the transaction and return the affected row count
+    async fn execute(&mut self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<u64, AppError>;
+
+    async fn commit(self: Box<Self>) -> Result<(), AppError>;
+
+    async fn rollback(self: Box<Self>) -> Result<(), AppError>;
+
+    /// Positional placeholders the driver binds values to
+    fn placeholder_style(&self) -> PlaceholderStyle;
+}
+
 /// Database adapter trait - abstraction layer for different database types
 /// All adapters use DataFusion as the intermediate semantic layer
 #[async_trait::async_trait]
@@ -30,14 +72,112 @@ pub trait DatabaseAdapter: Send + Sync {
         connection_id: String,
     ) -> Result<(DatabaseConnection, DatabaseMetadata), AppError>;
 
-    /// Execute a SQL query using DataFusion
-    /// The SQL is validated and converted to DataFusion's logical plan,
-    /// then executed against the target database
+    /// Retrieve metadata again, reading the columns only of tables and views
+    /// that are new or may have changed since `previous`; the others keep
+    /// their previous columns
+    ///
+    /// Adapters that cannot tell unchanged tables cheaply read everything.
+    async fn refresh_metadata(
+        &self,
+        connection_id: String,
+        previous: &DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
+        let _ = previous;
+        self.connect_and_get_metadata(connection_id)
+            .await
+            .map(|(_, metadata)| metadata)
+    }
+
+    /// Execute a SQL query with positional bind values and stream its rows
+    /// in batches of up to `batch_size`
+    ///
+    /// Rows are read from the database as the stream is consumed, so only the
+    /// batches in flight are held in memory. `timeout_secs` bounds the whole
+    /// stream; dropping the stream ends the query (cancelling it on the server
+    /// where the database supports it). Errors after the query started are the
+    /// last item of the stream.
+    ///
+    /// The placeholders follow [`DatabaseAdapter::placeholder_style`]. Adapters
+    /// that cannot bind values natively reject non-empty `params`.
+    async fn stream_query(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError>;
+
+    /// Execute a SQL query and collect all of its rows
     async fn execute_query(
         &self,
         sql: &str,
         timeout_secs: u64,
-    ) -> Result<QueryResult, AppError>;
+    ) -> Result<QueryResult, AppError> {
+        self.execute_query_with_params(sql, &[], timeout_secs).await
+    }
+
+    /// Execute a SQL query with positional bind values and collect all of its rows
+    ///
+    /// Bind values are handled as for [`DatabaseAdapter::stream_query`].
+    async fn execute_query_with_params(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+    ) -> Result<QueryResult, AppError> {
+        let start_time = Instant::now();
+        let batches = self.stream_query(sql, params, timeout_secs, STREAM_BATCH_ROWS).await?;
+        let rows = collect_rows(batches).await?;
+        Ok(QueryResult {
+            row_count: rows.len(),
+            rows,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Execute a write statement (INSERT/UPDATE/DELETE/DDL) with positional
+    /// bind values and return the number of affected rows
+    ///
+    /// Only reached through the write-mode endpoint, after the statement was
+    /// validated with `SqlValidator::validate_write`.
+    async fn execute_write(&self, _sql: &str, _params: &[Value], _timeout_secs: u64) -> Result<u64, AppError> {
+        Err(AppError::Validation(format!(
+            "Write statements are not supported for {} connections",
+            self.database_type()
+        )))
+    }
+
+    /// Start a transaction on a connection taken from the pool
+    ///
+    /// The connection stays out of the pool until the transaction ends.
+    async fn begin_transaction(&self) -> Result<Box<dyn Transaction>, AppError> {
+        Err(AppError::Validation(format!(
+            "Transactions are not supported for {} connections",
+            self.database_type()
+        )))
+    }
+
+    /// Insert rows into a table and return the number of rows inserted
+    ///
+    /// `table` is already quoted; rows are JSON objects read by column name
+    /// (missing columns insert NULL). The default sends multi-row INSERTs with
+    /// bind values through [`DatabaseAdapter::execute_write`]; adapters with a
+    /// native bulk load override it.
+    async fn bulk_insert(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: &[Value],
+        timeout_secs: u64,
+    ) -> Result<u64, AppError> {
+        let quoted: Vec<String> = columns.iter().map(|column| self.quote_identifier(column)).collect();
+        let mut inserted = 0;
+        for chunk in rows.chunks(insert_chunk_rows(columns.len())) {
+            let (sql, params) = insert_statement(table, columns, &quoted, chunk, self.placeholder_style());
+            inserted += self.execute_write(&sql, &params, timeout_secs).await?;
+        }
+        Ok(inserted)
+    }
 
     /// Execute a DataFusion SQL query and return Arrow RecordBatches
     /// This method is used for unified SQL execution with automatic dialect translation.
@@ -76,6 +216,314 @@ pub trait DatabaseAdapter: Send + Sync {
         // Adapters that implement DataFusion execution should override this
         false
     }
+
+    /// sqlparser dialect used to parse queries sent to this database
+    fn sql_dialect(&self) -> Box<dyn Dialect> {
+        Box::new(GenericDialect {})
+    }
+
+    /// Quote an identifier (schema, table or column name) for this database
+    fn quote_identifier(&self, ident: &str) -> String {
+        quote_identifier(ident, '"')
+    }
+
+    /// How this database expresses a row limit
+    fn limit_style(&self) -> LimitStyle {
+        LimitStyle::Limit
+    }
+
+    /// Positional placeholders the driver binds values to
+    fn placeholder_style(&self) -> PlaceholderStyle {
+        PlaceholderStyle::QuestionMark
+    }
+
+    /// Inspect whether the connection's credentials can modify data
+    ///
+    /// Used to verify connections marked read-only. Adapters inspect grants
+    /// rather than attempting writes.
+    async fn check_write_access(&self) -> Result<WriteAccess, AppError> {
+        Ok(WriteAccess::Unknown(format!(
+            "Write privilege checks are not supported for {}",
+            self.database_type()
+        )))
+    }
+
+    /// Estimate the number of rows a query returns from the database's EXPLAIN
+    /// output, without executing it (`None` when the database gives no estimate)
+    async fn estimate_rows(&self, _sql: &str) -> Result<Option<u64>, AppError> {
+        Ok(None)
+    }
+
+    /// Run the database's native EXPLAIN for a query (without executing it)
+    /// and normalize the plan
+    ///
+    /// `params` are bound as for [`DatabaseAdapter::execute_query_with_params`].
+    async fn explain(&self, _sql: &str, _params: &[Value], _timeout_secs: u64) -> Result<QueryPlan, AppError> {
+        Err(AppError::Validation(format!(
+            "EXPLAIN is not supported for {} connections",
+            self.database_type()
+        )))
+    }
+
+    /// Inject a row limit into the outermost query using this database's syntax
+    ///
+    /// Falls back to GenericDialect when the adapter dialect cannot parse the
+    /// query (e.g. DataFusion-specific syntax).
+    ///
+    /// # Returns
+    /// Tuple of (sql to execute, whether a limit was injected)
+    fn apply_row_limit(&self, sql: &str, limit: u64) -> Result<(String, bool), AppError> {
+        let style = self.limit_style();
+        match LimitRewriter::apply(sql, self.sql_dialect().as_ref(), style, limit) {
+            Ok(result) => Ok(result),
+            Err(_) => LimitRewriter::apply(sql, &GenericDialect {}, style, limit),
+        }
+    }
+}
+
+/// Wrap `ident` in `quote`, doubling any `quote` inside it
+pub(crate) fn quote_identifier(ident: &str, quote: char) -> String {
+    let escaped = ident.replace(quote, &format!("{}{}", quote, quote));
+    format!("{}{}{}", quote, escaped, quote)
+}
+
+/// Most bind values in one statement (the PostgreSQL and MySQL protocol limit)
+const MAX_BIND_VALUES: usize = 65_535;
+
+/// Rows per INSERT statement that stay within the bind value limit
+pub(crate) fn insert_chunk_rows(columns: usize) -> usize {
+    (MAX_BIND_VALUES / columns.max(1)).max(1)
+}
+
+/// Multi-row `INSERT INTO table (columns) VALUES (...), ...` and its bind values
+///
+/// `table` and `quoted_columns` (the quoted `columns`) go into the SQL as they are.
+pub(crate) fn insert_statement(
+    table: &str,
+    columns: &[String],
+    quoted_columns: &[String],
+    rows: &[Value],
+    style: PlaceholderStyle,
+) -> (String, Vec<Value>) {
+    let mut params = Vec::with_capacity(rows.len() * columns.len());
+    let mut tuples = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut placeholders = Vec::with_capacity(columns.len());
+        for column in columns {
+            params.push(row.get(column).cloned().unwrap_or(Value::Null));
+            placeholders.push(match style {
+                PlaceholderStyle::Numbered => format!("${}", params.len()),
+                PlaceholderStyle::QuestionMark => "?".to_string(),
+            });
+        }
+        tuples.push(format!("({})", placeholders.join(", ")));
+    }
+    let sql = format!("INSERT INTO {} ({}) VALUES {}", table, quoted_columns.join(", "), tuples.join(", "));
+    (sql, params)
+}
+
+/// Rejects bind values for adapters that cannot bind them natively
+pub(crate) fn ensure_no_params(adapter: &dyn DatabaseAdapter, params: &[Value]) -> Result<(), AppError> {
+    if params.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::Validation(format!(
+        "Bind parameters are not supported for {} connections",
+        adapter.database_type()
+    )))
+}
+
+/// Read a row stream to the end
+pub async fn collect_rows(mut batches: RowBatchStream) -> Result<Vec<Value>, AppError> {
+    let mut rows = Vec::new();
+    while let Some(batch) = batches.next().await {
+        rows.extend(batch?);
+    }
+    Ok(rows)
+}
+
+/// Producer side of a stream started with [`spawn_row_stream`]
+pub(crate) struct RowBatchSender {
+    tx: mpsc::Sender<Result<Vec<Value>, AppError>>,
+    batch: Vec<Value>,
+    batch_size: usize,
+}
+
+impl RowBatchSender {
+    /// Add a row, sending the batch once it is full
+    ///
+    /// Returns false once the stream was dropped; the producer should stop.
+    pub(crate) async fn push(&mut self, row: Value) -> bool {
+        self.batch.push(row);
+        if self.batch.len() < self.batch_size {
+            return true;
+        }
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(self.batch_size));
+        self.tx.send(Ok(batch)).await.is_ok()
+    }
+
+    /// Send the last, partial batch
+    pub(crate) async fn finish(mut self) {
+        if !self.batch.is_empty() {
+            let _ = self.tx.send(Ok(std::mem::take(&mut self.batch))).await;
+        }
+    }
+}
+
+/// Run `producer` in its own task, feeding the returned stream
+///
+/// The producer pushes rows and calls [`RowBatchSender::finish`] when done;
+/// an error it returns becomes the last item of the stream. It is dropped
+/// (which is where adapters cancel the statement) when `timeout_secs` elapse
+/// or the stream is dropped. Up to two full batches wait for the consumer
+/// before the producer is held back.
+pub(crate) fn spawn_row_stream<F, Fut>(batch_size: usize, timeout_secs: u64, producer: F) -> RowBatchStream
+where
+    F: FnOnce(RowBatchSender) -> Fut,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(2);
+    let batch_size = batch_size.max(1);
+    let work = producer(RowBatchSender {
+        tx: tx.clone(),
+        batch: Vec::with_capacity(batch_size),
+        batch_size,
+    });
+
+    tokio::spawn(async move {
+        let outcome = tokio::select! {
+            _ = tx.closed() => return,
+            outcome = tokio::time::timeout(Duration::from_secs(timeout_secs), work) => outcome,
+        };
+        let error = match outcome {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e,
+            Err(_) => AppError::Database(format!("Query timeout after {} seconds", timeout_secs)),
+        };
+        let _ = tx.send(Err(error)).await;
+    });
+
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) }).boxed()
+}
+
+/// Find a write privilege in MySQL-protocol `SHOW GRANTS` output
+///
+/// Handles both MySQL (`GRANT SELECT, INSERT ON db.* TO ...`) and Doris
+/// (`Load_priv`, `Alter_priv`, ...) formats. `USAGE` and `SELECT` are reads.
+pub(crate) fn find_write_grant(grants: &[String]) -> Option<String> {
+    const WRITE_PRIVILEGES: &[&str] = &[
+        "ALL PRIVILEGES", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER", "TRUNCATE",
+        "LOAD_PRIV", "ALTER_PRIV", "CREATE_PRIV", "DROP_PRIV", "ADMIN_PRIV", "NODE_PRIV",
+    ];
+
+    grants.iter().find_map(|grant| {
+        let upper = grant.to_uppercase();
+        let privileges = upper.split(" ON ").next().unwrap_or(&upper);
+        WRITE_PRIVILEGES
+            .iter()
+            .find(|privilege| {
+                privileges
+                    .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ' '))
+                    .any(|token| token.trim().trim_start_matches("GRANT ").trim() == **privilege)
+            })
+            .map(|privilege| format!("{} (from: {})", privilege, grant.trim()))
+    })
+}
+
+/// Numeric values of `key=<n>` entries in textual EXPLAIN output, in plan order
+///
+/// PostgreSQL prints `rows=<n>` per node (the first is the top node), Doris
+/// prints `cardinality=<n>` (`-1` when unknown, which is skipped).
+pub(crate) fn explain_estimates(plan: &str, key: &str) -> Vec<u64> {
+    let pattern = format!("{}=", key);
+    plan.match_indices(&pattern)
+        .filter_map(|(idx, _)| {
+            let digits: String = plan[idx + pattern.len()..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.parse().ok()
+        })
+        .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_estimates() {
+        let postgres = "Hash Join  (cost=1.09..24.48 rows=2500 width=36)\n  ->  Seq Scan on orders  (cost=0.00..18.50 rows=850 width=8)";
+        assert_eq!(explain_estimates(postgres, "rows"), vec![2500, 850]);
+
+        let doris = "0:VOlapScanNode\n     TABLE: sales.orders\n     cardinality=120000, avgRowSize=0.0\n  1:VEXCHANGE\n     cardinality=-1";
+        assert_eq!(explain_estimates(doris, "cardinality"), vec![120000]);
+        assert!(explain_estimates("Result", "rows").is_empty());
+    }
+
+    #[test]
+    fn test_insert_statement_binds_rows_by_column() {
+        let columns = vec!["id".to_string(), "name".to_string()];
+        let quoted = vec!["\"id\"".to_string(), "\"name\"".to_string()];
+        let rows = vec![serde_json::json!({"name": "Alice", "id": 1}), serde_json::json!({"id": 2})];
 
+        let (sql, params) = insert_statement("\"users\"", &columns, &quoted, &rows, PlaceholderStyle::Numbered);
+        assert_eq!(sql, "INSERT INTO \"users\" (\"id\", \"name\") VALUES ($1, $2), ($3, $4)");
+        assert_eq!(params, vec![serde_json::json!(1), serde_json::json!("Alice"), serde_json::json!(2), Value::Null]);
+
+        let (sql, _) = insert_statement("`users`", &columns, &columns, &rows[..1], PlaceholderStyle::QuestionMark);
+        assert_eq!(sql, "INSERT INTO `users` (id, name) VALUES (?, ?)");
+        assert_eq!(insert_chunk_rows(2), 32_767);
+        assert_eq!(insert_chunk_rows(0), 65_535);
+    }
+
+    #[tokio::test]
+    async fn test_row_stream_batches_and_timeout() {
+        let batches = spawn_row_stream(2, 5, |mut sender| async move {
+            for n in 0..5 {
+                sender.push(serde_json::json!({"n": n})).await;
+            }
+            sender.finish().await;
+            Ok(())
+        });
+        let sizes: Vec<usize> = batches.map(|batch| batch.unwrap().len()).collect().await;
+        assert_eq!(sizes, vec![2, 2, 1]);
+
+        // Rows sent before the timeout arrive, then the timeout ends the stream
+        let batches = spawn_row_stream(1, 1, |mut sender| async move {
+            sender.push(serde_json::json!({"n": 0})).await;
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        let items: Vec<Result<Vec<Value>, AppError>> = batches.collect().await;
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert!(matches!(&items[1], Err(AppError::Database(message)) if message.contains("timeout")));
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("orders", '"'), "\"orders\"");
+        assert_eq!(quote_identifier("a\"; DROP TABLE t; --", '"'), "\"a\"\"; DROP TABLE t; --\"");
+        assert_eq!(quote_identifier("odd`name", '`'), "`odd``name`");
+    }
+
+    #[test]
+    fn test_find_write_grant() {
+        let read_only = vec![
+            "GRANT USAGE ON *.* TO `reporter`@`%`".to_string(),
+            "GRANT SELECT ON `sales`.* TO `reporter`@`%`".to_string(),
+        ];
+        assert_eq!(find_write_grant(&read_only), None);
+
+        let writable = vec!["GRANT SELECT, INSERT, UPDATE ON `sales`.* TO `app`@`%`".to_string()];
+        assert!(find_write_grant(&writable).unwrap().starts_with("INSERT"));
+
+        let all = vec!["GRANT ALL PRIVILEGES ON *.* TO `root`@`localhost` WITH GRANT OPTION".to_string()];
+        assert!(find_write_grant(&all).unwrap().starts_with("ALL PRIVILEGES"));
+
+        // Doris privilege names
+        assert!(find_write_grant(&["Select_priv,Load_priv".to_string()]).is_some());
+        assert_eq!(find_write_grant(&["Select_priv".to_string()]), None);
+    }
+}
@@ -1,52 +1,101 @@
 // Apache Doris adapter using MySQL protocol compatibility
 // Doris is a high-performance analytical database that uses MySQL wire protocol
-use crate::models::{DatabaseConnection, DatabaseMetadata, Table, View, Column};
+use crate::models::{Column, DatabaseConnection, DatabaseMetadata, EngineMetadata, PartitionInfo, QueryPlan, Table, View};
 use crate::api::middleware::AppError;
-use crate::services::database::adapter::{DatabaseAdapter, QueryResult};
-use mysql_async::{Pool, OptsBuilder, Conn, Row, Value as MySqlValue, prelude::*};
+use crate::services::database::incremental::{from_epoch_secs, IncrementalMetadata, RelationListing};
+use crate::services::database::adapter::{
+    ensure_no_params, explain_estimates, find_write_grant, insert_chunk_rows, quote_identifier, spawn_row_stream, DatabaseAdapter,
+    RowBatchStream, WriteAccess,
+};
+use crate::services::database::plan::doris_plan;
+use crate::services::connection_pool::PoolMetrics;
+use mysql_async::{Pool, OptsBuilder, Conn, Params, Row, Value as MySqlValue, prelude::*};
 use url::Url;
 use serde_json::{json, Value};
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct DorisAdapter {
     pool: Pool,
     connection_url: String,
+    pool_metrics: Arc<PoolMetrics>,
+}
+
+/// A row of `information_schema.table_options`
+struct TableOptionsRow {
+    schema: String,
+    table: String,
+    model: Option<String>,
+    model_key: Option<String>,
+    distribute_key: Option<String>,
+    distribute_type: Option<String>,
+    buckets: Option<i64>,
+}
+
+/// A row of `information_schema.partitions`
+struct PartitionRow {
+    schema: String,
+    table: String,
+    name: String,
+    method: Option<String>,
+    expression: Option<String>,
+    description: Option<String>,
+    rows: Option<i64>,
 }
 
 impl DorisAdapter {
     pub fn new(connection_url: &str) -> Result<Self, AppError> {
+        // Build MySQL connection options from URL
+        let opts = OptsBuilder::from_opts(Self::mysql_url(connection_url)?.as_str());
+        let pool = Pool::new(opts);
+
+        Ok(Self::with_pool(pool, connection_url))
+    }
+
+    /// Adapter on a shared pool, opened on [`DorisAdapter::mysql_url`]
+    pub fn with_pool(pool: Pool, connection_url: &str) -> Self {
+        Self {
+            pool,
+            connection_url: connection_url.to_string(),
+            pool_metrics: Arc::new(PoolMetrics::default()),
+        }
+    }
+
+    /// Count checkouts in the pool's metrics, which also bound the wait for a connection
+    pub fn with_pool_metrics(mut self, pool_metrics: Arc<PoolMetrics>) -> Self {
+        self.pool_metrics = pool_metrics;
+        self
+    }
+
+    /// The `mysql://` URL the adapter connects to
+    pub fn mysql_url(connection_url: &str) -> Result<String, AppError> {
         // Validate Doris URL format
         // Doris uses MySQL protocol, so we accept both doris:// and mysql:// schemes
         let url = Url::parse(connection_url)
             .map_err(|e| AppError::Validation(format!("Invalid Doris URL: {}", e)))?;
 
         // Convert doris:// scheme to mysql:// for connection
-        let mysql_url = if url.scheme() == "doris" {
-            connection_url.replace("doris://", "mysql://")
+        if url.scheme() == "doris" {
+            Ok(connection_url.replace("doris://", "mysql://"))
         } else if url.scheme() == "mysql" {
-            connection_url.to_string()
+            Ok(connection_url.to_string())
         } else {
-            return Err(AppError::Validation(
+            Err(AppError::Validation(
                 "URL must use doris:// or mysql:// scheme for Doris".to_string()
-            ));
-        };
-
-        // Build MySQL connection options from URL
-        let opts = OptsBuilder::from_opts(mysql_url.as_str());
-        let pool = Pool::new(opts);
-
-        Ok(Self {
-            pool,
-            connection_url: connection_url.to_string(),
-        })
+            ))
+        }
     }
 
     /// Get a connection from the pool
     async fn get_conn(&self) -> Result<Conn, AppError> {
-        self.pool
-            .get_conn()
+        self.pool_metrics
+            .acquire(async {
+                self.pool
+                    .get_conn()
+                    .await
+                    .map_err(|e| AppError::Connection(format!("Failed to get Doris connection from pool: {}", e)))
+            })
             .await
-            .map_err(|e| AppError::Connection(format!("Failed to get Doris connection from pool: {}", e)))
     }
 }
 
@@ -75,57 +124,79 @@ impl DatabaseAdapter for DorisAdapter {
         Ok((db_connection, metadata))
     }
 
-    async fn execute_query(
+    async fn refresh_metadata(
         &self,
-        sql: &str,
-        timeout_secs: u64,
-    ) -> Result<QueryResult, AppError> {
-        // Get a connection from the pool
+        connection_id: String,
+        previous: &DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
         let mut conn = self.get_conn().await?;
 
-        let start_time = Instant::now();
-
-        // Execute query with timeout
-        let rows: Vec<Row> = tokio::time::timeout(
-            std::time::Duration::from_secs(timeout_secs),
-            conn.query(sql),
-        )
-        .await
-        .map_err(|_| AppError::Database(format!("Query timeout after {} seconds", timeout_secs)))?
-        .map_err(|e| AppError::Database(format!("Query execution failed: {}", e)))?;
-
-        // Convert rows to JSON
-        let mut json_rows = Vec::new();
-        for row in rows {
-            let mut row_obj = serde_json::Map::new();
-            let columns = row.columns_ref();
-
-            for (idx, column) in columns.iter().enumerate() {
-                let column_name = column.name_str();
-                let value: Value = match row.get_opt::<MySqlValue, usize>(idx) {
-                    Some(Ok(mysql_val)) => Self::mysql_value_to_json(mysql_val),
-                    Some(Err(_)) => Value::Null,
-                    None => Value::Null,
-                };
-                row_obj.insert(column_name.to_string(), value);
-            }
-            json_rows.push(Value::Object(row_obj));
+        let schemas = Self::get_schemas(&mut conn).await?;
+        let mut listings = Self::list_relations(&mut conn).await?;
+        let mut engine_metadata = Self::get_engine_metadata(&mut conn).await;
+        for listing in &mut listings {
+            listing.engine_metadata = engine_metadata.remove(&(listing.schema.clone(), listing.name.clone()));
+        }
+        let mut metadata = IncrementalMetadata::new(previous);
+        let mut all_columns = if metadata.needs_bulk_read(&listings) {
+            Self::get_all_columns(&mut conn).await?
+        } else {
+            HashMap::new()
+        };
+        for listing in listings {
+            let columns = match metadata.reusable_columns(&listing) {
+                Some(columns) => columns,
+                None => {
+                    metadata.columns_read += 1;
+                    match all_columns.remove(&(listing.schema.clone(), listing.name.clone())) {
+                        Some(columns) => columns,
+                        None => Self::get_table_columns(&mut conn, &listing.schema, &listing.name).await?,
+                    }
+                }
+            };
+            metadata.push(listing, columns);
         }
+        Ok(metadata.finish(&connection_id, schemas))
+    }
 
-        let row_count = json_rows.len();
-        let execution_time_ms = start_time.elapsed().as_millis() as u64;
+    async fn stream_query(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError> {
+        ensure_no_params(self, params)?;
 
-        Ok(QueryResult {
-            rows: json_rows,
-            row_count,
-            execution_time_ms,
-        })
+        // Get a connection from the pool; it stays with the stream until the rows are read
+        let mut conn = self.get_conn().await?;
+
+        let sql = sql.to_string();
+        Ok(spawn_row_stream(batch_size, timeout_secs, move |mut sender| async move {
+            let query_error = |e: mysql_async::Error| AppError::Database(format!("Query execution failed: {}", e));
+            let mut result = conn.query_iter(sql).await.map_err(query_error)?;
+            while let Some(row) = result.next().await.map_err(query_error)? {
+                if !sender.push(Self::row_to_json(row)).await {
+                    return Ok(());
+                }
+            }
+            sender.finish().await;
+            Ok(())
+        }))
     }
 
     fn database_type(&self) -> &str {
         "doris"
     }
 
+    fn sql_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        Box::new(sqlparser::dialect::MySqlDialect {})
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        quote_identifier(ident, '`')
+    }
+
     fn dialect_name(&self) -> &str {
         "mysql" // Doris uses MySQL-compatible dialect
     }
@@ -161,9 +232,9 @@ impl DatabaseAdapter for DorisAdapter {
         // Create translator service
         let translator_service = DialectTranslationService::new();
 
-        // Translate DataFusion SQL to MySQL/Doris dialect
+        // Translate DataFusion SQL to Doris dialect
         let translated_sql = translator_service
-            .translate_query(datafusion_sql, DFDatabaseType::MySQL)
+            .translate_query(datafusion_sql, DFDatabaseType::Doris)
             .await
             .map_err(|e| AppError::Database(format!("Failed to translate SQL: {}", e)))?;
 
@@ -204,6 +275,77 @@ impl DatabaseAdapter for DorisAdapter {
         Ok((schema, vec![batch]))
     }
 
+    async fn estimate_rows(&self, sql: &str) -> Result<Option<u64>, AppError> {
+        let mut conn = self.get_conn().await?;
+        let lines: Vec<String> = conn
+            .query(format!("EXPLAIN {}", sql))
+            .await
+            .map_err(|e| AppError::Database(format!("EXPLAIN failed: {}", e)))?;
+
+        // Largest node cardinality (scan nodes carry the table size estimates)
+        Ok(explain_estimates(&lines.join("\n"), "cardinality").into_iter().max())
+    }
+
+    async fn explain(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<QueryPlan, AppError> {
+        let result = self
+            .execute_query_with_params(&format!("EXPLAIN {}", sql), params, timeout_secs)
+            .await?;
+        // One plan line per row, in a single column whose name varies by version
+        let lines: Vec<String> = result
+            .rows
+            .iter()
+            .filter_map(|row| row.as_object()?.values().next()?.as_str().map(str::to_string))
+            .collect();
+        Ok(doris_plan(&lines))
+    }
+
+    async fn check_write_access(&self) -> Result<WriteAccess, AppError> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<Row> = conn
+            .query("SHOW GRANTS")
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to inspect Doris grants: {}", e)))?;
+
+        // Every text column counts (Doris lists global/catalog/database/table privileges in columns)
+        let grants: Vec<String> = rows
+            .into_iter()
+            .flat_map(|row| row.unwrap())
+            .filter_map(|value| match value {
+                MySqlValue::Bytes(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        Ok(match find_write_grant(&grants) {
+            Some(grant) => WriteAccess::Writable(grant),
+            None => WriteAccess::ReadOnly,
+        })
+    }
+
+    /// Sends multi-row `INSERT ... VALUES` with literal values, as Doris cannot
+    /// bind parameters (Stream Load needs the FE HTTP port, which connection
+    /// URLs do not carry)
+    async fn bulk_insert(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: &[Value],
+        timeout_secs: u64,
+    ) -> Result<u64, AppError> {
+        let quoted: Vec<String> = columns.iter().map(|column| self.quote_identifier(column)).collect();
+        let mut conn = self.get_conn().await?;
+        let mut inserted = 0;
+        for chunk in rows.chunks(insert_chunk_rows(columns.len())) {
+            let sql = Self::insert_values_sql(table, columns, &quoted, chunk);
+            tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), conn.query_drop(sql))
+                .await
+                .map_err(|_| AppError::Database(format!("Statement timeout after {} seconds", timeout_secs)))?
+                .map_err(|e| AppError::Database(format!("Statement execution failed: {}", e)))?;
+            inserted += conn.affected_rows();
+        }
+        Ok(inserted)
+    }
+
     async fn test_connection(&self) -> Result<(), AppError> {
         // Test connection by executing a simple query
         let mut conn = self.get_conn().await?;
@@ -215,6 +357,50 @@ impl DatabaseAdapter for DorisAdapter {
 }
 
 impl DorisAdapter {
+    /// `INSERT INTO table (columns) VALUES ...` with the rows as SQL literals
+    fn insert_values_sql(table: &str, columns: &[String], quoted_columns: &[String], rows: &[Value]) -> String {
+        let tuples: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let values: Vec<String> = columns
+                    .iter()
+                    .map(|column| Self::sql_literal(row.get(column).unwrap_or(&Value::Null)))
+                    .collect();
+                format!("({})", values.join(", "))
+            })
+            .collect();
+        format!("INSERT INTO {} ({}) VALUES {}", table, quoted_columns.join(", "), tuples.join(", "))
+    }
+
+    /// SQL literal of a JSON value (arrays and objects as JSON text)
+    fn sql_literal(value: &Value) -> String {
+        let text = match value {
+            Value::Null => return "NULL".to_string(),
+            Value::Bool(b) => return if *b { "TRUE" } else { "FALSE" }.to_string(),
+            Value::Number(n) => return n.to_string(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"))
+    }
+
+    /// Convert a row to a JSON object keyed by column name
+    fn row_to_json(row: Row) -> Value {
+        let mut row_obj = serde_json::Map::new();
+        let columns = row.columns_ref();
+
+        for (idx, column) in columns.iter().enumerate() {
+            let column_name = column.name_str();
+            let value: Value = match row.get_opt::<MySqlValue, usize>(idx) {
+                Some(Ok(mysql_val)) => Self::mysql_value_to_json(mysql_val),
+                Some(Err(_)) => Value::Null,
+                None => Value::Null,
+            };
+            row_obj.insert(column_name.to_string(), value);
+        }
+        Value::Object(row_obj)
+    }
+
     /// Retrieve database metadata (tables, views, schemas)
     async fn retrieve_metadata(
         conn: &mut Conn,
@@ -223,11 +409,19 @@ impl DorisAdapter {
         // Get schemas
         let schemas = Self::get_schemas(conn).await?;
 
+        // Columns of every table and view in one query, rather than one per table
+        let mut columns = Self::get_all_columns(conn).await?;
+
         // Get tables
-        let tables = Self::get_tables(conn).await?;
+        let mut tables = Self::get_tables(conn, &mut columns).await?;
+        let mut engine_metadata = Self::get_engine_metadata(conn).await;
+        for table in &mut tables {
+            let key = (table.schema.clone().unwrap_or_default(), table.name.clone());
+            table.engine_metadata = engine_metadata.remove(&key);
+        }
 
         // Get views
-        let views = Self::get_views(conn).await?;
+        let views = Self::get_views(conn, &mut columns).await?;
 
         Ok(DatabaseMetadata::new(
             connection_id.to_string(),
@@ -257,12 +451,16 @@ impl DorisAdapter {
     }
 
     /// Get list of tables with their metadata
-    async fn get_tables(conn: &mut Conn) -> Result<Vec<Table>, AppError> {
+    async fn get_tables(
+        conn: &mut Conn,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<Table>, AppError> {
         let query = r#"
             SELECT
                 TABLE_SCHEMA,
                 TABLE_NAME,
-                TABLE_TYPE
+                TABLE_TYPE,
+                TABLE_ROWS
             FROM INFORMATION_SCHEMA.TABLES
             WHERE TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')
                 AND TABLE_TYPE = 'BASE TABLE'
@@ -277,22 +475,202 @@ impl DorisAdapter {
         for row in rows {
             let schema: String = row.get(0).unwrap_or_default();
             let name: String = row.get(1).unwrap_or_default();
+            // Row count from the last statistics report of the tablets
+            let row_count: Option<i64> = row.get::<Option<i64>, usize>(3).flatten();
 
-            let columns = Self::get_table_columns(conn, &schema, &name).await?;
+            let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
             tables.push(Table {
                 name,
                 schema: Some(schema),
                 columns,
-                row_count: None,
+                row_count,
+                size_bytes: None,
                 description: None,
+                tags: Vec::new(),
+                engine_metadata: None,
             });
         }
 
         Ok(tables)
     }
 
+    /// Tables and views with the time they last changed and their column
+    /// count, in one query
+    ///
+    /// `UPDATE_TIME` moves with schema changes and data loads alike. Views
+    /// carry no timestamps, so their columns are always read.
+    async fn list_relations(conn: &mut Conn) -> Result<Vec<RelationListing>, AppError> {
+        let query = r#"
+            SELECT
+                t.TABLE_SCHEMA,
+                t.TABLE_NAME,
+                t.TABLE_TYPE,
+                t.TABLE_ROWS,
+                UNIX_TIMESTAMP(GREATEST(t.CREATE_TIME, COALESCE(t.UPDATE_TIME, t.CREATE_TIME))) AS changed_at,
+                c.column_count
+            FROM INFORMATION_SCHEMA.TABLES t
+            LEFT JOIN (
+                SELECT TABLE_SCHEMA, TABLE_NAME, COUNT(*) AS column_count
+                FROM INFORMATION_SCHEMA.COLUMNS
+                WHERE TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')
+                GROUP BY TABLE_SCHEMA, TABLE_NAME
+            ) c ON c.TABLE_SCHEMA = t.TABLE_SCHEMA AND c.TABLE_NAME = t.TABLE_NAME
+            WHERE t.TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')
+                AND t.TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+            ORDER BY t.TABLE_SCHEMA, t.TABLE_NAME
+        "#;
+
+        let rows: Vec<Row> = conn.query(query)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to list tables: {}", e)))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let table_type: String = row.get(2).unwrap_or_default();
+                RelationListing {
+                    schema: row.get(0).unwrap_or_default(),
+                    name: row.get(1).unwrap_or_default(),
+                    is_view: table_type == "VIEW",
+                    row_count: row.get::<Option<i64>, usize>(3).flatten(),
+                    changed_at: from_epoch_secs(row.get::<Option<i64>, usize>(4).flatten()),
+                    column_count: row
+                        .get::<Option<i64>, usize>(5)
+                        .flatten()
+                        .and_then(|count| usize::try_from(count).ok()),
+                    column_signature: None,
+                    engine_metadata: None,
+                }
+            })
+            .collect())
+    }
+
+    /// Partitioning, bucketing and data model of every table, from
+    /// `information_schema.table_options` and `information_schema.partitions`
+    ///
+    /// Both exist from Doris 2.1 on; older versions get no engine metadata
+    /// rather than a failed metadata read.
+    async fn get_engine_metadata(conn: &mut Conn) -> HashMap<(String, String), EngineMetadata> {
+        let options_query = r#"
+            SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_MODEL, TABLE_MODEL_KEY, DISTRIBUTE_KEY, DISTRIBUTE_TYPE, BUCKETS_NUM
+            FROM information_schema.table_options
+            WHERE TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')
+        "#;
+        let options: Vec<Row> = match conn.query(options_query).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::warn!("Failed to read Doris table options, engine metadata unavailable: {}", e);
+                return HashMap::new();
+            }
+        };
+
+        let partitions_query = r#"
+            SELECT TABLE_SCHEMA, TABLE_NAME, PARTITION_NAME, PARTITION_METHOD, PARTITION_EXPRESSION, PARTITION_DESCRIPTION, TABLE_ROWS
+            FROM information_schema.partitions
+            WHERE TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')
+                AND PARTITION_NAME IS NOT NULL
+            ORDER BY TABLE_SCHEMA, TABLE_NAME, PARTITION_ORDINAL_POSITION
+        "#;
+        let partitions: Vec<Row> = match conn.query(partitions_query).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::warn!("Failed to read Doris partitions: {}", e);
+                Vec::new()
+            }
+        };
+
+        let text = |row: &Row, index: usize| row.get::<Option<String>, usize>(index).flatten();
+        let options = options
+            .iter()
+            .map(|row| TableOptionsRow {
+                schema: text(row, 0).unwrap_or_default(),
+                table: text(row, 1).unwrap_or_default(),
+                model: text(row, 2),
+                model_key: text(row, 3),
+                distribute_key: text(row, 4),
+                distribute_type: text(row, 5),
+                buckets: row.get::<Option<i64>, usize>(6).flatten(),
+            })
+            .collect();
+        let partitions = partitions
+            .iter()
+            .map(|row| PartitionRow {
+                schema: text(row, 0).unwrap_or_default(),
+                table: text(row, 1).unwrap_or_default(),
+                name: text(row, 2).unwrap_or_default(),
+                method: text(row, 3),
+                expression: text(row, 4),
+                description: text(row, 5),
+                rows: row.get::<Option<i64>, usize>(6).flatten(),
+            })
+            .collect();
+        Self::build_engine_metadata(options, partitions)
+    }
+
+    /// Engine metadata per table from the rows of `table_options` and
+    /// `partitions` (in partition order)
+    ///
+    /// Unpartitioned tables have one partition named after the table and no
+    /// partition method; they get no partition details.
+    fn build_engine_metadata(
+        options: Vec<TableOptionsRow>,
+        partitions: Vec<PartitionRow>,
+    ) -> HashMap<(String, String), EngineMetadata> {
+        let key_list = |keys: Option<String>| -> Vec<String> {
+            keys.unwrap_or_default()
+                .split(',')
+                .map(|key| key.trim().trim_matches('`').to_string())
+                .filter(|key| !key.is_empty())
+                .collect()
+        };
+        let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let mut engine: HashMap<(String, String), EngineMetadata> = options
+            .into_iter()
+            .map(|row| {
+                let metadata = EngineMetadata {
+                    engine: "doris".to_string(),
+                    distribution_type: non_empty(row.distribute_type),
+                    distribution_keys: key_list(row.distribute_key),
+                    buckets: row.buckets.and_then(|buckets| u64::try_from(buckets).ok()),
+                    table_model: non_empty(row.model),
+                    key_columns: key_list(row.model_key),
+                    ..Default::default()
+                };
+                ((row.schema, row.table), metadata)
+            })
+            .collect();
+
+        for row in partitions {
+            let Some(method) = non_empty(row.method) else {
+                continue;
+            };
+            let metadata = engine.entry((row.schema, row.table)).or_insert_with(|| EngineMetadata {
+                engine: "doris".to_string(),
+                ..Default::default()
+            });
+            if metadata.partition_method.is_none() {
+                metadata.partition_method = Some(method.to_uppercase());
+                metadata.partition_keys = key_list(row.expression);
+            }
+            metadata.partitions.push(PartitionInfo {
+                name: row.name,
+                values: non_empty(row.description),
+                row_count: row.rows,
+            });
+        }
+        for metadata in engine.values_mut().filter(|metadata| !metadata.partitions.is_empty()) {
+            let partitions = std::mem::take(&mut metadata.partitions);
+            *metadata = std::mem::take(metadata).with_partitions(partitions);
+        }
+        engine
+    }
+
     /// Get list of views with their metadata
-    async fn get_views(conn: &mut Conn) -> Result<Vec<View>, AppError> {
+    async fn get_views(
+        conn: &mut Conn,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<View>, AppError> {
         let query = r#"
             SELECT
                 TABLE_SCHEMA,
@@ -311,27 +689,59 @@ impl DorisAdapter {
             let schema: String = row.get(0).unwrap_or_default();
             let name: String = row.get(1).unwrap_or_default();
 
-            let columns = Self::get_table_columns(conn, &schema, &name).await?;
+            let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
             views.push(View {
                 name,
                 schema: Some(schema),
                 columns,
                 definition: None,
                 description: None,
+                tags: Vec::new(),
             });
         }
 
         Ok(views)
     }
 
+    /// Columns of all tables and views in user databases, keyed by schema and name
+    async fn get_all_columns(conn: &mut Conn) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        Self::query_columns(
+            conn,
+            "c.TABLE_SCHEMA NOT IN ('information_schema', '__internal_schema', '_statistics_')",
+            Params::Empty,
+        )
+        .await
+    }
+
     /// Get columns for a specific table or view
     async fn get_table_columns(
         conn: &mut Conn,
         schema: &str,
         table_name: &str,
     ) -> Result<Vec<Column>, AppError> {
-        let query = r#"
+        let mut columns = Self::query_columns(
+            conn,
+            "c.TABLE_SCHEMA = ? AND c.TABLE_NAME = ?",
+            (schema, table_name).into(),
+        )
+        .await?;
+        Ok(columns
+            .remove(&(schema.to_string(), table_name.to_string()))
+            .unwrap_or_default())
+    }
+
+    /// Columns of the relations matching `filter` (a condition on
+    /// `INFORMATION_SCHEMA.COLUMNS c`), grouped by schema and name
+    async fn query_columns(
+        conn: &mut Conn,
+        filter: &str,
+        params: Params,
+    ) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        let query = format!(
+            r#"
             SELECT
+                c.TABLE_SCHEMA,
+                c.TABLE_NAME,
                 c.COLUMN_NAME,
                 c.DATA_TYPE,
                 c.IS_NULLABLE,
@@ -339,32 +749,37 @@ impl DorisAdapter {
                 c.COLUMN_KEY,
                 c.CHARACTER_MAXIMUM_LENGTH
             FROM INFORMATION_SCHEMA.COLUMNS c
-            WHERE c.TABLE_SCHEMA = ? AND c.TABLE_NAME = ?
-            ORDER BY c.ORDINAL_POSITION
-        "#;
+            WHERE {}
+            ORDER BY c.TABLE_SCHEMA, c.TABLE_NAME, c.ORDINAL_POSITION
+            "#,
+            filter
+        );
 
-        let rows: Vec<Row> = conn.exec(query, (schema, table_name))
+        let rows: Vec<Row> = conn.exec(query, params)
             .await
             .map_err(|e| AppError::Database(format!("Failed to get columns: {}", e)))?;
 
-        Ok(rows
-            .iter()
-            .map(|row| {
-                let column_key: String = row.get(4).unwrap_or_default();
-
-                Column {
-                    name: row.get(0).unwrap_or_default(),
-                    data_type: row.get(1).unwrap_or_default(),
-                    is_nullable: row.get::<String, usize>(2).unwrap_or_default() == "YES",
-                    default_value: row.get(3),
-                    is_primary_key: column_key == "PRI",
-                    is_foreign_key: column_key == "MUL" || column_key == "FOR",
-                    max_length: row.get::<Option<u64>, usize>(5)
-                        .and_then(|opt_val| opt_val.and_then(|v| i32::try_from(v).ok())),
-                    description: None,
-                }
-            })
-            .collect())
+        let mut columns: HashMap<(String, String), Vec<Column>> = HashMap::new();
+        for row in &rows {
+            let schema: String = row.get(0).unwrap_or_default();
+            let table: String = row.get(1).unwrap_or_default();
+            let column_key: String = row.get(6).unwrap_or_default();
+
+            columns.entry((schema, table)).or_default().push(Column {
+                name: row.get(2).unwrap_or_default(),
+                data_type: row.get(3).unwrap_or_default(),
+                is_nullable: row.get::<String, usize>(4).unwrap_or_default() == "YES",
+                default_value: row.get(5),
+                is_primary_key: column_key == "PRI",
+                is_foreign_key: column_key == "MUL" || column_key == "FOR",
+                max_length: row.get::<Option<u64>, usize>(7)
+                    .and_then(|opt_val| opt_val.and_then(|v| i32::try_from(v).ok())),
+                description: None,
+                distinct_count: None,
+                tags: Vec::new(),
+            });
+        }
+        Ok(columns)
     }
 
     /// Convert MySQL value to JSON
@@ -581,3 +996,59 @@ impl DorisAdapter {
             .map_err(|e| AppError::Database(format!("Failed to create RecordBatch: {}", e)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_values_sql_escapes_literals() {
+        let columns = vec!["id".to_string(), "name".to_string(), "active".to_string()];
+        let quoted: Vec<String> = columns.iter().map(|c| quote_identifier(c, '`')).collect();
+        let rows = vec![json!({"id": 1, "name": "O'Brien \\ co", "active": true}), json!({"id": 2})];
+
+        assert_eq!(
+            DorisAdapter::insert_values_sql("`users`", &columns, &quoted, &rows),
+            "INSERT INTO `users` (`id`, `name`, `active`) VALUES (1, 'O''Brien \\\\ co', TRUE), (2, NULL, NULL)"
+        );
+    }
+
+    #[test]
+    fn test_build_engine_metadata() {
+        let options = vec![TableOptionsRow {
+            schema: "sales".to_string(),
+            table: "events".to_string(),
+            model: Some("DUP".to_string()),
+            model_key: Some("dt,user_id".to_string()),
+            distribute_key: Some("`user_id`".to_string()),
+            distribute_type: Some("HASH".to_string()),
+            buckets: Some(16),
+        }];
+        let partition = |table: &str, name: &str, method: Option<&str>| PartitionRow {
+            schema: "sales".to_string(),
+            table: table.to_string(),
+            name: name.to_string(),
+            method: method.map(str::to_string),
+            expression: Some("`dt`".to_string()),
+            description: Some(format!("[{}]", name)),
+            rows: Some(10),
+        };
+        let partitions = vec![
+            partition("events", "p20240101", Some("range")),
+            partition("events", "p20240102", Some("range")),
+            // Unpartitioned tables list themselves without a method
+            partition("dims", "dims", None),
+        ];
+
+        let engine = DorisAdapter::build_engine_metadata(options, partitions);
+        let events = &engine[&("sales".to_string(), "events".to_string())];
+        assert_eq!(events.partition_method.as_deref(), Some("RANGE"));
+        assert_eq!(events.partition_keys, vec!["dt"]);
+        assert_eq!(events.partition_count, Some(2));
+        assert_eq!(events.partitions[1].values.as_deref(), Some("[p20240102]"));
+        assert_eq!(events.distribution_keys, vec!["user_id"]);
+        assert_eq!(events.buckets, Some(16));
+        assert_eq!(events.key_columns, vec!["dt", "user_id"]);
+        assert!(!engine.contains_key(&("sales".to_string(), "dims".to_string())));
+    }
+}
@@ -1,13 +1,15 @@
 // Apache Druid adapter using HTTP REST API
 // Druid is a real-time analytics database optimized for OLAP queries
-use crate::models::{DatabaseConnection, DatabaseMetadata, Table, Column};
+use crate::models::{DatabaseConnection, DatabaseMetadata, EngineMetadata, QueryPlan, Table, Column};
 use crate::api::middleware::AppError;
-use crate::services::database::adapter::{DatabaseAdapter, QueryResult};
+use crate::services::database::adapter::{DatabaseAdapter, RowBatchStream};
+use crate::services::database::plan::druid_plan;
 use reqwest::Client;
 use url::Url;
 use serde_json::{json, Value};
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
 
 pub struct DruidAdapter {
     base_url: String,
@@ -19,6 +21,29 @@ struct DruidSqlRequest {
     query: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     context: Option<Value>,
+    /// Values of the `?` placeholders, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parameters: Vec<DruidSqlParameter>,
+}
+
+/// Typed value of a Druid SQL dynamic parameter
+#[derive(Debug, Serialize, PartialEq)]
+struct DruidSqlParameter {
+    #[serde(rename = "type")]
+    sql_type: &'static str,
+    value: Value,
+}
+
+impl DruidSqlParameter {
+    fn from_json(value: &Value) -> Self {
+        let sql_type = match value {
+            Value::Bool(_) => "BOOLEAN",
+            Value::Number(n) if n.is_f64() => "DOUBLE",
+            Value::Number(_) => "BIGINT",
+            _ => "VARCHAR",
+        };
+        Self { sql_type, value: value.clone() }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,45 +62,87 @@ struct DruidColumn {
     data_type: String,
 }
 
-// DruidDatasource struct removed - not needed since we get datasources as Vec<String>
+/// Per-datasource statistics from `sys.segments`
+#[derive(Debug, Clone, PartialEq)]
+struct DatasourceStats {
+    row_count: Option<i64>,
+    size_bytes: Option<i64>,
+    segment_count: i64,
+    /// Start of the earliest and end of the latest segment interval
+    interval_start: Option<String>,
+    interval_end: Option<String>,
+}
+
+impl DatasourceStats {
+    /// Layout of the datasource: always partitioned by time on `__time`
+    fn engine_metadata(&self) -> EngineMetadata {
+        EngineMetadata {
+            engine: "druid".to_string(),
+            partition_method: Some("TIME".to_string()),
+            partition_keys: vec!["__time".to_string()],
+            partition_count: u64::try_from(self.segment_count).ok(),
+            range_start: self.interval_start.clone(),
+            range_end: self.interval_end.clone(),
+            ..Default::default()
+        }
+    }
+}
 
 impl DruidAdapter {
     pub fn new(connection_url: &str) -> Result<Self, AppError> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        Self::with_client(connection_url, client)
+    }
+
+    /// Adapter sending its requests through a shared HTTP client
+    pub fn with_client(connection_url: &str, client: Client) -> Result<Self, AppError> {
+        Ok(Self {
+            base_url: Self::base_url(connection_url)?,
+            client,
+        })
+    }
+
+    /// Base URL of the Druid router or broker of a connection URL
+    fn base_url(connection_url: &str) -> Result<String, AppError> {
         // Validate Druid URL format
         let url = Url::parse(connection_url)
             .map_err(|e| AppError::Validation(format!("Invalid Druid URL: {}", e)))?;
 
         // Convert druid:// scheme to http:// for REST API
-        let base_url = if url.scheme() == "druid" {
+        if url.scheme() == "druid" {
             // druid://host:port → http://host:port
-            format!("http://{}:{}",
+            Ok(format!("http://{}:{}",
                 url.host_str().unwrap_or("localhost"),
-                url.port().unwrap_or(8888))
+                url.port().unwrap_or(8888)))
         } else if url.scheme() == "http" || url.scheme() == "https" {
             // Use as-is, but remove path
-            format!("{}://{}{}",
+            Ok(format!("{}://{}{}",
                 url.scheme(),
                 url.host_str().unwrap_or("localhost"),
-                url.port().map(|p| format!(":{}", p)).unwrap_or_default())
+                url.port().map(|p| format!(":{}", p)).unwrap_or_default()))
         } else {
-            return Err(AppError::Validation(
+            Err(AppError::Validation(
                 "URL must use druid://, http://, or https:// scheme for Druid".to_string()
-            ));
-        };
-
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
-
-        Ok(Self {
-            base_url,
-            client,
-        })
+            ))
+        }
     }
 
     /// Execute SQL query via Druid SQL API
     async fn execute_sql(&self, sql: &str, timeout_secs: u64) -> Result<DruidSqlResponse, AppError> {
+        self.execute_sql_with_params(sql, &[], timeout_secs).await
+    }
+
+    /// Execute SQL with values for its `?` placeholders (Druid dynamic parameters)
+    async fn execute_sql_with_params(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+    ) -> Result<DruidSqlResponse, AppError> {
         let sql_endpoint = format!("{}/druid/v2/sql", self.base_url);
 
         let request = DruidSqlRequest {
@@ -84,6 +151,7 @@ impl DruidAdapter {
                 "sqlTimeZone": "UTC",
                 "useCache": true,
             })),
+            parameters: params.iter().map(DruidSqlParameter::from_json).collect(),
         };
 
         let response = tokio::time::timeout(
@@ -113,7 +181,39 @@ impl DruidAdapter {
     }
 
     /// Get list of datasources (equivalent to tables in Druid)
+    ///
+    /// Uses `INFORMATION_SCHEMA` through the SQL endpoint, which is always
+    /// routed wherever queries work (e.g. a broker or router URL). Falls back
+    /// to the coordinator API, which may not be reachable from the broker.
     async fn get_datasources(&self) -> Result<Vec<String>, AppError> {
+        match self.get_datasources_via_sql().await {
+            Ok(datasources) => Ok(datasources),
+            Err(e) => {
+                tracing::warn!("Druid INFORMATION_SCHEMA lookup failed, falling back to coordinator API: {}", e);
+                self.get_datasources_from_coordinator().await
+            }
+        }
+    }
+
+    /// List datasources from `INFORMATION_SCHEMA.TABLES`
+    async fn get_datasources_via_sql(&self) -> Result<Vec<String>, AppError> {
+        let response = self
+            .execute_sql(
+                "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES \
+                 WHERE TABLE_SCHEMA = 'druid' AND TABLE_TYPE = 'TABLE' ORDER BY TABLE_NAME",
+                30,
+            )
+            .await?;
+
+        Ok(response
+            .rows
+            .iter()
+            .filter_map(|row| row.first().and_then(|v| v.as_str()).map(|s| s.to_string()))
+            .collect())
+    }
+
+    /// List datasources from the coordinator REST API (legacy fallback)
+    async fn get_datasources_from_coordinator(&self) -> Result<Vec<String>, AppError> {
         let datasources_endpoint = format!("{}/druid/coordinator/v1/datasources", self.base_url);
 
         let response = self.client
@@ -137,12 +237,86 @@ impl DruidAdapter {
         Ok(datasources)
     }
 
+    /// Get row counts, sizes and time ranges per datasource from `sys.segments`
+    ///
+    /// Only published, non-overshadowed segments are counted so replaced
+    /// segments are not double counted. Returns an empty map when system
+    /// tables are unavailable (they can be disabled on the broker).
+    async fn get_datasource_stats(&self) -> HashMap<String, DatasourceStats> {
+        let sql = r#"SELECT "datasource", SUM("num_rows"), SUM("size"), COUNT(*), MIN("start"), MAX("end") FROM sys.segments WHERE is_published = 1 AND is_overshadowed = 0 GROUP BY "datasource""#;
+
+        match self.execute_sql(sql, 30).await {
+            Ok(response) => Self::parse_datasource_stats(&response.rows),
+            Err(e) => {
+                tracing::warn!("Failed to read Druid sys.segments, row counts unavailable: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    fn parse_datasource_stats(rows: &[Vec<Value>]) -> HashMap<String, DatasourceStats> {
+        rows.iter()
+            .filter_map(|row| {
+                let datasource = row.first()?.as_str()?.to_string();
+                Some((
+                    datasource,
+                    DatasourceStats {
+                        row_count: row.get(1).and_then(Value::as_i64),
+                        size_bytes: row.get(2).and_then(Value::as_i64),
+                        segment_count: row.get(3).and_then(Value::as_i64).unwrap_or(0),
+                        interval_start: row.get(4).and_then(Value::as_str).map(str::to_string),
+                        interval_end: row.get(5).and_then(Value::as_str).map(str::to_string),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Get columns of all datasources in a single `INFORMATION_SCHEMA` query
+    async fn get_all_columns(&self) -> Result<HashMap<String, Vec<Column>>, AppError> {
+        let response = self
+            .execute_sql(
+                "SELECT TABLE_NAME, COLUMN_NAME, DATA_TYPE, IS_NULLABLE FROM INFORMATION_SCHEMA.COLUMNS \
+                 WHERE TABLE_SCHEMA = 'druid' ORDER BY TABLE_NAME, ORDINAL_POSITION",
+                30,
+            )
+            .await?;
+
+        Ok(Self::group_columns(&response.rows))
+    }
+
+    fn group_columns(rows: &[Vec<Value>]) -> HashMap<String, Vec<Column>> {
+        let mut columns: HashMap<String, Vec<Column>> = HashMap::new();
+        for row in rows {
+            let (Some(table), Some(name)) = (
+                row.first().and_then(Value::as_str),
+                row.get(1).and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+
+            columns.entry(table.to_string()).or_default().push(Column {
+                name: name.to_string(),
+                data_type: row.get(2).and_then(Value::as_str).unwrap_or("STRING").to_string(),
+                is_nullable: row.get(3).and_then(Value::as_str).map(|v| v != "NO").unwrap_or(true),
+                is_primary_key: false, // Druid doesn't have primary keys
+                is_foreign_key: false, // Druid doesn't have foreign keys
+                default_value: None,
+                max_length: None,
+                description: None,
+                distinct_count: None,
+                tags: Vec::new(),
+            });
+        }
+        columns
+    }
+
     /// Get schema for a specific datasource
     async fn get_datasource_schema(&self, datasource: &str) -> Result<Vec<Column>, AppError> {
         // Use INFORMATION_SCHEMA to get column information
         let sql = format!(
             "SELECT COLUMN_NAME, DATA_TYPE FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_NAME = '{}'",
-            datasource
+            datasource.replace('\'', "''")
         );
 
         let response = self.execute_sql(&sql, 30).await?;
@@ -158,6 +332,8 @@ impl DruidAdapter {
                     default_value: None,
                     max_length: None,
                     description: None,
+                    distinct_count: None,
+                    tags: Vec::new(),
                 })
             } else {
                 None
@@ -187,24 +363,46 @@ impl DatabaseAdapter for DruidAdapter {
         db_connection.id = connection_id.clone();
         db_connection.mark_connected();
 
-        // Get metadata for each datasource
+        // Columns for all datasources in one query; per-datasource lookups as fallback
+        let mut all_columns = match self.get_all_columns().await {
+            Ok(columns) => columns,
+            Err(e) => {
+                tracing::warn!("Failed to load Druid columns in bulk: {}", e);
+                HashMap::new()
+            }
+        };
+        let stats = self.get_datasource_stats().await;
+
         let mut tables = Vec::new();
         for datasource in &datasources {
-            match self.get_datasource_schema(datasource).await {
-                Ok(columns) => {
-                    tables.push(Table {
-                        name: datasource.clone(),
-                        schema: Some("druid".to_string()), // Druid doesn't have traditional schemas
-                        columns,
-                        row_count: None,
-                        description: Some(format!("Druid datasource: {}", datasource)),
-                    });
-                }
-                Err(e) => {
-                    eprintln!("Warning: Failed to get schema for datasource {}: {}", datasource, e);
-                    // Continue with other datasources
-                }
-            }
+            let columns = match all_columns.remove(datasource) {
+                Some(columns) => columns,
+                None => match self.get_datasource_schema(datasource).await {
+                    Ok(columns) => columns,
+                    Err(e) => {
+                        tracing::warn!("Failed to get schema for datasource {}: {}", datasource, e);
+                        // Continue with other datasources
+                        continue;
+                    }
+                },
+            };
+
+            let datasource_stats = stats.get(datasource);
+            let description = match datasource_stats {
+                Some(s) => format!("Druid datasource: {} ({} segments)", datasource, s.segment_count),
+                None => format!("Druid datasource: {}", datasource),
+            };
+
+            tables.push(Table {
+                name: datasource.clone(),
+                schema: Some("druid".to_string()), // Druid doesn't have traditional schemas
+                columns,
+                row_count: datasource_stats.and_then(|s| s.row_count),
+                size_bytes: datasource_stats.and_then(|s| s.size_bytes),
+                description: Some(description),
+                tags: Vec::new(),
+                engine_metadata: datasource_stats.map(DatasourceStats::engine_metadata),
+            });
         }
 
         let metadata = DatabaseMetadata::new(
@@ -217,37 +415,35 @@ impl DatabaseAdapter for DruidAdapter {
         Ok((db_connection, metadata))
     }
 
-    async fn execute_query(
+    async fn stream_query(
         &self,
         sql: &str,
+        params: &[Value],
         timeout_secs: u64,
-    ) -> Result<QueryResult, AppError> {
-        let start_time = Instant::now();
-
-        let druid_response = self.execute_sql(sql, timeout_secs).await?;
-
-        // Convert Druid response to standard QueryResult format
-        let mut json_rows = Vec::new();
-
-        for row_values in druid_response.rows {
-            let mut row_obj = serde_json::Map::new();
-
-            for (idx, column) in druid_response.columns.iter().enumerate() {
-                let value = row_values.get(idx).cloned().unwrap_or(Value::Null);
-                row_obj.insert(column.name.clone(), value);
-            }
-
-            json_rows.push(Value::Object(row_obj));
-        }
-
-        let row_count = json_rows.len();
-        let execution_time_ms = start_time.elapsed().as_millis() as u64;
-
-        Ok(QueryResult {
-            rows: json_rows,
-            row_count,
-            execution_time_ms,
-        })
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError> {
+        // The SQL API answers with one document; its rows become objects batch by batch
+        let DruidSqlResponse { columns, rows } = self.execute_sql_with_params(sql, params, timeout_secs).await?;
+        let names: Vec<String> = columns.into_iter().map(|column| column.name).collect();
+        let batch_size = batch_size.max(1);
+        let mut rows = rows.into_iter();
+
+        let batches = std::iter::from_fn(move || {
+            let batch: Vec<Value> = rows
+                .by_ref()
+                .take(batch_size)
+                .map(|row_values| {
+                    let row_obj = names
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, name)| (name.clone(), row_values.get(idx).cloned().unwrap_or(Value::Null)))
+                        .collect();
+                    Value::Object(row_obj)
+                })
+                .collect();
+            (!batch.is_empty()).then_some(Ok(batch))
+        });
+        Ok(stream::iter(batches).boxed())
     }
 
     fn database_type(&self) -> &str {
@@ -300,6 +496,13 @@ impl DatabaseAdapter for DruidAdapter {
         Ok((schema, vec![batch]))
     }
 
+    async fn explain(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<QueryPlan, AppError> {
+        let result = self
+            .execute_query_with_params(&format!("EXPLAIN PLAN FOR {}", sql), params, timeout_secs)
+            .await?;
+        druid_plan(&result.rows)
+    }
+
     async fn test_connection(&self) -> Result<(), AppError> {
         // Test connection by getting status
         let status_endpoint = format!("{}/status", self.base_url);
@@ -437,3 +640,54 @@ impl DruidAdapter {
             .map_err(|e| AppError::Database(format!("Failed to create RecordBatch: {}", e)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sys_segments_and_columns() {
+        let stats = DruidAdapter::parse_datasource_stats(&[
+            vec![
+                json!("wikipedia"),
+                json!(24433),
+                json!(6019536),
+                json!(3),
+                json!("2016-06-27T00:00:00.000Z"),
+                json!("2016-06-30T00:00:00.000Z"),
+            ],
+            vec![json!(null), json!(1), json!(1), json!(1)],
+        ]);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            stats["wikipedia"],
+            DatasourceStats {
+                row_count: Some(24433),
+                size_bytes: Some(6019536),
+                segment_count: 3,
+                interval_start: Some("2016-06-27T00:00:00.000Z".to_string()),
+                interval_end: Some("2016-06-30T00:00:00.000Z".to_string()),
+            }
+        );
+        let engine = stats["wikipedia"].engine_metadata();
+        assert_eq!(engine.partition_keys, vec!["__time"]);
+        assert_eq!(engine.partition_count, Some(3));
+
+        let columns = DruidAdapter::group_columns(&[
+            vec![json!("wikipedia"), json!("__time"), json!("TIMESTAMP"), json!("NO")],
+            vec![json!("wikipedia"), json!("page"), json!("VARCHAR"), json!("YES")],
+        ]);
+        assert_eq!(columns["wikipedia"].len(), 2);
+        assert!(!columns["wikipedia"][0].is_nullable);
+        assert_eq!(columns["wikipedia"][1].data_type, "VARCHAR");
+    }
+
+    #[test]
+    fn test_sql_parameters_are_typed() {
+        let types: Vec<&str> = [json!(true), json!(3), json!(2.5), json!("x"), Value::Null]
+            .iter()
+            .map(|value| DruidSqlParameter::from_json(value).sql_type)
+            .collect();
+        assert_eq!(types, ["BOOLEAN", "BIGINT", "DOUBLE", "VARCHAR", "VARCHAR"]);
+    }
+}
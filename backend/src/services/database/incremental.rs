@@ -0,0 +1,248 @@
+// Incremental metadata retrieval
+// Reading columns table by table dominates a metadata refresh on large
+// warehouses. An incremental refresh lists all tables and views in one query
+// first, with whatever the database reports cheaply about their definitions
+// (a change timestamp, the column count, a column signature), and reads the
+// columns only of relations that are new or may have changed. The others keep
+// the columns of the previous metadata version.
+use crate::models::{Column, DatabaseMetadata, EngineMetadata, Table, View};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Change timestamps within this margin of the previous retrieval count as
+/// changes, covering clock differences between the database and this server
+const CLOCK_SKEW_MARGIN_SECS: i64 = 300;
+
+/// Relations to read above which an incremental refresh reads all columns in
+/// one query rather than one query per relation
+pub(crate) const BULK_COLUMNS_THRESHOLD: usize = 20;
+
+/// A table or view as listed before its columns are read
+#[derive(Debug, Clone)]
+pub(crate) struct RelationListing {
+    pub schema: String,
+    pub name: String,
+    pub is_view: bool,
+    pub row_count: Option<i64>,
+    /// When the definition last changed, where the database reports it
+    pub changed_at: Option<DateTime<Utc>>,
+    pub column_count: Option<usize>,
+    /// Columns as listed by [`column_signature`], where the database can
+    /// aggregate them in the listing query
+    pub column_signature: Option<String>,
+    /// Partitioning and distribution, where the engine reports them
+    pub engine_metadata: Option<EngineMetadata>,
+}
+
+/// Signature of a relation's columns: `name type nullable` per column, in
+/// order, separated by `,`
+///
+/// Adapters build the same string in SQL, so a listing can be compared with
+/// the columns of the previous version without reading them again.
+pub(crate) fn column_signature(columns: &[Column]) -> String {
+    columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{} {} {}",
+                column.name,
+                column.data_type,
+                if column.is_nullable { "YES" } else { "NO" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Metadata assembled from a listing, reusing the columns of `previous`
+pub(crate) struct IncrementalMetadata<'a> {
+    previous: HashMap<(bool, &'a str, &'a str), &'a [Column]>,
+    retrieved_at: DateTime<Utc>,
+    tables: Vec<Table>,
+    views: Vec<View>,
+    /// Relations whose columns were read again
+    pub columns_read: usize,
+}
+
+impl<'a> IncrementalMetadata<'a> {
+    pub fn new(previous: &'a DatabaseMetadata) -> Self {
+        let tables = previous
+            .tables
+            .iter()
+            .map(|t| ((false, t.schema.as_deref().unwrap_or(""), t.name.as_str()), t.columns.as_slice()));
+        let views = previous
+            .views
+            .iter()
+            .map(|v| ((true, v.schema.as_deref().unwrap_or(""), v.name.as_str()), v.columns.as_slice()));
+        Self {
+            previous: tables.chain(views).collect(),
+            retrieved_at: previous.retrieved_at,
+            tables: Vec::new(),
+            views: Vec::new(),
+            columns_read: 0,
+        }
+    }
+
+    /// Previous columns of a listed relation, if they are still current
+    ///
+    /// A relation without a change timestamp or column signature is read
+    /// again, since nothing tells it didn't change.
+    pub fn reusable_columns(&self, listing: &RelationListing) -> Option<Vec<Column>> {
+        self.current_columns(listing).map(<[Column]>::to_vec)
+    }
+
+    /// Whether the columns of more than [`BULK_COLUMNS_THRESHOLD`] listed
+    /// relations must be read
+    pub fn needs_bulk_read(&self, listings: &[RelationListing]) -> bool {
+        listings
+            .iter()
+            .filter(|listing| self.current_columns(listing).is_none())
+            .count()
+            > BULK_COLUMNS_THRESHOLD
+    }
+
+    fn current_columns(&self, listing: &RelationListing) -> Option<&'a [Column]> {
+        let columns = *self
+            .previous
+            .get(&(listing.is_view, listing.schema.as_str(), listing.name.as_str()))?;
+        if listing.column_count.is_some_and(|count| count != columns.len()) {
+            return None;
+        }
+        let unchanged = match (&listing.column_signature, listing.changed_at) {
+            (Some(signature), _) => *signature == column_signature(columns),
+            (None, Some(changed_at)) => {
+                changed_at < self.retrieved_at - Duration::seconds(CLOCK_SKEW_MARGIN_SECS)
+            }
+            (None, None) => false,
+        };
+        unchanged.then_some(columns)
+    }
+
+    /// Add a listed relation with its columns
+    pub fn push(&mut self, listing: RelationListing, columns: Vec<Column>) {
+        if listing.is_view {
+            self.views.push(View {
+                name: listing.name,
+                schema: Some(listing.schema),
+                columns,
+                definition: None,
+                description: None,
+                tags: Vec::new(),
+            });
+        } else {
+            self.tables.push(Table {
+                name: listing.name,
+                schema: Some(listing.schema),
+                columns,
+                row_count: listing.row_count,
+                size_bytes: None,
+                description: None,
+                tags: Vec::new(),
+                engine_metadata: listing.engine_metadata,
+            });
+        }
+    }
+
+    pub fn finish(self, connection_id: &str, schemas: Vec<String>) -> DatabaseMetadata {
+        tracing::info!(
+            connection_id = %connection_id,
+            "Incremental metadata retrieval read the columns of {} of {} tables and views",
+            self.columns_read,
+            self.tables.len() + self.views.len()
+        );
+        DatabaseMetadata::new(connection_id.to_string(), self.tables, self.views, schemas)
+    }
+}
+
+/// Timestamp of seconds since the epoch, as returned by `UNIX_TIMESTAMP`
+pub(crate) fn from_epoch_secs(secs: Option<i64>) -> Option<DateTime<Utc>> {
+    secs.filter(|secs| *secs > 0)
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn listing(name: &str) -> RelationListing {
+        RelationListing {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            is_view: false,
+            row_count: Some(10),
+            changed_at: None,
+            column_count: None,
+            column_signature: None,
+            engine_metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_reusable_columns() {
+        let mut previous = DatabaseMetadata::new(
+            "c".to_string(),
+            vec![Table {
+                name: "orders".to_string(),
+                schema: Some("public".to_string()),
+                columns: vec![column("id", "integer"), column("total", "numeric")],
+                row_count: None,
+                size_bytes: None,
+                description: None,
+                tags: Vec::new(),
+                engine_metadata: None,
+            }],
+            vec![],
+            vec!["public".to_string()],
+        );
+        previous.retrieved_at = Utc::now() - Duration::hours(1);
+        let incremental = IncrementalMetadata::new(&previous);
+
+        // New tables and tables the listing says nothing about are read
+        assert!(incremental.reusable_columns(&listing("customers")).is_none());
+        assert!(incremental.reusable_columns(&listing("orders")).is_none());
+        let listings: Vec<RelationListing> = (0..=BULK_COLUMNS_THRESHOLD).map(|i| listing(&format!("t{}", i))).collect();
+        assert!(incremental.needs_bulk_read(&listings));
+        assert!(!incremental.needs_bulk_read(&listings[1..]));
+
+        let signed = |signature: &str| RelationListing {
+            column_signature: Some(signature.to_string()),
+            ..listing("orders")
+        };
+        assert_eq!(
+            incremental.reusable_columns(&signed("id integer YES,total numeric YES")).map(|c| c.len()),
+            Some(2)
+        );
+        assert!(incremental.reusable_columns(&signed("id integer YES,total text YES")).is_none());
+
+        let stamped = |changed_at: DateTime<Utc>, column_count: usize| RelationListing {
+            changed_at: Some(changed_at),
+            column_count: Some(column_count),
+            ..listing("orders")
+        };
+        assert!(incremental.reusable_columns(&stamped(Utc::now() - Duration::days(1), 2)).is_some());
+        assert!(incremental.reusable_columns(&stamped(Utc::now() - Duration::days(1), 3)).is_none());
+        assert!(incremental.reusable_columns(&stamped(Utc::now(), 2)).is_none());
+
+        // A view of the same name is a different relation
+        let view = RelationListing {
+            is_view: true,
+            ..signed("id integer YES,total numeric YES")
+        };
+        assert!(incremental.reusable_columns(&view).is_none());
+    }
+}
@@ -4,12 +4,16 @@ pub mod postgresql;
 pub mod mysql;
 pub mod doris;
 pub mod druid;
+pub mod incremental;
+pub mod plan;
+pub mod snapshot;
 
-pub use adapter::DatabaseAdapter;
+pub use adapter::{collect_rows, DatabaseAdapter, RowBatchStream, Transaction, WriteAccess, STREAM_BATCH_ROWS};
 pub use postgresql::PostgreSQLAdapter;
 pub use mysql::MySQLAdapter;
 pub use doris::DorisAdapter;
 pub use druid::DruidAdapter;
+pub use snapshot::ParquetSnapshotAdapter;
 
 use crate::api::middleware::AppError;
 use crate::services::ConnectionPoolManager;
@@ -46,21 +50,43 @@ impl DatabaseType {
 }
 
 /// Factory function to create appropriate database adapter
-/// For PostgreSQL, uses connection pooling for optimal resource management
+/// Adapters share the manager's pool (Druid: HTTP client) of their connection URL
+/// and read-only flag; PostgreSQL and MySQL pools also cache prepared statements
 pub async fn create_adapter(
     db_type: DatabaseType,
     connection_url: &str,
+    read_only: bool,
     pool_manager: Arc<ConnectionPoolManager>,
 ) -> Result<Box<dyn DatabaseAdapter>, AppError> {
     match db_type {
         DatabaseType::PostgreSQL => {
             // Get or create connection pool for this database
-            let pool = pool_manager.get_or_create_pool(connection_url).await?;
-            Ok(Box::new(PostgreSQLAdapter::new(pool, connection_url)?))
+            let pool = pool_manager.get_or_create_pool(connection_url, read_only).await?;
+            let statement_cache = pool_manager.statement_cache(connection_url, read_only).await;
+            let pool_metrics = pool_manager.pool_metrics(connection_url, read_only).await;
+            Ok(Box::new(
+                PostgreSQLAdapter::new(pool, connection_url)?
+                    .with_statement_cache(statement_cache)
+                    .with_pool_metrics(pool_metrics),
+            ))
+        },
+        DatabaseType::MySQL => {
+            MySQLAdapter::validate_url(connection_url)?;
+            let pool = pool_manager.get_or_create_mysql_pool(connection_url, read_only).await?;
+            let statement_cache = pool_manager.statement_cache(connection_url, read_only).await;
+            let pool_metrics = pool_manager.pool_metrics(connection_url, read_only).await;
+            Ok(Box::new(MySQLAdapter::with_pool(pool, connection_url, statement_cache).with_pool_metrics(pool_metrics)))
+        },
+        DatabaseType::Doris => {
+            let mysql_url = DorisAdapter::mysql_url(connection_url)?;
+            let pool = pool_manager.get_or_create_doris_pool(connection_url, read_only, &mysql_url).await?;
+            let pool_metrics = pool_manager.pool_metrics(connection_url, read_only).await;
+            Ok(Box::new(DorisAdapter::with_pool(pool, connection_url).with_pool_metrics(pool_metrics)))
+        },
+        DatabaseType::Druid => {
+            let client = pool_manager.get_or_create_http_client(connection_url, read_only).await?;
+            Ok(Box::new(DruidAdapter::with_client(connection_url, client)?))
         },
-        DatabaseType::MySQL => Ok(Box::new(MySQLAdapter::new(connection_url)?)),
-        DatabaseType::Doris => Ok(Box::new(DorisAdapter::new(connection_url)?)),
-        DatabaseType::Druid => Ok(Box::new(DruidAdapter::new(connection_url)?)),
     }
 }
 
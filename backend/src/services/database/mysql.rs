@@ -1,26 +1,30 @@
 // MySQL adapter using connection pooling for optimal resource management
-use crate::models::{DatabaseConnection, DatabaseMetadata, Table, View, Column};
+use crate::models::{DatabaseConnection, DatabaseMetadata, QueryPlan, Table, View, Column};
 use crate::api::middleware::AppError;
-use crate::services::database::adapter::{DatabaseAdapter, QueryResult};
-use mysql_async::{Pool, OptsBuilder, Conn, Row, Value as MySqlValue, prelude::*};
+use crate::services::database::incremental::{from_epoch_secs, IncrementalMetadata, RelationListing};
+use crate::services::database::adapter::{
+    find_write_grant, quote_identifier, spawn_row_stream, DatabaseAdapter, RowBatchSender, RowBatchStream, Transaction, WriteAccess,
+};
+use crate::services::database::plan::mysql_plan;
+use mysql_async::{Pool, OptsBuilder, Conn, Params, Row, Value as MySqlValue, prelude::*};
+use crate::services::connection_pool::PoolMetrics;
+use crate::services::statement_cache::StatementCache;
+use crate::validation::PlaceholderStyle;
 use url::Url;
 use serde_json::{json, Value};
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct MySQLAdapter {
     pool: Pool,
     connection_url: String,
+    statement_cache: Option<Arc<StatementCache>>,
+    pool_metrics: Arc<PoolMetrics>,
 }
 
 impl MySQLAdapter {
     pub fn new(connection_url: &str) -> Result<Self, AppError> {
-        // Validate MySQL URL format
-        let url = Url::parse(connection_url)
-            .map_err(|e| AppError::Validation(format!("Invalid MySQL URL: {}", e)))?;
-
-        if url.scheme() != "mysql" && url.scheme() != "mariadb" {
-            return Err(AppError::Validation("URL must use mysql:// or mariadb:// scheme".to_string()));
-        }
+        Self::validate_url(connection_url)?;
 
         // Build MySQL connection options from URL
         let opts = OptsBuilder::from_opts(connection_url);
@@ -29,15 +33,213 @@ impl MySQLAdapter {
         Ok(Self {
             pool,
             connection_url: connection_url.to_string(),
+            statement_cache: None,
+            pool_metrics: Arc::new(PoolMetrics::default()),
         })
     }
 
+    /// Adapter on a shared pool whose connections cache prepared statements
+    ///
+    /// `statement_cache` must have the capacity the pool's `stmt_cache_size` was set to.
+    pub fn with_pool(pool: Pool, connection_url: &str, statement_cache: Arc<StatementCache>) -> Self {
+        Self {
+            pool,
+            connection_url: connection_url.to_string(),
+            statement_cache: Some(statement_cache),
+            pool_metrics: Arc::new(PoolMetrics::default()),
+        }
+    }
+
+    /// Count checkouts in the pool's metrics, which also bound the wait for a connection
+    pub fn with_pool_metrics(mut self, pool_metrics: Arc<PoolMetrics>) -> Self {
+        self.pool_metrics = pool_metrics;
+        self
+    }
+
+    /// Validate MySQL URL format
+    pub fn validate_url(connection_url: &str) -> Result<(), AppError> {
+        let url = Url::parse(connection_url)
+            .map_err(|e| AppError::Validation(format!("Invalid MySQL URL: {}", e)))?;
+
+        if url.scheme() != "mysql" && url.scheme() != "mariadb" {
+            return Err(AppError::Validation("URL must use mysql:// or mariadb:// scheme".to_string()));
+        }
+        Ok(())
+    }
+
     /// Get a connection from the pool
     async fn get_conn(&self) -> Result<Conn, AppError> {
-        self.pool
-            .get_conn()
+        self.pool_metrics
+            .acquire(async {
+                self.pool
+                    .get_conn()
+                    .await
+                    .map_err(|e| AppError::Connection(format!("Failed to get MySQL connection from pool: {}", e)))
+            })
             .await
-            .map_err(|e| AppError::Connection(format!("Failed to get MySQL connection from pool: {}", e)))
+    }
+}
+
+/// Kills the statement on the server when a query future is dropped before it
+/// finishes (timeout, cancelled query job, client disconnect)
+struct KillOnDrop {
+    pool: Pool,
+    connection_id: Option<u32>,
+}
+
+impl KillOnDrop {
+    fn disarm(mut self) {
+        self.connection_id = None;
+    }
+}
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let Some(connection_id) = self.connection_id.take() else {
+            return;
+        };
+        let pool = self.pool.clone();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let result = match pool.get_conn().await {
+                    Ok(mut conn) => conn.query_drop(format!("KILL QUERY {}", connection_id)).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Failed to kill MySQL query on connection {}: {}", connection_id, e);
+                }
+            });
+        }
+    }
+}
+
+/// Push the rows of `sql` to `sender`; false if the stream was dropped first
+///
+/// Queries with bind values, and all queries when statements are cached,
+/// run as prepared statements (binary protocol). The connection's
+/// statement cache is keyed by the SQL text.
+async fn send_rows(
+    conn: &mut Conn,
+    statement_cache: Option<&StatementCache>,
+    sql: &str,
+    params: &[Value],
+    sender: &mut RowBatchSender,
+) -> Result<bool, mysql_async::Error> {
+    if params.is_empty() && statement_cache.is_none() {
+        return drain_rows(conn.query_iter(sql).await?, sender).await;
+    }
+    let values = if params.is_empty() {
+        Params::Empty
+    } else {
+        Params::Positional(params.iter().map(MySQLAdapter::json_to_mysql_param).collect())
+    };
+    let connection_id = conn.id();
+    match conn.exec_iter(sql, values).await {
+        Ok(result) => {
+            if let Some(cache) = statement_cache {
+                cache.touch(connection_id, sql);
+            }
+            drain_rows(result, sender).await
+        }
+        // Statements the server cannot prepare (ER_UNSUPPORTED_PS) run as plain text
+        Err(mysql_async::Error::Server(e)) if e.code == 1295 && params.is_empty() => {
+            drain_rows(conn.query_iter(sql).await?, sender).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Push the rows of a started query's first result set to `sender`
+async fn drain_rows<P: Protocol>(
+    mut result: mysql_async::QueryResult<'_, 'static, P>,
+    sender: &mut RowBatchSender,
+) -> Result<bool, mysql_async::Error> {
+    while let Some(row) = result.next().await? {
+        if !sender.push(MySQLAdapter::row_to_json(row)).await {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Execute a write statement on `conn` and return the affected row count
+async fn execute_statement(
+    pool: &Pool,
+    conn: &mut Conn,
+    sql: &str,
+    params: &[Value],
+    timeout_secs: u64,
+) -> Result<u64, AppError> {
+    let kill_guard = KillOnDrop {
+        pool: pool.clone(),
+        connection_id: Some(conn.id()),
+    };
+    let statement = async {
+        if params.is_empty() {
+            conn.query_drop(sql).await?;
+        } else {
+            let values = params.iter().map(MySQLAdapter::json_to_mysql_param).collect();
+            conn.exec_drop(sql, Params::Positional(values)).await?;
+        }
+        Ok::<_, mysql_async::Error>(conn.affected_rows())
+    };
+
+    let outcome = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), statement).await;
+    if outcome.is_ok() {
+        kill_guard.disarm();
+    }
+    outcome
+        .map_err(|_| AppError::Database(format!("Statement timeout after {} seconds", timeout_secs)))?
+        .map_err(|e| AppError::Database(format!("Statement execution failed: {}", e)))
+}
+
+/// Transaction holding a pooled connection; rolled back in the background if dropped open
+struct MySqlTransaction {
+    pool: Pool,
+    conn: Option<Conn>,
+}
+
+impl MySqlTransaction {
+    async fn finish(mut self: Box<Self>, statement: &str) -> Result<(), AppError> {
+        let mut conn = self.conn.take().expect("transaction is open until finished");
+        conn.query_drop(statement).await
+            .map_err(|e| AppError::Database(format!("{} failed: {}", statement, e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl Transaction for MySqlTransaction {
+    async fn execute(&mut self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<u64, AppError> {
+        let conn = self.conn.as_mut().expect("transaction is open until finished");
+        execute_statement(&self.pool, conn, sql, params, timeout_secs).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), AppError> {
+        self.finish("COMMIT").await
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), AppError> {
+        self.finish("ROLLBACK").await
+    }
+
+    fn placeholder_style(&self) -> PlaceholderStyle {
+        PlaceholderStyle::QuestionMark
+    }
+}
+
+impl Drop for MySqlTransaction {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        // The connection goes back to the pool afterwards, so it must not stay in the transaction
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = conn.query_drop("ROLLBACK").await {
+                    tracing::warn!("Failed to roll back abandoned MySQL transaction: {}", e);
+                }
+            });
+        }
     }
 }
 
@@ -66,57 +268,95 @@ impl DatabaseAdapter for MySQLAdapter {
         Ok((db_connection, metadata))
     }
 
-    async fn execute_query(
+    async fn refresh_metadata(
         &self,
-        sql: &str,
-        timeout_secs: u64,
-    ) -> Result<QueryResult, AppError> {
-        // Get a connection from the pool
+        connection_id: String,
+        previous: &DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
         let mut conn = self.get_conn().await?;
 
-        let start_time = Instant::now();
+        let schemas = Self::get_schemas(&mut conn).await?;
+        let listings = Self::list_relations(&mut conn).await?;
+        let mut metadata = IncrementalMetadata::new(previous);
+        let mut all_columns = if metadata.needs_bulk_read(&listings) {
+            Self::get_all_columns(&mut conn).await?
+        } else {
+            HashMap::new()
+        };
+        for listing in listings {
+            let columns = match metadata.reusable_columns(&listing) {
+                Some(columns) => columns,
+                None => {
+                    metadata.columns_read += 1;
+                    match all_columns.remove(&(listing.schema.clone(), listing.name.clone())) {
+                        Some(columns) => columns,
+                        None => Self::get_table_columns(&mut conn, &listing.schema, &listing.name).await?,
+                    }
+                }
+            };
+            metadata.push(listing, columns);
+        }
+        Ok(metadata.finish(&connection_id, schemas))
+    }
 
-        // Execute query with timeout
-        let rows: Vec<Row> = tokio::time::timeout(
-            std::time::Duration::from_secs(timeout_secs),
-            conn.query(sql),
-        )
-        .await
-        .map_err(|_| AppError::Database(format!("Query timeout after {} seconds", timeout_secs)))?
-        .map_err(|e| AppError::Database(format!("Query execution failed: {}", e)))?;
+    async fn stream_query(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError> {
+        // Get a connection from the pool; it stays with the stream until the rows are read
+        let mut conn = self.get_conn().await?;
 
-        // Convert rows to JSON
-        let mut json_rows = Vec::new();
-        for row in rows {
-            let mut row_obj = serde_json::Map::new();
-            let columns = row.columns_ref();
-
-            for (idx, column) in columns.iter().enumerate() {
-                let column_name = column.name_str();
-                let value: Value = match row.get_opt::<MySqlValue, usize>(idx) {
-                    Some(Ok(mysql_val)) => Self::mysql_value_to_json(mysql_val),
-                    Some(Err(_)) => Value::Null,
-                    None => Value::Null,
-                };
-                row_obj.insert(column_name.to_string(), value);
+        let pool = self.pool.clone();
+        let statement_cache = self.statement_cache.clone().filter(|cache| cache.is_enabled());
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        Ok(spawn_row_stream(batch_size, timeout_secs, move |mut sender| async move {
+            let kill_guard = KillOnDrop {
+                pool,
+                connection_id: Some(conn.id()),
+            };
+            let outcome = send_rows(&mut conn, statement_cache.as_deref(), &sql, &params, &mut sender).await;
+            // A dropped stream leaves the guard armed, killing the rest of the query
+            if !matches!(outcome, Ok(false)) {
+                kill_guard.disarm();
             }
-            json_rows.push(Value::Object(row_obj));
-        }
+            if outcome.map_err(|e| AppError::Database(format!("Query execution failed: {}", e)))? {
+                sender.finish().await;
+            }
+            Ok(())
+        }))
+    }
 
-        let row_count = json_rows.len();
-        let execution_time_ms = start_time.elapsed().as_millis() as u64;
+    async fn execute_write(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<u64, AppError> {
+        let mut conn = self.get_conn().await?;
+        execute_statement(&self.pool, &mut conn, sql, params, timeout_secs).await
+    }
 
-        Ok(QueryResult {
-            rows: json_rows,
-            row_count,
-            execution_time_ms,
-        })
+    async fn begin_transaction(&self) -> Result<Box<dyn Transaction>, AppError> {
+        let mut conn = self.get_conn().await?;
+        conn.query_drop("START TRANSACTION").await
+            .map_err(|e| AppError::Database(format!("Failed to start transaction: {}", e)))?;
+        Ok(Box::new(MySqlTransaction {
+            pool: self.pool.clone(),
+            conn: Some(conn),
+        }))
     }
 
     fn database_type(&self) -> &str {
         "mysql"
     }
 
+    fn sql_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        Box::new(sqlparser::dialect::MySqlDialect {})
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        quote_identifier(ident, '`')
+    }
+
     fn dialect_name(&self) -> &str {
         "mysql"
     }
@@ -136,7 +376,6 @@ impl DatabaseAdapter for MySQLAdapter {
         };
         use datafusion::arrow::array::*;
         use datafusion::arrow::datatypes::{Schema, Field};
-        use std::sync::Arc;
         use std::time::Duration;
 
         // Create DataFusion session
@@ -184,6 +423,63 @@ impl DatabaseAdapter for MySQLAdapter {
         Ok((schema, vec![batch]))
     }
 
+    async fn estimate_rows(&self, sql: &str) -> Result<Option<u64>, AppError> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<Row> = conn
+            .query(format!("EXPLAIN {}", sql))
+            .await
+            .map_err(|e| AppError::Database(format!("EXPLAIN failed: {}", e)))?;
+
+        // Largest estimated row count of any plan step (after its filter estimate)
+        let estimate = rows
+            .iter()
+            .filter_map(|row| {
+                let examined = row.get::<Option<u64>, _>("rows").flatten()?;
+                let filtered = row.get::<Option<f64>, _>("filtered").flatten().unwrap_or(100.0);
+                Some((examined as f64 * filtered / 100.0).ceil() as u64)
+            })
+            .max();
+        Ok(estimate)
+    }
+
+    async fn explain(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<QueryPlan, AppError> {
+        let result = self
+            .execute_query_with_params(&format!("EXPLAIN FORMAT=JSON {}", sql), params, timeout_secs)
+            .await?;
+        let plan = result
+            .rows
+            .first()
+            .and_then(|row| row.get("EXPLAIN"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| AppError::Database("EXPLAIN returned no plan".to_string()))?;
+        let raw = serde_json::from_str(plan)
+            .map_err(|e| AppError::Database(format!("Failed to parse EXPLAIN output: {}", e)))?;
+        mysql_plan(raw)
+    }
+
+    async fn check_write_access(&self) -> Result<WriteAccess, AppError> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<Row> = conn
+            .query("SHOW GRANTS")
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to inspect MySQL grants: {}", e)))?;
+
+        // MySQL returns one GRANT statement per row
+        let grants: Vec<String> = rows
+            .into_iter()
+            .flat_map(|row| row.unwrap())
+            .filter_map(|value| match value {
+                MySqlValue::Bytes(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        Ok(match find_write_grant(&grants) {
+            Some(grant) => WriteAccess::Writable(grant),
+            None => WriteAccess::ReadOnly,
+        })
+    }
+
     async fn test_connection(&self) -> Result<(), AppError> {
         // Get a connection from the pool to test
         let _conn = self.get_conn().await?;
@@ -192,6 +488,38 @@ impl DatabaseAdapter for MySQLAdapter {
 }
 
 impl MySQLAdapter {
+    /// Convert a row to a JSON object keyed by column name
+    fn row_to_json(row: Row) -> Value {
+        let mut row_obj = serde_json::Map::new();
+        let columns = row.columns_ref();
+
+        for (idx, column) in columns.iter().enumerate() {
+            let column_name = column.name_str();
+            let value: Value = match row.get_opt::<MySqlValue, usize>(idx) {
+                Some(Ok(mysql_val)) => Self::mysql_value_to_json(mysql_val),
+                Some(Err(_)) => Value::Null,
+                None => Value::Null,
+            };
+            row_obj.insert(column_name.to_string(), value);
+        }
+        Value::Object(row_obj)
+    }
+
+    /// MySQL bind value for a JSON parameter (booleans bind as 0/1)
+    fn json_to_mysql_param(value: &Value) -> MySqlValue {
+        match value {
+            Value::Null => MySqlValue::NULL,
+            Value::Bool(b) => MySqlValue::Int(i64::from(*b)),
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => MySqlValue::Int(i),
+                (None, Some(u)) => MySqlValue::UInt(u),
+                _ => MySqlValue::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => MySqlValue::Bytes(s.clone().into_bytes()),
+            other => MySqlValue::Bytes(other.to_string().into_bytes()),
+        }
+    }
+
     /// Helper function to convert MySQL Value to JSON Value
     fn mysql_value_to_json(mysql_val: MySqlValue) -> Value {
         match mysql_val {
@@ -225,11 +553,14 @@ impl MySQLAdapter {
         // Get schemas
         let schemas = Self::get_schemas(conn).await?;
 
+        // Columns of every table and view in one query, rather than one per table
+        let mut columns = Self::get_all_columns(conn).await?;
+
         // Get tables
-        let tables = Self::get_tables(conn).await?;
+        let tables = Self::get_tables(conn, &mut columns).await?;
 
         // Get views
-        let views = Self::get_views(conn).await?;
+        let views = Self::get_views(conn, &mut columns).await?;
 
         Ok(DatabaseMetadata::new(
             connection_id.to_string(),
@@ -252,13 +583,17 @@ impl MySQLAdapter {
         Ok(rows)
     }
 
-    async fn get_tables(conn: &mut Conn) -> Result<Vec<Table>, AppError> {
-        let rows: Vec<(String, String)> = conn
+    async fn get_tables(
+        conn: &mut Conn,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<Table>, AppError> {
+        let rows: Vec<(String, String, Option<u64>)> = conn
             .query(
                 r#"
                 SELECT
                     TABLE_SCHEMA,
-                    TABLE_NAME
+                    TABLE_NAME,
+                    TABLE_ROWS
                 FROM information_schema.TABLES
                 WHERE TABLE_TYPE = 'BASE TABLE'
                   AND TABLE_SCHEMA NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')
@@ -269,21 +604,75 @@ impl MySQLAdapter {
             .map_err(|e| AppError::Database(format!("Failed to get tables: {}", e)))?;
 
         let mut tables = Vec::new();
-        for (schema, name) in rows {
-            let columns = Self::get_table_columns(conn, &schema, &name).await?;
+        // TABLE_ROWS is an estimate for InnoDB tables
+        for (schema, name, row_count) in rows {
+            let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
             tables.push(Table {
                 name,
                 schema: Some(schema),
                 columns,
-                row_count: None,
+                row_count: row_count.and_then(|rows| i64::try_from(rows).ok()),
+                size_bytes: None,
                 description: None,
+                tags: Vec::new(),
+                engine_metadata: None,
             });
         }
 
         Ok(tables)
     }
 
-    async fn get_views(conn: &mut Conn) -> Result<Vec<View>, AppError> {
+    /// Tables and views with the time their definition or data last changed
+    /// and their column count, in one query
+    ///
+    /// `CREATE_TIME` moves when `ALTER TABLE` rebuilds the table; in-place
+    /// changes that keep the column count go unnoticed until a full refresh.
+    /// Views carry no timestamps, so their columns are always read.
+    async fn list_relations(conn: &mut Conn) -> Result<Vec<RelationListing>, AppError> {
+        let rows: Vec<(String, String, String, Option<u64>, Option<i64>, Option<i64>)> = conn
+            .query(
+                r#"
+                SELECT
+                    t.TABLE_SCHEMA,
+                    t.TABLE_NAME,
+                    t.TABLE_TYPE,
+                    t.TABLE_ROWS,
+                    UNIX_TIMESTAMP(GREATEST(t.CREATE_TIME, COALESCE(t.UPDATE_TIME, t.CREATE_TIME))) AS changed_at,
+                    c.column_count
+                FROM information_schema.TABLES t
+                LEFT JOIN (
+                    SELECT TABLE_SCHEMA, TABLE_NAME, COUNT(*) AS column_count
+                    FROM information_schema.COLUMNS
+                    WHERE TABLE_SCHEMA NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')
+                    GROUP BY TABLE_SCHEMA, TABLE_NAME
+                ) c ON c.TABLE_SCHEMA = t.TABLE_SCHEMA AND c.TABLE_NAME = t.TABLE_NAME
+                WHERE t.TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+                  AND t.TABLE_SCHEMA NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')
+                ORDER BY t.TABLE_SCHEMA, t.TABLE_NAME
+                "#
+            )
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to list tables: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(schema, name, table_type, row_count, changed_at, column_count)| RelationListing {
+                schema,
+                name,
+                is_view: table_type == "VIEW",
+                row_count: row_count.and_then(|rows| i64::try_from(rows).ok()),
+                changed_at: from_epoch_secs(changed_at),
+                column_count: column_count.and_then(|count| usize::try_from(count).ok()),
+                column_signature: None,
+                engine_metadata: None,
+            })
+            .collect())
+    }
+
+    async fn get_views(
+        conn: &mut Conn,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<View>, AppError> {
         let rows: Vec<(String, String)> = conn
             .query(
                 r#"
@@ -300,46 +689,90 @@ impl MySQLAdapter {
 
         let mut views = Vec::new();
         for (schema, name) in rows {
-            let columns = Self::get_table_columns(conn, &schema, &name).await?;
+            let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
             views.push(View {
                 name,
                 schema: Some(schema),
                 columns,
                 definition: None,
                 description: None,
+                tags: Vec::new(),
             });
         }
 
         Ok(views)
     }
 
+    /// Columns of all tables and views in user schemas, keyed by schema and name
+    async fn get_all_columns(conn: &mut Conn) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        Self::query_columns(
+            conn,
+            "c.TABLE_SCHEMA NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')",
+            Params::Empty,
+        )
+        .await
+    }
+
     async fn get_table_columns(
         conn: &mut Conn,
         schema: &str,
         table_name: &str,
     ) -> Result<Vec<Column>, AppError> {
-        let query = r#"
+        let mut columns = Self::query_columns(
+            conn,
+            "c.TABLE_SCHEMA = ? AND c.TABLE_NAME = ?",
+            (schema, table_name).into(),
+        )
+        .await?;
+        Ok(columns
+            .remove(&(schema.to_string(), table_name.to_string()))
+            .unwrap_or_default())
+    }
+
+    /// Columns of the relations matching `filter` (a condition on
+    /// `information_schema.COLUMNS c`), grouped by schema and name
+    async fn query_columns(
+        conn: &mut Conn,
+        filter: &str,
+        params: Params,
+    ) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        let query = format!(
+            r#"
             SELECT
+                c.TABLE_SCHEMA,
+                c.TABLE_NAME,
                 c.COLUMN_NAME,
                 c.DATA_TYPE,
                 c.IS_NULLABLE,
                 c.COLUMN_DEFAULT,
                 CASE WHEN c.COLUMN_KEY = 'PRI' THEN 1 ELSE 0 END as is_primary_key,
                 CASE WHEN c.COLUMN_KEY = 'MUL' THEN 1 ELSE 0 END as is_foreign_key,
-                c.CHARACTER_MAXIMUM_LENGTH
+                c.CHARACTER_MAXIMUM_LENGTH,
+                s.distinct_count
             FROM information_schema.COLUMNS c
-            WHERE c.TABLE_SCHEMA = ? AND c.TABLE_NAME = ?
-            ORDER BY c.ORDINAL_POSITION
-        "#;
+            LEFT JOIN (
+                SELECT TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME, MAX(CARDINALITY) AS distinct_count
+                FROM information_schema.STATISTICS
+                WHERE SEQ_IN_INDEX = 1
+                GROUP BY TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME
+            ) s ON s.TABLE_SCHEMA = c.TABLE_SCHEMA
+                AND s.TABLE_NAME = c.TABLE_NAME
+                AND s.COLUMN_NAME = c.COLUMN_NAME
+            WHERE {}
+            ORDER BY c.TABLE_SCHEMA, c.TABLE_NAME, c.ORDINAL_POSITION
+            "#,
+            filter
+        );
 
-        let rows: Vec<(String, String, String, Option<String>, u8, u8, Option<u64>)> = conn
-            .exec(query, (schema, table_name))
+        #[allow(clippy::type_complexity)]
+        let rows: Vec<(String, String, String, String, String, Option<String>, u8, u8, Option<u64>, Option<i64>)> = conn
+            .exec(query, params)
             .await
             .map_err(|e| AppError::Database(format!("Failed to get columns: {}", e)))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|(name, data_type, is_nullable, default_value, is_pk, is_fk, max_length)| Column {
+        let mut columns: HashMap<(String, String), Vec<Column>> = HashMap::new();
+        for (schema, table, name, data_type, is_nullable, default_value, is_pk, is_fk, max_length, distinct_count) in rows {
+            columns.entry((schema, table)).or_default().push(Column {
                 name,
                 data_type,
                 is_nullable: is_nullable == "YES",
@@ -348,8 +781,12 @@ impl MySQLAdapter {
                 is_foreign_key: is_fk == 1,
                 max_length: max_length.map(|v| v as i32),
                 description: None,
-            })
-            .collect())
+                // Index cardinality estimate, only known for leading columns of an index
+                distinct_count,
+                tags: Vec::new(),
+            });
+        }
+        Ok(columns)
     }
 
     /// Convert MySQL column type to Arrow DataType
@@ -389,7 +826,6 @@ impl MySQLAdapter {
         use datafusion::arrow::array::*;
         use datafusion::arrow::datatypes::DataType;
         use datafusion::arrow::record_batch::RecordBatch;
-        use std::sync::Arc;
 
         if rows.is_empty() {
             let empty_batch = RecordBatch::new_empty(schema);
@@ -0,0 +1,259 @@
+// Normalization of native EXPLAIN output into `QueryPlan` trees
+//
+// PostgreSQL and MySQL return JSON plans, Druid returns the native queries a
+// SQL query is planned into, and Doris only prints a textual plan, which is
+// split into fragments and their nodes.
+
+use serde_json::{Map, Value};
+
+use crate::api::middleware::AppError;
+use crate::models::{PlanFormat, PlanNode, QueryPlan};
+
+fn unexpected(database: &str) -> AppError {
+    AppError::Database(format!("Unexpected {} EXPLAIN output", database))
+}
+
+/// `EXPLAIN (FORMAT JSON)` output: `[{"Plan": {...}}]`
+pub fn postgres_plan(raw: Value) -> Result<QueryPlan, AppError> {
+    let plan = raw
+        .get(0)
+        .and_then(|entry| entry.get("Plan"))
+        .and_then(Value::as_object)
+        .cloned()
+        .ok_or_else(|| unexpected("PostgreSQL"))?;
+    Ok(QueryPlan { format: PlanFormat::Json, root: postgres_node(plan), raw })
+}
+
+fn postgres_node(mut fields: Map<String, Value>) -> PlanNode {
+    let mut node = PlanNode::new(
+        fields
+            .remove("Node Type")
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_else(|| "Unknown".to_string()),
+    );
+    node.relation = fields.remove("Relation Name").and_then(|v| v.as_str().map(str::to_string));
+    node.estimated_rows = fields.remove("Plan Rows").and_then(|v| v.as_f64());
+    node.estimated_cost = fields.remove("Total Cost").and_then(|v| v.as_f64());
+    if let Some(Value::Array(plans)) = fields.remove("Plans") {
+        node.children = plans
+            .into_iter()
+            .filter_map(|plan| match plan {
+                Value::Object(child) => Some(postgres_node(child)),
+                _ => None,
+            })
+            .collect();
+    }
+    node.details = fields;
+    node
+}
+
+/// `EXPLAIN FORMAT=JSON` output: `{"query_block": {...}}`
+///
+/// Every nested object (`table`, `ordering_operation`, ...) becomes a node
+/// named after its key; arrays of objects (`nested_loop`) become a node with
+/// one child per element.
+pub fn mysql_plan(raw: Value) -> Result<QueryPlan, AppError> {
+    let block = raw
+        .get("query_block")
+        .and_then(Value::as_object)
+        .ok_or_else(|| unexpected("MySQL"))?;
+    Ok(QueryPlan { format: PlanFormat::Json, root: mysql_node("query_block", block), raw: raw.clone() })
+}
+
+fn mysql_node(operation: &str, fields: &Map<String, Value>) -> PlanNode {
+    let mut node = PlanNode::new(operation);
+    let cost = |key: &str| {
+        let value = fields.get("cost_info")?.get(key)?;
+        value.as_f64().or_else(|| value.as_str()?.parse().ok())
+    };
+    node.estimated_cost = cost("query_cost").or_else(|| cost("prefix_cost"));
+
+    for (key, value) in fields {
+        match (key.as_str(), value) {
+            ("cost_info", _) => {}
+            ("table_name", Value::String(table)) => node.relation = Some(table.clone()),
+            ("rows_produced_per_join", rows) => node.estimated_rows = rows.as_f64(),
+            ("rows_examined_per_scan", rows) if node.estimated_rows.is_none() => {
+                node.estimated_rows = rows.as_f64();
+            }
+            (_, Value::Object(child)) => node.children.push(mysql_node(key, child)),
+            (_, Value::Array(items)) if items.iter().any(Value::is_object) => {
+                let mut group = PlanNode::new(key.as_str());
+                group.children = items
+                    .iter()
+                    .filter_map(Value::as_object)
+                    .map(|item| match single_object_entry(item) {
+                        // `{"table": {...}}` elements of `nested_loop`
+                        Some((name, inner)) => mysql_node(name, inner),
+                        None => mysql_node(key, item),
+                    })
+                    .collect();
+                node.children.push(group);
+            }
+            _ => {
+                node.details.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    node
+}
+
+fn single_object_entry(map: &Map<String, Value>) -> Option<(&str, &Map<String, Value>)> {
+    match map.iter().next() {
+        Some((key, Value::Object(inner))) if map.len() == 1 => Some((key.as_str(), inner)),
+        _ => None,
+    }
+}
+
+/// Textual EXPLAIN output of Doris, one line per row
+///
+/// `PLAN FRAGMENT <n>` lines start a fragment and `<id>:<NODE>` lines a plan
+/// node; `TABLE:` and `cardinality=` lines fill in the current node, other
+/// lines are kept in its `lines` detail.
+pub fn doris_plan(lines: &[String]) -> QueryPlan {
+    let mut root = PlanNode::new("Plan");
+    for line in lines.iter().flat_map(|line| line.lines()) {
+        let text = line.trim_start_matches(|c: char| c.is_whitespace() || c == '|' || c == '-').trim_end();
+        if text.is_empty() {
+            continue;
+        }
+
+        if text.starts_with("PLAN FRAGMENT") {
+            root.children.push(PlanNode::new(text));
+            continue;
+        }
+        if root.children.is_empty() {
+            root.children.push(PlanNode::new("PLAN FRAGMENT"));
+        }
+        let fragment = root.children.last_mut().expect("a fragment was just ensured");
+
+        if let Some((id, name)) = text.split_once(':') {
+            if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && !name.starts_with(' ') {
+                let mut node = PlanNode::new(name.trim());
+                node.details.insert("id".to_string(), Value::from(id.parse::<u64>().unwrap_or(0)));
+                fragment.children.push(node);
+                continue;
+            }
+        }
+
+        let node = if fragment.children.is_empty() {
+            fragment
+        } else {
+            fragment.children.last_mut().expect("fragment has nodes")
+        };
+        if let Some(table) = text.strip_prefix("TABLE:") {
+            let table = table.trim().split(|c| c == '(' || c == ',' || c == ' ').next().unwrap_or_default();
+            node.relation = Some(table.to_string());
+        }
+        if let Some(rows) = crate::services::database::adapter::explain_estimates(text, "cardinality").first() {
+            node.estimated_rows = Some(*rows as f64);
+        }
+        if let Value::Array(details) = node.details.entry("lines").or_insert_with(|| Value::Array(Vec::new())) {
+            details.push(Value::from(text));
+        }
+    }
+
+    QueryPlan {
+        format: PlanFormat::Text,
+        root,
+        raw: Value::from(lines.join("\n")),
+    }
+}
+
+/// `EXPLAIN PLAN FOR` output of Druid: a `PLAN` column holding the native
+/// queries as JSON (plain text on old versions)
+pub fn druid_plan(rows: &[Value]) -> Result<QueryPlan, AppError> {
+    let plan = rows
+        .first()
+        .and_then(|row| row.get("PLAN"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| unexpected("Druid"))?;
+
+    let mut root = PlanNode::new("Plan");
+    let Ok(Value::Array(queries)) = serde_json::from_str::<Value>(plan) else {
+        root.details.insert("plan".to_string(), Value::from(plan));
+        return Ok(QueryPlan { format: PlanFormat::Text, root, raw: Value::Array(rows.to_vec()) });
+    };
+
+    for entry in queries {
+        let query = entry.get("query").cloned().unwrap_or(Value::Null);
+        let mut node = PlanNode::new(query.get("queryType").and_then(Value::as_str).unwrap_or("query"));
+        node.relation = match query.get("dataSource") {
+            Some(Value::String(name)) => Some(name.clone()),
+            Some(source) => source.get("name").and_then(Value::as_str).map(str::to_string),
+            None => None,
+        };
+        if let Value::Object(mut fields) = entry {
+            fields.remove("query");
+            node.details = fields;
+        }
+        node.details.insert("query".to_string(), query);
+        root.children.push(node);
+    }
+    Ok(QueryPlan { format: PlanFormat::Json, root, raw: Value::Array(rows.to_vec()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_postgres_and_mysql_plans() {
+        let raw = json!([{"Plan": {
+            "Node Type": "Hash Join", "Total Cost": 24.48, "Plan Rows": 2500, "Hash Cond": "(o.id = i.order_id)",
+            "Plans": [{"Node Type": "Seq Scan", "Relation Name": "orders", "Total Cost": 18.5, "Plan Rows": 850}]
+        }}]);
+        let plan = postgres_plan(raw).unwrap();
+        assert_eq!(plan.root.operation, "Hash Join");
+        assert_eq!(plan.root.details["Hash Cond"], "(o.id = i.order_id)");
+        assert_eq!(plan.root.children[0].relation.as_deref(), Some("orders"));
+        assert_eq!(plan.estimated_rows(), Some(2500.0));
+        assert!(postgres_plan(json!({})).is_err());
+
+        let raw = json!({"query_block": {
+            "select_id": 1,
+            "cost_info": {"query_cost": "12.50"},
+            "nested_loop": [
+                {"table": {"table_name": "o", "access_type": "ALL", "rows_examined_per_scan": 100, "rows_produced_per_join": 10}},
+                {"table": {"table_name": "i", "access_type": "ref", "rows_produced_per_join": 30}}
+            ]
+        }});
+        let plan = mysql_plan(raw).unwrap();
+        assert_eq!(plan.root.estimated_cost, Some(12.5));
+        let join = &plan.root.children[0];
+        assert_eq!(join.operation, "nested_loop");
+        assert_eq!(join.children[1].relation.as_deref(), Some("i"));
+        assert_eq!(join.children[0].estimated_rows, Some(10.0));
+        assert_eq!(join.children[0].details["access_type"], "ALL");
+    }
+
+    #[test]
+    fn test_doris_and_druid_plans() {
+        let lines: Vec<String> = [
+            "PLAN FRAGMENT 0",
+            "  OUTPUT EXPRS:",
+            "  1:VEXCHANGE",
+            "     offset: 0",
+            "PLAN FRAGMENT 1",
+            "  0:VOlapScanNode",
+            "     TABLE: sales.orders(orders), PREAGGREGATION: ON",
+            "     cardinality=120000, avgRowSize=0.0",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let plan = doris_plan(&lines);
+        assert_eq!(plan.format, PlanFormat::Text);
+        assert_eq!(plan.root.children.len(), 2);
+        let scan = &plan.root.children[1].children[0];
+        assert_eq!((scan.operation.as_str(), scan.relation.as_deref()), ("VOlapScanNode", Some("sales.orders")));
+        assert_eq!(plan.estimated_rows(), Some(120000.0));
+
+        let plan_json = json!([{"query": {"queryType": "timeseries", "dataSource": {"type": "table", "name": "wiki"}}, "signature": []}]);
+        let plan = druid_plan(&[json!({"PLAN": plan_json.to_string()})]).unwrap();
+        assert_eq!(plan.root.children[0].operation, "timeseries");
+        assert_eq!(plan.root.children[0].relation.as_deref(), Some("wiki"));
+        assert!(druid_plan(&[]).is_err());
+    }
+}
@@ -1,15 +1,29 @@
 // PostgreSQL adapter using connection pooling for optimal resource management
-use crate::models::{DatabaseConnection, DatabaseMetadata, Table, View, Column};
+use crate::models::{DatabaseConnection, DatabaseMetadata, QueryPlan, Table, View, Column};
 use crate::api::middleware::AppError;
-use crate::services::database::adapter::{DatabaseAdapter, QueryResult};
+use crate::services::database::incremental::{IncrementalMetadata, RelationListing};
+use crate::services::database::adapter::{
+    explain_estimates, spawn_row_stream, DatabaseAdapter, RowBatchSender, RowBatchStream, Transaction, WriteAccess,
+};
+use crate::services::database::plan::postgres_plan;
+use crate::services::connection_pool::PoolMetrics;
+use crate::services::statement_cache::{StatementCache, StatementLookup};
+use crate::validation::PlaceholderStyle;
+use bytes::{Bytes, BytesMut};
 use deadpool_postgres::Pool;
+use futures::{SinkExt, TryStreamExt};
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+use tokio_postgres::RowStream;
 use url::Url;
 use serde_json::{json, Value};
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct PostgreSQLAdapter {
     pool: Pool,
     connection_url: String,
+    statement_cache: Option<Arc<StatementCache>>,
+    pool_metrics: Arc<PoolMetrics>,
 }
 
 impl PostgreSQLAdapter {
@@ -25,8 +39,299 @@ impl PostgreSQLAdapter {
         Ok(Self {
             pool,
             connection_url: connection_url.to_string(),
+            statement_cache: None,
+            pool_metrics: Arc::new(PoolMetrics::default()),
         })
     }
+
+    /// Cache prepared statements on the pool's connections, within the cache's capacity
+    pub fn with_statement_cache(mut self, statement_cache: Arc<StatementCache>) -> Self {
+        self.statement_cache = Some(statement_cache);
+        self
+    }
+
+    /// Count checkouts in the pool's metrics, which also bound the wait for a client
+    pub fn with_pool_metrics(mut self, pool_metrics: Arc<PoolMetrics>) -> Self {
+        self.pool_metrics = pool_metrics;
+        self
+    }
+
+    /// Get a client from the pool
+    async fn client(&self) -> Result<deadpool_postgres::Object, AppError> {
+        self.pool_metrics
+            .acquire(async {
+                self.pool
+                    .get()
+                    .await
+                    .map_err(|e| AppError::Connection(format!("Failed to get connection from pool: {}", e)))
+            })
+            .await
+    }
+}
+
+/// Start `sql`, preparing it through the connection's statement cache if enabled
+///
+/// A connection whose cache outgrows the capacity drops its cached
+/// statements. Statements whose result type changed since they were
+/// prepared (after DDL) are prepared again.
+async fn query_row_stream(
+    client: &deadpool_postgres::Client,
+    statement_cache: Option<&StatementCache>,
+    sql: &str,
+    params: &[JsonParam<'_>],
+) -> Result<RowStream, tokio_postgres::Error> {
+    let bind_refs = || params.iter().map(|p| p as &dyn ToSql);
+    let Some(cache) = statement_cache.filter(|cache| cache.is_enabled()) else {
+        return client.query_raw(sql, bind_refs()).await;
+    };
+
+    let cached_before = client.statement_cache.size();
+    let statement = client.prepare_cached(sql).await?;
+    let cached = client.statement_cache.size();
+    if cached > cached_before {
+        let evicted = if cached > cache.capacity() {
+            client.statement_cache.clear();
+            cached
+        } else {
+            0
+        };
+        cache.record(StatementLookup::Miss { evicted });
+    } else {
+        cache.record(StatementLookup::Hit);
+    }
+
+    match client.query_raw(&statement, bind_refs()).await {
+        Err(e) if is_stale_statement(&e) => {
+            client.statement_cache.remove(sql, &[]);
+            let statement = client.prepare_cached(sql).await?;
+            client.query_raw(&statement, bind_refs()).await
+        }
+        result => result,
+    }
+}
+
+/// Push the rows of `sql` to `sender`; false if the stream was dropped first
+async fn send_rows(
+    client: &deadpool_postgres::Client,
+    statement_cache: Option<&StatementCache>,
+    sql: &str,
+    params: &[Value],
+    sender: &mut RowBatchSender,
+) -> Result<bool, tokio_postgres::Error> {
+    // The statement is prepared, so every value is sent as the type PostgreSQL inferred for it
+    let bind_values: Vec<JsonParam> = params.iter().map(JsonParam).collect();
+    let rows = query_row_stream(client, statement_cache, sql, &bind_values).await?;
+    futures::pin_mut!(rows);
+    while let Some(row) = rows.try_next().await? {
+        if !sender.push(PostgreSQLAdapter::row_to_json(&row)).await {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn query_error(e: tokio_postgres::Error) -> AppError {
+    let error_details = if let Some(db_error) = e.as_db_error() {
+        format!(
+            "Code: {}, Message: {}",
+            db_error.code().code(),
+            db_error.message()
+        )
+    } else {
+        format!("{}", e)
+    };
+    AppError::Database(format!("Query execution failed: {}", error_details))
+}
+
+/// Whether a cached statement no longer matches the tables it reads
+fn is_stale_statement(error: &tokio_postgres::Error) -> bool {
+    error.as_db_error().is_some_and(|db_error| {
+        db_error.code() == &tokio_postgres::error::SqlState::FEATURE_NOT_SUPPORTED
+            && db_error.message().contains("cached plan must not change result type")
+    })
+}
+
+/// Cancels the statement on the server when a query future is dropped before
+/// it finishes (timeout, cancelled query job, client disconnect)
+struct CancelOnDrop(Option<tokio_postgres::CancelToken>);
+
+impl CancelOnDrop {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let Some(token) = self.0.take() else {
+            return;
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = token.cancel_query(tokio_postgres::NoTls).await {
+                    tracing::warn!("Failed to cancel PostgreSQL query: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Execute a write statement on `client` and return the affected row count
+async fn execute_statement(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[Value],
+    timeout_secs: u64,
+) -> Result<u64, AppError> {
+    let bind_values: Vec<JsonParam> = params.iter().map(JsonParam).collect();
+    let bind_refs: Vec<&(dyn ToSql + Sync)> = bind_values.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+
+    // Write statements are not cached: DDL invalidates cached plans anyway
+    let cancel_guard = CancelOnDrop(Some(client.cancel_token()));
+    let outcome = tokio::time::timeout(
+        std::time::Duration::from_secs(timeout_secs),
+        client.execute(sql, &bind_refs),
+    )
+    .await;
+    if outcome.is_ok() {
+        cancel_guard.disarm();
+    }
+    outcome
+        .map_err(|_| AppError::Database(format!("Statement timeout after {} seconds", timeout_secs)))?
+        .map_err(|e| {
+            let error_details = match e.as_db_error() {
+                Some(db_error) => format!("Code: {}, Message: {}", db_error.code().code(), db_error.message()),
+                None => e.to_string(),
+            };
+            AppError::Database(format!("Statement execution failed: {}", error_details))
+        })
+}
+
+/// Rows as `COPY ... (FORMAT csv)` input: NULL is an empty unquoted field,
+/// other values are quoted text (JSON text for arrays and objects)
+fn csv_rows(columns: &[String], rows: &[Value]) -> String {
+    let mut csv = String::new();
+    for row in rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                let text = match row.get(column) {
+                    None | Some(Value::Null) => return String::new(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                };
+                format!("\"{}\"", text.replace('"', "\"\""))
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Transaction holding a pooled client; rolled back in the background if dropped open
+struct PgTransaction {
+    client: Option<deadpool_postgres::Object>,
+}
+
+impl PgTransaction {
+    async fn finish(mut self: Box<Self>, statement: &str) -> Result<(), AppError> {
+        let client = self.client.take().expect("transaction is open until finished");
+        client.batch_execute(statement).await
+            .map_err(|e| AppError::Database(format!("{} failed: {}", statement, e)))
+    }
+}
+
+#[async_trait::async_trait]
+impl Transaction for PgTransaction {
+    async fn execute(&mut self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<u64, AppError> {
+        let client = self.client.as_ref().expect("transaction is open until finished");
+        execute_statement(client, sql, params, timeout_secs).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), AppError> {
+        self.finish("COMMIT").await
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), AppError> {
+        self.finish("ROLLBACK").await
+    }
+
+    fn placeholder_style(&self) -> PlaceholderStyle {
+        PlaceholderStyle::Numbered
+    }
+}
+
+impl Drop for PgTransaction {
+    fn drop(&mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        // The client goes back to the pool afterwards, so it must not stay in the transaction
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = client.batch_execute("ROLLBACK").await {
+                    tracing::warn!("Failed to roll back abandoned PostgreSQL transaction: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// JSON bind value, encoded as the type PostgreSQL inferred for its placeholder
+///
+/// Strings are parsed for numeric, boolean and date/time parameters, so values
+/// from query strings work too. Other parameter types need a cast in the SQL
+/// (e.g. `:tags::text`).
+#[derive(Debug)]
+struct JsonParam<'a>(&'a Value);
+
+impl JsonParam<'_> {
+    fn text(&self) -> String {
+        match self.0 {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, ty: &Type) -> Result<T, Box<dyn std::error::Error + Sync + Send>> {
+        self.text()
+            .trim()
+            .parse::<T>()
+            .map_err(|_| format!("cannot bind {} to a parameter of type {}", self.0, ty).into())
+    }
+}
+
+impl ToSql for JsonParam<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        if self.0.is_null() {
+            return Ok(IsNull::Yes);
+        }
+        match *ty {
+            Type::BOOL => self.parse::<bool>(ty)?.to_sql(ty, out),
+            Type::INT2 => self.parse::<i16>(ty)?.to_sql(ty, out),
+            Type::INT4 => self.parse::<i32>(ty)?.to_sql(ty, out),
+            Type::INT8 => self.parse::<i64>(ty)?.to_sql(ty, out),
+            Type::FLOAT4 => self.parse::<f32>(ty)?.to_sql(ty, out),
+            Type::FLOAT8 => self.parse::<f64>(ty)?.to_sql(ty, out),
+            Type::NUMERIC => self.parse::<rust_decimal::Decimal>(ty)?.to_sql(ty, out),
+            Type::DATE => self.parse::<chrono::NaiveDate>(ty)?.to_sql(ty, out),
+            Type::TIMESTAMP => self.parse::<chrono::NaiveDateTime>(ty)?.to_sql(ty, out),
+            Type::TIMESTAMPTZ => self.parse::<chrono::DateTime<chrono::Utc>>(ty)?.to_sql(ty, out),
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => self.text().to_sql(ty, out),
+            _ => Err(format!(
+                "cannot bind a value to a parameter of type {}; cast the parameter, e.g. :name::text",
+                ty
+            )
+            .into()),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
 }
 
 #[async_trait::async_trait]
@@ -36,8 +341,7 @@ impl DatabaseAdapter for PostgreSQLAdapter {
         connection_id: String,
     ) -> Result<(DatabaseConnection, DatabaseMetadata), AppError> {
         // Get a connection from the pool
-        let client = self.pool.get().await
-            .map_err(|e| AppError::Connection(format!("Failed to get connection from pool: {}", e)))?;
+        let client = self.client().await?;
 
         // Create connection object
         let mut db_connection = DatabaseConnection::new(
@@ -55,96 +359,123 @@ impl DatabaseAdapter for PostgreSQLAdapter {
         Ok((db_connection, metadata))
     }
 
-    async fn execute_query(
+    async fn refresh_metadata(
+        &self,
+        connection_id: String,
+        previous: &DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
+        let client = self.client().await?;
+
+        let schemas = Self::get_schemas(&*client).await?;
+        let listings = Self::list_relations(&*client).await?;
+        let mut metadata = IncrementalMetadata::new(previous);
+        let mut all_columns = if metadata.needs_bulk_read(&listings) {
+            Self::get_all_columns(&*client).await?
+        } else {
+            HashMap::new()
+        };
+        for listing in listings {
+            let columns = match metadata.reusable_columns(&listing) {
+                Some(columns) => columns,
+                None => {
+                    metadata.columns_read += 1;
+                    match all_columns.remove(&(listing.schema.clone(), listing.name.clone())) {
+                        Some(columns) => columns,
+                        None => Self::get_table_columns(&*client, &listing.schema, &listing.name).await?,
+                    }
+                }
+            };
+            metadata.push(listing, columns);
+        }
+        Ok(metadata.finish(&connection_id, schemas))
+    }
+
+    async fn stream_query(
         &self,
         sql: &str,
+        params: &[Value],
         timeout_secs: u64,
-    ) -> Result<QueryResult, AppError> {
-        // Get a connection from the pool
-        let client = self.pool.get().await
-            .map_err(|e| AppError::Connection(format!("Failed to get connection from pool: {}", e)))?;
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError> {
+        // Get a connection from the pool; it stays with the stream until the rows are read
+        let client = self.client().await?;
 
-        let start_time = Instant::now();
+        let statement_cache = self.statement_cache.clone();
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        Ok(spawn_row_stream(batch_size, timeout_secs, move |mut sender| async move {
+            let cancel_guard = CancelOnDrop(Some(client.cancel_token()));
+            let outcome = send_rows(&client, statement_cache.as_deref(), &sql, &params, &mut sender).await;
+            // A dropped stream leaves the guard armed, cancelling the rest of the query
+            if !matches!(outcome, Ok(false)) {
+                cancel_guard.disarm();
+            }
+            if outcome.map_err(query_error)? {
+                sender.finish().await;
+            }
+            Ok(())
+        }))
+    }
 
-        let query_future = client.query(sql, &[]);
+    async fn execute_write(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<u64, AppError> {
+        let client = self.client().await?;
+        execute_statement(&client, sql, params, timeout_secs).await
+    }
 
-        let rows = tokio::time::timeout(
-            std::time::Duration::from_secs(timeout_secs),
-            query_future,
-        )
-        .await
-        .map_err(|_| AppError::Database(format!("Query timeout after {} seconds", timeout_secs)))?
-        .map_err(|e| {
-            let error_details = if let Some(db_error) = e.as_db_error() {
-                format!(
-                    "Code: {}, Message: {}",
-                    db_error.code().code(),
-                    db_error.message()
-                )
-            } else {
-                format!("{}", e)
-            };
-            AppError::Database(format!("Query execution failed: {}", error_details))
-        })?;
+    async fn begin_transaction(&self) -> Result<Box<dyn Transaction>, AppError> {
+        let client = self.client().await?;
+        client.batch_execute("BEGIN").await
+            .map_err(|e| AppError::Database(format!("Failed to start transaction: {}", e)))?;
+        Ok(Box::new(PgTransaction { client: Some(client) }))
+    }
 
-        // Convert rows to JSON
-        let mut json_rows = Vec::new();
-        for row in rows {
-            let mut row_obj = serde_json::Map::new();
-            for (idx, column) in row.columns().iter().enumerate() {
-                let column_name = column.name();
-                let value: Value = match *column.type_() {
-                    tokio_postgres::types::Type::INT2 |
-                    tokio_postgres::types::Type::INT4 |
-                    tokio_postgres::types::Type::INT8 => {
-                        row.get::<_, Option<i64>>(idx)
-                            .map(|v| json!(v))
-                            .unwrap_or(Value::Null)
-                    }
-                    tokio_postgres::types::Type::FLOAT4 |
-                    tokio_postgres::types::Type::FLOAT8 => {
-                        row.get::<_, Option<f64>>(idx)
-                            .map(|v| json!(v))
-                            .unwrap_or(Value::Null)
-                    }
-                    tokio_postgres::types::Type::BOOL => {
-                        row.get::<_, Option<bool>>(idx)
-                            .map(|v| json!(v))
-                            .unwrap_or(Value::Null)
-                    }
-                    _ => {
-                        // For all other types (TEXT, VARCHAR, TIMESTAMP, UUID, JSON, etc.)
-                        // try to get as string representation
-                        match row.try_get::<_, Option<String>>(idx) {
-                            Ok(Some(v)) => json!(v),
-                            Ok(None) => Value::Null,
-                            Err(_) => {
-                                // For types that can't be converted to string,
-                                // show the type name as placeholder
-                                json!(format!("<{}>", column.type_().name()))
-                            }
-                        }
-                    }
-                };
-                row_obj.insert(column_name.to_string(), value);
-            }
-            json_rows.push(Value::Object(row_obj));
+    /// Loads the rows with `COPY ... FROM STDIN` in CSV format, so the server
+    /// converts each value to its column's type
+    async fn bulk_insert(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: &[Value],
+        timeout_secs: u64,
+    ) -> Result<u64, AppError> {
+        if rows.is_empty() {
+            return Ok(0);
         }
+        let client = self.client().await?;
+        let quoted: Vec<String> = columns.iter().map(|column| self.quote_identifier(column)).collect();
+        let sql = format!("COPY {} ({}) FROM STDIN WITH (FORMAT csv)", table, quoted.join(", "));
 
-        let row_count = json_rows.len();
-        let execution_time_ms = start_time.elapsed().as_millis() as u64;
-
-        Ok(QueryResult {
-            rows: json_rows,
-            row_count,
-            execution_time_ms,
-        })
+        let copy = async {
+            let sink = client.copy_in::<_, Bytes>(sql.as_str()).await?;
+            let mut sink = std::pin::pin!(sink);
+            sink.send(Bytes::from(csv_rows(columns, rows))).await?;
+            sink.finish().await
+        };
+        // Dropping an unfinished COPY aborts it
+        tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), copy)
+            .await
+            .map_err(|_| AppError::Database(format!("COPY timeout after {} seconds", timeout_secs)))?
+            .map_err(|e| {
+                let error_details = match e.as_db_error() {
+                    Some(db_error) => format!("Code: {}, Message: {}", db_error.code().code(), db_error.message()),
+                    None => e.to_string(),
+                };
+                AppError::Database(format!("COPY failed: {}", error_details))
+            })
     }
 
     fn database_type(&self) -> &str {
         "postgresql"
     }
 
+    fn sql_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        Box::new(sqlparser::dialect::PostgreSqlDialect {})
+    }
+
+    fn placeholder_style(&self) -> PlaceholderStyle {
+        PlaceholderStyle::Numbered
+    }
+
     fn dialect_name(&self) -> &str {
         "postgresql"
     }
@@ -164,7 +495,6 @@ impl DatabaseAdapter for PostgreSQLAdapter {
         };
         use datafusion::arrow::array::*;
         use datafusion::arrow::datatypes::{Schema, Field};
-        use std::sync::Arc;
         use std::time::Duration;
 
         // Create DataFusion session
@@ -182,8 +512,7 @@ impl DatabaseAdapter for PostgreSQLAdapter {
             .map_err(|e| AppError::Database(format!("Failed to translate SQL: {}", e)))?;
 
         // Execute the translated query against PostgreSQL
-        let client = self.pool.get().await
-            .map_err(|e| AppError::Connection(format!("Failed to get connection from pool: {}", e)))?;
+        let client = self.client().await?;
 
         let query_future = client.query(&translated_sql, &[]);
         let rows = tokio::time::timeout(
@@ -214,6 +543,88 @@ impl DatabaseAdapter for PostgreSQLAdapter {
         Ok((schema, vec![batch]))
     }
 
+    async fn estimate_rows(&self, sql: &str) -> Result<Option<u64>, AppError> {
+        let client = self.client().await?;
+
+        let rows = client
+            .query(&format!("EXPLAIN {}", sql), &[])
+            .await
+            .map_err(|e| AppError::Database(format!("EXPLAIN failed: {}", e)))?;
+
+        // The first line describes the top plan node, i.e. the result
+        Ok(rows
+            .first()
+            .and_then(|row| row.try_get::<_, String>(0).ok())
+            .and_then(|line| explain_estimates(&line, "rows").first().copied()))
+    }
+
+    async fn explain(&self, sql: &str, params: &[Value], timeout_secs: u64) -> Result<QueryPlan, AppError> {
+        let client = self.client().await?;
+
+        let bind_values: Vec<JsonParam> = params.iter().map(JsonParam).collect();
+        let bind_refs: Vec<&(dyn ToSql + Sync)> = bind_values.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+        let explain_sql = format!("EXPLAIN (FORMAT JSON) {}", sql);
+
+        let cancel_guard = CancelOnDrop(Some(client.cancel_token()));
+        let outcome = tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_secs),
+            client.query_one(&explain_sql, &bind_refs),
+        )
+        .await;
+        if outcome.is_ok() {
+            cancel_guard.disarm();
+        }
+        let row = outcome
+            .map_err(|_| AppError::Database(format!("EXPLAIN timeout after {} seconds", timeout_secs)))?
+            .map_err(|e| AppError::Database(format!("EXPLAIN failed: {}", e)))?;
+        let raw: Value = row.try_get(0)
+            .map_err(|e| AppError::Database(format!("Failed to read EXPLAIN output: {}", e)))?;
+        postgres_plan(raw)
+    }
+
+    async fn check_write_access(&self) -> Result<WriteAccess, AppError> {
+        let client = self.client().await?;
+
+        // Standbys cannot write. default_transaction_read_only is not counted:
+        // pools of read-only connections set it, and a session may turn it off
+        let row = client
+            .query_one(
+                r#"
+                SELECT pg_catalog.pg_is_in_recovery(),
+                       (SELECT rolsuper FROM pg_catalog.pg_roles WHERE rolname = current_user),
+                       (SELECT count(*) FROM pg_catalog.pg_class c
+                        JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                        WHERE c.relkind IN ('r', 'p')
+                          AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+                          AND n.nspname NOT LIKE 'pg_toast%'
+                          AND (has_table_privilege(c.oid, 'INSERT')
+                               OR has_table_privilege(c.oid, 'UPDATE')
+                               OR has_table_privilege(c.oid, 'DELETE')
+                               OR has_table_privilege(c.oid, 'TRUNCATE')))
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to inspect privileges: {}", e)))?;
+
+        let standby: bool = row.get(0);
+        let superuser: Option<bool> = row.get(1);
+        let writable_tables: i64 = row.get(2);
+
+        Ok(if standby {
+            WriteAccess::ReadOnly
+        } else if superuser.unwrap_or(false) {
+            WriteAccess::Writable("role is a superuser".to_string())
+        } else if writable_tables > 0 {
+            WriteAccess::Writable(format!(
+                "INSERT/UPDATE/DELETE/TRUNCATE granted on {} table(s)",
+                writable_tables
+            ))
+        } else {
+            WriteAccess::ReadOnly
+        })
+    }
+
     async fn test_connection(&self) -> Result<(), AppError> {
         // Get a connection from the pool to test
         let _client = self.pool.get().await
@@ -223,6 +634,49 @@ impl DatabaseAdapter for PostgreSQLAdapter {
 }
 
 impl PostgreSQLAdapter {
+    /// Convert a row to a JSON object keyed by column name
+    fn row_to_json(row: &tokio_postgres::Row) -> Value {
+        let mut row_obj = serde_json::Map::new();
+        for (idx, column) in row.columns().iter().enumerate() {
+            let column_name = column.name();
+            let value: Value = match *column.type_() {
+                tokio_postgres::types::Type::INT2 |
+                tokio_postgres::types::Type::INT4 |
+                tokio_postgres::types::Type::INT8 => {
+                    row.get::<_, Option<i64>>(idx)
+                        .map(|v| json!(v))
+                        .unwrap_or(Value::Null)
+                }
+                tokio_postgres::types::Type::FLOAT4 |
+                tokio_postgres::types::Type::FLOAT8 => {
+                    row.get::<_, Option<f64>>(idx)
+                        .map(|v| json!(v))
+                        .unwrap_or(Value::Null)
+                }
+                tokio_postgres::types::Type::BOOL => {
+                    row.get::<_, Option<bool>>(idx)
+                        .map(|v| json!(v))
+                        .unwrap_or(Value::Null)
+                }
+                _ => {
+                    // For all other types (TEXT, VARCHAR, TIMESTAMP, UUID, JSON, etc.)
+                    // try to get as string representation
+                    match row.try_get::<_, Option<String>>(idx) {
+                        Ok(Some(v)) => json!(v),
+                        Ok(None) => Value::Null,
+                        Err(_) => {
+                            // For types that can't be converted to string,
+                            // show the type name as placeholder
+                            json!(format!("<{}>", column.type_().name()))
+                        }
+                    }
+                }
+            };
+            row_obj.insert(column_name.to_string(), value);
+        }
+        Value::Object(row_obj)
+    }
+
     /// Convert PostgreSQL type to Arrow DataType
     fn postgres_type_to_arrow(pg_type: &tokio_postgres::types::Type) -> datafusion::arrow::datatypes::DataType {
         use datafusion::arrow::datatypes::{DataType, TimeUnit};
@@ -375,11 +829,14 @@ impl PostgreSQLAdapter {
         // Get schemas
         let schemas = Self::get_schemas(client).await?;
 
+        // Columns of every table and view in one query, rather than one per table
+        let mut columns = Self::get_all_columns(client).await?;
+
         // Get tables
-        let tables = Self::get_tables(client).await?;
+        let tables = Self::get_tables(client, &mut columns).await?;
 
         // Get views
-        let views = Self::get_views(client).await?;
+        let views = Self::get_views(client, &mut columns).await?;
 
         Ok(DatabaseMetadata::new(
             connection_id.to_string(),
@@ -404,17 +861,23 @@ impl PostgreSQLAdapter {
             .collect())
     }
 
-    async fn get_tables(client: &tokio_postgres::Client) -> Result<Vec<Table>, AppError> {
+    async fn get_tables(
+        client: &tokio_postgres::Client,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<Table>, AppError> {
         let rows = client
             .query(
                 r#"
                 SELECT 
-                    table_schema,
-                    table_name,
-                    table_type
-                FROM information_schema.tables
-                WHERE table_schema NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
-                ORDER BY table_schema, table_name
+                    t.table_schema,
+                    t.table_name,
+                    t.table_type,
+                    c.reltuples::bigint AS estimated_rows
+                FROM information_schema.tables t
+                LEFT JOIN pg_catalog.pg_namespace n ON n.nspname = t.table_schema
+                LEFT JOIN pg_catalog.pg_class c ON c.relnamespace = n.oid AND c.relname = t.table_name
+                WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+                ORDER BY t.table_schema, t.table_name
                 "#,
                 &[],
             )
@@ -426,23 +889,83 @@ impl PostgreSQLAdapter {
             let schema = row.get::<_, String>(0);
             let name = row.get::<_, String>(1);
             let table_type = row.get::<_, String>(2);
+            // Planner statistics; -1 until the table was first analyzed
+            let row_count = row.get::<_, Option<i64>>(3).filter(|rows| *rows >= 0);
 
             if table_type == "BASE TABLE" {
-                let columns = Self::get_table_columns(client, &schema, &name).await?;
-            tables.push(Table {
-                name,
-                schema: Some(schema),
-                columns,
-                row_count: None,
-                description: None,
-            });
+                let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
+                tables.push(Table {
+                    name,
+                    schema: Some(schema),
+                    columns,
+                    row_count,
+                    size_bytes: None,
+                    description: None,
+                    tags: Vec::new(),
+                    engine_metadata: None,
+                });
             }
         }
 
         Ok(tables)
     }
 
-    async fn get_views(client: &tokio_postgres::Client) -> Result<Vec<View>, AppError> {
+    /// Tables and views with the signature of their columns, in one query
+    ///
+    /// PostgreSQL keeps no change timestamps of table definitions, so the
+    /// signature tells which tables changed.
+    async fn list_relations(client: &tokio_postgres::Client) -> Result<Vec<RelationListing>, AppError> {
+        let rows = client
+            .query(
+                r#"
+                SELECT
+                    t.table_schema,
+                    t.table_name,
+                    t.table_type,
+                    cls.reltuples::bigint AS estimated_rows,
+                    COUNT(c.column_name) AS column_count,
+                    COALESCE(
+                        string_agg(c.column_name || ' ' || c.data_type || ' ' || c.is_nullable, ',' ORDER BY c.ordinal_position),
+                        ''
+                    ) AS column_signature
+                FROM information_schema.tables t
+                LEFT JOIN information_schema.columns c
+                    ON c.table_schema = t.table_schema AND c.table_name = t.table_name
+                LEFT JOIN pg_catalog.pg_namespace n ON n.nspname = t.table_schema
+                LEFT JOIN pg_catalog.pg_class cls ON cls.relnamespace = n.oid AND cls.relname = t.table_name
+                WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+                    AND t.table_type IN ('BASE TABLE', 'VIEW')
+                GROUP BY t.table_schema, t.table_name, t.table_type, cls.reltuples
+                ORDER BY t.table_schema, t.table_name
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to list tables: {}", e)))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let is_view = row.get::<_, String>(2) == "VIEW";
+                RelationListing {
+                    schema: row.get(0),
+                    name: row.get(1),
+                    is_view,
+                    // Planner statistics; -1 until the table was first analyzed
+                    row_count: row.get::<_, Option<i64>>(3).filter(|rows| *rows >= 0 && !is_view),
+                    changed_at: None,
+                    column_count: usize::try_from(row.get::<_, i64>(4)).ok(),
+                    column_signature: Some(row.get(5)),
+                    engine_metadata: None,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_views(
+        client: &tokio_postgres::Client,
+        columns: &mut HashMap<(String, String), Vec<Column>>,
+    ) -> Result<Vec<View>, AppError> {
         let rows = client
             .query(
                 r#"
@@ -462,82 +985,162 @@ impl PostgreSQLAdapter {
         for row in rows {
             let schema = row.get::<_, String>(0);
             let name = row.get::<_, String>(1);
-            let columns = Self::get_table_columns(client, &schema, &name).await?;
+            let columns = columns.remove(&(schema.clone(), name.clone())).unwrap_or_default();
             views.push(View {
                 name,
                 schema: Some(schema),
                 columns,
                 definition: None,
                 description: None,
+                tags: Vec::new(),
             });
         }
 
         Ok(views)
     }
 
+    /// Columns of all tables and views in user schemas, keyed by schema and name
+    async fn get_all_columns(
+        client: &tokio_postgres::Client,
+    ) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        Self::query_columns(
+            client,
+            "c.table_schema NOT IN ('pg_catalog', 'information_schema', 'pg_toast')",
+            &[],
+        )
+        .await
+    }
+
     async fn get_table_columns(
         client: &tokio_postgres::Client,
         schema: &str,
         table_name: &str,
     ) -> Result<Vec<Column>, AppError> {
+        let mut columns = Self::query_columns(
+            client,
+            "c.table_schema = $1 AND c.table_name = $2",
+            &[&schema, &table_name],
+        )
+        .await?;
+        Ok(columns
+            .remove(&(schema.to_string(), table_name.to_string()))
+            .unwrap_or_default())
+    }
+
+    /// Columns of the relations matching `filter` (a condition on
+    /// `information_schema.columns c`), grouped by schema and name
+    async fn query_columns(
+        client: &tokio_postgres::Client,
+        filter: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<HashMap<(String, String), Vec<Column>>, AppError> {
+        let sql = format!(
+            r#"
+            SELECT
+                c.table_schema,
+                c.table_name,
+                c.column_name,
+                c.data_type,
+                c.is_nullable,
+                c.column_default,
+                CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key,
+                CASE WHEN fk.column_name IS NOT NULL THEN true ELSE false END as is_foreign_key,
+                -- n_distinct below zero is a fraction of the table's rows
+                CASE
+                    WHEN s.n_distinct >= 0 THEN s.n_distinct::bigint
+                    ELSE (-s.n_distinct * GREATEST(cls.reltuples, 0))::bigint
+                END AS distinct_count
+            FROM information_schema.columns c
+            LEFT JOIN (
+                SELECT DISTINCT ku.table_schema, ku.table_name, ku.column_name
+                FROM information_schema.table_constraints tc
+                JOIN information_schema.key_column_usage ku
+                    ON tc.constraint_name = ku.constraint_name
+                    AND tc.table_schema = ku.table_schema
+                    AND tc.table_name = ku.table_name
+                WHERE tc.constraint_type = 'PRIMARY KEY'
+            ) pk ON pk.table_schema = c.table_schema
+                AND pk.table_name = c.table_name
+                AND pk.column_name = c.column_name
+            LEFT JOIN (
+                SELECT DISTINCT ku.table_schema, ku.table_name, ku.column_name
+                FROM information_schema.table_constraints tc
+                JOIN information_schema.key_column_usage ku
+                    ON tc.constraint_name = ku.constraint_name
+                    AND tc.table_schema = ku.table_schema
+                    AND tc.table_name = ku.table_name
+                WHERE tc.constraint_type = 'FOREIGN KEY'
+            ) fk ON fk.table_schema = c.table_schema
+                AND fk.table_name = c.table_name
+                AND fk.column_name = c.column_name
+            LEFT JOIN pg_catalog.pg_stats s
+                ON s.schemaname = c.table_schema
+                AND s.tablename = c.table_name
+                AND s.attname = c.column_name
+                AND NOT s.inherited
+            LEFT JOIN pg_catalog.pg_namespace ns ON ns.nspname = c.table_schema
+            LEFT JOIN pg_catalog.pg_class cls ON cls.relnamespace = ns.oid AND cls.relname = c.table_name
+            WHERE {}
+            ORDER BY c.table_schema, c.table_name, c.ordinal_position
+            "#,
+            filter
+        );
         let rows = client
-            .query(
-                r#"
-                SELECT
-                    c.column_name,
-                    c.data_type,
-                    c.is_nullable,
-                    c.column_default,
-                    CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key,
-                    CASE WHEN fk.column_name IS NOT NULL THEN true ELSE false END as is_foreign_key
-                FROM information_schema.columns c
-                LEFT JOIN (
-                    SELECT ku.column_name
-                    FROM information_schema.table_constraints tc
-                    JOIN information_schema.key_column_usage ku
-                        ON tc.constraint_name = ku.constraint_name
-                        AND tc.table_schema = ku.table_schema
-                    WHERE tc.constraint_type = 'PRIMARY KEY'
-                        AND tc.table_schema = $1
-                        AND tc.table_name = $2
-                ) pk ON c.column_name = pk.column_name
-                LEFT JOIN (
-                    SELECT ku.column_name
-                    FROM information_schema.table_constraints tc
-                    JOIN information_schema.key_column_usage ku
-                        ON tc.constraint_name = ku.constraint_name
-                        AND tc.table_schema = ku.table_schema
-                    WHERE tc.constraint_type = 'FOREIGN KEY'
-                        AND tc.table_schema = $1
-                        AND tc.table_name = $2
-                ) fk ON c.column_name = fk.column_name
-                WHERE c.table_schema = $1 AND c.table_name = $2
-                ORDER BY c.ordinal_position
-                "#,
-                &[&schema, &table_name],
-            )
+            .query(&sql, params)
             .await
             .map_err(|e| AppError::Database(format!("Failed to get columns: {}", e)))?;
 
-        Ok(rows
-            .iter()
-            .map(|row| {
-                // Safely extract boolean values with proper type handling
-                let is_pk: bool = row.try_get(4).unwrap_or(false);
-                let is_fk: bool = row.try_get(5).unwrap_or(false);
-
-                Column {
-                    name: row.get(0),
-                    data_type: row.get(1),
-                    is_nullable: row.get::<_, String>(2) == "YES",
-                    default_value: row.get::<_, Option<String>>(3),
-                    is_primary_key: is_pk,
-                    is_foreign_key: is_fk,
-                    max_length: None,
-                    description: None,
-                }
-            })
-            .collect())
+        let mut columns: HashMap<(String, String), Vec<Column>> = HashMap::new();
+        for row in &rows {
+            // Safely extract boolean values with proper type handling
+            let is_pk: bool = row.try_get(6).unwrap_or(false);
+            let is_fk: bool = row.try_get(7).unwrap_or(false);
+
+            columns.entry((row.get(0), row.get(1))).or_default().push(Column {
+                name: row.get(2),
+                data_type: row.get(3),
+                is_nullable: row.get::<_, String>(4) == "YES",
+                default_value: row.get::<_, Option<String>>(5),
+                is_primary_key: is_pk,
+                is_foreign_key: is_fk,
+                max_length: None,
+                description: None,
+                // Planner statistics; absent until the table was first analyzed
+                distinct_count: row.try_get::<_, Option<i64>>(8).ok().flatten(),
+                tags: Vec::new(),
+            });
+        }
+        Ok(columns)
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_param_encodes_inferred_type() {
+        let mut out = BytesMut::new();
+        JsonParam(&json!("42")).to_sql(&Type::INT4, &mut out).unwrap();
+        assert_eq!(&out[..], &42i32.to_be_bytes());
+
+        let mut out = BytesMut::new();
+        assert!(matches!(JsonParam(&Value::Null).to_sql(&Type::INT8, &mut out), Ok(IsNull::Yes)));
+        assert!(JsonParam(&json!(1.5)).to_sql(&Type::INT4, &mut out).is_err());
+        assert!(JsonParam(&json!("x")).to_sql(&Type::JSONB, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_csv_rows_quote_values_and_leave_nulls_empty() {
+        let columns = vec!["id".to_string(), "name".to_string(), "tags".to_string()];
+        let rows = vec![
+            json!({"id": 1, "name": "say \"hi\", then\nleave", "tags": ["a"]}),
+            json!({"id": 2, "name": "", "tags": null}),
+        ];
+        assert_eq!(
+            csv_rows(&columns, &rows),
+            "\"1\",\"say \"\"hi\"\", then\nleave\",\"[\"\"a\"\"]\"\n\"2\",\"\",\n"
+        );
+    }
+}
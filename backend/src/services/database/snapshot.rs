@@ -0,0 +1,153 @@
+// Materialized view snapshot adapter
+// Reads the Parquet snapshot of a materialized view through DataFusion, so the
+// federated executor can treat the snapshot like any other connection.
+use crate::models::{DatabaseConnection, DatabaseMetadata};
+use crate::api::middleware::AppError;
+use crate::services::database::adapter::{ensure_no_params, DatabaseAdapter, RowBatchStream};
+use crate::services::datafusion::catalog::{DataFusionCatalogManager, SNAPSHOT_TABLE};
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::datafusion::DataFusionSessionPool;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use futures::stream::{self, StreamExt};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct ParquetSnapshotAdapter {
+    path: PathBuf,
+    sessions: Arc<DataFusionSessionPool>,
+}
+
+impl ParquetSnapshotAdapter {
+    /// Adapter reading the snapshot at `path` as the table `__snapshot`
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            sessions: Arc::new(DataFusionSessionPool::default()),
+        }
+    }
+
+    /// Read the snapshot in sessions of `sessions`
+    pub fn with_sessions(mut self, sessions: Arc<DataFusionSessionPool>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl DatabaseAdapter for ParquetSnapshotAdapter {
+    async fn connect_and_get_metadata(
+        &self,
+        _connection_id: String,
+    ) -> Result<(DatabaseConnection, DatabaseMetadata), AppError> {
+        Err(AppError::Validation("Materialized view snapshots have no metadata".to_string()))
+    }
+
+    async fn stream_query(
+        &self,
+        sql: &str,
+        params: &[Value],
+        timeout_secs: u64,
+        batch_size: usize,
+    ) -> Result<RowBatchStream, AppError> {
+        ensure_no_params(self, params)?;
+        let (schema, batches) = self.execute_datafusion_query(sql, timeout_secs).await?;
+        let rows = DataFusionResultConverter::convert_to_query_result(schema, batches)
+            .map_err(|e| AppError::Internal(format!("Failed to convert snapshot rows: {}", e)))?
+            .rows;
+
+        let batches: Vec<Result<Vec<Value>, AppError>> =
+            rows.chunks(batch_size.max(1)).map(|chunk| Ok(chunk.to_vec())).collect();
+        Ok(stream::iter(batches).boxed())
+    }
+
+    fn database_type(&self) -> &str {
+        "parquet"
+    }
+
+    fn dialect_name(&self) -> &str {
+        "generic" // Sub-queries run on DataFusion itself
+    }
+
+    fn supports_datafusion_execution(&self) -> bool {
+        true
+    }
+
+    async fn execute_datafusion_query(
+        &self,
+        datafusion_sql: &str,
+        timeout_secs: u64,
+    ) -> Result<(SchemaRef, Vec<RecordBatch>), AppError> {
+        let session = self
+            .sessions
+            .acquire()
+            .map_err(|e| AppError::Internal(format!("Failed to create DataFusion session: {}", e)))?;
+        // The snapshot table is dropped when the session returns to the pool
+        let catalog = DataFusionCatalogManager::new((*session).clone());
+        catalog
+            .register_snapshot(SNAPSHOT_TABLE, &self.path)
+            .await
+            .map_err(|e| AppError::Database(format!("{:#}", e)))?;
+
+        let query = async {
+            let df = catalog.session_context().sql(datafusion_sql).await?;
+            // The plan's schema survives results without rows
+            let schema: SchemaRef = Arc::new(df.schema().as_arrow().clone());
+            let batches = df.collect().await?;
+            Ok::<_, datafusion::error::DataFusionError>((schema, batches))
+        };
+        tokio::time::timeout(Duration::from_secs(timeout_secs), query)
+            .await
+            .map_err(|_| AppError::Database(format!("Snapshot query timeout after {} seconds", timeout_secs)))?
+            .map_err(|e| AppError::Database(format!("Snapshot query failed: {}", e)))
+    }
+
+    async fn test_connection(&self) -> Result<(), AppError> {
+        if self.path.is_file() {
+            Ok(())
+        } else {
+            Err(AppError::Connection(format!("Snapshot {} does not exist", self.path.display())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::result_transform::rows_to_record_batch;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_snapshot_queries_read_parquet() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("view.parquet");
+        let batch = rows_to_record_batch(&[json!({"id": 1, "total": 50}), json!({"id": 2, "total": 150})]).unwrap();
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let adapter = ParquetSnapshotAdapter::new(path);
+        adapter.test_connection().await.unwrap();
+
+        let result = adapter
+            .execute_query("WITH revenue AS (SELECT * FROM __snapshot) SELECT id FROM revenue WHERE total > 100", 10)
+            .await
+            .unwrap();
+        assert_eq!(result.rows, vec![json!({"id": 2})]);
+
+        // Empty results keep their columns
+        let (schema, batches) = adapter
+            .execute_datafusion_query("SELECT id, total FROM __snapshot WHERE total > 1000", 10)
+            .await
+            .unwrap();
+        assert_eq!(schema.fields().len(), 2);
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 0);
+
+        let missing = ParquetSnapshotAdapter::new(dir.path().join("missing.parquet"));
+        assert!(missing.test_connection().await.is_err());
+    }
+}
@@ -2,17 +2,114 @@
 //
 // Manages the registration of database tables as DataFusion catalogs.
 // Enables querying multiple databases through a unified interface.
+// Saved queries flagged as views are exposed as `domain.view_name` tables
+// (VirtualView) that cross-database queries resolve to their connection, or
+// to the Parquet snapshot registered as a table when the view is materialized.
+// Connections' cached tables are exposed as `alias.schema.table`, one catalog
+// per connection alias.
 
-use datafusion::catalog::CatalogProvider;
+use datafusion::catalog::{CatalogProvider, MemoryCatalogProvider, MemorySchemaProvider, SchemaProvider};
 use datafusion::prelude::*;
 use datafusion::datasource::MemTable;
 use datafusion::arrow::datatypes::{Schema, Field, DataType};
 use datafusion::arrow::record_batch::RecordBatch;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::path::Path;
 use anyhow::{Result, Context, anyhow};
 
 use crate::models::metadata::{DatabaseMetadata, Table, Column};
+use crate::models::SavedQuery;
+
+/// Table a materialized view's snapshot is registered under in the session reading it
+pub const SNAPSHOT_TABLE: &str = "__snapshot";
+
+/// Prefix of the connection IDs that stand for materialized view snapshots
+const SNAPSHOT_CONNECTION_PREFIX: &str = "snapshot:";
+
+/// Schema of a connection catalog holding the tables cached without one
+pub const DEFAULT_CATALOG_SCHEMA: &str = "public";
+
+/// Catalog alias of a connection: its name lowercased, other characters than
+/// letters, digits and underscores replaced by `_`
+///
+/// `"Sales DB (prod)"` => `sales_db_prod`. Aliases starting with a digit get a
+/// leading `_`; an empty name gives `connection`.
+pub fn connection_alias(name: &str) -> String {
+    let mut alias = String::new();
+    for c in name.trim().chars().map(|c| c.to_ascii_lowercase()) {
+        if c.is_ascii_alphanumeric() {
+            alias.push(c);
+        } else if !alias.is_empty() && !alias.ends_with('_') {
+            alias.push('_');
+        }
+    }
+    let alias = alias.trim_end_matches('_');
+    match alias.chars().next() {
+        None => "connection".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", alias),
+        Some(_) => alias.to_string(),
+    }
+}
+
+/// A saved query exposed as the table `schema.name`, where the schema is the
+/// name of the saved query's domain
+///
+/// Cross-database queries read views like tables: the query runs on its
+/// connection as a CTE of the sub-queries that reference the view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualView {
+    /// Domain name
+    pub schema: String,
+    pub name: String,
+    pub connection_id: String,
+    /// SQL in the connection's dialect
+    pub query: String,
+}
+
+impl VirtualView {
+    /// The view of a saved query, if it is flagged as one and runs on a single connection
+    pub fn from_saved_query(schema: &str, saved_query: &SavedQuery) -> Option<Self> {
+        Some(Self {
+            schema: schema.to_string(),
+            name: saved_query.view_name.clone()?,
+            connection_id: saved_query.connection_id.clone()?,
+            query: saved_query.query_text.trim().trim_end_matches(';').trim_end().to_string(),
+        })
+    }
+
+    /// Read the view from the Parquet snapshot of the saved query's materialization
+    ///
+    /// The snapshot acts as a connection of its own, so the view's query no
+    /// longer runs on the view's connection.
+    pub fn materialized(mut self, saved_query_id: &str) -> Self {
+        self.connection_id = Self::snapshot_connection_id(saved_query_id);
+        self.query = format!("SELECT * FROM {}", SNAPSHOT_TABLE);
+        self
+    }
+
+    /// Connection ID standing for the snapshot of a saved query's materialization
+    pub fn snapshot_connection_id(saved_query_id: &str) -> String {
+        format!("{}{}", SNAPSHOT_CONNECTION_PREFIX, saved_query_id)
+    }
+
+    /// Saved query whose snapshot a connection ID of a materialized view stands for
+    pub fn snapshot_of(connection_id: &str) -> Option<&str> {
+        connection_id.strip_prefix(SNAPSHOT_CONNECTION_PREFIX)
+    }
+
+    /// `schema.name`
+    pub fn reference(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    /// View names are plain identifiers, so they need no quoting in any dialect
+    pub fn is_valid_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
 
 /// Manages catalog registration for DataFusion
 ///
@@ -79,28 +176,62 @@ impl DataFusionCatalogManager {
         _catalog_name: &str,
         table: &Table,
     ) -> Result<()> {
-        // Convert table metadata to Arrow schema
-        let schema = self.metadata_to_arrow_schema(table)?;
+        // Register the table with the session context
+        self.ctx.register_table(
+            &table.name,
+            self.placeholder_table(table)?,
+        )?;
 
-        // Create an empty MemTable as a placeholder
-        // Actual data will be fetched when query is executed
-        let schema_ref = Arc::new(schema);
+        Ok(())
+    }
+
+    /// Register a connection's cached tables as the catalog `alias`, read as `alias.schema.table`
+    ///
+    /// Tables cached without a schema go to `public`. Like `register_database`,
+    /// the tables hold no rows: they describe the connection's columns, and
+    /// cross-database queries fetch the rows from the connection itself.
+    pub fn register_connection(&mut self, alias: &str, metadata: &DatabaseMetadata) -> Result<Arc<dyn CatalogProvider>> {
+        let catalog = MemoryCatalogProvider::new();
+        for table in &metadata.tables {
+            let schema_name = table.schema.as_deref().unwrap_or(DEFAULT_CATALOG_SCHEMA);
+            let schema = match catalog.schema(schema_name) {
+                Some(schema) => schema,
+                None => {
+                    let schema: Arc<dyn SchemaProvider> = Arc::new(MemorySchemaProvider::new());
+                    catalog.register_schema(schema_name, schema.clone())?;
+                    schema
+                }
+            };
+            schema
+                .register_table(table.name.clone(), self.placeholder_table(table)?)
+                .with_context(|| format!("Failed to register {}.{}.{}", alias, schema_name, table.name))?;
+        }
+
+        let catalog: Arc<dyn CatalogProvider> = Arc::new(catalog);
+        self.ctx.register_catalog(alias, catalog.clone());
+        self.registered_catalogs.insert(alias.to_string(), catalog.clone());
+        Ok(catalog)
+    }
+
+    /// Catalogs registered by `register_connection`, by alias
+    pub fn catalogs(&self) -> &HashMap<String, Arc<dyn CatalogProvider>> {
+        &self.registered_catalogs
+    }
+
+    /// Empty table with the columns of `table`
+    ///
+    /// Actual data will be fetched when query is executed.
+    fn placeholder_table(&self, table: &Table) -> Result<Arc<MemTable>> {
+        // Convert table metadata to Arrow schema
+        let schema_ref = Arc::new(self.metadata_to_arrow_schema(table)?);
 
         // Create an empty RecordBatch to satisfy MemTable requirements
         let empty_batch = RecordBatch::new_empty(schema_ref.clone());
 
-        let mem_table = MemTable::try_new(
+        Ok(Arc::new(MemTable::try_new(
             schema_ref,
             vec![vec![empty_batch]], // One partition with one empty batch
-        )?;
-
-        // Register the table with the session context
-        self.ctx.register_table(
-            &table.name,
-            Arc::new(mem_table),
-        )?;
-
-        Ok(())
+        )?))
     }
 
     /// Convert table metadata to Arrow schema
@@ -177,6 +308,17 @@ impl DataFusionCatalogManager {
         Ok(arrow_type)
     }
 
+    /// Register a materialized view's Parquet snapshot as the table `name`
+    pub async fn register_snapshot(&self, name: &str, path: &Path) -> Result<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow!("Snapshot path {} is not valid UTF-8", path.display()))?;
+        self.ctx
+            .register_parquet(name, path, ParquetReadOptions::default())
+            .await
+            .with_context(|| format!("Failed to register snapshot {}", path))
+    }
+
     /// Get the session context
     pub fn session_context(&self) -> &SessionContext {
         &self.ctx
@@ -252,6 +394,8 @@ mod tests {
                             default_value: None,
                             max_length: None,
                             description: None,
+                            distinct_count: None,
+                            tags: Vec::new(),
                         },
                         Column {
                             name: "name".to_string(),
@@ -262,10 +406,15 @@ mod tests {
                             default_value: None,
                             max_length: Some(255),
                             description: None,
+                            distinct_count: None,
+                            tags: Vec::new(),
                         },
                     ],
                     row_count: None,
+                    size_bytes: None,
                     description: None,
+                    tags: Vec::new(),
+                    engine_metadata: None,
                 },
             ],
             vec![],
@@ -273,6 +422,34 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_virtual_view_from_saved_query() {
+        let saved = SavedQuery::new(
+            "domain-1".to_string(),
+            "conn-1".to_string(),
+            "Monthly revenue".to_string(),
+            "SELECT month, SUM(amount) AS revenue FROM orders GROUP BY month;\n".to_string(),
+            None,
+        );
+        assert_eq!(VirtualView::from_saved_query("sales", &saved), None);
+        let saved_query_id = saved.id.clone();
+
+        let view = VirtualView::from_saved_query("sales", &saved.with_view_name(Some("monthly_revenue".to_string()))).unwrap();
+        assert_eq!(view.reference(), "sales.monthly_revenue");
+        assert_eq!(view.connection_id, "conn-1");
+        assert_eq!(view.query, "SELECT month, SUM(amount) AS revenue FROM orders GROUP BY month");
+
+        let materialized = view.clone().materialized(&saved_query_id);
+        assert_eq!(materialized.reference(), "sales.monthly_revenue");
+        assert_eq!(VirtualView::snapshot_of(&materialized.connection_id), Some(saved_query_id.as_str()));
+        assert_eq!(materialized.query, "SELECT * FROM __snapshot");
+        assert_eq!(VirtualView::snapshot_of(&view.connection_id), None);
+
+        assert!(VirtualView::is_valid_name("_daily_2"));
+        assert!(!VirtualView::is_valid_name("2daily"));
+        assert!(!VirtualView::is_valid_name("daily revenue"));
+    }
+
     #[tokio::test]
     async fn test_catalog_manager_creation() {
         let ctx = SessionContext::new();
@@ -297,6 +474,39 @@ mod tests {
         assert!(tables.contains(&"users".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_register_connection_catalog() {
+        let mut manager = DataFusionCatalogManager::new(SessionContext::new());
+        let mut metadata = create_test_metadata();
+        let mut events = metadata.tables[0].clone();
+        events.name = "events".to_string();
+        events.schema = None;
+        metadata.tables.push(events);
+
+        let catalog = manager.register_connection("sales_db", &metadata).unwrap();
+        assert_eq!(catalog.schema("public").unwrap().table_names().len(), 2);
+        assert!(manager.catalogs().contains_key("sales_db"));
+
+        let batches = manager
+            .session_context()
+            .sql("SELECT id, name FROM sales_db.public.users")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+    }
+
+    #[test]
+    fn test_connection_alias() {
+        assert_eq!(connection_alias("Sales DB (prod)"), "sales_db_prod");
+        assert_eq!(connection_alias("analytics"), "analytics");
+        assert_eq!(connection_alias("2024-archive"), "_2024_archive");
+        assert_eq!(connection_alias(" -- "), "connection");
+        assert!(VirtualView::is_valid_name(&connection_alias("Übersicht")));
+    }
+
     #[test]
     fn test_sql_type_mapping() {
         let ctx = SessionContext::new();
@@ -10,6 +10,7 @@ use serde_json::{json, Value as JsonValue};
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, NaiveDate};
 
+use crate::models::ColumnarResult;
 use crate::services::database::adapter::QueryResult;
 
 /// Converts DataFusion query results to JSON format
@@ -68,6 +69,30 @@ impl DataFusionResultConverter {
         })
     }
 
+    /// Convert query execution results to a column-oriented result
+    ///
+    /// Column types are the Arrow type names (e.g. `Int64`, `Utf8`).
+    pub fn convert_to_columnar(schema: SchemaRef, batches: &[RecordBatch]) -> Result<ColumnarResult> {
+        let row_count = batches.iter().map(RecordBatch::num_rows).sum();
+        let mut data: Vec<Vec<JsonValue>> = schema.fields().iter().map(|_| Vec::with_capacity(row_count)).collect();
+
+        for batch in batches {
+            for (col_idx, values) in data.iter_mut().enumerate() {
+                let column = batch.column(col_idx);
+                let data_type = schema.field(col_idx).data_type();
+                for row_idx in 0..batch.num_rows() {
+                    values.push(Self::array_value_to_json(column, row_idx, data_type)?);
+                }
+            }
+        }
+
+        Ok(ColumnarResult {
+            columns: schema.fields().iter().map(|field| field.name().clone()).collect(),
+            types: schema.fields().iter().map(|field| field.data_type().to_string()).collect(),
+            data,
+        })
+    }
+
     /// Convert a single RecordBatch to JSON rows
     ///
     /// # Arguments
@@ -408,6 +433,31 @@ mod tests {
         assert_eq!(row1.get("bool_val"), Some(&json!(false)));
     }
 
+    #[test]
+    fn test_convert_to_columnar() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batches: Vec<RecordBatch> = [vec![1, 2], vec![3]]
+            .into_iter()
+            .map(|ids| {
+                let names: Vec<Option<&str>> = ids.iter().map(|id| (*id != 2).then_some("x")).collect();
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int64Array::from(ids)), Arc::new(StringArray::from(names))],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let result = DataFusionResultConverter::convert_to_columnar(schema, &batches).unwrap();
+        assert_eq!(result.columns, vec!["id", "name"]);
+        assert_eq!(result.types, vec!["Int64", "Utf8"]);
+        assert_eq!(result.data[0], vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(result.data[1], vec![json!("x"), JsonValue::Null, json!("x")]);
+    }
+
     #[test]
     fn test_empty_result() {
         let schema = Arc::new(Schema::new(vec![
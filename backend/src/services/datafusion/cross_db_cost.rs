@@ -0,0 +1,203 @@
+// Cross-Database Cost Model
+//
+// Row estimates for federated sub-queries, from the table statistics kept in the
+// metadata cache (row counts and column distinct counts). The planner uses them
+// to order joins, to pick the table whose join keys filter the other side, and
+// to warn about plans that would transfer many rows.
+
+use crate::models::Table;
+use sqlparser::ast::{BinaryOperator, Expr};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::collections::HashMap;
+
+/// Fraction of rows assumed to pass a predicate the statistics say nothing about
+const DEFAULT_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Fraction of rows assumed to pass `column = value` or `IS NULL` without a distinct count
+const DEFAULT_EQUALITY_SELECTIVITY: f64 = 0.1;
+
+/// Table statistics of the connections in a cross-database query
+#[derive(Debug, Clone, Default)]
+pub struct CrossDatabaseCostModel {
+    /// Tables of each connection's cached metadata, by connection ID
+    tables: HashMap<String, Vec<Table>>,
+}
+
+impl CrossDatabaseCostModel {
+    /// Add the cached tables of a connection
+    pub fn add_connection(&mut self, connection_id: impl Into<String>, tables: Vec<Table>) {
+        self.tables.insert(connection_id.into(), tables);
+    }
+
+    /// Rows expected from `SELECT * FROM table WHERE <predicates>`
+    ///
+    /// None without a row count for the table.
+    pub fn estimate_rows(&self, connection_id: &str, table: &str, predicates: &[String]) -> Option<u64> {
+        let table = self.table(connection_id, table)?;
+        let rows = table.row_count.filter(|rows| *rows >= 0)? as f64;
+        let selectivity: f64 = predicates
+            .iter()
+            .map(|predicate| Self::predicate_selectivity(table, predicate))
+            .product();
+        Some((rows * selectivity).round() as u64)
+    }
+
+    /// Distinct values of a column, from the database's statistics
+    pub fn distinct_count(&self, connection_id: &str, table: &str, column: &str) -> Option<u64> {
+        let table = self.table(connection_id, table)?;
+        column_distinct_count(table, column)
+    }
+
+    /// Statistics of `name` (or `schema.name`) on a connection
+    ///
+    /// A name without a schema found in several schemas has none.
+    fn table(&self, connection_id: &str, name: &str) -> Option<&Table> {
+        let (schema, name) = match name.split_once('.') {
+            Some((schema, name)) => (Some(schema), name),
+            None => (None, name),
+        };
+        let mut matches = self
+            .tables
+            .get(connection_id)?
+            .iter()
+            .filter(|table| table.name.eq_ignore_ascii_case(name))
+            .filter(|table| {
+                schema.is_none_or(|schema| table.schema.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(schema)))
+            });
+        let table = matches.next()?;
+        matches.next().is_none().then_some(table)
+    }
+
+    fn predicate_selectivity(table: &Table, predicate: &str) -> f64 {
+        let expr = Parser::new(&GenericDialect {})
+            .try_with_sql(predicate)
+            .and_then(|mut parser| parser.parse_expr());
+        match expr {
+            Ok(expr) => expr_selectivity(table, &expr),
+            Err(_) => DEFAULT_SELECTIVITY,
+        }
+    }
+}
+
+/// Fraction of the table's rows expected to pass `expr`
+fn expr_selectivity(table: &Table, expr: &Expr) -> f64 {
+    match expr {
+        Expr::Nested(inner) => expr_selectivity(table, inner),
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            expr_selectivity(table, left) * expr_selectivity(table, right)
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            let (left, right) = (expr_selectivity(table, left), expr_selectivity(table, right));
+            left + right - left * right
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Eq, right } => equality_selectivity(table, left)
+            .or_else(|| equality_selectivity(table, right))
+            .unwrap_or(DEFAULT_EQUALITY_SELECTIVITY),
+        Expr::BinaryOp { left, op: BinaryOperator::NotEq, right } => {
+            1.0 - equality_selectivity(table, left)
+                .or_else(|| equality_selectivity(table, right))
+                .unwrap_or(DEFAULT_EQUALITY_SELECTIVITY)
+        }
+        Expr::InList { expr, list, negated } => {
+            let each = equality_selectivity(table, expr).unwrap_or(DEFAULT_EQUALITY_SELECTIVITY);
+            let selectivity = (each * list.len() as f64).min(1.0);
+            if *negated { 1.0 - selectivity } else { selectivity }
+        }
+        Expr::IsNull(_) => DEFAULT_EQUALITY_SELECTIVITY,
+        Expr::IsNotNull(_) => 1.0 - DEFAULT_EQUALITY_SELECTIVITY,
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// `1 / distinct values` when `expr` is a column with a distinct count
+fn equality_selectivity(table: &Table, expr: &Expr) -> Option<f64> {
+    let column = match expr {
+        Expr::Identifier(ident) => &ident.value,
+        Expr::CompoundIdentifier(idents) => &idents.last()?.value,
+        _ => return None,
+    };
+    column_distinct_count(table, column).map(|distinct| 1.0 / distinct as f64)
+}
+
+fn column_distinct_count(table: &Table, column: &str) -> Option<u64> {
+    table
+        .columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(column))?
+        .distinct_count
+        .filter(|distinct| *distinct > 0)
+        .map(|distinct| distinct as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Column;
+
+    fn model() -> CrossDatabaseCostModel {
+        let column = |name: &str, distinct_count: Option<i64>| Column {
+            name: name.to_string(),
+            data_type: "integer".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count,
+            tags: Vec::new(),
+        };
+        let table = |schema: &str, name: &str, row_count: Option<i64>| Table {
+            name: name.to_string(),
+            schema: Some(schema.to_string()),
+            columns: vec![column("id", row_count), column("status", Some(4)), column("note", None)],
+            row_count,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        };
+
+        let mut model = CrossDatabaseCostModel::default();
+        model.add_connection(
+            "conn1",
+            vec![
+                table("public", "orders", Some(10_000)),
+                table("public", "events", None),
+                table("public", "logs", Some(50)),
+                table("archive", "logs", Some(70)),
+            ],
+        );
+        model
+    }
+
+    #[test]
+    fn test_estimate_rows_applies_predicate_selectivity() {
+        let model = model();
+
+        assert_eq!(model.estimate_rows("conn1", "orders", &[]), Some(10_000));
+        assert_eq!(model.estimate_rows("conn1", "ORDERS", &["o.status = 'open'".to_string()]), Some(2_500));
+        assert_eq!(model.estimate_rows("conn1", "orders", &["o.id IN (1, 2, 3)".to_string()]), Some(3));
+        // Without a distinct count equality keeps a tenth, anything else a third
+        assert_eq!(
+            model.estimate_rows("conn1", "orders", &["o.note = 'x'".to_string(), "o.id > 5".to_string()]),
+            Some(333)
+        );
+    }
+
+    #[test]
+    fn test_estimate_rows_unknown_without_statistics() {
+        let model = model();
+
+        assert_eq!(model.estimate_rows("conn1", "events", &[]), None);
+        assert_eq!(model.estimate_rows("conn1", "missing", &[]), None);
+        assert_eq!(model.estimate_rows("conn2", "orders", &[]), None);
+        // Ambiguous across schemas
+        assert_eq!(model.estimate_rows("conn1", "logs", &[]), None);
+        // unless the schema is named
+        assert_eq!(model.estimate_rows("conn1", "archive.logs", &[]), Some(70));
+        assert_eq!(model.estimate_rows("conn1", "staging.logs", &[]), None);
+        assert_eq!(model.distinct_count("conn1", "orders", "Status"), Some(4));
+    }
+}
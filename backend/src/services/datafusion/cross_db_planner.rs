@@ -3,13 +3,22 @@
 // Parses cross-database SQL queries and creates execution plans for federated query execution.
 
 use crate::api::middleware::AppError;
+use crate::config::CrossDatabaseConfig;
 use crate::models::cross_database_query::{
-    CrossDatabaseExecutionPlan, CrossDatabaseQueryRequest, JoinCondition, MergeStrategy, SubQuery,
+    BroadcastJoin, CrossDatabaseExecutionPlan, CrossDatabaseQueryRequest, JoinCondition, MergeClauses, MergeStrategy,
+    SubQuery,
+};
+use crate::models::DomainSettings;
+use crate::services::datafusion::catalog::VirtualView;
+use crate::services::datafusion::cross_db_cost::CrossDatabaseCostModel;
+use sqlparser::ast::{
+    visit_relations, Expr, GroupByExpr, Ident, ObjectName, ObjectNamePart, SelectItem, SelectItemQualifiedWildcardKind,
+    SetExpr, Statement, TableFactor, Visit, VisitMut, Visitor, VisitorMut,
 };
-use sqlparser::ast::{Statement, TableFactor, SetExpr};
 use sqlparser::dialect::GenericDialect;
 use sqlparser::parser::Parser;
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 
 /// Cross-Database Query Planner
 ///
@@ -18,6 +27,16 @@ pub struct CrossDatabaseQueryPlanner {
     /// Map of table qualifiers to connection IDs
     /// Example: {"mysql_conn" => "connection-id-1", "pg_conn" => "connection-id-2"}
     connection_map: HashMap<String, String>,
+    /// Table statistics for row estimates
+    cost_model: CrossDatabaseCostModel,
+    /// Estimated rows above which a plan carries a warning (0 disables)
+    transfer_warning_rows: u64,
+    /// Largest estimated table whose join keys filter the other side (0 disables)
+    broadcast_max_rows: u64,
+    /// Saved queries readable as `domain.view_name`
+    views: Vec<VirtualView>,
+    /// Settings of the domains the query's connections belong to
+    domain_settings: Vec<DomainSettings>,
 }
 
 impl CrossDatabaseQueryPlanner {
@@ -36,7 +55,7 @@ impl CrossDatabaseQueryPlanner {
             connection_map.insert(id.clone(), id);
         }
 
-        Self { connection_map }
+        Self::with_aliases(connection_map)
     }
 
     /// Create a new planner with custom aliases
@@ -49,6 +68,11 @@ impl CrossDatabaseQueryPlanner {
     pub fn with_aliases(aliases: HashMap<String, String>) -> Self {
         Self {
             connection_map: aliases,
+            cost_model: CrossDatabaseCostModel::default(),
+            transfer_warning_rows: 0,
+            broadcast_max_rows: 0,
+            views: Vec::new(),
+            domain_settings: Vec::new(),
         }
     }
 
@@ -63,6 +87,76 @@ impl CrossDatabaseQueryPlanner {
         }
     }
 
+    /// Use table statistics to estimate sub-query rows
+    ///
+    /// Estimates order inner joins smallest table first, choose a broadcast join
+    /// and produce a warning when a plan exceeds the configured transfer threshold.
+    pub fn with_cost_model(mut self, cost_model: CrossDatabaseCostModel, config: &CrossDatabaseConfig) -> Self {
+        self.cost_model = cost_model;
+        self.transfer_warning_rows = config.transfer_warning_rows;
+        self.broadcast_max_rows = config.broadcast_max_rows;
+        self
+    }
+
+    /// Let queries read saved query views as `domain.view_name`
+    ///
+    /// A view's connection does not need to be among the request's connections.
+    /// Views take precedence over tables whose database qualifier equals a domain name.
+    pub fn with_views(mut self, views: Vec<VirtualView>) -> Self {
+        self.views = views;
+        self
+    }
+
+    /// Keep the plan's timeout and row limit within these domains' settings
+    pub fn with_domain_settings(mut self, domain_settings: Vec<DomainSettings>) -> Self {
+        self.domain_settings = domain_settings;
+        self
+    }
+
+    /// Timeout and row limit of a request
+    ///
+    /// Every domain clamps the requested values (or supplies its defaults) and
+    /// the strictest result applies. Without domain settings the defaults of a
+    /// domain that never customized them apply.
+    fn limits(&self, request: &CrossDatabaseQueryRequest) -> (u64, u32) {
+        let defaults = DomainSettings::defaults(String::new());
+        let requested_limit = request.limit_value.map(u64::from);
+
+        let timeout_secs = self
+            .domain_settings
+            .iter()
+            .map(|settings| settings.timeout_secs(request.timeout_secs))
+            .min()
+            .unwrap_or_else(|| defaults.timeout_secs(request.timeout_secs));
+        let limit_value = self
+            .domain_settings
+            .iter()
+            .map(|settings| settings.row_limit(requested_limit))
+            .min()
+            .unwrap_or_else(|| defaults.row_limit(requested_limit));
+        (timeout_secs, u32::try_from(limit_value).unwrap_or(u32::MAX))
+    }
+
+    /// Database qualifiers of the `qualifier.schema.table` relations of a query, in order of appearance
+    ///
+    /// Cross-database queries that list no connections resolve these through the
+    /// federated catalog's connection aliases.
+    pub fn catalog_qualifiers(sql: &str) -> Result<Vec<String>, AppError> {
+        let statements = Parser::parse_sql(&GenericDialect {}, sql)
+            .map_err(|e| AppError::InvalidSql(format!("Failed to parse query: {}", e)))?;
+
+        let mut qualifiers: Vec<String> = Vec::new();
+        let _ = visit_relations(&statements, |name| {
+            if let [ObjectNamePart::Identifier(qualifier), _, _] = name.0.as_slice() {
+                if !qualifiers.contains(&qualifier.value) {
+                    qualifiers.push(qualifier.value.clone());
+                }
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        Ok(qualifiers)
+    }
+
     /// Plan a cross-database query
     ///
     /// Parses the query, identifies tables and their sources, decomposes into sub-queries,
@@ -108,27 +202,29 @@ impl CrossDatabaseQueryPlanner {
 
         // Regular SELECT query - check for JOINs
         if let SetExpr::Select(select) = &*query.body {
-            let tables = self.extract_tables(select)?;
+            let ctes = self.cte_scope(query)?;
+            let tables = self.extract_tables(select, &ctes)?;
 
             if tables.is_empty() {
                 return Err(AppError::InvalidSql("No tables found in query".to_string()));
             }
 
-            // Check if query involves multiple databases
-            let databases = self.identify_databases(&tables)?;
-
-            if databases.len() == 1 {
+            // Check if query involves multiple databases, counting CTE bodies and subqueries
+            if self.query_connections(query, &ctes)?.len() == 1 {
                 // Single database query - no cross-database execution needed
-                return self.plan_single_database_query(&tables, request);
+                return self.plan_single_database_query(&tables, &ctes, request);
             }
 
-            // Multiple databases - check for JOINs
-            if select.from.len() > 0 && !select.from[0].joins.is_empty() {
-                return self.plan_join_query(select, &tables, request);
+            // Multiple databases - scalar and IN subqueries run on their own connection
+            // and the merge reads their results
+            let mut query = query.clone();
+            let nested = match query.body.as_mut() {
+                SetExpr::Select(select) => self.extract_subqueries(select, &ctes)?,
+                _ => vec![],
+            };
+            if let SetExpr::Select(select) = query.body.as_ref() {
+                return self.plan_join_query(&query, select, &tables, &ctes, nested, request);
             }
-
-            // Multiple tables but no explicit JOIN - may still need federation
-            return self.plan_join_query(select, &tables, request);
         }
 
         Err(AppError::InvalidSql(
@@ -137,13 +233,19 @@ impl CrossDatabaseQueryPlanner {
     }
 
     /// Extract table references from a SELECT statement
-    fn extract_tables(&self, select: &sqlparser::ast::Select) -> Result<Vec<(String, Option<String>, String)>, AppError> {
+    ///
+    /// References to a CTE of the query resolve to the connection its body reads from.
+    fn extract_tables(
+        &self,
+        select: &sqlparser::ast::Select,
+        ctes: &CteScope,
+    ) -> Result<Vec<(String, Option<String>, String)>, AppError> {
         let mut tables = Vec::new();
 
         for table_with_joins in &select.from {
             // Main table
             if let TableFactor::Table { name, alias, .. } = &table_with_joins.relation {
-                let (qualifier, table_name) = self.parse_table_name(name)?;
+                let (qualifier, table_name) = self.resolve_relation(name, ctes)?;
                 let alias_name = alias.as_ref().map(|a| a.name.value.clone());
                 tables.push((qualifier, alias_name, table_name));
             }
@@ -151,7 +253,7 @@ impl CrossDatabaseQueryPlanner {
             // Joined tables
             for join in &table_with_joins.joins {
                 if let TableFactor::Table { name, alias, .. } = &join.relation {
-                    let (qualifier, table_name) = self.parse_table_name(name)?;
+                    let (qualifier, table_name) = self.resolve_relation(name, ctes)?;
                     let alias_name = alias.as_ref().map(|a| a.name.value.clone());
                     tables.push((qualifier, alias_name, table_name));
                 }
@@ -180,10 +282,11 @@ impl CrossDatabaseQueryPlanner {
                     .clone();
                 Ok((first_conn_id, parts[0].clone()))
             }
-            2 => {
-                // Qualified table name: qualifier.table
+            2 | 3 => {
+                // Qualified table name: qualifier.table, or qualifier.schema.table
+                // where the table runs on its database as schema.table
                 let qualifier = &parts[0];
-                let table_name = &parts[1];
+                let table_name = parts[1..].join(".");
 
                 // Verify qualifier exists in connection map
                 if !self.connection_map.contains_key(qualifier) {
@@ -194,48 +297,264 @@ impl CrossDatabaseQueryPlanner {
                     )));
                 }
 
-                Ok((qualifier.clone(), table_name.clone()))
+                Ok((qualifier.clone(), table_name))
             }
             _ => Err(AppError::InvalidSql(format!(
-                "Invalid table name format: {}. Expected 'table' or 'db.table'",
+                "Invalid table name format: {}. Expected 'table', 'db.table' or 'db.schema.table'",
                 parts.join(".")
             ))),
         }
     }
 
-    /// Identify which databases are involved in the query
-    fn identify_databases(
+    /// Qualifier and table name of a relation, which may be a CTE of the query or a view
+    fn resolve_relation(&self, name: &ObjectName, ctes: &CteScope) -> Result<(String, String), AppError> {
+        if let Some(cte) = ctes.get(&name.to_string()) {
+            return Ok((cte.qualifier.clone(), cte.name.clone()));
+        }
+        match self.view(name) {
+            Some(view) => Ok((view.reference(), view.name.clone())),
+            None => self.parse_table_name(name),
+        }
+    }
+
+    /// View a relation name refers to
+    fn view(&self, name: &ObjectName) -> Option<&VirtualView> {
+        view_named(&self.views, name)
+    }
+
+    /// Connection of a database qualifier, or of a view's `domain.view_name` reference
+    fn connection_id(&self, qualifier: &str) -> Option<&String> {
+        self.connection_map.get(qualifier).or_else(|| {
+            self.views
+                .iter()
+                .find(|view| view.reference() == qualifier)
+                .map(|view| &view.connection_id)
+        })
+    }
+
+    /// Connection IDs of every table the query reads, including CTE bodies and subqueries
+    fn query_connections(
         &self,
-        tables: &[(String, Option<String>, String)],
-    ) -> Result<HashMap<String, Vec<String>>, AppError> {
-        let mut databases: HashMap<String, Vec<String>> = HashMap::new();
+        query: &sqlparser::ast::Query,
+        ctes: &CteScope,
+    ) -> Result<std::collections::HashSet<String>, AppError> {
+        let mut connections = std::collections::HashSet::new();
+        let flow = visit_relations(query, |name| {
+            if ctes.get(&name.to_string()).is_some() {
+                return ControlFlow::Continue(());
+            }
+            if let Some(view) = self.view(name) {
+                connections.insert(view.connection_id.clone());
+                return ControlFlow::Continue(());
+            }
+            match self.parse_table_name(name) {
+                Ok((qualifier, _)) => {
+                    connections.extend(self.connection_id(&qualifier).cloned());
+                    ControlFlow::Continue(())
+                }
+                Err(e) => ControlFlow::Break(e),
+            }
+        });
+        match flow {
+            ControlFlow::Break(e) => Err(e),
+            ControlFlow::Continue(()) => Ok(connections),
+        }
+    }
+
+    /// CTEs of the query, each mapped to the single connection its body reads from
+    ///
+    /// A CTE may read from tables and earlier CTEs of one connection; its body is sent
+    /// to that database as a `WITH` prefix of the sub-queries that use it.
+    ///
+    /// The scope starts with the views the query reads, so CTEs can read views too.
+    fn cte_scope(&self, query: &sqlparser::ast::Query) -> Result<CteScope, AppError> {
+        let mut scope = self.view_scope(query);
+        let Some(with) = &query.with else {
+            return Ok(scope);
+        };
+        scope.recursive = with.recursive;
+
+        // Definitions are sent as written, minus database qualifiers
+        let mut stripped = with.clone();
+        let _ = VisitMut::visit(&mut stripped, &mut QualifierStripper {
+            qualifiers: &self.connection_map,
+            views: &self.views,
+        });
+
+        for (cte, definition) in with.cte_tables.iter().zip(&stripped.cte_tables) {
+            let name = cte.alias.name.value.clone();
+            let mut qualifiers = std::collections::HashSet::new();
+            let flow = visit_relations(cte.query.as_ref(), |relation| {
+                if *relation == ObjectName::from(vec![cte.alias.name.clone()]) {
+                    // Recursive reference to itself
+                    return ControlFlow::Continue(());
+                }
+                match self.resolve_relation(relation, &scope) {
+                    Ok((qualifier, _)) => {
+                        qualifiers.insert(qualifier);
+                        ControlFlow::Continue(())
+                    }
+                    Err(e) => ControlFlow::Break(e),
+                }
+            });
+            if let ControlFlow::Break(e) = flow {
+                return Err(e);
+            }
+
+            let resolved: Vec<(&String, &String)> = qualifiers
+                .iter()
+                .filter_map(|q| self.connection_id(q).map(|conn_id| (q, conn_id)))
+                .collect();
+            let (qualifier, connection_id) = match resolved.first() {
+                Some((qualifier, conn_id)) if resolved.iter().all(|(_, other)| other == conn_id) => {
+                    ((*qualifier).clone(), (*conn_id).clone())
+                }
+                _ => {
+                    return Err(AppError::InvalidSql(format!(
+                        "CTE '{}' must read from exactly one database in a cross-database query",
+                        name
+                    )))
+                }
+            };
+
+            scope.ctes.push(CteSource {
+                name,
+                qualifier,
+                connection_id,
+                definition: definition.to_string(),
+                view: false,
+            });
+        }
+
+        Ok(scope)
+    }
+
+    /// Views the query reads, as CTEs defined by the view's query on its connection
+    fn view_scope(&self, query: &sqlparser::ast::Query) -> CteScope {
+        let mut scope = CteScope::default();
+        let _ = visit_relations(query, |relation| {
+            if let Some(view) = self.view(relation) {
+                if !scope.ctes.iter().any(|cte| cte.qualifier == view.reference()) {
+                    scope.ctes.push(CteSource {
+                        name: view.name.clone(),
+                        qualifier: view.reference(),
+                        connection_id: view.connection_id.clone(),
+                        definition: format!("{} AS ({})", view.name, view.query),
+                        view: true,
+                    });
+                }
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        scope
+    }
 
-        for (qualifier, _alias, table_name) in tables {
-            let conn_id = self.connection_map.get(qualifier)
-                .ok_or_else(|| AppError::Validation(format!("Unknown qualifier: {}", qualifier)))?;
+    /// Move scalar and IN subqueries of the SELECT list, WHERE and HAVING into sub-queries
+    ///
+    /// Each subquery is replaced by `SELECT * FROM subquery_<n>`, the table its result
+    /// is registered under for the merge. Correlated subqueries and subqueries reading
+    /// from more than one database are rejected.
+    fn extract_subqueries(
+        &self,
+        select: &mut sqlparser::ast::Select,
+        ctes: &CteScope,
+    ) -> Result<Vec<SubQuery>, AppError> {
+        let mut extractor = SubqueryExtractor {
+            planner: self,
+            ctes,
+            sub_queries: Vec::new(),
+        };
 
-            databases
-                .entry(conn_id.clone())
-                .or_insert_with(Vec::new)
-                .push(table_name.clone());
+        let mut flow = VisitMut::visit(&mut select.projection, &mut extractor);
+        if flow.is_continue() {
+            flow = VisitMut::visit(&mut select.selection, &mut extractor);
         }
+        if flow.is_continue() {
+            flow = VisitMut::visit(&mut select.having, &mut extractor);
+        }
+        match flow {
+            ControlFlow::Break(e) => Err(e),
+            ControlFlow::Continue(()) => Ok(extractor.sub_queries),
+        }
+    }
 
-        Ok(databases)
+    /// Plan an uncorrelated subquery as a sub-query named `subquery_<index>`
+    fn plan_subquery(
+        &self,
+        subquery: &sqlparser::ast::Query,
+        ctes: &CteScope,
+        index: usize,
+    ) -> Result<SubQuery, AppError> {
+        let mut relations = RelationCollector {
+            qualifiers: &self.connection_map,
+            names: Vec::new(),
+            own: std::collections::HashSet::new(),
+            referenced: std::collections::HashSet::new(),
+        };
+        let _ = Visit::visit(subquery, &mut relations);
+
+        if relations.referenced.iter().any(|name| !relations.own.contains(name)) {
+            return Err(AppError::InvalidSql(format!(
+                "Correlated subqueries are not supported in cross-database queries: {}",
+                subquery
+            )));
+        }
+
+        let mut qualifiers = Vec::new();
+        let mut tables = Vec::new();
+        let mut uses_cte = false;
+        for name in &relations.names {
+            let (qualifier, table) = self.resolve_relation(name, ctes)?;
+            uses_cte |= ctes.get(&name.to_string()).is_some();
+            qualifiers.push(qualifier);
+            tables.push(table);
+        }
+
+        let connections: std::collections::HashSet<_> =
+            qualifiers.iter().filter_map(|q| self.connection_id(q)).collect();
+        let connection_id = match connections.into_iter().collect::<Vec<_>>().as_slice() {
+            [connection_id] => (*connection_id).clone(),
+            _ => {
+                return Err(AppError::InvalidSql(format!(
+                    "Subquery must read from exactly one database in a cross-database query: {}",
+                    subquery
+                )))
+            }
+        };
+
+        let mut query = self.strip_qualifiers(&subquery.to_string())?;
+        if uses_cte && subquery.with.is_none() {
+            query = format!("{}{}", ctes.with_prefix(&connection_id), query);
+        } else {
+            query = ctes.with_views(&connection_id, query);
+        }
+
+        Ok(SubQuery {
+            connection_id,
+            database_type: "unknown".to_string(),
+            query,
+            tables,
+            result_alias: format!("subquery_{}", index),
+            pushed_predicates: vec![],
+            join_input: false,
+            estimated_rows: None,
+        })
     }
 
     /// Plan a single database query (no cross-database execution needed)
     fn plan_single_database_query(
         &self,
         tables: &[(String, Option<String>, String)],
+        ctes: &CteScope,
         request: &CrossDatabaseQueryRequest,
     ) -> Result<CrossDatabaseExecutionPlan, AppError> {
         let (qualifier, _alias, _table_name) = &tables[0];
-        let connection_id = self.connection_map.get(qualifier)
+        let connection_id = self.connection_id(qualifier)
             .ok_or_else(|| AppError::Validation(format!("Unknown qualifier: {}", qualifier)))?
             .clone();
 
-        // Strip qualifiers from query for single-database execution
-        let query_without_qualifiers = self.strip_qualifiers(&request.query)?;
+        // Strip qualifiers from query for single-database execution; views it reads become CTEs
+        let query_without_qualifiers = ctes.with_views(&connection_id, self.strip_qualifiers(&request.query)?);
 
         let sub_query = SubQuery {
             connection_id: connection_id.clone(),
@@ -243,85 +562,452 @@ impl CrossDatabaseQueryPlanner {
             query: query_without_qualifiers,
             tables: tables.iter().map(|(_, _, t)| t.clone()).collect(),
             result_alias: "result".to_string(),
+            pushed_predicates: vec![],
+            join_input: true,
+            estimated_rows: None,
         };
 
+        let (timeout_secs, limit_value) = self.limits(request);
         Ok(CrossDatabaseExecutionPlan {
             original_query: request.query.clone(),
             sub_queries: vec![sub_query],
             merge_strategy: MergeStrategy::None,
-            timeout_secs: request.timeout_secs.unwrap_or(60),
+            merge_clauses: MergeClauses::default(),
+            broadcast: None,
+            warnings: vec![],
+            timeout_secs,
             apply_limit: request.apply_limit.unwrap_or(true),
-            limit_value: request.limit_value.unwrap_or(1000),
+            limit_value,
         })
     }
 
     /// Plan a JOIN query across databases
+    ///
+    /// `nested` holds the sub-queries of subqueries already moved out of `select`.
     fn plan_join_query(
         &self,
+        query: &sqlparser::ast::Query,
         select: &sqlparser::ast::Select,
         tables: &[(String, Option<String>, String)],
+        ctes: &CteScope,
+        nested: Vec<SubQuery>,
         request: &CrossDatabaseQueryRequest,
     ) -> Result<CrossDatabaseExecutionPlan, AppError> {
-        let databases = self.identify_databases(tables)?;
-
         // Extract JOIN conditions from the SQL
         let join_conditions = self.extract_join_conditions(select, tables)?;
 
-        // Build a map of table alias to connection ID
-        let mut table_to_conn: HashMap<String, String> = HashMap::new();
-        for (qualifier, table_alias, table_name) in tables {
-            let conn_id = self.connection_map.get(qualifier).unwrap().clone();
-            let key = table_alias.clone().unwrap_or_else(|| table_name.clone());
-            table_to_conn.insert(key, conn_id);
+        // Determine merge strategy based on join type
+        if join_conditions.is_empty() && tables.len() > 1 {
+            // If no join conditions found, the executor falls back to a Cartesian product
+            tracing::warn!("No explicit JOIN conditions found, using placeholder");
         }
+        let merge_strategy = self.join_merge_strategy(select, join_conditions)?;
+
+        // Filter each table in its own database where the WHERE clause allows it
+        let mut pushed = self.pushdown_predicates(select, tables, &merge_strategy);
+
+        // Generate one sub-query per table, in FROM/JOIN order, so the executor can
+        // join them by the aliases the JOIN conditions refer to
+        let mut sub_queries: Vec<SubQuery> = Vec::new();
+        for (qualifier, alias, table_name) in tables {
+            let conn_id = self.connection_id(qualifier)
+                .ok_or_else(|| AppError::Validation(format!("Unknown qualifier: {}", qualifier)))?
+                .clone();
+            let result_alias = relation_name(alias, table_name);
+            if sub_queries.iter().any(|q| q.result_alias == result_alias) {
+                return Err(AppError::InvalidSql(format!(
+                    "Table reference '{}' is used more than once; give each occurrence its own alias",
+                    result_alias
+                )));
+            }
 
-        // Generate sub-queries for each database
-        let mut sub_queries = Vec::new();
-        for (conn_id, table_names) in databases {
-            // For cross-database JOINs, we need to fetch all data from each table
-            // TODO: Implement predicate pushdown for optimization
-            let query = format!("SELECT * FROM {}", table_names.join(", "));
-
-            // Find the table alias for this sub-query
-            let result_alias = tables
-                .iter()
-                .find(|(q, _, t)| {
-                    let cid = self.connection_map.get(q).unwrap();
-                    cid == &conn_id && table_names.contains(t)
-                })
-                .and_then(|(_, a, t)| a.clone().or_else(|| Some(t.clone())))
-                .unwrap_or_else(|| format!("result_{}", conn_id));
+            // For cross-database JOINs, we fetch every row that survives the pushed predicates
+            let from = match alias {
+                Some(alias) => format!("{} AS {}", table_name, alias),
+                None => table_name.clone(),
+            };
+            // A CTE or view is read through its definition, sent along as a WITH clause
+            let with = match ctes.defines(table_name) {
+                Some(_) => ctes.with_prefix(&conn_id),
+                None => String::new(),
+            };
+            let pushed_predicates = pushed.remove(&result_alias).unwrap_or_default();
+            // A CTE has no statistics; a table of the same name would give a wrong estimate
+            let estimated_rows = match ctes.defines(table_name) {
+                Some(_) => None,
+                None => self.cost_model.estimate_rows(&conn_id, table_name, &pushed_predicates),
+            };
+            let query = match pushed_predicates.len() {
+                0 => format!("{}SELECT * FROM {}", with, from),
+                1 => format!("{}SELECT * FROM {} WHERE {}", with, from, pushed_predicates[0]),
+                _ => format!("{}SELECT * FROM {} WHERE ({})", with, from, pushed_predicates.join(") AND (")),
+            };
+
+            if !pushed_predicates.is_empty() {
+                tracing::debug!(
+                    connection_id = %conn_id,
+                    table = %result_alias,
+                    predicates = pushed_predicates.len(),
+                    "Pushed WHERE predicates into sub-query"
+                );
+            }
 
             sub_queries.push(SubQuery {
-                connection_id: conn_id.clone(),
+                connection_id: conn_id,
                 database_type: "unknown".to_string(),
                 query,
-                tables: table_names,
+                tables: vec![table_name.clone()],
                 result_alias,
+                pushed_predicates,
+                join_input: true,
+                estimated_rows,
             });
         }
 
-        // Determine merge strategy based on join type
-        let merge_strategy = if !join_conditions.is_empty() {
-            MergeStrategy::InnerJoin {
-                conditions: join_conditions,
-            }
-        } else {
-            // If no join conditions found, try to infer from query
-            tracing::warn!("No explicit JOIN conditions found, using placeholder");
-            MergeStrategy::InnerJoin { conditions: vec![] }
-        };
+        // Projection, WHERE, grouping and ordering run over the joined rows
+        let mut merge_clauses = self.merge_clauses(query, tables);
+
+        if let MergeStrategy::InnerJoin { .. } = merge_strategy {
+            Self::order_joins_by_size(&mut sub_queries, &mut merge_clauses);
+        }
+        let broadcast = self.choose_broadcast(&sub_queries, &merge_strategy);
+        let warnings = self.transfer_warnings(&sub_queries, broadcast.as_ref());
+        sub_queries.extend(nested);
 
+        let (timeout_secs, limit_value) = self.limits(request);
         Ok(CrossDatabaseExecutionPlan {
             original_query: request.query.clone(),
             sub_queries,
             merge_strategy,
-            timeout_secs: request.timeout_secs.unwrap_or(60),
+            merge_clauses,
+            broadcast,
+            warnings,
+            timeout_secs,
             apply_limit: request.apply_limit.unwrap_or(true),
-            limit_value: request.limit_value.unwrap_or(1000),
+            limit_value,
         })
     }
 
+    /// Join the tables of an inner join smallest first
+    ///
+    /// The executor joins tables in sub-query order, so the smallest table starts the
+    /// join tree and each step adds the smallest table linked to it. Only reorders when
+    /// every table has an estimate. `*` in the projection is expanded to the tables in
+    /// their original order, keeping the result's columns where the query put them.
+    fn order_joins_by_size(sub_queries: &mut [SubQuery], merge_clauses: &mut MergeClauses) {
+        if sub_queries.iter().any(|q| q.estimated_rows.is_none()) {
+            return;
+        }
+        let original: Vec<String> = sub_queries.iter().map(|q| q.result_alias.clone()).collect();
+        sub_queries.sort_by_key(|q| q.estimated_rows);
+        if sub_queries.iter().map(|q| &q.result_alias).eq(original.iter()) {
+            return;
+        }
+
+        merge_clauses.projection = std::mem::take(&mut merge_clauses.projection)
+            .into_iter()
+            .flat_map(|item| match item.as_str() {
+                "*" => original
+                    .iter()
+                    .map(|alias| format!("{}.*", Ident::with_quote('"', alias)))
+                    .collect(),
+                _ => vec![item],
+            })
+            .collect();
+
+        tracing::debug!(
+            order = ?sub_queries.iter().map(|q| q.result_alias.as_str()).collect::<Vec<_>>(),
+            "Ordered JOIN by estimated table size"
+        );
+    }
+
+    /// Pick a join whose smaller table's keys can filter the larger table
+    ///
+    /// The smaller (build) side must be estimated at no more than `broadcast_max_rows`
+    /// and the other (probe) side must be larger. Outer joins may only filter the
+    /// NULL-supplying side by the keys of the preserved side; FULL joins preserve both
+    /// and never broadcast. Among candidates the largest probe side wins.
+    fn choose_broadcast(&self, sub_queries: &[SubQuery], merge_strategy: &MergeStrategy) -> Option<BroadcastJoin> {
+        if self.broadcast_max_rows == 0 {
+            return None;
+        }
+        let (conditions, preserved) = match merge_strategy {
+            MergeStrategy::InnerJoin { conditions } => (conditions, None),
+            MergeStrategy::LeftJoin { conditions } => (conditions, sub_queries.first()),
+            MergeStrategy::RightJoin { conditions } => (conditions, sub_queries.last()),
+            _ => return None,
+        };
+        let estimate = |alias: &str| {
+            sub_queries
+                .iter()
+                .find(|q| q.result_alias == alias)
+                .and_then(|q| q.estimated_rows)
+        };
+
+        conditions
+            .iter()
+            .flat_map(|c| {
+                [
+                    (&c.left_alias, &c.left_column, &c.right_alias, &c.right_column),
+                    (&c.right_alias, &c.right_column, &c.left_alias, &c.left_column),
+                ]
+            })
+            .filter(|(build, ..)| preserved.is_none_or(|q| q.result_alias == **build))
+            .filter_map(|(build, build_column, probe, probe_column)| {
+                let (build_rows, probe_rows) = (estimate(build)?, estimate(probe)?);
+                (build_rows <= self.broadcast_max_rows && build_rows < probe_rows).then(|| {
+                    let broadcast = BroadcastJoin {
+                        build_alias: build.clone(),
+                        build_column: build_column.clone(),
+                        probe_alias: probe.clone(),
+                        probe_column: probe_column.clone(),
+                    };
+                    (probe_rows, std::cmp::Reverse(build_rows), broadcast)
+                })
+            })
+            .max_by_key(|(probe_rows, build_rows, _)| (*probe_rows, *build_rows))
+            .map(|(_, _, broadcast)| broadcast)
+    }
+
+    /// Warning when the sub-queries are estimated to fetch more rows than allowed
+    ///
+    /// A broadcast probe side is expected to return the rows matching the build
+    /// side's keys when the probe column's distinct count is known.
+    fn transfer_warnings(&self, sub_queries: &[SubQuery], broadcast: Option<&BroadcastJoin>) -> Vec<String> {
+        if self.transfer_warning_rows == 0 {
+            return vec![];
+        }
+
+        let total: u64 = sub_queries
+            .iter()
+            .filter_map(|q| {
+                let rows = q.estimated_rows?;
+                let Some(broadcast) = broadcast.filter(|b| b.probe_alias == q.result_alias) else {
+                    return Some(rows);
+                };
+                let keys = sub_queries
+                    .iter()
+                    .find(|b| b.result_alias == broadcast.build_alias)
+                    .and_then(|b| b.estimated_rows)?;
+                let distinct = q.tables.first().and_then(|table| {
+                    self.cost_model.distinct_count(&q.connection_id, table, &broadcast.probe_column)
+                });
+                Some(match distinct {
+                    Some(distinct) if keys < distinct => rows.saturating_mul(keys) / distinct,
+                    _ => rows,
+                })
+            })
+            .sum();
+
+        if total <= self.transfer_warning_rows {
+            return vec![];
+        }
+        tracing::warn!(
+            estimated_rows = total,
+            threshold = self.transfer_warning_rows,
+            "Cross-database plan exceeds the transfer threshold"
+        );
+        vec![format!(
+            "An estimated {} rows will be transferred from the databases (warning threshold {}); \
+             add WHERE filters on single tables so they run in their databases",
+            total, self.transfer_warning_rows
+        )]
+    }
+
+    /// Pick the merge strategy matching the JOIN keyword of the query
+    ///
+    /// All joins must use the same type because the executor applies one join type
+    /// to every table it merges. Comma-separated tables and CROSS JOINs merge as inner joins.
+    fn join_merge_strategy(
+        &self,
+        select: &sqlparser::ast::Select,
+        conditions: Vec<JoinCondition>,
+    ) -> Result<MergeStrategy, AppError> {
+        use sqlparser::ast::JoinOperator;
+
+        let mut join_type: Option<&'static str> = None;
+        for join in select.from.iter().flat_map(|t| &t.joins) {
+            let current = match &join.join_operator {
+                JoinOperator::Join(_) | JoinOperator::Inner(_) | JoinOperator::CrossJoin(_) => "INNER",
+                JoinOperator::Left(_) | JoinOperator::LeftOuter(_) => "LEFT",
+                JoinOperator::Right(_) | JoinOperator::RightOuter(_) => "RIGHT",
+                JoinOperator::FullOuter(_) => "FULL OUTER",
+                other => {
+                    return Err(AppError::InvalidSql(format!(
+                        "Unsupported join type for cross-database execution: {:?}",
+                        other
+                    )))
+                }
+            };
+
+            match join_type {
+                Some(previous) if previous != current => {
+                    return Err(AppError::InvalidSql(format!(
+                        "Mixing {} JOIN and {} JOIN is not supported for cross-database execution",
+                        previous, current
+                    )));
+                }
+                _ => join_type = Some(current),
+            }
+        }
+
+        Ok(match join_type.unwrap_or("INNER") {
+            "LEFT" => MergeStrategy::LeftJoin { conditions },
+            "RIGHT" => MergeStrategy::RightJoin { conditions },
+            "FULL OUTER" => MergeStrategy::FullOuterJoin { conditions },
+            _ => MergeStrategy::InnerJoin { conditions },
+        })
+    }
+
+    /// WHERE conjuncts that can run inside a single table's sub-query, keyed by relation
+    /// name (alias, or table name when unaliased)
+    ///
+    /// A conjunct is pushed when every column it reads is qualified with the same
+    /// relation and it only uses operators all supported databases share. Tables on
+    /// the NULL-supplying side of an outer join are skipped, as filtering them before
+    /// the join would keep rows the WHERE clause removes.
+    fn pushdown_predicates(
+        &self,
+        select: &sqlparser::ast::Select,
+        tables: &[(String, Option<String>, String)],
+        merge_strategy: &MergeStrategy,
+    ) -> HashMap<String, Vec<String>> {
+        let mut pushed: HashMap<String, Vec<String>> = HashMap::new();
+        let Some(selection) = &select.selection else {
+            return pushed;
+        };
+
+        // Relations whose rows the join keeps
+        let last = tables.len().saturating_sub(1);
+        let preserved: std::collections::HashSet<String> = tables
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| match merge_strategy {
+                MergeStrategy::LeftJoin { .. } => *idx == 0,
+                MergeStrategy::RightJoin { .. } => *idx == last,
+                MergeStrategy::FullOuterJoin { .. } => false,
+                _ => true,
+            })
+            .map(|(_, (_, alias, table))| relation_name(alias, table))
+            .collect();
+
+        let mut conjuncts = Vec::new();
+        Self::split_conjunction(selection, &mut conjuncts);
+
+        for conjunct in conjuncts {
+            let mut referenced = std::collections::HashSet::new();
+            if !Self::pushable_relations(conjunct, &mut referenced) {
+                continue;
+            }
+            if let [relation] = referenced.into_iter().collect::<Vec<_>>().as_slice() {
+                if preserved.contains(relation.as_str()) {
+                    pushed.entry(relation.clone()).or_default().push(conjunct.to_string());
+                }
+            }
+        }
+
+        pushed
+    }
+
+    /// Split `a AND (b AND c)` into `[a, b, c]`
+    fn split_conjunction<'a>(expr: &'a sqlparser::ast::Expr, conjuncts: &mut Vec<&'a sqlparser::ast::Expr>) {
+        use sqlparser::ast::{BinaryOperator, Expr};
+
+        match expr {
+            Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+                Self::split_conjunction(left, conjuncts);
+                Self::split_conjunction(right, conjuncts);
+            }
+            Expr::Nested(inner) if matches!(inner.as_ref(), Expr::BinaryOp { op: BinaryOperator::And, .. }) => {
+                Self::split_conjunction(inner, conjuncts);
+            }
+            _ => conjuncts.push(expr),
+        }
+    }
+
+    /// Collect the relations a predicate reads; false if it cannot be pushed down
+    ///
+    /// Unqualified columns are ambiguous across sub-queries, and functions, casts
+    /// and subqueries may not exist or behave the same in every database.
+    fn pushable_relations(expr: &sqlparser::ast::Expr, relations: &mut std::collections::HashSet<String>) -> bool {
+        use sqlparser::ast::Expr;
+
+        match expr {
+            Expr::CompoundIdentifier(idents) if idents.len() == 2 => {
+                relations.insert(idents[0].value.clone());
+                true
+            }
+            Expr::Value(_) => true,
+            Expr::Nested(inner)
+            | Expr::UnaryOp { expr: inner, .. }
+            | Expr::IsNull(inner)
+            | Expr::IsNotNull(inner)
+            | Expr::IsTrue(inner)
+            | Expr::IsFalse(inner)
+            | Expr::IsNotTrue(inner)
+            | Expr::IsNotFalse(inner) => Self::pushable_relations(inner, relations),
+            Expr::BinaryOp { left, right, .. } => {
+                Self::pushable_relations(left, relations) && Self::pushable_relations(right, relations)
+            }
+            Expr::Between { expr, low, high, .. } => {
+                Self::pushable_relations(expr, relations)
+                    && Self::pushable_relations(low, relations)
+                    && Self::pushable_relations(high, relations)
+            }
+            Expr::InList { expr, list, .. } => {
+                Self::pushable_relations(expr, relations)
+                    && list.iter().all(|item| Self::pushable_relations(item, relations))
+            }
+            Expr::Like { expr, pattern, escape_char: None, .. } => {
+                Self::pushable_relations(expr, relations) && Self::pushable_relations(pattern, relations)
+            }
+            _ => false,
+        }
+    }
+
+    /// Clauses of the original query to evaluate over the merged rows
+    ///
+    /// `tables` are the relations registered for the merge; `alias.*` wildcards are
+    /// rewritten to them. Set operations only carry their ORDER BY.
+    fn merge_clauses(
+        &self,
+        query: &sqlparser::ast::Query,
+        tables: &[(String, Option<String>, String)],
+    ) -> MergeClauses {
+        let relations: Vec<String> = tables
+            .iter()
+            .map(|(_, alias, table)| relation_name(alias, table))
+            .collect();
+        let mut query = query.clone();
+        let _ = VisitMut::visit(&mut query, &mut MergeClauseRenderer {
+            stripper: QualifierStripper {
+                qualifiers: &self.connection_map,
+                views: &self.views,
+            },
+            relations: &relations,
+        });
+
+        let order_by = query.order_by.as_ref().map(|order_by| order_by.to_string());
+        let SetExpr::Select(select) = query.body.as_ref() else {
+            return MergeClauses {
+                order_by,
+                ..MergeClauses::default()
+            };
+        };
+
+        MergeClauses {
+            distinct: select.distinct.as_ref().map(|distinct| distinct.to_string()),
+            projection: select.projection.iter().map(|item| item.to_string()).collect(),
+            selection: select.selection.as_ref().map(|expr| expr.to_string()),
+            group_by: match &select.group_by {
+                GroupByExpr::Expressions(exprs, _) if exprs.is_empty() => None,
+                group_by => Some(group_by.to_string()),
+            },
+            having: select.having.as_ref().map(|expr| expr.to_string()),
+            order_by,
+        }
+    }
+
     /// Plan a UNION query across databases
     fn plan_union_query(
         &self,
@@ -343,6 +1029,7 @@ impl CrossDatabaseQueryPlanner {
 
         // Generate sub-queries for each SELECT in the UNION
         let mut sub_queries = Vec::new();
+        let views = self.view_scope(query);
 
         for (idx, select_sql) in select_queries.iter().enumerate() {
             // Parse each SELECT to identify tables
@@ -351,7 +1038,7 @@ impl CrossDatabaseQueryPlanner {
 
             if let Some(Statement::Query(q)) = parsed.first() {
                 if let SetExpr::Select(select) = &*q.body {
-                    let tables = self.extract_tables(select)?;
+                    let tables = self.extract_tables(select, &CteScope::default())?;
 
                     if tables.is_empty() {
                         continue;
@@ -359,12 +1046,12 @@ impl CrossDatabaseQueryPlanner {
 
                     // Get connection ID for this query
                     let (qualifier, _, _) = &tables[0];
-                    let connection_id = self.connection_map.get(qualifier)
+                    let connection_id = self.connection_id(qualifier)
                         .ok_or_else(|| AppError::Validation(format!("Unknown qualifier: {}", qualifier)))?
                         .clone();
 
                     // Strip qualifiers from SELECT SQL
-                    let stripped_sql = self.strip_qualifiers(select_sql)?;
+                    let stripped_sql = views.with_views(&connection_id, self.strip_qualifiers(select_sql)?);
 
                     sub_queries.push(SubQuery {
                         connection_id: connection_id.clone(),
@@ -372,6 +1059,9 @@ impl CrossDatabaseQueryPlanner {
                         query: stripped_sql,
                         tables: tables.iter().map(|(_, _, t)| t.clone()).collect(),
                         result_alias: format!("union_part_{}", idx),
+                        pushed_predicates: vec![],
+                        join_input: true,
+                        estimated_rows: None,
                     });
                 }
             }
@@ -383,13 +1073,21 @@ impl CrossDatabaseQueryPlanner {
 
         let merge_strategy = MergeStrategy::Union { all: is_union_all };
 
+        // Only the trailing ORDER BY applies to the whole UNION
+        let merge_clauses = self.merge_clauses(query, &[]);
+        let (broadcast, warnings) = (None, vec![]);
+
+        let (timeout_secs, limit_value) = self.limits(request);
         Ok(CrossDatabaseExecutionPlan {
             original_query: request.query.clone(),
             sub_queries,
             merge_strategy,
-            timeout_secs: request.timeout_secs.unwrap_or(60),
+            merge_clauses,
+            broadcast,
+            warnings,
+            timeout_secs,
             apply_limit: request.apply_limit.unwrap_or(true),
-            limit_value: request.limit_value.unwrap_or(1000),
+            limit_value,
         })
     }
 
@@ -453,27 +1151,29 @@ impl CrossDatabaseQueryPlanner {
     ///
     /// Converts "SELECT * FROM db1.users" to "SELECT * FROM users"
     /// This is needed when executing single-database queries
+    ///
+    /// Only table names, `db.table.column` references and `db.table.*` wildcards are
+    /// rewritten, so string literals and column names that contain a qualifier are kept.
     fn strip_qualifiers(&self, sql: &str) -> Result<String, AppError> {
         // Parse the SQL query
-        let statements = Parser::parse_sql(&GenericDialect {}, sql)
+        let mut statements = Parser::parse_sql(&GenericDialect {}, sql)
             .map_err(|e| AppError::InvalidSql(format!("Failed to parse query: {}", e)))?;
 
         if statements.is_empty() {
             return Err(AppError::InvalidSql("Empty query".to_string()));
         }
 
-        // For now, use a simple string replacement approach
-        // TODO: Implement proper AST rewriting for complex queries
-        let mut result = sql.to_string();
-
-        // Replace all qualified table names with unqualified names
-        for (alias, _conn_id) in &self.connection_map {
-            // Replace "alias.table" with "table"
-            let pattern = format!("{}.", alias);
-            result = result.replace(&pattern, "");
-        }
+        let mut stripper = QualifierStripper {
+            qualifiers: &self.connection_map,
+            views: &self.views,
+        };
+        let _ = VisitMut::visit(&mut statements, &mut stripper);
 
-        Ok(result)
+        Ok(statements
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect::<Vec<_>>()
+            .join("; "))
     }
 
     /// Extract JOIN conditions from SELECT statement
@@ -492,11 +1192,7 @@ impl CrossDatabaseQueryPlanner {
         // Build a map of table names to their aliases for quick lookup
         let mut table_aliases: HashMap<String, String> = HashMap::new();
         for (_, alias, table_name) in tables {
-            if let Some(ref a) = alias {
-                table_aliases.insert(table_name.clone(), a.clone());
-            } else {
-                table_aliases.insert(table_name.clone(), table_name.clone());
-            }
+            table_aliases.insert(table_name.clone(), relation_name(alias, table_name));
         }
 
         // Iterate through FROM clause tables with joins
@@ -504,7 +1200,13 @@ impl CrossDatabaseQueryPlanner {
             for join in &table_with_joins.joins {
                 // Extract join conditions based on join constraint
                 match &join.join_operator {
-                    JoinOperator::Inner(constraint) | JoinOperator::LeftOuter(constraint) | JoinOperator::RightOuter(constraint) => {
+                    JoinOperator::Join(constraint)
+                    | JoinOperator::Inner(constraint)
+                    | JoinOperator::Left(constraint)
+                    | JoinOperator::LeftOuter(constraint)
+                    | JoinOperator::Right(constraint)
+                    | JoinOperator::RightOuter(constraint)
+                    | JoinOperator::FullOuter(constraint) => {
                         if let JoinConstraint::On(expr) = constraint {
                             // Parse the ON expression to extract ALL conditions (including AND chains)
                             self.parse_all_join_conditions(expr, &table_aliases, &mut conditions)?;
@@ -635,6 +1337,288 @@ impl CrossDatabaseQueryPlanner {
     }
 }
 
+/// CTEs of a cross-database query
+#[derive(Default)]
+struct CteScope {
+    /// `WITH RECURSIVE`
+    recursive: bool,
+    /// In definition order
+    ctes: Vec<CteSource>,
+}
+
+/// A CTE whose body reads from a single connection
+struct CteSource {
+    name: String,
+    /// Qualifier of the tables the body reads
+    qualifier: String,
+    connection_id: String,
+    /// `name AS (...)` with database qualifiers removed
+    definition: String,
+    /// A view the query reads, rather than a CTE it defines
+    view: bool,
+}
+
+impl CteScope {
+    /// CTE of the query, by the name the query uses
+    fn get(&self, name: &str) -> Option<&CteSource> {
+        self.ctes.iter().find(|cte| !cte.view && cte.name == name)
+    }
+
+    /// CTE or view a sub-query reads by its table name
+    fn defines(&self, name: &str) -> Option<&CteSource> {
+        self.ctes.iter().find(|cte| cte.name == name)
+    }
+
+    /// `query` with the definitions of the views of a connection added to its WITH clause
+    fn with_views(&self, connection_id: &str, query: String) -> String {
+        let definitions: Vec<&str> = self
+            .ctes
+            .iter()
+            .filter(|cte| cte.view && cte.connection_id == connection_id)
+            .map(|cte| cte.definition.as_str())
+            .collect();
+        if definitions.is_empty() {
+            return query;
+        }
+        for keyword in ["WITH RECURSIVE ", "WITH "] {
+            if let Some(rest) = query.strip_prefix(keyword) {
+                return format!("{}{}, {}", keyword, definitions.join(", "), rest);
+            }
+        }
+        format!("WITH {} {}", definitions.join(", "), query)
+    }
+
+    /// `WITH ...` clause defining the CTEs of a connection, or "" if it has none
+    fn with_prefix(&self, connection_id: &str) -> String {
+        let definitions: Vec<&str> = self
+            .ctes
+            .iter()
+            .filter(|cte| cte.connection_id == connection_id)
+            .map(|cte| cte.definition.as_str())
+            .collect();
+        match (definitions.is_empty(), self.recursive) {
+            (true, _) => String::new(),
+            (false, true) => format!("WITH RECURSIVE {} ", definitions.join(", ")),
+            (false, false) => format!("WITH {} ", definitions.join(", ")),
+        }
+    }
+}
+
+/// Replaces scalar and IN subqueries with reads of the sub-query results registered for the merge
+struct SubqueryExtractor<'a> {
+    planner: &'a CrossDatabaseQueryPlanner,
+    ctes: &'a CteScope,
+    sub_queries: Vec<SubQuery>,
+}
+
+impl VisitorMut for SubqueryExtractor<'_> {
+    type Break = AppError;
+
+    fn pre_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<Self::Break> {
+        let subquery = match expr {
+            Expr::Subquery(subquery) | Expr::InSubquery { subquery, .. } => subquery,
+            _ => return ControlFlow::Continue(()),
+        };
+
+        let planned = self
+            .planner
+            .plan_subquery(subquery, self.ctes, self.sub_queries.len())
+            .and_then(|sub_query| {
+                let sql = format!("SELECT * FROM {}", sub_query.result_alias);
+                let parsed = Parser::parse_sql(&GenericDialect {}, &sql)
+                    .map_err(|e| AppError::InvalidSql(format!("Failed to parse query: {}", e)))?;
+                match parsed.into_iter().next() {
+                    Some(Statement::Query(placeholder)) => Ok((sub_query, placeholder)),
+                    _ => Err(AppError::InvalidSql(format!("Failed to build subquery placeholder: {}", sql))),
+                }
+            });
+        match planned {
+            Ok((sub_query, placeholder)) => {
+                *subquery = placeholder;
+                self.sub_queries.push(sub_query);
+                ControlFlow::Continue(())
+            }
+            Err(e) => ControlFlow::Break(e),
+        }
+    }
+}
+
+/// Tables a subquery reads and the relations its columns are qualified with
+struct RelationCollector<'a> {
+    qualifiers: &'a HashMap<String, String>,
+    /// Table names as written
+    names: Vec<ObjectName>,
+    /// Aliases and table names defined inside the subquery
+    own: std::collections::HashSet<String>,
+    /// Relations named in `relation.column` references
+    referenced: std::collections::HashSet<String>,
+}
+
+impl Visitor for RelationCollector<'_> {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        match table_factor {
+            TableFactor::Table { name, alias, .. } => {
+                if let Some(last) = name.0.last() {
+                    self.own.insert(last.to_string());
+                }
+                if let Some(alias) = alias {
+                    self.own.insert(alias.name.value.clone());
+                }
+                self.names.push(name.clone());
+            }
+            TableFactor::Derived { alias: Some(alias), .. } => {
+                self.own.insert(alias.name.value.clone());
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        if let Expr::CompoundIdentifier(idents) = expr {
+            // `db.table.column` names the table after the qualifier
+            let relation = match idents.len() {
+                2 => Some(&idents[0]),
+                n if n >= 3 && self.qualifiers.contains_key(&idents[0].to_string()) => Some(&idents[1]),
+                _ => None,
+            };
+            if let Some(relation) = relation {
+                self.referenced.insert(relation.value.clone());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// View a `domain.view_name` relation refers to
+fn view_named<'v>(views: &'v [VirtualView], name: &ObjectName) -> Option<&'v VirtualView> {
+    let [ObjectNamePart::Identifier(schema), ObjectNamePart::Identifier(view)] = name.0.as_slice() else {
+        return None;
+    };
+    views.iter().find(|v| v.schema == schema.value && v.name == view.value)
+}
+
+/// Name a table's rows are registered under in the merge: its alias, or the
+/// table name without its schema
+fn relation_name(alias: &Option<String>, table: &str) -> String {
+    match alias {
+        Some(alias) => alias.clone(),
+        None => table.rsplit('.').next().unwrap_or(table).to_string(),
+    }
+}
+
+/// Removes database qualifiers (keys of the planner's connection map) and view
+/// domains from an AST
+struct QualifierStripper<'a> {
+    qualifiers: &'a HashMap<String, String>,
+    views: &'a [VirtualView],
+}
+
+impl QualifierStripper<'_> {
+    /// `db1.users` => `users`, `sales.revenue` => `revenue`; one-part names and unknown qualifiers are kept
+    fn strip_object_name(&self, name: &mut ObjectName) {
+        if name.0.len() >= 2 && self.qualifiers.contains_key(&name.0[0].to_string()) {
+            name.0.remove(0);
+        } else if view_named(self.views, name).is_some() {
+            name.0.remove(0);
+        }
+    }
+
+    /// `db1.users.*` in the projections of a query block
+    fn strip_wildcards(&self, body: &mut SetExpr) {
+        match body {
+            SetExpr::Select(select) => {
+                for item in &mut select.projection {
+                    if let SelectItem::QualifiedWildcard(SelectItemQualifiedWildcardKind::ObjectName(name), _) = item {
+                        self.strip_object_name(name);
+                    }
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.strip_wildcards(left);
+                self.strip_wildcards(right);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rewrites the original query's clauses to run over the merged in-memory tables
+///
+/// Qualifiers are stripped and every column, alias and `alias.*` reference is
+/// double-quoted, so DataFusion matches them case-sensitively against the column
+/// names the source databases returned.
+struct MergeClauseRenderer<'a> {
+    stripper: QualifierStripper<'a>,
+    /// Registered table names (alias, or table name when unaliased)
+    relations: &'a [String],
+}
+
+impl VisitorMut for MergeClauseRenderer<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &mut sqlparser::ast::Query) -> ControlFlow<Self::Break> {
+        self.stripper.strip_wildcards(&mut query.body);
+        if let SetExpr::Select(select) = query.body.as_mut() {
+            for item in &mut select.projection {
+                match item {
+                    SelectItem::ExprWithAlias { alias, .. } => alias.quote_style = Some('"'),
+                    SelectItem::QualifiedWildcard(SelectItemQualifiedWildcardKind::ObjectName(name), _) => {
+                        if let Some(relation) = self.relations.iter().find(|r| **r == name.to_string()) {
+                            *name = ObjectName::from(vec![Ident::with_quote('"', relation)]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<Self::Break> {
+        let _ = self.stripper.pre_visit_expr(expr);
+        match expr {
+            Expr::Identifier(ident) => ident.quote_style = Some('"'),
+            Expr::CompoundIdentifier(idents) => {
+                for ident in idents {
+                    ident.quote_style = Some('"');
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl VisitorMut for QualifierStripper<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &mut sqlparser::ast::Query) -> ControlFlow<Self::Break> {
+        self.strip_wildcards(&mut query.body);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_relation(&mut self, relation: &mut ObjectName) -> ControlFlow<Self::Break> {
+        self.strip_object_name(relation);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<Self::Break> {
+        // Two-part `x.col` is a table or alias reference; only `db.table.col` carries a qualifier
+        if let Expr::CompoundIdentifier(idents) = expr {
+            let qualified = idents.len() >= 3
+                && (self.qualifiers.contains_key(&idents[0].to_string())
+                    || self.views.iter().any(|v| v.schema == idents[0].value && v.name == idents[1].value));
+            if qualified {
+                idents.remove(0);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -655,6 +1639,37 @@ mod tests {
         assert!(matches!(plan.merge_strategy, MergeStrategy::None));
     }
 
+    #[test]
+    fn test_domain_settings_limit_timeout_and_rows() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let mut request = CrossDatabaseQueryRequest::new(
+            "SELECT * FROM conn1.users u JOIN conn2.orders o ON u.id = o.user_id".to_string(),
+            conn_ids.clone(),
+        );
+        request.timeout_secs = None;
+        request.limit_value = None;
+
+        // Without settings the defaults apply
+        let plan = CrossDatabaseQueryPlanner::new(conn_ids.clone()).plan_query(&request).unwrap();
+        assert_eq!((plan.timeout_secs, plan.limit_value), (30, 1000));
+
+        let mut strict = DomainSettings::defaults("strict".to_string());
+        strict.default_timeout_secs = 10;
+        strict.max_timeout_secs = 20;
+        strict.default_row_limit = 100;
+        strict.max_row_limit = 500;
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids)
+            .with_domain_settings(vec![DomainSettings::defaults("lenient".to_string()), strict]);
+
+        // The strictest domain's defaults and caps apply
+        let plan = planner.plan_query(&request).unwrap();
+        assert_eq!((plan.timeout_secs, plan.limit_value), (10, 100));
+        request.timeout_secs = Some(300);
+        request.limit_value = Some(5000);
+        let plan = planner.plan_query(&request).unwrap();
+        assert_eq!((plan.timeout_secs, plan.limit_value), (20, 500));
+    }
+
     #[test]
     fn test_cross_database_join() {
         let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
@@ -671,6 +1686,365 @@ mod tests {
         assert!(matches!(plan.merge_strategy, MergeStrategy::InnerJoin { .. }));
     }
 
+    #[test]
+    fn test_cross_database_outer_joins() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+        let plan = |join: &str| {
+            let request = CrossDatabaseQueryRequest::new(
+                format!("SELECT u.id, t.title FROM conn1.users u {} conn2.todos t ON u.id = t.user_id", join),
+                conn_ids.clone(),
+            );
+            planner.plan_query(&request).unwrap().merge_strategy
+        };
+
+        assert!(matches!(plan("LEFT JOIN"), MergeStrategy::LeftJoin { conditions } if conditions.len() == 1));
+        assert!(matches!(plan("LEFT OUTER JOIN"), MergeStrategy::LeftJoin { .. }));
+        assert!(matches!(plan("RIGHT JOIN"), MergeStrategy::RightJoin { conditions } if conditions.len() == 1));
+        assert!(matches!(plan("FULL OUTER JOIN"), MergeStrategy::FullOuterJoin { conditions } if conditions.len() == 1));
+        assert!(matches!(plan("INNER JOIN"), MergeStrategy::InnerJoin { conditions } if conditions.len() == 1));
+    }
+
+    #[test]
+    fn test_mixed_join_types_rejected() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT * FROM conn1.users u LEFT JOIN conn2.todos t ON u.id = t.user_id \
+             JOIN conn2.tags g ON t.id = g.todo_id"
+                .to_string(),
+            conn_ids,
+        );
+
+        assert!(matches!(planner.plan_query(&request), Err(AppError::InvalidSql(_))));
+    }
+
+    #[test]
+    fn test_predicate_pushdown() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+        let plan = |join: &str| {
+            let request = CrossDatabaseQueryRequest::new(
+                format!(
+                    "SELECT u.id, t.title FROM conn1.users u {} conn2.todos t ON u.id = t.user_id \
+                     WHERE u.active = true AND (t.status = 'open' OR t.status IS NULL) \
+                     AND u.created_at > t.created_at AND LOWER(u.name) = 'bob' AND id > 5",
+                    join
+                ),
+                conn_ids.clone(),
+            );
+            let plan = planner.plan_query(&request).unwrap();
+            let mut sub_queries = plan.sub_queries;
+            sub_queries.sort_by(|a, b| a.connection_id.cmp(&b.connection_id));
+            sub_queries
+        };
+
+        // Cross-table, function and unqualified predicates stay out of the sub-queries
+        let inner = plan("JOIN");
+        assert_eq!(inner[0].query, "SELECT * FROM users AS u WHERE u.active = true");
+        assert_eq!(inner[0].pushed_predicates, vec!["u.active = true"]);
+        assert_eq!(
+            inner[1].query,
+            "SELECT * FROM todos AS t WHERE (t.status = 'open' OR t.status IS NULL)"
+        );
+
+        // The NULL-supplying side of an outer join is fetched unfiltered
+        let left = plan("LEFT JOIN");
+        assert_eq!(left[0].pushed_predicates, vec!["u.active = true"]);
+        assert!(left[1].pushed_predicates.is_empty());
+        assert_eq!(left[1].query, "SELECT * FROM todos AS t");
+    }
+
+    #[test]
+    fn test_statistics_order_joins_and_choose_broadcast() {
+        use crate::models::{Column, Table};
+
+        let table = |name: &str, row_count: i64, key: &str, distinct_count: i64| Table {
+            name: name.to_string(),
+            schema: Some("public".to_string()),
+            columns: vec![Column {
+                name: key.to_string(),
+                data_type: "integer".to_string(),
+                is_nullable: false,
+                is_primary_key: false,
+                is_foreign_key: false,
+                default_value: None,
+                max_length: None,
+                description: None,
+                distinct_count: Some(distinct_count),
+                tags: Vec::new(),
+            }],
+            row_count: Some(row_count),
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        };
+        let mut cost_model = CrossDatabaseCostModel::default();
+        cost_model.add_connection("conn1", vec![table("events", 2_000_000, "user_id", 1_000)]);
+        cost_model.add_connection("conn2", vec![table("users", 1_000, "id", 1_000)]);
+        let config = CrossDatabaseConfig {
+            transfer_warning_rows: 500_000,
+            broadcast_max_rows: 10_000,
+            subquery_cache_enabled: false,
+            subquery_cache_ttl_secs: 300,
+            subquery_cache_max_bytes: 0,
+            insert_select_max_rows: 1_000_000,
+            insert_select_batch_rows: 1000,
+        };
+
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone()).with_cost_model(cost_model, &config);
+        let plan = |join: &str, filter: &str| {
+            let request = CrossDatabaseQueryRequest::new(
+                format!("SELECT * FROM conn1.events e {} conn2.users u ON e.user_id = u.id {}", join, filter),
+                conn_ids.clone(),
+            );
+            planner.plan_query(&request).unwrap()
+        };
+
+        // The smaller table starts the inner join; `*` keeps the written column order
+        let inner = plan("JOIN", "");
+        let aliases: Vec<_> = inner.sub_queries.iter().map(|q| q.result_alias.as_str()).collect();
+        assert_eq!(aliases, vec!["u", "e"]);
+        assert_eq!(inner.sub_queries[1].estimated_rows, Some(2_000_000));
+        assert_eq!(inner.merge_clauses.projection, vec![r#""e".*"#, r#""u".*"#]);
+        assert_eq!(
+            inner.broadcast,
+            Some(BroadcastJoin {
+                build_alias: "u".to_string(),
+                build_column: "id".to_string(),
+                probe_alias: "e".to_string(),
+                probe_column: "user_id".to_string(),
+            })
+        );
+        // Broadcasting every user id still matches every event
+        assert_eq!(inner.warnings.len(), 1);
+
+        // A pushed filter on users leaves only the events of three users to fetch
+        let filtered = plan("JOIN", "WHERE u.id IN (1, 2, 3)");
+        assert_eq!(filtered.sub_queries[0].estimated_rows, Some(3));
+        assert!(filtered.warnings.is_empty());
+
+        // A LEFT JOIN keeps its order and may only broadcast the preserved side
+        let left = plan("LEFT JOIN", "");
+        assert_eq!(left.sub_queries[0].result_alias, "e");
+        assert_eq!(left.broadcast, None);
+    }
+
+    #[test]
+    fn test_three_connection_join_plans_one_sub_query_per_table() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string(), "conn3".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT * FROM conn1.users u JOIN conn2.todos t ON u.id = t.user_id \
+             JOIN conn3.tags g ON t.id = g.todo_id JOIN conn2.projects ON projects.id = t.project_id"
+                .to_string(),
+            conn_ids,
+        );
+        let plan = planner.plan_query(&request).unwrap();
+
+        let aliases: Vec<_> = plan.sub_queries.iter().map(|q| q.result_alias.as_str()).collect();
+        assert_eq!(aliases, vec!["u", "t", "g", "projects"]);
+        assert_eq!(plan.sub_queries[3].connection_id, "conn2");
+        assert_eq!(plan.sub_queries[3].query, "SELECT * FROM projects");
+        assert!(matches!(plan.merge_strategy, MergeStrategy::InnerJoin { conditions } if conditions.len() == 3));
+    }
+
+    #[test]
+    fn test_strip_qualifiers_rewrites_names_only() {
+        let planner = CrossDatabaseQueryPlanner::new(vec!["db1".to_string()]);
+
+        let stripped = planner
+            .strip_qualifiers(
+                "SELECT db1.users.*, db1.users.id, u.db1_id FROM db1.users AS u \
+                 WHERE u.note = 'see db1.users' AND u.id IN (SELECT user_id FROM db1.orders)",
+            )
+            .unwrap();
+
+        assert_eq!(
+            stripped,
+            "SELECT users.*, users.id, u.db1_id FROM users AS u \
+             WHERE u.note = 'see db1.users' AND u.id IN (SELECT user_id FROM orders)"
+        );
+    }
+
+    #[test]
+    fn test_merge_clauses_keep_projection_grouping_and_ordering() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT DISTINCT u.userName, COUNT(todos.id) AS Open, conn2.todos.* FROM conn1.users u \
+             JOIN conn2.todos ON u.id = todos.user_id WHERE conn2.todos.done = false \
+             GROUP BY u.userName HAVING COUNT(todos.id) > 1 ORDER BY Open DESC"
+                .to_string(),
+            conn_ids,
+        );
+        let clauses = planner.plan_query(&request).unwrap().merge_clauses;
+
+        assert_eq!(clauses.distinct.as_deref(), Some("DISTINCT"));
+        assert_eq!(
+            clauses.projection,
+            vec![r#""u"."userName""#, r#"COUNT("todos"."id") AS "Open""#, r#""todos".*"#]
+        );
+        assert_eq!(clauses.selection.as_deref(), Some(r#""todos"."done" = false"#));
+        assert_eq!(clauses.group_by.as_deref(), Some(r#"GROUP BY "u"."userName""#));
+        assert_eq!(clauses.having.as_deref(), Some(r#"COUNT("todos"."id") > 1"#));
+        assert_eq!(clauses.order_by.as_deref(), Some(r#"ORDER BY "Open" DESC"#));
+    }
+
+    #[test]
+    fn test_cte_runs_on_its_connection() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+
+        let request = CrossDatabaseQueryRequest::new(
+            "WITH active AS (SELECT id, name FROM conn1.users WHERE active = true) \
+             SELECT a.name, t.title FROM active a JOIN conn2.todos t ON a.id = t.user_id"
+                .to_string(),
+            conn_ids.clone(),
+        );
+        let plan = planner.plan_query(&request).unwrap();
+
+        assert_eq!(plan.sub_queries.len(), 2);
+        assert_eq!(plan.sub_queries[0].connection_id, "conn1");
+        assert_eq!(
+            plan.sub_queries[0].query,
+            "WITH active AS (SELECT id, name FROM users WHERE active = true) SELECT * FROM active AS a"
+        );
+        assert_eq!(plan.sub_queries[1].query, "SELECT * FROM todos AS t");
+
+        // A CTE reading two databases cannot be sent to either
+        let request = CrossDatabaseQueryRequest::new(
+            "WITH x AS (SELECT * FROM conn1.users u JOIN conn2.todos t ON u.id = t.user_id) SELECT * FROM x"
+                .to_string(),
+            conn_ids,
+        );
+        assert!(matches!(planner.plan_query(&request), Err(AppError::InvalidSql(_))));
+    }
+
+    #[test]
+    fn test_subqueries_run_on_their_connection() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone());
+
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT u.name, (SELECT MAX(id) FROM conn2.todos) AS latest FROM conn1.users u \
+             WHERE u.id IN (SELECT t.user_id FROM conn2.todos t WHERE t.done = false)"
+                .to_string(),
+            conn_ids.clone(),
+        );
+        let plan = planner.plan_query(&request).unwrap();
+
+        let summary: Vec<_> = plan
+            .sub_queries
+            .iter()
+            .map(|q| (q.result_alias.as_str(), q.connection_id.as_str(), q.query.as_str(), q.join_input))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("u", "conn1", "SELECT * FROM users AS u", true),
+                ("subquery_0", "conn2", "SELECT MAX(id) FROM todos", false),
+                ("subquery_1", "conn2", "SELECT t.user_id FROM todos AS t WHERE t.done = false", false),
+            ]
+        );
+        assert_eq!(
+            plan.merge_clauses.projection,
+            vec![r#""u"."name""#, r#"(SELECT * FROM subquery_0) AS "latest""#]
+        );
+        assert_eq!(
+            plan.merge_clauses.selection.as_deref(),
+            Some(r#""u"."id" IN (SELECT * FROM subquery_1)"#)
+        );
+
+        // Correlated subqueries need rows from the outer query
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT u.name FROM conn1.users u \
+             WHERE u.id = (SELECT MAX(t.user_id) FROM conn2.todos t WHERE t.owner = u.name)"
+                .to_string(),
+            conn_ids,
+        );
+        assert!(matches!(planner.plan_query(&request), Err(AppError::InvalidSql(_))));
+    }
+
+    #[test]
+    fn test_views_run_on_their_connection() {
+        let conn_ids = vec!["conn1".to_string(), "conn2".to_string()];
+        let planner = CrossDatabaseQueryPlanner::new(conn_ids.clone()).with_views(vec![VirtualView {
+            schema: "sales".to_string(),
+            name: "revenue".to_string(),
+            connection_id: "conn3".to_string(),
+            query: "SELECT user_id, SUM(amount) AS total FROM orders GROUP BY user_id".to_string(),
+        }]);
+
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT u.name, r.total FROM conn1.users u JOIN sales.revenue r ON u.id = r.user_id WHERE r.total > 100"
+                .to_string(),
+            conn_ids.clone(),
+        );
+        let plan = planner.plan_query(&request).unwrap();
+
+        assert_eq!(plan.sub_queries.len(), 2);
+        assert_eq!(plan.sub_queries[0].query, "SELECT * FROM users AS u");
+        assert_eq!(plan.sub_queries[1].connection_id, "conn3");
+        assert_eq!(
+            plan.sub_queries[1].query,
+            "WITH revenue AS (SELECT user_id, SUM(amount) AS total FROM orders GROUP BY user_id) \
+             SELECT * FROM revenue AS r WHERE r.total > 100"
+        );
+
+        // A query reading only the view runs on the view's connection
+        let request = CrossDatabaseQueryRequest::new(
+            "SELECT * FROM sales.revenue WHERE sales.revenue.total > 10".to_string(),
+            conn_ids,
+        );
+        let plan = planner.plan_query(&request).unwrap();
+        assert!(matches!(plan.merge_strategy, MergeStrategy::None));
+        assert_eq!(plan.sub_queries[0].connection_id, "conn3");
+        assert_eq!(
+            plan.sub_queries[0].query,
+            "WITH revenue AS (SELECT user_id, SUM(amount) AS total FROM orders GROUP BY user_id) \
+             SELECT * FROM revenue WHERE revenue.total > 10"
+        );
+    }
+
+    #[test]
+    fn test_schema_qualified_tables() {
+        let query = "SELECT o.id, c.region FROM sales.public.orders o JOIN crm.crm.customers c ON o.customer_id = c.id";
+        assert_eq!(
+            CrossDatabaseQueryPlanner::catalog_qualifiers(query).unwrap(),
+            vec!["sales".to_string(), "crm".to_string()]
+        );
+
+        let aliases = HashMap::from([
+            ("sales".to_string(), "conn1".to_string()),
+            ("crm".to_string(), "conn2".to_string()),
+        ]);
+        let planner = CrossDatabaseQueryPlanner::with_aliases(aliases.clone());
+        let request = CrossDatabaseQueryRequest::with_aliases(
+            query.to_string(),
+            vec!["conn1".to_string(), "conn2".to_string()],
+            aliases,
+        );
+        let plan = planner.plan_query(&request).unwrap();
+        assert_eq!(plan.sub_queries[0].query, "SELECT * FROM public.orders AS o");
+        assert_eq!(plan.sub_queries[1].query, "SELECT * FROM crm.customers AS c");
+
+        // Unaliased tables are merged under their name without the schema
+        let request = CrossDatabaseQueryRequest::with_aliases(
+            "SELECT * FROM sales.public.orders".to_string(),
+            vec!["conn1".to_string()],
+            HashMap::from([("sales".to_string(), "conn1".to_string())]),
+        );
+        let plan = planner.plan_query(&request).unwrap();
+        assert_eq!(plan.sub_queries[0].tables, vec!["public.orders".to_string()]);
+        assert_eq!(relation_name(&None, "public.orders"), "orders");
+    }
+
     #[test]
     fn test_invalid_qualifier() {
         let conn_ids = vec!["conn1".to_string()];
@@ -1,13 +1,22 @@
 // DataFusion DialectTranslator
 //
 // Defines the trait and implementations for translating DataFusion SQL
-// to database-specific SQL dialects (PostgreSQL, MySQL, etc.)
+// to database-specific SQL dialects (PostgreSQL, MySQL, etc.). Built-in
+// dialects are plug-ins registered in the DatabaseDialectRegistry.
+
+use std::sync::Arc;
 
 use anyhow::{Result, Context, anyhow};
-use sqlparser::dialect::{PostgreSqlDialect, MySqlDialect, GenericDialect};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
 use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, TokenWithSpan, Tokenizer};
 use async_trait::async_trait;
 
+use super::dialect_registry::{DialectSpec, IntervalStyle};
+use crate::models::{RewriteKind, SqlRewrite};
+use crate::services::database::adapter::quote_identifier;
+
 /// Trait for translating DataFusion SQL to database-specific dialects
 ///
 /// Each database type implements this trait to handle dialect-specific
@@ -29,6 +38,13 @@ pub trait DialectTranslator: Send + Sync {
     /// Returns error if SQL cannot be parsed or translation fails
     async fn translate(&self, datafusion_sql: &str) -> Result<String>;
 
+    /// Translate and list the rewrites applied, for translation previews
+    ///
+    /// Translators that cannot report their rewrites return an empty list.
+    async fn translate_with_rewrites(&self, datafusion_sql: &str) -> Result<(String, Vec<SqlRewrite>)> {
+        Ok((self.translate(datafusion_sql).await?, Vec::new()))
+    }
+
     /// Check if a specific SQL feature is supported in this dialect
     fn supports_feature(&self, feature: SqlFeature) -> bool;
 }
@@ -52,146 +68,233 @@ pub enum SqlFeature {
     BacktickIdentifiers,
 }
 
-/// PostgreSQL dialect translator
-pub struct PostgreSQLDialectTranslator {
-    dialect: PostgreSqlDialect,
+/// Translator driven by a dialect plug-in (see `DatabaseDialectRegistry`)
+///
+/// Rewrites the token stream, so string literals and comments are never
+/// touched: quoted identifiers are re-quoted, mapped functions renamed, and
+/// CAST target types and INTERVAL literals written the dialect's way.
+pub struct SpecDialectTranslator {
+    spec: Arc<DialectSpec>,
 }
 
-impl PostgreSQLDialectTranslator {
-    pub fn new() -> Self {
-        Self {
-            dialect: PostgreSqlDialect {},
+impl SpecDialectTranslator {
+    pub fn new(spec: impl Into<Arc<DialectSpec>>) -> Self {
+        Self { spec: spec.into() }
+    }
+
+    /// The dialect plug-in this translator applies
+    pub fn spec(&self) -> &DialectSpec {
+        &self.spec
+    }
+
+    /// Rewrite `sql` token by token, listing the changes made
+    fn rewrite(&self, sql: &str) -> Result<(String, Vec<SqlRewrite>)> {
+        let tokens = Tokenizer::new(&GenericDialect {}, sql)
+            .tokenize_with_location()
+            .map_err(|e| anyhow!("Failed to tokenize SQL for {}: {}", self.spec.name, e))?;
+
+        let mut translated = String::with_capacity(sql.len());
+        let mut rewrites = Vec::new();
+        // Whether each open parenthesis is the argument list of a CAST
+        let mut parens: Vec<bool> = Vec::new();
+        let mut cast_type_next = false;
+        let mut previous: Option<&Token> = None;
+        for (index, TokenWithSpan { token, span }) in tokens.iter().enumerate() {
+            let mut rewritten = |kind: RewriteKind, original: String, replacement: String| {
+                rewrites.push(SqlRewrite {
+                    kind,
+                    original,
+                    translated: replacement.clone(),
+                    line: span.start.line,
+                    column: span.start.column,
+                });
+                replacement
+            };
+            let text = match token {
+                Token::Word(word) if word.quote_style.is_some() => {
+                    cast_type_next = false;
+                    let quoted = quote_identifier(&word.value, self.spec.identifier_quote);
+                    let original = quote_identifier(&word.value, word.quote_style.unwrap_or('"'));
+                    if quoted == original {
+                        quoted
+                    } else {
+                        rewritten(RewriteKind::IdentifierQuoting, original, quoted)
+                    }
+                }
+                Token::Word(word) => {
+                    let type_name = std::mem::take(&mut cast_type_next)
+                        .then(|| self.spec.type_name(&word.value))
+                        .flatten();
+                    let function = self.spec.function(&word.value);
+                    let called = matches!(next_significant(&tokens, index), Some(Token::LParen));
+                    cast_type_next = word.keyword == Keyword::AS && parens.last() == Some(&true);
+                    match (type_name, function) {
+                        (Some(type_name), _) => {
+                            rewritten(RewriteKind::TypeName, word.value.clone(), type_name.to_string())
+                        }
+                        (None, Some(function)) if called => {
+                            rewritten(RewriteKind::Function, word.value.clone(), function.to_string())
+                        }
+                        (None, Some(function)) if NILADIC_FUNCTIONS.contains(&word.keyword) => {
+                            rewritten(RewriteKind::Function, word.value.clone(), format!("{}()", function))
+                        }
+                        _ => word.value.clone(),
+                    }
+                }
+                Token::LParen => {
+                    let is_cast = matches!(
+                        previous,
+                        Some(Token::Word(word)) if word.quote_style.is_none()
+                            && matches!(word.keyword, Keyword::CAST | Keyword::TRY_CAST)
+                    );
+                    parens.push(is_cast);
+                    "(".to_string()
+                }
+                Token::RParen => {
+                    parens.pop();
+                    ")".to_string()
+                }
+                Token::SingleQuotedString(value) => {
+                    let literal = format!("'{}'", value.replace('\'', "''"));
+                    let after_interval = matches!(
+                        previous,
+                        Some(Token::Word(word)) if word.quote_style.is_none() && word.keyword == Keyword::INTERVAL
+                    );
+                    match after_interval.then(|| self.interval(value)).flatten() {
+                        Some(interval) => {
+                            rewritten(
+                                RewriteKind::Interval,
+                                format!("INTERVAL {}", literal),
+                                format!("INTERVAL {}", interval),
+                            );
+                            interval
+                        }
+                        None => literal,
+                    }
+                }
+                other => other.to_string(),
+            };
+            translated.push_str(&text);
+            if !matches!(token, Token::Whitespace(_)) {
+                previous = Some(token);
+            }
         }
+        Ok((translated, rewrites))
     }
-}
 
-impl Default for PostgreSQLDialectTranslator {
-    fn default() -> Self {
-        Self::new()
+    /// An `INTERVAL '<n> <unit>'` literal's value in the dialect's syntax
+    fn interval(&self, value: &str) -> Option<String> {
+        let mut parts = value.split_whitespace();
+        let (Some(amount), Some(unit), None) = (parts.next(), parts.next(), parts.next()) else {
+            return None;
+        };
+        amount.parse::<i64>().ok()?;
+        let unit = unit.to_uppercase();
+        let unit = unit.strip_suffix('S').unwrap_or(&unit);
+        if !INTERVAL_UNITS.contains(&unit) {
+            return None;
+        }
+        match self.spec.interval_style {
+            IntervalStyle::Quoted => None,
+            IntervalStyle::UnitKeyword => Some(format!("{} {}", amount, unit)),
+            IntervalStyle::QuotedValueUnit => Some(format!("'{}' {}", amount, unit)),
+        }
     }
 }
 
+/// Functions written without parentheses in DataFusion SQL
+const NILADIC_FUNCTIONS: &[Keyword] = &[
+    Keyword::CURRENT_DATE,
+    Keyword::CURRENT_TIME,
+    Keyword::CURRENT_TIMESTAMP,
+    Keyword::LOCALTIME,
+    Keyword::LOCALTIMESTAMP,
+];
+
+const INTERVAL_UNITS: &[&str] = &["YEAR", "QUARTER", "MONTH", "WEEK", "DAY", "HOUR", "MINUTE", "SECOND"];
+
+/// First non-whitespace token after `index`
+fn next_significant(tokens: &[TokenWithSpan], index: usize) -> Option<&Token> {
+    tokens[index + 1..]
+        .iter()
+        .map(|token| &token.token)
+        .find(|token| !matches!(token, Token::Whitespace(_)))
+}
+
 #[async_trait]
-impl DialectTranslator for PostgreSQLDialectTranslator {
+impl DialectTranslator for SpecDialectTranslator {
     fn dialect_name(&self) -> &str {
-        "PostgreSQL"
+        &self.spec.name
     }
 
     async fn translate(&self, datafusion_sql: &str) -> Result<String> {
-        // For PostgreSQL, DataFusion SQL is already very compatible
-        // Just validate the SQL can be parsed
-        let statements = Parser::parse_sql(&self.dialect, datafusion_sql)
-            .context("Failed to parse SQL for PostgreSQL")?;
+        self.translate_with_rewrites(datafusion_sql)
+            .await
+            .map(|(translated, _)| translated)
+    }
+
+    async fn translate_with_rewrites(&self, datafusion_sql: &str) -> Result<(String, Vec<SqlRewrite>)> {
+        // Validate SQL can be parsed
+        let statements = Parser::parse_sql(&GenericDialect {}, datafusion_sql)
+            .with_context(|| format!("Failed to parse SQL for {}", self.spec.name))?;
 
         if statements.is_empty() {
             return Err(anyhow!("Empty SQL statement"));
         }
 
-        // PostgreSQL-specific transformations
-        let translated = datafusion_sql.to_string();
-
-        // Handle any PostgreSQL-specific syntax needs
-        // (Currently DataFusion SQL is already PostgreSQL-compatible)
-
-        Ok(translated)
+        self.rewrite(datafusion_sql)
     }
 
     fn supports_feature(&self, feature: SqlFeature) -> bool {
-        match feature {
-            SqlFeature::ConcatOperator => true,
-            SqlFeature::ConcatFunction => true,
-            SqlFeature::IntervalSyntax => true,
-            SqlFeature::ReturningClause => true,
-            SqlFeature::CommonTableExpressions => true,
-            SqlFeature::DoubleQuotedIdentifiers => true,
-            SqlFeature::BacktickIdentifiers => false,
-        }
+        self.spec.supports_feature(feature)
     }
 }
 
-/// MySQL dialect translator
-pub struct MySQLDialectTranslator {
-    dialect: MySqlDialect,
-}
+/// PostgreSQL dialect translator
+///
+/// DataFusion SQL is already PostgreSQL-compatible; see `DialectSpec::postgresql`.
+pub struct PostgreSQLDialectTranslator(SpecDialectTranslator);
 
-impl MySQLDialectTranslator {
+impl PostgreSQLDialectTranslator {
     pub fn new() -> Self {
-        Self {
-            dialect: MySqlDialect {},
-        }
+        Self(SpecDialectTranslator::new(DialectSpec::postgresql()))
     }
+}
 
-    /// Translate identifier quoting from double quotes to backticks
-    fn translate_identifiers(&self, sql: &str) -> String {
-        // Simple replacement of double quotes with backticks for identifiers
-        // This is a basic implementation; a full parser-based approach would be more robust
-        let mut result = String::new();
-        let mut in_string = false;
-        let mut in_identifier = false;
-        let mut prev_char = ' ';
-
-        for ch in sql.chars() {
-            match ch {
-                '\'' => {
-                    // Single quote - string literal
-                    if !in_identifier {
-                        in_string = !in_string;
-                    }
-                    result.push(ch);
-                }
-                '"' if !in_string => {
-                    // Double quote - identifier (convert to backtick)
-                    in_identifier = !in_identifier;
-                    result.push('`');
-                }
-                _ => {
-                    result.push(ch);
-                }
-            }
-            prev_char = ch;
-        }
-
-        result
+impl Default for PostgreSQLDialectTranslator {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    /// Translate CONCAT operator (||) to CONCAT function
-    fn translate_concat_operator(&self, sql: &str) -> String {
-        // This is a simplified transformation
-        // A real implementation would need to parse the SQL properly
-        // For now, we rely on DataFusion to handle this
-        sql.to_string()
+#[async_trait]
+impl DialectTranslator for PostgreSQLDialectTranslator {
+    fn dialect_name(&self) -> &str {
+        self.0.dialect_name()
     }
 
-    /// Translate INTERVAL syntax
-    fn translate_interval(&self, sql: &str) -> String {
-        // PostgreSQL: INTERVAL '7 days'
-        // MySQL: INTERVAL 7 DAY
-
-        let mut result = sql.to_string();
-
-        // Simple pattern matching for common interval patterns
-        // Real implementation would use AST transformation
-        result = result.replace("INTERVAL '1 day'", "INTERVAL 1 DAY");
-        result = result.replace("INTERVAL '7 days'", "INTERVAL 7 DAY");
-        result = result.replace("INTERVAL '30 days'", "INTERVAL 30 DAY");
-        result = result.replace("INTERVAL '1 hour'", "INTERVAL 1 HOUR");
-        result = result.replace("INTERVAL '1 month'", "INTERVAL 1 MONTH");
-        result = result.replace("INTERVAL '1 year'", "INTERVAL 1 YEAR");
-
-        result
+    async fn translate(&self, datafusion_sql: &str) -> Result<String> {
+        self.0.translate(datafusion_sql).await
     }
 
-    /// Translate date functions
-    fn translate_date_functions(&self, sql: &str) -> String {
-        let mut result = sql.to_string();
+    async fn translate_with_rewrites(&self, datafusion_sql: &str) -> Result<(String, Vec<SqlRewrite>)> {
+        self.0.translate_with_rewrites(datafusion_sql).await
+    }
 
-        // CURRENT_DATE -> CURDATE()
-        result = result.replace("CURRENT_DATE", "CURDATE()");
+    fn supports_feature(&self, feature: SqlFeature) -> bool {
+        self.0.supports_feature(feature)
+    }
+}
 
-        // CURRENT_TIMESTAMP -> NOW()
-        result = result.replace("CURRENT_TIMESTAMP", "NOW()");
+/// MySQL dialect translator
+///
+/// Backtick identifiers, `INTERVAL 7 DAY` and MySQL date functions; see
+/// `DialectSpec::mysql`.
+pub struct MySQLDialectTranslator(SpecDialectTranslator);
 
-        result
+impl MySQLDialectTranslator {
+    pub fn new() -> Self {
+        Self(SpecDialectTranslator::new(DialectSpec::mysql()))
     }
 }
 
@@ -204,46 +307,19 @@ impl Default for MySQLDialectTranslator {
 #[async_trait]
 impl DialectTranslator for MySQLDialectTranslator {
     fn dialect_name(&self) -> &str {
-        "MySQL"
+        self.0.dialect_name()
     }
 
     async fn translate(&self, datafusion_sql: &str) -> Result<String> {
-        // Validate SQL can be parsed
-        let statements = Parser::parse_sql(&GenericDialect {}, datafusion_sql)
-            .context("Failed to parse SQL for MySQL translation")?;
-
-        if statements.is_empty() {
-            return Err(anyhow!("Empty SQL statement"));
-        }
-
-        // Apply MySQL-specific transformations
-        let mut translated = datafusion_sql.to_string();
-
-        // 1. Translate identifier quoting: " -> `
-        translated = self.translate_identifiers(&translated);
-
-        // 2. Translate INTERVAL syntax
-        translated = self.translate_interval(&translated);
-
-        // 3. Translate date functions
-        translated = self.translate_date_functions(&translated);
-
-        // 4. CONCAT operator translation (if needed)
-        // Note: DataFusion should handle this at the logical plan level
+        self.0.translate(datafusion_sql).await
+    }
 
-        Ok(translated)
+    async fn translate_with_rewrites(&self, datafusion_sql: &str) -> Result<(String, Vec<SqlRewrite>)> {
+        self.0.translate_with_rewrites(datafusion_sql).await
     }
 
     fn supports_feature(&self, feature: SqlFeature) -> bool {
-        match feature {
-            SqlFeature::ConcatOperator => false, // MySQL uses CONCAT()
-            SqlFeature::ConcatFunction => true,
-            SqlFeature::IntervalSyntax => true,  // But different syntax
-            SqlFeature::ReturningClause => false,
-            SqlFeature::CommonTableExpressions => true, // MySQL 8.0+
-            SqlFeature::DoubleQuotedIdentifiers => false, // MySQL uses backticks
-            SqlFeature::BacktickIdentifiers => true,
-        }
+        self.0.supports_feature(feature)
     }
 }
 
@@ -352,6 +428,78 @@ mod tests {
         assert!(!translator.supports_feature(SqlFeature::DoubleQuotedIdentifiers));
     }
 
+    #[tokio::test]
+    async fn test_spec_translator_leaves_literals_alone() {
+        let translator = SpecDialectTranslator::new(DialectSpec::mysql());
+
+        let sql = r#"SELECT CAST("total" AS TEXT), 'CURRENT_DATE "x"', 'it''s' FROM "orders" WHERE random() > 0.5"#;
+        let translated = translator.translate(sql).await.unwrap();
+
+        assert_eq!(
+            translated,
+            r#"SELECT CAST(`total` AS CHAR), 'CURRENT_DATE "x"', 'it''s' FROM `orders` WHERE RAND() > 0.5"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spec_translator_reports_rewrites() {
+        let translator = SpecDialectTranslator::new(DialectSpec::mysql());
+
+        let sql = "SELECT \"id\"\nFROM t WHERE d > CURRENT_DATE - INTERVAL '7 days'";
+        let (translated, rewrites) = translator.translate_with_rewrites(sql).await.unwrap();
+
+        assert_eq!(translated, "SELECT `id`\nFROM t WHERE d > CURDATE() - INTERVAL 7 DAY");
+        let summary: Vec<(RewriteKind, &str, &str, u64, u64)> = rewrites
+            .iter()
+            .map(|r| (r.kind, r.original.as_str(), r.translated.as_str(), r.line, r.column))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (RewriteKind::IdentifierQuoting, "\"id\"", "`id`", 1, 8),
+                (RewriteKind::Function, "CURRENT_DATE", "CURDATE()", 2, 18),
+                (RewriteKind::Interval, "INTERVAL '7 days'", "INTERVAL 7 DAY", 2, 42),
+            ]
+        );
+    }
+
+    /// Translations parse in the target dialect and translating them again changes nothing
+    #[tokio::test]
+    async fn test_round_trip_translations() {
+        use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
+
+        let queries = [
+            r#"SELECT "u"."name", COUNT(*) AS "total" FROM "users" "u" GROUP BY "u"."name" ORDER BY "total" DESC LIMIT 10"#,
+            "SELECT id, CAST(amount AS TEXT) FROM orders WHERE created_at >= CURRENT_DATE - INTERVAL '30 days'",
+            "SELECT o.id FROM orders o WHERE o.note = 'it''s \"quoted\"' AND random() < 0.1",
+            "WITH recent AS (SELECT * FROM events WHERE ts > CURRENT_TIMESTAMP - INTERVAL '1 hour') SELECT COUNT(*) FROM recent",
+        ];
+        let targets: Vec<(DialectSpec, Box<dyn Dialect>)> = vec![
+            (DialectSpec::postgresql(), Box::new(PostgreSqlDialect {})),
+            (DialectSpec::mysql(), Box::new(MySqlDialect {})),
+            (DialectSpec::doris(), Box::new(MySqlDialect {})),
+            (DialectSpec::druid(), Box::new(GenericDialect {})),
+        ];
+
+        for (spec, dialect) in targets {
+            let name = spec.name.clone();
+            let translator = SpecDialectTranslator::new(spec);
+            for sql in queries {
+                let translated = translator.translate(sql).await.unwrap();
+                assert!(
+                    Parser::parse_sql(dialect.as_ref(), &translated).is_ok(),
+                    "{} translation does not parse: {}",
+                    name,
+                    translated
+                );
+                assert_eq!(translator.translate(&translated).await.unwrap(), translated, "{}", name);
+                if name == "PostgreSQL" {
+                    assert_eq!(translated, sql);
+                }
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_generic_translator() {
         let translator = GenericDialectTranslator::new();
@@ -0,0 +1,316 @@
+// DataFusion DatabaseDialectRegistry
+//
+// Per-database dialect plug-ins. Each database type registers a DialectSpec
+// describing its identifier quoting, function mappings, type names, INTERVAL
+// and LIMIT syntax; the registry turns specs into DialectTranslators. Adding a
+// dialect means registering a new spec (or a custom translator), not adding
+// translation branches.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::dialect::{DialectTranslator, SpecDialectTranslator, SqlFeature};
+use super::translator::DatabaseType;
+use crate::validation::LimitStyle;
+
+/// How a dialect writes INTERVAL literals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalStyle {
+    /// `INTERVAL '7 days'` (PostgreSQL, DataFusion)
+    Quoted,
+    /// `INTERVAL 7 DAY` (MySQL, Doris)
+    UnitKeyword,
+    /// `INTERVAL '7' DAY` (ANSI, Druid)
+    QuotedValueUnit,
+}
+
+/// How a dialect truncates timestamps to a time grain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateTruncStyle {
+    /// `DATE_TRUNC('month', ts)` (PostgreSQL, DataFusion, Druid)
+    DateTrunc,
+    /// `DATE(ts)`, `DATE_FORMAT(ts, '%Y-%m-01')` and friends (MySQL, Doris)
+    DateFormat,
+}
+
+/// Dialect plug-in of one database type
+///
+/// Function and type mappings are keyed by upper-case DataFusion name.
+#[derive(Debug, Clone)]
+pub struct DialectSpec {
+    /// Dialect name (e.g. "PostgreSQL", "MySQL")
+    pub name: String,
+    /// Character quoting identifiers
+    pub identifier_quote: char,
+    /// Function renames, including niladic functions like CURRENT_DATE
+    pub functions: HashMap<String, String>,
+    /// Type names of CAST targets
+    pub type_names: HashMap<String, String>,
+    pub interval_style: IntervalStyle,
+    pub limit_style: LimitStyle,
+    pub date_trunc_style: DateTruncStyle,
+    /// Supported SQL features
+    pub features: Vec<SqlFeature>,
+}
+
+impl DialectSpec {
+    /// A dialect without mappings, using LIMIT and quoted INTERVAL literals
+    pub fn new(name: impl Into<String>, identifier_quote: char) -> Self {
+        Self {
+            name: name.into(),
+            identifier_quote,
+            functions: HashMap::new(),
+            type_names: HashMap::new(),
+            interval_style: IntervalStyle::Quoted,
+            limit_style: LimitStyle::Limit,
+            date_trunc_style: DateTruncStyle::DateTrunc,
+            features: Vec::new(),
+        }
+    }
+
+    /// Map a DataFusion function to the dialect's function
+    pub fn with_function(mut self, datafusion_name: &str, name: &str) -> Self {
+        self.functions.insert(datafusion_name.to_uppercase(), name.to_string());
+        self
+    }
+
+    /// Map a DataFusion type name to the dialect's CAST type
+    pub fn with_type_name(mut self, datafusion_name: &str, name: &str) -> Self {
+        self.type_names.insert(datafusion_name.to_uppercase(), name.to_string());
+        self
+    }
+
+    pub fn with_interval_style(mut self, interval_style: IntervalStyle) -> Self {
+        self.interval_style = interval_style;
+        self
+    }
+
+    pub fn with_limit_style(mut self, limit_style: LimitStyle) -> Self {
+        self.limit_style = limit_style;
+        self
+    }
+
+    pub fn with_date_trunc_style(mut self, date_trunc_style: DateTruncStyle) -> Self {
+        self.date_trunc_style = date_trunc_style;
+        self
+    }
+
+    pub fn with_features(mut self, features: &[SqlFeature]) -> Self {
+        self.features = features.to_vec();
+        self
+    }
+
+    /// Dialect function for a DataFusion function name
+    pub fn function(&self, name: &str) -> Option<&str> {
+        self.functions.get(&name.to_uppercase()).map(String::as_str)
+    }
+
+    /// Dialect CAST type for a DataFusion type name
+    pub fn type_name(&self, name: &str) -> Option<&str> {
+        self.type_names.get(&name.to_uppercase()).map(String::as_str)
+    }
+
+    pub fn supports_feature(&self, feature: SqlFeature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Expression truncating `expr` to the start of its `unit` (day, week,
+    /// month, quarter or year); weeks start on Monday
+    pub fn date_trunc(&self, unit: &str, expr: &str) -> String {
+        match (self.date_trunc_style, unit) {
+            (DateTruncStyle::DateFormat, "day") => format!("DATE({})", expr),
+            (DateTruncStyle::DateFormat, "week") => format!("SUBDATE(DATE({0}), WEEKDAY({0}))", expr),
+            (DateTruncStyle::DateFormat, "month") => format!("DATE(DATE_FORMAT({}, '%Y-%m-01'))", expr),
+            (DateTruncStyle::DateFormat, "quarter") => {
+                format!("STR_TO_DATE(CONCAT(YEAR({0}), '-', QUARTER({0}) * 3 - 2, '-01'), '%Y-%m-%d')", expr)
+            }
+            (DateTruncStyle::DateFormat, "year") => format!("DATE(DATE_FORMAT({}, '%Y-01-01'))", expr),
+            _ => format!("DATE_TRUNC('{}', {})", unit, expr),
+        }
+    }
+
+    /// PostgreSQL: DataFusion SQL is already PostgreSQL-compatible
+    pub fn postgresql() -> Self {
+        Self::new("PostgreSQL", '"').with_features(&[
+            SqlFeature::ConcatOperator,
+            SqlFeature::ConcatFunction,
+            SqlFeature::IntervalSyntax,
+            SqlFeature::ReturningClause,
+            SqlFeature::CommonTableExpressions,
+            SqlFeature::DoubleQuotedIdentifiers,
+        ])
+    }
+
+    /// MySQL 8.0
+    pub fn mysql() -> Self {
+        Self::new("MySQL", '`')
+            .with_function("CURRENT_DATE", "CURDATE")
+            .with_function("CURRENT_TIMESTAMP", "NOW")
+            .with_function("RANDOM", "RAND")
+            .with_type_name("TEXT", "CHAR")
+            .with_type_name("VARCHAR", "CHAR")
+            .with_type_name("STRING", "CHAR")
+            .with_type_name("INT", "SIGNED")
+            .with_type_name("INTEGER", "SIGNED")
+            .with_type_name("BIGINT", "SIGNED")
+            .with_type_name("TIMESTAMP", "DATETIME")
+            .with_interval_style(IntervalStyle::UnitKeyword)
+            .with_date_trunc_style(DateTruncStyle::DateFormat)
+            .with_features(&[
+                SqlFeature::ConcatFunction,
+                SqlFeature::IntervalSyntax,
+                SqlFeature::CommonTableExpressions,
+                SqlFeature::BacktickIdentifiers,
+            ])
+    }
+
+    /// Apache Doris: MySQL-compatible, with Doris type names
+    pub fn doris() -> Self {
+        Self::new("Doris", '`')
+            .with_function("CURRENT_DATE", "CURDATE")
+            .with_function("CURRENT_TIMESTAMP", "NOW")
+            .with_function("RANDOM", "RAND")
+            .with_type_name("TEXT", "STRING")
+            .with_type_name("TIMESTAMP", "DATETIME")
+            .with_interval_style(IntervalStyle::UnitKeyword)
+            .with_date_trunc_style(DateTruncStyle::DateFormat)
+            .with_features(&[
+                SqlFeature::ConcatFunction,
+                SqlFeature::IntervalSyntax,
+                SqlFeature::CommonTableExpressions,
+                SqlFeature::BacktickIdentifiers,
+            ])
+    }
+
+    /// Apache Druid SQL
+    pub fn druid() -> Self {
+        Self::new("Druid", '"')
+            .with_type_name("TEXT", "VARCHAR")
+            .with_type_name("STRING", "VARCHAR")
+            .with_interval_style(IntervalStyle::QuotedValueUnit)
+            .with_features(&[
+                SqlFeature::ConcatOperator,
+                SqlFeature::ConcatFunction,
+                SqlFeature::IntervalSyntax,
+                SqlFeature::CommonTableExpressions,
+                SqlFeature::DoubleQuotedIdentifiers,
+            ])
+    }
+}
+
+/// Registry of dialect plug-ins and translators by database type
+///
+/// # Example
+/// ```rust,ignore
+/// let mut registry = DatabaseDialectRegistry::with_defaults();
+/// registry.register(DatabaseType::Doris, DialectSpec::doris().with_function("RANDOM", "RANDOM"));
+/// let translator = registry.translator(DatabaseType::Doris).unwrap();
+/// ```
+pub struct DatabaseDialectRegistry {
+    dialects: HashMap<DatabaseType, Arc<DialectSpec>>,
+    translators: HashMap<DatabaseType, Arc<dyn DialectTranslator>>,
+}
+
+impl DatabaseDialectRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            dialects: HashMap::new(),
+            translators: HashMap::new(),
+        }
+    }
+
+    /// Create a registry with the built-in dialects
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(DatabaseType::PostgreSQL, DialectSpec::postgresql());
+        registry.register(DatabaseType::MySQL, DialectSpec::mysql());
+        registry.register(DatabaseType::Doris, DialectSpec::doris());
+        registry.register(DatabaseType::Druid, DialectSpec::druid());
+        registry
+    }
+
+    /// Register (or replace) the dialect of a database type
+    pub fn register(&mut self, db_type: DatabaseType, spec: DialectSpec) {
+        let spec = Arc::new(spec);
+        self.translators
+            .insert(db_type, Arc::new(SpecDialectTranslator::new(spec.clone())));
+        self.dialects.insert(db_type, spec);
+    }
+
+    /// Override the translator of a database type
+    ///
+    /// The registered spec, if any, is kept for its LIMIT syntax and features.
+    pub fn register_translator(&mut self, db_type: DatabaseType, translator: Arc<dyn DialectTranslator>) {
+        self.translators.insert(db_type, translator);
+    }
+
+    /// Dialect spec of a database type
+    pub fn dialect(&self, db_type: DatabaseType) -> Option<&DialectSpec> {
+        self.dialects.get(&db_type).map(Arc::as_ref)
+    }
+
+    /// Translator of a database type
+    pub fn translator(&self, db_type: DatabaseType) -> Option<Arc<dyn DialectTranslator>> {
+        self.translators.get(&db_type).cloned()
+    }
+
+    /// Database types with a translator
+    pub fn database_types(&self) -> Vec<DatabaseType> {
+        self.translators.keys().copied().collect()
+    }
+}
+
+impl Default for DatabaseDialectRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registered_dialect_is_translated_from_its_spec() {
+        let mut registry = DatabaseDialectRegistry::new();
+        registry.register(
+            DatabaseType::Druid,
+            DialectSpec::new("Custom", '`')
+                .with_function("strpos", "INSTR")
+                .with_type_name("text", "NVARCHAR")
+                .with_interval_style(IntervalStyle::QuotedValueUnit),
+        );
+
+        let translator = registry.translator(DatabaseType::Druid).unwrap();
+        assert_eq!(translator.dialect_name(), "Custom");
+        let translated = translator
+            .translate(r#"SELECT strpos("name", 'a'), CAST(id AS text) FROM t WHERE ts > NOW() - INTERVAL '2 hours'"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            translated,
+            "SELECT INSTR(`name`, 'a'), CAST(id AS NVARCHAR) FROM t WHERE ts > NOW() - INTERVAL '2' HOUR"
+        );
+        assert!(registry.translator(DatabaseType::MySQL).is_none());
+    }
+
+    #[test]
+    fn test_default_dialects() {
+        let registry = DatabaseDialectRegistry::with_defaults();
+        assert_eq!(registry.database_types().len(), 4);
+
+        let mysql = registry.dialect(DatabaseType::MySQL).unwrap();
+        assert_eq!(mysql.identifier_quote, '`');
+        assert_eq!(mysql.function("current_date"), Some("CURDATE"));
+        assert_eq!(mysql.type_name("Text"), Some("CHAR"));
+        assert_eq!(mysql.limit_style, LimitStyle::Limit);
+        assert!(!mysql.supports_feature(SqlFeature::ReturningClause));
+        assert_eq!(mysql.date_trunc("month", "ts"), "DATE(DATE_FORMAT(ts, '%Y-%m-01'))");
+
+        let druid = registry.dialect(DatabaseType::Druid).unwrap();
+        assert_eq!(druid.interval_style, IntervalStyle::QuotedValueUnit);
+        assert!(druid.supports_feature(SqlFeature::DoubleQuotedIdentifiers));
+        assert_eq!(druid.date_trunc("week", "__time"), "DATE_TRUNC('week', __time)");
+    }
+}
@@ -4,14 +4,24 @@
 // and merging results using DataFusion's in-memory execution engine.
 
 use crate::api::middleware::AppError;
+use crate::models::CacheStatus;
 use crate::models::cross_database_query::{
-    CrossDatabaseExecutionPlan, CrossDatabaseQueryResponse, MergeStrategy, SubQuery,
+    BroadcastJoin, CrossDatabaseExecutionPlan, CrossDatabaseQueryResponse, MergeClauses, MergeStrategy, SubQuery,
     SubQueryExecution,
 };
 use crate::services::database::adapter::DatabaseAdapter;
-use crate::services::datafusion::{DataFusionSessionManager, SessionConfig};
-use datafusion::arrow::array::{ArrayRef, RecordBatch, StringArray, Int64Array, Float64Array, Array};
-use datafusion::arrow::datatypes::{Schema, Field, DataType};
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::datafusion::DataFusionSessionPool;
+use crate::services::SubQueryResultCache;
+use crate::services::result_transform::rows_to_record_batch;
+use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Schema, SchemaRef};
+use datafusion::common::TableReference;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::SessionContext;
+use futures::future::try_join_all;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::time::timeout;
@@ -20,30 +30,62 @@ use std::time::Duration;
 /// Result from executing a sub-query
 struct SubQueryResult {
     connection_id: String,
+    /// Table name the rows are registered under for merging
+    result_alias: String,
+    /// Joined by the merge, rather than only read by subqueries in its clauses
+    join_input: bool,
     database_type: String,
     query: String,
-    rows: Vec<serde_json::Value>,
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
     execution_time_ms: u128,
+    pushed_predicates: Vec<String>,
+    estimated_rows: Option<u64>,
+    /// Size of the `IN` list of join keys this sub-query was filtered by
+    broadcast_keys: Option<usize>,
+    /// Sub-query cache outcome (None when the cache is not used)
+    cache: Option<CacheStatus>,
 }
 
+/// Join keys above which a broadcast is skipped and the probe side fetched unfiltered
+///
+/// Guards against build sides far larger than their estimate.
+const MAX_BROADCAST_KEYS: usize = 50_000;
+
 /// DataFusion Federated Executor
 ///
 /// Executes cross-database queries by:
 /// 1. Running sub-queries in parallel against each database
-/// 2. Converting results to Arrow RecordBatches
+/// 2. Fetching their results as Arrow RecordBatches typed by the database's columns
 /// 3. Merging results using DataFusion's join/union operators
 pub struct DataFusionFederatedExecutor {
-    session_manager: DataFusionSessionManager,
+    /// Sessions the merge runs in
+    sessions: Arc<DataFusionSessionPool>,
+    /// Results of earlier sub-queries (None fetches every sub-query)
+    cache: Option<Arc<SubQueryResultCache>>,
 }
 
 impl DataFusionFederatedExecutor {
     /// Create a new federated executor
     pub fn new() -> Self {
         Self {
-            session_manager: DataFusionSessionManager::new(SessionConfig::default()),
+            sessions: Arc::new(DataFusionSessionPool::default()),
+            cache: None,
         }
     }
 
+    /// Serve unchanged sub-queries from `cache` and cache the ones fetched
+    pub fn with_cache(mut self, cache: Option<Arc<SubQueryResultCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Merge in sessions of `sessions` (with their UDFs and memory limit)
+    pub fn with_sessions(mut self, sessions: Arc<DataFusionSessionPool>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
     /// Execute a cross-database query
     ///
     /// # Arguments
@@ -57,7 +99,7 @@ impl DataFusionFederatedExecutor {
     pub async fn execute_cross_database_query(
         &self,
         plan: CrossDatabaseExecutionPlan,
-        adapters: std::collections::HashMap<String, Box<dyn DatabaseAdapter>>,
+        adapters: HashMap<String, Box<dyn DatabaseAdapter>>,
     ) -> Result<CrossDatabaseQueryResponse, AppError> {
         let start_time = Instant::now();
 
@@ -71,30 +113,37 @@ impl DataFusionFederatedExecutor {
             }
         }
 
-        // Execute sub-queries in parallel
-        let sub_results = self.execute_sub_queries_parallel(plan.sub_queries.clone(), adapters, plan.timeout_secs).await?;
+        // Execute sub-queries in parallel, after the build side of a broadcast join
+        let sub_results = match &plan.broadcast {
+            Some(broadcast) => {
+                self.execute_with_broadcast(plan.sub_queries.clone(), broadcast, &adapters, plan.timeout_secs).await?
+            }
+            None => self.execute_sub_queries_parallel(plan.sub_queries.clone(), &adapters, plan.timeout_secs).await?,
+        };
 
         // Merge results based on strategy
         let merged_results = match plan.merge_strategy {
             MergeStrategy::None => {
                 // Single database query - return results directly
-                if sub_results.is_empty() {
-                    vec![]
-                } else {
-                    sub_results[0].rows.clone()
+                match sub_results.first() {
+                    Some(result) => self.record_batches_to_json(&result.batches)?,
+                    None => vec![],
                 }
             }
             MergeStrategy::InnerJoin { ref conditions } => {
-                self.merge_with_join(&sub_results, conditions, "INNER", plan.apply_limit, plan.limit_value).await?
+                self.merge_with_join(&sub_results, conditions, "INNER", &plan.merge_clauses, plan.apply_limit, plan.limit_value).await?
             }
             MergeStrategy::LeftJoin { ref conditions } => {
-                self.merge_with_join(&sub_results, conditions, "LEFT", plan.apply_limit, plan.limit_value).await?
+                self.merge_with_join(&sub_results, conditions, "LEFT", &plan.merge_clauses, plan.apply_limit, plan.limit_value).await?
             }
             MergeStrategy::RightJoin { ref conditions } => {
-                self.merge_with_join(&sub_results, conditions, "RIGHT", plan.apply_limit, plan.limit_value).await?
+                self.merge_with_join(&sub_results, conditions, "RIGHT", &plan.merge_clauses, plan.apply_limit, plan.limit_value).await?
+            }
+            MergeStrategy::FullOuterJoin { ref conditions } => {
+                self.merge_with_join(&sub_results, conditions, "FULL OUTER", &plan.merge_clauses, plan.apply_limit, plan.limit_value).await?
             }
             MergeStrategy::Union { all } => {
-                self.merge_with_union(&sub_results, all, plan.apply_limit, plan.limit_value).await?
+                self.merge_with_union(&sub_results, all, plan.merge_clauses.order_by.as_deref(), plan.apply_limit, plan.limit_value).await?
             }
         };
 
@@ -107,25 +156,34 @@ impl DataFusionFederatedExecutor {
                 connection_id: r.connection_id.clone(),
                 database_type: r.database_type.clone(),
                 query: r.query.clone(),
-                row_count: r.rows.len(),
+                row_count: r.batches.iter().map(RecordBatch::num_rows).sum(),
                 execution_time_ms: r.execution_time_ms,
+                pushed_predicates: r.pushed_predicates.clone(),
+                estimated_rows: r.estimated_rows,
+                broadcast_keys: r.broadcast_keys,
+                cache: r.cache.clone(),
             })
             .collect();
 
-        Ok(CrossDatabaseQueryResponse::new(
+        let mut response = CrossDatabaseQueryResponse::new(
             plan.original_query,
             sub_query_executions,
             merged_results,
             execution_time_ms,
             plan.apply_limit,
-        ))
+        );
+        response.warnings = plan.warnings;
+        Ok(response)
     }
 
     /// Execute sub-queries in parallel
+    ///
+    /// All sub-queries run concurrently, each under its own timeout, so the total
+    /// latency is that of the slowest one. The first failure cancels the others.
     async fn execute_sub_queries_parallel(
         &self,
         sub_queries: Vec<SubQuery>,
-        adapters: std::collections::HashMap<String, Box<dyn DatabaseAdapter>>,
+        adapters: &HashMap<String, Box<dyn DatabaseAdapter>>,
         timeout_secs: u64,
     ) -> Result<Vec<SubQueryResult>, AppError> {
         let mut tasks = Vec::new();
@@ -134,83 +192,191 @@ impl DataFusionFederatedExecutor {
             let adapter = adapters.get(&sub_query.connection_id)
                 .ok_or_else(|| AppError::Validation(format!("Adapter not found: {}", sub_query.connection_id)))?;
 
-            // Clone necessary data for the task
-            let conn_id = sub_query.connection_id.clone();
-            let db_type = adapter.database_type().to_string(); // Get from adapter instead of sub_query
-            let query = sub_query.query.clone();
-
-            let start = Instant::now();
+            tasks.push(async move {
+                let db_type = adapter.database_type().to_string(); // Get from adapter instead of sub_query
+                let start = Instant::now();
+
+                let cached = self.cache.as_ref().and_then(|cache| cache.get(&sub_query.connection_id, &sub_query.query));
+                let (schema, batches, cache) = match cached {
+                    Some(hit) => (hit.schema, hit.batches, Some(CacheStatus { hit: true, cached_at: Some(hit.cached_at) })),
+                    None => {
+                        // Execute the query with timeout
+                        let (schema, batches) = timeout(
+                            Duration::from_secs(timeout_secs),
+                            self.fetch_batches(adapter.as_ref(), &sub_query.query, timeout_secs)
+                        )
+                        .await
+                        .map_err(|_| AppError::Database(format!("Sub-query timeout after {} seconds", timeout_secs)))?
+                        .map_err(|e| AppError::Database(format!("Sub-query execution failed: {}", e)))?;
+
+                        if let Some(cache) = &self.cache {
+                            cache.put(&sub_query.connection_id, &sub_query.query, &schema, &batches);
+                        }
+                        let cache = self.cache.as_ref().map(|_| CacheStatus { hit: false, cached_at: None });
+                        (schema, batches, cache)
+                    }
+                };
+
+                Ok::<_, AppError>(SubQueryResult {
+                    connection_id: sub_query.connection_id,
+                    result_alias: sub_query.result_alias,
+                    join_input: sub_query.join_input,
+                    database_type: db_type,
+                    query: sub_query.query,
+                    schema,
+                    batches,
+                    execution_time_ms: start.elapsed().as_millis(),
+                    pushed_predicates: sub_query.pushed_predicates,
+                    estimated_rows: sub_query.estimated_rows,
+                    broadcast_keys: None,
+                    cache,
+                })
+            });
+        }
 
-            // Execute the query with timeout
-            let query_result = timeout(
-                Duration::from_secs(timeout_secs),
-                adapter.execute_query(&query, timeout_secs)
-            )
-            .await
-            .map_err(|_| AppError::Database(format!("Sub-query timeout after {} seconds", timeout_secs)))?
-            .map_err(|e| AppError::Database(format!("Sub-query execution failed: {}", e)))?;
+        // Results keep the order of the sub-queries, which the JOIN tree relies on
+        try_join_all(tasks).await
+    }
 
-            let execution_time = start.elapsed().as_millis();
+    /// Execute sub-queries with a broadcast join
+    ///
+    /// The build sub-query runs first. The probe sub-query is then restricted to the
+    /// build side's distinct join keys and runs in parallel with the others. Results
+    /// keep the order of the sub-queries.
+    async fn execute_with_broadcast(
+        &self,
+        mut sub_queries: Vec<SubQuery>,
+        broadcast: &BroadcastJoin,
+        adapters: &HashMap<String, Box<dyn DatabaseAdapter>>,
+        timeout_secs: u64,
+    ) -> Result<Vec<SubQueryResult>, AppError> {
+        let build_idx = sub_queries
+            .iter()
+            .position(|q| q.result_alias == broadcast.build_alias)
+            .ok_or_else(|| AppError::Internal(format!("Broadcast table {} is not planned", broadcast.build_alias)))?;
+        let build_query = sub_queries.remove(build_idx);
+        let build = self
+            .execute_sub_queries_parallel(vec![build_query], adapters, timeout_secs)
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::Internal("Broadcast sub-query returned no result".to_string()))?;
+
+        let keys = broadcast_keys(&build, &broadcast.build_column);
+        let probe = sub_queries.iter_mut().find(|q| q.result_alias == broadcast.probe_alias);
+        let filtered = match (keys, probe) {
+            (Some(keys), Some(probe)) => {
+                let adapter = adapters
+                    .get(&probe.connection_id)
+                    .ok_or_else(|| AppError::Validation(format!("Adapter not found: {}", probe.connection_id)))?;
+                probe.query = broadcast_filter_sql(adapter.as_ref(), &probe.query, &broadcast.probe_column, &keys);
+                tracing::debug!(
+                    build = %broadcast.build_alias,
+                    probe = %broadcast.probe_alias,
+                    keys = keys.len(),
+                    "Filtering sub-query by broadcast join keys"
+                );
+                Some(keys.len())
+            }
+            _ => {
+                tracing::debug!(
+                    build = %broadcast.build_alias,
+                    "Join keys cannot be broadcast, fetching {} unfiltered",
+                    broadcast.probe_alias
+                );
+                None
+            }
+        };
 
-            let result = SubQueryResult {
-                connection_id: conn_id,
-                database_type: db_type,
-                query,
-                rows: query_result.rows,
-                execution_time_ms: execution_time,
-            };
+        let mut results = self.execute_sub_queries_parallel(sub_queries, adapters, timeout_secs).await?;
+        if let Some(probe) = results.iter_mut().find(|r| r.result_alias == broadcast.probe_alias) {
+            probe.broadcast_keys = filtered;
+        }
+        results.insert(build_idx, build);
+        Ok(results)
+    }
 
-            tasks.push(result);
+    /// Run a sub-query and return its rows as Arrow batches
+    ///
+    /// Adapters with DataFusion execution type the batches from the result's column
+    /// types. Other adapters return JSON rows, which are converted here.
+    async fn fetch_batches(
+        &self,
+        adapter: &dyn DatabaseAdapter,
+        sql: &str,
+        timeout_secs: u64,
+    ) -> Result<(SchemaRef, Vec<RecordBatch>), AppError> {
+        if adapter.supports_datafusion_execution() {
+            return adapter.execute_datafusion_query(sql, timeout_secs).await;
         }
 
-        Ok(tasks)
+        let result = adapter.execute_query(sql, timeout_secs).await?;
+        let batch = self.json_to_record_batch(&result.rows)?;
+        Ok((batch.schema(), vec![batch]))
+    }
+
+    /// Register a sub-query's batches as an in-memory table
+    fn register_result(&self, ctx: &SessionContext, name: &str, result: &SubQueryResult) -> Result<(), AppError> {
+        let table = MemTable::try_new(result.schema.clone(), vec![result.batches.clone()])
+            .map_err(|e| AppError::Database(format!("Failed to create table {}: {}", name, e)))?;
+        // Bare reference keeps the name case-sensitive, matching the quoted names in the merge SQL
+        ctx.register_table(TableReference::bare(name), Arc::new(table))
+            .map_err(|e| AppError::Database(format!("Failed to register table {}: {}", name, e)))?;
+        Ok(())
     }
 
     /// Merge results using JOIN
-    /// ENHANCEMENT: Now supports INNER, LEFT, and RIGHT JOIN types
+    /// ENHANCEMENT: Now supports INNER, LEFT, RIGHT, and FULL OUTER JOIN types
     async fn merge_with_join(
         &self,
         sub_results: &[SubQueryResult],
         conditions: &[crate::models::cross_database_query::JoinCondition],
-        join_type: &str,  // "INNER", "LEFT", or "RIGHT"
+        join_type: &str,  // "INNER", "LEFT", "RIGHT", or "FULL OUTER"
+        clauses: &MergeClauses,
         apply_limit: bool,
         limit_value: u32,
     ) -> Result<Vec<serde_json::Value>, AppError> {
-        if sub_results.len() < 2 {
-            return Err(AppError::Validation("JOIN requires at least 2 sub-queries".to_string()));
+        if !sub_results.iter().any(|r| r.join_input) {
+            return Err(AppError::Validation("JOIN requires at least 1 table sub-query".to_string()));
         }
 
         // Create DataFusion session
-        let ctx = self.session_manager.create_session()
+        let ctx = self.sessions.acquire()
             .map_err(|e| AppError::Database(format!("Failed to create DataFusion session: {}", e)))?;
 
-        // Register each sub-result as a temporary table
-        for (idx, result) in sub_results.iter().enumerate() {
-            if result.rows.is_empty() {
-                tracing::warn!("Sub-query {} returned no rows, {} JOIN may return empty/partial result", idx, join_type);
-                // For LEFT/RIGHT JOIN, empty tables are valid
+        // Register each sub-result as a temporary table named after its alias in the query
+        for result in sub_results {
+            let row_count: usize = result.batches.iter().map(RecordBatch::num_rows).sum();
+            if row_count == 0 {
+                tracing::warn!("Sub-query {} returned no rows, {} JOIN may return empty/partial result", result.result_alias, join_type);
+                // For outer JOINs, empty tables are valid
             }
 
-            let batch = self.json_to_record_batch(&result.rows)?;
-            let table_name = format!("table_{}", idx);
+            self.register_result(&ctx, &result.result_alias, result)?;
 
-            ctx.register_batch(&table_name, batch)
-                .map_err(|e| AppError::Database(format!("Failed to register table {}: {}", table_name, e)))?;
-
-            tracing::debug!("Registered table_{} with {} rows for {} JOIN", idx, result.rows.len(), join_type);
+            tracing::debug!("Registered {} with {} rows for {} JOIN", result.result_alias, row_count, join_type);
         }
 
+        let aliases: Vec<&str> = sub_results
+            .iter()
+            .filter(|r| r.join_input)
+            .map(|r| r.result_alias.as_str())
+            .collect();
+
         // Build JOIN SQL with specified join type
-        let join_sql = if !conditions.is_empty() {
-            // Use explicit JOIN conditions
-            self.build_join_sql_with_type(conditions, sub_results.len(), join_type)
+        let join_sql = if !conditions.is_empty() || aliases.len() == 1 {
+            // Use explicit JOIN conditions (a single table needs none)
+            self.build_join_sql_with_type(conditions, &aliases, join_type, clauses)
         } else {
             // Fallback: simple Cartesian product for testing
             tracing::warn!("No JOIN conditions provided, using Cartesian product");
-            self.build_cartesian_product_sql(sub_results.len())
+            self.build_cartesian_product_sql(&aliases, clauses)
         };
 
-        tracing::info!("Executing {} JOIN SQL: {}", join_type, join_sql);
+        tracing::info!(
+            join_type = %join_type,
+            sql = %crate::logging::redactor().sql(&join_sql),
+            "Executing JOIN SQL"
+        );
 
         // Execute JOIN query using DataFusion
         let df = ctx.sql(&join_sql).await
@@ -229,11 +395,7 @@ impl DataFusionFederatedExecutor {
             .map_err(|e| AppError::Database(format!("Failed to collect JOIN results: {}", e)))?;
 
         // Convert RecordBatch results back to JSON
-        let mut results = Vec::new();
-        for batch in batches {
-            let json_rows = self.record_batch_to_json(&batch)?;
-            results.extend(json_rows);
-        }
+        let results = self.record_batches_to_json(&batches)?;
 
         tracing::info!("JOIN produced {} rows", results.len());
 
@@ -242,100 +404,117 @@ impl DataFusionFederatedExecutor {
 
     /// Build JOIN SQL from JOIN conditions (INNER JOIN only)
     /// DEPRECATED: Use build_join_sql_with_type instead
-    fn build_join_sql(&self, conditions: &[crate::models::cross_database_query::JoinCondition], table_count: usize) -> String {
-        self.build_join_sql_with_type(conditions, table_count, "INNER")
+    fn build_join_sql(&self, conditions: &[crate::models::cross_database_query::JoinCondition], aliases: &[&str]) -> String {
+        self.build_join_sql_with_type(conditions, aliases, "INNER", &MergeClauses::default())
     }
 
     /// Build JOIN SQL from JOIN conditions with specific join type
-    /// ENHANCEMENT: Now supports multi-table JOINs, multiple conditions, and different JOIN types
+    ///
+    /// The original query's clauses are evaluated over the joined tables.
+    ///
+    /// Tables are joined one at a time, starting from the first alias (the FROM table).
+    /// Each step takes the earliest remaining table with a condition linking it to the
+    /// tables already joined, and joins it ON every such condition, so conditions are
+    /// applied in dependency order whatever order they were written in. A table no
+    /// condition reaches is CROSS JOINed.
     fn build_join_sql_with_type(
         &self,
         conditions: &[crate::models::cross_database_query::JoinCondition],
-        table_count: usize,
-        join_type: &str,  // "INNER", "LEFT", or "RIGHT"
+        aliases: &[&str],
+        join_type: &str,  // "INNER", "LEFT", "RIGHT", or "FULL OUTER"
+        clauses: &MergeClauses,
     ) -> String {
-        if conditions.is_empty() || table_count < 2 {
-            return "SELECT * FROM table_0".to_string();
+        let Some((first, rest)) = aliases.split_first() else {
+            return String::new();
+        };
+
+        for cond in conditions {
+            if !aliases.contains(&cond.left_alias.as_str()) || !aliases.contains(&cond.right_alias.as_str()) {
+                tracing::warn!(
+                    "Ignoring JOIN condition {}.{} = {}.{} on a table outside the query",
+                    cond.left_alias, cond.left_column, cond.right_alias, cond.right_column
+                );
+            }
         }
 
-        // Build JOIN SQL for multiple tables
-        let mut sql = String::from("SELECT * FROM table_0");
-
-        // Group conditions by table pairs
-        // For now, assume sequential joins: table_0 JOIN table_1 JOIN table_2...
-        for table_idx in 1..table_count {
-            let table_name = format!("table_{}", table_idx);
-
-            // Find conditions involving this table
-            let relevant_conditions: Vec<_> = conditions
-                .iter()
-                .filter(|cond| {
-                    // Match conditions where either side references this table
-                    cond.left_alias.contains(&format!("table_{}", table_idx)) ||
-                    cond.right_alias.contains(&format!("table_{}", table_idx))
-                })
-                .collect();
+        let mut from = quote_ident(first);
+        let mut joined: Vec<&str> = vec![first];
+        let mut remaining: Vec<&str> = rest.to_vec();
+
+        while !remaining.is_empty() {
+            // Conditions between a candidate table and the tables joined so far
+            let linking = |table: &str| -> Vec<&crate::models::cross_database_query::JoinCondition> {
+                conditions
+                    .iter()
+                    .filter(|cond| {
+                        (cond.left_alias == table && joined.contains(&cond.right_alias.as_str()))
+                            || (cond.right_alias == table && joined.contains(&cond.left_alias.as_str()))
+                    })
+                    .collect()
+            };
+
+            let next = remaining.iter().position(|table| !linking(table).is_empty());
+            let table = remaining.remove(next.unwrap_or(0));
+            let relevant_conditions = linking(table);
 
             if relevant_conditions.is_empty() {
                 // No explicit condition - use Cartesian product (cross join)
-                sql.push_str(&format!(" CROSS JOIN {}", table_name));
-                tracing::warn!("No JOIN condition for {}, using CROSS JOIN", table_name);
+                from.push_str(&format!(" CROSS JOIN {}", quote_ident(table)));
+                tracing::warn!("No JOIN condition for {}, using CROSS JOIN", table);
             } else {
                 // Build ON clause with multiple conditions
-                sql.push_str(&format!(" {} JOIN {} ON ", join_type, table_name));
+                from.push_str(&format!(" {} JOIN {} ON ", join_type, quote_ident(table)));
 
                 let conditions_sql: Vec<String> = relevant_conditions
                     .iter()
                     .map(|cond| {
                         format!("{}.{} = {}.{}",
-                            cond.left_alias, cond.left_column,
-                            cond.right_alias, cond.right_column)
+                            quote_ident(&cond.left_alias), quote_ident(&cond.left_column),
+                            quote_ident(&cond.right_alias), quote_ident(&cond.right_column))
                     })
                     .collect();
 
-                sql.push_str(&conditions_sql.join(" AND "));
+                from.push_str(&conditions_sql.join(" AND "));
             }
+            joined.push(table);
         }
 
-        tracing::debug!("Built multi-table {} JOIN SQL: {}", join_type, sql);
+        let sql = merge_select(clauses, &from);
+        tracing::debug!(
+            join_type = %join_type,
+            sql = %crate::logging::redactor().sql(&sql),
+            "Built multi-table JOIN SQL"
+        );
         sql
     }
 
     /// Build Cartesian product SQL (fallback when no conditions)
-    fn build_cartesian_product_sql(&self, table_count: usize) -> String {
-        if table_count < 2 {
-            return "SELECT * FROM table_0".to_string();
-        }
-
-        let mut sql = "SELECT * FROM table_0".to_string();
-        for i in 1..table_count {
-            sql.push_str(&format!(", table_{}", i));
-        }
-        sql
+    fn build_cartesian_product_sql(&self, aliases: &[&str], clauses: &MergeClauses) -> String {
+        let tables: Vec<String> = aliases.iter().map(|alias| quote_ident(alias)).collect();
+        merge_select(clauses, &tables.join(", "))
     }
 
     /// Merge results using UNION
+    ///
+    /// `order_by` is the trailing `ORDER BY ...` clause of the original query.
     async fn merge_with_union(
         &self,
         sub_results: &[SubQueryResult],
         _all: bool,
+        order_by: Option<&str>,
         apply_limit: bool,
         limit_value: u32,
     ) -> Result<Vec<serde_json::Value>, AppError> {
         // Create DataFusion session
-        let ctx = self.session_manager.create_session()
+        let ctx = self.sessions.acquire()
             .map_err(|e| AppError::Database(format!("Failed to create DataFusion session: {}", e)))?;
 
-        // Convert each sub-result to RecordBatch
+        // Register each sub-result as a temporary table
         let mut all_batches = Vec::new();
 
         for (idx, result) in sub_results.iter().enumerate() {
-            let batch = self.json_to_record_batch(&result.rows)?;
-
-            // Register as temporary table
             let table_name = format!("temp_table_{}", idx);
-            ctx.register_batch(&table_name, batch)
-                .map_err(|e| AppError::Database(format!("Failed to register table: {}", e)))?;
+            self.register_result(&ctx, &table_name, result)?;
 
             all_batches.push(table_name);
         }
@@ -347,9 +526,15 @@ impl DataFusionFederatedExecutor {
             .collect();
 
         let union_operator = if _all { " UNION ALL " } else { " UNION " };
-        let union_query = select_statements.join(union_operator);
+        let mut union_query = select_statements.join(union_operator);
+        if let Some(order_by) = order_by {
+            union_query = format!("{} {}", union_query, order_by);
+        }
 
-        tracing::debug!("Executing UNION query: {}", union_query);
+        tracing::debug!(
+            sql = %crate::logging::redactor().sql(&union_query),
+            "Executing UNION query"
+        );
 
         // Execute UNION
         let df = ctx.sql(&union_query).await
@@ -372,139 +557,108 @@ impl DataFusionFederatedExecutor {
     }
 
     /// Convert JSON rows to Arrow RecordBatch
+    ///
+    /// Every row is scanned, so a NULL or a missing key in the first rows does not
+    /// decide a column's type. Integers mixed with floats become Float64.
     fn json_to_record_batch(&self, rows: &[serde_json::Value]) -> Result<RecordBatch, AppError> {
         if rows.is_empty() {
             return Ok(RecordBatch::new_empty(Arc::new(Schema::empty())));
         }
-
-        // Infer schema from first row
-        let first_row = &rows[0];
-        let obj = first_row.as_object()
-            .ok_or_else(|| AppError::Database("Expected JSON object".to_string()))?;
-
-        let mut fields = Vec::new();
-        let mut column_names = Vec::new();
-
-        for (key, value) in obj {
-            column_names.push(key.clone());
-
-            let data_type = match value {
-                serde_json::Value::Number(n) if n.is_i64() => DataType::Int64,
-                serde_json::Value::Number(n) if n.is_f64() => DataType::Float64,
-                serde_json::Value::String(_) => DataType::Utf8,
-                serde_json::Value::Bool(_) => DataType::Boolean,
-                _ => DataType::Utf8, // Default to string
-            };
-
-            fields.push(Field::new(key, data_type, true));
-        }
-
-        let schema = Arc::new(Schema::new(fields));
-
-        // Build arrays for each column
-        let mut arrays: Vec<ArrayRef> = Vec::new();
-
-        for (field_idx, field) in schema.fields().iter().enumerate() {
-            let column_name = &column_names[field_idx];
-
-            match field.data_type() {
-                DataType::Int64 => {
-                    let values: Vec<Option<i64>> = rows.iter().map(|row| {
-                        row.get(column_name)
-                            .and_then(|v| v.as_i64())
-                    }).collect();
-                    arrays.push(Arc::new(Int64Array::from(values)) as ArrayRef);
-                }
-                DataType::Float64 => {
-                    let values: Vec<Option<f64>> = rows.iter().map(|row| {
-                        row.get(column_name)
-                            .and_then(|v| v.as_f64())
-                    }).collect();
-                    arrays.push(Arc::new(Float64Array::from(values)) as ArrayRef);
-                }
-                DataType::Utf8 => {
-                    let values: Vec<Option<String>> = rows.iter().map(|row| {
-                        row.get(column_name)
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string())
-                    }).collect();
-                    arrays.push(Arc::new(StringArray::from(values)) as ArrayRef);
-                }
-                _ => {
-                    // Default: convert to string
-                    let values: Vec<Option<String>> = rows.iter().map(|row| {
-                        row.get(column_name)
-                            .map(|v| v.to_string())
-                    }).collect();
-                    arrays.push(Arc::new(StringArray::from(values)) as ArrayRef);
-                }
-            }
-        }
-
-        RecordBatch::try_new(schema, arrays)
-            .map_err(|e| AppError::Database(format!("Failed to create RecordBatch: {}", e)))
+        rows_to_record_batch(rows)
     }
 
     /// Convert Arrow RecordBatches to JSON
     fn record_batches_to_json(&self, batches: &[RecordBatch]) -> Result<Vec<serde_json::Value>, AppError> {
-        let mut results = Vec::new();
-
-        for batch in batches {
-            let schema = batch.schema();
-
-            for row_idx in 0..batch.num_rows() {
-                let mut row_obj = serde_json::Map::new();
-
-                for (col_idx, field) in schema.fields().iter().enumerate() {
-                    let column = batch.column(col_idx);
-                    let column_name = field.name();
-
-                    // Extract value based on type
-                    let value = match field.data_type() {
-                        DataType::Int64 => {
-                            let array = column.as_any().downcast_ref::<Int64Array>()
-                                .ok_or_else(|| AppError::Database("Type mismatch".to_string()))?;
-                            if array.is_null(row_idx) {
-                                serde_json::Value::Null
-                            } else {
-                                serde_json::json!(array.value(row_idx))
-                            }
-                        }
-                        DataType::Float64 => {
-                            let array = column.as_any().downcast_ref::<Float64Array>()
-                                .ok_or_else(|| AppError::Database("Type mismatch".to_string()))?;
-                            if array.is_null(row_idx) {
-                                serde_json::Value::Null
-                            } else {
-                                serde_json::json!(array.value(row_idx))
-                            }
-                        }
-                        DataType::Utf8 => {
-                            let array = column.as_any().downcast_ref::<StringArray>()
-                                .ok_or_else(|| AppError::Database("Type mismatch".to_string()))?;
-                            if array.is_null(row_idx) {
-                                serde_json::Value::Null
-                            } else {
-                                serde_json::json!(array.value(row_idx))
-                            }
-                        }
-                        _ => serde_json::Value::Null,
-                    };
+        let Some(first) = batches.first() else {
+            return Ok(vec![]);
+        };
 
-                    row_obj.insert(column_name.clone(), value);
-                }
+        DataFusionResultConverter::convert_to_query_result(first.schema(), batches.to_vec())
+            .map(|result| result.rows)
+            .map_err(|e| AppError::Database(format!("Failed to convert results to JSON: {}", e)))
+    }
+}
 
-                results.push(serde_json::Value::Object(row_obj));
-            }
+/// Distinct non-NULL values of a result column as SQL literals
+///
+/// None when the column is missing, is neither integer nor text (other literals do
+/// not compare the same in every database), or has more than `MAX_BROADCAST_KEYS` values.
+fn broadcast_keys(result: &SubQueryResult, column: &str) -> Option<Vec<String>> {
+    let idx = result
+        .schema
+        .index_of(column)
+        .ok()
+        .or_else(|| result.schema.fields().iter().position(|f| f.name().eq_ignore_ascii_case(column)))?;
+
+    let mut keys = BTreeSet::new();
+    for batch in &result.batches {
+        let array = batch.column(idx);
+        let data_type = array.data_type();
+        if data_type.is_integer() {
+            let values = cast(array, &DataType::Int64).ok()?;
+            let values = values.as_any().downcast_ref::<Int64Array>()?;
+            keys.extend(values.iter().flatten().map(|v| v.to_string()));
+        } else if matches!(data_type, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View) {
+            let values = cast(array, &DataType::Utf8).ok()?;
+            let values = values.as_any().downcast_ref::<StringArray>()?;
+            keys.extend(values.iter().flatten().map(|v| format!("'{}'", v.replace('\'', "''"))));
+        } else {
+            return None;
+        }
+        if keys.len() > MAX_BROADCAST_KEYS {
+            return None;
         }
-
-        Ok(results)
     }
+    Some(keys.into_iter().collect())
+}
 
-    /// Convert a single Arrow RecordBatch to JSON (convenience method)
-    fn record_batch_to_json(&self, batch: &RecordBatch) -> Result<Vec<serde_json::Value>, AppError> {
-        self.record_batches_to_json(&[batch.clone()])
+/// Restrict a sub-query to rows whose `column` is one of `keys`
+///
+/// The column is quoted only when it is not a plain identifier, so databases that
+/// fold unquoted names resolve it as the original query did. No keys match nothing.
+fn broadcast_filter_sql(adapter: &dyn DatabaseAdapter, query: &str, column: &str, keys: &[String]) -> String {
+    let plain = column.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && column.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let column = if plain { column.to_string() } else { adapter.quote_identifier(column) };
+    let predicate = match keys.is_empty() {
+        true => "1 = 0".to_string(),
+        false => format!("{} IN ({})", column, keys.join(", ")),
+    };
+    format!("SELECT * FROM ({}) AS broadcast_probe WHERE {}", query, predicate)
+}
+
+/// `SELECT` over `from` with the clauses of the original query
+fn merge_select(clauses: &MergeClauses, from: &str) -> String {
+    let mut sql = String::from("SELECT ");
+    if let Some(distinct) = &clauses.distinct {
+        sql.push_str(distinct);
+        sql.push(' ');
+    }
+    if clauses.projection.is_empty() {
+        sql.push('*');
+    } else {
+        sql.push_str(&clauses.projection.join(", "));
     }
+    sql.push_str(" FROM ");
+    sql.push_str(from);
+    if let Some(selection) = &clauses.selection {
+        sql.push_str(&format!(" WHERE {}", selection));
+    }
+    if let Some(group_by) = &clauses.group_by {
+        sql.push_str(&format!(" {}", group_by));
+    }
+    if let Some(having) = &clauses.having {
+        sql.push_str(&format!(" HAVING {}", having));
+    }
+    if let Some(order_by) = &clauses.order_by {
+        sql.push_str(&format!(" {}", order_by));
+    }
+    sql
+}
+
+/// Double-quoted identifier, so DataFusion keeps its case
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
 }
 
 impl Default for DataFusionFederatedExecutor {
@@ -516,6 +670,7 @@ impl Default for DataFusionFederatedExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::cross_database_query::JoinCondition;
 
     #[test]
     fn test_json_to_record_batch() {
@@ -532,6 +687,140 @@ mod tests {
         assert_eq!(batch.num_columns(), 2);
     }
 
+    fn condition(left: &str, left_column: &str, right: &str, right_column: &str) -> JoinCondition {
+        JoinCondition {
+            left_alias: left.to_string(),
+            left_column: left_column.to_string(),
+            right_alias: right.to_string(),
+            right_column: right_column.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_join_sql_with_outer_join_type() {
+        let executor = DataFusionFederatedExecutor::new();
+        let conditions = vec![condition("u", "id", "t", "userId")];
+
+        assert_eq!(
+            executor.build_join_sql_with_type(&conditions, &["u", "t"], "FULL OUTER", &MergeClauses::default()),
+            r#"SELECT * FROM "u" FULL OUTER JOIN "t" ON "u"."id" = "t"."userId""#
+        );
+        assert_eq!(
+            executor.build_join_sql_with_type(&conditions, &["u", "t"], "RIGHT", &MergeClauses::default()),
+            r#"SELECT * FROM "u" RIGHT JOIN "t" ON "u"."id" = "t"."userId""#
+        );
+    }
+
+    #[test]
+    fn test_build_join_sql_orders_multi_table_joins() {
+        let executor = DataFusionFederatedExecutor::new();
+        // "g" only links to "t", so "t" must be joined first
+        let conditions = vec![
+            condition("t", "id", "g", "todo_id"),
+            condition("u", "id", "t", "user_id"),
+            condition("u", "org", "t", "org"),
+        ];
+
+        assert_eq!(
+            executor.build_join_sql_with_type(&conditions, &["u", "g", "t", "x"], "INNER", &MergeClauses::default()),
+            r#"SELECT * FROM "u" INNER JOIN "t" ON "u"."id" = "t"."user_id" AND "u"."org" = "t"."org" INNER JOIN "g" ON "t"."id" = "g"."todo_id" CROSS JOIN "x""#
+        );
+    }
+
+    fn sub_result(alias: &str, rows: Vec<serde_json::Value>) -> SubQueryResult {
+        let batch = DataFusionFederatedExecutor::new().json_to_record_batch(&rows).unwrap();
+        SubQueryResult {
+            connection_id: format!("conn_{}", alias),
+            result_alias: alias.to_string(),
+            join_input: true,
+            database_type: "postgresql".to_string(),
+            query: String::new(),
+            schema: batch.schema(),
+            batches: vec![batch],
+            execution_time_ms: 0,
+            pushed_predicates: vec![],
+            estimated_rows: None,
+            broadcast_keys: None,
+            cache: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_with_three_way_join() {
+        let executor = DataFusionFederatedExecutor::new();
+        let sub_results = vec![
+            sub_result("u", vec![serde_json::json!({"id": 1, "name": "Alice"}), serde_json::json!({"id": 2, "name": "Bob"})]),
+            sub_result("g", vec![serde_json::json!({"todo_id": 10, "tag": "urgent"})]),
+            sub_result("t", vec![serde_json::json!({"tid": 10, "userId": 1}), serde_json::json!({"tid": 11, "userId": 2})]),
+        ];
+        let conditions = vec![condition("t", "tid", "g", "todo_id"), condition("u", "id", "t", "userId")];
+
+        let rows = executor
+            .merge_with_join(&sub_results, &conditions, "INNER", &MergeClauses::default(), false, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "Alice");
+        assert_eq!(rows[0]["tag"], "urgent");
+
+        // The original projection, filter and ordering run over the joined rows
+        let clauses = MergeClauses {
+            projection: vec![r#""u"."name""#.to_string(), r#""t"."tid" AS "todo""#.to_string()],
+            selection: Some(r#""u"."id" > 0"#.to_string()),
+            order_by: Some(r#"ORDER BY "u"."name" DESC"#.to_string()),
+            ..MergeClauses::default()
+        };
+        let conditions = vec![condition("u", "id", "t", "userId")];
+        let rows = executor
+            .merge_with_join(&sub_results, &conditions, "INNER", &clauses, false, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                serde_json::json!({"name": "Bob", "todo": 11}),
+                serde_json::json!({"name": "Alice", "todo": 10}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_broadcast_keys_are_distinct_literals() {
+        let result = sub_result(
+            "u",
+            vec![
+                serde_json::json!({"id": 2, "name": "O'Brien", "score": 1.5}),
+                serde_json::json!({"id": 1, "name": "Ann", "score": 2}),
+                serde_json::json!({"id": 2, "name": null, "score": null}),
+            ],
+        );
+
+        assert_eq!(broadcast_keys(&result, "id"), Some(vec!["1".to_string(), "2".to_string()]));
+        assert_eq!(broadcast_keys(&result, "NAME"), Some(vec!["'Ann'".to_string(), "'O''Brien'".to_string()]));
+        // Float keys and unknown columns are not broadcast
+        assert_eq!(broadcast_keys(&result, "score"), None);
+        assert_eq!(broadcast_keys(&result, "missing"), None);
+    }
+
+    #[test]
+    fn test_json_to_record_batch_scans_all_rows() {
+        let executor = DataFusionFederatedExecutor::new();
+        let rows = vec![
+            serde_json::json!({"id": null, "amount": 3, "active": true}),
+            serde_json::json!({"id": 2, "amount": 4.5, "active": false, "note": "late"}),
+        ];
+
+        let batch = executor.json_to_record_batch(&rows).unwrap();
+        let schema = batch.schema();
+
+        assert_eq!(schema.field_with_name("id").unwrap().data_type(), &DataType::Int64);
+        assert_eq!(schema.field_with_name("amount").unwrap().data_type(), &DataType::Float64);
+        assert_eq!(schema.field_with_name("active").unwrap().data_type(), &DataType::Boolean);
+        assert_eq!(schema.field_with_name("note").unwrap().data_type(), &DataType::Utf8);
+    }
+
     #[test]
     fn test_empty_json_to_record_batch() {
         let executor = DataFusionFederatedExecutor::new();
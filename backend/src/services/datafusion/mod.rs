@@ -9,6 +9,7 @@
 
 // Phase 2: Core Infrastructure
 pub mod session; // DataFusionSessionManager
+pub mod session_pool; // DataFusionSessionPool of reusable sessions
 pub mod catalog; // DataFusionCatalogManager
 pub mod dialect; // DialectTranslator trait
 pub mod executor; // DataFusionQueryExecutor
@@ -19,13 +20,20 @@ pub mod translator; // Dialect translation service
 
 // Phase 4: User Story 2 - Cross-Database Queries
 pub mod cross_db_planner;  // CrossDatabaseQueryPlanner
+pub mod cross_db_cost; // CrossDatabaseCostModel
 pub mod federated_executor; // DataFusionFederatedExecutor
+pub mod udf; // Custom scalar UDFs registered into sessions
 
 // Phase 5: User Story 3 - Extensible Architecture
-// pub mod dialect_registry; // DatabaseDialectRegistry (coming soon)
+pub mod dialect_registry; // DatabaseDialectRegistry
 
 // Re-exports for convenient access
 pub use session::{DataFusionSessionManager, SessionConfig};
+pub use session_pool::{DataFusionSessionPool, PooledSession, SHARED_SCHEMA};
+pub use catalog::{connection_alias, DataFusionCatalogManager, VirtualView, SNAPSHOT_TABLE};
+pub use udf::ScalarUdfRegistry;
 pub use translator::{DialectTranslationService, DatabaseType};
+pub use dialect_registry::{DatabaseDialectRegistry, DateTruncStyle, DialectSpec, IntervalStyle};
 pub use cross_db_planner::CrossDatabaseQueryPlanner;
+pub use cross_db_cost::CrossDatabaseCostModel;
 pub use federated_executor::DataFusionFederatedExecutor;
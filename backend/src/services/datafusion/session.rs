@@ -10,6 +10,8 @@ use datafusion::execution::runtime_env::RuntimeEnv;
 use std::sync::Arc;
 use anyhow::{Result, Context};
 
+use super::udf::ScalarUdfRegistry;
+
 /// Configuration for DataFusion sessions
 #[derive(Debug, Clone)]
 pub struct SessionConfig {
@@ -45,12 +47,20 @@ impl Default for SessionConfig {
 /// ```
 pub struct DataFusionSessionManager {
     config: SessionConfig,
+    /// Custom scalar functions registered into every session
+    udfs: Option<Arc<ScalarUdfRegistry>>,
 }
 
 impl DataFusionSessionManager {
     /// Create a new SessionManager with the given configuration
     pub fn new(config: SessionConfig) -> Self {
-        Self { config }
+        Self { config, udfs: None }
+    }
+
+    /// Register the functions of `udfs` into the sessions created
+    pub fn with_udfs(mut self, udfs: Option<Arc<ScalarUdfRegistry>>) -> Self {
+        self.udfs = udfs;
+        self
     }
 
     /// Create a new SessionManager with default configuration
@@ -73,13 +83,9 @@ impl DataFusionSessionManager {
     /// let results = session.sql("SELECT * FROM my_table").await?;
     /// ```
     pub fn create_session(&self) -> Result<SessionContext> {
-        // Create DataFusion configuration
-        let config = DataFusionSessionConfig::new()
-            .with_batch_size(self.config.batch_size)
-            .with_target_partitions(self.config.target_partitions);
-
         // Create and return the session context
-        let ctx = SessionContext::new_with_config(config);
+        let ctx = SessionContext::new_with_config(self.session_config());
+        self.register_udfs(&ctx);
 
         Ok(ctx)
     }
@@ -98,16 +104,27 @@ impl DataFusionSessionManager {
         &self,
         runtime_env: Arc<RuntimeEnv>,
     ) -> Result<SessionContext> {
-        let config = DataFusionSessionConfig::new()
-            .with_batch_size(self.config.batch_size)
-            .with_target_partitions(self.config.target_partitions);
-
         let state = SessionStateBuilder::new()
-            .with_config(config)
+            .with_config(self.session_config())
             .with_runtime_env(runtime_env)
             .build();
 
-        Ok(SessionContext::new_with_state(state))
+        let ctx = SessionContext::new_with_state(state);
+        self.register_udfs(&ctx);
+        Ok(ctx)
+    }
+
+    /// DataFusion configuration of the sessions created
+    pub fn session_config(&self) -> DataFusionSessionConfig {
+        DataFusionSessionConfig::new()
+            .with_batch_size(self.config.batch_size)
+            .with_target_partitions(self.config.target_partitions)
+    }
+
+    fn register_udfs(&self, ctx: &SessionContext) {
+        if let Some(udfs) = &self.udfs {
+            udfs.register(ctx);
+        }
     }
 
     /// Get the current configuration
@@ -0,0 +1,324 @@
+// DataFusion SessionPool
+//
+// Keeps configured SessionContexts (UDFs registered) for reuse instead of
+// building one per federated merge, result transformation or snapshot read.
+// Pooled sessions share one runtime, so the memory limit covers all of them,
+// and a `shared` schema whose table registrations persist across queries.
+// Tables a query registers in the default schema are dropped when its session
+// returns to the pool. Connection catalogs (`alias.schema.table`) are registered
+// into every session; replacing them retires the sessions built with the old ones.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{Context, Result};
+use datafusion::catalog::{CatalogProvider, MemorySchemaProvider, SchemaProvider, TableProvider};
+use datafusion::common::TableReference;
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
+use datafusion::prelude::SessionContext;
+
+use super::session::{DataFusionSessionManager, SessionConfig};
+use super::udf::ScalarUdfRegistry;
+use crate::config::DataFusionConfig;
+
+/// Schema of the default catalog holding persistent registrations
+pub const SHARED_SCHEMA: &str = "shared";
+
+/// Pool of reusable DataFusion sessions
+///
+/// # Example
+/// ```rust,ignore
+/// let pool = Arc::new(DataFusionSessionPool::from_config(&config.datafusion, udfs)?);
+/// let session = pool.acquire()?;
+/// session.register_batch("rows", batch)?;
+/// let df = session.sql("SELECT * FROM rows").await?;
+/// // Dropping `session` unregisters `rows` and returns the context to the pool
+/// ```
+pub struct DataFusionSessionPool {
+    manager: DataFusionSessionManager,
+    runtime: Arc<RuntimeEnv>,
+    shared: Arc<MemorySchemaProvider>,
+    idle: Mutex<Vec<SessionContext>>,
+    max_idle: usize,
+    /// Catalogs registered into every session, by name
+    catalogs: RwLock<HashMap<String, Arc<dyn CatalogProvider>>>,
+    /// Incremented when the catalogs are replaced
+    generation: AtomicU64,
+    created: AtomicU64,
+    reused: AtomicU64,
+}
+
+impl DataFusionSessionPool {
+    /// Create a pool keeping up to `max_idle` idle sessions
+    ///
+    /// `memory_limit` (bytes) bounds the memory of all sessions together.
+    pub fn new(manager: DataFusionSessionManager, memory_limit: Option<usize>, max_idle: usize) -> Result<Self> {
+        let mut runtime = RuntimeEnvBuilder::new();
+        if let Some(limit) = memory_limit {
+            runtime = runtime.with_memory_limit(limit, 1.0);
+        }
+        let runtime = runtime.build_arc().context("Failed to create DataFusion runtime")?;
+
+        Ok(Self {
+            manager,
+            runtime,
+            shared: Arc::new(MemorySchemaProvider::new()),
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+            catalogs: RwLock::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            created: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+        })
+    }
+
+    /// Create a pool from the DataFusion settings, registering `udfs` into every session
+    pub fn from_config(config: &DataFusionConfig, udfs: Arc<ScalarUdfRegistry>) -> Result<Self> {
+        let mut session_config = SessionConfig {
+            batch_size: config.batch_size.max(1),
+            ..SessionConfig::default()
+        };
+        if config.target_partitions > 0 {
+            session_config.target_partitions = config.target_partitions;
+        }
+        let memory_limit = (config.memory_limit_mb > 0).then(|| config.memory_limit_mb as usize * 1024 * 1024);
+
+        Self::new(
+            DataFusionSessionManager::new(session_config).with_udfs(Some(udfs)),
+            memory_limit,
+            config.session_pool_size,
+        )
+    }
+
+    /// Take an idle session, or create one when none is idle
+    pub fn acquire(self: &Arc<Self>) -> Result<PooledSession> {
+        let generation = self.generation.load(Ordering::Acquire);
+        let idle = self.idle.lock().unwrap().pop();
+        let ctx = match idle {
+            Some(ctx) => {
+                self.reused.fetch_add(1, Ordering::Relaxed);
+                ctx
+            }
+            None => {
+                let ctx = self.manager.create_session_with_runtime(self.runtime.clone())?;
+                ctx.catalog(&default_catalog(&ctx))
+                    .context("DataFusion session has no default catalog")?
+                    .register_schema(SHARED_SCHEMA, self.shared.clone())
+                    .context("Failed to register the shared schema")?;
+                for (name, catalog) in self.catalogs.read().unwrap().iter() {
+                    ctx.register_catalog(name.as_str(), catalog.clone());
+                }
+                self.created.fetch_add(1, Ordering::Relaxed);
+                ctx
+            }
+        };
+
+        Ok(PooledSession {
+            ctx: Some(ctx),
+            pool: self.clone(),
+            generation,
+        })
+    }
+
+    /// Replace the catalogs registered into every session
+    ///
+    /// Idle sessions are dropped, and sessions in use are not returned to the
+    /// pool, so no session keeps a catalog that was removed or replaced.
+    pub fn set_catalogs(&self, catalogs: HashMap<String, Arc<dyn CatalogProvider>>) {
+        let mut idle = self.idle.lock().unwrap();
+        *self.catalogs.write().unwrap() = catalogs;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        idle.clear();
+    }
+
+    /// Names of the catalogs registered into every session
+    pub fn catalog_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.catalogs.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Register a table in the shared schema, visible to every session as `shared.name`
+    pub fn register_shared_table(&self, name: &str, table: Arc<dyn TableProvider>) -> Result<()> {
+        self.shared
+            .register_table(name.to_string(), table)
+            .with_context(|| format!("Failed to register shared table {}", name))?;
+        Ok(())
+    }
+
+    /// Remove a table from the shared schema, returning whether it existed
+    pub fn deregister_shared_table(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .shared
+            .deregister_table(name)
+            .with_context(|| format!("Failed to deregister shared table {}", name))?
+            .is_some())
+    }
+
+    /// Sessions created and acquisitions served by an idle session
+    pub fn stats(&self) -> (u64, u64) {
+        (self.created.load(Ordering::Relaxed), self.reused.load(Ordering::Relaxed))
+    }
+
+    /// Return a session, dropping it if its tables cannot be cleared, its catalogs
+    /// were replaced or the pool is full
+    ///
+    /// Settings changed while the session was held are reset.
+    fn release(&self, ctx: SessionContext, generation: u64) {
+        if !clear_default_schema(&ctx) {
+            return;
+        }
+        *ctx.state_ref().write().config_mut() = self.manager.session_config();
+        let mut idle = self.idle.lock().unwrap();
+        if generation == self.generation.load(Ordering::Acquire) && idle.len() < self.max_idle {
+            idle.push(ctx);
+        }
+    }
+}
+
+impl Default for DataFusionSessionPool {
+    /// Pool with default session settings, no UDFs and no memory limit
+    fn default() -> Self {
+        Self::new(DataFusionSessionManager::default_config(), None, 8).expect("default DataFusion runtime")
+    }
+}
+
+/// Session taken from a `DataFusionSessionPool`, returned to it when dropped
+pub struct PooledSession {
+    ctx: Option<SessionContext>,
+    pool: Arc<DataFusionSessionPool>,
+    /// Catalog generation of the pool when the session was taken
+    generation: u64,
+}
+
+impl PooledSession {
+    /// Execute in a single partition, so operators keep the input's row order
+    pub fn single_partition(self) -> Self {
+        self.state_ref().write().config_mut().options_mut().execution.target_partitions = 1;
+        self
+    }
+}
+
+impl Deref for PooledSession {
+    type Target = SessionContext;
+
+    fn deref(&self) -> &SessionContext {
+        self.ctx.as_ref().expect("session is held until dropped")
+    }
+}
+
+impl Drop for PooledSession {
+    fn drop(&mut self) {
+        if let Some(ctx) = self.ctx.take() {
+            self.pool.release(ctx, self.generation);
+        }
+    }
+}
+
+fn default_catalog(ctx: &SessionContext) -> String {
+    ctx.state().config().options().catalog.default_catalog.clone()
+}
+
+/// Drop the tables registered in a session's default schema
+fn clear_default_schema(ctx: &SessionContext) -> bool {
+    let state = ctx.state();
+    let options = &state.config().options().catalog;
+    let Some(schema) = ctx
+        .catalog(&options.default_catalog)
+        .and_then(|catalog| catalog.schema(&options.default_schema))
+    else {
+        return false;
+    };
+    schema
+        .table_names()
+        .into_iter()
+        .all(|name| ctx.deregister_table(TableReference::bare(name)).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::datasource::MemTable;
+
+    fn batch(values: Vec<i64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(values))]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_reused_without_their_tables() {
+        let pool = Arc::new(DataFusionSessionPool::default());
+
+        let first = pool.acquire().unwrap();
+        let session_id = first.session_id();
+        first.register_batch("rows", batch(vec![1, 2])).unwrap();
+        assert!(first.table_exist("rows").unwrap());
+        drop(first);
+
+        let second = pool.acquire().unwrap();
+        assert_eq!(second.session_id(), session_id);
+        assert!(!second.table_exist("rows").unwrap());
+
+        // A session in use is not handed out twice
+        let third = pool.acquire().unwrap();
+        assert_ne!(third.session_id(), session_id);
+        assert_eq!(pool.stats(), (2, 1));
+
+        // Settings return to the pool's when a session is released
+        let partitions = second.copied_config().target_partitions();
+        let second = second.single_partition();
+        assert_eq!(second.copied_config().target_partitions(), 1);
+        drop(third);
+        drop(second);
+        let reused = pool.acquire().unwrap();
+        assert_eq!(reused.session_id(), session_id);
+        assert_eq!(reused.copied_config().target_partitions(), partitions);
+    }
+
+    #[tokio::test]
+    async fn test_shared_tables_persist_across_sessions() {
+        let pool = Arc::new(DataFusionSessionPool::default());
+        let batch = batch(vec![1, 2, 3]);
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+        pool.register_shared_table("numbers", Arc::new(table)).unwrap();
+
+        for _ in 0..2 {
+            let session = pool.acquire().unwrap();
+            let batches = session
+                .sql("SELECT COUNT(*) FROM shared.numbers")
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap();
+            assert_eq!(batches[0].num_rows(), 1);
+        }
+
+        assert!(pool.deregister_shared_table("numbers").unwrap());
+        assert!(!pool.deregister_shared_table("numbers").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_replacing_catalogs_retires_sessions() {
+        let pool = Arc::new(DataFusionSessionPool::default());
+        let held = pool.acquire().unwrap();
+        drop(pool.acquire().unwrap());
+
+        let catalog = datafusion::catalog::MemoryCatalogProvider::new();
+        catalog.register_schema("public", Arc::new(MemorySchemaProvider::new())).unwrap();
+        let catalog: Arc<dyn CatalogProvider> = Arc::new(catalog);
+        pool.set_catalogs(HashMap::from([("sales_db".to_string(), catalog)]));
+        assert_eq!(pool.catalog_names(), vec!["sales_db".to_string()]);
+
+        // Neither the idle session nor the one held while replacing is reused
+        drop(held);
+        let session = pool.acquire().unwrap();
+        assert!(session.catalog("sales_db").is_some());
+        assert_eq!(pool.stats(), (3, 0));
+    }
+}
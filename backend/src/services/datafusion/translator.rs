@@ -7,7 +7,10 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use anyhow::{Result, anyhow, Context};
 
-use super::dialect::{DialectTranslator, PostgreSQLDialectTranslator, MySQLDialectTranslator, GenericDialectTranslator};
+use super::dialect::DialectTranslator;
+use super::dialect_registry::{DatabaseDialectRegistry, DialectSpec};
+use crate::models::SqlRewrite;
+use crate::validation::LimitRewriter;
 
 /// Database types supported by the translation service
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -44,8 +47,8 @@ impl DatabaseType {
 /// Service for managing SQL dialect translations
 ///
 /// The DialectTranslationService coordinates translation between DataFusion SQL
-/// and various database dialects. It maintains a registry of translators and
-/// provides caching for performance.
+/// and various database dialects. Translators come from a
+/// `DatabaseDialectRegistry` of per-database plug-ins; translations can be cached.
 ///
 /// # Example
 /// ```rust,ignore
@@ -57,38 +60,22 @@ impl DatabaseType {
 /// // Result: "SELECT * FROM `users` WHERE created_at >= CURDATE() - INTERVAL 7 DAY"
 /// ```
 pub struct DialectTranslationService {
-    /// Registry of dialect translators by database type
-    translators: HashMap<DatabaseType, Arc<dyn DialectTranslator>>,
+    /// Dialect plug-ins and translators by database type
+    registry: DatabaseDialectRegistry,
     /// Optional translation cache (query -> translated query)
     cache: Option<Arc<tokio::sync::RwLock<HashMap<(String, DatabaseType), String>>>>,
 }
 
 impl DialectTranslationService {
-    /// Create a new translation service with default translators
+    /// Create a new translation service with the built-in dialects
     pub fn new() -> Self {
-        let mut translators: HashMap<DatabaseType, Arc<dyn DialectTranslator>> = HashMap::new();
-
-        // Register default translators
-        translators.insert(
-            DatabaseType::PostgreSQL,
-            Arc::new(PostgreSQLDialectTranslator::new()),
-        );
-        translators.insert(
-            DatabaseType::MySQL,
-            Arc::new(MySQLDialectTranslator::new()),
-        );
-        // Doris and Druid use generic translator for now
-        translators.insert(
-            DatabaseType::Doris,
-            Arc::new(GenericDialectTranslator::new()),
-        );
-        translators.insert(
-            DatabaseType::Druid,
-            Arc::new(GenericDialectTranslator::new()),
-        );
+        Self::with_registry(DatabaseDialectRegistry::with_defaults())
+    }
 
+    /// Create a new translation service over a dialect registry
+    pub fn with_registry(registry: DatabaseDialectRegistry) -> Self {
         Self {
-            translators,
+            registry,
             cache: None,
         }
     }
@@ -113,7 +100,17 @@ impl DialectTranslationService {
         db_type: DatabaseType,
         translator: Arc<dyn DialectTranslator>,
     ) {
-        self.translators.insert(db_type, translator);
+        self.registry.register_translator(db_type, translator);
+    }
+
+    /// Register (or replace) the dialect plug-in of a database type
+    pub fn register_dialect(&mut self, db_type: DatabaseType, spec: DialectSpec) {
+        self.registry.register(db_type, spec);
+    }
+
+    /// Dialect plug-in of a database type
+    pub fn dialect(&self, db_type: DatabaseType) -> Option<&DialectSpec> {
+        self.registry.dialect(db_type)
     }
 
     /// Translate a DataFusion SQL query to a target database dialect
@@ -144,8 +141,8 @@ impl DialectTranslationService {
 
         // Get translator for target database
         let translator = self
-            .translators
-            .get(&target_db)
+            .registry
+            .translator(target_db)
             .ok_or_else(|| anyhow!("No translator registered for {:?}", target_db))?;
 
         // Perform translation
@@ -165,6 +162,25 @@ impl DialectTranslationService {
         Ok(translated)
     }
 
+    /// Translate a query without caching, listing the rewrites applied
+    ///
+    /// Used to preview translations; the result is the same as `translate_query`.
+    pub async fn preview_translation(
+        &self,
+        datafusion_sql: &str,
+        target_db: DatabaseType,
+    ) -> Result<(String, Vec<SqlRewrite>)> {
+        let translator = self
+            .registry
+            .translator(target_db)
+            .ok_or_else(|| anyhow!("No translator registered for {:?}", target_db))?;
+
+        translator
+            .translate_with_rewrites(datafusion_sql)
+            .await
+            .context(format!("Failed to translate query to {} dialect", target_db.as_str()))
+    }
+
     /// Batch translate multiple queries
     ///
     /// Useful for translating a set of queries at once, potentially
@@ -195,12 +211,25 @@ impl DialectTranslationService {
     ///
     /// Returns None if no translator is registered for the database type.
     pub fn get_translator(&self, db_type: DatabaseType) -> Option<Arc<dyn DialectTranslator>> {
-        self.translators.get(&db_type).cloned()
+        self.registry.translator(db_type)
     }
 
     /// List all supported database types
     pub fn supported_databases(&self) -> Vec<DatabaseType> {
-        self.translators.keys().copied().collect()
+        self.registry.database_types()
+    }
+
+    /// Inject a row limit into the outermost query in the target's LIMIT syntax
+    ///
+    /// Returns the SQL and whether a limit had to be added.
+    pub fn apply_limit(&self, sql: &str, target_db: DatabaseType, limit: u64) -> Result<(String, bool)> {
+        let limit_style = self
+            .registry
+            .dialect(target_db)
+            .map(|spec| spec.limit_style)
+            .unwrap_or(crate::validation::LimitStyle::Limit);
+        LimitRewriter::apply(sql, &sqlparser::dialect::GenericDialect {}, limit_style, limit)
+            .map_err(|e| anyhow!("Failed to apply LIMIT for {}: {}", target_db.as_str(), e))
     }
 
     /// Clear the translation cache
@@ -236,6 +265,7 @@ impl Default for DialectTranslationService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::datafusion::dialect::GenericDialectTranslator;
 
     #[test]
     fn test_database_type_parsing() {
@@ -342,6 +372,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_doris_and_druid_use_their_dialects() {
+        let service = DialectTranslationService::new();
+        let sql = r#"SELECT "id" FROM "events" WHERE ts > CURRENT_TIMESTAMP - INTERVAL '3 days'"#;
+
+        let doris = service.translate_query(sql, DatabaseType::Doris).await.unwrap();
+        assert_eq!(doris, "SELECT `id` FROM `events` WHERE ts > NOW() - INTERVAL 3 DAY");
+        let druid = service.translate_query(sql, DatabaseType::Druid).await.unwrap();
+        assert_eq!(druid, r#"SELECT "id" FROM "events" WHERE ts > CURRENT_TIMESTAMP - INTERVAL '3' DAY"#);
+
+        let (limited, applied) = service.apply_limit("SELECT 1", DatabaseType::Druid, 10).unwrap();
+        assert!(applied);
+        assert!(limited.ends_with("LIMIT 10"));
+    }
+
     #[test]
     fn test_database_type_as_str() {
         assert_eq!(DatabaseType::PostgreSQL.as_str(), "PostgreSQL");
@@ -0,0 +1,287 @@
+// DataFusion Scalar UDFs
+//
+// Custom scalar functions registered into every DataFusion session created for
+// federated merges and result transformations. Functions are enabled by name
+// in the configuration (`DATAFUSION_UDFS`):
+//
+// - `parse_user_agent(user_agent, part)`: browser, version, os or device of a
+//   User-Agent header
+// - `ip_to_country(ip)`: ISO country code of an IP address, looked up in a
+//   local MaxMind MMDB database (`DATAFUSION_GEOIP_MMDB_PATH`)
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use datafusion::arrow::array::{Array, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::cast::as_string_array;
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::{create_udf, ColumnarValue, ScalarUDF, Volatility};
+use datafusion::prelude::SessionContext;
+use maxminddb::geoip2;
+
+use crate::config::DataFusionConfig;
+
+/// Names of the built-in UDFs that can be enabled
+pub const BUILTIN_UDFS: &[&str] = &["parse_user_agent", "ip_to_country"];
+
+/// Scalar UDFs registered into DataFusion sessions
+#[derive(Default)]
+pub struct ScalarUdfRegistry {
+    udfs: Vec<ScalarUDF>,
+}
+
+impl ScalarUdfRegistry {
+    /// Create a registry without functions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the functions enabled in the configuration
+    ///
+    /// Fails on unknown function names and when `ip_to_country` is enabled
+    /// without a readable MMDB file.
+    pub fn from_config(config: &DataFusionConfig) -> Result<Self> {
+        let mut registry = Self::new();
+        for name in config.udfs.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let udf = match name {
+                "parse_user_agent" => parse_user_agent_udf(),
+                "ip_to_country" => {
+                    let path = config
+                        .geoip_mmdb_path
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("ip_to_country needs DATAFUSION_GEOIP_MMDB_PATH"))?;
+                    ip_to_country_udf(path)?
+                }
+                other => {
+                    return Err(anyhow!(
+                        "Unknown UDF {}; available functions: {}",
+                        other,
+                        BUILTIN_UDFS.join(", ")
+                    ))
+                }
+            };
+            registry = registry.with_udf(udf);
+        }
+        Ok(registry)
+    }
+
+    /// Add a function
+    pub fn with_udf(mut self, udf: ScalarUDF) -> Self {
+        self.udfs.retain(|existing| existing.name() != udf.name());
+        self.udfs.push(udf);
+        self
+    }
+
+    /// Register every function into a session
+    pub fn register(&self, ctx: &SessionContext) {
+        for udf in &self.udfs {
+            ctx.register_udf(udf.clone());
+        }
+    }
+
+    /// Names of the registered functions
+    pub fn names(&self) -> Vec<&str> {
+        self.udfs.iter().map(|udf| udf.name()).collect()
+    }
+}
+
+/// `parse_user_agent(user_agent, part)` with part `browser`, `version`, `os` or `device`
+fn parse_user_agent_udf() -> ScalarUDF {
+    let fun = Arc::new(|args: &[ColumnarValue]| -> datafusion::error::Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(args)?;
+        let user_agents = as_string_array(&arrays[0])?;
+        let parts = as_string_array(&arrays[1])?;
+        let parsed = (0..user_agents.len())
+            .map(|i| {
+                if user_agents.is_null(i) || parts.is_null(i) {
+                    return Ok(None);
+                }
+                let user_agent = UserAgent::parse(user_agents.value(i));
+                match parts.value(i).to_lowercase().as_str() {
+                    "browser" => Ok(Some(user_agent.browser.to_string())),
+                    "version" => Ok(user_agent.version),
+                    "os" => Ok(Some(user_agent.os.to_string())),
+                    "device" => Ok(Some(user_agent.device.to_string())),
+                    other => Err(DataFusionError::Execution(format!(
+                        "parse_user_agent: unknown part '{}' (expected browser, version, os or device)",
+                        other
+                    ))),
+                }
+            })
+            .collect::<Result<StringArray, DataFusionError>>()?;
+        Ok(ColumnarValue::Array(Arc::new(parsed)))
+    });
+    create_udf(
+        "parse_user_agent",
+        vec![DataType::Utf8, DataType::Utf8],
+        DataType::Utf8,
+        Volatility::Immutable,
+        fun,
+    )
+}
+
+/// `ip_to_country(ip)`: ISO code of the country of an IP address (null when unknown)
+fn ip_to_country_udf(mmdb_path: &str) -> Result<ScalarUDF> {
+    let reader = Arc::new(
+        maxminddb::Reader::open_readfile(mmdb_path)
+            .with_context(|| format!("Failed to open MMDB file {}", mmdb_path))?,
+    );
+    let fun = Arc::new(move |args: &[ColumnarValue]| -> datafusion::error::Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(args)?;
+        let ips = as_string_array(&arrays[0])?;
+        let countries: StringArray = ips
+            .iter()
+            .map(|ip| {
+                let ip: IpAddr = ip?.trim().parse().ok()?;
+                let country: geoip2::Country = reader.lookup(ip).ok()?;
+                country.country?.iso_code.map(str::to_string)
+            })
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(countries)))
+    });
+    Ok(create_udf(
+        "ip_to_country",
+        vec![DataType::Utf8],
+        DataType::Utf8,
+        Volatility::Immutable,
+        fun,
+    ))
+}
+
+/// Browser, OS and device class recognised in a User-Agent header
+#[derive(Debug, PartialEq)]
+struct UserAgent {
+    browser: &'static str,
+    /// Major version of the browser
+    version: Option<String>,
+    os: &'static str,
+    device: &'static str,
+}
+
+impl UserAgent {
+    /// Markers checked in order: Chromium-based browsers also send `Chrome/`
+    /// and `Safari/`, so the more specific ones come first
+    const BROWSERS: &'static [(&'static str, &'static str)] = &[
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("SamsungBrowser/", "Samsung Internet"),
+        ("Firefox/", "Firefox"),
+        ("FxiOS/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+        ("Version/", "Safari"),
+        ("MSIE ", "Internet Explorer"),
+        ("rv:", "Internet Explorer"),
+    ];
+
+    const OPERATING_SYSTEMS: &'static [(&'static str, &'static str)] = &[
+        ("Windows", "Windows"),
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("iPod", "iOS"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "Chrome OS"),
+        ("Linux", "Linux"),
+    ];
+
+    fn parse(user_agent: &str) -> Self {
+        let lower = user_agent.to_lowercase();
+        let is_bot = ["bot", "spider", "crawler"].iter().any(|marker| lower.contains(marker));
+
+        let browser = Self::BROWSERS
+            .iter()
+            .filter(|(marker, _)| *marker != "rv:" || user_agent.contains("Trident/"))
+            .find(|(marker, _)| user_agent.contains(marker));
+        let version = browser.and_then(|(marker, _)| {
+            let start = user_agent.find(marker)? + marker.len();
+            let major: String = user_agent[start..].chars().take_while(char::is_ascii_digit).collect();
+            (!major.is_empty()).then_some(major)
+        });
+        let os = Self::OPERATING_SYSTEMS
+            .iter()
+            .find(|(marker, _)| user_agent.contains(marker))
+            .map_or("Other", |(_, os)| os);
+        let device = if is_bot {
+            "bot"
+        } else if user_agent.contains("iPad") || user_agent.contains("Tablet")
+            || (user_agent.contains("Android") && !user_agent.contains("Mobile"))
+        {
+            "tablet"
+        } else if user_agent.contains("Mobi") || user_agent.contains("iPhone") {
+            "mobile"
+        } else {
+            "desktop"
+        };
+
+        Self {
+            browser: if is_bot { "Bot" } else { browser.map_or("Other", |(_, name)| name) },
+            version: if is_bot { None } else { version },
+            os,
+            device,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_agent() {
+        let chrome = UserAgent::parse(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.109 Safari/537.36",
+        );
+        assert_eq!(
+            chrome,
+            UserAgent { browser: "Chrome", version: Some("120".to_string()), os: "Windows", device: "desktop" }
+        );
+
+        let safari = UserAgent::parse(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1",
+        );
+        assert_eq!((safari.browser, safari.os, safari.device), ("Safari", "iOS", "mobile"));
+
+        let edge = UserAgent::parse("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) Chrome/120.0 Safari/537.36 Edg/120.0.2210.61");
+        assert_eq!((edge.browser, edge.os), ("Edge", "macOS"));
+
+        let bot = UserAgent::parse("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)");
+        assert_eq!((bot.browser, bot.device), ("Bot", "bot"));
+    }
+
+    fn config(udfs: &str) -> DataFusionConfig {
+        DataFusionConfig {
+            udfs: udfs.to_string(),
+            geoip_mmdb_path: None,
+            batch_size: 8192,
+            target_partitions: 0,
+            memory_limit_mb: 0,
+            session_pool_size: 8,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_udf_runs_in_sessions() {
+        let registry = ScalarUdfRegistry::from_config(&config(" parse_user_agent ")).unwrap();
+        assert_eq!(registry.names(), vec!["parse_user_agent"]);
+
+        let ctx = SessionContext::new();
+        registry.register(&ctx);
+        let batches = ctx
+            .sql("SELECT parse_user_agent('Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0', 'browser') AS b")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let browsers = as_string_array(batches[0].column(0)).unwrap();
+        assert_eq!(browsers.value(0), "Firefox");
+
+        let unknown = config("geo");
+        assert!(ScalarUdfRegistry::from_config(&unknown).is_err());
+        let without_mmdb = config("ip_to_country");
+        assert!(ScalarUdfRegistry::from_config(&without_mmdb).is_err());
+    }
+}
@@ -18,13 +18,20 @@ impl DbService {
         connection_id: String,
         connection_url: &str,
         database_type: &str,
+        read_only: bool,
         pool_manager: Arc<ConnectionPoolManager>,
     ) -> Result<(DatabaseConnection, DatabaseMetadata), AppError> {
-        tracing::info!("Connecting to {} database: {}", database_type, connection_url);
+        crate::logging::redactor().register_connection(&connection_id, connection_url);
+        tracing::info!(
+            connection_id = %connection_id,
+            database_type = %database_type,
+            url = %crate::logging::redactor().url(connection_url),
+            "Connecting to database"
+        );
 
         // Determine database type and create appropriate adapter
         let db_type = DatabaseType::from_str(database_type)?;
-        let adapter = create_adapter(db_type, connection_url, pool_manager).await?;
+        let adapter = create_adapter(db_type, connection_url, read_only, pool_manager).await?;
 
         // Use adapter to connect and retrieve metadata
         let (db_connection, metadata) = adapter
@@ -35,4 +42,20 @@ impl DbService {
 
         Ok((db_connection, metadata))
     }
+
+    /// Retrieve a connection's metadata again, reading the columns only of
+    /// tables and views that are new or changed since `previous`
+    pub async fn refresh_metadata(
+        connection_id: String,
+        connection_url: &str,
+        database_type: &str,
+        read_only: bool,
+        pool_manager: Arc<ConnectionPoolManager>,
+        previous: &DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
+        crate::logging::redactor().register_connection(&connection_id, connection_url);
+        let db_type = DatabaseType::from_str(database_type)?;
+        let adapter = create_adapter(db_type, connection_url, read_only, pool_manager).await?;
+        adapter.refresh_metadata(connection_id, previous).await
+    }
 }
@@ -0,0 +1,91 @@
+// Domain Settings Service
+//
+// Resolves per-domain query defaults (row limit, timeout), history retention
+// overrides and allowed database types. Domains without stored settings use
+// the built-in defaults.
+
+use std::sync::Arc;
+
+use crate::api::middleware::AppError;
+use crate::models::{DomainSettings, UpdateDomainSettingsRequest};
+use crate::services::database::DatabaseType;
+use crate::storage::SqliteStorage;
+
+pub struct DomainSettingsService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl DomainSettingsService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Effective settings for a domain (defaults when none are stored)
+    pub async fn get(&self, domain_id: &str) -> Result<DomainSettings, AppError> {
+        Ok(self
+            .storage
+            .get_domain_settings(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .unwrap_or_else(|| DomainSettings::defaults(domain_id.to_string())))
+    }
+
+    /// Effective settings for a connection's domain (defaults for unassigned connections)
+    pub async fn for_domain(&self, domain_id: Option<&str>) -> Result<DomainSettings, AppError> {
+        match domain_id {
+            Some(domain_id) => self.get(domain_id).await,
+            None => Ok(DomainSettings::defaults(String::new())),
+        }
+    }
+
+    /// Validate and store a partial settings update
+    pub async fn update(
+        &self,
+        domain_id: &str,
+        mut request: UpdateDomainSettingsRequest,
+    ) -> Result<DomainSettings, AppError> {
+        self.storage
+            .get_domain(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+
+        // Normalize aliases such as "postgres" and reject unknown types
+        if let Some(types) = request.allowed_database_types.take() {
+            let normalized = types
+                .iter()
+                .map(|t| DatabaseType::from_str(t.trim()).map(|t| t.as_str().to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            request.allowed_database_types = Some(normalized);
+        }
+
+        let mut settings = self.get(domain_id).await?;
+        settings.apply(request).map_err(AppError::Validation)?;
+
+        self.storage
+            .save_domain_settings(&settings)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!("Updated settings for domain {}", domain_id);
+        Ok(settings)
+    }
+
+    /// Reject connections whose database type is not allowed in the domain
+    pub fn ensure_database_type_allowed(settings: &DomainSettings, database_type: &str) -> Result<(), AppError> {
+        let normalized = DatabaseType::from_str(database_type)
+            .map(|t| t.as_str().to_string())
+            .unwrap_or_else(|_| database_type.to_lowercase());
+
+        if settings.allows_database_type(&normalized) {
+            Ok(())
+        } else {
+            Err(AppError::Validation(format!(
+                "Database type {} is not allowed in domain {} (allowed: {})",
+                database_type,
+                settings.domain_id,
+                settings.allowed_database_types.join(", ")
+            )))
+        }
+    }
+}
@@ -0,0 +1,550 @@
+// Embeddings
+//
+// Schema retrieval for natural language queries: on databases with hundreds of
+// tables the full schema no longer fits a useful prompt, so tables are indexed
+// by an embedding of their names, columns and descriptions, and only the tables
+// closest to the question are passed to the LLM.
+//
+// With LLM_EMBEDDING_URL set, texts are embedded by an OpenAI-compatible
+// embeddings API. Otherwise a local model hashes words and character trigrams
+// into a fixed-size vector: no network, and questions sharing words or word
+// fragments with table and column names find those tables. Tables and columns
+// the prompt redactor withholds are never embedded, and every text sent to the
+// embeddings API is recorded in the LLM audit log first.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use reqwest::Client as HttpClient;
+use serde_json::json;
+
+use crate::api::middleware::AppError;
+use crate::config::LlmConfig;
+use crate::models::{DatabaseMetadata, LlmAuditContext, LlmAuditEntry, PromptRedactions, Table};
+use crate::services::PromptRedactor;
+use crate::storage::SqliteStorage;
+
+/// Dimensions of the local hashing model
+pub const LOCAL_DIMENSIONS: usize = 512;
+
+/// Texts per embeddings API request
+const API_BATCH_SIZE: usize = 100;
+
+/// Model turning texts into vectors
+pub enum EmbeddingModel {
+    /// Feature hashing of words and character trigrams
+    Local,
+    /// OpenAI-compatible `POST {url}` with `{"model", "input": [...]}`
+    Api {
+        url: String,
+        model: Option<String>,
+        api_key: Option<String>,
+        http_client: HttpClient,
+    },
+}
+
+pub struct EmbeddingService {
+    model: EmbeddingModel,
+    redactor: PromptRedactor,
+    /// What the redactor held back since the last audited request
+    redactions: Mutex<PromptRedactions>,
+    audit: Option<(Arc<SqliteStorage>, LlmAuditContext)>,
+}
+
+impl EmbeddingService {
+    pub fn new(model: EmbeddingModel) -> Self {
+        Self {
+            model,
+            redactor: PromptRedactor::default(),
+            redactions: Mutex::new(PromptRedactions::default()),
+            audit: None,
+        }
+    }
+
+    /// Withhold the tables and columns this redactor denies (e.g. one with the domain's deny-list)
+    pub fn with_redactor(mut self, redactor: PromptRedactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Record every request to the embeddings API in the LLM audit log
+    pub fn with_audit(mut self, storage: Arc<SqliteStorage>, context: LlmAuditContext) -> Self {
+        self.audit = Some((storage, context));
+        self
+    }
+
+    /// The embeddings API when configured, otherwise the local model
+    pub fn from_config(config: &LlmConfig) -> Self {
+        match &config.embedding_url {
+            Some(url) if !url.is_empty() => Self::new(EmbeddingModel::Api {
+                url: url.clone(),
+                model: config.embedding_model.clone(),
+                api_key: config.api_key.clone(),
+                http_client: HttpClient::new(),
+            }),
+            _ => Self::local(),
+        }
+    }
+
+    pub fn local() -> Self {
+        Self::new(EmbeddingModel::Local)
+    }
+
+    /// Identifies the model, so indexes built by another model are not reused
+    pub fn model_id(&self) -> String {
+        match &self.model {
+            EmbeddingModel::Local => format!("local-{}", LOCAL_DIMENSIONS),
+            EmbeddingModel::Api { url, model, .. } => format!("{}#{}", url, model.as_deref().unwrap_or_default()),
+        }
+    }
+
+    /// Identifies the model and what it withholds, so an index built before the
+    /// deny-list changed is not reused
+    fn index_id(&self) -> String {
+        format!("{}|{}", self.model_id(), self.redactor.denied_identifiers().join(","))
+    }
+
+    /// Schema as it may be embedded, without the tables and columns the redactor withholds
+    pub fn redact_metadata(&self, metadata: &DatabaseMetadata) -> DatabaseMetadata {
+        let (redacted, redactions) = self.redactor.redact_metadata(metadata);
+        self.redactions.lock().unwrap_or_else(|e| e.into_inner()).merge(redactions);
+        redacted
+    }
+
+    /// Record texts in the audit log before they are sent to the embeddings API
+    ///
+    /// Texts that can't be recorded are not sent.
+    async fn audit_texts(&self, purpose: &str, texts: &[String]) -> Result<(), AppError> {
+        let redactions = std::mem::take(&mut *self.redactions.lock().unwrap_or_else(|e| e.into_inner()));
+        let Some((storage, context)) = &self.audit else {
+            return Ok(());
+        };
+        storage
+            .add_llm_audit_entry(&LlmAuditEntry::new(context, purpose, &texts.join("\n"), redactions))
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to record embedding input in the audit log: {}", e)))
+    }
+
+    /// One vector per text, in order
+    ///
+    /// `purpose` names the texts in the audit log, e.g. `embed_schema`.
+    pub async fn embed(&self, purpose: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, AppError> {
+        match &self.model {
+            EmbeddingModel::Local => Ok(texts.iter().map(|text| local_embedding(text)).collect()),
+            EmbeddingModel::Api { url, model, api_key, http_client } => {
+                let mut vectors = Vec::with_capacity(texts.len());
+                for batch in texts.chunks(API_BATCH_SIZE) {
+                    self.audit_texts(purpose, batch).await?;
+                    let mut request = http_client.post(url).json(&json!({
+                        "model": model,
+                        "input": batch,
+                    }));
+                    if let Some(api_key) = api_key {
+                        request = request.header("Authorization", format!("Bearer {}", api_key));
+                    }
+
+                    let response = request
+                        .send()
+                        .await
+                        .map_err(|e| AppError::LlmService(format!("Failed to call embeddings API: {}", e)))?;
+                    if !response.status().is_success() {
+                        let status = response.status();
+                        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                        return Err(AppError::LlmService(format!(
+                            "Embeddings API returned error {}: {}",
+                            status, error_text
+                        )));
+                    }
+                    let result: serde_json::Value = response
+                        .json()
+                        .await
+                        .map_err(|e| AppError::LlmService(format!("Failed to parse embeddings response: {}", e)))?;
+
+                    let batch_vectors = parse_embeddings(&result).ok_or_else(|| {
+                        AppError::LlmService("Embeddings response does not contain embeddings".to_string())
+                    })?;
+                    if batch_vectors.len() != batch.len() {
+                        return Err(AppError::LlmService(format!(
+                            "Embeddings API returned {} embeddings for {} texts",
+                            batch_vectors.len(),
+                            batch.len()
+                        )));
+                    }
+                    vectors.extend(batch_vectors);
+                }
+                Ok(vectors)
+            }
+        }
+    }
+}
+
+/// Vectors of `{"data": [{"embedding": [...]}]}` (OpenAI) or `{"embeddings": [[...]]}`
+fn parse_embeddings(result: &serde_json::Value) -> Option<Vec<Vec<f32>>> {
+    let vector = |value: &serde_json::Value| -> Option<Vec<f32>> {
+        value.as_array()?.iter().map(|x| x.as_f64().map(|x| x as f32)).collect()
+    };
+    match result["data"].as_array() {
+        Some(data) => data.iter().map(|item| vector(&item["embedding"])).collect(),
+        None => result["embeddings"].as_array()?.iter().map(vector).collect(),
+    }
+}
+
+/// Local embedding: words (weight 1) and character trigrams of words (weight 0.5)
+/// hashed into `LOCAL_DIMENSIONS` signed buckets, L2-normalized
+///
+/// Identifiers split at non-alphanumeric characters (`order_items` is `order`,
+/// `items`) and a trailing plural `s` is dropped.
+pub fn local_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0f32; LOCAL_DIMENSIONS];
+    let mut add = |feature: &str, weight: f32| {
+        let hash = fnv1a(feature.as_bytes());
+        let sign = if hash & (1 << 63) == 0 { 1.0 } else { -1.0 };
+        vector[(hash % LOCAL_DIMENSIONS as u64) as usize] += sign * weight;
+    };
+
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let word = word.to_lowercase();
+        let word = match word.strip_suffix('s') {
+            Some(stem) if stem.chars().count() >= 3 => stem.to_string(),
+            _ => word,
+        };
+        add(&word, 1.0);
+
+        let chars: Vec<char> = format!("#{}#", word).chars().collect();
+        for trigram in chars.windows(3) {
+            add(&trigram.iter().collect::<String>(), 0.5);
+        }
+    }
+
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Cosine similarity (0 for zero vectors or different lengths)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 { 0.0 } else { dot / norm }
+}
+
+/// Text a table is embedded from: its name, description, tags and columns
+fn table_document(table: &Table) -> String {
+    let mut document = match &table.schema {
+        Some(schema) => format!("{}.{} {}", schema, table.name, table.name),
+        None => format!("{} {}", table.name, table.name),
+    };
+    if let Some(description) = &table.description {
+        document.push(' ');
+        document.push_str(description);
+    }
+    for tag in &table.tags {
+        document.push(' ');
+        document.push_str(tag);
+    }
+    for column in &table.columns {
+        document.push(' ');
+        document.push_str(&column.name);
+        if let Some(description) = &column.description {
+            document.push(' ');
+            document.push_str(description);
+        }
+        for tag in &column.tags {
+            document.push(' ');
+            document.push_str(tag);
+        }
+    }
+    document
+}
+
+/// Embeddings of a connection's tables, in the order of its metadata
+pub struct SchemaIndex {
+    vectors: Vec<Vec<f32>>,
+}
+
+impl SchemaIndex {
+    /// Index of already redacted metadata (see `EmbeddingService::redact_metadata`)
+    pub async fn build(service: &EmbeddingService, metadata: &DatabaseMetadata) -> Result<Self, AppError> {
+        let documents: Vec<String> = metadata.tables.iter().map(table_document).collect();
+        Ok(Self {
+            vectors: service.embed("embed_schema", &documents).await?,
+        })
+    }
+
+    /// Indexes of the `k` tables most similar to `query`, most similar first
+    pub fn top_k(&self, query: &[f32], k: usize) -> Vec<usize> {
+        let mut scored: Vec<(usize, f32)> = self
+            .vectors
+            .iter()
+            .enumerate()
+            .map(|(i, vector)| (i, cosine_similarity(query, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().take(k).map(|(i, _)| i).collect()
+    }
+}
+
+/// Schema indexes by connection, rebuilt when the connection's metadata version, the model or the deny-list changes
+#[derive(Default)]
+pub struct SchemaIndexCache {
+    indexes: Mutex<HashMap<String, (i32, String, Arc<SchemaIndex>)>>,
+}
+
+impl SchemaIndexCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop the index of a connection, e.g. after its data dictionary changed
+    pub fn invalidate(&self, connection_id: &str) {
+        self.indexes.lock().unwrap().remove(connection_id);
+    }
+
+    /// `metadata` narrowed to the tables most relevant to `question`
+    ///
+    /// Schemas with at most `schema_retrieval_threshold` tables are returned
+    /// unchanged. Otherwise the `schema_top_k` tables closest to the question are
+    /// kept, plus any table the question names; views are kept as they are. When
+    /// the embeddings API fails, the local model is used instead. Only the
+    /// redacted schema and question are embedded.
+    pub async fn relevant_metadata(
+        &self,
+        service: &EmbeddingService,
+        config: &LlmConfig,
+        question: &str,
+        metadata: DatabaseMetadata,
+    ) -> Result<DatabaseMetadata, AppError> {
+        if config.schema_retrieval_threshold == 0 || metadata.tables.len() <= config.schema_retrieval_threshold {
+            return Ok(metadata);
+        }
+
+        let redacted = service.redact_metadata(&metadata);
+        let redacted_question = crate::logging::redactor().text(question);
+        let ranked = match self.rank(service, &redacted_question, &redacted, config.schema_top_k).await {
+            Ok(ranked) => ranked,
+            Err(e) => {
+                tracing::warn!("Schema retrieval fell back to the local embedding model: {}", e);
+                self.rank(&EmbeddingService::local(), &redacted_question, &redacted, config.schema_top_k).await?
+            }
+        };
+        // The prompt is built from the full schema, redacted again (and audited) by the LLM service
+        let ranked: Vec<(Option<&str>, &str)> = ranked
+            .iter()
+            .map(|&i| (redacted.tables[i].schema.as_deref(), redacted.tables[i].name.as_str()))
+            .collect();
+
+        let question_words: Vec<String> = question
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .map(str::to_lowercase)
+            .collect();
+        let total = metadata.tables.len();
+        let mut narrowed = metadata;
+        let tables = std::mem::take(&mut narrowed.tables);
+        narrowed.tables = tables
+            .into_iter()
+            .filter(|table| {
+                ranked.contains(&(table.schema.as_deref(), table.name.as_str()))
+                    || question_words.contains(&table.name.to_lowercase())
+            })
+            .collect();
+
+        tracing::debug!(
+            connection_id = %narrowed.connection_id,
+            "Schema retrieval kept {} of {} tables for the prompt",
+            narrowed.tables.len(),
+            total
+        );
+        Ok(narrowed)
+    }
+
+    /// Indexes of the `k` tables closest to the question
+    async fn rank(
+        &self,
+        service: &EmbeddingService,
+        question: &str,
+        metadata: &DatabaseMetadata,
+        k: usize,
+    ) -> Result<Vec<usize>, AppError> {
+        let index = self.index(service, metadata).await?;
+        let query = service
+            .embed("embed_question", &[question.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::LlmService("No embedding returned for the question".to_string()))?;
+        Ok(index.top_k(&query, k))
+    }
+
+    /// Cached index of the metadata, built on first use
+    async fn index(&self, service: &EmbeddingService, metadata: &DatabaseMetadata) -> Result<Arc<SchemaIndex>, AppError> {
+        let index_id = service.index_id();
+        if let Some((version, id, index)) = self.indexes.lock().unwrap().get(&metadata.connection_id) {
+            if *version == metadata.version && *id == index_id {
+                return Ok(index.clone());
+            }
+        }
+
+        let index = Arc::new(SchemaIndex::build(service, metadata).await?);
+        self.indexes
+            .lock()
+            .unwrap()
+            .insert(metadata.connection_id.clone(), (metadata.version, index_id, index.clone()));
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Column;
+
+    fn metadata(names: &[&str]) -> DatabaseMetadata {
+        let tables = names
+            .iter()
+            .map(|name| Table {
+                name: name.to_string(),
+                schema: Some("public".to_string()),
+                columns: vec![Column {
+                    name: "id".to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: false,
+                    is_primary_key: true,
+                    is_foreign_key: false,
+                    default_value: None,
+                    max_length: None,
+                    description: None,
+                    distinct_count: None,
+                    tags: Vec::new(),
+                }],
+                row_count: None,
+                size_bytes: None,
+                description: None,
+                tags: Vec::new(),
+                engine_metadata: None,
+            })
+            .collect();
+        DatabaseMetadata::new("conn-1".to_string(), tables, vec![], vec!["public".to_string()])
+    }
+
+    fn config(threshold: usize, top_k: usize) -> LlmConfig {
+        LlmConfig {
+            gateway_url: String::new(),
+            api_key: None,
+            embedding_url: None,
+            embedding_model: None,
+            schema_retrieval_threshold: threshold,
+            schema_top_k: top_k,
+            max_repair_attempts: 0,
+            few_shot_examples: 0,
+            monthly_token_budget: 0,
+            budget_exceeded_action: "reject".to_string(),
+            degraded_schema_top_k: 0,
+            generation_cache_ttl_secs: 0,
+            generation_cache_max_entries: 0,
+            redacted_column_patterns: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_local_embedding_similarity() {
+        let question = local_embedding("total amount of orders per customer");
+        let orders = local_embedding("public.order_items order_items order_id amount");
+        let logs = local_embedding("public.audit_logs audit_logs event_type payload");
+
+        assert!(cosine_similarity(&question, &orders) > cosine_similarity(&question, &logs));
+        assert!((cosine_similarity(&orders, &orders) - 1.0).abs() < 1e-5);
+        assert_eq!(cosine_similarity(&local_embedding(""), &orders), 0.0);
+    }
+
+    #[test]
+    fn test_parse_embeddings() {
+        let openai = json!({"data": [{"embedding": [0.5, 1.0]}, {"embedding": [1.0, 0.0]}]});
+        assert_eq!(parse_embeddings(&openai), Some(vec![vec![0.5, 1.0], vec![1.0, 0.0]]));
+        let plain = json!({"embeddings": [[0.25]]});
+        assert_eq!(parse_embeddings(&plain), Some(vec![vec![0.25]]));
+        assert_eq!(parse_embeddings(&json!({"text": "x"})), None);
+    }
+
+    #[tokio::test]
+    async fn test_relevant_metadata_keeps_top_tables() {
+        let cache = SchemaIndexCache::new();
+        let service = EmbeddingService::local();
+        let names = ["customers", "orders", "audit_logs", "sessions", "invoices", "shipments"];
+
+        // Small schemas are passed through
+        let small = cache
+            .relevant_metadata(&service, &config(10, 2), "orders per customer", metadata(&names))
+            .await
+            .unwrap();
+        assert_eq!(small.tables.len(), names.len());
+
+        let narrowed = cache
+            .relevant_metadata(&service, &config(3, 2), "number of orders per customer", metadata(&names))
+            .await
+            .unwrap();
+        let kept: Vec<&str> = narrowed.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(kept, vec!["customers", "orders"]);
+
+        // Tables named in the question are kept beyond the top K
+        let named = cache
+            .relevant_metadata(&service, &config(3, 1), "orders and their shipments", metadata(&names))
+            .await
+            .unwrap();
+        assert!(named.tables.iter().any(|t| t.name == "shipments"));
+        assert!(named.tables.iter().any(|t| t.name == "orders"));
+    }
+
+    #[tokio::test]
+    async fn test_api_embeddings_are_redacted_and_audited() {
+        // An embeddings API answering every text with the same vector
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/embeddings", listener.local_addr().unwrap());
+        let app = axum::Router::new().route(
+            "/embeddings",
+            axum::routing::post(|axum::Json(body): axum::Json<serde_json::Value>| async move {
+                let count = body["input"].as_array().map_or(0, |input| input.len());
+                axum::Json(json!({"embeddings": vec![vec![1.0]; count]}))
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let mut settings = crate::models::DomainSettings::defaults("default-domain-id".to_string());
+        settings.llm_denied_identifiers = vec!["salaries".to_string()];
+        let context = LlmAuditContext {
+            domain_id: Some("default-domain-id".to_string()),
+            connection_id: None,
+            principal: None,
+        };
+        let model = EmbeddingModel::Api { url, model: None, api_key: None, http_client: HttpClient::new() };
+        let service = EmbeddingService::new(model)
+            .with_redactor(PromptRedactor::new(&config(0, 0)).with_domain(&settings))
+            .with_audit(storage.clone(), context);
+
+        let names = ["customers", "orders", "salaries", "sessions"];
+        let narrowed = SchemaIndexCache::new()
+            .relevant_metadata(&service, &config(2, 2), "orders per customer", metadata(&names))
+            .await
+            .unwrap();
+        assert!(!narrowed.tables.iter().any(|t| t.name == "salaries"));
+
+        let list_query = crate::models::ListParams::default()
+            .resolve(SqliteStorage::LLM_AUDIT_SORT_FIELDS, "created_at")
+            .unwrap();
+        let (entries, _) = storage.list_llm_audit_page("default-domain-id", &list_query).await.unwrap();
+        let purposes: Vec<&str> = entries.iter().map(|entry| entry.purpose.as_str()).collect();
+        assert!(purposes.contains(&"embed_schema") && purposes.contains(&"embed_question"));
+        let schema = entries.iter().find(|entry| entry.purpose == "embed_schema").unwrap();
+        assert!(!schema.prompt.contains("salaries"));
+        assert_eq!(schema.redactions.withheld, vec!["public.salaries"]);
+    }
+}
@@ -0,0 +1,163 @@
+// Federated Catalog Service
+//
+// Every connection gets a catalog alias derived from its name, and its cached
+// tables are registered as `alias.schema.table` into the pooled DataFusion
+// sessions. Cross-database queries that list no connections resolve the
+// aliases they reference through this catalog.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::api::middleware::AppError;
+use crate::config::MetadataConfig;
+use crate::models::{CatalogConnection, CatalogSchema, DatabaseConnection, FederatedCatalog};
+use crate::services::datafusion::{connection_alias, CrossDatabaseQueryPlanner, DataFusionCatalogManager, DataFusionSessionPool};
+use crate::services::MetadataCacheService;
+use crate::storage::SqliteStorage;
+use datafusion::prelude::SessionContext;
+
+/// Catalog names DataFusion sessions already use
+const RESERVED_ALIASES: &[&str] = &["datafusion"];
+
+pub struct FederatedCatalogService {
+    storage: Arc<SqliteStorage>,
+    metadata: MetadataConfig,
+}
+
+impl FederatedCatalogService {
+    pub fn new(storage: Arc<SqliteStorage>, metadata: MetadataConfig) -> Self {
+        Self { storage, metadata }
+    }
+
+    /// Every connection with its catalog alias, oldest connection first
+    pub async fn aliases(&self) -> Result<Vec<(String, DatabaseConnection)>, AppError> {
+        let mut connections = self
+            .storage
+            .list_connections()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        connections.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(assign_aliases(&connections).into_iter().zip(connections).collect())
+    }
+
+    /// Register the cached tables of every connection into the sessions of `sessions`
+    ///
+    /// Returns the catalog of the connections in `visible` domains (`None`: all).
+    pub async fn sync(
+        &self,
+        sessions: &DataFusionSessionPool,
+        visible: Option<&[String]>,
+    ) -> Result<FederatedCatalog, AppError> {
+        let cache_service = MetadataCacheService::new(self.storage.clone(), self.metadata.clone());
+        let mut manager = DataFusionCatalogManager::new(SessionContext::new());
+        let mut connections = Vec::new();
+
+        for (alias, connection) in self.aliases().await? {
+            let mut schemas = Vec::new();
+            if let Some(metadata) = cache_service.get_cached_metadata(&connection.id).await? {
+                let catalog = manager
+                    .register_connection(&alias, &metadata)
+                    .map_err(|e| AppError::Internal(format!("Failed to register catalog {}: {}", alias, e)))?;
+                let mut schema_names = catalog.schema_names();
+                schema_names.sort();
+                for name in schema_names {
+                    let mut tables = catalog.schema(&name).map(|s| s.table_names()).unwrap_or_default();
+                    tables.sort();
+                    schemas.push(CatalogSchema { name, tables });
+                }
+            }
+
+            let is_visible = match visible {
+                None => true,
+                Some(domains) => connection.domain_id.as_ref().is_some_and(|d| domains.contains(d)),
+            };
+            if is_visible {
+                connections.push(CatalogConnection {
+                    alias,
+                    connection_id: connection.id,
+                    name: connection.name,
+                    database_type: connection.database_type,
+                    schemas,
+                });
+            }
+        }
+
+        sessions.set_catalogs(manager.catalogs().clone());
+        tracing::debug!("Registered {} connection catalogs", manager.catalogs().len());
+        Ok(FederatedCatalog { connections })
+    }
+
+    /// Connections the `alias.schema.table` references of a query resolve to, by alias
+    pub async fn resolve(&self, query: &str) -> Result<HashMap<String, String>, AppError> {
+        let qualifiers = CrossDatabaseQueryPlanner::catalog_qualifiers(query)?;
+        if qualifiers.is_empty() {
+            return Err(AppError::Validation(
+                "Query references no `connection.schema.table` tables; list connection_ids or qualify tables with a catalog alias"
+                    .to_string(),
+            ));
+        }
+
+        let aliases: HashMap<String, String> = self
+            .aliases()
+            .await?
+            .into_iter()
+            .map(|(alias, connection)| (alias, connection.id))
+            .collect();
+        qualifiers
+            .into_iter()
+            .map(|qualifier| match aliases.get(&qualifier) {
+                Some(connection_id) => Ok((qualifier, connection_id.clone())),
+                None => Err(AppError::Validation(format!(
+                    "Unknown catalog '{}'; see GET /api/cross-database/catalog for the connection aliases",
+                    qualifier
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Aliases of `connections`, in order; later connections with a taken alias get a `_2`, `_3`... suffix
+fn assign_aliases(connections: &[DatabaseConnection]) -> Vec<String> {
+    let mut taken: Vec<String> = RESERVED_ALIASES.iter().map(|a| a.to_string()).collect();
+    let mut aliases = Vec::new();
+    for connection in connections {
+        let base = connection_alias(connection.name.as_deref().unwrap_or(&connection.database_type));
+        let mut alias = base.clone();
+        let mut n = 1;
+        while taken.contains(&alias) {
+            n += 1;
+            alias = format!("{}_{}", base, n);
+        }
+        taken.push(alias.clone());
+        aliases.push(alias);
+    }
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_aliases() {
+        let connection = |name: Option<&str>| {
+            DatabaseConnection::new(
+                name.map(str::to_string),
+                "postgres://localhost/db".to_string(),
+                "postgresql".to_string(),
+                None,
+            )
+        };
+        let connections = vec![
+            connection(Some("Sales DB")),
+            connection(Some("sales-db")),
+            connection(None),
+            connection(Some("DataFusion")),
+        ];
+
+        assert_eq!(
+            assign_aliases(&connections),
+            vec!["sales_db", "sales_db_2", "postgresql", "datafusion_2"]
+        );
+    }
+}
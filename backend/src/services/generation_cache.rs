@@ -0,0 +1,193 @@
+// SQL Generation Cache
+//
+// Keeps the SQL generated for natural language questions, keyed by connection,
+// normalized question, metadata version and dialect, for a TTL. Asking the same
+// question again reuses the SQL instead of another LLM round trip; refreshed
+// metadata changes the version and so misses. Only SQL that ran successfully is
+// kept (after any repairs), so a cached answer never starts with a known error.
+
+use crate::config::LlmConfig;
+use crate::services::QueryResultCache;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Generated SQL with the time it was generated
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedGeneration {
+    pub sql: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+struct Entry {
+    connection_id: String,
+    question: String,
+    generation: CachedGeneration,
+    cached_at: Instant,
+}
+
+/// TTL cache of generated SQL, evicting the oldest entries when full
+pub struct SqlGenerationCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl SqlGenerationCache {
+    pub fn new(ttl_secs: u64, max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl: Duration::from_secs(ttl_secs),
+            max_entries,
+        }
+    }
+
+    pub fn from_config(config: &LlmConfig) -> Self {
+        Self::new(config.generation_cache_ttl_secs, config.generation_cache_max_entries)
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.max_entries > 0
+    }
+
+    fn key(connection_id: &str, question: &str, metadata_version: i32, database_type: &str) -> String {
+        QueryResultCache::generate_key(
+            connection_id,
+            &format!("{}\n{}\n{}", normalize_question(question), metadata_version, database_type),
+        )
+    }
+
+    /// Unexpired SQL generated for `question` on a connection's metadata version
+    pub fn get(
+        &self,
+        connection_id: &str,
+        question: &str,
+        metadata_version: i32,
+        database_type: &str,
+    ) -> Option<CachedGeneration> {
+        if !self.enabled() {
+            return None;
+        }
+        let key = Self::key(connection_id, question, metadata_version, database_type);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(&key)?;
+        if entry.cached_at.elapsed() > self.ttl {
+            entries.remove(&key);
+            return None;
+        }
+        tracing::debug!(connection_id = %connection_id, "Generated SQL served from cache");
+        Some(entry.generation.clone())
+    }
+
+    /// Cache SQL that answered `question` on a connection's metadata version
+    ///
+    /// Expired and then the oldest entries are evicted to make room.
+    pub fn put(&self, connection_id: &str, question: &str, metadata_version: i32, database_type: &str, sql: &str) {
+        if !self.enabled() {
+            return;
+        }
+        let key = Self::key(connection_id, question, metadata_version, database_type);
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+        entries.retain(|_, entry| entry.cached_at.elapsed() <= self.ttl);
+        while entries.len() >= self.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.cached_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+
+        entries.insert(
+            key,
+            Entry {
+                connection_id: connection_id.to_string(),
+                question: normalize_question(question),
+                generation: CachedGeneration {
+                    sql: sql.to_string(),
+                    generated_at: Utc::now(),
+                },
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop the SQL cached for `question` on a connection, whatever the metadata
+    /// version and dialect (e.g. after feedback on it)
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate_question(&self, connection_id: &str, question: &str) -> usize {
+        let question = normalize_question(question);
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.connection_id != connection_id || entry.question != question);
+        before - entries.len()
+    }
+
+    /// Drop all SQL cached for a connection (e.g. after its data dictionary
+    /// changed)
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate_connection(&self, connection_id: &str) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.connection_id != connection_id);
+        before - entries.len()
+    }
+}
+
+/// Question in canonical form: lowercase, single spaces, no trailing punctuation
+fn normalize_question(question: &str) -> String {
+    question
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(['?', '.', '!', '？', '。', '！'])
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_by_normalized_question_and_version() {
+        let cache = SqlGenerationCache::new(60, 2);
+        cache.put("c1", "How many users?", 3, "postgresql", "SELECT COUNT(*) FROM users");
+
+        let hit = cache.get("c1", "  how many   USERS ", 3, "postgresql").unwrap();
+        assert_eq!(hit.sql, "SELECT COUNT(*) FROM users");
+        assert!(cache.get("c1", "How many users?", 4, "postgresql").is_none());
+        assert!(cache.get("c1", "How many users?", 3, "mysql").is_none());
+        assert!(cache.get("c2", "How many users?", 3, "postgresql").is_none());
+
+        // The oldest entry makes room
+        cache.put("c1", "orders", 3, "postgresql", "SELECT * FROM orders");
+        cache.put("c1", "products", 3, "postgresql", "SELECT * FROM products");
+        assert!(cache.get("c1", "How many users?", 3, "postgresql").is_none());
+        assert!(cache.get("c1", "products", 3, "postgresql").is_some());
+
+        assert_eq!(cache.invalidate_question("c1", "Orders."), 1);
+        assert!(cache.get("c1", "orders", 3, "postgresql").is_none());
+    }
+
+    #[test]
+    fn test_cache_disabled_and_expired() {
+        let disabled = SqlGenerationCache::new(0, 100);
+        disabled.put("c1", "users", 1, "postgresql", "SELECT 1");
+        assert!(disabled.get("c1", "users", 1, "postgresql").is_none());
+
+        let cache = SqlGenerationCache::new(60, 100);
+        cache.put("c1", "users", 1, "postgresql", "SELECT 1");
+        cache.entries.lock().unwrap().values_mut().for_each(|entry| {
+            entry.cached_at = Instant::now() - Duration::from_secs(61);
+        });
+        assert!(cache.get("c1", "users", 1, "postgresql").is_none());
+    }
+}
@@ -0,0 +1,517 @@
+// Query History Archive Service
+//
+// Prunes query history past the configured retention window. When archival is
+// enabled, pruned rows are first written to Parquet files partitioned by month
+// (`<archive_dir>/month=YYYY-MM/*.parquet`) so they remain queryable through
+// DataFusion for long-term audit without growing the SQLite database.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use datafusion::arrow::array::{
+    ArrayRef, BooleanArray, Int64Array, StringArray, TimestampMillisecondArray,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::api::middleware::AppError;
+use crate::config::HistoryConfig;
+use crate::models::{HistoryScope, QueryHistory};
+use crate::services::database::adapter::QueryResult;
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::datafusion::executor::DataFusionQueryExecutor;
+use crate::services::datafusion::DataFusionSessionManager;
+use crate::storage::SqliteStorage;
+use crate::validation::SqlValidator;
+
+/// Table name under which archived history is exposed to archive queries
+pub const ARCHIVE_TABLE_NAME: &str = "query_history_archive";
+
+/// Summary of a single prune run
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct PruneSummary {
+    /// Rows removed from SQLite
+    pub pruned: usize,
+    /// Rows written to Parquet before removal
+    pub archived: usize,
+    /// Parquet files created during this run
+    pub files: Vec<String>,
+}
+
+/// Service for pruning and archiving query history
+pub struct HistoryArchiveService {
+    storage: Arc<SqliteStorage>,
+    config: HistoryConfig,
+}
+
+impl HistoryArchiveService {
+    pub fn new(storage: Arc<SqliteStorage>, config: HistoryConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Arrow schema of archived history files (excluding the `month` partition column)
+    ///
+    /// Columns added after the first release are nullable, so files archived
+    /// before them still read (with NULLs).
+    pub fn archive_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("domain_id", DataType::Utf8, false),
+            // NULL for cross-database queries
+            Field::new("connection_id", DataType::Utf8, true),
+            Field::new("query_text", DataType::Utf8, false),
+            Field::new("row_count", DataType::Int64, false),
+            Field::new("execution_time_ms", DataType::Int64, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("error_message", DataType::Utf8, true),
+            Field::new(
+                "executed_at",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                false,
+            ),
+            Field::new("is_llm_generated", DataType::Boolean, false),
+            // `read` or `write`
+            Field::new("kind", DataType::Utf8, true),
+            // User id or `api_key:<id>`; NULL without auth
+            Field::new("executed_by", DataType::Utf8, true),
+            // Cross-database queries: their connections (JSON array) and
+            // aliases (JSON object, keys sorted); NULL for single-connection queries
+            Field::new("connection_ids", DataType::Utf8, true),
+            Field::new("database_aliases", DataType::Utf8, true),
+            Field::new("auto_repaired", DataType::Boolean, true),
+            // Saved query the entry ran or was promoted to
+            Field::new("saved_query_id", DataType::Utf8, true),
+            Field::new("is_slow", DataType::Boolean, true),
+            // SQL with its literals replaced by `?`; NULL for entries recorded
+            // before fingerprints
+            Field::new("fingerprint", DataType::Utf8, true),
+        ]))
+    }
+
+    /// Prune history rows older than the retention window
+    ///
+    /// Domains with their own `history_retention_days` setting use it instead
+    /// of the server-wide retention (0 keeps that domain's history forever).
+    /// Rows are archived to Parquet first when archival is enabled; if writing
+    /// any partition fails, nothing in that scope is deleted.
+    pub async fn prune(&self) -> Result<PruneSummary, AppError> {
+        let overrides: Vec<(String, u32)> = self
+            .storage
+            .list_domain_settings()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .into_iter()
+            .filter_map(|s| s.history_retention_days.map(|days| (s.domain_id, days)))
+            .collect();
+
+        let mut summary = PruneSummary::default();
+
+        if self.config.retention_days > 0 {
+            let excluded = overrides.iter().map(|(domain_id, _)| domain_id.clone()).collect();
+            self.prune_scope(&HistoryScope::AllExcept(excluded), self.config.retention_days, &mut summary)
+                .await?;
+        }
+
+        for (domain_id, retention_days) in overrides {
+            if retention_days > 0 {
+                self.prune_scope(&HistoryScope::Domain(domain_id), retention_days, &mut summary)
+                    .await?;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn prune_scope(
+        &self,
+        scope: &HistoryScope,
+        retention_days: u32,
+        summary: &mut PruneSummary,
+    ) -> Result<(), AppError> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days as i64);
+        let mut archived = 0;
+
+        if self.config.archive_enabled {
+            let rows = self
+                .storage
+                .list_query_history_before(cutoff, scope)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+
+            let mut partitions: BTreeMap<String, Vec<QueryHistory>> = BTreeMap::new();
+            for row in rows {
+                partitions
+                    .entry(row.executed_at.format("%Y-%m").to_string())
+                    .or_default()
+                    .push(row);
+            }
+
+            for (month, rows) in partitions {
+                let archive_dir = PathBuf::from(&self.config.archive_dir);
+                let count = rows.len();
+                let path = tokio::task::spawn_blocking(move || {
+                    Self::write_partition(&archive_dir, &month, &rows)
+                })
+                .await
+                .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))??;
+
+                archived += count;
+                summary.files.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        let pruned = self
+            .storage
+            .delete_query_history_before(cutoff, scope)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if pruned > 0 {
+            tracing::info!(
+                "Pruned {} query history rows older than {} ({:?}, {} archived)",
+                pruned,
+                cutoff.to_rfc3339(),
+                scope,
+                archived
+            );
+        }
+
+        summary.pruned += pruned;
+        summary.archived += archived;
+        Ok(())
+    }
+
+    /// Write one month of history rows to a new Parquet file
+    fn write_partition(archive_dir: &Path, month: &str, rows: &[QueryHistory]) -> Result<PathBuf, AppError> {
+        let partition_dir = archive_dir.join(format!("month={}", month));
+        std::fs::create_dir_all(&partition_dir)
+            .map_err(|e| AppError::Internal(format!("Failed to create archive directory: {}", e)))?;
+
+        let file_path = partition_dir.join(format!(
+            "history-{}-{}.parquet",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            uuid::Uuid::new_v4()
+        ));
+
+        let batch = Self::rows_to_record_batch(rows)?;
+        let file = std::fs::File::create(&file_path)
+            .map_err(|e| AppError::Internal(format!("Failed to create archive file: {}", e)))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)
+            .map_err(|e| AppError::Internal(format!("Failed to create Parquet writer: {}", e)))?;
+        writer
+            .write(&batch)
+            .map_err(|e| AppError::Internal(format!("Failed to write Parquet archive: {}", e)))?;
+        writer
+            .close()
+            .map_err(|e| AppError::Internal(format!("Failed to finalize Parquet archive: {}", e)))?;
+
+        Ok(file_path)
+    }
+
+    /// Convert history rows into a record batch matching `archive_schema`
+    fn rows_to_record_batch(rows: &[QueryHistory]) -> Result<RecordBatch, AppError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.id.as_str()))),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.domain_id.as_str()))),
+            Arc::new(StringArray::from(
+                rows.iter().map(|r| r.connection_id.clone()).collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.query_text.as_str()))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.row_count as i64))),
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.execution_time_ms as i64))),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|r| format!("{:?}", r.status).to_lowercase()),
+            )),
+            Arc::new(StringArray::from(
+                rows.iter().map(|r| r.error_message.clone()).collect::<Vec<_>>(),
+            )),
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rows.iter().map(|r| r.executed_at.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(BooleanArray::from(
+                rows.iter().map(|r| r.is_llm_generated).collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.kind.as_str()))),
+            Arc::new(StringArray::from(
+                rows.iter().map(|r| r.executed_by.clone()).collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(
+                rows.iter()
+                    .map(|r| {
+                        (!r.connection_ids.is_empty())
+                            .then(|| serde_json::to_string(&r.connection_ids).ok())
+                            .flatten()
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(
+                rows.iter()
+                    .map(|r| {
+                        r.database_aliases.as_ref().and_then(|aliases| {
+                            serde_json::to_string(&aliases.iter().collect::<BTreeMap<_, _>>()).ok()
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+            Arc::new(BooleanArray::from(
+                rows.iter().map(|r| r.auto_repaired).collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(
+                rows.iter().map(|r| r.saved_query_id.clone()).collect::<Vec<_>>(),
+            )),
+            Arc::new(BooleanArray::from(
+                rows.iter().map(|r| r.is_slow).collect::<Vec<_>>(),
+            )),
+            Arc::new(StringArray::from(
+                rows.iter()
+                    .map(|r| (!r.fingerprint.is_empty()).then(|| r.fingerprint.clone()))
+                    .collect::<Vec<_>>(),
+            )),
+        ];
+
+        RecordBatch::try_new(Self::archive_schema(), columns)
+            .map_err(|e| AppError::Internal(format!("Failed to build archive batch: {}", e)))
+    }
+
+    /// Query archived history for a domain through DataFusion
+    ///
+    /// The SQL must be a SELECT against `query_history_archive`; the table is
+    /// pre-filtered to the given domain so archives of other domains are never
+    /// visible. A default LIMIT is applied like for live queries.
+    pub async fn query_archive(&self, domain_id: &str, sql: &str) -> Result<QueryResult, AppError> {
+        let (prepared_sql, _) = SqlValidator::validate_and_prepare(sql, 1000)?;
+
+        std::fs::create_dir_all(&self.config.archive_dir)
+            .map_err(|e| AppError::Internal(format!("Failed to create archive directory: {}", e)))?;
+
+        let ctx = DataFusionSessionManager::default_config()
+            .create_session()
+            .map_err(|e| AppError::Internal(format!("Failed to create DataFusion session: {}", e)))?;
+
+        let schema = Self::archive_schema();
+        let options = ParquetReadOptions::default()
+            .schema(schema.as_ref())
+            .table_partition_cols(vec![("month".to_string(), DataType::Utf8)]);
+
+        let archive = ctx
+            .read_parquet(self.config.archive_dir.as_str(), options)
+            .await
+            .and_then(|df| df.filter(col("domain_id").eq(lit(domain_id))))
+            .map_err(|e| AppError::Internal(format!("Failed to open history archive: {}", e)))?;
+
+        ctx.register_table(ARCHIVE_TABLE_NAME, archive.into_view())
+            .map_err(|e| AppError::Internal(format!("Failed to register history archive: {}", e)))?;
+
+        let executor = DataFusionQueryExecutor::new(ctx, Duration::from_secs(30));
+        let result = executor
+            .execute_query(&prepared_sql)
+            .await
+            .map_err(|e| AppError::InvalidSql(format!("Archive query failed: {}", e)))?;
+
+        let mut query_result = DataFusionResultConverter::convert_to_query_result(result.schema, result.batches)
+            .map_err(|e| AppError::Internal(format!("Failed to convert archive results: {}", e)))?;
+        query_result.execution_time_ms = result.execution_time_ms as u64;
+
+        Ok(query_result)
+    }
+
+    /// Spawn a background task that prunes history on the configured interval
+    ///
+    /// Always runs, since domains may enable retention even when the
+    /// server-wide retention is disabled.
+    pub fn spawn_scheduler(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval_secs = self.config.prune_interval_secs.max(60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.prune().await {
+                    tracing::warn!("Query history prune failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn history_at(days_ago: i64, query: &str) -> QueryHistory {
+        let mut history = QueryHistory::new(
+            "default-domain-id".to_string(),
+            "conn-1".to_string(),
+            query.to_string(),
+            3,
+            12,
+            false,
+        );
+        history.executed_at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+        history
+    }
+
+    #[test]
+    fn test_rows_to_record_batch() {
+        let rows = vec![history_at(1, "SELECT 1"), history_at(2, "SELECT 2")];
+        let batch = HistoryArchiveService::rows_to_record_batch(&rows).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), HistoryArchiveService::archive_schema().fields().len());
+    }
+
+    #[tokio::test]
+    async fn test_write_and_query_partition() {
+        let dir = tempdir().unwrap();
+        let mut write = history_at(1, "UPDATE orders SET status = 'shipped'");
+        write.kind = crate::models::QueryKind::Write;
+        write.executed_by = Some("user-1".to_string());
+        write.auto_repaired = true;
+        write.saved_query_id = Some("saved-1".to_string());
+        write.is_slow = true;
+        write.fingerprint = "UPDATE orders SET status = ?".to_string();
+        let rows = vec![history_at(1, "SELECT 1"), write];
+
+        let path = HistoryArchiveService::write_partition(dir.path(), "2024-01", &rows).unwrap();
+        assert!(path.exists());
+        assert!(path.parent().unwrap().ends_with("month=2024-01"));
+
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let service = HistoryArchiveService::new(
+            storage,
+            HistoryConfig {
+                retention_days: 30,
+                archive_enabled: true,
+                archive_dir: dir.path().to_string_lossy().to_string(),
+                prune_interval_secs: 3600,
+                result_max_rows: 100,
+                result_max_bytes: 1024 * 1024,
+            },
+        );
+
+        let result = service
+            .query_archive("default-domain-id", "SELECT query_text, month FROM query_history_archive")
+            .await
+            .unwrap();
+        assert_eq!(result.row_count, 2);
+
+        let writes = service
+            .query_archive(
+                "default-domain-id",
+                "SELECT query_text, executed_by FROM query_history_archive WHERE kind = 'write'",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            writes.rows,
+            vec![serde_json::json!({
+                "query_text": "UPDATE orders SET status = 'shipped'",
+                "executed_by": "user-1",
+            })]
+        );
+
+        let flags = service
+            .query_archive(
+                "default-domain-id",
+                "SELECT auto_repaired, saved_query_id, is_slow, fingerprint FROM query_history_archive \
+                 ORDER BY kind",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            flags.rows,
+            vec![
+                serde_json::json!({
+                    "auto_repaired": false,
+                    "saved_query_id": null,
+                    "is_slow": false,
+                    "fingerprint": null,
+                }),
+                serde_json::json!({
+                    "auto_repaired": true,
+                    "saved_query_id": "saved-1",
+                    "is_slow": true,
+                    "fingerprint": "UPDATE orders SET status = ?",
+                }),
+            ]
+        );
+
+        let other = service
+            .query_archive("other-domain", "SELECT * FROM query_history_archive")
+            .await
+            .unwrap();
+        assert_eq!(other.row_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_query_archived_cross_database_entry() {
+        let dir = tempdir().unwrap();
+        let aliases = [("pg".to_string(), "conn-1".to_string()), ("my".to_string(), "conn-2".to_string())];
+        let cross = history_at(1, "SELECT * FROM pg.orders o JOIN my.customers c ON o.customer_id = c.id")
+            .with_connections(
+                vec!["conn-1".to_string(), "conn-2".to_string()],
+                Some(aliases.into_iter().collect()),
+            );
+        HistoryArchiveService::write_partition(dir.path(), "2024-02", &[history_at(1, "SELECT 1"), cross]).unwrap();
+
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let service = HistoryArchiveService::new(
+            storage,
+            HistoryConfig {
+                retention_days: 30,
+                archive_enabled: true,
+                archive_dir: dir.path().to_string_lossy().to_string(),
+                prune_interval_secs: 3600,
+                result_max_rows: 100,
+                result_max_bytes: 1024 * 1024,
+            },
+        );
+
+        let result = service
+            .query_archive(
+                "default-domain-id",
+                "SELECT connection_id, connection_ids, database_aliases FROM query_history_archive \
+                 WHERE connection_ids IS NOT NULL",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.rows,
+            vec![serde_json::json!({
+                "connection_id": null,
+                "connection_ids": r#"["conn-1","conn-2"]"#,
+                "database_aliases": r#"{"my":"conn-2","pg":"conn-1"}"#,
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_disabled_when_retention_zero() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let service = HistoryArchiveService::new(
+            storage,
+            HistoryConfig {
+                retention_days: 0,
+                archive_enabled: true,
+                archive_dir: dir.path().to_string_lossy().to_string(),
+                prune_interval_secs: 3600,
+                result_max_rows: 100,
+                result_max_bytes: 1024 * 1024,
+            },
+        );
+
+        let summary = service.prune().await.unwrap();
+        assert_eq!(summary.pruned, 0);
+        assert!(summary.files.is_empty());
+    }
+}
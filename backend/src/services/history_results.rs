@@ -0,0 +1,196 @@
+// History Result Service
+//
+// Keeps the result of a query with its history entry when the query asks for
+// it (`save_result`). `rows` keeps the first `HISTORY_RESULT_MAX_ROWS` rows as
+// JSON; `parquet` keeps the whole result as one Parquet file, as long as the
+// file stays within `HISTORY_RESULT_MAX_BYTES`. Results that cannot be kept
+// as Parquet (no rows, or a file over the limit) fall back to the first rows.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use chrono::Utc;
+use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use datafusion::parquet::arrow::ArrowWriter;
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::config::HistoryConfig;
+use crate::models::{HistoryResult, HistoryResultFormat};
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::result_transform::rows_to_record_batch;
+use crate::storage::SqliteStorage;
+
+pub struct HistoryResultService {
+    storage: Arc<SqliteStorage>,
+    config: HistoryConfig,
+}
+
+impl HistoryResultService {
+    pub fn new(storage: Arc<SqliteStorage>, config: HistoryConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Keep the rows of a query result with its history entry
+    pub async fn capture(
+        &self,
+        history_id: &str,
+        format: HistoryResultFormat,
+        rows: &[Value],
+    ) -> Result<HistoryResult, AppError> {
+        if format == HistoryResultFormat::Parquet && !rows.is_empty() {
+            let parquet = encode_parquet(rows)?;
+            if parquet.len() as u64 <= self.config.result_max_bytes {
+                let result = HistoryResult {
+                    history_id: history_id.to_string(),
+                    format,
+                    total_row_count: rows.len(),
+                    row_count: rows.len(),
+                    truncated: false,
+                    size_bytes: parquet.len() as u64,
+                    captured_at: Utc::now(),
+                    results: Vec::new(),
+                };
+                self.storage
+                    .save_history_result(&result, Some(&parquet))
+                    .await
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                return Ok(result);
+            }
+            tracing::debug!(
+                history_id,
+                size_bytes = parquet.len(),
+                "Parquet result over the size limit, keeping the first rows instead"
+            );
+        }
+
+        let kept = &rows[..rows.len().min(self.config.result_max_rows)];
+        let result = HistoryResult {
+            history_id: history_id.to_string(),
+            format: HistoryResultFormat::Rows,
+            total_row_count: rows.len(),
+            row_count: kept.len(),
+            truncated: kept.len() < rows.len(),
+            size_bytes: serde_json::to_string(kept).map(|json| json.len() as u64).unwrap_or(0),
+            captured_at: Utc::now(),
+            results: kept.to_vec(),
+        };
+        self.storage
+            .save_history_result(&result, None)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(result)
+    }
+
+    /// The result kept with a history entry, with its rows, and the Parquet
+    /// file for `parquet` results
+    pub async fn get(&self, history_id: &str) -> Result<(HistoryResult, Option<Vec<u8>>), AppError> {
+        let (mut result, parquet) = self
+            .storage
+            .get_history_result(history_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("No result was saved with query history {}", history_id)))?;
+        if let Some(parquet) = &parquet {
+            result.results = decode_parquet(parquet)?;
+        }
+        Ok((result, parquet))
+    }
+}
+
+fn encode_parquet(rows: &[Value]) -> Result<Vec<u8>, AppError> {
+    let batch = rows_to_record_batch(rows)?;
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None)
+        .map_err(|e| AppError::Internal(format!("Failed to create Parquet writer: {}", e)))?;
+    writer
+        .write(&batch)
+        .map_err(|e| AppError::Internal(format!("Failed to write result: {}", e)))?;
+    writer
+        .close()
+        .map_err(|e| AppError::Internal(format!("Failed to finalize result: {}", e)))?;
+    Ok(buffer)
+}
+
+fn decode_parquet(parquet: &[u8]) -> Result<Vec<Value>, AppError> {
+    let read_error = |e: &dyn std::fmt::Display| AppError::Internal(format!("Failed to read saved result: {}", e));
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(parquet)).map_err(|e| read_error(&e))?;
+    let schema = builder.schema().clone();
+    let batches = builder
+        .build()
+        .map_err(|e| read_error(&e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| read_error(&e))?;
+    let result = DataFusionResultConverter::convert_to_query_result(schema, batches).map_err(|e| read_error(&e))?;
+    Ok(result.rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DatabaseConnection, QueryHistory};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_capture_rows_and_parquet() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let connection = DatabaseConnection::new(
+                None,
+                "postgresql://localhost/test".to_string(),
+                "postgresql".to_string(),
+                Some("default-domain-id".to_string()),
+            );
+            storage.save_connection(&connection).await.unwrap();
+            let mut entries = Vec::new();
+            for _ in 0..3 {
+                let history = QueryHistory::new(
+                    "default-domain-id".to_string(),
+                    connection.id.clone(),
+                    "SELECT id, region FROM orders".to_string(),
+                    3,
+                    10,
+                    false,
+                );
+                storage.add_query_history(&history).await.unwrap();
+                entries.push(history);
+            }
+
+            let rows: Vec<Value> = (1..=3).map(|id| json!({"id": id, "region": "eu"})).collect();
+            let config = HistoryConfig {
+                retention_days: 0,
+                archive_enabled: false,
+                archive_dir: String::new(),
+                prune_interval_secs: 3600,
+                result_max_rows: 2,
+                result_max_bytes: 1024 * 1024,
+            };
+            let service = HistoryResultService::new(storage.clone(), config.clone());
+
+            let kept = service.capture(&entries[0].id, HistoryResultFormat::Rows, &rows).await.unwrap();
+            assert_eq!((kept.row_count, kept.total_row_count, kept.truncated), (2, 3, true));
+            let (stored, parquet) = service.get(&entries[0].id).await.unwrap();
+            assert!(parquet.is_none());
+            assert_eq!(stored.results, rows[..2].to_vec());
+            assert!(storage.get_query_history(&entries[0].id).await.unwrap().unwrap().has_result);
+
+            service.capture(&entries[1].id, HistoryResultFormat::Parquet, &rows).await.unwrap();
+            let (stored, parquet) = service.get(&entries[1].id).await.unwrap();
+            assert_eq!(stored.format, HistoryResultFormat::Parquet);
+            assert_eq!(stored.size_bytes, parquet.unwrap().len() as u64);
+            assert_eq!(stored.results, rows);
+
+            // Files over the size limit fall back to the first rows
+            let small = HistoryResultService::new(storage.clone(), HistoryConfig { result_max_bytes: 16, ..config });
+            let fallback = small.capture(&entries[2].id, HistoryResultFormat::Parquet, &rows).await.unwrap();
+            assert_eq!(fallback.format, HistoryResultFormat::Rows);
+            assert_eq!(fallback.row_count, 2);
+
+            assert!(matches!(service.get("missing").await, Err(AppError::NotFound(_))));
+        });
+    }
+}
@@ -1,14 +1,33 @@
-use crate::models::DatabaseMetadata;
+use crate::models::{
+    ChatTurn, DatabaseMetadata, LlmAuditContext, LlmAuditEntry, LlmTokenUsage, PromptRedactions, SqlExample,
+};
 use crate::api::middleware::AppError;
 use crate::config::Config;
+use crate::services::PromptRedactor;
+use crate::storage::SqliteStorage;
+use std::sync::Arc;
 use serde_json::json;
 use reqwest::Client as HttpClient;
+use tokio::sync::mpsc;
+
+/// Bytes of EXPLAIN output given to the LLM for optimization advice
+const ADVICE_PLAN_MAX_BYTES: usize = 8000;
 
 /// LLM service for converting metadata to JSON format and generating SQL from natural language
 pub struct LlmService {
     gateway_url: String,
     api_key: Option<String>,
     http_client: HttpClient,
+    /// Answered questions given to SQL prompts as examples
+    examples: Vec<SqlExample>,
+    /// Tokens of the gateway calls made so far
+    usage: std::sync::Mutex<LlmTokenUsage>,
+    /// Personal data kept out of prompts
+    redactor: PromptRedactor,
+    /// Redactions of the prompt being built, recorded when it is sent
+    redactions: std::sync::Mutex<PromptRedactions>,
+    /// Audit log every prompt sent to the gateway is recorded in
+    audit: Option<(Arc<SqliteStorage>, LlmAuditContext)>,
 }
 
 impl LlmService {
@@ -17,9 +36,73 @@ impl LlmService {
             gateway_url: config.llm.gateway_url.clone(),
             api_key: config.llm.api_key.clone(),
             http_client: HttpClient::new(),
+            examples: Vec::new(),
+            usage: std::sync::Mutex::new(LlmTokenUsage::default()),
+            redactor: PromptRedactor::new(&config.llm),
+            redactions: std::sync::Mutex::new(PromptRedactions::default()),
+            audit: None,
         }
     }
 
+    /// Give SQL generation these answered questions as few-shot examples
+    pub fn with_examples(mut self, examples: Vec<SqlExample>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Redact prompts with this redactor (e.g. one with the domain's deny-list)
+    pub fn with_redactor(mut self, redactor: PromptRedactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Record every prompt sent to the gateway in the LLM audit log
+    ///
+    /// Without it prompts are still redacted, but not recorded.
+    pub fn with_audit(mut self, storage: Arc<SqliteStorage>, context: LlmAuditContext) -> Self {
+        self.audit = Some((storage, context));
+        self
+    }
+
+    /// Whether values of a result column are masked in prompts
+    pub fn masks_column(&self, column: &str) -> bool {
+        self.redactor.masks_column(column)
+    }
+
+    /// Result rows as they may go into a prompt, with masked values
+    pub fn redact_rows(&self, rows: &[serde_json::Value]) -> Vec<serde_json::Value> {
+        let (rows, redactions) = self.redactor.mask_rows(rows);
+        self.note_redactions(redactions);
+        rows
+    }
+
+    fn note_redactions(&self, redactions: PromptRedactions) {
+        self.redactions.lock().unwrap_or_else(|e| e.into_inner()).merge(redactions);
+    }
+
+    /// Record `prompt` in the audit log before it is sent
+    ///
+    /// A prompt that can't be recorded is not sent.
+    async fn audit_prompt(&self, purpose: &str, prompt: &str) -> Result<(), AppError> {
+        let redactions = std::mem::take(&mut *self.redactions.lock().unwrap_or_else(|e| e.into_inner()));
+        let Some((storage, context)) = &self.audit else {
+            return Ok(());
+        };
+        storage
+            .add_llm_audit_entry(&LlmAuditEntry::new(context, purpose, prompt, redactions))
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to record LLM prompt in the audit log: {}", e)))
+    }
+
+    /// Tokens of the gateway calls this service made (none for the rule-based fallback)
+    pub fn token_usage(&self) -> LlmTokenUsage {
+        *self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add_token_usage(&self, usage: LlmTokenUsage) {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner()).add(usage);
+    }
+
     /// Convert metadata to JSON format using LLM
     /// For Phase 3, we'll use a simple JSON serialization
     /// Full LLM integration will be added when rig.rs is available
@@ -41,8 +124,11 @@ impl LlmService {
             .map_err(|e| AppError::LlmService(format!("Failed to serialize metadata: {}", e)))
     }
 
-    /// Prepare metadata context for LLM
+    /// Prepare metadata context for LLM, without the tables and columns the redactor withholds
     pub fn prepare_metadata_context(&self, metadata: &DatabaseMetadata) -> String {
+        let (metadata, redactions) = self.redactor.redact_metadata(metadata);
+        self.note_redactions(redactions);
+
         let mut context = String::from("Database Schema:\n\n");
         
         // Add schemas
@@ -56,10 +142,17 @@ impl LlmService {
         if !metadata.tables.is_empty() {
             context.push_str("Tables:\n");
             for table in &metadata.tables {
-                context.push_str(&format!("  - {}.{}\n", 
+                context.push_str(&format!("  - {}.{}", 
                     table.schema.as_ref().unwrap_or(&"public".to_string()),
                     table.name
                 ));
+                push_annotation(&mut context, table.description.as_deref(), &table.tags);
+                // Filters on partition keys let Druid and Doris skip data
+                if let Some(summary) = table.engine_metadata.as_ref().map(|engine| engine.summary()) {
+                    if !summary.is_empty() {
+                        context.push_str(&format!("    Layout: {}\n", summary));
+                    }
+                }
                 context.push_str("    Columns:\n");
                 for column in &table.columns {
                     context.push_str(&format!("      * {} ({})", column.name, column.data_type));
@@ -72,7 +165,7 @@ impl LlmService {
                     if !column.is_nullable {
                         context.push_str(" [NOT NULL]");
                     }
-                    context.push('\n');
+                    push_annotation(&mut context, column.description.as_deref(), &column.tags);
                 }
             }
             context.push('\n');
@@ -82,13 +175,15 @@ impl LlmService {
         if !metadata.views.is_empty() {
             context.push_str("Views:\n");
             for view in &metadata.views {
-                context.push_str(&format!("  - {}.{}\n",
+                context.push_str(&format!("  - {}.{}",
                     view.schema.as_ref().unwrap_or(&"public".to_string()),
                     view.name
                 ));
+                push_annotation(&mut context, view.description.as_deref(), &view.tags);
                 context.push_str("    Columns:\n");
                 for column in &view.columns {
-                    context.push_str(&format!("      * {} ({})\n", column.name, column.data_type));
+                    context.push_str(&format!("      * {} ({})", column.name, column.data_type));
+                    push_annotation(&mut context, column.description.as_deref(), &column.tags);
                 }
             }
         }
@@ -103,32 +198,274 @@ impl LlmService {
         metadata: &DatabaseMetadata,
         database_type: &str,  // Add database_type parameter
     ) -> Result<String, AppError> {
+        let prompt = self.sql_prompt(question, metadata, database_type, &[]);
+
+        // Call LLM service
+        // For now, we'll use a simple HTTP-based approach
+        // In production, this would use rig.rs or a proper LLM gateway
+        self.call_llm_api("generate_sql", &prompt).await
+    }
+
+    /// Generate SQL like `generate_sql_from_natural_language`, sending the text
+    /// to `tokens` as the LLM produces it
+    ///
+    /// Returns the complete SQL, with markdown code fences removed (the tokens
+    /// are sent as received).
+    pub async fn stream_sql_from_natural_language(
+        &self,
+        question: &str,
+        metadata: &DatabaseMetadata,
+        database_type: &str,
+        tokens: mpsc::UnboundedSender<String>,
+    ) -> Result<String, AppError> {
+        let prompt = self.sql_prompt(question, metadata, database_type, &[]);
+        self.stream_llm_api("generate_sql", &prompt, tokens).await
+    }
+
+    /// Generate one cross-database query answering `question` over several connections
+    ///
+    /// `catalogs` holds the catalog alias, database type and metadata of each
+    /// connection; tables are to be referenced as `alias.schema.table`. Fails
+    /// without a configured gateway, as the rule-based fallback can't join
+    /// tables of different connections.
+    pub async fn generate_cross_database_sql(
+        &self,
+        question: &str,
+        catalogs: &[(String, String, DatabaseMetadata)],
+    ) -> Result<String, AppError> {
+        if !self.is_configured() {
+            return Err(AppError::LlmService(
+                "Cross-database natural language queries need a configured LLM gateway".to_string(),
+            ));
+        }
+
+        let schemas: String = catalogs
+            .iter()
+            .map(|(alias, database_type, metadata)| {
+                format!(
+                    "Connection `{alias}` ({database_type}); reference its tables as {alias}.<schema>.<table>\n{context}",
+                    alias = alias,
+                    database_type = database_type,
+                    context = self.prepare_metadata_context(metadata),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let prompt = format!(
+            r#"You are a SQL expert. Given the schemas of several databases and a natural language question, generate one SELECT query that may read tables of different databases.
+
+{schemas}
+Question: {question}
+
+Instructions:
+1. Generate ONLY a valid SELECT query in standard SQL (it runs on DataFusion, which is PostgreSQL-compatible)
+2. Do not include any explanations or markdown formatting
+3. Qualify every table with its connection, as alias.schema.table, using only the connections above
+4. Only join tables of different connections on columns that hold the same values (e.g. ids, emails)
+5. Use portable functions only; avoid functions specific to one database
+6. If the question asks about "数量" (count) or "多少" (how many), use COUNT(*)
+
+SQL Query:"#,
+            schemas = schemas,
+            question = question,
+        );
+        self.call_llm_api("cross_database_sql", &prompt).await
+    }
+
+    /// Generate SQL for a follow-up question of a chat session
+    ///
+    /// `history` (oldest first) goes into the prompt, so a question such as
+    /// "now only for last month" refines the SQL of the previous turn.
+    pub async fn generate_sql_in_conversation(
+        &self,
+        question: &str,
+        history: &[ChatTurn],
+        metadata: &DatabaseMetadata,
+        database_type: &str,
+    ) -> Result<String, AppError> {
+        let prompt = self.sql_prompt(question, metadata, database_type, history);
+        self.call_llm_api("chat_sql", &prompt).await
+    }
+
+    /// Rewrite generated SQL the database rejected with `error_message`
+    ///
+    /// The LLM gets the question (with the chat `history`, if any), the failed
+    /// SQL and the error, and is asked for a corrected query.
+    pub async fn repair_sql(
+        &self,
+        question: &str,
+        history: &[ChatTurn],
+        failed_sql: &str,
+        error_message: &str,
+        metadata: &DatabaseMetadata,
+        database_type: &str,
+    ) -> Result<String, AppError> {
+        let metadata_context = self.prepare_metadata_context(metadata);
+        let prompt = format!(
+            r#"You are a SQL expert. A {database_type} query generated for the question below failed. Fix it.
+
+Database Schema:
+{metadata_context}
+{conversation}
+Question: {question}
+
+Failed SQL:
+{failed_sql}
+
+Database error:
+{error_message}
+
+Instructions:
+1. Return a corrected {database_type} SELECT query that answers the question
+2. Address the database error; use only tables and columns from the schema above
+3. Do not include any explanations or markdown formatting
+4. Return ONLY the SQL query, nothing else
+{dialect_hints}
+
+SQL Query:"#,
+            database_type = database_type,
+            metadata_context = metadata_context,
+            conversation = conversation_context(history),
+            question = question,
+            failed_sql = failed_sql,
+            error_message = error_message,
+            dialect_hints = dialect_hints(database_type),
+        );
+        self.call_llm_api("repair_sql", &prompt).await
+    }
+
+    /// Ask for optimization advice on `sql`, given its EXPLAIN output and the schema of its tables
+    ///
+    /// Returns the LLM's answer, asked to be a JSON array of suggestions. Fails
+    /// without a configured gateway, as the rule-based fallback only writes SQL.
+    pub async fn advise_query(
+        &self,
+        sql: &str,
+        plan: &serde_json::Value,
+        metadata: &DatabaseMetadata,
+        database_type: &str,
+    ) -> Result<String, AppError> {
+        if !self.is_configured() {
+            return Err(AppError::LlmService("LLM gateway is not configured".to_string()));
+        }
+
+        let mut plan_text = serde_json::to_string(plan).unwrap_or_default();
+        if plan_text.len() > ADVICE_PLAN_MAX_BYTES {
+            let mut end = ADVICE_PLAN_MAX_BYTES;
+            while !plan_text.is_char_boundary(end) {
+                end -= 1;
+            }
+            plan_text.truncate(end);
+            plan_text.push_str(" ...(truncated)");
+        }
+        let table_sizes: String = metadata
+            .tables
+            .iter()
+            .filter_map(|table| {
+                let rows = table.row_count?;
+                Some(format!("  - {}.{}: ~{} rows\n", table.schema.as_deref().unwrap_or("public"), table.name, rows))
+            })
+            .collect();
+
+        let prompt = format!(
+            r#"You are a {database_type} performance expert. Suggest how to make the query below faster.
+
+{metadata_context}
+Table sizes:
+{table_sizes}
+Query:
+{sql}
+
+EXPLAIN output:
+{plan_text}
+
+Instructions:
+1. Answer with ONLY a JSON array, no explanations or markdown formatting
+2. Each element is an object with the fields:
+   "kind": "missing_index", "rewrite", "statistics" or "other"
+   "impact": "high", "medium" or "low"
+   "title": short summary
+   "description": why it helps, referring to the plan
+   "table": affected table or null
+   "columns": array of column names (the index columns for missing_index)
+   "sql": the CREATE INDEX statement for missing_index, the rewritten query for rewrite, otherwise null
+3. Only suggest indexes on columns the query filters, joins or sorts on
+4. Answer [] if the query is already efficient"#,
+            database_type = database_type,
+            metadata_context = self.prepare_metadata_context(metadata),
+            table_sizes = table_sizes,
+            sql = sql,
+            plan_text = plan_text,
+        );
+        self.complete("advise_query", &prompt, 1500).await
+    }
+
+    /// Ask for the takeaways of a query result, given its column statistics and sampled rows
+    ///
+    /// Returns the LLM's answer, asked to be a JSON object with a summary,
+    /// insights and follow-up questions. Fails without a configured gateway.
+    pub async fn summarize_result(
+        &self,
+        question: Option<&str>,
+        sql: &str,
+        column_profile: &str,
+        sample: &[serde_json::Value],
+        row_count: usize,
+    ) -> Result<String, AppError> {
+        if !self.is_configured() {
+            return Err(AppError::LlmService("LLM gateway is not configured".to_string()));
+        }
+
+        let question = question
+            .map(|q| format!("Question: {}\n", q))
+            .unwrap_or_default();
+        let prompt = format!(
+            r#"You are a data analyst explaining a query result to a non-technical reader.
+
+{question}Query:
+{sql}
+
+The result has {row_count} rows. Column statistics:
+{column_profile}
+
+Sample of {sample_len} rows:
+{sample}
+
+Instructions:
+1. Answer with ONLY a JSON object, no explanations or markdown formatting
+2. The object has the fields:
+   "summary": two or three plain sentences on what the result shows
+   "insights": array of short notable facts (extremes, concentrations, trends, gaps)
+   "follow_up_questions": array of up to 3 questions worth asking next, phrased as natural language queries
+3. Base every statement on the statistics and sample; don't invent values
+4. Mention when the sample may not represent the whole result"#,
+            question = question,
+            sql = sql,
+            row_count = row_count,
+            column_profile = column_profile,
+            sample_len = sample.len(),
+            sample = serde_json::to_string(sample).unwrap_or_default(),
+        );
+        self.complete("summarize_result", &prompt, 800).await
+    }
+
+    /// Prompt asking for a SELECT query answering `question`, after the turns of `history`
+    fn sql_prompt(&self, question: &str, metadata: &DatabaseMetadata, database_type: &str, history: &[ChatTurn]) -> String {
         // Prepare metadata context
         let metadata_context = self.prepare_metadata_context(metadata);
+        let examples = examples_context(&self.examples);
+        let conversation = conversation_context(history);
 
-        // Determine SQL dialect hints based on database type
-        let dialect_hints = match database_type {
-            "mysql" => r#"
-- Use MySQL syntax and functions
-- Use LIMIT syntax (not TOP or FETCH FIRST)
-- For dates, use functions like NOW(), CURDATE(), DATE_SUB(), etc.
-- String concatenation uses CONCAT() function
-- Use backticks for identifier quoting if needed: `table_name`"#,
-            "postgresql" | _ => r#"
-- Use PostgreSQL syntax and functions
-- Use LIMIT syntax (or FETCH FIRST)
-- For dates, use functions like NOW(), CURRENT_DATE, interval arithmetic
-- String concatenation uses || operator or CONCAT()
-- Use double quotes for identifier quoting if needed: "table_name""#,
-        };
+        let dialect_hints = dialect_hints(database_type);
 
         // Create prompt for LLM
-        let prompt = format!(
+        format!(
             r#"You are a SQL expert. Given a database schema and a natural language question, generate a valid {database_type} SELECT query.
 
 Database Schema:
 {metadata_context}
-
+{examples}{conversation}
 Question: {question}
 
 Instructions:
@@ -138,35 +475,48 @@ Instructions:
 4. Return ONLY the SQL query, nothing else
 5. If the question asks about "数量" (count) or "多少" (how many), use COUNT(*)
 6. If the question asks about specific columns, select only those columns
+7. If the question follows up on an earlier turn of the conversation, adapt that turn's SQL instead of starting over
 {dialect_hints}
 
 SQL Query:"#,
             database_type = database_type,
             metadata_context = metadata_context,
+            examples = examples,
+            conversation = conversation,
             question = question,
             dialect_hints = dialect_hints
-        );
+        )
+    }
 
-        // Call LLM service
-        // For now, we'll use a simple HTTP-based approach
-        // In production, this would use rig.rs or a proper LLM gateway
-        self.call_llm_api(&prompt).await
+    /// Whether an LLM gateway is configured (otherwise SQL comes from the rule-based fallback)
+    pub fn is_configured(&self) -> bool {
+        !self.gateway_url.is_empty() && self.gateway_url != "http://localhost:8080"
     }
 
     /// Call LLM API to generate SQL
-    async fn call_llm_api(&self, prompt: &str) -> Result<String, AppError> {
+    async fn call_llm_api(&self, purpose: &str, prompt: &str) -> Result<String, AppError> {
         // Check if LLM gateway is configured
-        if self.gateway_url.is_empty() || self.gateway_url == "http://localhost:8080" {
+        if !self.is_configured() {
             // Fallback: Use a simple rule-based approach for demonstration
             return self.fallback_sql_generation(prompt);
         }
 
+        let sql = self.complete(purpose, prompt, 500).await?;
+        Ok(clean_sql(&sql))
+    }
+
+    /// Send `prompt` to the LLM gateway and return the text of its response
+    ///
+    /// The prompt is recorded in the audit log first, under `purpose`.
+    async fn complete(&self, purpose: &str, prompt: &str, max_tokens: u32) -> Result<String, AppError> {
+        self.audit_prompt(purpose, prompt).await?;
+
         // Prepare request
         let mut request = self.http_client
             .post(&self.gateway_url)
             .json(&json!({
                 "prompt": prompt,
-                "max_tokens": 500,
+                "max_tokens": max_tokens,
                 "temperature": 0.1,
             }));
 
@@ -196,25 +546,110 @@ SQL Query:"#,
             .await
             .map_err(|e| AppError::LlmService(format!("Failed to parse LLM response: {}", e)))?;
 
-        // Extract SQL from response (adjust based on your LLM API format)
-        let sql = result["text"]
-            .as_str()
-            .or_else(|| result["content"].as_str())
-            .or_else(|| result["response"].as_str())
-            .ok_or_else(|| {
+        // Extract text from response (adjust based on your LLM API format)
+        let text = response_text(&result)
+            .map(str::to_string)
+            .ok_or_else(|| AppError::LlmService("LLM response does not contain text".to_string()))?;
+        self.add_token_usage(response_usage(&result, prompt, &text));
+        Ok(text)
+    }
+
+    /// Call the LLM API with `"stream": true`, sending each generated piece of text to `tokens`
+    ///
+    /// The gateway streams Server-Sent Events (`data: {...}`) or newline-delimited
+    /// JSON, each object holding the next piece of text like a complete response
+    /// holds the SQL. A gateway answering with a single JSON response is read as
+    /// one token. Without a configured gateway, the fallback SQL is sent word by word.
+    async fn stream_llm_api(
+        &self,
+        purpose: &str,
+        prompt: &str,
+        tokens: mpsc::UnboundedSender<String>,
+    ) -> Result<String, AppError> {
+        if !self.is_configured() {
+            let sql = self.fallback_sql_generation(prompt)?;
+            for token in sql.split_inclusive(' ') {
+                let _ = tokens.send(token.to_string());
+            }
+            return Ok(sql);
+        }
+        self.audit_prompt(purpose, prompt).await?;
+
+        let mut request = self.http_client
+            .post(&self.gateway_url)
+            .json(&json!({
+                "prompt": prompt,
+                "max_tokens": 500,
+                "temperature": 0.1,
+                "stream": true,
+            }));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| AppError::LlmService(format!("Failed to call LLM service: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::LlmService(format!(
+                "LLM service returned error {}: {}",
+                status, error_text
+            )));
+        }
+
+        let mut body = Vec::new();
+        let mut pending = Vec::new();
+        let mut sql = String::new();
+        let mut streamed = false;
+        loop {
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(|e| AppError::LlmService(format!("Failed to read LLM stream: {}", e)))?;
+            let done = chunk.is_none();
+            match chunk {
+                Some(chunk) => {
+                    body.extend_from_slice(&chunk);
+                    pending.extend_from_slice(&chunk);
+                }
+                // The last line may have no newline
+                None => pending.push(b'\n'),
+            }
+
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                if let Some(token) = stream_token(&String::from_utf8_lossy(&line)) {
+                    streamed = true;
+                    sql.push_str(&token);
+                    let _ = tokens.send(token);
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        if !streamed {
+            // Not a stream: a single (possibly multi-line) JSON response
+            let result: serde_json::Value = serde_json::from_slice(&body)
+                .map_err(|e| AppError::LlmService(format!("Failed to parse LLM response: {}", e)))?;
+            let text = response_text(&result).ok_or_else(|| {
                 AppError::LlmService("LLM response does not contain SQL query".to_string())
             })?;
+            sql = text.to_string();
+            let _ = tokens.send(sql.clone());
+            self.add_token_usage(response_usage(&result, prompt, &sql));
+        } else {
+            self.add_token_usage(LlmTokenUsage {
+                prompt_tokens: estimate_tokens(prompt),
+                completion_tokens: estimate_tokens(&sql),
+            });
+        }
 
-        // Clean up SQL (remove markdown code blocks if present)
-        let cleaned_sql = sql
-            .trim()
-            .trim_start_matches("```sql")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim()
-            .to_string();
-
-        Ok(cleaned_sql)
+        Ok(clean_sql(&sql))
     }
 
     /// Fallback SQL generation using simple pattern matching
@@ -336,10 +771,212 @@ SQL Query:"#,
             format!("SELECT * FROM {}", table_name)
         };
         
-        tracing::warn!("Using fallback SQL generation. Configure LLM service for better results. Generated: {}", sql);
-        tracing::warn!("Question was: {}", question);
+        tracing::warn!(
+            sql = %crate::logging::redactor().sql(&sql),
+            question = %crate::logging::redactor().text(question),
+            "Using fallback SQL generation. Configure LLM service for better results."
+        );
         
         Ok(sql)
     }
 }
 
+/// SQL text of an LLM response (adjust based on your LLM API format)
+fn response_text(result: &serde_json::Value) -> Option<&str> {
+    result["text"]
+        .as_str()
+        .or_else(|| result["content"].as_str())
+        .or_else(|| result["response"].as_str())
+}
+
+/// Tokens of a gateway response: its `usage` object (OpenAI `prompt_tokens` /
+/// `completion_tokens` or Anthropic `input_tokens` / `output_tokens`), estimated
+/// from the prompt and text when the gateway reports none
+fn response_usage(result: &serde_json::Value, prompt: &str, text: &str) -> LlmTokenUsage {
+    let usage = &result["usage"];
+    let count = |names: [&str; 2]| names.iter().find_map(|name| usage[*name].as_u64());
+    LlmTokenUsage {
+        prompt_tokens: count(["prompt_tokens", "input_tokens"]).unwrap_or_else(|| estimate_tokens(prompt)),
+        completion_tokens: count(["completion_tokens", "output_tokens"]).unwrap_or_else(|| estimate_tokens(text)),
+    }
+}
+
+/// Rough token count of text: about four characters per token
+fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// SQL dialect hints for the prompt, based on the database type
+fn dialect_hints(database_type: &str) -> &'static str {
+    match database_type {
+        "mysql" => r#"
+- Use MySQL syntax and functions
+- Use LIMIT syntax (not TOP or FETCH FIRST)
+- For dates, use functions like NOW(), CURDATE(), DATE_SUB(), etc.
+- String concatenation uses CONCAT() function
+- Use backticks for identifier quoting if needed: `table_name`"#,
+        "postgresql" | _ => r#"
+- Use PostgreSQL syntax and functions
+- Use LIMIT syntax (or FETCH FIRST)
+- For dates, use functions like NOW(), CURRENT_DATE, interval arithmetic
+- String concatenation uses || operator or CONCAT()
+- Use double quotes for identifier quoting if needed: "table_name""#,
+    }
+}
+
+/// Earlier turns of a chat session for the prompt (empty without turns)
+///
+/// Labels avoid `Question:`, which marks the current question in the prompt.
+fn conversation_context(history: &[ChatTurn]) -> String {
+    if history.is_empty() {
+        return String::new();
+    }
+    let mut context = String::from("Conversation so far (oldest first):\n");
+    for (n, turn) in history.iter().enumerate() {
+        context.push_str(&format!("{}. Asked: {}\n", n + 1, turn.question));
+        if let Some(sql) = &turn.generated_sql {
+            context.push_str(&format!("   SQL: {}\n", sql.split_whitespace().collect::<Vec<_>>().join(" ")));
+        }
+        if let Some(summary) = &turn.result_summary {
+            context.push_str(&format!("   Result: {}\n", summary));
+        }
+        if let Some(error) = &turn.error_message {
+            context.push_str(&format!("   Failed: {}\n", error));
+        }
+    }
+    context.push('\n');
+    context
+}
+
+/// Answered questions for the prompt (empty without examples)
+///
+/// Labels avoid `Question:`, which marks the current question in the prompt.
+fn examples_context(examples: &[SqlExample]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut context = String::from("Examples of questions answered correctly on this database:\n");
+    for example in examples {
+        context.push_str(&format!("Example: {}\n", example.question));
+        context.push_str(&format!("SQL: {}\n", example.sql.split_whitespace().collect::<Vec<_>>().join(" ")));
+    }
+    context.push('\n');
+    context
+}
+
+/// End a schema line with its data dictionary description and tags, if any
+fn push_annotation(context: &mut String, description: Option<&str>, tags: &[String]) {
+    if let Some(description) = description {
+        context.push_str(" -- ");
+        context.push_str(&description.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    if !tags.is_empty() {
+        context.push_str(&format!(" [tags: {}]", tags.join(", ")));
+    }
+    context.push('\n');
+}
+
+/// Clean up SQL (remove markdown code blocks if present)
+fn clean_sql(sql: &str) -> String {
+    sql.trim()
+        .trim_start_matches("```sql")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
+        .to_string()
+}
+
+/// Text of one line of a streamed LLM response (`data: {...}` or a bare JSON object)
+///
+/// Blank lines, SSE comments and fields other than `data`, `[DONE]` and objects
+/// without text yield nothing.
+fn stream_token(line: &str) -> Option<String> {
+    let line = line.trim();
+    let data = match line.strip_prefix("data:") {
+        Some(data) => data.trim(),
+        None if line.starts_with('{') => line,
+        None => return None,
+    };
+    if data == "[DONE]" {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    response_text(&value).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_token() {
+        assert_eq!(stream_token("data: {\"text\": \"SELECT \"}\n"), Some("SELECT ".to_string()));
+        assert_eq!(stream_token("{\"response\": \"COUNT(*)\"}"), Some("COUNT(*)".to_string()));
+        assert_eq!(stream_token("data: [DONE]"), None);
+        assert_eq!(stream_token("event: token"), None);
+        assert_eq!(stream_token(": keep-alive"), None);
+        assert_eq!(stream_token("data: {\"done\": true}"), None);
+        assert_eq!(stream_token(""), None);
+    }
+
+    #[test]
+    fn test_conversation_context() {
+        assert_eq!(conversation_context(&[]), "");
+
+        let turn = ChatTurn::new(
+            "session".to_string(),
+            "Revenue by region".to_string(),
+            "SELECT region, SUM(total)\nFROM sales\nGROUP BY region".to_string(),
+        );
+        let failed = ChatTurn::new("session".to_string(), "and by month".to_string(), "SELECT month".to_string())
+            .with_error("column \"month\" does not exist".to_string());
+        let context = conversation_context(&[turn, failed]);
+        assert!(context.contains("1. Asked: Revenue by region\n   SQL: SELECT region, SUM(total) FROM sales GROUP BY region\n"));
+        assert!(context.contains("2. Asked: and by month\n"));
+        assert!(context.contains("   Failed: column \"month\" does not exist\n"));
+        assert!(!context.contains("Question:"));
+    }
+
+    #[test]
+    fn test_examples_context() {
+        assert_eq!(examples_context(&[]), "");
+
+        let context = examples_context(&[SqlExample {
+            question: "Orders per region".to_string(),
+            sql: "SELECT region, COUNT(*)\nFROM orders\nGROUP BY region".to_string(),
+        }]);
+        assert!(context.contains("Example: Orders per region\nSQL: SELECT region, COUNT(*) FROM orders GROUP BY region\n"));
+        assert!(!context.contains("Question:"));
+    }
+
+    #[test]
+    fn test_push_annotation() {
+        let mut context = String::from("  - public.orders");
+        push_annotation(&mut context, Some("Customer\norders"), &["finance".to_string(), "pii".to_string()]);
+        assert_eq!(context, "  - public.orders -- Customer orders [tags: finance, pii]\n");
+
+        let mut context = String::from("      * id (integer)");
+        push_annotation(&mut context, None, &[]);
+        assert_eq!(context, "      * id (integer)\n");
+    }
+
+    #[test]
+    fn test_response_usage() {
+        let reported = json!({"text": "SELECT 1", "usage": {"prompt_tokens": 120, "completion_tokens": 4}});
+        assert_eq!(
+            response_usage(&reported, "prompt", "SELECT 1"),
+            LlmTokenUsage { prompt_tokens: 120, completion_tokens: 4 }
+        );
+        let anthropic = json!({"content": "SELECT 1", "usage": {"input_tokens": 90, "output_tokens": 3}});
+        assert_eq!(response_usage(&anthropic, "prompt", "SELECT 1").total(), 93);
+        // No usage reported: about four characters per token
+        let estimated = response_usage(&json!({"text": "SELECT 1"}), "List all users", "SELECT 1");
+        assert_eq!(estimated, LlmTokenUsage { prompt_tokens: 4, completion_tokens: 2 });
+    }
+
+    #[test]
+    fn test_clean_sql() {
+        assert_eq!(clean_sql("```sql\nSELECT 1\n```"), "SELECT 1");
+        assert_eq!(clean_sql("  SELECT 1 "), "SELECT 1");
+    }
+}
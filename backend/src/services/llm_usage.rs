@@ -0,0 +1,177 @@
+// LLM Usage Service
+//
+// Token budgets of domains. Every request to the LLM gateway is metered per
+// domain, principal and day (`llm_usage_daily`) with the tokens the gateway
+// reports, or an estimate; a domain's monthly budget (server-wide, or its own
+// setting) caps natural language queries. Over budget they are rejected, or
+// degraded: sent with fewer tables, no examples and no repairs.
+
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate, Utc};
+
+use crate::api::middleware::AppError;
+use crate::config::LlmConfig;
+use crate::models::{DomainSettings, LlmBudgetAction, LlmBudgetStatus, LlmUsageReport};
+use crate::services::month_range;
+use crate::storage::SqliteStorage;
+
+pub struct LlmUsageService {
+    storage: Arc<SqliteStorage>,
+    config: LlmConfig,
+}
+
+impl LlmUsageService {
+    pub fn new(storage: Arc<SqliteStorage>, config: &LlmConfig) -> Self {
+        Self {
+            storage,
+            config: config.clone(),
+        }
+    }
+
+    /// Budget of a domain this month and the tokens spent on it
+    pub async fn budget(&self, settings: &DomainSettings) -> Result<LlmBudgetStatus, AppError> {
+        let budget = settings
+            .llm_monthly_token_budget
+            .unwrap_or(self.config.monthly_token_budget);
+        let used_tokens = if budget == 0 {
+            0
+        } else {
+            self.storage
+                .llm_tokens_used(&settings.domain_id, first_day_of_month())
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+        };
+        Ok(LlmBudgetStatus::new(
+            budget,
+            used_tokens,
+            LlmBudgetAction::parse(&self.config.budget_exceeded_action),
+        ))
+    }
+
+    /// Check a domain's budget before a natural language query
+    ///
+    /// Returns whether the query must be degraded; fails with `QuotaExceeded`
+    /// when the domain is over budget and its queries are rejected. Connections
+    /// outside a domain have no budget.
+    pub async fn ensure_within_budget(
+        &self,
+        domain_id: Option<&str>,
+        settings: &DomainSettings,
+    ) -> Result<bool, AppError> {
+        if domain_id.is_none() {
+            return Ok(false);
+        }
+        let budget = self.budget(settings).await?;
+        match (budget.exceeded, budget.exceeded_action) {
+            (false, _) => Ok(false),
+            (true, LlmBudgetAction::Degrade) => {
+                tracing::info!(
+                    domain_id = %settings.domain_id,
+                    used_tokens = budget.used_tokens,
+                    "Domain is over its LLM token budget; degrading the natural language query"
+                );
+                Ok(true)
+            }
+            (true, LlmBudgetAction::Reject) => Err(AppError::QuotaExceeded(format!(
+                "Domain {} used {} of its {} LLM tokens this month",
+                settings.domain_id,
+                budget.used_tokens,
+                budget.monthly_token_budget.unwrap_or_default()
+            ))),
+        }
+    }
+
+    /// LLM usage of a domain for a month (`YYYY-MM`, default: current month)
+    pub async fn monthly_report(
+        &self,
+        settings: &DomainSettings,
+        month: Option<&str>,
+    ) -> Result<LlmUsageReport, AppError> {
+        let (first_day, next_month) = month_range(month)?;
+        let records = self
+            .storage
+            .list_llm_usage(&settings.domain_id, first_day, next_month)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let budget = self.budget(settings).await?;
+        Ok(LlmUsageReport::from_records(settings.domain_id.clone(), first_day, &records, budget))
+    }
+}
+
+/// LLM settings of a degraded query: schema retrieval keeps only
+/// `degraded_schema_top_k` tables, whatever the size of the schema
+pub fn degraded_llm_config(config: &LlmConfig) -> LlmConfig {
+    let top_k = config.degraded_schema_top_k.max(1);
+    LlmConfig {
+        schema_retrieval_threshold: top_k,
+        schema_top_k: top_k,
+        few_shot_examples: 0,
+        max_repair_attempts: 0,
+        ..config.clone()
+    }
+}
+
+fn first_day_of_month() -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today.with_day(1).unwrap_or(today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LlmTokenUsage;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_budget_enforcement() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let mut config = LlmConfig {
+                gateway_url: String::new(),
+                api_key: None,
+                embedding_url: None,
+                embedding_model: None,
+                schema_retrieval_threshold: 50,
+                schema_top_k: 15,
+                max_repair_attempts: 2,
+                few_shot_examples: 3,
+                monthly_token_budget: 100,
+                budget_exceeded_action: "reject".to_string(),
+                degraded_schema_top_k: 5,
+                generation_cache_ttl_secs: 0,
+                generation_cache_max_entries: 0,
+                redacted_column_patterns: String::new(),
+            };
+
+            let mut settings = DomainSettings::defaults("default-domain-id".to_string());
+            let service = LlmUsageService::new(storage.clone(), &config);
+            assert!(!service.ensure_within_budget(Some("default-domain-id"), &settings).await.unwrap());
+
+            let tokens = LlmTokenUsage { prompt_tokens: 90, completion_tokens: 10 };
+            storage.record_llm_request("default-domain-id", Some("alice"), tokens, false).await.unwrap();
+            let rejected = service.ensure_within_budget(Some("default-domain-id"), &settings).await;
+            assert!(matches!(rejected, Err(AppError::QuotaExceeded(_))));
+            assert!(!service.ensure_within_budget(None, &settings).await.unwrap());
+
+            config.budget_exceeded_action = "degrade".to_string();
+            let service = LlmUsageService::new(storage.clone(), &config);
+            assert!(service.ensure_within_budget(Some("default-domain-id"), &settings).await.unwrap());
+
+            // The domain's own budget wins, 0 being unlimited
+            settings.llm_monthly_token_budget = Some(0);
+            assert!(!service.ensure_within_budget(Some("default-domain-id"), &settings).await.unwrap());
+
+            let report = service.monthly_report(&settings, None).await.unwrap();
+            assert_eq!(report.totals.total_tokens, 100);
+            assert_eq!(report.principals[0].principal.as_deref(), Some("alice"));
+            assert_eq!(report.budget.monthly_token_budget, None);
+
+            let degraded = degraded_llm_config(&config);
+            assert_eq!(degraded.schema_top_k, config.degraded_schema_top_k);
+            assert_eq!(degraded.few_shot_examples, 0);
+        });
+    }
+}
@@ -1,34 +1,52 @@
-use crate::models::DatabaseMetadata;
+use crate::config::MetadataConfig;
+use crate::models::{DatabaseMetadata, MetadataDiff, MetadataVersionSummary};
 use crate::storage::SqliteStorage;
 use crate::api::middleware::AppError;
 
 /// Metadata cache service for storing and retrieving cached metadata
 pub struct MetadataCacheService {
     storage: std::sync::Arc<SqliteStorage>,
+    config: MetadataConfig,
 }
 
 impl MetadataCacheService {
-    pub fn new(storage: std::sync::Arc<SqliteStorage>) -> Self {
-        Self { storage }
+    pub fn new(storage: std::sync::Arc<SqliteStorage>, config: MetadataConfig) -> Self {
+        Self { storage, config }
     }
 
-    /// Get cached metadata for a connection
+    /// Get cached metadata for a connection, with the descriptions and tags
+    /// of its data dictionary merged in
     pub async fn get_cached_metadata(
         &self,
         connection_id: &str,
     ) -> Result<Option<DatabaseMetadata>, AppError> {
-        self.storage
+        let Some(mut metadata) = self
+            .storage
             .get_metadata_cache(connection_id)
             .await
-            .map_err(|e| AppError::Database(e.to_string()))
+            .map_err(|e| AppError::Database(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        let dictionary = self
+            .storage
+            .list_dictionary_entries(connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        metadata.annotate(&dictionary);
+        Ok(Some(metadata))
     }
 
-    /// Save metadata to cache
-    pub async fn save_metadata(&self, metadata: &DatabaseMetadata) -> Result<(), AppError> {
-        self.storage
-            .save_metadata_cache(metadata)
+    /// Save metadata to cache as a new version
+    ///
+    /// Updates `metadata.version` with the version assigned by storage.
+    pub async fn save_metadata(&self, metadata: &mut DatabaseMetadata) -> Result<(), AppError> {
+        metadata.version = self
+            .storage
+            .save_metadata_cache(metadata, self.config.retained_versions)
             .await
-            .map_err(|e| AppError::Database(e.to_string()))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
     }
 
     /// Check if cached metadata exists and is fresh
@@ -38,5 +56,63 @@ impl MetadataCacheService {
             None => Ok(false),
         }
     }
-}
 
+    /// List retained metadata versions (newest first)
+    pub async fn list_versions(&self, connection_id: &str) -> Result<Vec<MetadataVersionSummary>, AppError> {
+        self.storage
+            .list_metadata_versions(connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Diff two retained metadata versions
+    ///
+    /// `to` defaults to the latest version and `from` to the version retained
+    /// just before `to`.
+    pub async fn diff(
+        &self,
+        connection_id: &str,
+        from: Option<i32>,
+        to: Option<i32>,
+    ) -> Result<MetadataDiff, AppError> {
+        let versions = self.list_versions(connection_id).await?;
+        if versions.is_empty() {
+            return Err(AppError::NotFound(format!(
+                "No cached metadata for connection {}",
+                connection_id
+            )));
+        }
+
+        let to_version = to.unwrap_or(versions[0].version);
+        let from_version = match from {
+            Some(from) => from,
+            None => versions
+                .iter()
+                .map(|v| v.version)
+                .find(|v| *v < to_version)
+                .ok_or_else(|| {
+                    AppError::NotFound(format!(
+                        "No metadata version older than {} is retained for connection {}",
+                        to_version, connection_id
+                    ))
+                })?,
+        };
+
+        let from_metadata = self.get_version(connection_id, from_version).await?;
+        let to_metadata = self.get_version(connection_id, to_version).await?;
+        Ok(MetadataDiff::between(&from_metadata, &to_metadata))
+    }
+
+    async fn get_version(&self, connection_id: &str, version: i32) -> Result<DatabaseMetadata, AppError> {
+        self.storage
+            .get_metadata_cache_version(connection_id, version)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Metadata version {} not found for connection {} (it may have been pruned)",
+                    version, connection_id
+                ))
+            })
+    }
+}
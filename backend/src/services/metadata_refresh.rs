@@ -0,0 +1,308 @@
+// Metadata Refresh Service
+//
+// Re-reads connection schemas so the cached metadata doesn't go stale after
+// connect time. A refresh saves the schema as a new metadata version; when
+// tables were added, removed or changed, cached results of the connection are
+// dropped and the `metadata.changed` webhook event is raised. Refreshes run on
+// demand or from the background scheduler, once a connection's metadata is
+// older than its domain's refresh interval. With a previous version at hand,
+// only the columns of new or changed tables are read. Saved queries using
+// tables or columns that disappeared are flagged broken.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::api::middleware::AppError;
+use crate::config::{Config, MetadataConfig};
+use crate::models::{
+    ConnectionStatus, DatabaseConnection, DatabaseMetadata, DomainSettings, MetadataDiff, WebhookEvent,
+};
+use crate::services::{
+    ConnectionPoolManager, DbService, LlmService, MetadataCacheService, NotificationService, QueryResultCache,
+    SchemaBreakageService, SubQueryResultCache,
+};
+use crate::storage::SqliteStorage;
+
+/// Schema read by a refresh and how it differs from the previous version
+pub struct MetadataRefresh {
+    pub metadata: DatabaseMetadata,
+    /// None when the connection had no cached metadata
+    pub diff: Option<MetadataDiff>,
+}
+
+pub struct MetadataRefreshService {
+    storage: Arc<SqliteStorage>,
+    pool_manager: Arc<ConnectionPoolManager>,
+    query_cache: Arc<QueryResultCache>,
+    subquery_cache: Arc<SubQueryResultCache>,
+    config: MetadataConfig,
+    llm: LlmService,
+    /// Connections whose schema is being read
+    refreshing: Mutex<HashSet<String>>,
+    /// Last refresh attempt per connection, so failing databases are retried
+    /// once per interval rather than on every check
+    attempted: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MetadataRefreshService {
+    pub fn new(
+        storage: Arc<SqliteStorage>,
+        pool_manager: Arc<ConnectionPoolManager>,
+        query_cache: Arc<QueryResultCache>,
+        subquery_cache: Arc<SubQueryResultCache>,
+        config: &Config,
+    ) -> Self {
+        Self {
+            storage,
+            pool_manager,
+            query_cache,
+            subquery_cache,
+            config: config.metadata.clone(),
+            llm: LlmService::new(config),
+            refreshing: Mutex::new(HashSet::new()),
+            attempted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read a connection's schema, recording whether the database was reachable
+    ///
+    /// With `previous` metadata, only the columns of new or changed tables are
+    /// read. A change of the stored status raises the
+    /// `connection.status_changed` webhook event.
+    pub async fn read_schema(
+        &self,
+        connection: &DatabaseConnection,
+        previous: Option<&DatabaseMetadata>,
+    ) -> Result<DatabaseMetadata, AppError> {
+        let outcome = match previous {
+            Some(previous) => {
+                DbService::refresh_metadata(
+                    connection.id.clone(),
+                    &connection.connection_url,
+                    &connection.database_type,
+                    connection.read_only,
+                    self.pool_manager.clone(),
+                    previous,
+                )
+                .await
+            }
+            None => DbService::connect_and_get_metadata(
+                connection.id.clone(),
+                &connection.connection_url,
+                &connection.database_type,
+                connection.read_only,
+                self.pool_manager.clone(),
+            )
+            .await
+            .map(|(_, metadata)| metadata),
+        };
+
+        let status = if outcome.is_ok() { ConnectionStatus::Connected } else { ConnectionStatus::Error };
+        match self.storage.set_connection_status(&connection.id, &status).await {
+            Ok(Some(previous)) => {
+                let data = json!({
+                    "connection_id": connection.id,
+                    "name": connection.name,
+                    "previous_status": previous,
+                    "status": status,
+                    "error": outcome.as_ref().err().map(|e| e.to_string()),
+                });
+                NotificationService::new(self.storage.clone())
+                    .notify(connection.domain_id.as_deref(), WebhookEvent::ConnectionStatusChanged, data)
+                    .await;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to record status of connection {}: {}", connection.id, e),
+        }
+
+        outcome
+    }
+
+    /// Re-read a connection's schema and save it as a new metadata version
+    ///
+    /// Only the columns of tables that are new or changed since the previous
+    /// version are read, unless `full`. Runs in its own task, so a client
+    /// disconnecting doesn't abandon it halfway. Fails with `Conflict` while
+    /// the connection is already being refreshed.
+    pub async fn refresh(
+        self: &Arc<Self>,
+        connection: &DatabaseConnection,
+        full: bool,
+    ) -> Result<MetadataRefresh, AppError> {
+        if !self.refreshing.lock().unwrap().insert(connection.id.clone()) {
+            return Err(AppError::Conflict(format!(
+                "Metadata of connection {} is already being refreshed",
+                connection.id
+            )));
+        }
+        self.attempted.lock().unwrap().insert(connection.id.clone(), Utc::now());
+
+        let service = self.clone();
+        let task_connection = connection.clone();
+        let outcome = tokio::spawn(async move { service.run_refresh(&task_connection, full).await })
+            .await
+            .map_err(|e| AppError::Internal(format!("Metadata refresh task failed: {}", e)))
+            .and_then(|outcome| outcome);
+        self.refreshing.lock().unwrap().remove(&connection.id);
+        outcome
+    }
+
+    async fn run_refresh(&self, connection: &DatabaseConnection, full: bool) -> Result<MetadataRefresh, AppError> {
+        // As read from the database, without the data dictionary merged in
+        let previous = self
+            .storage
+            .get_metadata_cache(&connection.id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut metadata = self
+            .read_schema(connection, previous.as_ref().filter(|_| !full))
+            .await?;
+        metadata.metadata_json = self.llm.convert_metadata_to_json(&metadata).await?;
+        let cache_service = MetadataCacheService::new(self.storage.clone(), self.config.clone());
+        cache_service.save_metadata(&mut metadata).await?;
+        tracing::info!(
+            connection_id = %connection.id,
+            version = metadata.version,
+            "Metadata refreshed. Found {} tables and {} views",
+            metadata.tables.len(),
+            metadata.views.len()
+        );
+
+        let diff = previous.map(|previous| MetadataDiff::between(&previous, &metadata));
+        if let Some(diff) = diff.as_ref().filter(|diff| !diff.is_empty()) {
+            // Cached query results may no longer match a changed schema
+            let removed = self.query_cache.invalidate_connection(&connection.id).await
+                + self.subquery_cache.invalidate_connection(&connection.id);
+            tracing::info!(
+                connection_id = %connection.id,
+                "Schema changed, dropped {} cached query results",
+                removed
+            );
+
+            let data = json!({
+                "connection_id": connection.id,
+                "name": connection.name,
+                "diff": diff,
+            });
+            NotificationService::new(self.storage.clone())
+                .notify(connection.domain_id.as_deref(), WebhookEvent::MetadataChanged, data)
+                .await;
+
+            // The refresh itself succeeded; a failure here only delays the flags
+            if let Err(e) = SchemaBreakageService::new(self.storage.clone())
+                .flag_broken_queries(connection, diff, &metadata)
+                .await
+            {
+                tracing::warn!(connection_id = %connection.id, "Failed to flag broken saved queries: {}", e);
+            }
+        }
+
+        let dictionary = self
+            .storage
+            .list_dictionary_entries(&connection.id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        metadata.annotate(&dictionary);
+        Ok(MetadataRefresh { metadata, diff })
+    }
+
+    /// Refresh every connection whose metadata is older than its domain's
+    /// refresh interval, returning the number refreshed
+    ///
+    /// Connections that never had their schema read are left to connect time.
+    pub async fn refresh_due(self: &Arc<Self>) -> Result<usize, AppError> {
+        let settings: HashMap<String, DomainSettings> = self
+            .storage
+            .list_domain_settings()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .into_iter()
+            .map(|settings| (settings.domain_id.clone(), settings))
+            .collect();
+        let connections = self
+            .storage
+            .list_connections()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let now = Utc::now();
+        let mut refreshed = 0;
+        for connection in connections {
+            let interval_secs = connection
+                .domain_id
+                .as_ref()
+                .and_then(|domain_id| settings.get(domain_id))
+                .and_then(|settings| settings.metadata_refresh_interval_secs)
+                .unwrap_or(self.config.refresh_interval_secs);
+            if interval_secs == 0 || self.refreshing.lock().unwrap().contains(&connection.id) {
+                continue;
+            }
+            let retrieved_at = match self.storage.list_metadata_versions(&connection.id).await {
+                Ok(versions) => match versions.first() {
+                    Some(latest) => latest.retrieved_at,
+                    None => continue,
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to read metadata versions of connection {}: {}", connection.id, e);
+                    continue;
+                }
+            };
+            let attempted_at = self.attempted.lock().unwrap().get(&connection.id).copied();
+            if !is_due(retrieved_at, attempted_at, interval_secs, now) {
+                continue;
+            }
+
+            match self.refresh(&connection, false).await {
+                Ok(_) => refreshed += 1,
+                Err(e) => tracing::warn!(connection_id = %connection.id, "Scheduled metadata refresh failed: {}", e),
+            }
+        }
+        Ok(refreshed)
+    }
+
+    /// Spawn a background task that refreshes due metadata on the configured check interval
+    pub fn spawn_scheduler(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval_secs = self.config.refresh_check_interval_secs.max(10);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh_due().await {
+                    tracing::warn!("Scheduled metadata refresh failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Whether metadata read at `retrieved_at` (last attempted at `attempted_at`)
+/// is due for a refresh every `interval_secs`
+fn is_due(retrieved_at: DateTime<Utc>, attempted_at: Option<DateTime<Utc>>, interval_secs: u64, now: DateTime<Utc>) -> bool {
+    if interval_secs == 0 {
+        return false;
+    }
+    let since = attempted_at.map_or(retrieved_at, |attempted_at| attempted_at.max(retrieved_at));
+    now - since >= chrono::Duration::seconds(interval_secs as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let hours_ago = |hours| now - chrono::Duration::hours(hours);
+
+        assert!(is_due(hours_ago(2), None, 3600, now));
+        assert!(!is_due(hours_ago(2), None, 0, now));
+        assert!(!is_due(now - chrono::Duration::minutes(30), None, 3600, now));
+        // A failed attempt waits for the next interval
+        assert!(!is_due(hours_ago(5), Some(now - chrono::Duration::minutes(10)), 3600, now));
+        assert!(is_due(hours_ago(5), Some(hours_ago(1)), 3600, now));
+    }
+}
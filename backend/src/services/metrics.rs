@@ -0,0 +1,421 @@
+// Semantic Metrics Service
+//
+// Stores a domain's metrics definition (entities with dimensions and
+// measures, plus default joins) and compiles metric requests such as
+// "revenue by region per day" into SQL. The compiler emits DataFusion SQL,
+// which the dialect translator rewrites for the target connection.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::api::middleware::AppError;
+use crate::models::{
+    CompileMetricRequest, CompiledMetric, DomainMetrics, MeasureAggregation, MetricEntity, MetricJoin, MetricsDefinition,
+};
+use crate::services::datafusion::{DatabaseType, DialectSpec, DialectTranslationService};
+use crate::storage::SqliteStorage;
+
+pub struct MetricsService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl MetricsService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Metrics definition of a domain
+    pub async fn get(&self, domain_id: &str) -> Result<DomainMetrics, AppError> {
+        self.storage
+            .get_domain_metrics(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Domain {} has no metrics definition", domain_id)))
+    }
+
+    /// Validate and store (replace) a domain's metrics definition
+    pub async fn save(&self, domain_id: &str, definition: MetricsDefinition) -> Result<DomainMetrics, AppError> {
+        self.storage
+            .get_domain(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+        definition.validate().map_err(AppError::Validation)?;
+
+        let metrics = DomainMetrics {
+            domain_id: domain_id.to_string(),
+            definition,
+            updated_at: chrono::Utc::now(),
+        };
+        self.storage
+            .save_domain_metrics(&metrics)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!("Updated metrics definition of domain {}", domain_id);
+        Ok(metrics)
+    }
+
+    /// Compile a metric request to SQL in the dialect of one of the domain's connections
+    pub async fn compile(&self, domain_id: &str, request: &CompileMetricRequest) -> Result<CompiledMetric, AppError> {
+        let metrics = self.get(domain_id).await?;
+        let connection = self
+            .storage
+            .get_connection(&request.connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|c| c.domain_id.as_deref() == Some(domain_id))
+            .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", request.connection_id)))?;
+
+        let db_type = DatabaseType::from_str(&connection.database_type).map_err(|e| AppError::Validation(e.to_string()))?;
+        let translator = DialectTranslationService::new();
+        let (sql, columns) = MetricCompiler::new(&metrics.definition).compile(request, translator.dialect(db_type))?;
+
+        let mut sql = translator
+            .translate_query(&sql, db_type)
+            .await
+            .map_err(|e| AppError::InvalidSql(format!("{:#}", e)))?;
+        if let Some(limit) = request.limit {
+            sql = translator
+                .apply_limit(&sql, db_type, limit)
+                .map_err(|e| AppError::InvalidSql(format!("{:#}", e)))?
+                .0;
+        }
+
+        Ok(CompiledMetric {
+            connection_id: connection.id,
+            dialect: db_type.as_str().to_string(),
+            sql,
+            columns,
+        })
+    }
+}
+
+/// Compiles metric requests against a metrics definition
+pub struct MetricCompiler<'a> {
+    definition: &'a MetricsDefinition,
+}
+
+impl<'a> MetricCompiler<'a> {
+    pub fn new(definition: &'a MetricsDefinition) -> Self {
+        Self { definition }
+    }
+
+    /// DataFusion SQL of a metric request and its result columns
+    ///
+    /// Time grains are truncated the way `spec` does (DATE_TRUNC when None).
+    /// Dimensions of other entities are reached over the default joins.
+    pub fn compile(
+        &self,
+        request: &CompileMetricRequest,
+        spec: Option<&DialectSpec>,
+    ) -> Result<(String, Vec<String>), AppError> {
+        let (entity, measure) = self.resolve(&request.metric, |e, name| e.measures.iter().any(|m| m.name == name))?;
+        let measure = entity.measures.iter().find(|m| m.name == measure).expect("resolved measure");
+
+        // (alias, expression) of every grouped column
+        let mut groups = Vec::new();
+        if let Some(grain) = request.grain {
+            let time_dimension = entity.time_dimension.as_deref().ok_or_else(|| {
+                AppError::Validation(format!("Entity '{}' has no time dimension to apply a grain to", entity.name))
+            })?;
+            let expr = qualify(&entity.name, time_dimension);
+            let truncated = match spec {
+                Some(spec) => spec.date_trunc(grain.as_str(), &expr),
+                None => format!("DATE_TRUNC('{}', {})", grain.as_str(), expr),
+            };
+            groups.push((grain.as_str().to_string(), truncated));
+        }
+
+        let mut joined = vec![entity.name.as_str()];
+        let mut joins = Vec::new();
+        for name in &request.group_by {
+            let (owner, dimension) = self.resolve(name, |e, name| e.dimensions.iter().any(|d| d.name == name))?;
+            let dimension = owner.dimensions.iter().find(|d| d.name == dimension).expect("resolved dimension");
+
+            for (join, reversed) in self.join_path(&entity.name, &owner.name)? {
+                let (from, to) = if reversed { (&join.to, &join.from) } else { (&join.from, &join.to) };
+                if joined.contains(&to.as_str()) {
+                    continue;
+                }
+                let on = join
+                    .on
+                    .iter()
+                    .map(|c| {
+                        let (from_column, to_column) = if reversed { (&c.to, &c.from) } else { (&c.from, &c.to) };
+                        format!("{} = {}", qualify(from, from_column), qualify(to, to_column))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+                let table = &self.entity(to)?.table;
+                joins.push(format!("LEFT JOIN {} AS {} ON {}", quote_table(table), quote(to), on));
+                joined.push(to.as_str());
+            }
+
+            let expr = qualify(&owner.name, dimension.expr.as_deref().unwrap_or(&dimension.name));
+            groups.push((dimension.name.clone(), expr));
+        }
+
+        let value = match (measure.agg, measure.expr.as_deref()) {
+            (MeasureAggregation::Count, None) => "COUNT(*)".to_string(),
+            (agg, expr) => {
+                let expr = qualify(&entity.name, expr.unwrap_or(&measure.name));
+                match agg {
+                    MeasureAggregation::Sum => format!("SUM({})", expr),
+                    MeasureAggregation::Count => format!("COUNT({})", expr),
+                    MeasureAggregation::CountDistinct => format!("COUNT(DISTINCT {})", expr),
+                    MeasureAggregation::Avg => format!("AVG({})", expr),
+                    MeasureAggregation::Min => format!("MIN({})", expr),
+                    MeasureAggregation::Max => format!("MAX({})", expr),
+                }
+            }
+        };
+
+        let mut columns: Vec<String> = groups.iter().map(|(alias, _)| alias.clone()).collect();
+        columns.push(measure.name.clone());
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = columns.iter().find(|c| !seen.insert(c.as_str())) {
+            return Err(AppError::Validation(format!("Column '{}' is requested more than once", duplicate)));
+        }
+
+        let select = groups
+            .iter()
+            .map(|(alias, expr)| format!("{} AS {}", expr, quote(alias)))
+            .chain(std::iter::once(format!("{} AS {}", value, quote(&measure.name))))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut sql = format!("SELECT {}\nFROM {} AS {}", select, quote_table(&entity.table), quote(&entity.name));
+        for join in joins {
+            sql.push('\n');
+            sql.push_str(&join);
+        }
+        if !groups.is_empty() {
+            let group_by = groups.iter().map(|(_, expr)| expr.as_str()).collect::<Vec<_>>().join(", ");
+            let order_by = groups.iter().map(|(alias, _)| quote(alias)).collect::<Vec<_>>().join(", ");
+            sql.push_str(&format!("\nGROUP BY {}\nORDER BY {}", group_by, order_by));
+        }
+
+        Ok((sql, columns))
+    }
+
+    fn entity(&self, name: &str) -> Result<&'a MetricEntity, AppError> {
+        self.definition
+            .entity(name)
+            .ok_or_else(|| AppError::Validation(format!("Unknown entity '{}'", name)))
+    }
+
+    /// Entity and field name of `entity.field`, or of an unqualified field
+    /// defined by exactly one entity
+    fn resolve(
+        &self,
+        reference: &str,
+        defines: impl Fn(&MetricEntity, &str) -> bool,
+    ) -> Result<(&'a MetricEntity, String), AppError> {
+        if let Some((entity, field)) = reference.split_once('.') {
+            let entity = self.entity(entity)?;
+            return if defines(entity, field) {
+                Ok((entity, field.to_string()))
+            } else {
+                Err(AppError::Validation(format!("Entity '{}' has no '{}'", entity.name, field)))
+            };
+        }
+
+        let owners: Vec<&MetricEntity> = self.definition.entities.iter().filter(|e| defines(e, reference)).collect();
+        match owners.as_slice() {
+            [entity] => Ok((*entity, reference.to_string())),
+            [] => Err(AppError::Validation(format!("Unknown metric or dimension '{}'", reference))),
+            _ => Err(AppError::Validation(format!(
+                "'{}' is defined by several entities ({}); qualify it as entity.{}",
+                reference,
+                owners.iter().map(|e| e.name.as_str()).collect::<Vec<_>>().join(", "),
+                reference
+            ))),
+        }
+    }
+
+    /// Shortest chain of default joins from one entity to another, with
+    /// whether each join is followed from `to` to `from`
+    fn join_path(&self, from: &str, to: &str) -> Result<Vec<(&'a MetricJoin, bool)>, AppError> {
+        let mut previous: HashMap<&str, (&'a MetricJoin, bool)> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = Vec::new();
+                let mut entity = to;
+                while entity != from {
+                    let (join, reversed) = previous[entity];
+                    path.push((join, reversed));
+                    entity = if reversed { join.to.as_str() } else { join.from.as_str() };
+                }
+                path.reverse();
+                return Ok(path);
+            }
+            for join in &self.definition.joins {
+                let next = if join.from == current {
+                    Some((join.to.as_str(), false))
+                } else if join.to == current {
+                    Some((join.from.as_str(), true))
+                } else {
+                    None
+                };
+                if let Some((next, reversed)) = next {
+                    if visited.insert(next) {
+                        previous.insert(next, (join, reversed));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        Err(AppError::Validation(format!("No join path from entity '{}' to '{}'", from, to)))
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Quote each part of a (schema-qualified) table name
+fn quote_table(table: &str) -> String {
+    table.split('.').map(|part| quote(part.trim())).collect::<Vec<_>>().join(".")
+}
+
+/// Qualify a bare column with its entity; other expressions are used as written
+fn qualify(entity: &str, expr: &str) -> String {
+    let is_column = expr.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && expr.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_column {
+        format!("{}.{}", quote(entity), quote(expr))
+    } else {
+        expr.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TimeGrain;
+
+    fn definition() -> MetricsDefinition {
+        serde_yaml::from_str(
+            r#"
+entities:
+  - name: orders
+    table: public.orders
+    time_dimension: created_at
+    measures:
+      - name: revenue
+        agg: sum
+        expr: amount
+      - name: order_count
+        agg: count
+  - name: customers
+    table: public.customers
+    dimensions:
+      - name: region
+      - name: country
+        expr: UPPER("customers"."country_code")
+  - name: regions
+    table: regions
+    dimensions:
+      - name: region
+        expr: name
+joins:
+  - from: orders
+    to: customers
+    on:
+      - from: customer_id
+        to: id
+  - from: regions
+    to: customers
+    on:
+      - from: id
+        to: region_id
+"#,
+        )
+        .unwrap()
+    }
+
+    fn request(metric: &str, group_by: &[&str], grain: Option<TimeGrain>) -> CompileMetricRequest {
+        CompileMetricRequest {
+            metric: metric.to_string(),
+            group_by: group_by.iter().map(|g| g.to_string()).collect(),
+            grain,
+            connection_id: "conn-1".to_string(),
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn test_compile_metric_with_grain_and_joined_dimension() {
+        let definition = definition();
+        let (sql, columns) = MetricCompiler::new(&definition)
+            .compile(&request("revenue", &["customers.region"], Some(TimeGrain::Day)), None)
+            .unwrap();
+
+        assert_eq!(columns, vec!["day", "region", "revenue"]);
+        assert_eq!(
+            sql,
+            "SELECT DATE_TRUNC('day', \"orders\".\"created_at\") AS \"day\", \"customers\".\"region\" AS \"region\", \
+             SUM(\"orders\".\"amount\") AS \"revenue\"\n\
+             FROM \"public\".\"orders\" AS \"orders\"\n\
+             LEFT JOIN \"public\".\"customers\" AS \"customers\" ON \"orders\".\"customer_id\" = \"customers\".\"id\"\n\
+             GROUP BY DATE_TRUNC('day', \"orders\".\"created_at\"), \"customers\".\"region\"\n\
+             ORDER BY \"day\", \"region\""
+        );
+    }
+
+    #[test]
+    fn test_compile_follows_joins_in_both_directions() {
+        let definition = definition();
+        let (sql, _) = MetricCompiler::new(&definition)
+            .compile(&request("order_count", &["regions.region", "country"], None), None)
+            .unwrap();
+
+        assert!(sql.starts_with("SELECT \"regions\".\"name\" AS \"region\", UPPER(\"customers\".\"country_code\") AS \"country\", COUNT(*)"));
+        assert!(sql.contains("LEFT JOIN \"public\".\"customers\" AS \"customers\" ON \"orders\".\"customer_id\" = \"customers\".\"id\"\n"));
+        assert!(sql.contains("LEFT JOIN \"regions\" AS \"regions\" ON \"customers\".\"region_id\" = \"regions\".\"id\""));
+        assert_eq!(sql.matches("LEFT JOIN").count(), 2);
+    }
+
+    #[test]
+    fn test_compile_rejects_unresolvable_requests() {
+        let definition = definition();
+        let compiler = MetricCompiler::new(&definition);
+
+        // "region" is defined by two entities
+        assert!(compiler.compile(&request("revenue", &["region"], None), None).is_err());
+        assert!(compiler.compile(&request("profit", &[], None), None).is_err());
+        // Qualified references must name a field of that entity
+        assert!(compiler.compile(&request("customers.revenue", &[], None), None).is_err());
+
+        let mut unjoined = definition.clone();
+        unjoined.joins.clear();
+        assert!(MetricCompiler::new(&unjoined)
+            .compile(&request("revenue", &["customers.region"], None), None)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compiled_metric_translates_to_mysql() {
+        let definition = definition();
+        let translator = DialectTranslationService::new();
+        let (sql, _) = MetricCompiler::new(&definition)
+            .compile(
+                &request("revenue", &[], Some(TimeGrain::Month)),
+                translator.dialect(DatabaseType::MySQL),
+            )
+            .unwrap();
+        let translated = translator.translate_query(&sql, DatabaseType::MySQL).await.unwrap();
+
+        assert_eq!(
+            translated,
+            "SELECT DATE(DATE_FORMAT(`orders`.`created_at`, '%Y-%m-01')) AS `month`, SUM(`orders`.`amount`) AS `revenue`\n\
+             FROM `public`.`orders` AS `orders`\n\
+             GROUP BY DATE(DATE_FORMAT(`orders`.`created_at`, '%Y-%m-01'))\n\
+             ORDER BY `month`"
+        );
+    }
+}
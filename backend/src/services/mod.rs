@@ -1,16 +1,93 @@
 pub mod connection_pool;
+pub mod circuit_breaker; // Per-pool circuit breakers that fail fast while a database is down
 pub mod db_service;
 pub mod llm_service;
+pub mod embeddings; // Embedding index over table metadata for NL-to-SQL schema retrieval
 pub mod metadata_cache;
 pub mod query_service;
 pub mod query_cache; // Query result cache with LRU and TTL
+pub mod subquery_cache; // Arrow IPC cache of cross-database sub-query results
+pub mod statement_cache; // Prepared statement cache limits and hit rates per pool
+pub mod history_archive; // Query history retention and Parquet archival
+pub mod trash; // Soft-delete trash with scheduled purge
+pub mod replay; // Execution snapshots and historic query replay
+pub mod result_contract; // Declared result schemas of saved queries and drift checks
+pub mod domain_settings; // Per-domain query defaults and restrictions
+pub mod domain_bundle; // Domain export/import as portable bundles
+pub mod api_keys; // API key issuing and bearer token authentication
+pub mod auth; // User accounts, login tokens and per-domain roles
+pub mod connection_policy; // Label-bound masking and quota policies
+pub mod query_jobs; // Background query jobs with polling and cancellation
+pub mod transactions; // Write-mode transaction sessions pinned to one pooled connection
+pub mod running_queries; // Cancellation of synchronous queries by request id
+pub mod notifications; // Domain webhooks with a retrying delivery queue
+pub mod sql_completion; // Schema-aware SQL autocompletion from cached metadata
+pub mod table_preview; // Generated row samples and counts of single tables
+pub mod table_profile; // Column statistics of single tables from generated profiling SQL
+pub mod result_transform; // Post-processing pipeline for query results
+pub mod api_usage; // Per-domain API usage rollups and reports
+pub mod result_export; // CSV, NDJSON, Parquet and Excel result exports
+pub mod data_transfer; // Cross-database INSERT ... SELECT through adapter bulk loads
+pub mod view_materialization; // Parquet snapshots of saved query views with scheduled refresh
+pub mod metrics; // Semantic metrics definitions compiled to dialect SQL
+pub mod federated_catalog; // Connection aliases and `alias.schema.table` catalogs from cached metadata
+pub mod query_advisor; // Optimization suggestions from EXPLAIN plans, schema metadata and the LLM
+pub mod result_insights; // Result summaries, insights and follow-up questions
+pub mod chart_recommendation; // Chart suggestions from the column shape of query results
+pub mod sql_feedback; // Ratings and corrections of generated SQL as few-shot examples
+pub mod llm_usage; // LLM token metering and monthly domain budgets
+pub mod generation_cache; // Generated SQL reused for repeated natural language questions
+pub mod prompt_redaction; // Personal data kept out of LLM prompts
+pub mod metadata_refresh; // On-demand and scheduled schema re-reads with change events
+pub mod schema_breakage; // Saved queries flagged broken by dropped or renamed tables and columns
+pub mod query_shares; // Share links serving saved query results without authentication
+pub mod slow_queries; // Slow query flagging and reports grouped by SQL fingerprint
+pub mod history_results; // Query results kept with history entries as rows or Parquet
 pub mod database; // Multi-database support with DataFusion
 pub mod datafusion; // DataFusion semantic layer
 
 pub use connection_pool::*;
+pub use circuit_breaker::*;
 pub use db_service::*;
 pub use llm_service::*;
+pub use embeddings::*;
 pub use metadata_cache::*;
 pub use query_service::*;
 pub use query_cache::*;
-
+pub use subquery_cache::*;
+pub use statement_cache::*;
+pub use history_archive::*;
+pub use trash::*;
+pub use replay::*;
+pub use result_contract::*;
+pub use domain_settings::*;
+pub use domain_bundle::*;
+pub use api_keys::*;
+pub use auth::*;
+pub use connection_policy::*;
+pub use query_jobs::*;
+pub use transactions::*;
+pub use running_queries::*;
+pub use notifications::*;
+pub use sql_completion::*;
+pub use table_preview::*;
+pub use table_profile::*;
+pub use result_transform::*;
+pub use api_usage::*;
+pub use result_export::*;
+pub use data_transfer::*;
+pub use view_materialization::*;
+pub use metrics::*;
+pub use federated_catalog::*;
+pub use query_advisor::*;
+pub use result_insights::*;
+pub use chart_recommendation::*;
+pub use sql_feedback::*;
+pub use llm_usage::*;
+pub use generation_cache::*;
+pub use prompt_redaction::*;
+pub use metadata_refresh::*;
+pub use schema_breakage::*;
+pub use query_shares::*;
+pub use slow_queries::*;
+pub use history_results::*;
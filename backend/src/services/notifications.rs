@@ -0,0 +1,322 @@
+// Webhook Notifications
+//
+// Domains register webhooks (URL, secret, event filter). Events are queued in
+// the webhook_deliveries table and POSTed by a background worker, so a slow
+// or unreachable receiver never delays the request that raised the event.
+// Failed attempts are retried with exponential backoff (30s, 1m, 2m, ...) up
+// to `WEBHOOK_MAX_ATTEMPTS`. Every request is signed: `X-DbQuery-Signature`
+// is `sha256=` plus the hex HMAC-SHA256 of `{timestamp}.{body}`, keyed with
+// the webhook secret, where the timestamp is sent in `X-DbQuery-Timestamp`.
+// Webhook hosts must resolve to public addresses: they are checked when the
+// webhook is created and again before every delivery, which connects to the
+// checked addresses and does not follow redirects.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::api::middleware::AppError;
+use crate::config::WebhooksConfig;
+use crate::models::{
+    is_deliverable_address, CreateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryStatus, WebhookEvent,
+};
+use crate::storage::SqliteStorage;
+
+/// Deliveries attempted per worker run
+const DELIVERY_BATCH_SIZE: usize = 50;
+
+/// Interval between worker runs
+const DELIVERY_INTERVAL_SECS: u64 = 5;
+
+/// Deliveries listed per webhook
+const RECENT_DELIVERIES: usize = 50;
+
+pub struct NotificationService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl NotificationService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    pub async fn create_webhook(&self, domain_id: &str, request: CreateWebhookRequest) -> Result<Webhook, AppError> {
+        request.validate().map_err(AppError::Validation)?;
+        Self::resolve_host(&request.url).await.map_err(AppError::Validation)?;
+        self.storage
+            .get_domain(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Domain {} not found", domain_id)))?;
+        let webhook = Webhook::new(domain_id.to_string(), request.url, request.secret, request.events);
+        self.storage
+            .create_webhook(&webhook)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        tracing::info!(webhook_id = %webhook.id, domain_id = %domain_id, "Created webhook");
+        Ok(webhook)
+    }
+
+    pub async fn list_webhooks(&self, domain_id: &str) -> Result<Vec<Webhook>, AppError> {
+        self.storage
+            .list_webhooks(domain_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    pub async fn delete_webhook(&self, domain_id: &str, id: &str) -> Result<(), AppError> {
+        let deleted = self
+            .storage
+            .delete_webhook(domain_id, id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if !deleted {
+            return Err(AppError::NotFound(format!("Webhook {} not found in domain {}", id, domain_id)));
+        }
+        Ok(())
+    }
+
+    /// Most recent deliveries of a domain's webhook
+    pub async fn list_deliveries(&self, domain_id: &str, webhook_id: &str) -> Result<Vec<WebhookDelivery>, AppError> {
+        self.storage
+            .get_webhook(webhook_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|webhook| webhook.domain_id == domain_id)
+            .ok_or_else(|| AppError::NotFound(format!("Webhook {} not found in domain {}", webhook_id, domain_id)))?;
+        self.storage
+            .list_webhook_deliveries(webhook_id, RECENT_DELIVERIES)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Queue an event for the domain's webhooks that subscribe to it
+    ///
+    /// Resources outside a domain have no webhooks. Failures are logged and
+    /// never affect the caller.
+    pub async fn notify(&self, domain_id: Option<&str>, event: WebhookEvent, data: Value) {
+        let Some(domain_id) = domain_id else {
+            return;
+        };
+        let webhooks = match self.storage.list_webhooks(domain_id).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::warn!("Failed to load webhooks of domain {}: {}", domain_id, e);
+                return;
+            }
+        };
+
+        let payload = json!({
+            "event": event.as_str(),
+            "domain_id": domain_id,
+            "occurred_at": Utc::now().to_rfc3339(),
+            "data": data,
+        });
+        for webhook in webhooks.iter().filter(|webhook| webhook.subscribes_to(event)) {
+            let delivery = WebhookDelivery::new(webhook.id.clone(), event, payload.clone());
+            if let Err(e) = self.storage.create_webhook_delivery(&delivery).await {
+                tracing::warn!(webhook_id = %webhook.id, "Failed to queue webhook delivery: {}", e);
+            }
+        }
+    }
+
+    /// Attempt the deliveries that are due; returns how many were attempted
+    pub async fn deliver_due(&self, config: &WebhooksConfig) -> Result<usize, AppError> {
+        let deliveries = self
+            .storage
+            .due_webhook_deliveries(Utc::now(), DELIVERY_BATCH_SIZE)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let mut webhooks: HashMap<String, Option<Webhook>> = HashMap::new();
+        for mut delivery in deliveries.iter().cloned() {
+            if !webhooks.contains_key(&delivery.webhook_id) {
+                let webhook = self
+                    .storage
+                    .get_webhook(&delivery.webhook_id)
+                    .await
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                webhooks.insert(delivery.webhook_id.clone(), webhook);
+            }
+
+            let outcome = match &webhooks[&delivery.webhook_id] {
+                Some(webhook) => Self::attempt(config, webhook, &delivery).await,
+                None => Err("Webhook was deleted".to_string()),
+            };
+            delivery.attempts += 1;
+            match outcome {
+                Ok(()) => {
+                    delivery.status = WebhookDeliveryStatus::Delivered;
+                    delivery.delivered_at = Some(Utc::now());
+                    delivery.last_error = None;
+                }
+                Err(error) => {
+                    tracing::warn!(delivery_id = %delivery.id, attempts = delivery.attempts, "Webhook delivery failed: {}", error);
+                    if delivery.attempts >= config.max_attempts.max(1) {
+                        delivery.status = WebhookDeliveryStatus::Failed;
+                    } else {
+                        delivery.next_attempt_at = Utc::now() + Self::retry_delay(delivery.attempts);
+                    }
+                    delivery.last_error = Some(error);
+                }
+            }
+            self.storage
+                .update_webhook_delivery(&delivery)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        Ok(deliveries.len())
+    }
+
+    /// POST a delivery's payload; any 2xx response counts as delivered
+    ///
+    /// The host is resolved again and the request sent to the checked
+    /// addresses, so a host that starts resolving to an internal address is
+    /// not reached. Only the status of a failed response is recorded.
+    async fn attempt(config: &WebhooksConfig, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<(), String> {
+        let (host, addrs) = Self::resolve_host(&webhook.url).await?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(&host, &addrs)
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let response = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-DbQuery-Event", delivery.event.as_str())
+            .header("X-DbQuery-Delivery", &delivery.id)
+            .header("X-DbQuery-Timestamp", timestamp.to_string())
+            .header("X-DbQuery-Signature", Self::sign(&webhook.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e.without_url()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        Err(format!("HTTP {}", status))
+    }
+
+    /// Host of a webhook URL and the addresses it resolves to
+    ///
+    /// Fails when any of the addresses is loopback, private, link-local or
+    /// unspecified.
+    async fn resolve_host(url: &str) -> Result<(String, Vec<SocketAddr>), String> {
+        let url = url::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+        let host = url.host_str().ok_or("Webhook URL must have a host")?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|e| format!("Failed to resolve webhook host {}: {}", host, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(format!("Webhook host {} did not resolve to any address", host));
+        }
+        if let Some(addr) = addrs.iter().find(|addr| !is_deliverable_address(addr.ip())) {
+            return Err(format!("Webhook host {} resolves to the internal address {}", host, addr.ip()));
+        }
+        Ok((host, addrs))
+    }
+
+    /// `X-DbQuery-Signature` of a body sent at `timestamp`
+    pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body.as_bytes());
+        let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("sha256={}", hex)
+    }
+
+    /// Wait before retry number `attempts`: 30s, doubling up to an hour
+    fn retry_delay(attempts: u32) -> chrono::Duration {
+        let secs = 30u64.saturating_mul(1 << attempts.saturating_sub(1).min(7));
+        chrono::Duration::seconds(secs.min(3600) as i64)
+    }
+
+    /// Deliver queued events in the background
+    pub fn spawn_worker(self: Arc<Self>, config: WebhooksConfig) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(DELIVERY_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.deliver_due(&config).await {
+                    tracing::warn!("Webhook delivery run failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_signature_and_backoff() {
+        // printf '1700000000.{}' | openssl dgst -sha256 -hmac secret
+        let signature = NotificationService::sign("secret", 1_700_000_000, "{}");
+        assert_eq!(signature, "sha256=b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163");
+        assert_ne!(signature, NotificationService::sign("other", 1_700_000_000, "{}"));
+
+        assert_eq!(NotificationService::retry_delay(1).num_seconds(), 30);
+        assert_eq!(NotificationService::retry_delay(3).num_seconds(), 120);
+        assert_eq!(NotificationService::retry_delay(20).num_seconds(), 3600);
+    }
+
+    #[tokio::test]
+    async fn test_notify_queues_and_failed_deliveries_retry() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let domain = crate::models::Domain::new("Hooks".to_string(), None).unwrap();
+        storage.create_domain(&domain).await.unwrap();
+
+        let service = NotificationService::new(storage.clone());
+        // Internal hosts are refused, whether given as an address or a name
+        for url in ["http://127.0.0.1:9/hook", "http://localhost:9/hook"] {
+            let request = CreateWebhookRequest {
+                url: url.to_string(),
+                secret: "s".repeat(16),
+                events: Vec::new(),
+            };
+            assert!(matches!(service.create_webhook(&domain.id, request).await, Err(AppError::Validation(_))), "{}", url);
+        }
+
+        // A webhook whose host resolves to an internal address is never reached
+        let webhook = Webhook::new(
+            domain.id.clone(),
+            "http://localhost:9/hook".to_string(),
+            "s".repeat(16),
+            vec![WebhookEvent::QueryJobFailed],
+        );
+        storage.create_webhook(&webhook).await.unwrap();
+
+        service.notify(Some(&domain.id), WebhookEvent::QueryJobCompleted, json!({})).await;
+        service.notify(Some(&domain.id), WebhookEvent::QueryJobFailed, json!({"job_id": "j1"})).await;
+        let deliveries = service.list_deliveries(&domain.id, &webhook.id).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].payload["data"]["job_id"], "j1");
+
+        let config = WebhooksConfig { max_attempts: 2, timeout_secs: 1 };
+        assert_eq!(service.deliver_due(&config).await.unwrap(), 1);
+        let delivery = &service.list_deliveries(&domain.id, &webhook.id).await.unwrap()[0];
+        assert_eq!((delivery.status, delivery.attempts), (WebhookDeliveryStatus::Pending, 1));
+        assert!(delivery.last_error.as_deref().unwrap().contains("internal address"));
+        // The retry is not due yet
+        assert_eq!(service.deliver_due(&config).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,231 @@
+// Prompt Redaction
+//
+// Keeps personal data out of prompts sent to the LLM gateway. Columns whose
+// names look personal (the fragments of `LLM_REDACTED_COLUMN_PATTERNS`) keep
+// their names in the schema, which SQL generation needs, but their values are
+// masked in sample data. Tables and columns on the domain's deny-list are left
+// out of the schema altogether, and their values masked as well. What was held
+// back goes into the LLM audit log with the prompt.
+
+use serde_json::Value;
+
+use crate::config::LlmConfig;
+use crate::models::{DatabaseMetadata, DomainSettings, PromptRedactions, MASKED_VALUE};
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptRedactor {
+    /// Column name fragments, lowercase alphanumerics only
+    patterns: Vec<String>,
+    /// `table`, `column`, `schema.table` or `table.column`, lowercase
+    denied: Vec<String>,
+}
+
+impl PromptRedactor {
+    pub fn new(config: &LlmConfig) -> Self {
+        Self {
+            patterns: config
+                .redacted_column_patterns
+                .split(',')
+                .map(normalize)
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+            denied: Vec::new(),
+        }
+    }
+
+    /// Also withhold the tables and columns of a domain's deny-list
+    pub fn with_domain(mut self, settings: &DomainSettings) -> Self {
+        self.denied = settings.llm_denied_identifiers.clone();
+        self
+    }
+
+    /// Tables and columns withheld from prompts
+    pub fn denied_identifiers(&self) -> &[String] {
+        &self.denied
+    }
+
+    fn denies(&self, identifier: &str) -> bool {
+        self.denied.iter().any(|denied| denied.eq_ignore_ascii_case(identifier))
+    }
+
+    fn denies_table(&self, schema: Option<&str>, table: &str) -> bool {
+        self.denies(table) || schema.is_some_and(|schema| self.denies(&format!("{}.{}", schema, table)))
+    }
+
+    fn denies_column(&self, table: &str, column: &str) -> bool {
+        self.denies(column) || self.denies(&format!("{}.{}", table, column))
+    }
+
+    /// Whether values of a result column are masked before they reach the LLM
+    ///
+    /// Result columns carry no table, so a denied `table.column` masks every
+    /// column of that name.
+    pub fn masks_column(&self, column: &str) -> bool {
+        let normalized = normalize(column);
+        self.patterns.iter().any(|pattern| normalized.contains(pattern.as_str()))
+            || self.denied.iter().any(|denied| {
+                let name = denied.rsplit('.').next().unwrap_or(denied);
+                name.eq_ignore_ascii_case(column)
+            })
+    }
+
+    /// Schema without the denied tables, views and columns, with what was left out
+    pub fn redact_metadata(&self, metadata: &DatabaseMetadata) -> (DatabaseMetadata, PromptRedactions) {
+        let mut redactions = PromptRedactions::default();
+        let mut redacted = metadata.clone();
+        if self.denied.is_empty() {
+            return (redacted, redactions);
+        }
+
+        redacted.tables.retain_mut(|table| {
+            if self.denies_table(table.schema.as_deref(), &table.name) {
+                redactions.withheld.push(qualified(table.schema.as_deref(), &table.name));
+                return false;
+            }
+            table.columns.retain(|column| {
+                let denied = self.denies_column(&table.name, &column.name);
+                if denied {
+                    redactions.withheld.push(format!("{}.{}", table.name, column.name));
+                }
+                !denied
+            });
+            true
+        });
+        redacted.views.retain_mut(|view| {
+            if self.denies_table(view.schema.as_deref(), &view.name) {
+                redactions.withheld.push(qualified(view.schema.as_deref(), &view.name));
+                return false;
+            }
+            view.columns.retain(|column| {
+                let denied = self.denies_column(&view.name, &column.name);
+                if denied {
+                    redactions.withheld.push(format!("{}.{}", view.name, column.name));
+                }
+                !denied
+            });
+            true
+        });
+        // The serialized schema still describes everything
+        redacted.metadata_json.clear();
+        (redacted, redactions)
+    }
+
+    /// Result rows (JSON objects) with the values of masked columns replaced
+    pub fn mask_rows(&self, rows: &[Value]) -> (Vec<Value>, PromptRedactions) {
+        let mut redactions = PromptRedactions::default();
+        let mut masked = rows.to_vec();
+        for row in &mut masked {
+            let Some(object) = row.as_object_mut() else { continue };
+            for (column, value) in object.iter_mut() {
+                if !value.is_null() && self.masks_column(column) {
+                    *value = Value::String(MASKED_VALUE.to_string());
+                    if !redactions.masked_columns.contains(column) {
+                        redactions.masked_columns.push(column.clone());
+                    }
+                }
+            }
+        }
+        (masked, redactions)
+    }
+}
+
+/// Lowercase alphanumerics of a name, so `Email_Address` contains `email`
+/// and `creditCard` contains `credit_card`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn qualified(schema: Option<&str>, name: &str) -> String {
+    match schema {
+        Some(schema) => format!("{}.{}", schema, name),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Column, Table};
+    use serde_json::json;
+
+    fn redactor(denied: &[&str]) -> PromptRedactor {
+        let mut settings = DomainSettings::defaults("d".to_string());
+        settings.llm_denied_identifiers = denied.iter().map(|d| d.to_string()).collect();
+        PromptRedactor {
+            patterns: vec!["email".to_string(), "creditcard".to_string()],
+            denied: Vec::new(),
+        }
+        .with_domain(&settings)
+    }
+
+    fn table(name: &str, columns: &[&str]) -> Table {
+        Table {
+            name: name.to_string(),
+            schema: Some("public".to_string()),
+            columns: columns
+                .iter()
+                .map(|column| Column {
+                    name: column.to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: true,
+                    is_primary_key: false,
+                    is_foreign_key: false,
+                    default_value: None,
+                    max_length: None,
+                    description: None,
+                    distinct_count: None,
+                    tags: Vec::new(),
+                })
+                .collect(),
+            row_count: None,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_redact_metadata() {
+        let metadata = DatabaseMetadata::new(
+            "c".to_string(),
+            vec![
+                table("customers", &["id", "name", "email", "national_id"]),
+                table("salaries", &["customer_id", "amount"]),
+            ],
+            vec![],
+            vec!["public".to_string()],
+        );
+
+        let (redacted, redactions) = redactor(&["public.salaries", "customers.national_id"]).redact_metadata(&metadata);
+        assert_eq!(redacted.tables.len(), 1);
+        let columns: Vec<&str> = redacted.tables[0].columns.iter().map(|c| c.name.as_str()).collect();
+        // Personal-looking columns keep their names; only their values are masked
+        assert_eq!(columns, vec!["id", "name", "email"]);
+        assert_eq!(redactions.withheld, vec!["customers.national_id", "public.salaries"]);
+
+        let (unchanged, none) = redactor(&[]).redact_metadata(&metadata);
+        assert_eq!(unchanged.tables.len(), 2);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_mask_rows() {
+        let rows = vec![
+            json!({"name": "Ann", "Email_Address": "ann@example.com", "creditCard": "4111", "national_id": "X1"}),
+            json!({"name": "Bob", "Email_Address": null, "creditCard": "5500", "national_id": "X2"}),
+        ];
+
+        let (masked, redactions) = redactor(&["customers.national_id"]).mask_rows(&rows);
+        assert_eq!(
+            masked[0],
+            json!({"name": "Ann", "Email_Address": MASKED_VALUE, "creditCard": MASKED_VALUE, "national_id": MASKED_VALUE})
+        );
+        assert_eq!(masked[1]["Email_Address"], Value::Null);
+        assert_eq!(redactions.masked_columns.len(), 3);
+        assert!(!redactor(&[]).masks_column("name"));
+    }
+}
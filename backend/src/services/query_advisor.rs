@@ -0,0 +1,396 @@
+// Query Optimization Advisor
+//
+// Combines a query's native EXPLAIN plan with the cached schema of the tables
+// it reads. The LLM is asked for structured suggestions (missing indexes,
+// rewrites); rules over the plan add the suggestions that need no LLM, such as
+// an index for a filtered full scan of a large table. Nothing is executed.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+use sqlparser::ast::{SelectItem, SetExpr, Statement};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::api::middleware::AppError;
+use crate::config::Config;
+use crate::models::{
+    DatabaseMetadata, LlmAuditContext, LlmTokenUsage, OptimizationSuggestion, PlanNode, QueryPlan, SuggestionImpact,
+    SuggestionKind, SuggestionSource, Table,
+};
+use crate::services::{LlmService, PromptRedactor};
+use crate::storage::SqliteStorage;
+
+/// Rows from which a filtered full scan gets an index suggestion
+const FULL_SCAN_MIN_ROWS: f64 = 10_000.0;
+
+/// Rows from which a missing index has high impact
+const HIGH_IMPACT_ROWS: f64 = 1_000_000.0;
+
+/// Columns of a suggested index
+const INDEX_MAX_COLUMNS: usize = 3;
+
+pub struct QueryAdvisorService {
+    llm: LlmService,
+}
+
+impl QueryAdvisorService {
+    pub fn new(config: &Config) -> Self {
+        Self { llm: LlmService::new(config) }
+    }
+
+    /// Redact prompts with this redactor (see `LlmService::with_redactor`)
+    pub fn with_redactor(mut self, redactor: PromptRedactor) -> Self {
+        self.llm = self.llm.with_redactor(redactor);
+        self
+    }
+
+    /// Record prompts in the LLM audit log (see `LlmService::with_audit`)
+    pub fn with_audit(mut self, storage: Arc<SqliteStorage>, context: LlmAuditContext) -> Self {
+        self.llm = self.llm.with_audit(storage, context);
+        self
+    }
+
+    /// Tokens of the LLM calls made so far
+    pub fn token_usage(&self) -> LlmTokenUsage {
+        self.llm.token_usage()
+    }
+
+    /// Suggestions of the LLM for `sql` (None without a configured LLM gateway)
+    pub async fn llm_suggestions(
+        &self,
+        sql: &str,
+        plan: &QueryPlan,
+        metadata: &DatabaseMetadata,
+        database_type: &str,
+    ) -> Result<Option<Vec<OptimizationSuggestion>>, AppError> {
+        if !self.llm.is_configured() {
+            return Ok(None);
+        }
+        let metadata = referenced_metadata(sql, plan, metadata);
+        let answer = self.llm.advise_query(sql, &plan.raw, &metadata, database_type).await?;
+        parse_llm_suggestions(&answer).map(Some)
+    }
+
+    /// Suggestions from rules over the plan: indexes for filtered full scans of
+    /// large tables, and explicit columns instead of `SELECT *`
+    pub fn heuristic_suggestions(sql: &str, plan: &QueryPlan, metadata: &DatabaseMetadata) -> Vec<OptimizationSuggestion> {
+        let mut suggestions = Vec::new();
+        full_scan_suggestions(&plan.root, metadata, &mut suggestions);
+
+        if selects_wildcard(sql) {
+            suggestions.push(OptimizationSuggestion {
+                kind: SuggestionKind::Rewrite,
+                impact: SuggestionImpact::Low,
+                title: "Select only the columns you need".to_string(),
+                description: "SELECT * reads and transfers every column; listing the needed columns reduces I/O and can allow index-only scans.".to_string(),
+                table: None,
+                columns: Vec::new(),
+                sql: None,
+                source: SuggestionSource::Heuristic,
+            });
+        }
+        suggestions
+    }
+
+    /// The LLM's suggestions, followed by the heuristic ones of a kind and table it did not cover
+    pub fn merge(llm: Vec<OptimizationSuggestion>, heuristics: Vec<OptimizationSuggestion>) -> Vec<OptimizationSuggestion> {
+        let key = |s: &OptimizationSuggestion| (s.kind, s.table.as_deref().map(table_name).map(str::to_lowercase));
+        let covered: HashSet<_> = llm.iter().map(key).collect();
+        let mut suggestions = llm;
+        suggestions.extend(heuristics.into_iter().filter(|s| !covered.contains(&key(s))));
+        suggestions
+    }
+}
+
+/// Last segment of a possibly schema-qualified table name
+fn table_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Lowercase identifier-like words of `text`, outside single-quoted literals
+pub(crate) fn identifiers(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_literal = false;
+    for c in text.chars() {
+        if c == '\'' {
+            in_literal = !in_literal;
+        }
+        if !in_literal && (c.is_alphanumeric() || c == '_') {
+            word.push(c.to_ascii_lowercase());
+        } else if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Metadata narrowed to the tables `sql` or its plan refer to
+fn referenced_metadata(sql: &str, plan: &QueryPlan, metadata: &DatabaseMetadata) -> DatabaseMetadata {
+    let mut names: HashSet<String> = identifiers(sql).into_iter().collect();
+    fn relations(node: &PlanNode, names: &mut HashSet<String>) {
+        if let Some(relation) = &node.relation {
+            names.insert(table_name(relation).to_lowercase());
+        }
+        node.children.iter().for_each(|child| relations(child, names));
+    }
+    relations(&plan.root, &mut names);
+
+    let mut narrowed = metadata.clone();
+    narrowed.tables.retain(|table| names.contains(&table.name.to_lowercase()));
+    narrowed.views.retain(|view| names.contains(&view.name.to_lowercase()));
+    narrowed
+}
+
+/// Index suggestions for filtered full scans of large tables in the plan under `node`
+///
+/// Full scans are PostgreSQL `Seq Scan` nodes with a `Filter` and MySQL tables
+/// with `access_type: ALL` and an `attached_condition`.
+fn full_scan_suggestions(node: &PlanNode, metadata: &DatabaseMetadata, suggestions: &mut Vec<OptimizationSuggestion>) {
+    let detail = |key: &str| node.details.get(key).and_then(Value::as_str);
+    let filter = match node.operation.as_str() {
+        "Seq Scan" => detail("Filter"),
+        "table" if detail("access_type") == Some("ALL") => detail("attached_condition"),
+        _ => None,
+    };
+
+    let table = node.relation.as_deref().and_then(|relation| {
+        let relation = table_name(relation);
+        metadata.tables.iter().find(|t| t.name.eq_ignore_ascii_case(relation))
+    });
+    if let (Some(filter), Some(table)) = (filter, table) {
+        let rows = table.row_count.map(|rows| rows as f64).or(node.estimated_rows).unwrap_or(0.0);
+        let columns = filter_columns(filter, table);
+        let indexed = columns.iter().all(|name| {
+            table.columns.iter().any(|c| c.is_primary_key && c.name == *name)
+        });
+        if rows >= FULL_SCAN_MIN_ROWS && !columns.is_empty() && !indexed {
+            suggestions.push(index_suggestion(table, columns, rows));
+        }
+    }
+
+    for child in &node.children {
+        full_scan_suggestions(child, metadata, suggestions);
+    }
+}
+
+/// Columns of `table` a scan filter refers to, in order of appearance
+fn filter_columns(filter: &str, table: &Table) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for word in identifiers(filter) {
+        if let Some(column) = table.columns.iter().find(|c| c.name.eq_ignore_ascii_case(&word)) {
+            if !columns.contains(&column.name) {
+                columns.push(column.name.clone());
+            }
+        }
+    }
+    columns.truncate(INDEX_MAX_COLUMNS);
+    columns
+}
+
+fn index_suggestion(table: &Table, columns: Vec<String>, rows: f64) -> OptimizationSuggestion {
+    let qualified = match &table.schema {
+        Some(schema) => format!("{}.{}", schema, table.name),
+        None => table.name.clone(),
+    };
+    let index_name = format!("idx_{}_{}", table.name, columns.join("_")).to_lowercase();
+    OptimizationSuggestion {
+        kind: SuggestionKind::MissingIndex,
+        impact: if rows >= HIGH_IMPACT_ROWS { SuggestionImpact::High } else { SuggestionImpact::Medium },
+        title: format!("Index {} on {}", columns.join(", "), table.name),
+        description: format!(
+            "The plan scans all of {} (~{} rows) and filters on {}; an index lets the database read only the matching rows.",
+            qualified,
+            rows as u64,
+            columns.join(", ")
+        ),
+        table: Some(qualified.clone()),
+        sql: Some(format!("CREATE INDEX {} ON {} ({})", index_name, qualified, columns.join(", "))),
+        columns,
+        source: SuggestionSource::Heuristic,
+    }
+}
+
+/// Whether the outermost SELECT of `sql` projects `*` or `table.*`
+fn selects_wildcard(sql: &str) -> bool {
+    let Ok(statements) = Parser::parse_sql(&GenericDialect {}, sql) else {
+        return false;
+    };
+    let Some(Statement::Query(query)) = statements.first() else {
+        return false;
+    };
+    match query.body.as_ref() {
+        SetExpr::Select(select) => select
+            .projection
+            .iter()
+            .any(|item| matches!(item, SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..))),
+        _ => false,
+    }
+}
+
+/// Suggestion as the LLM writes it; unknown kinds and impacts are tolerated
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LlmSuggestion {
+    kind: String,
+    impact: String,
+    title: String,
+    description: String,
+    table: Option<String>,
+    columns: Vec<String>,
+    sql: Option<String>,
+}
+
+/// Suggestions of an LLM answer: a JSON array, or an object with a `suggestions` array,
+/// possibly inside a markdown code block
+fn parse_llm_suggestions(answer: &str) -> Result<Vec<OptimizationSuggestion>, AppError> {
+    let invalid = || AppError::LlmService("LLM advice is not a JSON array of suggestions".to_string());
+    let start = answer.find(['[', '{']).ok_or_else(invalid)?;
+    let end = answer.rfind([']', '}']).filter(|end| *end >= start).ok_or_else(invalid)?;
+    let value: Value = serde_json::from_str(&answer[start..=end]).map_err(|_| invalid())?;
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(mut object) => match object.remove("suggestions") {
+            Some(Value::Array(items)) => items,
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+
+    let enum_value = |text: &str| Value::String(text.trim().to_lowercase().replace([' ', '-'], "_"));
+    Ok(items
+        .into_iter()
+        .filter_map(|item| serde_json::from_value::<LlmSuggestion>(item).ok())
+        .filter(|s| !s.title.trim().is_empty() || !s.description.trim().is_empty())
+        .map(|s| OptimizationSuggestion {
+            kind: serde_json::from_value(enum_value(&s.kind)).unwrap_or(SuggestionKind::Other),
+            impact: serde_json::from_value(enum_value(&s.impact)).unwrap_or(SuggestionImpact::Medium),
+            title: s.title,
+            description: s.description,
+            table: s.table.filter(|t| !t.trim().is_empty()),
+            columns: s.columns,
+            sql: s.sql.filter(|sql| !sql.trim().is_empty()),
+            source: SuggestionSource::Llm,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Column;
+    use crate::services::database::plan::postgres_plan;
+    use serde_json::json;
+
+    fn column(name: &str, is_primary_key: bool) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable: !is_primary_key,
+            is_primary_key,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn metadata(row_count: i64) -> DatabaseMetadata {
+        let orders = Table {
+            name: "orders".to_string(),
+            schema: Some("public".to_string()),
+            columns: vec![column("id", true), column("status", false), column("created_at", false)],
+            row_count: Some(row_count),
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        };
+        DatabaseMetadata::new("conn".to_string(), vec![orders], vec![], vec!["public".to_string()])
+    }
+
+    fn plan(filter: &str) -> QueryPlan {
+        postgres_plan(json!([{
+            "Plan": {"Node Type": "Seq Scan", "Relation Name": "orders", "Plan Rows": 120, "Filter": filter}
+        }]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_heuristic_suggestions() {
+        let sql = "SELECT * FROM orders WHERE status = 'paid'";
+        let suggestions =
+            QueryAdvisorService::heuristic_suggestions(sql, &plan("(status = 'paid'::text)"), &metadata(50_000));
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].kind, SuggestionKind::MissingIndex);
+        assert_eq!(suggestions[0].impact, SuggestionImpact::Medium);
+        assert_eq!(suggestions[0].columns, vec!["status"]);
+        assert_eq!(
+            suggestions[0].sql.as_deref(),
+            Some("CREATE INDEX idx_orders_status ON public.orders (status)")
+        );
+        assert_eq!(suggestions[1].kind, SuggestionKind::Rewrite);
+
+        // Small tables and primary key filters need no index
+        let small = QueryAdvisorService::heuristic_suggestions(
+            "SELECT id FROM orders WHERE status = 'paid'",
+            &plan("(status = 'paid'::text)"),
+            &metadata(500),
+        );
+        assert!(small.is_empty());
+        let by_key =
+            QueryAdvisorService::heuristic_suggestions("SELECT id FROM orders", &plan("(id = 7)"), &metadata(50_000));
+        assert!(by_key.is_empty());
+    }
+
+    #[test]
+    fn test_parse_llm_suggestions() {
+        let answer = r#"```json
+{"suggestions": [
+  {"kind": "missing_index", "impact": "HIGH", "title": "Index status", "description": "Seq Scan on orders",
+   "table": "public.orders", "columns": ["status"], "sql": "CREATE INDEX ON orders (status)"},
+  {"kind": "partitioning", "title": "Partition by month", "description": ""},
+  {"kind": "rewrite", "title": "", "description": ""}
+]}
+```"#;
+        let suggestions = parse_llm_suggestions(answer).unwrap();
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].kind, SuggestionKind::MissingIndex);
+        assert_eq!(suggestions[0].impact, SuggestionImpact::High);
+        assert_eq!(suggestions[0].source, SuggestionSource::Llm);
+        assert_eq!(suggestions[1].kind, SuggestionKind::Other);
+        assert_eq!(suggestions[1].impact, SuggestionImpact::Medium);
+        assert!(parse_llm_suggestions("[]").unwrap().is_empty());
+        assert!(parse_llm_suggestions("Add an index on status.").is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_uncovered_heuristics() {
+        let heuristics =
+            QueryAdvisorService::heuristic_suggestions("SELECT * FROM orders WHERE status = 'paid'", &plan("(status = 'x')"), &metadata(50_000));
+        let llm = parse_llm_suggestions(
+            r#"[{"kind": "missing_index", "impact": "high", "title": "Index status", "description": "d", "table": "orders"}]"#,
+        )
+        .unwrap();
+
+        let merged = QueryAdvisorService::merge(llm, heuristics);
+        let kinds: Vec<_> = merged.iter().map(|s| (s.kind, s.source)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (SuggestionKind::MissingIndex, SuggestionSource::Llm),
+                (SuggestionKind::Rewrite, SuggestionSource::Heuristic),
+            ]
+        );
+    }
+}
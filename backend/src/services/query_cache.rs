@@ -2,8 +2,17 @@
 //
 // Implements LRU cache for query results with TTL support.
 // Reduces database load by caching frequently accessed query results.
+// Entries hit often enough are persisted to SQLite, so hot results survive
+// restarts and are shared with a fresh in-memory cache.
 
+use crate::config::QueryCacheConfig;
+use crate::models::QueryParameters;
 use crate::services::database::adapter::QueryResult;
+use crate::storage::SqliteStorage;
+use crate::validation::SqlFingerprint;
+use chrono::{DateTime, Utc};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -21,6 +30,10 @@ struct CachedResult {
     ttl: Duration,
     /// Number of times this cache entry was hit
     hit_count: u64,
+    /// Wall-clock time when cached (reported to clients and persisted)
+    cached_at_utc: DateTime<Utc>,
+    /// Whether the entry was written to SQLite
+    persisted: bool,
 }
 
 impl CachedResult {
@@ -57,6 +70,17 @@ pub struct QueryResultCache {
     default_ttl: Duration,
     /// Cache statistics
     stats: Arc<Mutex<CacheStats>>,
+    /// Persistence of hot entries (None keeps the cache in memory)
+    storage: Option<Arc<SqliteStorage>>,
+    /// Hits after which an entry is persisted
+    persist_after_hits: u64,
+}
+
+/// Result served from the cache
+#[derive(Debug, Clone)]
+pub struct CacheHit {
+    pub result: QueryResult,
+    pub cached_at: DateTime<Utc>,
 }
 
 /// Cache statistics
@@ -98,9 +122,22 @@ impl QueryResultCache {
             max_size,
             default_ttl: Duration::from_secs(default_ttl_secs),
             stats: Arc::new(Mutex::new(CacheStats::default())),
+            storage: None,
+            persist_after_hits: 0,
         }
     }
 
+    /// Create from configuration, persisting hot entries to `storage`
+    ///
+    /// Storage is kept even when persistence is off, so invalidation still
+    /// clears entries persisted before.
+    pub fn from_config(config: &QueryCacheConfig, storage: Arc<SqliteStorage>) -> Self {
+        let mut cache = Self::new(config.max_entries.max(1), config.ttl_secs);
+        cache.storage = Some(storage);
+        cache.persist_after_hits = config.persist_after_hits;
+        cache
+    }
+
     /// Create with default settings (1000 entries, 5 min TTL)
     pub fn default() -> Self {
         Self::new(1000, 300)
@@ -116,10 +153,107 @@ impl QueryResultCache {
         format!("{}:{:x}", connection_id, hasher.finish())
     }
 
+    /// Cache key of a query: its SQL fingerprint, the literals the
+    /// fingerprint replaced and its bind parameters
+    ///
+    /// The SQL should already carry the injected row limit, so results of
+    /// different limits are cached separately.
+    pub fn key_for(connection_id: &str, sql: &str, parameters: &QueryParameters) -> String {
+        let (mut normalized, literals) = SqlFingerprint::with_literals(sql);
+        if !literals.is_empty() {
+            normalized.push('\n');
+            normalized.push_str(&serde_json::to_string(&literals).unwrap_or_default());
+        }
+        if !parameters.is_empty() {
+            normalized.push('\n');
+            normalized.push_str(&serde_json::to_string(parameters).unwrap_or_default());
+        }
+        Self::generate_key(connection_id, &normalized)
+    }
+
+    /// SQL printed back from its AST, so case and whitespace do not matter
+    ///
+    /// SQL the generic dialect cannot parse only has its whitespace collapsed.
+    pub fn normalize_sql(sql: &str) -> String {
+        match Parser::parse_sql(&GenericDialect {}, sql) {
+            Ok(statements) if !statements.is_empty() => statements
+                .iter()
+                .map(|statement| statement.to_string())
+                .collect::<Vec<_>>()
+                .join("; "),
+            _ => sql.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+
     /// Get cached result if available and not expired
     ///
     /// Returns None if cache miss or expired
     pub fn get(&self, key: &str) -> Option<QueryResult> {
+        let hit = self.get_entry(key).map(|(hit, _)| hit.result);
+        if hit.is_none() {
+            self.record_miss(key);
+        }
+        hit
+    }
+
+    /// Get a cached result from memory, falling back to persisted entries
+    ///
+    /// An entry reaching `persist_after_hits` hits is written to SQLite.
+    pub async fn lookup(&self, key: &str) -> Option<CacheHit> {
+        if let Some((hit, persist)) = self.get_entry(key) {
+            if persist {
+                self.persist(key, &hit).await;
+            }
+            return Some(hit);
+        }
+
+        if let Some(storage) = self.storage.as_ref().filter(|_| self.persist_after_hits > 0) {
+            let now = Utc::now();
+            match storage.get_query_cache_entry(key, now).await {
+                Ok(Some((result, cached_at, expires_at))) => match serde_json::from_str::<QueryResult>(&result) {
+                    Ok(result) => {
+                        let ttl = (expires_at - now).to_std().unwrap_or_default();
+                        self.insert(key.to_string(), result.clone(), ttl, cached_at, true);
+                        self.stats.lock().unwrap().hits += 1;
+                        return Some(CacheHit { result, cached_at });
+                    }
+                    Err(e) => tracing::warn!("Discarding unreadable persisted cache entry {}: {}", key, e),
+                },
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read persisted cache entry {}: {}", key, e),
+            }
+        }
+
+        self.record_miss(key);
+        None
+    }
+
+    async fn persist(&self, key: &str, hit: &CacheHit) {
+        let Some(storage) = &self.storage else {
+            return;
+        };
+        let Ok(result) = serde_json::to_string(&hit.result) else {
+            return;
+        };
+        let expires_at = hit.cached_at + chrono::Duration::from_std(self.default_ttl).unwrap_or_default();
+        let connection_id = Self::connection_of(key);
+        if let Err(e) = storage.save_query_cache_entry(key, connection_id, &result, hit.cached_at, expires_at).await {
+            tracing::warn!("Failed to persist cache entry {}: {}", key, e);
+        }
+    }
+
+    /// Connection id of a key made by `generate_key`
+    fn connection_of(key: &str) -> &str {
+        key.rsplit_once(':').map_or(key, |(connection_id, _)| connection_id)
+    }
+
+    fn record_miss(&self, key: &str) {
+        self.stats.lock().unwrap().misses += 1;
+        tracing::debug!("Cache miss for key: {}", key);
+    }
+
+    /// Unexpired entry from memory, and whether it just became hot enough to persist
+    fn get_entry(&self, key: &str) -> Option<(CacheHit, bool)> {
         let mut cache = self.cache.lock().unwrap();
         let mut stats = self.stats.lock().unwrap();
 
@@ -127,7 +261,6 @@ impl QueryResultCache {
             if cached.is_expired() {
                 // Expired - remove and count as miss
                 cache.remove(key);
-                stats.misses += 1;
                 stats.expirations += 1;
 
                 // Remove from LRU list
@@ -148,13 +281,20 @@ impl QueryResultCache {
                 entry.last_accessed = Instant::now();
             }
 
+            let persist = self.storage.is_some()
+                && self.persist_after_hits > 0
+                && !cached.persisted
+                && cached.hit_count >= self.persist_after_hits;
+            cached.persisted |= persist;
+
             tracing::debug!("Cache hit for key: {} (hit_count: {})", key, cached.hit_count);
-            return Some(cached.result.clone());
+            let hit = CacheHit {
+                result: cached.result.clone(),
+                cached_at: cached.cached_at_utc,
+            };
+            return Some((hit, persist));
         }
 
-        // Miss
-        stats.misses += 1;
-        tracing::debug!("Cache miss for key: {}", key);
         None
     }
 
@@ -166,6 +306,10 @@ impl QueryResultCache {
     /// * `result` - Query result to cache
     /// * `ttl` - Optional custom TTL (uses default if None)
     pub fn put(&self, key: String, result: QueryResult, ttl: Option<Duration>) {
+        self.insert(key, result, ttl.unwrap_or(self.default_ttl), Utc::now(), false);
+    }
+
+    fn insert(&self, key: String, result: QueryResult, ttl: Duration, cached_at_utc: DateTime<Utc>, persisted: bool) {
         let mut cache = self.cache.lock().unwrap();
         let mut lru = self.lru_list.lock().unwrap();
 
@@ -177,13 +321,16 @@ impl QueryResultCache {
         let cached = CachedResult {
             result,
             cached_at: Instant::now(),
-            ttl: ttl.unwrap_or(self.default_ttl),
+            ttl,
             hit_count: 0,
+            cached_at_utc,
+            persisted,
         };
 
         cache.insert(key.clone(), cached);
 
-        // Add to LRU list
+        // Add to LRU list (replacing the entry of an overwritten key)
+        lru.retain(|entry| entry.key != key);
         lru.push(LruEntry {
             key: key.clone(),
             last_accessed: Instant::now(),
@@ -226,6 +373,36 @@ impl QueryResultCache {
         tracing::info!("Cleared {} cache entries", count);
     }
 
+    /// Drop all entries of a connection, in memory and persisted
+    ///
+    /// Returns the number of distinct entries removed.
+    pub async fn invalidate_connection(&self, connection_id: &str) -> usize {
+        let mut removed: std::collections::HashSet<String> = {
+            let mut cache = self.cache.lock().unwrap();
+            let mut lru = self.lru_list.lock().unwrap();
+            let keys: Vec<String> = cache
+                .keys()
+                .filter(|key| Self::connection_of(key) == connection_id)
+                .cloned()
+                .collect();
+            for key in &keys {
+                cache.remove(key);
+            }
+            lru.retain(|entry| Self::connection_of(&entry.key) != connection_id);
+            keys.into_iter().collect()
+        };
+
+        if let Some(storage) = &self.storage {
+            match storage.delete_query_cache_entries(connection_id).await {
+                Ok(keys) => removed.extend(keys),
+                Err(e) => tracing::warn!("Failed to delete persisted cache entries of {}: {}", connection_id, e),
+            }
+        }
+
+        tracing::info!("Invalidated {} cache entries of connection {}", removed.len(), connection_id);
+        removed.len()
+    }
+
     /// Get cache statistics
     pub fn get_stats(&self) -> CacheStats {
         self.stats.lock().unwrap().clone()
@@ -367,6 +544,42 @@ mod tests {
         assert_eq!(cache.size(), 0);
     }
 
+    #[test]
+    fn test_key_ignores_formatting() {
+        let params = QueryParameters::new();
+        let key = QueryResultCache::key_for("conn1", "select id\n  from users limit 10", &params);
+        assert_eq!(key, QueryResultCache::key_for("conn1", "SELECT id FROM users LIMIT 10", &params));
+        assert!(key.starts_with("conn1:"));
+
+        let mut bound = QueryParameters::new();
+        bound.insert("id".to_string(), json!(7));
+        assert_ne!(key, QueryResultCache::key_for("conn1", "SELECT id FROM users LIMIT 10", &bound));
+        // Same fingerprint, other literals
+        assert_ne!(key, QueryResultCache::key_for("conn1", "SELECT id FROM users LIMIT 20", &params));
+    }
+
+    #[tokio::test]
+    async fn test_hot_entries_persist_until_invalidated() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let config = QueryCacheConfig { enabled: true, max_entries: 10, ttl_secs: 60, persist_after_hits: 1 };
+
+        let key = QueryResultCache::generate_key("conn1", "SELECT 1");
+        let cache = QueryResultCache::from_config(&config, storage.clone());
+        cache.put(key.clone(), create_test_result(), None);
+        assert!(cache.lookup(&key).await.is_some()); // first hit persists
+
+        // A fresh cache (e.g. after a restart) finds the persisted entry
+        let restarted = QueryResultCache::from_config(&config, storage.clone());
+        let hit = restarted.lookup(&key).await.unwrap();
+        assert_eq!(hit.result.row_count, 2);
+        assert_eq!(restarted.size(), 1);
+
+        assert_eq!(restarted.invalidate_connection("conn1").await, 1);
+        assert!(restarted.lookup(&key).await.is_none());
+        assert!(QueryResultCache::from_config(&config, storage).lookup(&key).await.is_none());
+    }
+
     #[test]
     fn test_lru_eviction() {
         let cache = QueryResultCache::new(3, 60); // Max 3 entries
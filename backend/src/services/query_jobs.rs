@@ -0,0 +1,319 @@
+// Query Job Service
+//
+// Runs queries in the background so long analytical queries do not hold an
+// HTTP request open. Job state lives in the query_jobs table and clients poll
+// it by id. Running jobs are tracked in a process-wide registry so they can be
+// cancelled: aborting the task drops the adapter future, and the PostgreSQL
+// and MySQL adapters then cancel the statement on the server as well. The
+// registry also lets clients follow a job as a stream of events (SSE).
+// Finished jobs raise the query_job.completed / query_job.failed webhooks.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde_json::json;
+use tokio::sync::{mpsc, watch};
+use tokio::task::AbortHandle;
+
+use crate::api::middleware::AppError;
+use crate::models::{Query, QueryJob, QueryJobEvent, QueryJobStatus, WebhookEvent};
+use crate::services::NotificationService;
+use crate::storage::SqliteStorage;
+
+/// Rows per `row-batch` event of a job's event stream
+pub const ROW_BATCH_SIZE: usize = 500;
+
+/// A job running in this process
+struct RunningJob {
+    abort: AbortHandle,
+    /// Moves to `running` when the query starts; closed when the task ends
+    status: watch::Receiver<QueryJobStatus>,
+}
+
+/// Jobs running in this process
+#[derive(Default)]
+pub struct QueryJobRegistry {
+    running: Mutex<HashMap<String, RunningJob>>,
+}
+
+impl QueryJobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn remove(&self, job_id: &str) {
+        self.running.lock().unwrap().remove(job_id);
+    }
+
+    /// Abort a running job's task; false if it is not running here
+    fn abort(&self, job_id: &str) -> bool {
+        match self.running.lock().unwrap().remove(job_id) {
+            Some(job) => {
+                job.abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn watch(&self, job_id: &str) -> Option<watch::Receiver<QueryJobStatus>> {
+        self.running.lock().unwrap().get(job_id).map(|job| job.status.clone())
+    }
+}
+
+pub struct QueryJobService {
+    storage: Arc<SqliteStorage>,
+    registry: Arc<QueryJobRegistry>,
+}
+
+impl QueryJobService {
+    pub fn new(storage: Arc<SqliteStorage>, registry: Arc<QueryJobRegistry>) -> Self {
+        Self { storage, registry }
+    }
+
+    /// Store a new job and run `work` for it in the background
+    ///
+    /// The job moves to `running` when the task starts and to `completed` or
+    /// `failed` with the query's outcome.
+    pub async fn submit<F>(&self, job: QueryJob, work: F) -> Result<QueryJob, AppError>
+    where
+        F: Future<Output = Result<Query, AppError>> + Send + 'static,
+    {
+        self.storage
+            .create_query_job(&job)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let storage = self.storage.clone();
+        let registry = self.registry.clone();
+        let mut outcome = job.clone();
+        let (status_tx, status_rx) = watch::channel(QueryJobStatus::Pending);
+
+        // Hold the registry lock while spawning so a fast job cannot
+        // deregister itself before it has been registered
+        let mut running = self.registry.running.lock().unwrap();
+        let task = tokio::spawn(async move {
+            let job_id = outcome.id.clone();
+            match storage.mark_query_job_running(&job_id, Utc::now()).await {
+                Ok(true) => {
+                    let _ = status_tx.send(QueryJobStatus::Running);
+                    match work.await {
+                        Ok(query) => outcome.complete_with(query),
+                        Err(e) => outcome.fail(e.to_string()),
+                    }
+                    if let Err(e) = storage.finish_query_job(&outcome).await {
+                        tracing::error!(job_id = %job_id, "Failed to store query job outcome: {}", e);
+                    }
+                    tracing::info!(job_id = %job_id, status = outcome.status.as_str(), "Query job finished");
+                    let event = if outcome.status == QueryJobStatus::Completed {
+                        WebhookEvent::QueryJobCompleted
+                    } else {
+                        WebhookEvent::QueryJobFailed
+                    };
+                    let data = json!({
+                        "job_id": job_id,
+                        "connection_id": outcome.connection_id,
+                        "status": outcome.status.as_str(),
+                        "row_count": outcome.row_count,
+                        "error": outcome.error_message,
+                    });
+                    NotificationService::new(storage.clone())
+                        .notify(outcome.domain_id.as_deref(), event, data)
+                        .await;
+                }
+                // Cancelled before it started
+                Ok(false) => {}
+                Err(e) => tracing::error!(job_id = %job_id, "Failed to start query job: {}", e),
+            }
+            registry.remove(&job_id);
+        });
+        running.insert(
+            job.id.clone(),
+            RunningJob {
+                abort: task.abort_handle(),
+                status: status_rx,
+            },
+        );
+        drop(running);
+
+        tracing::info!(job_id = %job.id, connection_id = %job.connection_id, "Submitted query job");
+        Ok(job)
+    }
+
+    /// Get a job by ID
+    pub async fn get(&self, id: &str) -> Result<QueryJob, AppError> {
+        self.storage
+            .get_query_job(id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Query job {} not found", id)))
+    }
+
+    /// Cancel a pending or running job
+    ///
+    /// The cancelled state is stored before the task is aborted, so a job that
+    /// finishes concurrently cannot overwrite it.
+    pub async fn cancel(&self, id: &str) -> Result<QueryJob, AppError> {
+        let mut job = self.get(id).await?;
+        if job.status.is_finished() {
+            return Err(AppError::Conflict(format!(
+                "Query job {} has already {}",
+                id,
+                job.status.as_str()
+            )));
+        }
+
+        job.status = QueryJobStatus::Cancelled;
+        job.error_message = Some("Cancelled by request".to_string());
+        job.finished_at = Some(Utc::now());
+        let cancelled = self
+            .storage
+            .finish_query_job(&job)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if !cancelled {
+            let current = self.get(id).await?;
+            return Err(AppError::Conflict(format!(
+                "Query job {} has already {}",
+                id,
+                current.status.as_str()
+            )));
+        }
+
+        if !self.registry.abort(id) {
+            tracing::warn!(job_id = %id, "Cancelled query job was not running in this process");
+        }
+        tracing::info!(job_id = %id, "Cancelled query job");
+        Ok(job)
+    }
+
+    /// Follow a job as events: `execution-started`, then `row-batch` events
+    /// and `completed`, `failed` or `cancelled` once it has finished
+    ///
+    /// Finished jobs are replayed from storage. The channel closes after the
+    /// last event; dropping the receiver stops the stream.
+    pub fn events(&self, job: QueryJob) -> mpsc::Receiver<QueryJobEvent> {
+        let (tx, rx) = mpsc::channel(16);
+        let status = self.registry.watch(&job.id);
+        let storage = self.storage.clone();
+
+        tokio::spawn(async move {
+            let mut job = job;
+            if let Some(mut status) = status {
+                // Wait for the query to start, then for the task to end
+                loop {
+                    if *status.borrow_and_update() != QueryJobStatus::Pending {
+                        job = reload(&storage, job).await;
+                        if let Some(event) = QueryJobEvent::started(&job) {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                        break;
+                    }
+                    if status.changed().await.is_err() {
+                        break;
+                    }
+                }
+                while status.changed().await.is_ok() {}
+                job = reload(&storage, job).await;
+            } else if let Some(event) = QueryJobEvent::started(&job) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+
+            for event in QueryJobEvent::outcome(&job, ROW_BATCH_SIZE) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        rx
+    }
+}
+
+/// Latest stored state of a job (the given state if it cannot be read)
+async fn reload(storage: &SqliteStorage, job: QueryJob) -> QueryJob {
+    match storage.get_query_job(&job.id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => job,
+        Err(e) => {
+            tracing::warn!(job_id = %job.id, "Failed to reload query job: {}", e);
+            job
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DatabaseConnection;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    async fn wait_for(service: &QueryJobService, id: &str, status: QueryJobStatus) -> QueryJob {
+        for _ in 0..100 {
+            let job = service.get(id).await.unwrap();
+            if job.status == status {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} never reached {:?}", id, status);
+    }
+
+    #[test]
+    fn test_job_completes_and_cancels() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let registry = Arc::new(QueryJobRegistry::new());
+            let service = QueryJobService::new(storage.clone(), registry.clone());
+
+            let connection = DatabaseConnection::new(None, "postgresql://localhost/db".to_string(), "postgresql".to_string(), None);
+            storage.save_connection(&connection).await.unwrap();
+
+            // Completed job keeps the result rows
+            let job = QueryJob::new(connection.id.clone(), None, "SELECT 1".to_string(), None);
+            let connection_id = connection.id.clone();
+            let job = service
+                .submit(job, async move {
+                    let mut query = Query::new(connection_id, "SELECT 1".to_string(), false);
+                    query.mark_completed(vec![serde_json::json!({"n": 1})], 5);
+                    Ok(query)
+                })
+                .await
+                .unwrap();
+            let done = wait_for(&service, &job.id, QueryJobStatus::Completed).await;
+            assert!(service.cancel(&job.id).await.is_err());
+
+            // Finished jobs are replayed as events
+            let mut events = service.events(done.clone());
+            let mut names = Vec::new();
+            while let Some(event) = events.recv().await {
+                names.push(event.name());
+            }
+            assert_eq!(names, ["execution-started", "row-batch", "completed"]);
+            assert_eq!(done.results.unwrap().len(), 1);
+
+            // A job that never finishes on its own can be cancelled
+            let job = QueryJob::new(connection.id.clone(), None, "SELECT pg_sleep(60)".to_string(), None);
+            let job = service
+                .submit(job, async {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Err(AppError::Internal("unreachable".to_string()))
+                })
+                .await
+                .unwrap();
+            wait_for(&service, &job.id, QueryJobStatus::Running).await;
+            assert_eq!(service.cancel(&job.id).await.unwrap().status, QueryJobStatus::Cancelled);
+            assert_eq!(service.get(&job.id).await.unwrap().status, QueryJobStatus::Cancelled);
+            assert!(registry.running.lock().unwrap().is_empty());
+        });
+    }
+}
@@ -1,22 +1,126 @@
-use crate::models::{Query, UnifiedQueryRequest, UnifiedQueryResponse, DatabaseType};
+use crate::models::{
+    CacheStatus, Query, RowEstimate, UnifiedQueryRequest, UnifiedQueryResponse, DatabaseType, DEFAULT_QUERY_TIMEOUT_SECS,
+    DEFAULT_ROW_LIMIT, QueryParameters, QueryPlan, WriteResult,
+};
 use crate::api::middleware::AppError;
-use crate::validation::SqlValidator;
-use crate::services::database::DatabaseAdapter;
+use crate::validation::{BindParams, PlaceholderStyle, SqlAuditContext, SqlComments, SqlValidator};
+use crate::services::database::{DatabaseAdapter, RowBatchStream, Transaction};
+use crate::services::QueryResultCache;
 use crate::services::datafusion::{
     DialectTranslationService,
     DatabaseType as DFDatabaseType,
 };
 use tokio_postgres::Client;
+use std::sync::Arc;
 use std::time::Instant;
 
 pub struct QueryService {
     dialect_translator: DialectTranslationService,
+    audit: Option<SqlAuditContext>,
+    large_result_gate: Option<LargeResultGate>,
+    parameters: QueryParameters,
+    result_cache: Option<Arc<QueryResultCache>>,
+}
+
+/// Validated query with the row limit applied
+struct LimitedQuery {
+    validated_sql: String,
+    sql: String,
+    limit_applied: bool,
+}
+
+/// Confirmation gate for queries without LIMIT that are estimated to be large
+#[derive(Debug, Clone, Copy)]
+struct LargeResultGate {
+    threshold: u64,
+    confirmed: bool,
 }
 
 impl QueryService {
     pub fn new() -> Self {
         Self {
             dialect_translator: DialectTranslationService::with_cache(),
+            audit: None,
+            large_result_gate: None,
+            parameters: QueryParameters::new(),
+            result_cache: None,
+        }
+    }
+
+    /// Serve repeated queries from `cache` (None disables caching)
+    pub fn with_result_cache(mut self, cache: Option<Arc<QueryResultCache>>) -> Self {
+        self.result_cache = cache;
+        self
+    }
+
+    /// Require confirmation for queries without LIMIT whose EXPLAIN estimate
+    /// exceeds `threshold` rows (0 disables the check)
+    pub fn with_large_result_gate(mut self, threshold: u64, confirmed: bool) -> Self {
+        self.large_result_gate = (threshold > 0).then_some(LargeResultGate { threshold, confirmed });
+        self
+    }
+
+    /// Reject unconfirmed queries whose estimated result exceeds the threshold
+    ///
+    /// Estimation failures are logged and never block execution.
+    async fn check_large_result(
+        &self,
+        sql: &str,
+        adapter: &dyn DatabaseAdapter,
+        timeout_secs: u64,
+    ) -> Result<(), AppError> {
+        let Some(gate) = self.large_result_gate.filter(|gate| !gate.confirmed) else {
+            return Ok(());
+        };
+
+        let estimate = tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_secs),
+            adapter.estimate_rows(sql),
+        )
+        .await;
+        let estimated_rows = match estimate {
+            Ok(Ok(Some(rows))) => rows,
+            Ok(Ok(None)) => return Ok(()),
+            Ok(Err(e)) => {
+                tracing::warn!("Row estimation failed, executing without confirmation: {}", e);
+                return Ok(());
+            }
+            Err(_) => {
+                tracing::warn!("Row estimation timed out, executing without confirmation");
+                return Ok(());
+            }
+        };
+
+        if estimated_rows > gate.threshold {
+            return Err(AppError::ConfirmationRequired(
+                format!(
+                    "Query has no LIMIT and is estimated to return {} rows (threshold {}). Add a LIMIT or resend with confirm_large_result=true.",
+                    estimated_rows, gate.threshold
+                ),
+                RowEstimate { estimated_rows, threshold: gate.threshold },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Values for `:name` placeholders, bound natively by the adapter
+    pub fn with_parameters(mut self, parameters: QueryParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Append an audit comment (request id, user) to SQL sent to the database
+    pub fn with_audit(mut self, audit: Option<SqlAuditContext>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Restore comments/hints lost in AST rewriting and append the audit comment
+    fn finalize_sql(&self, original_sql: &str, rewritten_sql: &str) -> String {
+        let sql = SqlComments::extract(original_sql).restore(rewritten_sql);
+        match &self.audit {
+            Some(audit) => audit.append_to(&sql),
+            None => sql,
         }
     }
 
@@ -42,17 +146,20 @@ impl QueryService {
         SqlValidator::validate_select_only(&request.query)
             .map_err(|e| AppError::InvalidSql(e.to_string()))?;
 
-        // Apply LIMIT if needed
+        // Convert DatabaseType to DFDatabaseType
+        let df_db_type = Self::convert_database_type(request.database_type)?;
+
+        // Apply LIMIT if needed, in the target dialect's limit syntax
         let datafusion_sql = if request.apply_limit {
-            SqlValidator::ensure_limit(&request.query, request.limit_value as u64)
+            let limit = request.limit_value.map_or(DEFAULT_ROW_LIMIT, |limit| limit as u64);
+            self.dialect_translator
+                .apply_limit(&request.query, df_db_type, limit)
                 .map_err(|e| AppError::InvalidSql(e.to_string()))?
+                .0
         } else {
             request.query.clone()
         };
 
-        // Convert DatabaseType to DFDatabaseType
-        let df_db_type = Self::convert_database_type(request.database_type)?;
-
         // Translate to target dialect
         let translated_sql = self.dialect_translator
             .translate_query(&datafusion_sql, df_db_type)
@@ -60,15 +167,18 @@ impl QueryService {
             .map_err(|e| AppError::Database(format!("Dialect translation failed: {}", e)))?;
 
         tracing::info!(
-            "Translated query from DataFusion to {}: {} -> {}",
-            adapter.dialect_name(),
-            datafusion_sql,
-            translated_sql
+            dialect = %adapter.dialect_name(),
+            original_sql = %crate::logging::redactor().sql(&datafusion_sql),
+            sql = %crate::logging::redactor().sql(&translated_sql),
+            "Translated query from DataFusion"
         );
 
+        // Translation does not keep comments; restore leading comments and hints
+        let translated_sql = self.finalize_sql(&request.query, &translated_sql);
+
         // Execute the translated query
         let query_result = adapter
-            .execute_query(&translated_sql, request.timeout_secs)
+            .execute_query(&translated_sql, request.timeout_secs.unwrap_or(DEFAULT_QUERY_TIMEOUT_SECS))
             .await?;
 
         let execution_time_ms = start_time.elapsed().as_millis();
@@ -98,29 +208,55 @@ impl QueryService {
 
     /// Execute a SQL query using a database adapter (with connection pooling)
     pub async fn execute_query_with_adapter(
+        &self,
+        query: Query,
+        adapter: Box<dyn DatabaseAdapter>,
+    ) -> Result<Query, AppError> {
+        self.execute_query_with_limits(query, adapter, DEFAULT_ROW_LIMIT, DEFAULT_QUERY_TIMEOUT_SECS)
+            .await
+    }
+
+    /// Execute a SQL query using a database adapter with an explicit row limit and timeout
+    pub async fn execute_query_with_limits(
         &self,
         mut query: Query,
         adapter: Box<dyn DatabaseAdapter>,
+        row_limit: u64,
+        timeout_secs: u64,
     ) -> Result<Query, AppError> {
         let start_time = Instant::now();
         query.mark_executing();
 
-        // Validate and prepare SQL (SELECT-only check and LIMIT enforcement)
-        let (prepared_sql, limit_applied) = SqlValidator::validate_and_prepare(&query.query_text, 1000)
-            .map_err(|e| {
-                query.mark_failed(e.to_string());
-                e
-            })?;
+        let limited = self.limit_query(&mut query, adapter.as_ref(), row_limit)?;
 
-        query.limit_applied = limit_applied;
+        // Served from the cache before any database round trip (including the estimate)
+        let cache_key = self
+            .result_cache
+            .as_ref()
+            .map(|_| QueryResultCache::key_for(&query.connection_id, &limited.sql, &self.parameters));
+        if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+            if let Some(hit) = cache.lookup(key).await {
+                query.limit_applied = limited.limit_applied;
+                query.mark_completed(hit.result.rows, start_time.elapsed().as_millis() as u64);
+                query.cache = Some(CacheStatus { hit: true, cached_at: Some(hit.cached_at) });
+                return Ok(query);
+            }
+        }
+
+        let (prepared_sql, bind_values) = self.prepare_query(&mut query, adapter.as_ref(), &limited, timeout_secs).await?;
 
         // Execute query using the adapter (which uses connection pool internally)
-        let query_result = adapter.execute_query(&prepared_sql, 30).await
+        let query_result = adapter.execute_query_with_params(&prepared_sql, &bind_values, timeout_secs).await
             .map_err(|e| {
                 query.mark_failed(e.to_string());
                 e
             })?;
 
+        if let (Some(cache), Some(key)) = (&self.result_cache, cache_key) {
+            cache.put(key, query_result.clone(), None);
+            query.cache = Some(CacheStatus { hit: false, cached_at: None });
+        }
+
         // Convert adapter QueryResult to our Query model
         let execution_time_ms = start_time.elapsed().as_millis() as u64;
         query.mark_completed(query_result.rows, execution_time_ms);
@@ -128,6 +264,173 @@ impl QueryService {
         Ok(query)
     }
 
+    /// Start a SQL query and return its rows as a stream of batches, for
+    /// results consumed incrementally (exports, interactive sessions)
+    ///
+    /// Validated, limited and bound like [`QueryService::execute_query_with_limits`],
+    /// but never served from or stored in the result cache. The returned
+    /// query is executing, with `limit_applied` set.
+    pub async fn stream_query_with_limits(
+        &self,
+        mut query: Query,
+        adapter: Box<dyn DatabaseAdapter>,
+        row_limit: u64,
+        timeout_secs: u64,
+        batch_size: usize,
+    ) -> Result<(Query, RowBatchStream), AppError> {
+        query.mark_executing();
+
+        let limited = self.limit_query(&mut query, adapter.as_ref(), row_limit)?;
+        let (prepared_sql, bind_values) = self.prepare_query(&mut query, adapter.as_ref(), &limited, timeout_secs).await?;
+
+        let batches = adapter.stream_query(&prepared_sql, &bind_values, timeout_secs, batch_size).await
+            .map_err(|e| {
+                query.mark_failed(e.to_string());
+                e
+            })?;
+        Ok((query, batches))
+    }
+
+    /// Validate a query (SELECT-only check), then let the adapter inject the
+    /// LIMIT in its own dialect, at the outermost query only
+    fn limit_query(
+        &self,
+        query: &mut Query,
+        adapter: &dyn DatabaseAdapter,
+        row_limit: u64,
+    ) -> Result<LimitedQuery, AppError> {
+        let validated_sql = SqlValidator::validate_select_only(&query.query_text)
+            .map_err(|e| {
+                query.mark_failed(e.to_string());
+                e
+            })?;
+        let (sql, limit_applied) = adapter.apply_row_limit(&validated_sql, row_limit)
+            .map_err(|e| {
+                query.mark_failed(e.to_string());
+                e
+            })?;
+        Ok(LimitedQuery { validated_sql, sql, limit_applied })
+    }
+
+    /// Apply the large result gate, restore comments and bind the parameters
+    /// of a limited query
+    async fn prepare_query(
+        &self,
+        query: &mut Query,
+        adapter: &dyn DatabaseAdapter,
+        limited: &LimitedQuery,
+        timeout_secs: u64,
+    ) -> Result<(String, Vec<serde_json::Value>), AppError> {
+        // A LIMIT was injected, so the user did not bound the result themselves
+        if limited.limit_applied {
+            self.check_large_result(&limited.validated_sql, adapter, timeout_secs).await?;
+        }
+
+        query.limit_applied = limited.limit_applied;
+        let prepared_sql = self.finalize_sql(&query.query_text, &limited.sql);
+
+        // Named placeholders become the driver's positional ones; without
+        // parameters the SQL is sent as is
+        if self.parameters.is_empty() {
+            return Ok((prepared_sql, Vec::new()));
+        }
+        BindParams::bind(&prepared_sql, &self.parameters, adapter.placeholder_style())
+            .map_err(|e| {
+                query.mark_failed(e.to_string());
+                e
+            })
+    }
+
+    /// Explain a query without executing it
+    ///
+    /// `unified` is the connection's type when the query is in DataFusion
+    /// syntax; it is then translated first, and the translation is returned
+    /// with the plan. The query is explained as written: no row limit is
+    /// injected. Parameters and the audit comment are handled as for queries.
+    pub async fn explain(
+        &self,
+        sql: &str,
+        adapter: &dyn DatabaseAdapter,
+        unified: Option<DatabaseType>,
+        timeout_secs: u64,
+    ) -> Result<(Option<String>, QueryPlan), AppError> {
+        let validated_sql = SqlValidator::validate_select_only(sql)?;
+
+        let translated_sql = match unified {
+            Some(database_type) => Some(
+                self.dialect_translator
+                    .translate_query(sql, Self::convert_database_type(database_type)?)
+                    .await
+                    .map_err(|e| AppError::Database(format!("Dialect translation failed: {}", e)))?,
+            ),
+            None => None,
+        };
+        let target_sql = self.finalize_sql(sql, translated_sql.as_deref().unwrap_or(&validated_sql));
+
+        let (target_sql, bind_values) = if self.parameters.is_empty() {
+            (target_sql, Vec::new())
+        } else {
+            BindParams::bind(&target_sql, &self.parameters, adapter.placeholder_style())?
+        };
+        let plan = adapter.explain(&target_sql, &bind_values, timeout_secs).await?;
+        Ok((translated_sql, plan))
+    }
+
+    /// Execute a write statement (write mode) and return the affected row count
+    ///
+    /// The statement must pass `SqlValidator::validate_write`; no row limit or
+    /// large result gate applies. Parameters and the audit comment are handled
+    /// as for queries.
+    pub async fn execute_write(
+        &self,
+        statement: &str,
+        adapter: Box<dyn DatabaseAdapter>,
+        timeout_secs: u64,
+    ) -> Result<WriteResult, AppError> {
+        let start_time = Instant::now();
+        let (statement_kind, sql, bind_values) = self.prepare_write(statement, adapter.placeholder_style())?;
+        let affected_rows = adapter.execute_write(&sql, &bind_values, timeout_secs).await?;
+        Ok(WriteResult {
+            statement_kind: statement_kind.to_string(),
+            affected_rows,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Execute a write statement within an open transaction
+    ///
+    /// Validated and bound like [`QueryService::execute_write`].
+    pub async fn execute_in_transaction(
+        &self,
+        statement: &str,
+        transaction: &mut dyn Transaction,
+        timeout_secs: u64,
+    ) -> Result<WriteResult, AppError> {
+        let start_time = Instant::now();
+        let (statement_kind, sql, bind_values) = self.prepare_write(statement, transaction.placeholder_style())?;
+        let affected_rows = transaction.execute(&sql, &bind_values, timeout_secs).await?;
+        Ok(WriteResult {
+            statement_kind: statement_kind.to_string(),
+            affected_rows,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Validate a write statement, append the audit comment and bind its parameters
+    fn prepare_write(
+        &self,
+        statement: &str,
+        style: PlaceholderStyle,
+    ) -> Result<(&'static str, String, Vec<serde_json::Value>), AppError> {
+        let statement_kind = SqlValidator::validate_write(statement)?;
+        let prepared_sql = self.finalize_sql(statement, statement);
+        if self.parameters.is_empty() {
+            return Ok((statement_kind, prepared_sql, Vec::new()));
+        }
+        let (sql, bind_values) = BindParams::bind(&prepared_sql, &self.parameters, style)?;
+        Ok((statement_kind, sql, bind_values))
+    }
+
     /// Execute a SQL query against a PostgreSQL database (legacy method for backward compatibility)
     /// This method is deprecated - use execute_query_with_adapter instead
     pub async fn execute_query(
@@ -196,7 +499,12 @@ impl QueryService {
                 };
                 
                 let error_msg = format!("{}: {}. SQL: {}", error_code, error_details, prepared_sql);
-                tracing::error!("Query execution error: {}", error_msg);
+                tracing::error!(
+                    error_code = %error_code,
+                    error = %crate::logging::redactor().text(&error_details),
+                    sql = %crate::logging::redactor().sql(&prepared_sql),
+                    "Query execution error"
+                );
                 query.mark_failed(error_msg.clone());
                 AppError::Database(error_msg)
             })?;
@@ -0,0 +1,235 @@
+// Query Share Service
+//
+// Share links let people without an account read the result of a saved
+// query. A link either serves a result frozen when it was created or runs the
+// query (with the parameter values fixed at creation) on every visit. Live
+// links only run the SQL they were created for: once the saved query's SQL or
+// connection changes, they stop serving results. Only SHA-256 hashes of the
+// tokens are stored; links can expire and be revoked.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::api::middleware::AppError;
+use crate::models::{CreateQueryShareRequest, CreateQueryShareResponse, Query, QueryShare, QueryStatus, SavedQuery};
+use crate::storage::SqliteStorage;
+use crate::validation::BindParams;
+
+pub struct QueryShareService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl QueryShareService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Check a share request before any result is captured for it
+    pub fn validate(saved: &SavedQuery, request: &CreateQueryShareRequest) -> Result<(), AppError> {
+        request.validate().map_err(AppError::Validation)?;
+        BindParams::validate(&request.parameters)?;
+        if saved.connection_id.is_none() {
+            return Err(AppError::Validation(format!(
+                "Saved query {} is a cross-database query and cannot be shared",
+                saved.id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create a share link; `snapshot` is the result frozen for snapshot shares
+    pub async fn create(
+        &self,
+        saved: &SavedQuery,
+        request: CreateQueryShareRequest,
+        created_by: Option<String>,
+        snapshot: Option<Query>,
+    ) -> Result<CreateQueryShareResponse, AppError> {
+        Self::validate(saved, &request)?;
+        if let Some(query) = &snapshot {
+            if query.status != QueryStatus::Completed {
+                return Err(AppError::Validation(format!(
+                    "The saved query failed, so there is no result to share: {}",
+                    query.error_message.as_deref().unwrap_or("unknown error")
+                )));
+            }
+        }
+
+        let (mut share, token) = QueryShare::generate(
+            saved.domain_id.clone(),
+            saved.id.clone(),
+            request.parameters,
+            request.expires_in_secs,
+            created_by,
+        );
+        share.snapshot_at = snapshot.as_ref().map(|query| query.executed_at.unwrap_or(share.created_at));
+        share.query_hash = QueryShare::saved_query_hash(saved);
+        self.storage
+            .create_query_share(&share, snapshot.as_ref())
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!(share_id = %share.id, saved_query_id = %saved.id, "Created share link");
+        let path = format!("/api/share/{}", token);
+        Ok(CreateQueryShareResponse { share, token, path })
+    }
+
+    /// Share links of a saved query (tokens are never returned)
+    pub async fn list(&self, saved_query_id: &str) -> Result<Vec<QueryShare>, AppError> {
+        self.storage
+            .list_query_shares(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Revoke a share link; its snapshot is deleted and visits fail at once
+    pub async fn revoke(&self, saved_query_id: &str, id: &str) -> Result<(), AppError> {
+        let revoked = self
+            .storage
+            .revoke_query_share(id, saved_query_id, Utc::now())
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if !revoked {
+            return Err(AppError::NotFound(format!("Active share {} not found", id)));
+        }
+        tracing::info!(share_id = %id, "Revoked share link");
+        Ok(())
+    }
+
+    /// Resolve a token to its share and frozen result, rejecting unknown,
+    /// revoked and expired links alike
+    pub async fn resolve(&self, token: &str) -> Result<(QueryShare, Option<Query>), AppError> {
+        let not_found = || AppError::NotFound("Share link not found, revoked or expired".to_string());
+        let (share, snapshot) = self
+            .storage
+            .get_query_share_by_hash(&QueryShare::hash(token))
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(not_found)?;
+        if share.is_revoked() || share.is_expired(Utc::now()) {
+            return Err(not_found());
+        }
+        Ok((share, snapshot))
+    }
+
+    /// Check that a live share may run `saved`: its SQL and connection are the
+    /// ones the share was created for, so editing the query cannot change what
+    /// the link discloses
+    pub fn ensure_unchanged(share: &QueryShare, saved: &SavedQuery) -> Result<(), AppError> {
+        if !share.matches_saved_query(saved) {
+            tracing::info!(share_id = %share.id, saved_query_id = %saved.id, "Saved query changed since it was shared");
+            return Err(AppError::NotFound("Share link not found, revoked or expired".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_share_resolve_and_revoke() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let connection = crate::models::DatabaseConnection::new(
+                None,
+                "postgresql://localhost/test".to_string(),
+                "postgresql".to_string(),
+                Some("default-domain-id".to_string()),
+            );
+            storage.save_connection(&connection).await.unwrap();
+            let saved = SavedQuery::new(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                "Orders".to_string(),
+                "SELECT * FROM orders".to_string(),
+                None,
+            );
+            storage.save_query(&saved).await.unwrap();
+            let service = QueryShareService::new(storage);
+
+            let mut result = Query::new(connection.id.clone(), saved.query_text.clone(), false);
+            result.status = QueryStatus::Completed;
+            result.results = Some(vec![serde_json::json!({ "id": 1 })]);
+            result.row_count = Some(1);
+            let created = service
+                .create(&saved, CreateQueryShareRequest { snapshot: true, ..Default::default() }, None, Some(result))
+                .await
+                .unwrap();
+            assert!(created.share.snapshot_at.is_some());
+            assert_eq!(created.path, format!("/api/share/{}", created.token));
+
+            let (share, snapshot) = service.resolve(&created.token).await.unwrap();
+            assert_eq!(share.id, created.share.id);
+            assert_eq!(snapshot.unwrap().row_count, Some(1));
+            assert!(service.resolve("dbs_unknown").await.is_err());
+            assert_eq!(service.list(&saved.id).await.unwrap().len(), 1);
+
+            // A failed result cannot be frozen
+            let failed = Query::new(connection.id.clone(), saved.query_text.clone(), false);
+            assert!(service
+                .create(&saved, CreateQueryShareRequest::default(), None, Some(failed))
+                .await
+                .is_err());
+
+            assert!(share.matches_saved_query(&saved));
+
+            service.revoke(&saved.id, &share.id).await.unwrap();
+            assert!(matches!(service.resolve(&created.token).await, Err(AppError::NotFound(_))));
+            assert!(service.revoke(&saved.id, &share.id).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_live_share_stops_matching_edited_query() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let connection = crate::models::DatabaseConnection::new(
+                None,
+                "postgresql://localhost/test".to_string(),
+                "postgresql".to_string(),
+                Some("default-domain-id".to_string()),
+            );
+            storage.save_connection(&connection).await.unwrap();
+            let saved = SavedQuery::new(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                "Orders".to_string(),
+                "SELECT id, total FROM orders".to_string(),
+                None,
+            );
+            storage.save_query(&saved).await.unwrap();
+            let service = QueryShareService::new(storage.clone());
+
+            let created = service
+                .create(&saved, CreateQueryShareRequest::default(), None, None)
+                .await
+                .unwrap();
+            let (share, snapshot) = service.resolve(&created.token).await.unwrap();
+            assert!(snapshot.is_none());
+            let current = storage.get_saved_query(&saved.id).await.unwrap().unwrap();
+            assert!(QueryShareService::ensure_unchanged(&share, &current).is_ok());
+
+            // Editing the SQL after sharing must not change what the link serves
+            let edited_sql = "SELECT id, email, password_hash FROM users".to_string();
+            assert!(storage
+                .update_saved_query(&saved.id, None, Some(edited_sql.clone()), None, None, None, None, saved.version)
+                .await
+                .unwrap());
+            let edited = storage.get_saved_query(&saved.id).await.unwrap().unwrap();
+            assert_eq!(edited.query_text, edited_sql);
+            assert!(matches!(
+                QueryShareService::ensure_unchanged(&share, &edited),
+                Err(AppError::NotFound(_))
+            ));
+        });
+    }
+}
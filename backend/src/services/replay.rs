@@ -0,0 +1,386 @@
+// Query Replay Service
+//
+// Captures the execution context (connection settings fingerprint, metadata
+// version, result hash) of each history entry and replays historic queries
+// against the current database to explain "what changed" between runs.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::models::{
+    DatabaseConnection, Query, QueryHistory, QueryHistorySnapshot, QueryKind, QueryStatus, ReplayDifference,
+    ReplayDifferenceKind, ReplayExecution, ReplayReport,
+};
+use crate::services::database::{create_adapter, DatabaseType};
+use crate::services::{ConnectionPoolManager, DomainSettingsService, QueryService};
+use crate::storage::SqliteStorage;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Stable 64-bit FNV-1a hash, hex encoded
+///
+/// Unlike `DefaultHasher`, the output is stable across builds and restarts,
+/// which is required for hashes persisted in history.
+pub fn stable_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Hash of a result set (row order sensitive, key order insensitive)
+pub fn result_hash(rows: &[Value]) -> String {
+    let serialized = serde_json::to_string(rows).unwrap_or_default();
+    stable_hash(serialized.as_bytes())
+}
+
+/// Fingerprint of a connection's settings that never exposes credentials
+///
+/// The user, password and secret query parameters are removed from the URL
+/// before hashing: the hash is unkeyed, so hashing a password would let
+/// anyone who reads the fingerprint test guesses against it offline.
+/// Credential changes still bump the connection version.
+pub fn connection_fingerprint(connection: &DatabaseConnection) -> String {
+    let url = url_without_credentials(&connection.connection_url);
+    stable_hash(format!("{}|{}", connection.database_type, url).as_bytes())
+}
+
+/// Connection URL without its user info and secret query parameters
+fn url_without_credentials(connection_url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(connection_url) else {
+        // Where the credentials are in an unparseable URL is unknown
+        return "[invalid-url]".to_string();
+    };
+    let _ = parsed.set_username("");
+    let _ = parsed.set_password(None);
+
+    if parsed.query().is_some() {
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(key, _)| !crate::logging::is_secret_param(key))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        if pairs.is_empty() {
+            parsed.set_query(None);
+        } else {
+            parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+    parsed.to_string()
+}
+
+/// Service for capturing execution snapshots and replaying historic queries
+pub struct QueryReplayService {
+    storage: Arc<SqliteStorage>,
+    pool_manager: Arc<ConnectionPoolManager>,
+}
+
+impl QueryReplayService {
+    pub fn new(storage: Arc<SqliteStorage>, pool_manager: Arc<ConnectionPoolManager>) -> Self {
+        Self { storage, pool_manager }
+    }
+
+    /// Build the snapshot for a history entry from the connection and executed query
+    async fn build_snapshot(
+        &self,
+        history_id: &str,
+        connection: &DatabaseConnection,
+        query: &Query,
+    ) -> Result<QueryHistorySnapshot, AppError> {
+        let metadata = self
+            .storage
+            .get_metadata_cache(&connection.id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(QueryHistorySnapshot {
+            history_id: history_id.to_string(),
+            connection_id: connection.id.clone(),
+            connection_version: connection.version,
+            connection_fingerprint: connection_fingerprint(connection),
+            database_type: connection.database_type.clone(),
+            metadata_cache_id: metadata.as_ref().map(|m| m.id.clone()),
+            metadata_version: metadata.as_ref().map(|m| m.version),
+            result_hash: query.results.as_deref().map(result_hash),
+            limit_applied: query.limit_applied,
+            captured_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Capture and persist the execution context for a history entry
+    pub async fn capture_snapshot(
+        &self,
+        history: &QueryHistory,
+        connection: &DatabaseConnection,
+        query: &Query,
+    ) -> Result<(), AppError> {
+        let snapshot = self.build_snapshot(&history.id, connection, query).await?;
+        self.storage
+            .save_history_snapshot(&snapshot)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Replay a historic query against the connection's current settings
+    pub async fn replay(&self, domain_id: &str, history_id: &str) -> Result<ReplayReport, AppError> {
+        let history = self
+            .storage
+            .get_query_history(history_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .filter(|h| h.domain_id == domain_id)
+            .ok_or_else(|| AppError::NotFound(format!("Query history {} not found in domain {}", history_id, domain_id)))?;
+        if history.kind == QueryKind::Write {
+            return Err(AppError::Validation(format!(
+                "Query history {} is a write statement; writes are not replayed",
+                history_id
+            )));
+        }
+
+        let Some(connection_id) = history.connection_id.as_deref() else {
+            return Err(AppError::Validation(format!(
+                "Query history {} is a cross-database query; re-run it with POST /api/cross-database/query",
+                history_id
+            )));
+        };
+        let connection = self
+            .storage
+            .get_connection(connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Connection {} not found", connection_id)))?;
+
+        let original_snapshot = self
+            .storage
+            .get_history_snapshot(history_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let db_type = DatabaseType::from_str(&connection.database_type)?;
+        let adapter = create_adapter(
+            db_type,
+            &connection.connection_url,
+            connection.read_only,
+            self.pool_manager.clone(),
+        )
+        .await?;
+
+        let settings = DomainSettingsService::new(self.storage.clone()).get(domain_id).await?;
+        let query = Query::new(connection.id.clone(), history.query_text.clone(), history.is_llm_generated);
+        let replayed = QueryService::new()
+            .execute_query_with_limits(query, adapter, settings.default_row_limit, settings.default_timeout_secs)
+            .await?;
+        if replayed.status != QueryStatus::Completed {
+            return Err(AppError::Internal(format!(
+                "Replay did not complete: {}",
+                replayed.error_message.unwrap_or_default()
+            )));
+        }
+
+        let current_snapshot = self.build_snapshot(history_id, &connection, &replayed).await?;
+
+        tracing::info!("Replayed query history {} on connection {}", history_id, connection.id);
+        Ok(Self::compare(&history, original_snapshot.as_ref(), &current_snapshot, replayed.row_count.unwrap_or(0)))
+    }
+
+    /// Compare the original execution with a replay and explain differences
+    pub fn compare(
+        history: &QueryHistory,
+        original: Option<&QueryHistorySnapshot>,
+        current: &QueryHistorySnapshot,
+        replay_row_count: usize,
+    ) -> ReplayReport {
+        let mut differences = Vec::new();
+
+        let results_match = match original.and_then(|s| s.result_hash.as_ref()) {
+            Some(hash) => current.result_hash.as_ref() == Some(hash),
+            None => false,
+        };
+
+        if history.row_count != replay_row_count {
+            differences.push(ReplayDifference {
+                kind: ReplayDifferenceKind::RowCountChanged,
+                message: format!("Row count changed from {} to {}", history.row_count, replay_row_count),
+            });
+        }
+
+        match original {
+            None => differences.push(ReplayDifference {
+                kind: ReplayDifferenceKind::MissingSnapshot,
+                message: "No execution snapshot was captured for this history entry; only row counts can be compared".to_string(),
+            }),
+            Some(original) => {
+                if original.result_hash.is_some() && !results_match {
+                    differences.push(ReplayDifference {
+                        kind: ReplayDifferenceKind::ResultChanged,
+                        message: "Result data differs from the original execution".to_string(),
+                    });
+                }
+
+                if original.database_type != current.database_type {
+                    differences.push(ReplayDifference {
+                        kind: ReplayDifferenceKind::DatabaseTypeChanged,
+                        message: format!(
+                            "Database type changed from {} to {}",
+                            original.database_type, current.database_type
+                        ),
+                    });
+                }
+
+                // Snapshots from before credentials were stripped have no fingerprint
+                let settings_changed = if original.connection_fingerprint.is_empty() {
+                    original.connection_version != current.connection_version
+                } else {
+                    original.connection_fingerprint != current.connection_fingerprint
+                };
+                if settings_changed {
+                    differences.push(ReplayDifference {
+                        kind: ReplayDifferenceKind::ConnectionSettingsChanged,
+                        message: format!(
+                            "Connection settings changed since the original run (version {} -> {})",
+                            original.connection_version, current.connection_version
+                        ),
+                    });
+                }
+
+                if original.metadata_version != current.metadata_version
+                    || original.metadata_cache_id != current.metadata_cache_id
+                {
+                    differences.push(ReplayDifference {
+                        kind: ReplayDifferenceKind::MetadataChanged,
+                        message: format!(
+                            "Schema metadata changed since the original run (version {} -> {})",
+                            original.metadata_version.map_or("unknown".to_string(), |v| v.to_string()),
+                            current.metadata_version.map_or("unknown".to_string(), |v| v.to_string())
+                        ),
+                    });
+                }
+
+                if original.limit_applied != current.limit_applied {
+                    differences.push(ReplayDifference {
+                        kind: ReplayDifferenceKind::LimitChanged,
+                        message: "Automatic LIMIT handling differs from the original run".to_string(),
+                    });
+                }
+            }
+        }
+
+        ReplayReport {
+            history_id: history.id.clone(),
+            query_text: history.query_text.clone(),
+            original: ReplayExecution {
+                row_count: history.row_count,
+                result_hash: original.and_then(|s| s.result_hash.clone()),
+                connection_version: original.map(|s| s.connection_version),
+                metadata_version: original.and_then(|s| s.metadata_version),
+                executed_at: history.executed_at,
+            },
+            replay: ReplayExecution {
+                row_count: replay_row_count,
+                result_hash: current.result_hash.clone(),
+                connection_version: Some(current.connection_version),
+                metadata_version: current.metadata_version,
+                executed_at: current.captured_at,
+            },
+            results_match,
+            differences,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(result_hash: &str, metadata_version: i32) -> QueryHistorySnapshot {
+        QueryHistorySnapshot {
+            history_id: "h1".to_string(),
+            connection_id: "c1".to_string(),
+            connection_version: 1,
+            connection_fingerprint: "fp".to_string(),
+            database_type: "postgresql".to_string(),
+            metadata_cache_id: Some("m1".to_string()),
+            metadata_version: Some(metadata_version),
+            result_hash: Some(result_hash.to_string()),
+            limit_applied: true,
+            captured_at: chrono::Utc::now(),
+        }
+    }
+
+    fn history() -> QueryHistory {
+        QueryHistory::new("d1".to_string(), "c1".to_string(), "SELECT 1".to_string(), 2, 5, false)
+    }
+
+    #[test]
+    fn test_result_hash_is_stable_and_key_order_insensitive() {
+        let a = vec![json!({"id": 1, "name": "a"})];
+        let b = vec![json!({"name": "a", "id": 1})];
+        assert_eq!(result_hash(&a), result_hash(&b));
+        assert_ne!(result_hash(&a), result_hash(&[json!({"id": 2, "name": "a"})]));
+        assert_eq!(stable_hash(b""), "cbf29ce484222325");
+    }
+
+    #[test]
+    fn test_compare_identical() {
+        let original = snapshot("abc", 1);
+        let current = snapshot("abc", 1);
+        let report = QueryReplayService::compare(&history(), Some(&original), &current, 2);
+
+        assert!(report.results_match);
+        assert!(report.differences.is_empty());
+    }
+
+    #[test]
+    fn test_compare_flags_metadata_and_result_changes() {
+        let original = snapshot("abc", 1);
+        let mut current = snapshot("def", 2);
+        current.connection_fingerprint = "other".to_string();
+        current.connection_version = 2;
+        let report = QueryReplayService::compare(&history(), Some(&original), &current, 3);
+
+        assert!(!report.results_match);
+        let kinds: Vec<_> = report.differences.iter().map(|d| d.kind.clone()).collect();
+        assert!(kinds.contains(&ReplayDifferenceKind::RowCountChanged));
+        assert!(kinds.contains(&ReplayDifferenceKind::ResultChanged));
+        assert!(kinds.contains(&ReplayDifferenceKind::MetadataChanged));
+        assert!(kinds.contains(&ReplayDifferenceKind::ConnectionSettingsChanged));
+    }
+
+    #[test]
+    fn test_connection_fingerprint_ignores_credentials() {
+        let connection = |url: &str| {
+            DatabaseConnection::new(None, url.to_string(), "postgresql".to_string(), Some("d1".to_string()))
+        };
+        let base = connection_fingerprint(&connection("postgresql://app:s3cret@db:5432/sales?sslmode=require"));
+        assert_eq!(
+            base,
+            connection_fingerprint(&connection("postgresql://other:guess@db:5432/sales?sslmode=require&password=x"))
+        );
+        assert_eq!(base, stable_hash(b"postgresql|postgresql://db:5432/sales?sslmode=require"));
+        assert_ne!(base, connection_fingerprint(&connection("postgresql://app:s3cret@db:5432/crm?sslmode=require")));
+    }
+
+    #[test]
+    fn test_compare_legacy_snapshot_uses_version() {
+        let mut original = snapshot("abc", 1);
+        original.connection_fingerprint = String::new();
+        let mut current = snapshot("abc", 1);
+        let report = QueryReplayService::compare(&history(), Some(&original), &current, 2);
+        assert!(report.differences.is_empty());
+
+        current.connection_version = 2;
+        let report = QueryReplayService::compare(&history(), Some(&original), &current, 2);
+        assert_eq!(report.differences[0].kind, ReplayDifferenceKind::ConnectionSettingsChanged);
+    }
+
+    #[test]
+    fn test_compare_without_snapshot() {
+        let report = QueryReplayService::compare(&history(), None, &snapshot("abc", 1), 2);
+        assert!(!report.results_match);
+        assert_eq!(report.differences[0].kind, ReplayDifferenceKind::MissingSnapshot);
+    }
+}
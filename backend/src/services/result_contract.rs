@@ -0,0 +1,248 @@
+// Result Contract Service
+//
+// A saved query can declare the columns (and their types) its result is
+// expected to have. Whenever the query's result is checked, the differences
+// from that contract are recorded on it, so consumers of the result learn
+// about breaking changes (a dropped column, a type change) as soon as they
+// happen. A drift is logged and raised as the `saved_query.schema_drift`
+// webhook event once, when it first appears or changes.
+
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, Schema};
+
+use crate::api::middleware::AppError;
+use crate::models::{ContractColumn, ContractType, ResultContract, SavedQuery, SetResultContractRequest, WebhookEvent};
+use crate::services::NotificationService;
+use crate::storage::SqliteStorage;
+
+pub struct ResultContractService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl ResultContractService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Contract of a saved query, if it declared one
+    pub async fn get(&self, saved_query_id: &str) -> Result<Option<ResultContract>, AppError> {
+        self.storage
+            .get_result_contract(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Declare or replace a saved query's contract; drift found against the
+    /// previous contract is cleared
+    pub async fn set(&self, saved: &SavedQuery, request: SetResultContractRequest) -> Result<ResultContract, AppError> {
+        request.validate().map_err(AppError::Validation)?;
+
+        let contract = ResultContract::new(saved.id.clone(), saved.domain_id.clone(), request.columns);
+        self.storage
+            .save_result_contract(&contract)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        tracing::info!(saved_query_id = %saved.id, "Declared result contract ({} columns)", contract.columns.len());
+        Ok(contract)
+    }
+
+    /// Remove a saved query's contract; false if it had none
+    pub async fn delete(&self, saved_query_id: &str) -> Result<bool, AppError> {
+        self.storage
+            .delete_result_contract(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Check a result of a saved query against its contract
+    ///
+    /// The differences are recorded on the contract. Returns them when they
+    /// are new (they differ from the previous check's), so a drift is reported
+    /// once rather than on every run; empty when the result matches, the drift
+    /// is unchanged or the query has no contract.
+    pub async fn check(&self, saved_query_id: &str, schema: &Schema) -> Result<Vec<String>, AppError> {
+        let Some(contract) = self.get(saved_query_id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let drift = schema_drift(&contract.columns, schema);
+        self.storage
+            .record_result_contract_check(saved_query_id, &drift, chrono::Utc::now())
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if drift.is_empty() || drift == contract.drift {
+            return Ok(Vec::new());
+        }
+        tracing::warn!(
+            saved_query_id = %saved_query_id,
+            "Result drifted from its contract: {}",
+            drift.join("; ")
+        );
+        NotificationService::new(self.storage.clone())
+            .notify(
+                Some(&contract.domain_id),
+                WebhookEvent::SchemaDrift,
+                serde_json::json!({
+                    "saved_query_id": saved_query_id,
+                    "differences": drift,
+                }),
+            )
+            .await;
+        Ok(drift)
+    }
+}
+
+/// How a result's columns differ from a contract
+///
+/// Reports declared columns that are missing or of another type, columns the
+/// contract does not declare, and (when the columns match) a changed order.
+/// Empty when the result matches.
+pub fn schema_drift(contract: &[ContractColumn], schema: &Schema) -> Vec<String> {
+    let mut drift = Vec::new();
+    for column in contract {
+        let Ok(field) = schema.field_with_name(&column.name) else {
+            drift.push(format!("missing column {}", column.name));
+            continue;
+        };
+        let Some(declared) = column.data_type else {
+            continue;
+        };
+        let actual = contract_type(field.data_type());
+        if actual != Some(declared) {
+            let actual = actual.map(|t| t.as_str().to_string()).unwrap_or_else(|| field.data_type().to_string());
+            drift.push(format!("column {} is {}, declared {}", column.name, actual, declared.as_str()));
+        }
+    }
+    for field in schema.fields() {
+        if !contract.iter().any(|column| &column.name == field.name()) {
+            drift.push(format!("unexpected column {}", field.name()));
+        }
+    }
+
+    if drift.is_empty() {
+        let actual: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+        let declared: Vec<&str> = contract.iter().map(|column| column.name.as_str()).collect();
+        if actual != declared {
+            drift.push(format!("columns are in order {}, declared {}", actual.join(", "), declared.join(", ")));
+        }
+    }
+    drift
+}
+
+/// The contract type an Arrow column type satisfies, if any
+fn contract_type(data_type: &DataType) -> Option<ContractType> {
+    match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => Some(ContractType::Integer),
+        DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => Some(ContractType::Float),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(ContractType::String),
+        DataType::Boolean => Some(ContractType::Boolean),
+        DataType::Date32 | DataType::Date64 => Some(ContractType::Date),
+        DataType::Timestamp(_, _) => Some(ContractType::Timestamp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::Field;
+    use tempfile::tempdir;
+
+    fn column(name: &str, data_type: Option<ContractType>) -> ContractColumn {
+        ContractColumn { name: name.to_string(), data_type }
+    }
+
+    fn revenue_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("region", DataType::Utf8, true),
+            Field::new("revenue", DataType::Int64, true),
+        ])
+    }
+
+    #[test]
+    fn test_schema_drift() {
+        let schema = revenue_schema();
+
+        let contract = [column("region", Some(ContractType::String)), column("revenue", Some(ContractType::Integer))];
+        assert!(schema_drift(&contract, &schema).is_empty());
+        // Untyped columns only need to exist
+        assert!(schema_drift(&[column("region", None), column("revenue", None)], &schema).is_empty());
+
+        let drift = schema_drift(
+            &[
+                column("region", Some(ContractType::String)),
+                column("revenue", Some(ContractType::Float)),
+                column("orders", None),
+            ],
+            &schema,
+        );
+        assert_eq!(drift, vec!["column revenue is integer, declared float", "missing column orders"]);
+
+        let drift = schema_drift(&[column("region", None)], &schema);
+        assert_eq!(drift, vec!["unexpected column revenue"]);
+
+        let drift = schema_drift(&[column("revenue", None), column("region", None)], &schema);
+        assert_eq!(drift, vec!["columns are in order region, revenue, declared revenue, region"]);
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_new_drift_once() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        storage.save_connection(&connection).await.unwrap();
+        let saved = SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Revenue".to_string(),
+            "SELECT region, SUM(amount) AS revenue FROM orders GROUP BY region".to_string(),
+            None,
+        );
+        storage.save_query(&saved).await.unwrap();
+        let service = ResultContractService::new(storage);
+
+        // Without a contract nothing is checked
+        assert!(service.check(&saved.id, &revenue_schema()).await.unwrap().is_empty());
+        assert!(service.set(&saved, SetResultContractRequest { columns: Vec::new() }).await.is_err());
+
+        let columns = vec![column("region", Some(ContractType::String)), column("revenue", Some(ContractType::Float))];
+        service.set(&saved, SetResultContractRequest { columns }).await.unwrap();
+
+        let drift = service.check(&saved.id, &revenue_schema()).await.unwrap();
+        assert_eq!(drift, vec!["column revenue is integer, declared float"]);
+        // The same drift is only reported once, but stays recorded
+        assert!(service.check(&saved.id, &revenue_schema()).await.unwrap().is_empty());
+        let contract = service.get(&saved.id).await.unwrap().unwrap();
+        assert_eq!(contract.drift, drift);
+        assert!(contract.checked_at.is_some());
+
+        // A matching result clears it
+        let matching = Schema::new(vec![
+            Field::new("region", DataType::Utf8, true),
+            Field::new("revenue", DataType::Float64, true),
+        ]);
+        assert!(service.check(&saved.id, &matching).await.unwrap().is_empty());
+        assert!(service.get(&saved.id).await.unwrap().unwrap().drift.is_empty());
+
+        assert!(service.delete(&saved.id).await.unwrap());
+        assert!(!service.delete(&saved.id).await.unwrap());
+    }
+}
@@ -0,0 +1,288 @@
+// Result Export Service
+//
+// Encodes query results as downloadable files (CSV, NDJSON, Parquet, Excel,
+// Arrow IPC).
+// The rows are loaded into one Arrow record batch, typed the same way as for
+// result transformations, and written with the Arrow writers. CSV and NDJSON
+// are produced slice by slice so the response body is streamed, or batch by
+// batch straight from a database row stream; Parquet, Excel and Arrow files
+// are written in one piece. The same typing is used for the column-oriented
+// JSON form of results (`result_format=columnar`).
+
+use axum::body::Bytes;
+use datafusion::arrow::array::{Array, AsArray};
+use datafusion::arrow::csv::WriterBuilder as CsvWriterBuilder;
+use datafusion::arrow::datatypes::{DataType, Float64Type, Int64Type, Schema};
+use datafusion::arrow::ipc::writer::StreamWriter;
+use datafusion::arrow::json::LineDelimitedWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+use futures::stream::{self, Stream, StreamExt};
+use rust_xlsxwriter::{Workbook, XlsxError};
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::models::{ColumnarResult, ExportFormat};
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::result_transform::rows_to_record_batch;
+
+/// Rows per streamed chunk of a CSV or NDJSON export
+pub const EXPORT_BATCH_ROWS: usize = 8192;
+
+/// Rows of an Excel worksheet, including the header row
+const XLSX_MAX_ROWS: usize = 1_048_576;
+
+pub struct ResultExportService;
+
+impl ResultExportService {
+    /// Encode result rows as a stream of file chunks
+    ///
+    /// Fails up front if the rows cannot be loaded or do not fit the format
+    /// (Excel sheets hold at most 1,048,575 data rows, Parquet needs columns).
+    pub fn encode(
+        format: ExportFormat,
+        rows: &[Value],
+    ) -> Result<impl Stream<Item = Result<Bytes, AppError>> + Send + 'static, AppError> {
+        if format == ExportFormat::Xlsx && rows.len() >= XLSX_MAX_ROWS {
+            return Err(AppError::Validation(format!(
+                "Result has {} rows; Excel exports hold at most {} rows, use csv, ndjson or parquet",
+                rows.len(),
+                XLSX_MAX_ROWS - 1
+            )));
+        }
+        if format == ExportFormat::Parquet && rows.is_empty() {
+            return Err(AppError::Validation(
+                "Query returned no rows; Parquet exports need at least one row to know the columns".to_string(),
+            ));
+        }
+
+        let batch = if rows.is_empty() {
+            None
+        } else {
+            Some(rows_to_record_batch(rows)?)
+        };
+        let chunks = if format.is_streamable() {
+            ((rows.len() + EXPORT_BATCH_ROWS - 1) / EXPORT_BATCH_ROWS).max(1)
+        } else {
+            1
+        };
+
+        Ok(stream::iter(0..chunks).map(move |index| {
+            let bytes = match batch.as_ref() {
+                Some(batch) if format.is_streamable() => {
+                    let offset = index * EXPORT_BATCH_ROWS;
+                    let slice = batch.slice(offset, EXPORT_BATCH_ROWS.min(batch.num_rows() - offset));
+                    encode_slice(format, &slice, index == 0)?
+                }
+                Some(batch) => encode_file(format, batch)?,
+                None => encode_empty(format)?,
+            };
+            Ok(Bytes::from(bytes))
+        }))
+    }
+
+    /// Encode row batches as CSV or NDJSON chunks as they arrive
+    ///
+    /// Each batch is typed on its own; only the first chunk carries the CSV
+    /// header. An error in `batches` ends the stream with that error.
+    pub fn encode_stream(
+        format: ExportFormat,
+        batches: impl Stream<Item = Result<Vec<Value>, AppError>> + Send + 'static,
+    ) -> Result<impl Stream<Item = Result<Bytes, AppError>> + Send + 'static, AppError> {
+        if !format.is_streamable() {
+            return Err(AppError::Internal(format!(
+                "{} exports are written in one piece and cannot be encoded as a stream",
+                format.extension()
+            )));
+        }
+
+        let mut first = true;
+        Ok(batches.map(move |rows| {
+            let rows = rows?;
+            if rows.is_empty() {
+                return Ok(Bytes::new());
+            }
+            let bytes = encode_slice(format, &rows_to_record_batch(&rows)?, first)?;
+            first = false;
+            Ok(Bytes::from(bytes))
+        }))
+    }
+
+    /// Column-oriented form of result rows (`result_format=columnar`)
+    ///
+    /// Columns are typed like exports; nested values (JSON objects, arrays)
+    /// and columns of mixed types become text.
+    pub fn columnar(rows: &[Value]) -> Result<ColumnarResult, AppError> {
+        if rows.is_empty() {
+            return Ok(ColumnarResult::default());
+        }
+        let batch = rows_to_record_batch(rows)?;
+        DataFusionResultConverter::convert_to_columnar(batch.schema(), &[batch])
+            .map_err(|e| AppError::Internal(format!("Failed to convert results to columns: {}", e)))
+    }
+}
+
+fn export_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("Failed to encode export: {}", e))
+}
+
+/// CSV or NDJSON lines for a slice of the rows
+fn encode_slice(format: ExportFormat, slice: &RecordBatch, first: bool) -> Result<Vec<u8>, AppError> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = CsvWriterBuilder::new().with_header(first).build(Vec::new());
+            writer.write(slice).map_err(export_error)?;
+            Ok(writer.into_inner())
+        }
+        _ => {
+            let mut writer = LineDelimitedWriter::new(Vec::new());
+            writer.write(slice).map_err(export_error)?;
+            writer.finish().map_err(export_error)?;
+            Ok(writer.into_inner())
+        }
+    }
+}
+
+/// Whole Parquet, Excel or Arrow file
+fn encode_file(format: ExportFormat, batch: &RecordBatch) -> Result<Vec<u8>, AppError> {
+    match format {
+        ExportFormat::Parquet => {
+            let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), None).map_err(export_error)?;
+            writer.write(batch).map_err(export_error)?;
+            writer.into_inner().map_err(export_error)
+        }
+        ExportFormat::Arrow => encode_arrow(&batch.schema(), Some(batch)),
+        _ => encode_xlsx(Some(batch)),
+    }
+}
+
+/// File for a result without rows (and therefore without known columns)
+///
+/// Parquet is rejected in [`ResultExportService::encode`], as it needs a schema.
+fn encode_empty(format: ExportFormat) -> Result<Vec<u8>, AppError> {
+    match format {
+        ExportFormat::Xlsx => encode_xlsx(None),
+        ExportFormat::Arrow => encode_arrow(&Schema::empty(), None),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Arrow IPC stream: the schema message, the record batch and the end-of-stream marker
+fn encode_arrow(schema: &Schema, batch: Option<&RecordBatch>) -> Result<Vec<u8>, AppError> {
+    let mut writer = StreamWriter::try_new(Vec::new(), schema).map_err(export_error)?;
+    if let Some(batch) = batch {
+        writer.write(batch).map_err(export_error)?;
+    }
+    writer.finish().map_err(export_error)?;
+    writer.into_inner().map_err(export_error)
+}
+
+/// Excel workbook with a header row and one row per result row
+///
+/// Numbers and booleans keep their cell type; nulls are left empty.
+fn encode_xlsx(batch: Option<&RecordBatch>) -> Result<Vec<u8>, AppError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    if let Some(batch) = batch {
+        for (index, field) in batch.schema().fields().iter().enumerate() {
+            let col = u16::try_from(index)
+                .map_err(|_| AppError::Validation("Too many columns for an Excel export".to_string()))?;
+            sheet.write_string(0, col, field.name()).map_err(export_error)?;
+
+            let array = batch.column(index);
+            for row in 0..batch.num_rows() {
+                if array.is_null(row) {
+                    continue;
+                }
+                let cell = row as u32 + 1;
+                let written: Result<_, XlsxError> = match field.data_type() {
+                    DataType::Boolean => sheet.write_boolean(cell, col, array.as_boolean().value(row)),
+                    DataType::Int64 => {
+                        sheet.write_number(cell, col, array.as_primitive::<Int64Type>().value(row) as f64)
+                    }
+                    DataType::Float64 => sheet.write_number(cell, col, array.as_primitive::<Float64Type>().value(row)),
+                    _ => sheet.write_string(cell, col, array.as_string::<i32>().value(row)),
+                };
+                written.map_err(export_error)?;
+            }
+        }
+    }
+    workbook.save_to_buffer().map_err(export_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn collect(format: ExportFormat, rows: &[Value]) -> Vec<Bytes> {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            ResultExportService::encode(format, rows)
+                .unwrap()
+                .map(|chunk| chunk.unwrap())
+                .collect()
+                .await
+        })
+    }
+
+    #[test]
+    fn test_csv_and_ndjson_are_chunked() {
+        let rows: Vec<Value> = (0..EXPORT_BATCH_ROWS + 1).map(|n| json!({"id": n, "name": "a,b"})).collect();
+
+        let chunks = collect(ExportFormat::Csv, &rows);
+        assert_eq!(chunks.len(), 2);
+        let first = String::from_utf8(chunks[0].to_vec()).unwrap();
+        assert!(first.starts_with("id,name\n0,\"a,b\"\n"));
+        // Only the first chunk has the header
+        assert_eq!(String::from_utf8(chunks[1].to_vec()).unwrap(), format!("{},\"a,b\"\n", EXPORT_BATCH_ROWS));
+
+        let chunks = collect(ExportFormat::Ndjson, &rows[..2]);
+        assert_eq!(
+            String::from_utf8(chunks[0].to_vec()).unwrap(),
+            "{\"id\":0,\"name\":\"a,b\"}\n{\"id\":1,\"name\":\"a,b\"}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_stream_writes_header_once() {
+        let batches = stream::iter(vec![
+            Ok(vec![json!({"id": 1, "name": "a"})]),
+            Ok(vec![json!({"id": 2, "name": "b"})]),
+            Err(AppError::Database("connection lost".to_string())),
+        ]);
+        let chunks: Vec<Result<Bytes, AppError>> = ResultExportService::encode_stream(ExportFormat::Csv, batches)
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].as_ref().unwrap().as_ref(), b"id,name\n1,a\n");
+        assert_eq!(chunks[1].as_ref().unwrap().as_ref(), b"2,b\n");
+        assert!(chunks[2].is_err());
+        assert!(ResultExportService::encode_stream(ExportFormat::Parquet, stream::empty()).is_err());
+    }
+
+    #[test]
+    fn test_binary_formats_are_single_files() {
+        let rows = vec![json!({"id": 1, "ok": true}), json!({"id": 2, "ok": null})];
+
+        let parquet = collect(ExportFormat::Parquet, &rows);
+        assert_eq!(parquet.len(), 1);
+        assert!(parquet[0].starts_with(b"PAR1"));
+
+        let xlsx = collect(ExportFormat::Xlsx, &rows);
+        assert_eq!(xlsx.len(), 1);
+        // Excel files are zip archives
+        assert!(xlsx[0].starts_with(b"PK"));
+
+        let arrow = collect(ExportFormat::Arrow, &rows);
+        let reader = datafusion::arrow::ipc::reader::StreamReader::try_new(std::io::Cursor::new(arrow[0].to_vec()), None)
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field_with_name("id").unwrap().data_type(), &DataType::Int64);
+
+        assert!(ResultExportService::encode(ExportFormat::Parquet, &[]).is_err());
+    }
+}
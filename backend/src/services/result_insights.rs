@@ -0,0 +1,414 @@
+// Result Insights
+//
+// Takeaways of a query result for people who don't read result grids. Column
+// statistics are computed over every submitted row; the LLM gets them with an
+// evenly spaced sample of the rows and answers with a short summary, insights
+// and follow-up questions. Without an LLM the statistics are phrased directly.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::config::Config;
+use crate::models::{LlmAuditContext, LlmTokenUsage, ResultSummary, SummarizeResultRequest, SummarySource};
+use crate::services::{LlmService, PromptRedactor};
+use crate::storage::SqliteStorage;
+
+/// Rows of a result given to the LLM
+const SAMPLE_ROWS: usize = 50;
+
+/// Characters of a text value kept in the sample
+const SAMPLE_VALUE_MAX_CHARS: usize = 200;
+
+/// Distinct values counted per column before counting stops
+const PROFILE_MAX_DISTINCT: usize = 1000;
+
+/// Most frequent values reported per text column
+const TOP_VALUES: usize = 3;
+
+/// Follow-up questions of a statistics summary
+const MAX_FOLLOW_UPS: usize = 3;
+
+/// Statistics of one result column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub non_null: usize,
+    /// Distinct non-null values, up to `PROFILE_MAX_DISTINCT`
+    pub distinct: usize,
+    /// Minimum, maximum and mean, when every non-null value is a number
+    pub numeric: Option<(f64, f64, f64)>,
+    /// Most frequent values with their counts (non-numeric columns)
+    pub top_values: Vec<(String, usize)>,
+}
+
+impl ColumnProfile {
+    /// The profile without values, for columns masked in prompts
+    fn redacted(&self) -> ColumnProfile {
+        ColumnProfile {
+            numeric: None,
+            top_values: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    fn describe(&self, row_count: usize) -> String {
+        let mut text = format!("- {}: {} non-null of {}", self.name, self.non_null, row_count);
+        if let Some((min, max, mean)) = self.numeric {
+            text.push_str(&format!(", number, min {}, max {}, mean {}", number(min), number(max), number(mean)));
+        } else {
+            text.push_str(&format!(", {} distinct", self.distinct));
+            if !self.top_values.is_empty() {
+                let top: Vec<String> = self.top_values.iter().map(|(v, n)| format!("{} ({})", v, n)).collect();
+                text.push_str(&format!(", most frequent: {}", top.join(", ")));
+            }
+        }
+        text
+    }
+}
+
+pub struct ResultInsightService {
+    llm: LlmService,
+}
+
+impl ResultInsightService {
+    pub fn new(config: &Config) -> Self {
+        Self { llm: LlmService::new(config) }
+    }
+
+    /// Redact prompts with this redactor (see `LlmService::with_redactor`)
+    pub fn with_redactor(mut self, redactor: PromptRedactor) -> Self {
+        self.llm = self.llm.with_redactor(redactor);
+        self
+    }
+
+    /// Record prompts in the LLM audit log (see `LlmService::with_audit`)
+    pub fn with_audit(mut self, storage: Arc<SqliteStorage>, context: LlmAuditContext) -> Self {
+        self.llm = self.llm.with_audit(storage, context);
+        self
+    }
+
+    /// Tokens of the LLM calls made so far
+    pub fn token_usage(&self) -> LlmTokenUsage {
+        self.llm.token_usage()
+    }
+
+    /// Summary of the LLM (None without a configured LLM gateway)
+    pub async fn llm_summary(
+        &self,
+        request: &SummarizeResultRequest,
+        profiles: &[ColumnProfile],
+    ) -> Result<Option<ResultSummary>, AppError> {
+        if !self.llm.is_configured() {
+            return Ok(None);
+        }
+        let row_count = request.row_count.unwrap_or(request.results.len()).max(request.results.len());
+        let sample = self.llm.redact_rows(&sample_rows(&request.results));
+        let profile: Vec<String> = profiles
+            .iter()
+            .map(|p| {
+                if self.llm.masks_column(&p.name) {
+                    p.redacted().describe(request.results.len())
+                } else {
+                    p.describe(request.results.len())
+                }
+            })
+            .collect();
+        let answer = self
+            .llm
+            .summarize_result(request.question.as_deref(), &request.query, &profile.join("\n"), &sample, row_count)
+            .await?;
+
+        let parsed = parse_llm_summary(&answer)?;
+        Ok(Some(ResultSummary {
+            summary: parsed.summary,
+            insights: parsed.insights,
+            follow_up_questions: parsed.follow_up_questions,
+            sampled_rows: sample.len(),
+            row_count,
+            source: SummarySource::Llm,
+            llm_error: None,
+        }))
+    }
+
+    /// Summary phrased from the column statistics alone
+    pub fn statistics_summary(request: &SummarizeResultRequest, profiles: &[ColumnProfile]) -> ResultSummary {
+        let rows = request.results.len();
+        let row_count = request.row_count.unwrap_or(rows).max(rows);
+        let mut summary = ResultSummary {
+            summary: String::new(),
+            insights: Vec::new(),
+            follow_up_questions: Vec::new(),
+            sampled_rows: 0,
+            row_count,
+            source: SummarySource::Statistics,
+            llm_error: None,
+        };
+        if rows == 0 {
+            summary.summary = "The query returned no rows.".to_string();
+            return summary;
+        }
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        summary.summary = format!(
+            "The result has {} row{} and {} column{} ({}).",
+            row_count,
+            if row_count == 1 { "" } else { "s" },
+            names.len(),
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        );
+        if rows < row_count {
+            summary.summary.push_str(&format!(" Statistics cover the first {} rows.", rows));
+        }
+
+        for profile in profiles {
+            if let Some((min, max, mean)) = profile.numeric {
+                summary.insights.push(format!(
+                    "{} ranges from {} to {} (average {}).",
+                    profile.name,
+                    number(min),
+                    number(max),
+                    number(mean)
+                ));
+            } else if let Some((value, count)) = profile.top_values.first() {
+                if *count > 1 {
+                    summary.insights.push(format!(
+                        "The most frequent {} is {} ({} of {} rows).",
+                        profile.name, value, count, profile.non_null
+                    ));
+                }
+            }
+            if profile.non_null < rows {
+                summary.insights.push(format!("{} is empty in {} rows.", profile.name, rows - profile.non_null));
+            }
+        }
+
+        let measure = profiles.iter().find(|p| p.numeric.is_some()).map(|p| p.name.as_str());
+        let dimension = profiles
+            .iter()
+            .find(|p| p.numeric.is_none() && p.distinct > 1 && p.distinct < rows.max(2))
+            .map(|p| p.name.as_str());
+        let follow_ups = &mut summary.follow_up_questions;
+        if let (Some(measure), Some(dimension)) = (measure, dimension) {
+            follow_ups.push(format!("What is the total {} by {}?", measure, dimension));
+            follow_ups.push(format!("Which {} has the highest {}?", dimension, measure));
+        }
+        if let Some(measure) = measure {
+            follow_ups.push(format!("How has {} changed over time?", measure));
+        }
+        follow_ups.truncate(MAX_FOLLOW_UPS);
+        summary
+    }
+}
+
+/// Statistics of every column of `rows`, in the order columns first appear
+pub fn profile_columns(rows: &[Value]) -> Vec<ColumnProfile> {
+    struct Accumulator {
+        non_null: usize,
+        counts: HashMap<String, usize>,
+        all_numbers: bool,
+        min: f64,
+        max: f64,
+        sum: f64,
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let mut columns: HashMap<String, Accumulator> = HashMap::new();
+    for row in rows {
+        let Some(object) = row.as_object() else { continue };
+        for (name, value) in object {
+            let column = columns.entry(name.clone()).or_insert_with(|| {
+                order.push(name.clone());
+                Accumulator {
+                    non_null: 0,
+                    counts: HashMap::new(),
+                    all_numbers: true,
+                    min: f64::INFINITY,
+                    max: f64::NEG_INFINITY,
+                    sum: 0.0,
+                }
+            });
+            if value.is_null() {
+                continue;
+            }
+            column.non_null += 1;
+            match value.as_f64() {
+                Some(n) => {
+                    column.min = column.min.min(n);
+                    column.max = column.max.max(n);
+                    column.sum += n;
+                }
+                None => column.all_numbers = false,
+            }
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if let Some(count) = column.counts.get_mut(&text) {
+                *count += 1;
+            } else if column.counts.len() < PROFILE_MAX_DISTINCT {
+                column.counts.insert(text, 1);
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|name| {
+            let column = &columns[&name];
+            let numeric = (column.all_numbers && column.non_null > 0)
+                .then(|| (column.min, column.max, column.sum / column.non_null as f64));
+            let mut top_values: Vec<(String, usize)> = if numeric.is_none() {
+                column.counts.iter().map(|(v, n)| (v.clone(), *n)).collect()
+            } else {
+                Vec::new()
+            };
+            top_values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            top_values.truncate(TOP_VALUES);
+            ColumnProfile {
+                name,
+                non_null: column.non_null,
+                distinct: column.counts.len(),
+                numeric,
+                top_values,
+            }
+        })
+        .collect()
+}
+
+/// Up to `SAMPLE_ROWS` evenly spaced rows, with long text values shortened
+fn sample_rows(rows: &[Value]) -> Vec<Value> {
+    let picked: Vec<&Value> = if rows.len() <= SAMPLE_ROWS {
+        rows.iter().collect()
+    } else {
+        (0..SAMPLE_ROWS).map(|i| &rows[i * rows.len() / SAMPLE_ROWS]).collect()
+    };
+    picked
+        .into_iter()
+        .map(|row| match row {
+            Value::Object(object) => Value::Object(
+                object
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            Value::String(s) if s.chars().count() > SAMPLE_VALUE_MAX_CHARS => {
+                                Value::String(format!("{}...", s.chars().take(SAMPLE_VALUE_MAX_CHARS).collect::<String>()))
+                            }
+                            other => other.clone(),
+                        };
+                        (name.clone(), value)
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// Number without a fraction when it has none, otherwise with two decimals
+fn number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{:.2}", n)
+    }
+}
+
+/// Summary as the LLM writes it
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LlmSummary {
+    summary: String,
+    insights: Vec<String>,
+    follow_up_questions: Vec<String>,
+}
+
+/// Summary of an LLM answer: a JSON object, possibly inside a markdown code block
+fn parse_llm_summary(answer: &str) -> Result<LlmSummary, AppError> {
+    let invalid = || AppError::LlmService("LLM summary is not a JSON object".to_string());
+    let start = answer.find('{').ok_or_else(invalid)?;
+    let end = answer.rfind('}').filter(|end| *end > start).ok_or_else(invalid)?;
+    let summary: LlmSummary = serde_json::from_str(&answer[start..=end]).map_err(|_| invalid())?;
+    if summary.summary.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(results: Vec<Value>) -> SummarizeResultRequest {
+        SummarizeResultRequest {
+            query: "SELECT region, total FROM sales".to_string(),
+            question: None,
+            results,
+            row_count: None,
+        }
+    }
+
+    #[test]
+    fn test_profile_columns() {
+        let rows = vec![
+            json!({"region": "EU", "total": 10, "note": null}),
+            json!({"region": "US", "total": 30, "note": "late"}),
+            json!({"region": "EU", "total": 20.5, "note": null}),
+        ];
+        let profiles = profile_columns(&rows);
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names.len(), 3);
+        let region = profiles.iter().find(|p| p.name == "region").unwrap();
+        assert_eq!(region.distinct, 2);
+        assert_eq!(region.top_values[0], ("EU".to_string(), 2));
+        let total = profiles.iter().find(|p| p.name == "total").unwrap();
+        assert_eq!(total.numeric, Some((10.0, 30.0, 60.5 / 3.0)));
+        let note = profiles.iter().find(|p| p.name == "note").unwrap();
+        assert_eq!(note.non_null, 1);
+    }
+
+    #[test]
+    fn test_statistics_summary() {
+        let rows = vec![
+            json!({"region": "EU", "amount": 10}),
+            json!({"region": "US", "amount": 30}),
+            json!({"region": "EU", "amount": 20}),
+        ];
+        let request = request(rows);
+        let summary = ResultInsightService::statistics_summary(&request, &profile_columns(&request.results));
+
+        assert!(summary.summary.starts_with("The result has 3 rows and 2 columns"));
+        assert!(summary.insights.contains(&"amount ranges from 10 to 30 (average 20).".to_string()));
+        assert!(summary.insights.contains(&"The most frequent region is EU (2 of 3 rows).".to_string()));
+        assert_eq!(summary.follow_up_questions[0], "What is the total amount by region?");
+        assert_eq!(summary.source, SummarySource::Statistics);
+
+        let empty = ResultInsightService::statistics_summary(&request(Vec::new()), &[]);
+        assert_eq!(empty.summary, "The query returned no rows.");
+        assert!(empty.follow_up_questions.is_empty());
+    }
+
+    #[test]
+    fn test_sample_rows_and_parse() {
+        let rows: Vec<Value> = (0..200).map(|i| json!({"id": i, "text": "x".repeat(500)})).collect();
+        let sample = sample_rows(&rows);
+        assert_eq!(sample.len(), SAMPLE_ROWS);
+        assert_eq!(sample[1]["id"], 4);
+        assert_eq!(sample[0]["text"].as_str().unwrap().chars().count(), SAMPLE_VALUE_MAX_CHARS + 3);
+
+        let parsed = parse_llm_summary(
+            "```json\n{\"summary\": \"EU leads.\", \"follow_up_questions\": [\"Why is US lower?\"]}\n```",
+        )
+        .unwrap();
+        assert_eq!(parsed.summary, "EU leads.");
+        assert!(parsed.insights.is_empty());
+        assert_eq!(parsed.follow_up_questions, vec!["Why is US lower?"]);
+        assert!(parse_llm_summary("EU leads.").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// Result Transformation Service
+//
+// Runs a declarative post-processing pipeline (rename, cast, derive, filter)
+// over query results before they are returned. The rows are loaded into an
+// in-memory DataFusion table and every step becomes a DataFrame operation,
+// so derived columns and filters use the same SQL expressions as DataFusion
+// queries. Row order is preserved.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::prelude::*;
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::models::{validate_transform, CastType, TransformStep};
+use crate::services::datafusion::converter::DataFusionResultConverter;
+use crate::services::datafusion::DataFusionSessionPool;
+use crate::storage::SqliteStorage;
+
+/// Name of the table holding the rows being transformed
+const RESULT_TABLE: &str = "result";
+
+pub struct ResultTransformService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl ResultTransformService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Steps to run for a query request: the saved query's transformation
+    /// (if `saved_query_id` is given) followed by the request's own steps
+    pub async fn steps_for_request(
+        &self,
+        connection_id: &str,
+        saved_query_id: Option<&str>,
+        request_steps: &[TransformStep],
+    ) -> Result<Vec<TransformStep>, AppError> {
+        let mut steps = Vec::new();
+        if let Some(saved_query_id) = saved_query_id {
+            let saved = self
+                .storage
+                .get_saved_query(saved_query_id)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+                .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found", saved_query_id)))?;
+            if saved.connection_id.as_deref() != Some(connection_id) {
+                return Err(AppError::Validation(format!(
+                    "Saved query {} does not belong to connection {}",
+                    saved_query_id, connection_id
+                )));
+            }
+            steps = saved.transform;
+        }
+        steps.extend_from_slice(request_steps);
+        validate_transform(&steps).map_err(AppError::Validation)?;
+        Ok(steps)
+    }
+
+    /// Apply `steps` to result rows
+    ///
+    /// Errors in a step (unknown column, invalid expression) are reported as
+    /// validation errors naming the step. Values that cannot be cast become null.
+    /// Steps run in a session of `sessions`, so expressions may call its scalar UDFs.
+    pub async fn apply(steps: &[TransformStep], sessions: &Arc<DataFusionSessionPool>, rows: Vec<Value>) -> Result<Vec<Value>, AppError> {
+        if steps.is_empty() || rows.is_empty() {
+            return Ok(rows);
+        }
+
+        let ctx = sessions
+            .acquire()
+            .map_err(|e| AppError::Internal(format!("Failed to create DataFusion session: {}", e)))?
+            .single_partition();
+        ctx.register_batch(RESULT_TABLE, rows_to_record_batch(&rows)?)
+            .map_err(|e| AppError::Internal(format!("Failed to load result rows: {}", e)))?;
+        let mut df = ctx
+            .table(RESULT_TABLE)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to load result rows: {}", e)))?;
+
+        for (index, step) in steps.iter().enumerate() {
+            let step_error = |e: String| AppError::Validation(format!("Transformation step {} ({}): {}", index + 1, step.op(), e));
+            df = match step {
+                TransformStep::Rename { column, to } => {
+                    ensure_column(&df, column).map_err(step_error)?;
+                    df.with_column_renamed(column.as_str(), to).map_err(|e| step_error(e.to_string()))?
+                }
+                TransformStep::Cast { column, to } => {
+                    ensure_column(&df, column).map_err(step_error)?;
+                    let expr = try_cast(ident(column), cast_data_type(*to));
+                    df.with_column(column, expr).map_err(|e| step_error(e.to_string()))?
+                }
+                TransformStep::Derive { column, expr } => {
+                    let expr = df.parse_sql_expr(expr).map_err(|e| step_error(e.to_string()))?;
+                    df.with_column(column, expr).map_err(|e| step_error(e.to_string()))?
+                }
+                TransformStep::Filter { predicate } => {
+                    let predicate = df.parse_sql_expr(predicate).map_err(|e| step_error(e.to_string()))?;
+                    df.filter(predicate).map_err(|e| step_error(e.to_string()))?
+                }
+            };
+        }
+
+        let schema = Arc::new(df.schema().as_arrow().clone());
+        let batches = df
+            .collect()
+            .await
+            .map_err(|e| AppError::Validation(format!("Failed to transform results: {}", e)))?;
+        let result = DataFusionResultConverter::convert_to_query_result(schema, batches)
+            .map_err(|e| AppError::Internal(format!("Failed to convert transformed results: {}", e)))?;
+        Ok(result.rows)
+    }
+}
+
+fn ensure_column(df: &DataFrame, column: &str) -> Result<(), String> {
+    if df.schema().has_column_with_unqualified_name(column) {
+        Ok(())
+    } else {
+        Err(format!("unknown column '{}'", column))
+    }
+}
+
+fn cast_data_type(cast: CastType) -> DataType {
+    match cast {
+        CastType::Integer => DataType::Int64,
+        CastType::Float => DataType::Float64,
+        CastType::String => DataType::Utf8,
+        CastType::Boolean => DataType::Boolean,
+        CastType::Date => DataType::Date32,
+        CastType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+    }
+}
+
+/// Build a record batch from JSON rows, typing each column by all of its values
+///
+/// NULLs and missing keys are skipped when typing. Columns holding only
+/// integers or only booleans keep that type, integers mixed with floats become
+/// Float64, and anything else (mixed values, objects, arrays, all NULL) becomes text.
+pub(crate) fn rows_to_record_batch(rows: &[Value]) -> Result<RecordBatch, AppError> {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        let object = row
+            .as_object()
+            .ok_or_else(|| AppError::Internal("Expected result rows to be JSON objects".to_string()))?;
+        for key in object.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(columns.len());
+    for column in columns {
+        let values: Vec<Option<&Value>> = rows
+            .iter()
+            .map(|row| row.get(column).filter(|v| !v.is_null()))
+            .collect();
+        let present = || values.iter().flatten();
+
+        let (data_type, array): (DataType, ArrayRef) = if present().next().is_none() {
+            (DataType::Utf8, Arc::new(StringArray::from(vec![None::<&str>; values.len()])))
+        } else if present().all(|v| v.is_boolean()) {
+            let array: BooleanArray = values.iter().map(|v| v.and_then(Value::as_bool)).collect();
+            (DataType::Boolean, Arc::new(array))
+        } else if present().all(|v| v.is_i64()) {
+            let array: Int64Array = values.iter().map(|v| v.and_then(Value::as_i64)).collect();
+            (DataType::Int64, Arc::new(array))
+        } else if present().all(|v| v.is_number()) {
+            let array: Float64Array = values.iter().map(|v| v.and_then(Value::as_f64)).collect();
+            (DataType::Float64, Arc::new(array))
+        } else {
+            let array: StringArray = values
+                .iter()
+                .map(|v| {
+                    v.map(|v| match v {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                })
+                .collect();
+            (DataType::Utf8, Arc::new(array))
+        };
+        fields.push(Field::new(column, data_type, true));
+        arrays.push(array);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+        .map_err(|e| AppError::Internal(format!("Failed to load result rows: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_pipeline() {
+        let rows = vec![
+            json!({"id": 1, "amt": "12.5", "qty": 2}),
+            json!({"id": 2, "amt": "3", "qty": 1}),
+            json!({"id": 3, "amt": "n/a", "qty": 4}),
+        ];
+        let steps = vec![
+            TransformStep::Rename { column: "amt".to_string(), to: "price".to_string() },
+            TransformStep::Cast { column: "price".to_string(), to: CastType::Float },
+            TransformStep::Derive { column: "total".to_string(), expr: "price * qty".to_string() },
+            TransformStep::Filter { predicate: "total IS NULL OR total > 5".to_string() },
+        ];
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let result = rt.block_on(ResultTransformService::apply(&steps, &Arc::new(DataFusionSessionPool::default()), rows)).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0]["id"], json!(1));
+        assert_eq!(result[0]["total"], json!(25.0));
+        assert!(result[0].get("amt").is_none());
+        // Unparseable values become null instead of failing the query
+        assert_eq!(result[1]["id"], json!(3));
+        assert!(result[1]["price"].is_null());
+    }
+
+    #[test]
+    fn test_unknown_column_names_step() {
+        let steps = vec![TransformStep::Rename { column: "missing".to_string(), to: "x".to_string() }];
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let err = rt
+            .block_on(ResultTransformService::apply(&steps, &Arc::new(DataFusionSessionPool::default()), vec![json!({"id": 1})]))
+            .unwrap_err();
+        assert!(err.to_string().contains("step 1 (rename): unknown column 'missing'"));
+    }
+}
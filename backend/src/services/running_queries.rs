@@ -0,0 +1,149 @@
+// Running Query Registry
+//
+// Tracks the queries of the synchronous query endpoints while they execute,
+// keyed by the request id (`X-Request-Id`, which clients may choose), so a
+// second request can cancel them. Cancelling aborts the query's future; the
+// PostgreSQL and MySQL adapters then cancel the statement on the database
+// server as well (cancel request / `KILL QUERY`).
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use futures::future::{AbortHandle, Abortable};
+
+use crate::api::middleware::AppError;
+use crate::models::RunningQuery;
+
+struct RunningEntry {
+    query: RunningQuery,
+    abort: AbortHandle,
+    /// Tells this run apart from a later one reusing the request id
+    token: u64,
+}
+
+/// Synchronous queries running in this process
+#[derive(Default)]
+pub struct RunningQueryRegistry {
+    running: Mutex<HashMap<String, RunningEntry>>,
+    next_token: AtomicU64,
+}
+
+/// Removes a query from the registry when it ends, however it ends
+///
+/// A cancelled query's entry is removed right away, so its id may be taken by
+/// a new query before the cancelled one's future is dropped; only the entry
+/// this run registered is removed.
+struct Deregister<'a> {
+    registry: &'a RunningQueryRegistry,
+    id: &'a str,
+    token: u64,
+}
+
+impl Drop for Deregister<'_> {
+    fn drop(&mut self) {
+        let mut running = self.registry.running.lock().unwrap();
+        if running.get(self.id).is_some_and(|entry| entry.token == self.token) {
+            running.remove(self.id);
+        }
+    }
+}
+
+impl RunningQueryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `work` as the query `query.id`, returning `AppError::Cancelled` if it is cancelled
+    pub async fn run<T, F>(&self, query: RunningQuery, work: F) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, AppError>>,
+    {
+        let id = query.id.clone();
+        let (abort, registration) = AbortHandle::new_pair();
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut running = self.running.lock().unwrap();
+            if running.contains_key(&id) {
+                return Err(AppError::Conflict(format!(
+                    "A query with request id {} is already running; send a unique X-Request-Id",
+                    id
+                )));
+            }
+            running.insert(id.clone(), RunningEntry { query, abort, token });
+        }
+        let _deregister = Deregister { registry: self, id: &id, token };
+
+        match Abortable::new(work, registration).await {
+            Ok(result) => result,
+            Err(_) => Err(AppError::Cancelled(format!("Query {} was cancelled by request", id))),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<RunningQuery> {
+        self.running.lock().unwrap().get(id).map(|entry| entry.query.clone())
+    }
+
+    /// Abort a running query; `None` if it is not running in this process
+    pub fn cancel(&self, id: &str) -> Option<RunningQuery> {
+        let entry = self.running.lock().unwrap().remove(id)?;
+        entry.abort.abort();
+        tracing::info!(query_id = %id, connection_id = %entry.query.connection_id, "Cancelled running query");
+        Some(entry.query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_aborts_running_query() {
+        let registry = RunningQueryRegistry::new();
+        let query = RunningQuery::new("req-1".to_string(), "conn".to_string(), None, None);
+
+        let run = registry.run(query.clone(), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        let cancel = async {
+            while registry.get("req-1").is_none() {
+                tokio::task::yield_now().await;
+            }
+            // The id is taken while the query runs
+            assert!(registry.run(query.clone(), async { Ok(()) }).await.is_err());
+            registry.cancel("req-1")
+        };
+        let (outcome, cancelled) = tokio::join!(run, cancel);
+
+        assert!(matches!(outcome, Err(AppError::Cancelled(_))));
+        assert_eq!(cancelled.unwrap().connection_id, "conn");
+        assert!(registry.get("req-1").is_none());
+        assert!(registry.cancel("req-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_query_keeps_reused_id_registered() {
+        let registry = RunningQueryRegistry::new();
+        let query = RunningQuery::new("req-1".to_string(), "conn".to_string(), None, None);
+        let sleep = || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<_, AppError>(())
+        };
+
+        let mut cancelled = Box::pin(registry.run(query.clone(), sleep()));
+        assert!(futures::poll!(&mut cancelled).is_pending());
+        assert!(registry.cancel("req-1").is_some());
+
+        // A new query takes the id before the cancelled one is dropped
+        let mut reused = Box::pin(registry.run(query.clone(), sleep()));
+        assert!(futures::poll!(&mut reused).is_pending());
+        drop(cancelled);
+        assert!(registry.get("req-1").is_some());
+
+        drop(reused);
+        assert!(registry.get("req-1").is_none());
+    }
+}
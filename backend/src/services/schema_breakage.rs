@@ -0,0 +1,192 @@
+// Schema Breakage
+//
+// Saved queries keep working only while the tables and columns they use
+// exist. When a metadata refresh finds tables or columns removed (dropped, or
+// renamed, which a diff cannot tell apart), saved queries on the connection
+// that mention them are flagged broken with the reason, and the
+// `saved_query.broken` webhook event is raised for each, so dashboards built
+// on them don't fail silently. Mentions are matched on the identifier words
+// of the query text, like the query advisor does.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::api::middleware::AppError;
+use crate::models::{DatabaseConnection, DatabaseMetadata, MetadataDiff, WebhookEvent};
+use crate::services::query_advisor::identifiers;
+use crate::services::NotificationService;
+use crate::storage::SqliteStorage;
+
+pub struct SchemaBreakageService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl SchemaBreakageService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Flag the saved queries of `connection` that `diff` broke, returning
+    /// the number newly flagged
+    ///
+    /// `metadata` is the schema after the change. Queries already flagged are
+    /// left alone, so each breakage is reported once.
+    pub async fn flag_broken_queries(
+        &self,
+        connection: &DatabaseConnection,
+        diff: &MetadataDiff,
+        metadata: &DatabaseMetadata,
+    ) -> Result<usize, AppError> {
+        if diff.removed_tables.is_empty() && diff.changed_tables.iter().all(|table| table.removed_columns.is_empty()) {
+            return Ok(0);
+        }
+        let queries = self
+            .storage
+            .list_saved_queries_for_connection(&connection.id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let mut flagged = 0;
+        for query in queries.iter().filter(|query| !query.is_broken) {
+            let Some(reason) = breakage_reason(&query.query_text, diff, metadata) else {
+                continue;
+            };
+            let updated = self
+                .storage
+                .mark_saved_query_broken(&query.id, &reason)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            if !updated {
+                continue;
+            }
+            flagged += 1;
+            tracing::info!(
+                saved_query_id = %query.id,
+                connection_id = %connection.id,
+                "Saved query broken by a schema change: {}",
+                reason
+            );
+
+            let data = json!({
+                "saved_query_id": query.id,
+                "name": query.name,
+                "connection_id": connection.id,
+                "metadata_version": diff.to_version,
+                "reason": reason,
+            });
+            NotificationService::new(self.storage.clone())
+                .notify(Some(&query.domain_id), WebhookEvent::SavedQueryBroken, data)
+                .await;
+        }
+        Ok(flagged)
+    }
+}
+
+/// Why `sql` fails after `diff`, if it mentions a table or column the diff removed
+///
+/// A removed name still found in `metadata` (the table in another schema,
+/// the column in another table the query mentions) doesn't count, since the
+/// query may refer to that one.
+pub fn breakage_reason(sql: &str, diff: &MetadataDiff, metadata: &DatabaseMetadata) -> Option<String> {
+    let words: HashSet<String> = identifiers(sql).into_iter().collect();
+    let relations = metadata
+        .tables
+        .iter()
+        .map(|t| (t.name.to_lowercase(), &t.columns))
+        .chain(metadata.views.iter().map(|v| (v.name.to_lowercase(), &v.columns)));
+    let mut existing_relations = HashSet::new();
+    let mut mentioned_columns = HashSet::new();
+    for (name, columns) in relations {
+        if words.contains(&name) {
+            mentioned_columns.extend(columns.iter().map(|column| column.name.to_lowercase()));
+        }
+        existing_relations.insert(name);
+    }
+    let name_of = |qualified: &str| qualified.rsplit('.').next().unwrap_or(qualified).to_lowercase();
+
+    let mut reasons = Vec::new();
+    for qualified in &diff.removed_tables {
+        let name = name_of(qualified);
+        if words.contains(&name) && !existing_relations.contains(&name) {
+            reasons.push(format!("Table {} was dropped or renamed", qualified));
+        }
+    }
+    for table in &diff.changed_tables {
+        if !words.contains(&name_of(&table.name)) {
+            continue;
+        }
+        for column in &table.removed_columns {
+            let name = column.name.to_lowercase();
+            if words.contains(&name) && !mentioned_columns.contains(&name) {
+                reasons.push(format!("Column {}.{} was dropped or renamed", table.name, column.name));
+            }
+        }
+    }
+
+    (!reasons.is_empty()).then(|| reasons.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Column, Table};
+
+    fn table(name: &str, columns: &[&str]) -> Table {
+        Table {
+            name: name.to_string(),
+            schema: Some("public".to_string()),
+            columns: columns
+                .iter()
+                .map(|column| Column {
+                    name: column.to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: true,
+                    is_primary_key: false,
+                    is_foreign_key: false,
+                    default_value: None,
+                    max_length: None,
+                    description: None,
+                    distinct_count: None,
+                    tags: Vec::new(),
+                })
+                .collect(),
+            row_count: None,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_breakage_reason() {
+        let metadata = |tables: Vec<Table>| DatabaseMetadata::new("c".to_string(), tables, vec![], vec!["public".to_string()]);
+        let before = metadata(vec![
+            table("orders", &["id", "status", "customer_id"]),
+            table("customers", &["id", "name"]),
+            table("refunds", &["id"]),
+        ]);
+        // status renamed to state, customers.name dropped, refunds dropped
+        let after = metadata(vec![
+            table("orders", &["id", "state", "customer_id"]),
+            table("customers", &["id"]),
+        ]);
+        let diff = MetadataDiff::between(&before, &after);
+
+        assert_eq!(
+            breakage_reason("SELECT o.status FROM public.orders o", &diff, &after).as_deref(),
+            Some("Column public.orders.status was dropped or renamed")
+        );
+        assert_eq!(
+            breakage_reason("SELECT * FROM Refunds r JOIN orders o ON o.id = r.id", &diff, &after).as_deref(),
+            Some("Table public.refunds was dropped or renamed")
+        );
+        // Unaffected columns, and removed names only inside literals
+        assert_eq!(breakage_reason("SELECT id, state FROM orders WHERE state <> 'status'", &diff, &after), None);
+        // `id` is gone from no table the query mentions
+        assert_eq!(breakage_reason("SELECT c.id FROM customers c", &diff, &after), None);
+        assert!(breakage_reason("SELECT name FROM customers", &diff, &after).is_some());
+    }
+}
@@ -0,0 +1,119 @@
+// Slow Query Service
+//
+// History entries whose execution time reaches the domain's
+// `slow_query_threshold_ms` are flagged as slow when they are recorded. The
+// report groups the flagged runs by their stored SQL fingerprint, so runs that
+// only differ in literal values count as one query, and ranks the groups by
+// total or average execution time.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::api::middleware::AppError;
+use crate::models::{SlowQueryReport, SlowQuerySort};
+use crate::services::DomainSettingsService;
+use crate::storage::SqliteStorage;
+
+pub struct SlowQueryService {
+    storage: Arc<SqliteStorage>,
+}
+
+impl SlowQueryService {
+    pub fn new(storage: Arc<SqliteStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// The `top` slowest query shapes of a domain over the last `days` days
+    pub async fn report(
+        &self,
+        domain_id: &str,
+        days: i64,
+        top: usize,
+        sort: SlowQuerySort,
+    ) -> Result<SlowQueryReport, AppError> {
+        let settings = DomainSettingsService::new(self.storage.clone()).get(domain_id).await?;
+        let until = Utc::now();
+        let since = until - Duration::days(days);
+        let queries = self
+            .storage
+            .slow_query_groups(domain_id, since, until, sort, top)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(SlowQueryReport {
+            domain_id: domain_id.to_string(),
+            since,
+            until,
+            threshold_ms: settings.slow_query_threshold_ms,
+            sort,
+            queries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DomainSettings, QueryHistory};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_slow_queries_grouped_by_fingerprint() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+            let mut settings = DomainSettings::defaults("default-domain-id".to_string());
+            settings.slow_query_threshold_ms = 500;
+            storage.save_domain_settings(&settings).await.unwrap();
+            let connection = crate::models::DatabaseConnection::new(
+                None,
+                "postgresql://localhost/test".to_string(),
+                "postgresql".to_string(),
+                Some("default-domain-id".to_string()),
+            );
+            storage.save_connection(&connection).await.unwrap();
+
+            let runs = [
+                ("SELECT * FROM orders WHERE customer_id = 1", 900),
+                ("SELECT * FROM orders WHERE customer_id = 2", 700),
+                ("select * from orders where customer_id = 3", 400),
+                ("SELECT * FROM refunds", 1500),
+            ];
+            for (sql, execution_time_ms) in runs {
+                let history = QueryHistory::new(
+                    "default-domain-id".to_string(),
+                    connection.id.clone(),
+                    sql.to_string(),
+                    10,
+                    execution_time_ms,
+                    false,
+                );
+                storage.add_query_history(&history).await.unwrap();
+            }
+
+            let service = SlowQueryService::new(storage);
+            let report = service
+                .report("default-domain-id", 1, 10, SlowQuerySort::Total)
+                .await
+                .unwrap();
+            assert_eq!(report.threshold_ms, 500);
+            assert_eq!(report.queries.len(), 2);
+            let orders = &report.queries[0];
+            assert_eq!(orders.fingerprint, "SELECT * FROM orders WHERE customer_id = ?");
+            // The 400 ms run stayed below the threshold
+            assert_eq!(orders.executions, 2);
+            assert_eq!(orders.total_execution_time_ms, 1600);
+            assert_eq!(orders.max_execution_time_ms, 900);
+            assert_eq!(orders.example_query, "SELECT * FROM orders WHERE customer_id = 2");
+
+            let by_avg = service
+                .report("default-domain-id", 1, 1, SlowQuerySort::Avg)
+                .await
+                .unwrap();
+            assert_eq!(by_avg.queries.len(), 1);
+            assert_eq!(by_avg.queries[0].example_query, "SELECT * FROM refunds");
+        });
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::{Keyword, RESERVED_FOR_TABLE_ALIAS};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::models::{Column, Completion, CompletionContext, CompletionKind, DatabaseMetadata};
+
+/// Keywords and functions offered outside of identifier positions
+const KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "JOIN", "LEFT JOIN", "RIGHT JOIN", "INNER JOIN", "FULL JOIN", "CROSS JOIN", "ON",
+    "USING", "AND", "OR", "NOT", "IN", "EXISTS", "BETWEEN", "LIKE", "IS NULL", "IS NOT NULL", "AS", "DISTINCT",
+    "GROUP BY", "ORDER BY", "HAVING", "LIMIT", "OFFSET", "ASC", "DESC", "UNION", "UNION ALL", "WITH", "CASE",
+    "WHEN", "THEN", "ELSE", "END", "COUNT", "SUM", "AVG", "MIN", "MAX",
+];
+
+/// Keywords followed by a table name
+const TABLE_KEYWORDS: &[Keyword] = &[Keyword::FROM, Keyword::JOIN, Keyword::INTO, Keyword::UPDATE, Keyword::TABLE];
+
+/// Keywords followed by a column or expression
+const COLUMN_KEYWORDS: &[Keyword] = &[
+    Keyword::SELECT,
+    Keyword::WHERE,
+    Keyword::AND,
+    Keyword::OR,
+    Keyword::NOT,
+    Keyword::ON,
+    Keyword::BY,
+    Keyword::HAVING,
+    Keyword::SET,
+    Keyword::DISTINCT,
+    Keyword::CASE,
+    Keyword::WHEN,
+    Keyword::THEN,
+    Keyword::ELSE,
+];
+
+/// Table referenced in FROM or JOIN
+#[derive(Debug, Clone, PartialEq)]
+struct TableRef {
+    schema: Option<String>,
+    name: String,
+    alias: Option<String>,
+}
+
+/// Suggestions for one cursor position
+#[derive(Debug)]
+pub struct Completions {
+    pub context: CompletionContext,
+    /// Partial identifier before the cursor that suggestions complete
+    pub prefix: String,
+    pub suggestions: Vec<Completion>,
+}
+
+/// Schema-aware completion of partial SQL
+///
+/// Works on tokens rather than the AST so that incomplete statements still
+/// complete. Aliases are resolved from the FROM and JOIN clauses of the whole
+/// text, so columns can be completed in a select list written before them.
+pub struct SqlCompleter<'a> {
+    metadata: Option<&'a DatabaseMetadata>,
+}
+
+impl<'a> SqlCompleter<'a> {
+    /// Completer over cached metadata; without metadata only keywords are offered
+    pub fn new(metadata: Option<&'a DatabaseMetadata>) -> Self {
+        Self { metadata }
+    }
+
+    /// Complete `sql` at a character offset (clamped to the end)
+    pub fn complete(&self, sql: &str, cursor: usize, limit: usize) -> Completions {
+        let cursor = sql.char_indices().nth(cursor).map(|(i, _)| i).unwrap_or(sql.len());
+        let before = &sql[..cursor];
+        let word_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_identifier_char(*c))
+            .last()
+            .map(|(i, _)| i)
+            .unwrap_or(cursor);
+        let prefix = &before[word_start..];
+
+        let (qualifier, context_end) = match before[..word_start].strip_suffix('.') {
+            Some(head) => {
+                let head = head.trim_end_matches(['"', '`']);
+                let start = head
+                    .char_indices()
+                    .rev()
+                    .take_while(|(_, c)| is_identifier_char(*c))
+                    .last()
+                    .map(|(i, _)| i)
+                    .unwrap_or(head.len());
+                (Some(&head[start..]).filter(|q| !q.is_empty()), start)
+            }
+            None => (None, word_start),
+        };
+
+        let tables = table_refs(&tokens(sql).or_else(|| tokens(before)).unwrap_or_default());
+        let context = match qualifier {
+            Some(_) => CompletionContext::Qualified,
+            None => tokens(&before[..context_end]).map(|t| context_of(&t)).unwrap_or(CompletionContext::Keyword),
+        };
+
+        let mut candidates = Vec::new();
+        match context {
+            CompletionContext::Qualified => {
+                let qualifier = qualifier.unwrap_or_default();
+                match self.resolve(qualifier, &tables) {
+                    Some(columns) => candidates.extend(columns.iter().map(column_completion)),
+                    None => candidates.extend(self.relations(Some(qualifier))),
+                }
+            }
+            CompletionContext::Column => {
+                let scoped: Vec<&Column> = if tables.is_empty() {
+                    self.all_columns()
+                } else {
+                    tables.iter().filter_map(|t| self.columns(t.schema.as_deref(), &t.name)).flatten().collect()
+                };
+                candidates.extend(scoped.into_iter().map(column_completion));
+                candidates.extend(keywords(prefix));
+            }
+            CompletionContext::Table => {
+                candidates.extend(self.relations(None));
+                candidates.extend(self.schemas());
+            }
+            CompletionContext::Keyword => candidates.extend(keywords(prefix)),
+        }
+
+        let needle = prefix.to_lowercase();
+        let mut seen = HashSet::new();
+        let suggestions = candidates
+            .into_iter()
+            .filter(|c| c.label.to_lowercase().starts_with(&needle))
+            .filter(|c| seen.insert((c.label.clone(), c.kind)))
+            .take(limit)
+            .collect();
+
+        Completions { context, prefix: prefix.to_string(), suggestions }
+    }
+
+    /// Columns of the table an alias or table name refers to
+    fn resolve(&self, qualifier: &str, tables: &[TableRef]) -> Option<Vec<&'a Column>> {
+        let matches = |name: &Option<String>| name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(qualifier));
+        let table = tables
+            .iter()
+            .find(|t| matches(&t.alias))
+            .or_else(|| tables.iter().find(|t| t.alias.is_none() && t.name.eq_ignore_ascii_case(qualifier)));
+        match table {
+            Some(table) => self.columns(table.schema.as_deref(), &table.name).map(|c| c.iter().collect()),
+            None => self.columns(None, qualifier).map(|c| c.iter().collect()),
+        }
+    }
+
+    fn columns(&self, schema: Option<&str>, name: &str) -> Option<&'a [Column]> {
+        let metadata = self.metadata?;
+        let schema_matches = |s: &Option<String>| match (schema, s) {
+            (Some(wanted), Some(s)) => wanted.eq_ignore_ascii_case(s),
+            _ => true,
+        };
+        metadata
+            .tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name) && schema_matches(&t.schema))
+            .map(|t| t.columns.as_slice())
+            .or_else(|| {
+                metadata
+                    .views
+                    .iter()
+                    .find(|v| v.name.eq_ignore_ascii_case(name) && schema_matches(&v.schema))
+                    .map(|v| v.columns.as_slice())
+            })
+    }
+
+    fn all_columns(&self) -> Vec<&'a Column> {
+        let Some(metadata) = self.metadata else {
+            return Vec::new();
+        };
+        let mut columns: Vec<&Column> = metadata
+            .tables
+            .iter()
+            .flat_map(|t| &t.columns)
+            .chain(metadata.views.iter().flat_map(|v| &v.columns))
+            .collect();
+        columns.sort_by(|a, b| a.name.cmp(&b.name));
+        columns
+    }
+
+    /// Tables and views, optionally only those of one schema
+    fn relations(&self, schema: Option<&str>) -> Vec<Completion> {
+        let Some(metadata) = self.metadata else {
+            return Vec::new();
+        };
+        let in_schema = |s: &Option<String>| schema.map_or(true, |wanted| s.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(wanted)));
+        let tables = metadata.tables.iter().filter(|t| in_schema(&t.schema)).map(|t| (&t.name, &t.schema, CompletionKind::Table));
+        let views = metadata.views.iter().filter(|v| in_schema(&v.schema)).map(|v| (&v.name, &v.schema, CompletionKind::View));
+        let mut relations: Vec<Completion> = tables
+            .chain(views)
+            .map(|(name, schema, kind)| Completion { label: name.clone(), kind, detail: schema.clone() })
+            .collect();
+        relations.sort_by(|a, b| a.label.cmp(&b.label));
+        relations
+    }
+
+    fn schemas(&self) -> Vec<Completion> {
+        self.metadata
+            .map(|m| m.schemas.iter())
+            .into_iter()
+            .flatten()
+            .map(|schema| Completion { label: schema.clone(), kind: CompletionKind::Schema, detail: None })
+            .collect()
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn column_completion(column: &Column) -> Completion {
+    Completion {
+        label: column.name.clone(),
+        kind: CompletionKind::Column,
+        detail: Some(column.data_type.clone()),
+    }
+}
+
+/// Keywords in the case the user is typing in
+fn keywords(prefix: &str) -> impl Iterator<Item = Completion> {
+    let lowercase = !prefix.is_empty() && !prefix.chars().any(char::is_uppercase);
+    KEYWORDS.iter().map(move |keyword| Completion {
+        label: if lowercase { keyword.to_lowercase() } else { keyword.to_string() },
+        kind: CompletionKind::Keyword,
+        detail: None,
+    })
+}
+
+/// Significant tokens, or `None` if the text cannot be tokenized (e.g. an open string)
+fn tokens(sql: &str) -> Option<Vec<Token>> {
+    let tokens = Tokenizer::new(&GenericDialect {}, sql).tokenize().ok()?;
+    Some(tokens.into_iter().filter(|t| !matches!(t, Token::Whitespace(_))).collect())
+}
+
+fn keyword_of(token: &Token) -> Option<Keyword> {
+    match token {
+        Token::Word(word) if word.quote_style.is_none() => Some(word.keyword),
+        _ => None,
+    }
+}
+
+/// What the position after `tokens` expects
+fn context_of(tokens: &[Token]) -> CompletionContext {
+    let Some(last) = tokens.last() else {
+        return CompletionContext::Keyword;
+    };
+    match last {
+        Token::Comma => tokens
+            .iter()
+            .rev()
+            .find_map(|t| match keyword_of(t) {
+                Some(k) if TABLE_KEYWORDS.contains(&k) => Some(CompletionContext::Table),
+                Some(k) if COLUMN_KEYWORDS.contains(&k) => Some(CompletionContext::Column),
+                _ => None,
+            })
+            .unwrap_or(CompletionContext::Column),
+        Token::LParen
+        | Token::Eq
+        | Token::Neq
+        | Token::Lt
+        | Token::Gt
+        | Token::LtEq
+        | Token::GtEq
+        | Token::Plus
+        | Token::Minus
+        | Token::Mul
+        | Token::Div => CompletionContext::Column,
+        token => match keyword_of(token) {
+            Some(k) if TABLE_KEYWORDS.contains(&k) => CompletionContext::Table,
+            Some(k) if COLUMN_KEYWORDS.contains(&k) => CompletionContext::Column,
+            _ => CompletionContext::Keyword,
+        },
+    }
+}
+
+/// Tables named after FROM (comma-separated) and JOIN, with their aliases
+fn table_refs(tokens: &[Token]) -> Vec<TableRef> {
+    let mut refs = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let keyword = keyword_of(&tokens[i]);
+        i += 1;
+        if !matches!(keyword, Some(Keyword::FROM | Keyword::JOIN | Keyword::UPDATE | Keyword::INTO)) {
+            continue;
+        }
+        loop {
+            let mut parts = Vec::new();
+            while let Some(Token::Word(word)) = tokens.get(i) {
+                parts.push(word.value.clone());
+                i += 1;
+                if tokens.get(i) != Some(&Token::Period) {
+                    break;
+                }
+                i += 1;
+            }
+            let Some(name) = parts.pop() else {
+                break;
+            };
+            if keyword_of(tokens.get(i).unwrap_or(&Token::EOF)) == Some(Keyword::AS) {
+                i += 1;
+            }
+            let alias = match tokens.get(i) {
+                Some(Token::Word(word)) if word.quote_style.is_some() || !RESERVED_FOR_TABLE_ALIAS.contains(&word.keyword) => {
+                    i += 1;
+                    Some(word.value.clone())
+                }
+                _ => None,
+            };
+            refs.push(TableRef { schema: parts.pop(), name, alias });
+
+            if keyword == Some(Keyword::FROM) && tokens.get(i) == Some(&Token::Comma) {
+                i += 1;
+                continue;
+            }
+            break;
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Table;
+
+    fn metadata() -> DatabaseMetadata {
+        let column = |name: &str, data_type: &str| Column {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        };
+        let table = |name: &str, columns: Vec<Column>| Table {
+            name: name.to_string(),
+            schema: Some("public".to_string()),
+            columns,
+            row_count: None,
+            size_bytes: None,
+            description: None,
+            tags: Vec::new(),
+            engine_metadata: None,
+        };
+        DatabaseMetadata {
+            id: "m1".to_string(),
+            connection_id: "c1".to_string(),
+            tables: vec![
+                table("orders", vec![column("id", "integer"), column("user_id", "integer"), column("total", "numeric")]),
+                table("users", vec![column("id", "integer"), column("email", "text")]),
+            ],
+            views: vec![],
+            schemas: vec!["public".to_string()],
+            metadata_json: String::new(),
+            retrieved_at: chrono::Utc::now(),
+            version: 1,
+        }
+    }
+
+    fn labels(completions: &Completions) -> Vec<&str> {
+        completions.suggestions.iter().map(|c| c.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_completes_tables_columns_and_aliases() {
+        let metadata = metadata();
+        let completer = SqlCompleter::new(Some(&metadata));
+
+        let tables = completer.complete("SELECT * FROM us", 16, 50);
+        assert_eq!(tables.context, CompletionContext::Table);
+        assert_eq!(tables.prefix, "us");
+        assert_eq!(labels(&tables), vec!["users"]);
+
+        // Alias defined after the cursor
+        let sql = "SELECT o. FROM public.orders o JOIN users AS u ON u.id = o.user_id";
+        let qualified = completer.complete(sql, 9, 50);
+        assert_eq!(qualified.context, CompletionContext::Qualified);
+        assert_eq!(labels(&qualified), vec!["id", "user_id", "total"]);
+        assert_eq!(qualified.suggestions[2].detail.as_deref(), Some("numeric"));
+
+        let sql = "SELECT id FROM users WHERE em";
+        let columns = completer.complete(sql, sql.len(), 50);
+        assert_eq!(columns.context, CompletionContext::Column);
+        assert_eq!(labels(&columns), vec!["email"]);
+    }
+
+    #[test]
+    fn test_keywords_without_metadata() {
+        let completer = SqlCompleter::new(None);
+        let sql = "SELECT id FROM users u gr";
+        let completions = completer.complete(sql, sql.len(), 50);
+        assert_eq!(completions.context, CompletionContext::Keyword);
+        assert_eq!(labels(&completions), vec!["group by"]);
+        assert!(completer.complete("SELECT 'unterminated", 8, 50).suggestions.iter().all(|c| c.kind == CompletionKind::Keyword));
+    }
+}
@@ -0,0 +1,121 @@
+// SQL Feedback Service
+//
+// Few-shot examples from feedback on generated SQL. Questions of a domain rated
+// up or corrected are kept with the SQL that answers them; a new question gets
+// the closest of them in its prompt, so the LLM learns the domain's tables and
+// conventions from earlier answers. Questions are compared with the local
+// embedding model, which needs no network and matches shared words.
+
+use std::sync::Arc;
+
+use crate::api::middleware::AppError;
+use crate::config::LlmConfig;
+use crate::models::{SqlExample, SqlFeedback};
+use crate::services::{cosine_similarity, local_embedding};
+use crate::storage::SqliteStorage;
+
+/// Newest examples of a domain compared with a question
+const EXAMPLE_CANDIDATES: usize = 500;
+
+/// Similarity below which an example is not given to the LLM
+const MIN_EXAMPLE_SIMILARITY: f32 = 0.3;
+
+pub struct SqlFeedbackService {
+    storage: Arc<SqliteStorage>,
+    max_examples: usize,
+}
+
+impl SqlFeedbackService {
+    pub fn new(storage: Arc<SqliteStorage>, config: &LlmConfig) -> Self {
+        Self {
+            storage,
+            max_examples: config.few_shot_examples,
+        }
+    }
+
+    /// Examples of the domain closest to `question`, most similar first
+    ///
+    /// Connections outside a domain have no examples.
+    pub async fn similar_examples(
+        &self,
+        domain_id: Option<&str>,
+        database_type: &str,
+        question: &str,
+    ) -> Result<Vec<SqlExample>, AppError> {
+        let Some(domain_id) = domain_id else {
+            return Ok(Vec::new());
+        };
+        if self.max_examples == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = self
+            .storage
+            .list_sql_examples(domain_id, database_type, EXAMPLE_CANDIDATES)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(rank_examples(question, &candidates, self.max_examples))
+    }
+}
+
+/// The `k` examples of `candidates` (newest first) most similar to `question`
+///
+/// Of feedback on the same question, only the newest counts.
+fn rank_examples(question: &str, candidates: &[SqlFeedback], k: usize) -> Vec<SqlExample> {
+    let query = local_embedding(question);
+    let mut seen: Vec<String> = Vec::new();
+    let mut scored: Vec<(f32, SqlExample)> = Vec::new();
+    for candidate in candidates {
+        let normalized = candidate.question.to_lowercase();
+        if seen.contains(&normalized) {
+            continue;
+        }
+        seen.push(normalized);
+        let Some(example) = candidate.example() else { continue };
+        let similarity = cosine_similarity(&query, &local_embedding(&example.question));
+        if similarity >= MIN_EXAMPLE_SIMILARITY {
+            scored.push((similarity, example));
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(k).map(|(_, example)| example).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FeedbackRating, SqlFeedbackRequest};
+
+    fn feedback(question: &str, sql: &str, rating: FeedbackRating) -> SqlFeedback {
+        SqlFeedback::new(
+            "d".to_string(),
+            "c".to_string(),
+            "postgresql".to_string(),
+            SqlFeedbackRequest {
+                question: question.to_string(),
+                generated_sql: sql.to_string(),
+                rating,
+                corrected_sql: None,
+                comment: None,
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn test_rank_examples() {
+        let candidates = vec![
+            feedback("orders per region last month", "SELECT 3", FeedbackRating::Up),
+            feedback("Orders per region", "SELECT 2", FeedbackRating::Up),
+            feedback("active users", "SELECT 4", FeedbackRating::Up),
+            feedback("orders per region", "SELECT 1", FeedbackRating::Up),
+            feedback("orders per customer", "SELECT 5", FeedbackRating::Down),
+        ];
+
+        let examples = rank_examples("orders per region", &candidates, 2);
+        let sql: Vec<&str> = examples.iter().map(|e| e.sql.as_str()).collect();
+        // The newest feedback on a question wins; unrelated and rated-down questions are left out
+        assert_eq!(sql, vec!["SELECT 2", "SELECT 3"]);
+        assert!(rank_examples("weather tomorrow", &candidates, 2).is_empty());
+    }
+}
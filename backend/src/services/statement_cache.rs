@@ -0,0 +1,158 @@
+// Prepared Statement Cache
+//
+// Per-pool bookkeeping for prepared statements of the PostgreSQL and MySQL
+// adapters. Statements are connection-bound, so each pooled connection keeps
+// its own statements (deadpool-postgres' statement cache, mysql_async's LRU
+// statement cache); this module caps how many a connection keeps and counts
+// hits, misses and evictions for the whole pool.
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+/// Connections tracked by [`StatementCache::touch`] before closed ones are forgotten
+const MAX_TRACKED_CONNECTIONS: usize = 256;
+
+/// Prepared statement cache of one connection pool
+pub struct StatementCache {
+    /// Statements kept per connection (0 disables caching)
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    /// SQL hashes prepared on each connection, most recently used first
+    /// (for drivers that manage the statements themselves)
+    connections: Mutex<HashMap<u32, VecDeque<u64>>>,
+}
+
+/// Outcome of looking up a statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementLookup {
+    Hit,
+    /// The statement was prepared; `evicted` statements made room for it
+    Miss { evicted: usize },
+}
+
+/// Hit rate and size of a pool's prepared statement cache
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StatementCacheStats {
+    /// Statements kept per pooled connection
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Hits over lookups (0.0 to 1.0)
+    pub hit_rate: f64,
+}
+
+impl StatementCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            connections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Cache key of a statement
+    pub fn key(sql: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        sql.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Count a lookup reported by the driver's own cache
+    pub fn record(&self, lookup: StatementLookup) {
+        match lookup {
+            StatementLookup::Hit => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            StatementLookup::Miss { evicted } => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Look up `sql` on connection `connection_id`, tracking the connection's
+    /// statements with the same LRU policy as the driver, and count the lookup
+    pub fn touch(&self, connection_id: u32, sql: &str) -> StatementLookup {
+        let key = Self::key(sql);
+        let mut connections = self.connections.lock().unwrap();
+        if connections.len() >= MAX_TRACKED_CONNECTIONS && !connections.contains_key(&connection_id) {
+            // Connection ids are not reused, so most of these belong to closed connections
+            connections.clear();
+        }
+        let keys = connections.entry(connection_id).or_default();
+
+        let lookup = match keys.iter().position(|k| *k == key) {
+            Some(position) => {
+                keys.remove(position);
+                keys.push_front(key);
+                StatementLookup::Hit
+            }
+            None => {
+                keys.push_front(key);
+                let evicted = keys.len().saturating_sub(self.capacity);
+                keys.truncate(self.capacity);
+                StatementLookup::Miss { evicted }
+            }
+        };
+        drop(connections);
+
+        self.record(lookup);
+        lookup
+    }
+
+    pub fn stats(&self) -> StatementCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        StatementCacheStats {
+            capacity: self.capacity,
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touch_tracks_lru_per_connection() {
+        let cache = StatementCache::new(2);
+        assert_eq!(cache.touch(1, "SELECT 1"), StatementLookup::Miss { evicted: 0 });
+        assert_eq!(cache.touch(1, "SELECT 1"), StatementLookup::Hit);
+        // Statements are connection-bound
+        assert_eq!(cache.touch(2, "SELECT 1"), StatementLookup::Miss { evicted: 0 });
+
+        assert_eq!(cache.touch(1, "SELECT 2"), StatementLookup::Miss { evicted: 0 });
+        assert_eq!(cache.touch(1, "SELECT 1"), StatementLookup::Hit);
+        // "SELECT 2" is the least recently used
+        assert_eq!(cache.touch(1, "SELECT 3"), StatementLookup::Miss { evicted: 1 });
+        assert_eq!(cache.touch(1, "SELECT 1"), StatementLookup::Hit);
+        assert_eq!(cache.touch(1, "SELECT 2"), StatementLookup::Miss { evicted: 1 });
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (3, 5, 2));
+        assert!((stats.hit_rate - 3.0 / 8.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,234 @@
+// Federated Sub-Query Cache
+//
+// Keeps the results of cross-database sub-queries, keyed by connection and
+// normalized sub-query SQL, for a TTL. Re-running a cross-database query whose
+// sub-queries did not change (e.g. while editing its join or projection) reads
+// the remote tables from here instead of the databases. Results are held as
+// Arrow IPC streams, which bound the cache by their encoded size.
+
+use crate::config::CrossDatabaseConfig;
+use crate::services::QueryResultCache;
+use chrono::{DateTime, Utc};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ipc::reader::StreamReader;
+use datafusion::arrow::ipc::writer::StreamWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Sub-query result as an Arrow IPC stream
+struct CachedSubQuery {
+    connection_id: String,
+    ipc: Vec<u8>,
+    cached_at: Instant,
+    /// Wall-clock time when cached (reported to clients)
+    cached_at_utc: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, CachedSubQuery>,
+    /// Sum of the entries' IPC sizes
+    bytes: usize,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.by_key.remove(key) {
+            self.bytes -= entry.ipc.len();
+        }
+    }
+}
+
+/// Cached sub-query result
+pub struct CachedBatches {
+    pub schema: SchemaRef,
+    pub batches: Vec<RecordBatch>,
+    pub cached_at: DateTime<Utc>,
+}
+
+/// TTL cache of federated sub-query results, evicting the oldest entries when full
+pub struct SubQueryResultCache {
+    entries: Mutex<Entries>,
+    ttl: Duration,
+    /// Total IPC bytes kept; larger results are not cached
+    max_bytes: usize,
+}
+
+impl SubQueryResultCache {
+    pub fn new(ttl_secs: u64, max_bytes: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            ttl: Duration::from_secs(ttl_secs),
+            max_bytes,
+        }
+    }
+
+    pub fn from_config(config: &CrossDatabaseConfig) -> Self {
+        Self::new(config.subquery_cache_ttl_secs, config.subquery_cache_max_bytes)
+    }
+
+    /// Cache key of a sub-query: its connection and SQL in canonical form
+    fn key(connection_id: &str, sql: &str) -> String {
+        QueryResultCache::generate_key(connection_id, &QueryResultCache::normalize_sql(sql))
+    }
+
+    /// Unexpired result of `sql` on a connection
+    pub fn get(&self, connection_id: &str, sql: &str) -> Option<CachedBatches> {
+        let key = Self::key(connection_id, sql);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.by_key.get(&key)?;
+        if entry.cached_at.elapsed() > self.ttl {
+            entries.remove(&key);
+            return None;
+        }
+
+        match decode(&entry.ipc) {
+            Ok((schema, batches)) => {
+                tracing::debug!(connection_id = %connection_id, "Sub-query served from cache");
+                Some(CachedBatches { schema, batches, cached_at: entry.cached_at_utc })
+            }
+            Err(e) => {
+                tracing::warn!("Discarding unreadable cached sub-query {}: {}", key, e);
+                entries.remove(&key);
+                None
+            }
+        }
+    }
+
+    /// Cache the result of `sql` on a connection
+    ///
+    /// Expired and then the oldest entries are evicted to make room.
+    pub fn put(&self, connection_id: &str, sql: &str, schema: &SchemaRef, batches: &[RecordBatch]) {
+        let ipc = match encode(schema, batches) {
+            Ok(ipc) => ipc,
+            Err(e) => {
+                tracing::warn!("Failed to encode sub-query result for the cache: {}", e);
+                return;
+            }
+        };
+        if ipc.len() > self.max_bytes {
+            tracing::debug!("Sub-query result of {} bytes exceeds the cache size", ipc.len());
+            return;
+        }
+
+        let key = Self::key(connection_id, sql);
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+
+        let expired: Vec<String> = entries
+            .by_key
+            .iter()
+            .filter(|(_, entry)| entry.cached_at.elapsed() > self.ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            entries.remove(key);
+        }
+        while entries.bytes + ipc.len() > self.max_bytes {
+            let Some(oldest) = entries
+                .by_key
+                .iter()
+                .min_by_key(|(_, entry)| entry.cached_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+
+        entries.bytes += ipc.len();
+        entries.by_key.insert(
+            key,
+            CachedSubQuery {
+                connection_id: connection_id.to_string(),
+                ipc,
+                cached_at: Instant::now(),
+                cached_at_utc: Utc::now(),
+            },
+        );
+    }
+
+    /// Drop all cached sub-query results of a connection
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate_connection(&self, connection_id: &str) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let keys: Vec<String> = entries
+            .by_key
+            .iter()
+            .filter(|(_, entry)| entry.connection_id == connection_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            entries.remove(key);
+        }
+        keys.len()
+    }
+}
+
+fn encode(schema: &SchemaRef, batches: &[RecordBatch]) -> Result<Vec<u8>, ArrowError> {
+    let mut writer = StreamWriter::try_new(Vec::new(), schema)?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    writer.into_inner()
+}
+
+fn decode(ipc: &[u8]) -> Result<(SchemaRef, Vec<RecordBatch>), ArrowError> {
+    let reader = StreamReader::try_new(std::io::Cursor::new(ipc), None)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::result_transform::rows_to_record_batch;
+    use serde_json::json;
+
+    fn batch() -> RecordBatch {
+        rows_to_record_batch(&[json!({"id": 1, "name": "Alice"}), json!({"id": 2, "name": "Bob"})]).unwrap()
+    }
+
+    #[test]
+    fn test_cached_result_round_trips_by_normalized_sql() {
+        let cache = SubQueryResultCache::new(60, 1024 * 1024);
+        let batch = batch();
+        cache.put("conn1", "SELECT * FROM users AS u", &batch.schema(), &[batch.clone()]);
+
+        let hit = cache.get("conn1", "select *\n  from users as u").unwrap();
+        assert_eq!(hit.schema, batch.schema());
+        assert_eq!(hit.batches, vec![batch]);
+        assert!(cache.get("conn2", "SELECT * FROM users AS u").is_none());
+        assert!(cache.get("conn1", "SELECT * FROM users AS u WHERE u.id = 1").is_none());
+
+        assert_eq!(cache.invalidate_connection("conn1"), 1);
+        assert!(cache.get("conn1", "SELECT * FROM users AS u").is_none());
+    }
+
+    #[test]
+    fn test_expired_and_oversized_results_are_not_served() {
+        let batch = batch();
+        let size = encode(&batch.schema(), &[batch.clone()]).unwrap().len();
+
+        let cache = SubQueryResultCache::new(0, size);
+        cache.put("conn1", "SELECT * FROM users", &batch.schema(), &[batch.clone()]);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get("conn1", "SELECT * FROM users").is_none());
+
+        // Room for one result: the older one is evicted, a larger one is skipped
+        let cache = SubQueryResultCache::new(60, size);
+        cache.put("conn1", "SELECT * FROM users", &batch.schema(), &[batch.clone()]);
+        cache.put("conn1", "SELECT * FROM accounts", &batch.schema(), &[batch.clone()]);
+        assert!(cache.get("conn1", "SELECT * FROM users").is_none());
+        assert!(cache.get("conn1", "SELECT * FROM accounts").is_some());
+        cache.put("conn1", "SELECT * FROM pairs", &batch.schema(), &[batch.clone(), batch.clone()]);
+        assert!(cache.get("conn1", "SELECT * FROM pairs").is_none());
+        assert!(cache.get("conn1", "SELECT * FROM accounts").is_some());
+    }
+}
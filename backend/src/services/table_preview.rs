@@ -0,0 +1,97 @@
+// Table Preview Service
+//
+// Row samples and exact row counts of a single table, read with queries
+// generated here instead of SQL written by users. Schema and table names are
+// quoted in the adapter's dialect, so they cannot change the statement.
+
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+use crate::services::database::adapter::QueryResult;
+use crate::services::database::DatabaseAdapter;
+
+/// Rows returned by a table sample unless the request asks otherwise
+pub const DEFAULT_SAMPLE_ROWS: u64 = 100;
+
+/// Longest schema or table name accepted in a preview path
+const MAX_IDENTIFIER_LENGTH: usize = 256;
+
+pub struct TablePreviewService;
+
+impl TablePreviewService {
+    /// First `limit` rows of `schema.table`, in the order the database returns them
+    pub async fn sample(
+        adapter: &dyn DatabaseAdapter,
+        schema: &str,
+        table: &str,
+        limit: u64,
+        timeout_secs: u64,
+    ) -> Result<QueryResult, AppError> {
+        let sql = format!("SELECT * FROM {}", Self::table_reference(adapter, schema, table)?);
+        let (sql, _) = adapter.apply_row_limit(&sql, limit)?;
+        adapter.execute_query(&sql, timeout_secs).await
+    }
+
+    /// Exact number of rows in `schema.table` (`COUNT(*)`)
+    pub async fn count(
+        adapter: &dyn DatabaseAdapter,
+        schema: &str,
+        table: &str,
+        timeout_secs: u64,
+    ) -> Result<u64, AppError> {
+        let sql = format!(
+            "SELECT COUNT(*) AS row_count FROM {}",
+            Self::table_reference(adapter, schema, table)?
+        );
+        let result = adapter.execute_query(&sql, timeout_secs).await?;
+        Self::count_from_rows(&result.rows)
+    }
+
+    /// Quoted `schema.table` in the adapter's dialect
+    pub(crate) fn table_reference(adapter: &dyn DatabaseAdapter, schema: &str, table: &str) -> Result<String, AppError> {
+        for (kind, name) in [("Schema", schema), ("Table", table)] {
+            if name.is_empty() || name.len() > MAX_IDENTIFIER_LENGTH || name.contains('\0') {
+                return Err(AppError::Validation(format!(
+                    "{} name must be 1 to {} characters without NUL bytes",
+                    kind, MAX_IDENTIFIER_LENGTH
+                )));
+            }
+        }
+        Ok(format!(
+            "{}.{}",
+            adapter.quote_identifier(schema),
+            adapter.quote_identifier(table)
+        ))
+    }
+
+    /// The count of a `COUNT(*)` result row
+    ///
+    /// Drivers return it as a number or, for wide integer types, as text.
+    fn count_from_rows(rows: &[Value]) -> Result<u64, AppError> {
+        let value = rows
+            .first()
+            .and_then(Value::as_object)
+            .and_then(|row| row.values().next());
+        let count = match value {
+            Some(Value::Number(n)) => n.as_u64(),
+            Some(Value::String(s)) => s.parse().ok(),
+            _ => None,
+        };
+        count.ok_or_else(|| AppError::Database(format!("Unexpected COUNT(*) result: {:?}", value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_count_from_rows() {
+        assert_eq!(TablePreviewService::count_from_rows(&[json!({"row_count": 42})]).unwrap(), 42);
+        // Doris and MySQL may send BIGINT counts as text
+        assert_eq!(TablePreviewService::count_from_rows(&[json!({"ROW_COUNT": "7"})]).unwrap(), 7);
+        assert!(TablePreviewService::count_from_rows(&[]).is_err());
+        assert!(TablePreviewService::count_from_rows(&[json!({"row_count": null})]).is_err());
+    }
+}
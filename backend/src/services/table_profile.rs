@@ -0,0 +1,338 @@
+// Table Profile Service
+//
+// Column statistics of a single table — null ratio, distinct count, min/max,
+// most frequent values and histogram buckets — computed by queries generated
+// here. Columns and their types come from the connection's cached metadata;
+// identifiers are quoted in the adapter's dialect and bucket bounds are
+// numbers formatted here, so nothing from the request reaches the SQL text.
+// One query reads the counts and extremes of all columns, then one query per
+// column reads its most frequent values and one per numeric column its
+// histogram.
+
+use std::time::Instant;
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+use crate::api::middleware::AppError;
+use crate::models::{
+    Column, ColumnKind, ColumnStatistics, DatabaseMetadata, HistogramBucket, TableProfile, ValueFrequency,
+};
+use crate::services::database::DatabaseAdapter;
+use crate::services::TablePreviewService;
+
+/// Most frequent values reported per column
+pub const PROFILE_TOP_VALUES: u64 = 10;
+
+/// Histogram buckets of a numeric column
+pub const PROFILE_HISTOGRAM_BUCKETS: usize = 10;
+
+/// Columns profiled per table; the rest are listed as skipped
+pub const MAX_PROFILE_COLUMNS: usize = 50;
+
+/// A table or view found in cached metadata
+pub struct ProfiledRelation<'a> {
+    pub schema: String,
+    pub name: String,
+    pub columns: &'a [Column],
+}
+
+pub struct TableProfileService;
+
+impl TableProfileService {
+    /// The table or view `table` of `metadata`, in `schema` when given
+    ///
+    /// Names match case-insensitively. Without a schema the name must be
+    /// unique across schemas.
+    pub fn find_relation<'a>(
+        metadata: &'a DatabaseMetadata,
+        schema: Option<&str>,
+        table: &str,
+    ) -> Result<ProfiledRelation<'a>, AppError> {
+        let relations = metadata
+            .tables
+            .iter()
+            .map(|t| (t.schema.as_deref(), t.name.as_str(), t.columns.as_slice()))
+            .chain(metadata.views.iter().map(|v| (v.schema.as_deref(), v.name.as_str(), v.columns.as_slice())));
+        let matches: Vec<_> = relations
+            .filter(|(relation_schema, name, _)| {
+                name.eq_ignore_ascii_case(table)
+                    && schema.is_none_or(|schema| relation_schema.is_some_and(|s| s.eq_ignore_ascii_case(schema)))
+            })
+            .collect();
+
+        match matches.as_slice() {
+            [] => Err(AppError::NotFound(format!(
+                "Table {}{} not found in the metadata of connection {}",
+                schema.map(|schema| format!("{}.", schema)).unwrap_or_default(),
+                table,
+                metadata.connection_id
+            ))),
+            [(relation_schema, name, columns)] => Ok(ProfiledRelation {
+                schema: relation_schema.unwrap_or_default().to_string(),
+                name: name.to_string(),
+                columns: *columns,
+            }),
+            _ => Err(AppError::Validation(format!(
+                "Table {} exists in several schemas; pass `schema` to pick one",
+                table
+            ))),
+        }
+    }
+
+    /// Profile the columns of `relation`
+    pub async fn profile(
+        adapter: &dyn DatabaseAdapter,
+        metadata: &DatabaseMetadata,
+        relation: &ProfiledRelation<'_>,
+        timeout_secs: u64,
+    ) -> Result<TableProfile, AppError> {
+        let start = Instant::now();
+        let reference = TablePreviewService::table_reference(adapter, &relation.schema, &relation.name)?;
+        let (profiled, skipped) = relation.columns.split_at(relation.columns.len().min(MAX_PROFILE_COLUMNS));
+        let quoted: Vec<(String, ColumnKind)> = profiled
+            .iter()
+            .map(|column| (adapter.quote_identifier(&column.name), ColumnKind::of(&column.data_type)))
+            .collect();
+
+        let summary = adapter.execute_query(&summary_sql(&reference, &quoted), timeout_secs).await?;
+        let summary = summary
+            .rows
+            .first()
+            .and_then(Value::as_object)
+            .ok_or_else(|| AppError::Database("Profiling query returned no row".to_string()))?;
+        let row_count = field(summary, "row_count").and_then(as_u64).unwrap_or(0);
+
+        let mut columns = Vec::with_capacity(profiled.len());
+        for (index, (column, (quoted_name, kind))) in profiled.iter().zip(&quoted).enumerate() {
+            let mut statistics = column_statistics(column, *kind, summary, index, row_count);
+
+            if kind.is_groupable() && statistics.null_count < row_count {
+                let (sql, _) = adapter.apply_row_limit(&top_values_sql(&reference, quoted_name), PROFILE_TOP_VALUES)?;
+                let result = adapter.execute_query(&sql, timeout_secs).await?;
+                statistics.top_values = result
+                    .rows
+                    .iter()
+                    .filter_map(Value::as_object)
+                    .map(|row| ValueFrequency {
+                        value: field(row, "top_value").cloned().unwrap_or(Value::Null),
+                        count: field(row, "frequency").and_then(as_u64).unwrap_or(0),
+                    })
+                    .collect();
+            }
+
+            if *kind == ColumnKind::Numeric {
+                let range = statistics.min.as_ref().and_then(as_f64).zip(statistics.max.as_ref().and_then(as_f64));
+                if let Some((min, max)) = range {
+                    let non_null = row_count - statistics.null_count;
+                    statistics.histogram =
+                        histogram(adapter, &reference, quoted_name, min, max, non_null, timeout_secs).await?;
+                }
+            }
+            columns.push(statistics);
+        }
+
+        Ok(TableProfile {
+            connection_id: metadata.connection_id.clone(),
+            schema: relation.schema.clone(),
+            table: relation.name.clone(),
+            metadata_version: metadata.version,
+            row_count,
+            columns,
+            skipped_columns: skipped.iter().map(|column| column.name.clone()).collect(),
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            profiled_at: Utc::now(),
+        })
+    }
+}
+
+/// Row count plus the non-null count, distinct count and extremes of every
+/// column, as far as its kind allows, in one statement
+///
+/// Aliases are `c{index}_{statistic}`, so column names never appear in them.
+fn summary_sql(reference: &str, columns: &[(String, ColumnKind)]) -> String {
+    let mut expressions = vec!["COUNT(*) AS row_count".to_string()];
+    for (index, (column, kind)) in columns.iter().enumerate() {
+        expressions.push(format!("COUNT({}) AS c{}_non_null", column, index));
+        if kind.is_groupable() {
+            expressions.push(format!("COUNT(DISTINCT {}) AS c{}_distinct", column, index));
+        }
+        if kind.is_ordered() {
+            expressions.push(format!("MIN({}) AS c{}_min", column, index));
+            expressions.push(format!("MAX({}) AS c{}_max", column, index));
+        }
+    }
+    format!("SELECT {} FROM {}", expressions.join(", "), reference)
+}
+
+fn top_values_sql(reference: &str, column: &str) -> String {
+    format!(
+        "SELECT {column} AS top_value, COUNT(*) AS frequency FROM {reference} WHERE {column} IS NOT NULL GROUP BY {column} ORDER BY frequency DESC"
+    )
+}
+
+/// Equal-width buckets between `min` and `max`, the last one closed
+fn bucket_bounds(min: f64, max: f64, buckets: usize) -> Vec<(f64, f64)> {
+    let width = (max - min) / buckets as f64;
+    (0..buckets)
+        .map(|i| {
+            let lower = min + width * i as f64;
+            let upper = if i + 1 == buckets { max } else { min + width * (i + 1) as f64 };
+            (lower, upper)
+        })
+        .collect()
+}
+
+fn histogram_sql(reference: &str, column: &str, bounds: &[(f64, f64)]) -> String {
+    let expressions: Vec<String> = bounds
+        .iter()
+        .enumerate()
+        .map(|(index, (lower, upper))| {
+            let upper_op = if index + 1 == bounds.len() { "<=" } else { "<" };
+            format!(
+                "SUM(CASE WHEN {column} >= {lower} AND {column} {upper_op} {upper} THEN 1 ELSE 0 END) AS b{index}"
+            )
+        })
+        .collect();
+    format!("SELECT {} FROM {}", expressions.join(", "), reference)
+}
+
+async fn histogram(
+    adapter: &dyn DatabaseAdapter,
+    reference: &str,
+    column: &str,
+    min: f64,
+    max: f64,
+    non_null: u64,
+    timeout_secs: u64,
+) -> Result<Vec<HistogramBucket>, AppError> {
+    if !min.is_finite() || !max.is_finite() || min > max {
+        return Ok(Vec::new());
+    }
+    // A single value fills a single bucket, no query needed
+    if min == max {
+        return Ok(vec![HistogramBucket { lower: min, upper: max, count: non_null }]);
+    }
+
+    let bounds = bucket_bounds(min, max, PROFILE_HISTOGRAM_BUCKETS);
+    let result = adapter.execute_query(&histogram_sql(reference, column, &bounds), timeout_secs).await?;
+    let row = result.rows.first().and_then(Value::as_object);
+    Ok(bounds
+        .into_iter()
+        .enumerate()
+        .map(|(index, (lower, upper))| HistogramBucket {
+            lower,
+            upper,
+            count: row.and_then(|row| field(row, &format!("b{}", index))).and_then(as_u64).unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Statistics of the column at `index` from the summary row
+fn column_statistics(
+    column: &Column,
+    kind: ColumnKind,
+    summary: &Map<String, Value>,
+    index: usize,
+    row_count: u64,
+) -> ColumnStatistics {
+    let statistic = |name: &str| field(summary, &format!("c{}_{}", index, name)).filter(|value| !value.is_null());
+    let non_null = statistic("non_null").and_then(as_u64).unwrap_or(0);
+    let null_count = row_count.saturating_sub(non_null);
+
+    ColumnStatistics {
+        name: column.name.clone(),
+        data_type: column.data_type.clone(),
+        kind,
+        null_count,
+        null_ratio: if row_count == 0 { 0.0 } else { null_count as f64 / row_count as f64 },
+        distinct_count: statistic("distinct").and_then(as_u64),
+        min: statistic("min").cloned(),
+        max: statistic("max").cloned(),
+        top_values: Vec::new(),
+        histogram: Vec::new(),
+        masked: false,
+    }
+}
+
+/// A field of a result row by alias, ignoring the case the database folded it to
+fn field<'a>(row: &'a Map<String, Value>, alias: &str) -> Option<&'a Value> {
+    row.get(alias)
+        .or_else(|| row.iter().find(|(key, _)| key.eq_ignore_ascii_case(alias)).map(|(_, value)| value))
+}
+
+/// Drivers return counts and sums as numbers or, for wide types, as text
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64().or_else(|| n.as_f64().map(|f| f as u64)),
+        Value::String(s) => s.parse::<u64>().ok().or_else(|| s.parse::<f64>().ok().map(|f| f as u64)),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_profiling_sql() {
+        let columns = vec![
+            ("\"amount\"".to_string(), ColumnKind::Numeric),
+            ("\"active\"".to_string(), ColumnKind::Boolean),
+            ("\"payload\"".to_string(), ColumnKind::Other),
+        ];
+        assert_eq!(
+            summary_sql("\"sales\".\"orders\"", &columns),
+            "SELECT COUNT(*) AS row_count, COUNT(\"amount\") AS c0_non_null, COUNT(DISTINCT \"amount\") AS c0_distinct, \
+             MIN(\"amount\") AS c0_min, MAX(\"amount\") AS c0_max, COUNT(\"active\") AS c1_non_null, \
+             COUNT(DISTINCT \"active\") AS c1_distinct, COUNT(\"payload\") AS c2_non_null FROM \"sales\".\"orders\""
+        );
+
+        let bounds = bucket_bounds(0.0, 10.0, 4);
+        assert_eq!(bounds, vec![(0.0, 2.5), (2.5, 5.0), (5.0, 7.5), (7.5, 10.0)]);
+        let sql = histogram_sql("t", "\"amount\"", &bounds[2..]);
+        assert_eq!(
+            sql,
+            "SELECT SUM(CASE WHEN \"amount\" >= 5 AND \"amount\" < 7.5 THEN 1 ELSE 0 END) AS b0, \
+             SUM(CASE WHEN \"amount\" >= 7.5 AND \"amount\" <= 10 THEN 1 ELSE 0 END) AS b1 FROM t"
+        );
+    }
+
+    #[test]
+    fn test_column_statistics_from_summary() {
+        let column = Column {
+            name: "amount".to_string(),
+            data_type: "numeric".to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_foreign_key: false,
+            default_value: None,
+            max_length: None,
+            description: None,
+            distinct_count: None,
+            tags: Vec::new(),
+        };
+        // MySQL sends BIGINT counts and DECIMAL extremes as text; PostgreSQL
+        // folds aliases to lowercase, Doris may not
+        let summary = json!({"row_count": 8, "C0_NON_NULL": "6", "c0_distinct": 4, "c0_min": "1.50", "c0_max": null});
+        let statistics = column_statistics(&column, ColumnKind::Numeric, summary.as_object().unwrap(), 0, 8);
+
+        assert_eq!(statistics.null_count, 2);
+        assert_eq!(statistics.null_ratio, 0.25);
+        assert_eq!(statistics.distinct_count, Some(4));
+        assert_eq!(statistics.min.as_ref().and_then(as_f64), Some(1.5));
+        assert_eq!(statistics.max, None);
+
+        let empty = column_statistics(&column, ColumnKind::Numeric, json!({}).as_object().unwrap(), 0, 0);
+        assert_eq!(empty.null_ratio, 0.0);
+    }
+}
@@ -0,0 +1,249 @@
+// Transaction Sessions
+//
+// Lets write-mode clients run several statements in one transaction over
+// plain HTTP. Opening a session takes a connection out of the pool and starts
+// a transaction on it; statements run on that connection until the session is
+// committed or rolled back. Sessions live in a process-wide registry and are
+// rolled back when idle past `TRANSACTION_IDLE_TIMEOUT_SECS`, so an abandoned
+// session cannot hold locks and a pooled connection forever.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::api::middleware::AppError;
+use crate::models::{TransactionSession, TransactionStatus, WriteResult};
+use crate::services::database::Transaction;
+use crate::services::QueryService;
+
+/// Most transaction sessions open at the same time
+pub const MAX_OPEN_TRANSACTIONS: usize = 64;
+
+/// A session and its transaction; `transaction` is taken when the session ends
+struct OpenSession {
+    session: TransactionSession,
+    transaction: Option<Box<dyn Transaction>>,
+}
+
+/// Transaction sessions open in this process
+pub struct TransactionRegistry {
+    idle_timeout_secs: u64,
+    /// Each session has its own lock, so its statements run one at a time
+    sessions: Mutex<HashMap<String, Arc<tokio::sync::Mutex<OpenSession>>>>,
+}
+
+impl TransactionRegistry {
+    pub fn new(idle_timeout_secs: u64) -> Self {
+        Self {
+            idle_timeout_secs: idle_timeout_secs.max(1),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a started transaction as a new session
+    pub fn open(
+        &self,
+        connection_id: String,
+        domain_id: Option<String>,
+        opened_by: Option<String>,
+        transaction: Box<dyn Transaction>,
+    ) -> Result<TransactionSession, AppError> {
+        let session = TransactionSession::new(connection_id, domain_id, opened_by, self.idle_timeout_secs);
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= MAX_OPEN_TRANSACTIONS {
+            // Dropping the transaction rolls it back
+            return Err(AppError::QuotaExceeded(format!(
+                "At most {} transaction sessions can be open at once",
+                MAX_OPEN_TRANSACTIONS
+            )));
+        }
+        sessions.insert(
+            session.id.clone(),
+            Arc::new(tokio::sync::Mutex::new(OpenSession {
+                session: session.clone(),
+                transaction: Some(transaction),
+            })),
+        );
+        tracing::info!(session_id = %session.id, connection_id = %session.connection_id, "Opened transaction session");
+        Ok(session)
+    }
+
+    fn entry(&self, id: &str) -> Result<Arc<tokio::sync::Mutex<OpenSession>>, AppError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Transaction session {} not found", id)))
+    }
+
+    /// Current state of an open session
+    pub async fn get(&self, id: &str) -> Result<TransactionSession, AppError> {
+        let entry = self.entry(id)?;
+        let open = entry.lock().await;
+        Self::ensure_open(id, &open)?;
+        Ok(open.session.clone())
+    }
+
+    /// Execute a write statement in a session
+    ///
+    /// A failed statement leaves the session open; on PostgreSQL the
+    /// transaction then only accepts a rollback.
+    pub async fn execute(
+        &self,
+        id: &str,
+        query_service: &QueryService,
+        statement: &str,
+        timeout_secs: u64,
+    ) -> Result<(TransactionSession, WriteResult), AppError> {
+        let entry = self.entry(id)?;
+        let mut open = entry.lock().await;
+        Self::ensure_open(id, &open)?;
+
+        let transaction = open.transaction.as_mut().expect("open sessions have a transaction");
+        let result = query_service
+            .execute_in_transaction(statement, transaction.as_mut(), timeout_secs)
+            .await;
+        let affected_rows = result.as_ref().map_or(0, |write| write.affected_rows);
+        open.session.record_statement(affected_rows, self.idle_timeout_secs);
+        Ok((open.session.clone(), result?))
+    }
+
+    /// Commit or roll back a session and close it
+    ///
+    /// The session is closed even if the commit fails; the database has then
+    /// rolled the transaction back.
+    pub async fn finish(&self, id: &str, commit: bool) -> Result<TransactionSession, AppError> {
+        let entry = self.entry(id)?;
+        let mut open = entry.lock().await;
+        Self::ensure_open(id, &open)?;
+
+        let transaction = open.transaction.take().expect("open sessions have a transaction");
+        self.sessions.lock().unwrap().remove(id);
+        let outcome = if commit {
+            transaction.commit().await
+        } else {
+            transaction.rollback().await
+        };
+
+        open.session.status = if commit && outcome.is_ok() {
+            TransactionStatus::Committed
+        } else {
+            TransactionStatus::RolledBack
+        };
+        open.session.last_used_at = Utc::now();
+        tracing::info!(session_id = %id, committed = commit && outcome.is_ok(), "Closed transaction session");
+        outcome?;
+        Ok(open.session.clone())
+    }
+
+    /// Roll back sessions idle past their deadline; returns how many were closed
+    ///
+    /// Sessions busy with a statement are skipped until the next run.
+    pub fn expire_idle(&self) -> usize {
+        let now = Utc::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired: Vec<String> = sessions
+            .iter()
+            .filter(|(_, entry)| entry.try_lock().is_ok_and(|open| open.session.is_expired(now)))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            if let Some(entry) = sessions.remove(id) {
+                if let Ok(mut open) = entry.try_lock() {
+                    // Dropping the transaction rolls it back
+                    open.transaction.take();
+                    open.session.status = TransactionStatus::RolledBack;
+                }
+                tracing::info!(session_id = %id, "Rolled back idle transaction session");
+            }
+        }
+        expired.len()
+    }
+
+    /// Roll back idle sessions in the background
+    pub fn spawn_reaper(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval_secs = (self.idle_timeout_secs / 4).clamp(1, 60);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                self.expire_idle();
+            }
+        })
+    }
+
+    fn ensure_open(id: &str, open: &OpenSession) -> Result<(), AppError> {
+        if open.transaction.is_none() {
+            return Err(AppError::NotFound(format!("Transaction session {} not found", id)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::PlaceholderStyle;
+    use serde_json::Value;
+
+    /// Transaction that reports one affected row per statement
+    struct FakeTransaction;
+
+    #[async_trait::async_trait]
+    impl Transaction for FakeTransaction {
+        async fn execute(&mut self, _sql: &str, _params: &[Value], _timeout_secs: u64) -> Result<u64, AppError> {
+            Ok(1)
+        }
+
+        async fn commit(self: Box<Self>) -> Result<(), AppError> {
+            Ok(())
+        }
+
+        async fn rollback(self: Box<Self>) -> Result<(), AppError> {
+            Ok(())
+        }
+
+        fn placeholder_style(&self) -> PlaceholderStyle {
+            PlaceholderStyle::Numbered
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_executes_and_commits() {
+        let registry = TransactionRegistry::new(300);
+        let session = registry
+            .open("conn".to_string(), None, None, Box::new(FakeTransaction))
+            .unwrap();
+        let query_service = QueryService::new();
+
+        let (state, write) = registry
+            .execute(&session.id, &query_service, "UPDATE t SET n = 1", 30)
+            .await
+            .unwrap();
+        assert_eq!(write.statement_kind, "UPDATE");
+        assert_eq!((state.statement_count, state.affected_rows), (1, 1));
+        // Only write statements run in a session
+        assert!(registry.execute(&session.id, &query_service, "COMMIT", 30).await.is_err());
+
+        let closed = registry.finish(&session.id, true).await.unwrap();
+        assert_eq!(closed.status, TransactionStatus::Committed);
+        assert!(registry.get(&session.id).await.is_err());
+        assert!(registry.finish(&session.id, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_expire() {
+        let registry = TransactionRegistry::new(300);
+        let session = registry
+            .open("conn".to_string(), None, None, Box::new(FakeTransaction))
+            .unwrap();
+        assert_eq!(registry.expire_idle(), 0);
+
+        registry.entry(&session.id).unwrap().lock().await.session.expires_at = Utc::now();
+        assert_eq!(registry.expire_idle(), 1);
+        assert!(registry.get(&session.id).await.is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// Trash Service
+//
+// Lists, restores and purges soft-deleted domains, connections and saved
+// queries. Items are purged permanently once the configured grace period has
+// elapsed, either on demand or by the background scheduler. Trashed domains
+// and saved queries keep their names until they are restored or purged.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::middleware::AppError;
+use crate::config::TrashConfig;
+use crate::models::{TrashItem, TrashResourceType};
+use crate::storage::SqliteStorage;
+
+/// Service for managing soft-deleted resources
+pub struct TrashService {
+    storage: Arc<SqliteStorage>,
+    config: TrashConfig,
+}
+
+impl TrashService {
+    pub fn new(storage: Arc<SqliteStorage>, config: TrashConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Grace period before purge, or None when purging is disabled
+    fn grace_period(&self) -> Option<chrono::Duration> {
+        match self.config.grace_period_days {
+            0 => None,
+            days => Some(chrono::Duration::days(days as i64)),
+        }
+    }
+
+    /// List trashed items, optionally filtered by resource type
+    pub async fn list(&self, resource_type: Option<TrashResourceType>) -> Result<Vec<TrashItem>, AppError> {
+        let grace_period = self.grace_period();
+        let items = self
+            .storage
+            .list_trash()
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .into_iter()
+            .filter(|item| resource_type.map_or(true, |t| item.resource_type == t))
+            .map(|mut item| {
+                item.purge_after = grace_period.map(|period| item.deleted_at + period);
+                item
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    /// Conflict for a name still held by a trashed item
+    pub fn name_held_by(item: &TrashItem) -> AppError {
+        AppError::Conflict(format!(
+            "The name '{}' is held by {} {} in the trash. Restore or purge it first",
+            item.name.as_deref().unwrap_or_default(),
+            item.resource_type.as_str(),
+            item.id
+        ))
+    }
+
+    /// Restore a trashed item
+    ///
+    /// Connections and saved queries can only be restored while their domain is
+    /// active, and saved queries only while their connections are.
+    pub async fn restore(&self, resource_type: TrashResourceType, id: &str) -> Result<(), AppError> {
+        let item = self
+            .storage
+            .get_trash_item(resource_type, id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("{} {} not found in trash", resource_type.as_str(), id)))?;
+
+        if let Some(domain_id) = &item.domain_id {
+            let domain_active = self
+                .storage
+                .get_domain(domain_id)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?
+                .is_some();
+
+            if !domain_active {
+                return Err(AppError::Validation(format!(
+                    "Domain {} is in the trash. Restore the domain first",
+                    domain_id
+                )));
+            }
+        }
+
+        if resource_type == TrashResourceType::SavedQuery {
+            let trashed_connections = self
+                .storage
+                .trashed_connections_of_saved_query(id)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            if !trashed_connections.is_empty() {
+                return Err(AppError::Validation(format!(
+                    "Connection {} is in the trash. Restore the connection first",
+                    trashed_connections.join(", ")
+                )));
+            }
+        }
+
+        self.storage
+            .restore_trash_item(resource_type, id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tracing::info!("Restored {} {} from trash", resource_type.as_str(), id);
+        Ok(())
+    }
+
+    /// Permanently delete a single trashed item
+    pub async fn purge(&self, resource_type: TrashResourceType, id: &str) -> Result<(), AppError> {
+        let purged = self
+            .storage
+            .purge_trash_item(resource_type, id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if !purged {
+            return Err(AppError::NotFound(format!(
+                "{} {} not found in trash",
+                resource_type.as_str(),
+                id
+            )));
+        }
+
+        tracing::info!("Purged {} {} from trash", resource_type.as_str(), id);
+        Ok(())
+    }
+
+    /// Permanently delete all items whose grace period has elapsed
+    pub async fn purge_expired(&self) -> Result<usize, AppError> {
+        let Some(grace_period) = self.grace_period() else {
+            return Ok(0);
+        };
+
+        let purged = self
+            .storage
+            .purge_trash_before(chrono::Utc::now() - grace_period)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if purged > 0 {
+            tracing::info!("Purged {} expired items from trash", purged);
+        }
+
+        Ok(purged)
+    }
+
+    /// Spawn a background task that purges expired trash on the configured interval
+    pub fn spawn_scheduler(self: Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        self.grace_period()?;
+
+        let interval_secs = self.config.purge_interval_secs.max(60);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.purge_expired().await {
+                    tracing::warn!("Trash purge failed: {}", e);
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_list_sets_purge_after() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+
+        let domain = crate::models::Domain::new("Trashed".to_string(), None).unwrap();
+        storage.create_domain(&domain).await.unwrap();
+        storage.delete_domain(&domain.id).await.unwrap();
+
+        let service = TrashService::new(
+            storage,
+            TrashConfig { grace_period_days: 7, purge_interval_secs: 3600 },
+        );
+
+        let items = service.list(Some(TrashResourceType::Domain)).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].purge_after, Some(items[0].deleted_at + chrono::Duration::days(7)));
+
+        assert!(service.list(Some(TrashResourceType::Connection)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_unknown_item() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let service = TrashService::new(
+            storage,
+            TrashConfig { grace_period_days: 0, purge_interval_secs: 3600 },
+        );
+
+        let result = service.restore(TrashResourceType::SavedQuery, "missing").await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(service.purge_expired().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_saved_query_restores_after_its_connection() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        storage.save_connection(&connection).await.unwrap();
+        let saved = crate::models::SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Revenue".to_string(),
+            "SELECT 1".to_string(),
+            None,
+        );
+        storage.save_query(&saved).await.unwrap();
+        storage.delete_saved_query(&saved.id).await.unwrap();
+        storage.delete_connection(&connection.id).await.unwrap();
+
+        // The trashed query keeps its name
+        let held = storage.trashed_saved_query_named("default-domain-id", "Revenue").await.unwrap().unwrap();
+        assert_eq!(held.id, saved.id);
+        assert!(matches!(TrashService::name_held_by(&held), AppError::Conflict(_)));
+        assert!(storage.trashed_saved_query_named("default-domain-id", "Other").await.unwrap().is_none());
+
+        let service = TrashService::new(
+            storage.clone(),
+            TrashConfig { grace_period_days: 0, purge_interval_secs: 3600 },
+        );
+        let result = service.restore(TrashResourceType::SavedQuery, &saved.id).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        service.restore(TrashResourceType::Connection, &connection.id).await.unwrap();
+        service.restore(TrashResourceType::SavedQuery, &saved.id).await.unwrap();
+        assert!(storage.get_saved_query(&saved.id).await.unwrap().is_some());
+    }
+}
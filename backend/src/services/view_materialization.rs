@@ -0,0 +1,407 @@
+// View Materialization Service
+//
+// Materializes saved query views: the view's result is written to a Parquet
+// snapshot (`<dir>/<saved_query_id>.parquet`) that cross-database queries read
+// instead of running the view's query on its connection, so dashboards hit
+// precomputed data. Snapshots are refreshed on demand or by the background
+// scheduler once their refresh interval has elapsed. Each refreshed snapshot
+// is checked against the saved query's result contract, if it declares one.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+
+use crate::api::middleware::AppError;
+use crate::config::MaterializationConfig;
+use crate::models::{ConnectionStatus, MaterializationStatus, SavedQuery, ViewMaterialization};
+use crate::services::database::{create_adapter, DatabaseAdapter, DatabaseType, ParquetSnapshotAdapter};
+use crate::services::datafusion::{DataFusionSessionPool, VirtualView};
+use crate::services::result_transform::rows_to_record_batch;
+use crate::services::{ConnectionPoolManager, ResultContractService, SubQueryResultCache};
+use crate::storage::SqliteStorage;
+
+/// Service for materializing saved query views as Parquet snapshots
+pub struct ViewMaterializationService {
+    storage: Arc<SqliteStorage>,
+    pool_manager: Arc<ConnectionPoolManager>,
+    /// Sub-query results read from a replaced snapshot are dropped from here
+    subquery_cache: Arc<SubQueryResultCache>,
+    config: MaterializationConfig,
+    /// Sessions snapshots are read in
+    sessions: Arc<DataFusionSessionPool>,
+    /// Saved queries whose snapshot is being refreshed
+    refreshing: Mutex<HashSet<String>>,
+}
+
+impl ViewMaterializationService {
+    pub fn new(
+        storage: Arc<SqliteStorage>,
+        pool_manager: Arc<ConnectionPoolManager>,
+        subquery_cache: Arc<SubQueryResultCache>,
+        config: MaterializationConfig,
+    ) -> Self {
+        Self {
+            storage,
+            pool_manager,
+            subquery_cache,
+            config,
+            sessions: Arc::new(DataFusionSessionPool::default()),
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Read snapshots in sessions of `sessions`
+    pub fn with_sessions(mut self, sessions: Arc<DataFusionSessionPool>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Parquet file of a saved query's snapshot
+    pub fn snapshot_path(&self, saved_query_id: &str) -> PathBuf {
+        Path::new(&self.config.dir).join(format!("{}.parquet", saved_query_id))
+    }
+
+    /// Adapter reading a saved query's snapshot
+    pub fn snapshot_adapter(&self, saved_query_id: &str) -> Box<dyn DatabaseAdapter> {
+        Box::new(ParquetSnapshotAdapter::new(self.snapshot_path(saved_query_id)).with_sessions(self.sessions.clone()))
+    }
+
+    /// Materialize a saved query view, or change the refresh interval of its materialization
+    ///
+    /// The snapshot is taken by the next refresh (right away by the scheduler
+    /// when an interval is set).
+    pub async fn materialize(
+        &self,
+        saved_query: &SavedQuery,
+        refresh_interval_secs: Option<u64>,
+    ) -> Result<ViewMaterialization, AppError> {
+        if VirtualView::from_saved_query("", saved_query).is_none() {
+            return Err(AppError::Validation(
+                "Only saved queries exposed as views (with a view_name) can be materialized".to_string(),
+            ));
+        }
+        if refresh_interval_secs == Some(0) {
+            return Err(AppError::Validation("refresh_interval_secs must be positive".to_string()));
+        }
+
+        let materialization = match self.get(&saved_query.id).await? {
+            Some(existing) => ViewMaterialization { refresh_interval_secs, ..existing },
+            None => ViewMaterialization::new(saved_query.id.clone(), saved_query.domain_id.clone(), refresh_interval_secs),
+        };
+        self.save(&materialization).await?;
+
+        tracing::info!("Materialized view of saved query {} (refresh interval: {:?}s)", saved_query.id, refresh_interval_secs);
+        Ok(materialization)
+    }
+
+    /// Materialization of a saved query
+    pub async fn get(&self, saved_query_id: &str) -> Result<Option<ViewMaterialization>, AppError> {
+        self.storage
+            .get_view_materialization(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Stop materializing a saved query view and delete its snapshot
+    ///
+    /// Returns false if the view was not materialized.
+    pub async fn dematerialize(&self, saved_query_id: &str) -> Result<bool, AppError> {
+        let deleted = self
+            .storage
+            .delete_view_materialization(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        match std::fs::remove_file(self.snapshot_path(saved_query_id)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to delete snapshot of saved query {}: {}", saved_query_id, e),
+        }
+        self.subquery_cache
+            .invalidate_connection(&VirtualView::snapshot_connection_id(saved_query_id));
+
+        if deleted {
+            tracing::info!("Stopped materializing saved query {}", saved_query_id);
+        }
+        Ok(deleted)
+    }
+
+    /// Take a new snapshot of a materialized view
+    ///
+    /// The refresh runs as its own task, so it completes (and its outcome is
+    /// recorded) even if the caller goes away. Queries keep reading the
+    /// previous snapshot until the new one is complete.
+    pub async fn refresh(self: &Arc<Self>, saved_query_id: &str) -> Result<ViewMaterialization, AppError> {
+        let service = self.clone();
+        let saved_query_id = saved_query_id.to_string();
+        tokio::spawn(async move { service.run_refresh(&saved_query_id).await })
+            .await
+            .map_err(|e| AppError::Internal(format!("Refresh task failed: {}", e)))?
+    }
+
+    async fn run_refresh(&self, saved_query_id: &str) -> Result<ViewMaterialization, AppError> {
+        let mut materialization = self
+            .get(saved_query_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Saved query {} is not materialized", saved_query_id)))?;
+
+        if !self.refreshing.lock().unwrap().insert(saved_query_id.to_string()) {
+            return Err(AppError::Conflict(format!(
+                "Snapshot of saved query {} is already being refreshed",
+                saved_query_id
+            )));
+        }
+
+        materialization.status = MaterializationStatus::Refreshing;
+        materialization.attempted_at = Some(chrono::Utc::now());
+        let outcome = match self.save(&materialization).await {
+            Ok(()) => self.take_snapshot(saved_query_id).await,
+            Err(e) => Err(e),
+        };
+        self.refreshing.lock().unwrap().remove(saved_query_id);
+
+        // The interval may have changed while the snapshot was taken
+        if let Some(current) = self.get(saved_query_id).await? {
+            materialization.refresh_interval_secs = current.refresh_interval_secs;
+        }
+        match &outcome {
+            Ok((row_count, size_bytes, schema)) => {
+                materialization.status = MaterializationStatus::Ready;
+                materialization.row_count = Some(*row_count);
+                materialization.size_bytes = Some(*size_bytes);
+                materialization.refreshed_at = Some(chrono::Utc::now());
+                materialization.last_error = None;
+                self.subquery_cache
+                    .invalidate_connection(&VirtualView::snapshot_connection_id(saved_query_id));
+                tracing::info!("Refreshed snapshot of saved query {} ({} rows)", saved_query_id, row_count);
+                // A drifted snapshot is still served; the check only reports it
+                if let Err(e) = ResultContractService::new(self.storage.clone()).check(saved_query_id, schema).await {
+                    tracing::warn!("Failed to check snapshot of saved query {} against its contract: {}", saved_query_id, e);
+                }
+            }
+            Err(e) => {
+                materialization.status = MaterializationStatus::Failed;
+                materialization.last_error = Some(e.to_string());
+                tracing::warn!("Failed to refresh snapshot of saved query {}: {}", saved_query_id, e);
+            }
+        }
+        self.save(&materialization).await?;
+
+        outcome.map(|_| materialization)
+    }
+
+    /// Run a view's query on its connection and write the result to its snapshot
+    ///
+    /// Returns the snapshot's row count, file size and schema.
+    async fn take_snapshot(&self, saved_query_id: &str) -> Result<(u64, u64, SchemaRef), AppError> {
+        let saved_query = self
+            .storage
+            .get_saved_query(saved_query_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Saved query {} not found", saved_query_id)))?;
+        let view = VirtualView::from_saved_query("", &saved_query)
+            .ok_or_else(|| AppError::Validation(format!("Saved query {} is no longer a view", saved_query_id)))?;
+
+        let connection = self
+            .storage
+            .get_connection(&view.connection_id)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Connection {} of a view not found", view.connection_id)))?;
+        if !matches!(connection.status, ConnectionStatus::Connected) {
+            return Err(AppError::Connection(format!(
+                "Connection {} of a view is not active (status: {:?})",
+                view.connection_id, connection.status
+            )));
+        }
+        let adapter = create_adapter(
+            DatabaseType::from_str(&connection.database_type)?,
+            &connection.connection_url,
+            connection.read_only,
+            self.pool_manager.clone(),
+        )
+        .await?;
+
+        let timeout_secs = self.config.refresh_timeout_secs;
+        let (schema, batches) = if adapter.supports_datafusion_execution() {
+            adapter.execute_datafusion_query(&view.query, timeout_secs).await?
+        } else {
+            let result = adapter.execute_query(&view.query, timeout_secs).await?;
+            let batch = rows_to_record_batch(&result.rows)?;
+            (batch.schema(), vec![batch])
+        };
+        let row_count = batches.iter().map(RecordBatch::num_rows).sum::<usize>() as u64;
+
+        let path = self.snapshot_path(saved_query_id);
+        let snapshot_schema = schema.clone();
+        let size_bytes = tokio::task::spawn_blocking(move || write_snapshot(&path, &snapshot_schema, &batches))
+            .await
+            .map_err(|e| AppError::Internal(format!("Snapshot task failed: {}", e)))??;
+
+        Ok((row_count, size_bytes, schema))
+    }
+
+    /// Refresh the materializations whose refresh interval has elapsed
+    ///
+    /// Returns the number of snapshots refreshed.
+    pub async fn refresh_due(self: &Arc<Self>) -> Result<usize, AppError> {
+        let now = chrono::Utc::now();
+        let due: Vec<String> = self
+            .storage
+            .list_view_materializations(None)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?
+            .into_iter()
+            .filter(|materialization| materialization.is_due(now))
+            .map(|materialization| materialization.saved_query_id)
+            .collect();
+
+        let mut refreshed = 0;
+        for saved_query_id in due {
+            if self.refreshing.lock().unwrap().contains(&saved_query_id) {
+                continue;
+            }
+            // Failures are recorded on the materialization and logged
+            if self.refresh(&saved_query_id).await.is_ok() {
+                refreshed += 1;
+            }
+        }
+        Ok(refreshed)
+    }
+
+    /// Spawn a background task that refreshes due snapshots on the configured interval
+    pub fn spawn_scheduler(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval_secs = self.config.check_interval_secs.max(10);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh_due().await {
+                    tracing::warn!("Scheduled snapshot refresh failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn save(&self, materialization: &ViewMaterialization) -> Result<(), AppError> {
+        self.storage
+            .save_view_materialization(materialization)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
+
+/// Write a snapshot next to `path` and move it into place, returning its size
+///
+/// Readers of the previous snapshot never see a partially written file.
+fn write_snapshot(path: &Path, schema: &SchemaRef, batches: &[RecordBatch]) -> Result<u64, AppError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| AppError::Internal(format!("Failed to create snapshot directory: {}", e)))?;
+    }
+
+    let partial = path.with_extension("parquet.partial");
+    let file = std::fs::File::create(&partial)
+        .map_err(|e| AppError::Internal(format!("Failed to create snapshot file: {}", e)))?;
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), None)
+        .map_err(|e| AppError::Internal(format!("Failed to create Parquet writer: {}", e)))?;
+    for batch in batches {
+        writer
+            .write(batch)
+            .map_err(|e| AppError::Internal(format!("Failed to write snapshot: {}", e)))?;
+    }
+    writer
+        .close()
+        .map_err(|e| AppError::Internal(format!("Failed to finalize snapshot: {}", e)))?;
+
+    std::fs::rename(&partial, path)
+        .map_err(|e| AppError::Internal(format!("Failed to replace snapshot: {}", e)))?;
+    let size = std::fs::metadata(path)
+        .map_err(|e| AppError::Internal(format!("Failed to read snapshot size: {}", e)))?
+        .len();
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_write_snapshot_replaces_previous_snapshot() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("snapshots").join("query-1.parquet");
+
+        let first = rows_to_record_batch(&[json!({"region": "eu", "revenue": 10})]).unwrap();
+        write_snapshot(&path, &first.schema(), &[first.clone()]).unwrap();
+        let second = rows_to_record_batch(&[
+            json!({"region": "eu", "revenue": 12}),
+            json!({"region": "us", "revenue": 30}),
+        ])
+        .unwrap();
+        let size = write_snapshot(&path, &second.schema(), &[second]).unwrap();
+
+        assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+        assert!(!path.with_extension("parquet.partial").exists());
+
+        let result = ParquetSnapshotAdapter::new(path)
+            .execute_query("SELECT region FROM __snapshot ORDER BY region", 10)
+            .await
+            .unwrap();
+        assert_eq!(result.rows, vec![json!({"region": "eu"}), json!({"region": "us"})]);
+    }
+
+    #[tokio::test]
+    async fn test_materialize_requires_a_view() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(SqliteStorage::new(dir.path().join("test.db")).await.unwrap());
+        let service = ViewMaterializationService::new(
+            storage.clone(),
+            Arc::new(ConnectionPoolManager::new()),
+            Arc::new(SubQueryResultCache::new(60, 1024)),
+            MaterializationConfig {
+                dir: dir.path().join("snapshots").to_string_lossy().to_string(),
+                check_interval_secs: 60,
+                refresh_timeout_secs: 60,
+            },
+        );
+
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        storage.save_connection(&connection).await.unwrap();
+        let saved = SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Revenue".to_string(),
+            "SELECT region, SUM(amount) AS revenue FROM orders GROUP BY region".to_string(),
+            None,
+        );
+        storage.save_query(&saved).await.unwrap();
+        assert!(matches!(service.materialize(&saved, None).await, Err(AppError::Validation(_))));
+
+        let view = saved.with_view_name(Some("revenue".to_string()));
+        assert!(matches!(service.materialize(&view, Some(0)).await, Err(AppError::Validation(_))));
+        let materialization = service.materialize(&view, Some(3600)).await.unwrap();
+        assert_eq!(materialization.status, MaterializationStatus::Pending);
+        assert!(!materialization.has_snapshot());
+
+        // Changing the interval keeps the materialization's state
+        let updated = service.materialize(&view, None).await.unwrap();
+        assert_eq!(updated.refresh_interval_secs, None);
+        assert_eq!(updated.created_at, materialization.created_at);
+
+        assert!(service.dematerialize(&view.id).await.unwrap());
+        assert!(!service.dematerialize(&view.id).await.unwrap());
+    }
+}
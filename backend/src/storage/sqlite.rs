@@ -3,6 +3,9 @@ use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Connection ID under which usage statistics count cross-database queries
+pub const CROSS_DATABASE_USAGE_KEY: &str = "cross-database";
+
 /// SQLite storage for metadata and connections
 /// Uses tokio::Mutex for async-friendly locking
 pub struct SqliteStorage {
@@ -122,7 +125,7 @@ impl SqliteStorage {
             CREATE TABLE IF NOT EXISTS saved_queries (
                 id TEXT PRIMARY KEY,
                 domain_id TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
+                connection_id TEXT,
                 name TEXT NOT NULL,
                 query_text TEXT NOT NULL,
                 description TEXT,
@@ -142,7 +145,7 @@ impl SqliteStorage {
             CREATE TABLE IF NOT EXISTS query_history (
                 id TEXT PRIMARY KEY,
                 domain_id TEXT NOT NULL,
-                connection_id TEXT NOT NULL,
+                connection_id TEXT,
                 query_text TEXT NOT NULL,
                 row_count INTEGER NOT NULL,
                 execution_time_ms INTEGER NOT NULL,
@@ -173,628 +176,5657 @@ impl SqliteStorage {
             [],
         )?;
 
-        Ok(())
-    }
-
-    /// Get a reference to the connection (for use in async contexts)
-    pub fn get_conn(&self) -> Arc<Mutex<Connection>> {
-        self.conn.clone()
-    }
-
-    /// Save a connection to the database
-    pub async fn save_connection(&self, conn: &crate::models::DatabaseConnection) -> SqliteResult<()> {
-        let db_conn = self.conn.lock().await;
-        db_conn.execute(
+        // Execution context captured with each history entry (for replay comparisons)
+        conn.execute(
             r#"
-            INSERT OR REPLACE INTO connections 
-            (id, name, connection_url, database_type, status, created_at, last_connected_at, metadata_cache_id)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            CREATE TABLE IF NOT EXISTS query_history_snapshots (
+                history_id TEXT PRIMARY KEY,
+                connection_id TEXT NOT NULL,
+                connection_version INTEGER NOT NULL,
+                connection_fingerprint TEXT NOT NULL,
+                database_type TEXT NOT NULL,
+                metadata_cache_id TEXT,
+                metadata_version INTEGER,
+                result_hash TEXT,
+                limit_applied INTEGER NOT NULL DEFAULT 0,
+                captured_at TEXT NOT NULL,
+                FOREIGN KEY (history_id) REFERENCES query_history(id) ON DELETE CASCADE
+            )
             "#,
-            rusqlite::params![
-                conn.id,
-                conn.name,
-                conn.connection_url,
-                conn.database_type,
-                format!("{:?}", conn.status).to_lowercase(),
-                conn.created_at.to_rfc3339(),
-                conn.last_connected_at.map(|d| d.to_rfc3339()),
-                conn.metadata_cache_id,
-            ],
+            [],
         )?;
-        Ok(())
-    }
 
-    /// Get a connection by ID
-    pub async fn get_connection(&self, id: &str) -> SqliteResult<Option<crate::models::DatabaseConnection>> {
-        let db_conn = self.conn.lock().await;
-        let mut stmt = db_conn.prepare(
-            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id FROM connections WHERE id = ?1"
+        // Results kept with history entries on request (first rows as JSON, or a Parquet file)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS query_history_results (
+                history_id TEXT PRIMARY KEY,
+                format TEXT NOT NULL,
+                rows TEXT,
+                parquet BLOB,
+                row_count INTEGER NOT NULL,
+                total_row_count INTEGER NOT NULL,
+                truncated INTEGER NOT NULL DEFAULT 0,
+                size_bytes INTEGER NOT NULL,
+                captured_at TEXT NOT NULL,
+                FOREIGN KEY (history_id) REFERENCES query_history(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
         )?;
 
-        let result = stmt.query_row(rusqlite::params![id], |row| {
-            Ok(crate::models::DatabaseConnection {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                connection_url: row.get(2)?,
-                database_type: row.get(3)?,
-                domain_id: row.get(4)?,
-                status: match row.get::<_, String>(5)?.as_str() {
-                    "connected" => crate::models::ConnectionStatus::Connected,
-                    "error" => crate::models::ConnectionStatus::Error,
-                    _ => crate::models::ConnectionStatus::Disconnected,
-                },
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                last_connected_at: row.get::<_, Option<String>>(7)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&chrono::Utc)),
-                metadata_cache_id: row.get(8)?,
-            })
-        });
+        // Hourly usage aggregates, maintained incrementally by add_query_history
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS query_usage_stats (
+                domain_id TEXT NOT NULL,
+                connection_id TEXT NOT NULL,
+                bucket_hour TEXT NOT NULL,
+                total_count INTEGER NOT NULL DEFAULT 0,
+                success_count INTEGER NOT NULL DEFAULT 0,
+                failed_count INTEGER NOT NULL DEFAULT 0,
+                llm_count INTEGER NOT NULL DEFAULT 0,
+                total_execution_time_ms INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (domain_id, connection_id, bucket_hour),
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
 
-        match result {
-            Ok(conn) => Ok(Some(conn)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
+        // Backfill aggregates from existing history the first time the table is created
+        let stats_rows: i64 = conn.query_row("SELECT COUNT(*) FROM query_usage_stats", [], |row| row.get(0))?;
+        if stats_rows == 0 {
+            conn.execute(
+                r#"
+                INSERT INTO query_usage_stats
+                (domain_id, connection_id, bucket_hour, total_count, success_count, failed_count, llm_count, total_execution_time_ms)
+                SELECT domain_id, COALESCE(connection_id, ?1), substr(executed_at, 1, 13),
+                       COUNT(*),
+                       SUM(CASE WHEN status = 'success' THEN 1 ELSE 0 END),
+                       SUM(CASE WHEN status = 'success' THEN 0 ELSE 1 END),
+                       SUM(is_llm_generated),
+                       SUM(execution_time_ms)
+                FROM query_history
+                GROUP BY domain_id, connection_id, substr(executed_at, 1, 13)
+                "#,
+                [CROSS_DATABASE_USAGE_KEY],
+            )?;
         }
-    }
 
-    /// List all connections
-    pub async fn list_connections(&self) -> SqliteResult<Vec<crate::models::DatabaseConnection>> {
-        let db_conn = self.conn.lock().await;
-        let mut stmt = db_conn.prepare(
-            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id FROM connections ORDER BY created_at DESC"
+        // Daily API usage rollups per domain (reports and chargeback)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS api_usage_daily (
+                domain_id TEXT NOT NULL,
+                day TEXT NOT NULL,
+                api_calls INTEGER NOT NULL DEFAULT 0,
+                api_errors INTEGER NOT NULL DEFAULT 0,
+                bytes_out INTEGER NOT NULL DEFAULT 0,
+                queries INTEGER NOT NULL DEFAULT 0,
+                failed_queries INTEGER NOT NULL DEFAULT 0,
+                rows_returned INTEGER NOT NULL DEFAULT 0,
+                llm_requests INTEGER NOT NULL DEFAULT 0,
+                llm_failures INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (domain_id, day),
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
         )?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(crate::models::DatabaseConnection {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                connection_url: row.get(2)?,
-                database_type: row.get(3)?,
-                domain_id: row.get(4)?,
-                status: match row.get::<_, String>(5)?.as_str() {
-                    "connected" => crate::models::ConnectionStatus::Connected,
-                    "error" => crate::models::ConnectionStatus::Error,
-                    _ => crate::models::ConnectionStatus::Disconnected,
-                },
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                last_connected_at: row.get::<_, Option<String>>(7)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&chrono::Utc)),
-                metadata_cache_id: row.get(8)?,
-            })
-        })?;
+        // Daily LLM requests and tokens per domain and principal (metering and budgets)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_usage_daily (
+                domain_id TEXT NOT NULL,
+                principal TEXT NOT NULL DEFAULT '',
+                day TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                failed_requests INTEGER NOT NULL DEFAULT 0,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (domain_id, day, principal),
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
 
-        let mut connections = Vec::new();
-        for row in rows {
-            connections.push(row?);
+        // Backfill query counts from existing history the first time the table is created
+        let daily_rows: i64 = conn.query_row("SELECT COUNT(*) FROM api_usage_daily", [], |row| row.get(0))?;
+        if daily_rows == 0 {
+            conn.execute(
+                r#"
+                INSERT INTO api_usage_daily (domain_id, day, queries, failed_queries, rows_returned)
+                SELECT domain_id, substr(executed_at, 1, 10),
+                       COUNT(*),
+                       SUM(CASE WHEN status = 'success' THEN 0 ELSE 1 END),
+                       SUM(row_count)
+                FROM query_history
+                GROUP BY domain_id, substr(executed_at, 1, 10)
+                "#,
+                [],
+            )?;
         }
-        Ok(connections)
-    }
-
-    /// Delete a connection
-    pub async fn delete_connection(&self, id: &str) -> SqliteResult<bool> {
-        let db_conn = self.conn.lock().await;
-        let rows_affected = db_conn.execute("DELETE FROM connections WHERE id = ?1", rusqlite::params![id])?;
-        Ok(rows_affected > 0)
-    }
 
-    /// Save metadata cache
-    pub async fn save_metadata_cache(&self, metadata: &crate::models::DatabaseMetadata) -> SqliteResult<()> {
-        let db_conn = self.conn.lock().await;
-        db_conn.execute(
+        // Per-domain query defaults and restrictions
+        conn.execute(
             r#"
-            INSERT OR REPLACE INTO metadata_cache 
-            (id, connection_id, metadata_json, retrieved_at, version)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            CREATE TABLE IF NOT EXISTS domain_settings (
+                domain_id TEXT PRIMARY KEY,
+                default_row_limit INTEGER NOT NULL,
+                default_timeout_secs INTEGER NOT NULL,
+                history_retention_days INTEGER,
+                allowed_database_types TEXT NOT NULL DEFAULT '[]',
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
             "#,
-            rusqlite::params![
-                metadata.id,
-                metadata.connection_id,
-                metadata.metadata_json,
-                metadata.retrieved_at.to_rfc3339(),
-                metadata.version,
-            ],
+            [],
         )?;
-        Ok(())
-    }
 
-    /// Get metadata cache by connection ID
-    pub async fn get_metadata_cache(&self, connection_id: &str) -> SqliteResult<Option<crate::models::DatabaseMetadata>> {
-        let db_conn = self.conn.lock().await;
-        let mut stmt = db_conn.prepare(
-            "SELECT id, connection_id, metadata_json, retrieved_at, version FROM metadata_cache WHERE connection_id = ?1 ORDER BY version DESC LIMIT 1"
+        // Semantic metrics definitions (entities, dimensions, measures, joins) as JSON
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS domain_metrics (
+                domain_id TEXT PRIMARY KEY,
+                definition TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
         )?;
 
-        let result = stmt.query_row(rusqlite::params![connection_id], |row| {
-            let metadata_json: String = row.get(2)?;
-            let json_value: serde_json::Value = serde_json::from_str(&metadata_json).unwrap_or_default();
-            
-            Ok(crate::models::DatabaseMetadata {
-                id: row.get(0)?,
-                connection_id: row.get(1)?,
-                tables: json_value["tables"].as_array()
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| serde_json::from_value(v.clone()).ok())
-                            .collect()
-                    })
-                    .unwrap_or_default(),
-                views: json_value["views"].as_array()
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| serde_json::from_value(v.clone()).ok())
-                            .collect()
-                    })
-                    .unwrap_or_default(),
-                schemas: json_value["schemas"].as_array()
-                    .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
-                    .unwrap_or_default(),
-                metadata_json,
-                retrieved_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                version: row.get(4)?,
-            })
-        });
-
-        match result {
-            Ok(metadata) => Ok(Some(metadata)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
-        }
-    }
-
-    // ==================== Domain Management ====================
-
-    /// Create a new domain
-    pub async fn create_domain(&self, domain: &crate::models::Domain) -> SqliteResult<()> {
-        let db_conn = self.conn.lock().await;
-        db_conn.execute(
+        // API keys (only SHA-256 hashes of the secrets are stored)
+        conn.execute(
             r#"
-            INSERT INTO domains (id, name, description, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            CREATE TABLE IF NOT EXISTS api_keys (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_prefix TEXT NOT NULL,
+                key_hash TEXT UNIQUE NOT NULL,
+                scope TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                revoked_at TEXT
+            )
             "#,
-            rusqlite::params![
-                domain.id,
-                domain.name,
-                domain.description,
-                domain.created_at.to_rfc3339(),
-                domain.updated_at.to_rfc3339(),
-            ],
+            [],
         )?;
-        Ok(())
-    }
 
-    /// Get a domain by ID
-    pub async fn get_domain(&self, id: &str) -> SqliteResult<Option<crate::models::Domain>> {
-        let db_conn = self.conn.lock().await;
-        let mut stmt = db_conn.prepare(
-            "SELECT id, name, description, created_at, updated_at FROM domains WHERE id = ?1"
+        // Share links of saved queries (only SHA-256 hashes of the tokens are
+        // stored), with the frozen result of snapshot shares
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS query_shares (
+                id TEXT PRIMARY KEY,
+                domain_id TEXT NOT NULL,
+                saved_query_id TEXT NOT NULL,
+                token_prefix TEXT NOT NULL,
+                token_hash TEXT UNIQUE NOT NULL,
+                parameters TEXT NOT NULL DEFAULT '{}',
+                snapshot TEXT,
+                snapshot_at TEXT,
+                created_by TEXT,
+                created_at TEXT NOT NULL,
+                expires_at TEXT,
+                revoked_at TEXT,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE,
+                FOREIGN KEY (saved_query_id) REFERENCES saved_queries(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_query_shares_saved_query ON query_shares(saved_query_id, created_at DESC)",
+            [],
         )?;
 
-        let result = stmt.query_row(rusqlite::params![id], |row| {
-            Ok(crate::models::Domain {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
-        });
+        // Per-user favorite connections and saved queries; `owner` is the
+        // principal's subject ('' while authentication is disabled)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS favorites (
+                owner TEXT NOT NULL,
+                resource_type TEXT NOT NULL,
+                resource_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (owner, resource_type, resource_id)
+            )
+            "#,
+            [],
+        )?;
 
-        match result {
-            Ok(domain) => Ok(Some(domain)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
-        }
-    }
+        // User accounts and per-domain role assignments
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS users (
+                id TEXT PRIMARY KEY,
+                username TEXT UNIQUE NOT NULL,
+                password_hash TEXT NOT NULL,
+                is_admin INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        )?;
 
-    /// List all domains with resource counts
-    pub async fn list_domains(&self) -> SqliteResult<Vec<crate::models::DomainResponse>> {
-        let db_conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS user_domain_roles (
+                user_id TEXT NOT NULL,
+                domain_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                assigned_at TEXT NOT NULL,
+                PRIMARY KEY (user_id, domain_id),
+                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
 
-        // Query to get domains with connection counts
-        let mut stmt = db_conn.prepare(
+        // Label-bound query policies (masking, quotas)
+        conn.execute(
             r#"
-            SELECT
-                d.id,
-                d.name,
-                d.description,
-                d.created_at,
-                d.updated_at,
-                COUNT(DISTINCT c.id) as connection_count
-            FROM domains d
-            LEFT JOIN connections c ON c.domain_id = d.id
-            GROUP BY d.id, d.name, d.description, d.created_at, d.updated_at
-            ORDER BY d.created_at DESC
-            "#
+            CREATE TABLE IF NOT EXISTS connection_policies (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                selector TEXT NOT NULL,
+                masked_columns TEXT NOT NULL DEFAULT '[]',
+                max_queries_per_hour INTEGER,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            [],
         )?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(crate::models::DomainResponse {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                connection_count: row.get::<_, i64>(5)? as usize,
-                // Note: saved_query_count and query_history_count will be 0 until we implement those tables
-                saved_query_count: 0,
-                query_history_count: 0,
-            })
-        })?;
+        // Declared result columns of saved queries (JSON array) and the
+        // differences the latest check found (JSON array of strings)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_query_contracts (
+                saved_query_id TEXT PRIMARY KEY,
+                domain_id TEXT NOT NULL,
+                columns TEXT NOT NULL,
+                drift TEXT NOT NULL DEFAULT '[]',
+                checked_at TEXT,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (saved_query_id) REFERENCES saved_queries(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
 
-        let mut domains = Vec::new();
-        for row in rows {
-            domains.push(row?);
+        // Asynchronous query jobs (results stored as a JSON array)
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS query_jobs (
+                id TEXT PRIMARY KEY,
+                connection_id TEXT NOT NULL,
+                domain_id TEXT,
+                query_text TEXT NOT NULL,
+                status TEXT NOT NULL,
+                submitted_by TEXT,
+                results TEXT,
+                row_count INTEGER,
+                execution_time_ms INTEGER,
+                limit_applied INTEGER NOT NULL DEFAULT 0,
+                error_message TEXT,
+                created_at TEXT NOT NULL,
+                started_at TEXT,
+                finished_at TEXT,
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        // Webhooks of domains and their delivery queue
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhooks (
+                id TEXT PRIMARY KEY,
+                domain_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                events TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id TEXT PRIMARY KEY,
+                webhook_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                delivered_at TEXT,
+                FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at)",
+            [],
+        )?;
+
+        // Persisted hot entries of the query result cache
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS query_cache_entries (
+                cache_key TEXT PRIMARY KEY,
+                connection_id TEXT NOT NULL,
+                result TEXT NOT NULL,
+                cached_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_query_cache_entries_connection ON query_cache_entries(connection_id)",
+            [],
+        )?;
+
+        // Parquet snapshots of materialized saved query views
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS view_materializations (
+                saved_query_id TEXT PRIMARY KEY,
+                domain_id TEXT NOT NULL,
+                refresh_interval_secs INTEGER,
+                status TEXT NOT NULL,
+                row_count INTEGER,
+                size_bytes INTEGER,
+                refreshed_at TEXT,
+                attempted_at TEXT,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (saved_query_id) REFERENCES saved_queries(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        // Natural language chat sessions and their turns
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS chat_sessions (
+                id TEXT PRIMARY KEY,
+                connection_id TEXT NOT NULL,
+                domain_id TEXT,
+                created_by TEXT,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS chat_turns (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                question TEXT NOT NULL,
+                generated_sql TEXT,
+                result_summary TEXT,
+                error_message TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES chat_sessions(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_chat_turns_session ON chat_turns(session_id, created_at)",
+            [],
+        )?;
+
+        // Ratings and corrections of generated SQL, the few-shot examples of a domain
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS sql_feedback (
+                id TEXT PRIMARY KEY,
+                domain_id TEXT NOT NULL,
+                connection_id TEXT,
+                database_type TEXT NOT NULL,
+                question TEXT NOT NULL,
+                generated_sql TEXT NOT NULL,
+                corrected_sql TEXT,
+                rating TEXT NOT NULL,
+                comment TEXT,
+                created_by TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE,
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE SET NULL
+            )
+            "#,
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sql_feedback_domain ON sql_feedback(domain_id, database_type, created_at)",
+            [],
+        )?;
+
+        // Prompts sent to the LLM gateway, as sent, with what was redacted from them
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_audit_log (
+                id TEXT PRIMARY KEY,
+                domain_id TEXT,
+                connection_id TEXT,
+                principal TEXT,
+                purpose TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                redactions TEXT NOT NULL DEFAULT '{}',
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (domain_id) REFERENCES domains(id) ON DELETE CASCADE,
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE SET NULL
+            )
+            "#,
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_llm_audit_log_domain ON llm_audit_log(domain_id, created_at)",
+            [],
+        )?;
+
+        // Data dictionary: descriptions and tags of tables and columns. Empty
+        // schema and column names stand for any schema and the table itself.
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS data_dictionary (
+                connection_id TEXT NOT NULL,
+                schema_name TEXT NOT NULL DEFAULT '',
+                table_name TEXT NOT NULL,
+                column_name TEXT NOT NULL DEFAULT '',
+                description TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                updated_by TEXT,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (connection_id, schema_name, table_name, column_name),
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        // Latest column profile per table, valid for the metadata version it
+        // was computed against
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS table_profiles (
+                connection_id TEXT NOT NULL,
+                schema_name TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                metadata_version INTEGER NOT NULL,
+                profile TEXT NOT NULL,
+                profiled_at TEXT NOT NULL,
+                PRIMARY KEY (connection_id, schema_name, table_name),
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+
+        // Free-form tags of connections, one row per tag so lists can filter on them
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS connection_tags (
+                connection_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (connection_id, tag),
+                FOREIGN KEY (connection_id) REFERENCES connections(id) ON DELETE CASCADE
+            )
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_connection_tags_tag ON connection_tags(tag)",
+            [],
+        )?;
+
+        // Read-only marker and labels (JSON object) of connections
+        Self::ensure_column(&conn, "connections", "read_only", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "connections", "labels", "TEXT NOT NULL DEFAULT '{}'")?;
+        // Opt-in for write statements (POST /api/connections/{id}/execute)
+        Self::ensure_column(&conn, "connections", "allow_writes", "INTEGER NOT NULL DEFAULT 0")?;
+        // Per-connection pool settings (JSON object)
+        Self::ensure_column(&conn, "connections", "pool_settings", "TEXT NOT NULL DEFAULT '{}'")?;
+
+        // Result transformation (JSON array of steps) of saved queries
+        Self::ensure_column(&conn, "saved_queries", "transform", "TEXT NOT NULL DEFAULT '[]'")?;
+        // Default bind parameter values (JSON object) of saved queries
+        Self::ensure_column(&conn, "saved_queries", "parameters", "TEXT NOT NULL DEFAULT '{}'")?;
+        // Name a saved query is exposed under as a view (`domain.view_name`) in cross-database queries
+        Self::ensure_column(&conn, "saved_queries", "view_name", "TEXT")?;
+        // Set when a schema change drops or renames a table or column the query uses
+        Self::ensure_column(&conn, "saved_queries", "is_broken", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "saved_queries", "breakage_reason", "TEXT")?;
+
+        // Caps on per-request row limit and timeout overrides of domains
+        Self::ensure_column(&conn, "domain_settings", "max_row_limit", "INTEGER NOT NULL DEFAULT 100000")?;
+        Self::ensure_column(&conn, "domain_settings", "max_timeout_secs", "INTEGER NOT NULL DEFAULT 3600")?;
+        Self::ensure_column(&conn, "domain_settings", "llm_monthly_token_budget", "INTEGER")?;
+        // Tables and columns kept out of LLM prompts (JSON array)
+        Self::ensure_column(&conn, "domain_settings", "llm_denied_identifiers", "TEXT NOT NULL DEFAULT '[]'")?;
+        Self::ensure_column(&conn, "domain_settings", "metadata_refresh_interval_secs", "INTEGER")?;
+        Self::ensure_column(&conn, "domain_settings", "slow_query_threshold_ms", "INTEGER NOT NULL DEFAULT 1000")?;
+
+        // Executing user of each history entry (added with multi-user support)
+        Self::ensure_column(&conn, "query_history", "executed_by", "TEXT")?;
+        // Read query or write statement (added with write mode)
+        Self::ensure_column(&conn, "query_history", "kind", "TEXT NOT NULL DEFAULT 'read'")?;
+        // Repair attempts of failing LLM-generated SQL
+        Self::ensure_column(&conn, "query_history", "auto_repaired", "INTEGER NOT NULL DEFAULT 0")?;
+        // Saved query an entry ran (POST .../queries/saved/{query_id}/execute)
+        Self::ensure_column(&conn, "query_history", "saved_query_id", "TEXT")?;
+        // Execution time reached the domain's slow query threshold
+        Self::ensure_column(&conn, "query_history", "is_slow", "INTEGER NOT NULL DEFAULT 0")?;
+        // SQL with its literals stripped, grouping runs of the same query shape
+        Self::ensure_column(&conn, "query_history", "fingerprint", "TEXT")?;
+        Self::backfill_history_fingerprints(&conn)?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_query_history_fingerprint ON query_history(domain_id, fingerprint)",
+            [],
+        )?;
+
+        // Connection fingerprints of snapshots once hashed the whole URL, password
+        // included; those are cleared and replays compare connection versions instead
+        Self::ensure_column(&conn, "query_history_snapshots", "fingerprint_scheme", "INTEGER NOT NULL DEFAULT 1")?;
+        conn.execute(
+            "UPDATE query_history_snapshots SET connection_fingerprint = '', fingerprint_scheme = 2 WHERE fingerprint_scheme < 2",
+            [],
+        )?;
+
+        // Progress of jobs that report it (cross-database INSERT ... SELECT)
+        Self::ensure_column(&conn, "query_jobs", "rows_processed", "INTEGER")?;
+
+        // Soft-delete support (added after initial release, so migrate existing tables)
+        for table in ["domains", "connections", "saved_queries"] {
+            Self::ensure_column(&conn, table, "deleted_at", "TEXT")?;
+            // Optimistic concurrency control
+            Self::ensure_column(&conn, table, "version", "INTEGER NOT NULL DEFAULT 1")?;
         }
-        Ok(domains)
+
+        // Cross-database queries: no single connection, their connections (JSON
+        // array) and aliases (JSON object) instead
+        for table in ["saved_queries", "query_history"] {
+            Self::make_column_nullable(&conn, table, "connection_id")?;
+            Self::ensure_column(&conn, table, "connection_ids", "TEXT")?;
+            Self::ensure_column(&conn, table, "database_aliases", "TEXT")?;
+        }
+
+        // Hash of the SQL and connection a share was created for; live shares
+        // stop running once the saved query no longer matches it
+        Self::ensure_column(&conn, "query_shares", "query_hash", "TEXT NOT NULL DEFAULT ''")?;
+
+        Ok(())
     }
 
-    /// Update an existing domain
-    pub async fn update_domain(&self, domain: &crate::models::Domain) -> SqliteResult<bool> {
+    /// Add a column to an existing table if it is not present yet
+    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> SqliteResult<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Compute the fingerprint of history entries recorded before the column existed
+    fn backfill_history_fingerprints(conn: &Connection) -> SqliteResult<()> {
+        let pending: Vec<(String, String)> = conn
+            .prepare("SELECT id, query_text FROM query_history WHERE fingerprint IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<_>>()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut update = tx.prepare("UPDATE query_history SET fingerprint = ?2 WHERE id = ?1")?;
+            for (id, query_text) in &pending {
+                update.execute(rusqlite::params![id, crate::validation::SqlFingerprint::of(query_text)])?;
+            }
+        }
+        tx.commit()?;
+        tracing::info!("Computed fingerprints of {} query history entries", pending.len());
+        Ok(())
+    }
+
+    /// Drop the NOT NULL constraint of a `<column> TEXT NOT NULL` column
+    ///
+    /// SQLite cannot alter columns, so the table is rebuilt from its stored
+    /// definition (with foreign key enforcement paused, so rows referencing it
+    /// are kept) and its indexes are recreated.
+    fn make_column_nullable(conn: &Connection, table: &str, column: &str) -> SqliteResult<()> {
+        let definition: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |row| row.get(0),
+        )?;
+        let not_null = format!("{} TEXT NOT NULL", column);
+        if !definition.contains(&not_null) {
+            return Ok(());
+        }
+
+        let indexes: Vec<String> = conn
+            .prepare("SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")?
+            .query_map([table], |row| row.get(0))?
+            .collect::<SqliteResult<_>>()?;
+        let rebuilt = format!("{}_rebuilt", table);
+        let create = definition
+            .replacen(table, &rebuilt, 1)
+            .replacen(&not_null, &format!("{} TEXT", column), 1);
+
+        conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+        let result = (|| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(&create, [])?;
+            tx.execute(&format!("INSERT INTO {} SELECT * FROM {}", rebuilt, table), [])?;
+            tx.execute(&format!("DROP TABLE {}", table), [])?;
+            tx.execute(&format!("ALTER TABLE {} RENAME TO {}", rebuilt, table), [])?;
+            for index in &indexes {
+                tx.execute(index, [])?;
+            }
+            tx.commit()
+        })();
+        conn.execute_batch("PRAGMA foreign_keys = ON")?;
+        result
+    }
+
+    /// Get a reference to the connection (for use in async contexts)
+    pub fn get_conn(&self) -> Arc<Mutex<Connection>> {
+        self.conn.clone()
+    }
+
+    /// Save a connection to the database
+    pub async fn save_connection(&self, conn: &crate::models::DatabaseConnection) -> SqliteResult<()> {
         let db_conn = self.conn.lock().await;
-        let rows_affected = db_conn.execute(
+        db_conn.execute(
             r#"
-            UPDATE domains
-            SET name = ?1, description = ?2, updated_at = ?3
-            WHERE id = ?4
+            INSERT OR REPLACE INTO connections 
+            (id, name, connection_url, database_type, status, created_at, last_connected_at, metadata_cache_id, version, domain_id, read_only, labels, allow_writes, pool_settings)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, COALESCE(?10, 'default-domain-id'), ?11, ?12, ?13, ?14)
             "#,
             rusqlite::params![
-                domain.name,
-                domain.description,
-                domain.updated_at.to_rfc3339(),
-                domain.id,
+                conn.id,
+                conn.name,
+                conn.connection_url,
+                conn.database_type,
+                format!("{:?}", conn.status).to_lowercase(),
+                conn.created_at.to_rfc3339(),
+                conn.last_connected_at.map(|d| d.to_rfc3339()),
+                conn.metadata_cache_id,
+                conn.version,
+                conn.domain_id,
+                conn.read_only,
+                serde_json::to_string(&conn.labels).unwrap_or_else(|_| "{}".to_string()),
+                conn.allow_writes,
+                serde_json::to_string(&conn.pool_settings).unwrap_or_else(|_| "{}".to_string()),
             ],
         )?;
-        Ok(rows_affected > 0)
+        Self::replace_connection_tags(&db_conn, &conn.id, &conn.tags)?;
+        Ok(())
     }
 
-    /// Delete a domain (CASCADE will delete associated connections)
-    pub async fn delete_domain(&self, id: &str) -> SqliteResult<bool> {
+    /// Store `tags` as the only tags of a connection
+    fn replace_connection_tags(db_conn: &Connection, connection_id: &str, tags: &[String]) -> SqliteResult<()> {
+        db_conn.execute("DELETE FROM connection_tags WHERE connection_id = ?1", rusqlite::params![connection_id])?;
+        for tag in tags {
+            db_conn.execute(
+                "INSERT OR IGNORE INTO connection_tags (connection_id, tag) VALUES (?1, ?2)",
+                rusqlite::params![connection_id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Update a connection's editable settings if its version still matches
+    ///
+    /// `conn.version` is the version the caller read; on success the stored
+    /// version is incremented. Returns false on a version mismatch or if the
+    /// connection does not exist.
+    pub async fn update_connection(&self, conn: &crate::models::DatabaseConnection) -> SqliteResult<bool> {
         let db_conn = self.conn.lock().await;
-        let rows_affected = db_conn.execute("DELETE FROM domains WHERE id = ?1", rusqlite::params![id])?;
+        let rows_affected = db_conn.execute(
+            r#"
+            UPDATE connections
+            SET name = ?1, connection_url = ?2, status = ?3, read_only = ?6, labels = ?7, allow_writes = ?8, pool_settings = ?9, version = version + 1
+            WHERE id = ?4 AND version = ?5 AND deleted_at IS NULL
+            "#,
+            rusqlite::params![
+                conn.name,
+                conn.connection_url,
+                format!("{:?}", conn.status).to_lowercase(),
+                conn.id,
+                conn.version,
+                conn.read_only,
+                serde_json::to_string(&conn.labels).unwrap_or_else(|_| "{}".to_string()),
+                conn.allow_writes,
+                serde_json::to_string(&conn.pool_settings).unwrap_or_else(|_| "{}".to_string()),
+            ],
+        )?;
+        if rows_affected > 0 {
+            Self::replace_connection_tags(&db_conn, &conn.id, &conn.tags)?;
+        }
         Ok(rows_affected > 0)
     }
 
-    /// Get connection count for a domain
-    pub async fn get_domain_connection_count(&self, domain_id: &str) -> SqliteResult<usize> {
+    /// Move a connection to another domain if its version still matches
+    ///
+    /// Increments the version like [`SqliteStorage::update_connection`].
+    /// Returns false on a version mismatch or if the connection does not exist.
+    pub async fn move_connection(&self, id: &str, version: i64, domain_id: &str) -> SqliteResult<bool> {
         let db_conn = self.conn.lock().await;
-        let count: i64 = db_conn.query_row(
-            "SELECT COUNT(*) FROM connections WHERE domain_id = ?1",
-            rusqlite::params![domain_id],
-            |row| row.get(0),
+        let rows_affected = db_conn.execute(
+            "UPDATE connections SET domain_id = ?3, version = version + 1 WHERE id = ?1 AND version = ?2 AND deleted_at IS NULL",
+            rusqlite::params![id, version, domain_id],
         )?;
-        Ok(count as usize)
+        Ok(rows_affected > 0)
     }
 
-    /// List connections for a specific domain
-    pub async fn list_connections_by_domain(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::DatabaseConnection>> {
+    /// Record the outcome of connecting to a connection's database
+    ///
+    /// A successful connect also sets `last_connected_at`. The version is not
+    /// incremented, as the status is not an editable setting. Returns the
+    /// previous status if it changed.
+    pub async fn set_connection_status(
+        &self,
+        id: &str,
+        status: &crate::models::ConnectionStatus,
+    ) -> SqliteResult<Option<crate::models::ConnectionStatus>> {
+        let db_conn = self.conn.lock().await;
+        let previous: String = match db_conn.query_row(
+            "SELECT status FROM connections WHERE id = ?1 AND deleted_at IS NULL",
+            [id],
+            |row| row.get(0),
+        ) {
+            Ok(status) => status,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let status_str = format!("{:?}", status).to_lowercase();
+        let connected_at = (*status == crate::models::ConnectionStatus::Connected).then(|| chrono::Utc::now().to_rfc3339());
+        db_conn.execute(
+            "UPDATE connections SET status = ?2, last_connected_at = COALESCE(?3, last_connected_at) WHERE id = ?1",
+            rusqlite::params![id, status_str, connected_at],
+        )?;
+        if previous == status_str {
+            return Ok(None);
+        }
+        Ok(Some(match previous.as_str() {
+            "connected" => crate::models::ConnectionStatus::Connected,
+            "error" => crate::models::ConnectionStatus::Error,
+            _ => crate::models::ConnectionStatus::Disconnected,
+        }))
+    }
+
+    /// Get a connection by ID
+    pub async fn get_connection(&self, id: &str) -> SqliteResult<Option<crate::models::DatabaseConnection>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id, version, read_only, labels, allow_writes, pool_settings, (SELECT json_group_array(tag) FROM (SELECT tag FROM connection_tags t WHERE t.connection_id = connections.id ORDER BY tag)) FROM connections WHERE id = ?1 AND deleted_at IS NULL"
+        )?;
+
+        let result = stmt.query_row(rusqlite::params![id], Self::row_to_connection);
+
+        match result {
+            Ok(conn) => Ok(Some(conn)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all connections
+    pub async fn list_connections(&self) -> SqliteResult<Vec<crate::models::DatabaseConnection>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id, version, read_only, labels, allow_writes, pool_settings, (SELECT json_group_array(tag) FROM (SELECT tag FROM connection_tags t WHERE t.connection_id = connections.id ORDER BY tag)) FROM connections WHERE deleted_at IS NULL ORDER BY created_at DESC"
+        )?;
+
+        let rows = stmt.query_map([], Self::row_to_connection)?;
+
+        let mut connections = Vec::new();
+        for row in rows {
+            connections.push(row?);
+        }
+        Ok(connections)
+    }
+
+    /// Delete a connection (soft delete - moves it to the trash)
+    pub async fn delete_connection(&self, id: &str) -> SqliteResult<bool> {
+        let db_conn = self.conn.lock().await;
+        let rows_affected = db_conn.execute(
+            "UPDATE connections SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Save a new metadata cache version for a connection
+    ///
+    /// The version is assigned here (latest + 1) so every refresh gets a new
+    /// version; only the newest `retained_versions` are kept (0 keeps all).
+    /// Returns the assigned version.
+    pub async fn save_metadata_cache(
+        &self,
+        metadata: &crate::models::DatabaseMetadata,
+        retained_versions: usize,
+    ) -> SqliteResult<i32> {
+        let db_conn = self.conn.lock().await;
+        let tx = db_conn.unchecked_transaction()?;
+
+        let version: i32 = tx.query_row(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM metadata_cache WHERE connection_id = ?1",
+            rusqlite::params![metadata.connection_id],
+            |row| row.get(0),
+        )?;
+
+        tx.execute(
+            r#"
+            INSERT OR REPLACE INTO metadata_cache 
+            (id, connection_id, metadata_json, retrieved_at, version)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            rusqlite::params![
+                metadata.id,
+                metadata.connection_id,
+                metadata.metadata_json,
+                metadata.retrieved_at.to_rfc3339(),
+                version,
+            ],
+        )?;
+
+        if retained_versions > 0 {
+            tx.execute(
+                "DELETE FROM metadata_cache WHERE connection_id = ?1 AND version <= ?2",
+                rusqlite::params![metadata.connection_id, version as i64 - retained_versions as i64],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(version)
+    }
+
+    /// Get the latest metadata cache by connection ID
+    pub async fn get_metadata_cache(&self, connection_id: &str) -> SqliteResult<Option<crate::models::DatabaseMetadata>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, connection_id, metadata_json, retrieved_at, version FROM metadata_cache WHERE connection_id = ?1 ORDER BY version DESC LIMIT 1"
+        )?;
+
+        match stmt.query_row(rusqlite::params![connection_id], Self::row_to_metadata) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get a specific retained metadata cache version
+    pub async fn get_metadata_cache_version(
+        &self,
+        connection_id: &str,
+        version: i32,
+    ) -> SqliteResult<Option<crate::models::DatabaseMetadata>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, connection_id, metadata_json, retrieved_at, version FROM metadata_cache WHERE connection_id = ?1 AND version = ?2"
+        )?;
+
+        match stmt.query_row(rusqlite::params![connection_id, version], Self::row_to_metadata) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List retained metadata versions for a connection (newest first)
+    pub async fn list_metadata_versions(
+        &self,
+        connection_id: &str,
+    ) -> SqliteResult<Vec<crate::models::MetadataVersionSummary>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, connection_id, metadata_json, retrieved_at, version FROM metadata_cache WHERE connection_id = ?1 ORDER BY version DESC"
+        )?;
+
+        let versions = stmt
+            .query_map(rusqlite::params![connection_id], Self::row_to_metadata)?
+            .map(|metadata| {
+                metadata.map(|m| crate::models::MetadataVersionSummary {
+                    id: m.id,
+                    version: m.version,
+                    retrieved_at: m.retrieved_at,
+                    table_count: m.tables.len(),
+                    view_count: m.views.len(),
+                })
+            })
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(versions)
+    }
+
+    fn row_to_metadata(row: &rusqlite::Row) -> rusqlite::Result<crate::models::DatabaseMetadata> {
+        let metadata_json: String = row.get(2)?;
+        let json_value: serde_json::Value = serde_json::from_str(&metadata_json).unwrap_or_default();
+
+        Ok(crate::models::DatabaseMetadata {
+            id: row.get(0)?,
+            connection_id: row.get(1)?,
+            tables: json_value["tables"].as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            views: json_value["views"].as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            schemas: json_value["schemas"].as_array()
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+                .unwrap_or_default(),
+            metadata_json,
+            retrieved_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            version: row.get(4)?,
+        })
+    }
+
+    // ==================== Domain Management ====================
+
+    /// Create a new domain
+    pub async fn create_domain(&self, domain: &crate::models::Domain) -> SqliteResult<()> {
+        let db_conn = self.conn.lock().await;
+        db_conn.execute(
+            r#"
+            INSERT INTO domains (id, name, description, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            rusqlite::params![
+                domain.id,
+                domain.name,
+                domain.description,
+                domain.created_at.to_rfc3339(),
+                domain.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a domain by ID
+    pub async fn get_domain(&self, id: &str) -> SqliteResult<Option<crate::models::Domain>> {
         let db_conn = self.conn.lock().await;
         let mut stmt = db_conn.prepare(
-            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id FROM connections WHERE domain_id = ?1 ORDER BY created_at DESC"
+            "SELECT id, name, description, created_at, updated_at, version FROM domains WHERE id = ?1 AND deleted_at IS NULL"
         )?;
 
-        let rows = stmt.query_map(rusqlite::params![domain_id], |row| {
-            Ok(crate::models::DatabaseConnection {
+        let result = stmt.query_row(rusqlite::params![id], |row| {
+            Ok(crate::models::Domain {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                connection_url: row.get(2)?,
-                database_type: row.get(3)?,
-                domain_id: row.get(4)?,
-                status: match row.get::<_, String>(5)?.as_str() {
-                    "connected" => crate::models::ConnectionStatus::Connected,
-                    "error" => crate::models::ConnectionStatus::Error,
-                    _ => crate::models::ConnectionStatus::Disconnected,
-                },
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                description: row.get(2)?,
+                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                version: row.get(5)?,
+            })
+        });
+
+        match result {
+            Ok(domain) => Ok(Some(domain)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all domains with resource counts
+    pub async fn list_domains(&self) -> SqliteResult<Vec<crate::models::DomainResponse>> {
+        let db_conn = self.conn.lock().await;
+
+        // Query to get domains with connection counts
+        let mut stmt = db_conn.prepare(
+            r#"
+            SELECT
+                d.id,
+                d.name,
+                d.description,
+                d.created_at,
+                d.updated_at,
+                d.version,
+                COUNT(DISTINCT c.id) as connection_count
+            FROM domains d
+            LEFT JOIN connections c ON c.domain_id = d.id AND c.deleted_at IS NULL
+            WHERE d.deleted_at IS NULL
+            GROUP BY d.id, d.name, d.description, d.created_at, d.updated_at, d.version
+            ORDER BY d.created_at DESC
+            "#
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(crate::models::DomainResponse {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
                     .unwrap()
                     .with_timezone(&chrono::Utc),
-                last_connected_at: row.get::<_, Option<String>>(7)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&chrono::Utc)),
-                metadata_cache_id: row.get(8)?,
+                version: row.get(5)?,
+                connection_count: row.get::<_, i64>(6)? as usize,
+                // Note: saved_query_count and query_history_count will be 0 until we implement those tables
+                saved_query_count: 0,
+                query_history_count: 0,
             })
         })?;
 
-        let mut connections = Vec::new();
-        for row in rows {
-            connections.push(row?);
-        }
-        Ok(connections)
-    }
+        let mut domains = Vec::new();
+        for row in rows {
+            domains.push(row?);
+        }
+        Ok(domains)
+    }
+
+    /// Update an existing domain if its version still matches
+    ///
+    /// `domain.version` is the version the caller read; on success the stored
+    /// version is incremented. Returns false on a version mismatch or if the
+    /// domain does not exist.
+    pub async fn update_domain(&self, domain: &crate::models::Domain) -> SqliteResult<bool> {
+        let db_conn = self.conn.lock().await;
+        let rows_affected = db_conn.execute(
+            r#"
+            UPDATE domains
+            SET name = ?1, description = ?2, updated_at = ?3, version = version + 1
+            WHERE id = ?4 AND version = ?5 AND deleted_at IS NULL
+            "#,
+            rusqlite::params![
+                domain.name,
+                domain.description,
+                domain.updated_at.to_rfc3339(),
+                domain.id,
+                domain.version,
+            ],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Delete a domain (soft delete - moves it to the trash)
+    ///
+    /// Active connections and saved queries of the domain are trashed with the
+    /// same timestamp so that restoring the domain brings them back as well.
+    pub async fn delete_domain(&self, id: &str) -> SqliteResult<bool> {
+        let db_conn = self.conn.lock().await;
+        let deleted_at = chrono::Utc::now().to_rfc3339();
+        let tx = db_conn.unchecked_transaction()?;
+
+        let rows_affected = tx.execute(
+            "UPDATE domains SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id, deleted_at],
+        )?;
+
+        if rows_affected > 0 {
+            tx.execute(
+                "UPDATE connections SET deleted_at = ?2 WHERE domain_id = ?1 AND deleted_at IS NULL",
+                rusqlite::params![id, deleted_at],
+            )?;
+            tx.execute(
+                "UPDATE saved_queries SET deleted_at = ?2 WHERE domain_id = ?1 AND deleted_at IS NULL",
+                rusqlite::params![id, deleted_at],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Get connection count for a domain
+    pub async fn get_domain_connection_count(&self, domain_id: &str) -> SqliteResult<usize> {
+        let db_conn = self.conn.lock().await;
+        let count: i64 = db_conn.query_row(
+            "SELECT COUNT(*) FROM connections WHERE domain_id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![domain_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// List connections for a specific domain
+    pub async fn list_connections_by_domain(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::DatabaseConnection>> {
+        let db_conn = self.conn.lock().await;
+        let mut stmt = db_conn.prepare(
+            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id, version, read_only, labels, allow_writes, pool_settings, (SELECT json_group_array(tag) FROM (SELECT tag FROM connection_tags t WHERE t.connection_id = connections.id ORDER BY tag)) FROM connections WHERE domain_id = ?1 AND deleted_at IS NULL ORDER BY created_at DESC"
+        )?;
+
+        let rows = stmt.query_map(rusqlite::params![domain_id], Self::row_to_connection)?;
+
+        let mut connections = Vec::new();
+        for row in rows {
+            connections.push(row?);
+        }
+        Ok(connections)
+    }
+
+    // ============================================================================
+    // Saved Query Operations (Domain-Scoped)
+    // ============================================================================
+
+    /// Save a query for a domain
+    pub async fn save_query(&self, query: &crate::models::SavedQuery) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO saved_queries (id, domain_id, connection_id, name, query_text, description, created_at, updated_at, transform, parameters, connection_ids, database_aliases, view_name)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            "#,
+            rusqlite::params![
+                query.id,
+                query.domain_id,
+                query.connection_id,
+                query.name,
+                query.query_text,
+                query.description,
+                query.created_at.to_rfc3339(),
+                query.updated_at.to_rfc3339(),
+                serde_json::to_string(&query.transform).unwrap_or_else(|_| "[]".to_string()),
+                serde_json::to_string(&query.parameters).unwrap_or_else(|_| "{}".to_string()),
+                Self::connection_ids_json(&query.connection_ids),
+                Self::database_aliases_json(query.database_aliases.as_ref()),
+                query.view_name,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a saved query by ID
+    pub async fn get_saved_query(&self, id: &str) -> SqliteResult<Option<crate::models::SavedQuery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at, version, transform, parameters, connection_ids, database_aliases, view_name, is_broken, breakage_reason
+             FROM saved_queries WHERE id = ?1 AND deleted_at IS NULL"
+        )?;
+
+        let result = stmt.query_row([id], Self::row_to_saved_query);
+
+        match result {
+            Ok(query) => Ok(Some(query)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all saved queries for a domain
+    pub async fn list_saved_queries(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::SavedQuery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at, version, transform, parameters, connection_ids, database_aliases, view_name, is_broken, breakage_reason
+             FROM saved_queries
+             WHERE domain_id = ?1 AND deleted_at IS NULL
+             ORDER BY created_at DESC"
+        )?;
+
+        let queries = stmt.query_map([domain_id], Self::row_to_saved_query)?;
+
+        queries.collect()
+    }
+
+    /// List the saved queries of a domain flagged as views
+    pub async fn list_saved_views(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::SavedQuery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at, version, transform, parameters, connection_ids, database_aliases, view_name, is_broken, breakage_reason
+             FROM saved_queries
+             WHERE domain_id = ?1 AND view_name IS NOT NULL AND deleted_at IS NULL
+             ORDER BY view_name"
+        )?;
+
+        let queries = stmt.query_map([domain_id], Self::row_to_saved_query)?;
+
+        queries.collect()
+    }
+
+    /// List the saved queries of every domain that run on a connection,
+    /// including cross-database queries using it
+    pub async fn list_saved_queries_for_connection(&self, connection_id: &str) -> SqliteResult<Vec<crate::models::SavedQuery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at, version, transform, parameters, connection_ids, database_aliases, view_name, is_broken, breakage_reason
+             FROM saved_queries
+             WHERE (connection_id = ?1 OR EXISTS (SELECT 1 FROM json_each(connection_ids) WHERE value = ?1)) AND deleted_at IS NULL
+             ORDER BY created_at DESC"
+        )?;
+
+        let queries = stmt.query_map([connection_id], Self::row_to_saved_query)?;
+
+        queries.collect()
+    }
+
+    /// Flag a saved query as broken by a schema change
+    ///
+    /// Leaves the version alone, since the query itself didn't change.
+    pub async fn mark_saved_query_broken(&self, id: &str, reason: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let updated = conn.execute(
+            "UPDATE saved_queries SET is_broken = 1, breakage_reason = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id, reason],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Update a saved query if its version still matches `expected_version`
+    ///
+    /// Returns false on a version mismatch or if the query does not exist.
+    pub async fn update_saved_query(
+        &self,
+        id: &str,
+        name: Option<String>,
+        query_text: Option<String>,
+        description: Option<String>,
+        transform: Option<&[crate::models::TransformStep]>,
+        parameters: Option<&crate::models::QueryParameters>,
+        view_name: Option<Option<&str>>,
+        expected_version: i64,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+
+        // Build dynamic UPDATE query based on which fields are provided
+        let mut updates = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(n) = name {
+            updates.push("name = ?");
+            params.push(Box::new(n));
+        }
+        if let Some(q) = query_text {
+            updates.push("query_text = ?");
+            params.push(Box::new(q));
+            // An edited query is no longer known to be broken
+            updates.push("is_broken = 0");
+            updates.push("breakage_reason = NULL");
+        }
+        if let Some(d) = description {
+            updates.push("description = ?");
+            params.push(Box::new(d));
+        }
+        if let Some(t) = transform {
+            updates.push("transform = ?");
+            params.push(Box::new(serde_json::to_string(t).unwrap_or_else(|_| "[]".to_string())));
+        }
+        if let Some(p) = parameters {
+            updates.push("parameters = ?");
+            params.push(Box::new(serde_json::to_string(p).unwrap_or_else(|_| "{}".to_string())));
+        }
+        if let Some(v) = view_name {
+            updates.push("view_name = ?");
+            params.push(Box::new(v.map(str::to_string)));
+        }
+
+        if updates.is_empty() {
+            // Nothing to update, but still report a stale version as a conflict
+            let current: Option<i64> = match conn.query_row(
+                "SELECT version FROM saved_queries WHERE id = ?1 AND deleted_at IS NULL",
+                [id],
+                |row| row.get(0),
+            ) {
+                Ok(version) => Some(version),
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(e) => return Err(e),
+            };
+            return Ok(current == Some(expected_version));
+        }
+
+        updates.push("updated_at = ?");
+        params.push(Box::new(chrono::Utc::now().to_rfc3339()));
+        updates.push("version = version + 1");
+
+        params.push(Box::new(id.to_string()));
+        params.push(Box::new(expected_version));
+
+        let query = format!(
+            "UPDATE saved_queries SET {} WHERE id = ? AND version = ? AND deleted_at IS NULL",
+            updates.join(", ")
+        );
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows_affected = conn.execute(&query, params_refs.as_slice())?;
+
+        Ok(rows_affected > 0)
+    }
+
+    fn row_to_saved_query(row: &rusqlite::Row) -> SqliteResult<crate::models::SavedQuery> {
+        Ok(crate::models::SavedQuery {
+            id: row.get(0)?,
+            domain_id: row.get(1)?,
+            connection_id: row.get(2)?,
+            name: row.get(3)?,
+            query_text: row.get(4)?,
+            description: row.get(5)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            version: row.get(8)?,
+            transform: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            parameters: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
+            connection_ids: Self::json_column(row, 11)?.unwrap_or_default(),
+            database_aliases: Self::json_column(row, 12)?,
+            view_name: row.get(13)?,
+            is_broken: row.get(14)?,
+            breakage_reason: row.get(15)?,
+        })
+    }
+
+    /// JSON array of a cross-database query's connections (NULL for single-connection queries)
+    fn connection_ids_json(connection_ids: &[String]) -> Option<String> {
+        if connection_ids.is_empty() {
+            return None;
+        }
+        serde_json::to_string(connection_ids).ok()
+    }
+
+    fn database_aliases_json(database_aliases: Option<&std::collections::HashMap<String, String>>) -> Option<String> {
+        database_aliases.and_then(|aliases| serde_json::to_string(aliases).ok())
+    }
+
+    /// Nullable JSON text column (None when NULL or unreadable)
+    fn json_column<T: serde::de::DeserializeOwned>(row: &rusqlite::Row, index: usize) -> SqliteResult<Option<T>> {
+        Ok(row
+            .get::<_, Option<String>>(index)?
+            .and_then(|json| serde_json::from_str(&json).ok()))
+    }
+
+    /// Delete a saved query (soft delete - moves it to the trash)
+    pub async fn delete_saved_query(&self, id: &str) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE saved_queries SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Query History Operations (Domain-Scoped)
+    // ============================================================================
+
+    /// Add a query execution to history and update its hourly usage bucket
+    pub async fn add_query_history(&self, history: &crate::models::QueryHistory) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            r#"
+            INSERT INTO query_history
+            (id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated, executed_by, kind, connection_ids, database_aliases, auto_repaired, saved_query_id, is_slow, fingerprint)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                    COALESCE((SELECT slow_query_threshold_ms > 0 AND ?6 >= slow_query_threshold_ms
+                              FROM domain_settings WHERE domain_id = ?2), ?17),
+                    ?18)
+            "#,
+            rusqlite::params![
+                history.id,
+                history.domain_id,
+                history.connection_id,
+                history.query_text,
+                history.row_count as i64,
+                history.execution_time_ms as i64,
+                format!("{:?}", history.status).to_lowercase(),
+                history.error_message,
+                history.executed_at.to_rfc3339(),
+                if history.is_llm_generated { 1 } else { 0 },
+                history.executed_by,
+                history.kind.as_str(),
+                Self::connection_ids_json(&history.connection_ids),
+                Self::database_aliases_json(history.database_aliases.as_ref()),
+                if history.auto_repaired { 1 } else { 0 },
+                history.saved_query_id,
+                // Domains without settings use the default threshold
+                history.execution_time_ms >= crate::models::DEFAULT_SLOW_QUERY_THRESHOLD_MS,
+                crate::validation::SqlFingerprint::of(&history.query_text),
+            ],
+        )?;
+
+        let succeeded = history.status == crate::models::QueryHistoryStatus::Success;
+        tx.execute(
+            r#"
+            INSERT INTO query_usage_stats
+            (domain_id, connection_id, bucket_hour, total_count, success_count, failed_count, llm_count, total_execution_time_ms)
+            VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7)
+            ON CONFLICT(domain_id, connection_id, bucket_hour) DO UPDATE SET
+                total_count = total_count + 1,
+                success_count = success_count + excluded.success_count,
+                failed_count = failed_count + excluded.failed_count,
+                llm_count = llm_count + excluded.llm_count,
+                total_execution_time_ms = total_execution_time_ms + excluded.total_execution_time_ms
+            "#,
+            rusqlite::params![
+                history.domain_id,
+                history.connection_id.as_deref().unwrap_or(CROSS_DATABASE_USAGE_KEY),
+                history.executed_at.format("%Y-%m-%dT%H").to_string(),
+                if succeeded { 1 } else { 0 },
+                if succeeded { 0 } else { 1 },
+                if history.is_llm_generated { 1 } else { 0 },
+                history.execution_time_ms as i64,
+            ],
+        )?;
+
+        tx.execute(
+            r#"
+            INSERT INTO api_usage_daily (domain_id, day, queries, failed_queries, rows_returned)
+            VALUES (?1, ?2, 1, ?3, ?4)
+            ON CONFLICT(domain_id, day) DO UPDATE SET
+                queries = queries + 1,
+                failed_queries = failed_queries + excluded.failed_queries,
+                rows_returned = rows_returned + excluded.rows_returned
+            "#,
+            rusqlite::params![
+                history.domain_id,
+                history.executed_at.format("%Y-%m-%d").to_string(),
+                if succeeded { 0 } else { 1 },
+                history.row_count as i64,
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a single query history entry
+    pub async fn get_query_history(&self, id: &str) -> SqliteResult<Option<crate::models::QueryHistory>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated, executed_by, kind, connection_ids, database_aliases, auto_repaired, saved_query_id, is_slow, fingerprint,
+                   EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = query_history.id)
+            FROM query_history
+            WHERE id = ?1
+            "#,
+            rusqlite::params![id],
+            |row| {
+                let status_str: String = row.get(6)?;
+                let status = match status_str.as_str() {
+                    "success" => crate::models::QueryHistoryStatus::Success,
+                    _ => crate::models::QueryHistoryStatus::Failed,
+                };
+
+                Ok(crate::models::QueryHistory {
+                    id: row.get(0)?,
+                    domain_id: row.get(1)?,
+                    connection_id: row.get(2)?,
+                    query_text: row.get(3)?,
+                    row_count: row.get::<_, i64>(4)? as usize,
+                    execution_time_ms: row.get::<_, i64>(5)? as u64,
+                    status,
+                    error_message: row.get(7)?,
+                    executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    is_llm_generated: row.get::<_, i32>(9)? == 1,
+                    executed_by: row.get(10)?,
+                    kind: crate::models::QueryKind::from_str(&row.get::<_, String>(11)?),
+                    connection_ids: Self::json_column(row, 12)?.unwrap_or_default(),
+                    database_aliases: Self::json_column(row, 13)?,
+                    auto_repaired: row.get::<_, i32>(14)? == 1,
+                    saved_query_id: row.get(15)?,
+                    is_slow: row.get::<_, i32>(16)? == 1,
+                    fingerprint: row.get::<_, Option<String>>(17)?.unwrap_or_default(),
+                    has_result: row.get::<_, i32>(18)? == 1,
+                })
+            },
+        );
+
+        match result {
+            Ok(history) => Ok(Some(history)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Link a history entry to the saved query it was promoted to, returning
+    /// whether the entry exists
+    pub async fn set_history_saved_query(&self, history_id: &str, saved_query_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "UPDATE query_history SET saved_query_id = ?2 WHERE id = ?1",
+            rusqlite::params![history_id, saved_query_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Save the execution context captured for a history entry
+    pub async fn save_history_snapshot(&self, snapshot: &crate::models::QueryHistorySnapshot) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO query_history_snapshots
+            (history_id, connection_id, connection_version, connection_fingerprint, database_type,
+             metadata_cache_id, metadata_version, result_hash, limit_applied, captured_at, fingerprint_scheme)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 2)
+            "#,
+            rusqlite::params![
+                snapshot.history_id,
+                snapshot.connection_id,
+                snapshot.connection_version,
+                snapshot.connection_fingerprint,
+                snapshot.database_type,
+                snapshot.metadata_cache_id,
+                snapshot.metadata_version,
+                snapshot.result_hash,
+                if snapshot.limit_applied { 1 } else { 0 },
+                snapshot.captured_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the execution context captured for a history entry
+    pub async fn get_history_snapshot(
+        &self,
+        history_id: &str,
+    ) -> SqliteResult<Option<crate::models::QueryHistorySnapshot>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT history_id, connection_id, connection_version, connection_fingerprint, database_type,
+                   metadata_cache_id, metadata_version, result_hash, limit_applied, captured_at
+            FROM query_history_snapshots
+            WHERE history_id = ?1
+            "#,
+            rusqlite::params![history_id],
+            |row| {
+                Ok(crate::models::QueryHistorySnapshot {
+                    history_id: row.get(0)?,
+                    connection_id: row.get(1)?,
+                    connection_version: row.get(2)?,
+                    connection_fingerprint: row.get(3)?,
+                    database_type: row.get(4)?,
+                    metadata_cache_id: row.get(5)?,
+                    metadata_version: row.get(6)?,
+                    result_hash: row.get(7)?,
+                    limit_applied: row.get::<_, i32>(8)? == 1,
+                    captured_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                })
+            },
+        );
+
+        match result {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the result kept with a history entry, with the Parquet file for
+    /// `parquet` results (the rows are then not stored as JSON)
+    pub async fn save_history_result(
+        &self,
+        result: &crate::models::HistoryResult,
+        parquet: Option<&[u8]>,
+    ) -> SqliteResult<()> {
+        let rows = match parquet {
+            Some(_) => None,
+            None => Some(serde_json::to_string(&result.results).unwrap_or_else(|_| "[]".to_string())),
+        };
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO query_history_results
+            (history_id, format, rows, parquet, row_count, total_row_count, truncated, size_bytes, captured_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            rusqlite::params![
+                result.history_id,
+                result.format.as_str(),
+                rows,
+                parquet,
+                result.row_count as i64,
+                result.total_row_count as i64,
+                if result.truncated { 1 } else { 0 },
+                result.size_bytes as i64,
+                result.captured_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the result kept with a history entry
+    ///
+    /// `results` is only filled for `rows` results; `parquet` results come
+    /// with their file instead.
+    pub async fn get_history_result(
+        &self,
+        history_id: &str,
+    ) -> SqliteResult<Option<(crate::models::HistoryResult, Option<Vec<u8>>)>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT history_id, format, rows, parquet, row_count, total_row_count, truncated, size_bytes, captured_at
+            FROM query_history_results
+            WHERE history_id = ?1
+            "#,
+            rusqlite::params![history_id],
+            |row| {
+                let result = crate::models::HistoryResult {
+                    history_id: row.get(0)?,
+                    format: crate::models::HistoryResultFormat::from_str(&row.get::<_, String>(1)?),
+                    total_row_count: row.get::<_, i64>(5)? as usize,
+                    row_count: row.get::<_, i64>(4)? as usize,
+                    truncated: row.get::<_, i32>(6)? == 1,
+                    size_bytes: row.get::<_, i64>(7)? as u64,
+                    captured_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    results: Self::json_column(row, 2)?.unwrap_or_default(),
+                };
+                Ok((result, row.get::<_, Option<Vec<u8>>>(3)?))
+            },
+        );
+
+        match result {
+            Ok(result) => Ok(Some(result)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Count an API call of a domain in today's usage rollup
+    ///
+    /// Calls for domains that do not exist (e.g. 404s) are not recorded.
+    pub async fn record_api_call(&self, domain_id: &str, failed: bool, bytes_out: u64) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO api_usage_daily (domain_id, day, api_calls, api_errors, bytes_out)
+            SELECT ?1, ?2, 1, ?3, ?4 WHERE EXISTS (SELECT 1 FROM domains WHERE id = ?1)
+            ON CONFLICT(domain_id, day) DO UPDATE SET
+                api_calls = api_calls + 1,
+                api_errors = api_errors + excluded.api_errors,
+                bytes_out = bytes_out + excluded.bytes_out
+            "#,
+            rusqlite::params![
+                domain_id,
+                chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                if failed { 1 } else { 0 },
+                bytes_out as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Count a request to the LLM gateway in today's usage rollups of a domain:
+    /// the API usage of the domain, and the tokens of the principal (None when
+    /// unauthenticated)
+    pub async fn record_llm_request(
+        &self,
+        domain_id: &str,
+        principal: Option<&str>,
+        tokens: crate::models::LlmTokenUsage,
+        failed: bool,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        let tx = conn.unchecked_transaction()?;
+        let day = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let failed = if failed { 1 } else { 0 };
+        tx.execute(
+            r#"
+            INSERT INTO api_usage_daily (domain_id, day, llm_requests, llm_failures)
+            SELECT ?1, ?2, 1, ?3 WHERE EXISTS (SELECT 1 FROM domains WHERE id = ?1)
+            ON CONFLICT(domain_id, day) DO UPDATE SET
+                llm_requests = llm_requests + 1,
+                llm_failures = llm_failures + excluded.llm_failures
+            "#,
+            rusqlite::params![domain_id, day, failed],
+        )?;
+        tx.execute(
+            r#"
+            INSERT INTO llm_usage_daily (domain_id, principal, day, requests, failed_requests, prompt_tokens, completion_tokens)
+            SELECT ?1, ?2, ?3, 1, ?4, ?5, ?6 WHERE EXISTS (SELECT 1 FROM domains WHERE id = ?1)
+            ON CONFLICT(domain_id, day, principal) DO UPDATE SET
+                requests = requests + 1,
+                failed_requests = failed_requests + excluded.failed_requests,
+                prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                completion_tokens = completion_tokens + excluded.completion_tokens
+            "#,
+            rusqlite::params![
+                domain_id,
+                principal.unwrap_or(""),
+                day,
+                failed,
+                tokens.prompt_tokens as i64,
+                tokens.completion_tokens as i64,
+            ],
+        )?;
+        tx.commit()
+    }
+
+    /// List the daily LLM usage of a domain's principals in `[from, to)` (oldest first)
+    pub async fn list_llm_usage(
+        &self,
+        domain_id: &str,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> SqliteResult<Vec<crate::models::LlmUsageRecord>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT principal, day, requests, failed_requests, prompt_tokens, completion_tokens
+            FROM llm_usage_daily
+            WHERE domain_id = ?1 AND day >= ?2 AND day < ?3
+            ORDER BY day ASC, principal ASC
+            "#
+        )?;
+
+        let records = stmt.query_map(
+            rusqlite::params![domain_id, from.to_string(), to.to_string()],
+            |row| {
+                let principal: String = row.get(0)?;
+                let day: String = row.get(1)?;
+                let date = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                        1,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    ))?;
+                Ok(crate::models::LlmUsageRecord {
+                    principal: (!principal.is_empty()).then_some(principal),
+                    date,
+                    requests: row.get::<_, i64>(2)? as u64,
+                    failed_requests: row.get::<_, i64>(3)? as u64,
+                    tokens: crate::models::LlmTokenUsage {
+                        prompt_tokens: row.get::<_, i64>(4)? as u64,
+                        completion_tokens: row.get::<_, i64>(5)? as u64,
+                    },
+                })
+            },
+        )?;
+
+        records.collect()
+    }
+
+    /// Tokens a domain spent on LLM requests since `from`
+    pub async fn llm_tokens_used(&self, domain_id: &str, from: chrono::NaiveDate) -> SqliteResult<u64> {
+        let conn = self.conn.lock().await;
+        let tokens: i64 = conn.query_row(
+            r#"
+            SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0)
+            FROM llm_usage_daily
+            WHERE domain_id = ?1 AND day >= ?2
+            "#,
+            rusqlite::params![domain_id, from.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(tokens as u64)
+    }
+
+    /// List the daily usage rollups of a domain in `[from, to)` (oldest first)
+    pub async fn list_api_usage_days(
+        &self,
+        domain_id: &str,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> SqliteResult<Vec<crate::models::DailyApiUsage>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT day, api_calls, api_errors, bytes_out, queries, failed_queries, rows_returned, llm_requests, llm_failures
+            FROM api_usage_daily
+            WHERE domain_id = ?1 AND day >= ?2 AND day < ?3
+            ORDER BY day ASC
+            "#
+        )?;
+
+        let days = stmt.query_map(
+            rusqlite::params![domain_id, from.to_string(), to.to_string()],
+            |row| {
+                let day: String = row.get(0)?;
+                let date = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                        0,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    ))?;
+                Ok(crate::models::DailyApiUsage {
+                    date,
+                    api_calls: row.get::<_, i64>(1)? as u64,
+                    api_errors: row.get::<_, i64>(2)? as u64,
+                    bytes_out: row.get::<_, i64>(3)? as u64,
+                    queries: row.get::<_, i64>(4)? as u64,
+                    failed_queries: row.get::<_, i64>(5)? as u64,
+                    rows_returned: row.get::<_, i64>(6)? as u64,
+                    llm_requests: row.get::<_, i64>(7)? as u64,
+                    llm_failures: row.get::<_, i64>(8)? as u64,
+                })
+            },
+        )?;
+
+        days.collect()
+    }
+
+    /// List hourly usage buckets for a domain since the given time (oldest first)
+    pub async fn list_usage_buckets(
+        &self,
+        domain_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<Vec<crate::models::UsageBucket>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT domain_id, connection_id, bucket_hour, total_count, success_count, failed_count, llm_count, total_execution_time_ms
+            FROM query_usage_stats
+            WHERE domain_id = ?1 AND bucket_hour >= ?2
+            ORDER BY bucket_hour ASC
+            "#
+        )?;
+
+        let buckets = stmt.query_map(
+            rusqlite::params![domain_id, since.format("%Y-%m-%dT%H").to_string()],
+            |row| {
+                let bucket_hour: String = row.get(2)?;
+                let bucket_start = chrono::NaiveDateTime::parse_from_str(
+                    &format!("{}:00:00", bucket_hour),
+                    "%Y-%m-%dT%H:%M:%S",
+                )
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                ))?
+                .and_utc();
+
+                Ok(crate::models::UsageBucket {
+                    domain_id: row.get(0)?,
+                    connection_id: row.get(1)?,
+                    bucket_start,
+                    total_count: row.get::<_, i64>(3)? as u64,
+                    success_count: row.get::<_, i64>(4)? as u64,
+                    failed_count: row.get::<_, i64>(5)? as u64,
+                    llm_count: row.get::<_, i64>(6)? as u64,
+                    total_execution_time_ms: row.get::<_, i64>(7)? as u64,
+                })
+            },
+        )?;
+
+        buckets.collect()
+    }
+
+    /// Aggregate the query history of a domain between `since` and `until`,
+    /// with its `top` busiest connections and users
+    pub async fn domain_stats(
+        &self,
+        domain_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+        top: usize,
+    ) -> SqliteResult<crate::models::DomainStats> {
+        use crate::models::{ConnectionQueryStats, QueryShapeStats, QueryTotals, UserQueryStats};
+
+        fn totals(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<QueryTotals> {
+            Ok(QueryTotals::new(
+                row.get::<_, i64>(offset)? as u64,
+                row.get::<_, i64>(offset + 1)? as u64,
+                row.get(offset + 2)?,
+                row.get::<_, i64>(offset + 3)? as u64,
+            ))
+        }
+
+        let conn = self.conn.lock().await;
+        let (from, to, top) = (since.to_rfc3339(), until.to_rfc3339(), top as i64);
+        let totals_all = conn.query_row(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(status = 'failed'), 0), COALESCE(AVG(execution_time_ms), 0.0), COALESCE(SUM(row_count), 0)
+            FROM query_history
+            WHERE domain_id = ?1 AND executed_at >= ?2 AND executed_at <= ?3
+            "#,
+            rusqlite::params![domain_id, from, to],
+            |row| totals(row, 0),
+        )?;
+
+        // Cross-database queries count for each of their connections
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT used.value, c.name,
+                   COUNT(*), SUM(h.status = 'failed'), AVG(h.execution_time_ms), SUM(h.row_count)
+            FROM query_history h,
+                 json_each(CASE WHEN h.connection_id IS NOT NULL THEN json_array(h.connection_id)
+                                ELSE COALESCE(h.connection_ids, '[]') END) used
+            LEFT JOIN connections c ON c.id = used.value
+            WHERE h.domain_id = ?1 AND h.executed_at >= ?2 AND h.executed_at <= ?3
+            GROUP BY used.value
+            ORDER BY COUNT(*) DESC, used.value
+            LIMIT ?4
+            "#,
+        )?;
+        let top_connections = stmt
+            .query_map(rusqlite::params![domain_id, from, to, top], |row| {
+                Ok(ConnectionQueryStats {
+                    connection_id: row.get(0)?,
+                    name: row.get(1)?,
+                    totals: totals(row, 2)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT executed_by, COUNT(*), SUM(status = 'failed'), AVG(execution_time_ms), SUM(row_count)
+            FROM query_history
+            WHERE domain_id = ?1 AND executed_at >= ?2 AND executed_at <= ?3 AND executed_by IS NOT NULL
+            GROUP BY executed_by
+            ORDER BY COUNT(*) DESC, executed_by
+            LIMIT ?4
+            "#,
+        )?;
+        let top_users = stmt
+            .query_map(rusqlite::params![domain_id, from, to, top], |row| {
+                Ok(UserQueryStats {
+                    executed_by: row.get(0)?,
+                    totals: totals(row, 1)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT fingerprint, COUNT(*), SUM(status = 'failed'), AVG(execution_time_ms), SUM(row_count)
+            FROM query_history
+            WHERE domain_id = ?1 AND executed_at >= ?2 AND executed_at <= ?3
+            GROUP BY fingerprint
+            ORDER BY COUNT(*) DESC, fingerprint
+            LIMIT ?4
+            "#,
+        )?;
+        let top_queries = stmt
+            .query_map(rusqlite::params![domain_id, from, to, top], |row| {
+                Ok(QueryShapeStats {
+                    fingerprint: row.get(0)?,
+                    totals: totals(row, 1)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(crate::models::DomainStats {
+            domain_id: domain_id.to_string(),
+            since,
+            until,
+            totals: totals_all,
+            top_connections,
+            top_users,
+            top_queries,
+        })
+    }
+
+    /// Aggregate the history entries of a domain flagged as slow between
+    /// `since` and `until` by fingerprint, keeping the `top` groups with the
+    /// most total (or average) execution time
+    pub async fn slow_query_groups(
+        &self,
+        domain_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+        sort: crate::models::SlowQuerySort,
+        top: usize,
+    ) -> SqliteResult<Vec<crate::models::SlowQueryGroup>> {
+        let order = match sort {
+            crate::models::SlowQuerySort::Total => "SUM(h.execution_time_ms)",
+            crate::models::SlowQuerySort::Avg => "AVG(h.execution_time_ms)",
+        };
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT h.fingerprint,
+                   (SELECT l.query_text FROM query_history l
+                    WHERE l.domain_id = ?1 AND l.fingerprint = h.fingerprint AND l.is_slow = 1
+                      AND l.executed_at >= ?2 AND l.executed_at <= ?3
+                    ORDER BY l.executed_at DESC LIMIT 1),
+                   COUNT(*), SUM(h.execution_time_ms), AVG(h.execution_time_ms), MAX(h.execution_time_ms), MAX(h.executed_at)
+            FROM query_history h
+            WHERE h.domain_id = ?1 AND h.is_slow = 1 AND h.executed_at >= ?2 AND h.executed_at <= ?3
+            GROUP BY h.fingerprint
+            ORDER BY {} DESC, h.fingerprint
+            LIMIT ?4
+            "#,
+            order
+        ))?;
+        let groups = stmt.query_map(
+            rusqlite::params![domain_id, since.to_rfc3339(), until.to_rfc3339(), top as i64],
+            |row| {
+                Ok(crate::models::SlowQueryGroup {
+                    fingerprint: row.get(0)?,
+                    example_query: row.get(1)?,
+                    executions: row.get::<_, i64>(2)? as u64,
+                    total_execution_time_ms: row.get::<_, i64>(3)? as u64,
+                    avg_execution_time_ms: row.get(4)?,
+                    max_execution_time_ms: row.get::<_, i64>(5)? as u64,
+                    last_executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                })
+            },
+        )?;
+        groups.collect()
+    }
+
+    /// List query history for a domain (with limit)
+    pub async fn list_query_history(
+        &self,
+        domain_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::models::QueryHistory>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated, executed_by, kind, connection_ids, database_aliases, auto_repaired, saved_query_id, is_slow, fingerprint,
+                   EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = query_history.id)
+            FROM query_history
+            WHERE domain_id = ?1
+            ORDER BY executed_at DESC
+            LIMIT ?2
+            "#
+        )?;
+
+        let histories = stmt.query_map(rusqlite::params![domain_id, limit as i64], |row| {
+            let status_str: String = row.get(6)?;
+            let status = match status_str.as_str() {
+                "success" => crate::models::QueryHistoryStatus::Success,
+                "failed" => crate::models::QueryHistoryStatus::Failed,
+                _ => crate::models::QueryHistoryStatus::Failed,
+            };
+
+            Ok(crate::models::QueryHistory {
+                id: row.get(0)?,
+                domain_id: row.get(1)?,
+                connection_id: row.get(2)?,
+                query_text: row.get(3)?,
+                row_count: row.get::<_, i64>(4)? as usize,
+                execution_time_ms: row.get::<_, i64>(5)? as u64,
+                status,
+                error_message: row.get(7)?,
+                executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                is_llm_generated: row.get::<_, i32>(9)? == 1,
+                executed_by: row.get(10)?,
+                kind: crate::models::QueryKind::from_str(&row.get::<_, String>(11)?),
+                connection_ids: Self::json_column(row, 12)?.unwrap_or_default(),
+                database_aliases: Self::json_column(row, 13)?,
+                auto_repaired: row.get::<_, i32>(14)? == 1,
+                saved_query_id: row.get(15)?,
+                is_slow: row.get::<_, i32>(16)? == 1,
+                fingerprint: row.get::<_, Option<String>>(17)?.unwrap_or_default(),
+                has_result: row.get::<_, i32>(18)? == 1,
+            })
+        })?;
+
+        histories.collect()
+    }
+
+    /// Build the domain filter and its parameters for a history scope
+    ///
+    /// Parameters are numbered from `?2` (`?1` is the cutoff).
+    fn history_scope_filter(scope: &crate::models::HistoryScope) -> (String, Vec<String>) {
+        match scope {
+            crate::models::HistoryScope::All => (String::new(), Vec::new()),
+            crate::models::HistoryScope::Domain(domain_id) => {
+                (" AND domain_id = ?2".to_string(), vec![domain_id.clone()])
+            }
+            crate::models::HistoryScope::AllExcept(domain_ids) if domain_ids.is_empty() => {
+                (String::new(), Vec::new())
+            }
+            crate::models::HistoryScope::AllExcept(domain_ids) => {
+                let placeholders: Vec<String> = (0..domain_ids.len()).map(|i| format!("?{}", i + 2)).collect();
+                (
+                    format!(" AND domain_id NOT IN ({})", placeholders.join(", ")),
+                    domain_ids.clone(),
+                )
+            }
+        }
+    }
+
+    /// List query history rows in scope executed before the given cutoff (oldest first)
+    pub async fn list_query_history_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        scope: &crate::models::HistoryScope,
+    ) -> SqliteResult<Vec<crate::models::QueryHistory>> {
+        let conn = self.conn.lock().await;
+        let (filter, scope_params) = Self::history_scope_filter(scope);
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated, executed_by, kind, connection_ids, database_aliases, auto_repaired, saved_query_id, is_slow, fingerprint,
+                   EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = query_history.id)
+            FROM query_history
+            WHERE executed_at < ?1{}
+            ORDER BY executed_at ASC
+            "#,
+            filter
+        ))?;
+
+        let params = std::iter::once(cutoff.to_rfc3339()).chain(scope_params);
+        let histories = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            let status_str: String = row.get(6)?;
+            let status = match status_str.as_str() {
+                "success" => crate::models::QueryHistoryStatus::Success,
+                "failed" => crate::models::QueryHistoryStatus::Failed,
+                _ => crate::models::QueryHistoryStatus::Failed,
+            };
+
+            Ok(crate::models::QueryHistory {
+                id: row.get(0)?,
+                domain_id: row.get(1)?,
+                connection_id: row.get(2)?,
+                query_text: row.get(3)?,
+                row_count: row.get::<_, i64>(4)? as usize,
+                execution_time_ms: row.get::<_, i64>(5)? as u64,
+                status,
+                error_message: row.get(7)?,
+                executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                is_llm_generated: row.get::<_, i32>(9)? == 1,
+                executed_by: row.get(10)?,
+                kind: crate::models::QueryKind::from_str(&row.get::<_, String>(11)?),
+                connection_ids: Self::json_column(row, 12)?.unwrap_or_default(),
+                database_aliases: Self::json_column(row, 13)?,
+                auto_repaired: row.get::<_, i32>(14)? == 1,
+                saved_query_id: row.get(15)?,
+                is_slow: row.get::<_, i32>(16)? == 1,
+                fingerprint: row.get::<_, Option<String>>(17)?.unwrap_or_default(),
+                has_result: row.get::<_, i32>(18)? == 1,
+            })
+        })?;
+
+        histories.collect()
+    }
+
+    /// Delete all query history rows executed before the given cutoff
+    ///
+    /// Returns the number of rows removed.
+    pub async fn delete_query_history_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        scope: &crate::models::HistoryScope,
+    ) -> SqliteResult<usize> {
+        let conn = self.conn.lock().await;
+        let (filter, scope_params) = Self::history_scope_filter(scope);
+        let params = std::iter::once(cutoff.to_rfc3339()).chain(scope_params);
+        let deleted = conn.execute(
+            &format!("DELETE FROM query_history WHERE executed_at < ?1{}", filter),
+            rusqlite::params_from_iter(params),
+        )?;
+        Ok(deleted)
+    }
+
+    // ==================== Domain Settings ====================
+
+    /// Get a domain's settings (None if the domain never customized them)
+    pub async fn get_domain_settings(&self, domain_id: &str) -> SqliteResult<Option<crate::models::DomainSettings>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT domain_id, default_row_limit, default_timeout_secs, history_retention_days, allowed_database_types, updated_at,
+                   max_row_limit, max_timeout_secs, llm_monthly_token_budget, llm_denied_identifiers,
+                   metadata_refresh_interval_secs, slow_query_threshold_ms
+            FROM domain_settings WHERE domain_id = ?1
+            "#,
+            rusqlite::params![domain_id],
+            Self::row_to_domain_settings,
+        );
+
+        match result {
+            Ok(settings) => Ok(Some(settings)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all customized domain settings
+    pub async fn list_domain_settings(&self) -> SqliteResult<Vec<crate::models::DomainSettings>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT domain_id, default_row_limit, default_timeout_secs, history_retention_days, allowed_database_types, updated_at,
+                   max_row_limit, max_timeout_secs, llm_monthly_token_budget, llm_denied_identifiers,
+                   metadata_refresh_interval_secs, slow_query_threshold_ms
+            FROM domain_settings
+            "#,
+        )?;
+        let settings = stmt.query_map([], Self::row_to_domain_settings)?;
+        settings.collect()
+    }
+
+    /// Create or replace a domain's settings
+    pub async fn save_domain_settings(&self, settings: &crate::models::DomainSettings) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO domain_settings
+            (domain_id, default_row_limit, default_timeout_secs, history_retention_days, allowed_database_types, updated_at,
+             max_row_limit, max_timeout_secs, llm_monthly_token_budget, llm_denied_identifiers,
+             metadata_refresh_interval_secs, slow_query_threshold_ms)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            ON CONFLICT(domain_id) DO UPDATE SET
+                default_row_limit = excluded.default_row_limit,
+                default_timeout_secs = excluded.default_timeout_secs,
+                max_row_limit = excluded.max_row_limit,
+                max_timeout_secs = excluded.max_timeout_secs,
+                history_retention_days = excluded.history_retention_days,
+                allowed_database_types = excluded.allowed_database_types,
+                llm_monthly_token_budget = excluded.llm_monthly_token_budget,
+                llm_denied_identifiers = excluded.llm_denied_identifiers,
+                metadata_refresh_interval_secs = excluded.metadata_refresh_interval_secs,
+                slow_query_threshold_ms = excluded.slow_query_threshold_ms,
+                updated_at = excluded.updated_at
+            "#,
+            rusqlite::params![
+                settings.domain_id,
+                settings.default_row_limit as i64,
+                settings.default_timeout_secs as i64,
+                settings.history_retention_days,
+                serde_json::to_string(&settings.allowed_database_types).unwrap_or_else(|_| "[]".to_string()),
+                settings.updated_at.to_rfc3339(),
+                settings.max_row_limit as i64,
+                settings.max_timeout_secs as i64,
+                settings.llm_monthly_token_budget.map(|budget| budget as i64),
+                serde_json::to_string(&settings.llm_denied_identifiers).unwrap_or_else(|_| "[]".to_string()),
+                settings.metadata_refresh_interval_secs.map(|interval| interval as i64),
+                settings.slow_query_threshold_ms as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn row_to_domain_settings(row: &rusqlite::Row) -> rusqlite::Result<crate::models::DomainSettings> {
+        let allowed: String = row.get(4)?;
+        Ok(crate::models::DomainSettings {
+            domain_id: row.get(0)?,
+            default_row_limit: row.get::<_, i64>(1)? as u64,
+            default_timeout_secs: row.get::<_, i64>(2)? as u64,
+            max_row_limit: row.get::<_, i64>(6)? as u64,
+            max_timeout_secs: row.get::<_, i64>(7)? as u64,
+            history_retention_days: row.get(3)?,
+            allowed_database_types: serde_json::from_str(&allowed).unwrap_or_default(),
+            llm_monthly_token_budget: row.get::<_, Option<i64>>(8)?.map(|budget| budget as u64),
+            llm_denied_identifiers: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            metadata_refresh_interval_secs: row.get::<_, Option<i64>>(10)?.map(|interval| interval as u64),
+            slow_query_threshold_ms: row.get::<_, i64>(11)? as u64,
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
+    }
+
+    // ==================== Domain Metrics ====================
+
+    /// Get a domain's metrics definition (None if never defined)
+    pub async fn get_domain_metrics(&self, domain_id: &str) -> SqliteResult<Option<crate::models::DomainMetrics>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT domain_id, definition, updated_at FROM domain_metrics WHERE domain_id = ?1",
+            rusqlite::params![domain_id],
+            |row| {
+                let definition: String = row.get(1)?;
+                Ok(crate::models::DomainMetrics {
+                    domain_id: row.get(0)?,
+                    definition: serde_json::from_str(&definition).unwrap_or_default(),
+                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                })
+            },
+        );
+
+        match result {
+            Ok(metrics) => Ok(Some(metrics)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create or replace a domain's metrics definition
+    pub async fn save_domain_metrics(&self, metrics: &crate::models::DomainMetrics) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO domain_metrics (domain_id, definition, updated_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(domain_id) DO UPDATE SET
+                definition = excluded.definition,
+                updated_at = excluded.updated_at
+            "#,
+            rusqlite::params![
+                metrics.domain_id,
+                serde_json::to_string(&metrics.definition).unwrap_or_else(|_| "{}".to_string()),
+                metrics.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // ==================== Result Contracts ====================
+
+    /// Store a saved query's result contract, replacing its previous one
+    pub async fn save_result_contract(&self, contract: &crate::models::ResultContract) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO saved_query_contracts (saved_query_id, domain_id, columns, drift, checked_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            rusqlite::params![
+                contract.saved_query_id,
+                contract.domain_id,
+                serde_json::to_string(&contract.columns).unwrap_or_else(|_| "[]".to_string()),
+                serde_json::to_string(&contract.drift).unwrap_or_else(|_| "[]".to_string()),
+                contract.checked_at.map(|t| t.to_rfc3339()),
+                contract.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the result contract of a saved query
+    pub async fn get_result_contract(&self, saved_query_id: &str) -> SqliteResult<Option<crate::models::ResultContract>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT saved_query_id, domain_id, columns, drift, checked_at, updated_at
+            FROM saved_query_contracts WHERE saved_query_id = ?1
+            "#,
+            rusqlite::params![saved_query_id],
+            Self::row_to_result_contract,
+        );
+
+        match result {
+            Ok(contract) => Ok(Some(contract)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record the outcome of checking a result against a contract
+    pub async fn record_result_contract_check(
+        &self,
+        saved_query_id: &str,
+        drift: &[String],
+        checked_at: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE saved_query_contracts SET drift = ?2, checked_at = ?3 WHERE saved_query_id = ?1",
+            rusqlite::params![
+                saved_query_id,
+                serde_json::to_string(drift).unwrap_or_else(|_| "[]".to_string()),
+                checked_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete the result contract of a saved query; false if it had none
+    pub async fn delete_result_contract(&self, saved_query_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "DELETE FROM saved_query_contracts WHERE saved_query_id = ?1",
+            rusqlite::params![saved_query_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    fn row_to_result_contract(row: &rusqlite::Row) -> rusqlite::Result<crate::models::ResultContract> {
+        let parse_time = |value: String| {
+            chrono::DateTime::parse_from_rfc3339(&value).ok().map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let columns: String = row.get(2)?;
+        let drift: String = row.get(3)?;
+        Ok(crate::models::ResultContract {
+            saved_query_id: row.get(0)?,
+            domain_id: row.get(1)?,
+            columns: serde_json::from_str(&columns).unwrap_or_default(),
+            drift: serde_json::from_str(&drift).unwrap_or_default(),
+            checked_at: row.get::<_, Option<String>>(4)?.and_then(parse_time),
+            updated_at: parse_time(row.get(5)?).unwrap_or_else(chrono::Utc::now),
+        })
+    }
+
+    // ==================== Users and Domain Roles ====================
+
+    /// Create a user account
+    pub async fn create_user(&self, user: &crate::models::User) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO users (id, username, password_hash, is_admin, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            rusqlite::params![
+                user.id,
+                user.username,
+                user.password_hash,
+                if user.is_admin { 1 } else { 0 },
+                user.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a user by ID
+    pub async fn get_user(&self, id: &str) -> SqliteResult<Option<crate::models::User>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT id, username, password_hash, is_admin, created_at FROM users WHERE id = ?1",
+            rusqlite::params![id],
+            Self::row_to_user,
+        );
+
+        match result {
+            Ok(user) => Ok(Some(user)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get a user by username (case-insensitive)
+    pub async fn get_user_by_username(&self, username: &str) -> SqliteResult<Option<crate::models::User>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT id, username, password_hash, is_admin, created_at FROM users WHERE username = ?1 COLLATE NOCASE",
+            rusqlite::params![username],
+            Self::row_to_user,
+        );
+
+        match result {
+            Ok(user) => Ok(Some(user)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all users
+    pub async fn list_users(&self) -> SqliteResult<Vec<crate::models::User>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, username, password_hash, is_admin, created_at FROM users ORDER BY username",
+        )?;
+        let users = stmt.query_map([], Self::row_to_user)?;
+        users.collect()
+    }
+
+    fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<crate::models::User> {
+        Ok(crate::models::User {
+            id: row.get(0)?,
+            username: row.get(1)?,
+            password_hash: row.get(2)?,
+            is_admin: row.get::<_, i32>(3)? == 1,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        })
+    }
+
+    /// Assign (or change) a user's role in a domain
+    pub async fn set_domain_role(&self, assignment: &crate::models::DomainRoleAssignment) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO user_domain_roles (user_id, domain_id, role, assigned_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(user_id, domain_id) DO UPDATE SET
+                role = excluded.role,
+                assigned_at = excluded.assigned_at
+            "#,
+            rusqlite::params![
+                assignment.user_id,
+                assignment.domain_id,
+                assignment.role.as_str(),
+                assignment.assigned_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a user's role in a domain; returns false if there was none
+    pub async fn remove_domain_role(&self, user_id: &str, domain_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "DELETE FROM user_domain_roles WHERE user_id = ?1 AND domain_id = ?2",
+            rusqlite::params![user_id, domain_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Get a user's role in a domain
+    pub async fn get_domain_role(&self, user_id: &str, domain_id: &str) -> SqliteResult<Option<crate::models::DomainRole>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT role FROM user_domain_roles WHERE user_id = ?1 AND domain_id = ?2",
+            rusqlite::params![user_id, domain_id],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(role) => Ok(crate::models::DomainRole::from_str(&role).ok()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List role assignments of a domain
+    pub async fn list_domain_roles(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::DomainRoleAssignment>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT user_id, domain_id, role, assigned_at FROM user_domain_roles WHERE domain_id = ?1 ORDER BY assigned_at",
+        )?;
+        let assignments = stmt.query_map(rusqlite::params![domain_id], |row| {
+            let role: String = row.get(2)?;
+            Ok(crate::models::DomainRoleAssignment {
+                user_id: row.get(0)?,
+                domain_id: row.get(1)?,
+                role: crate::models::DomainRole::from_str(&role).unwrap_or(crate::models::DomainRole::Viewer),
+                assigned_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            })
+        })?;
+        assignments.collect()
+    }
+
+    /// List the ids of domains a user has any role in
+    pub async fn list_user_domain_ids(&self, user_id: &str) -> SqliteResult<Vec<String>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare("SELECT domain_id FROM user_domain_roles WHERE user_id = ?1")?;
+        let ids = stmt.query_map(rusqlite::params![user_id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    // ==================== API Keys ====================
+
+    /// Store a newly issued API key
+    pub async fn create_api_key(&self, key: &crate::models::ApiKey) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO api_keys (id, name, key_prefix, key_hash, scope, created_at, last_used_at, revoked_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            rusqlite::params![
+                key.id,
+                key.name,
+                key.key_prefix,
+                key.key_hash,
+                key.scope.as_str(),
+                key.created_at.to_rfc3339(),
+                key.last_used_at.map(|t| t.to_rfc3339()),
+                key.revoked_at.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Look up an API key by the hash of its secret (revoked keys included)
+    pub async fn get_api_key_by_hash(&self, key_hash: &str) -> SqliteResult<Option<crate::models::ApiKey>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT id, name, key_prefix, key_hash, scope, created_at, last_used_at, revoked_at
+            FROM api_keys WHERE key_hash = ?1
+            "#,
+            rusqlite::params![key_hash],
+            Self::row_to_api_key,
+        );
+
+        match result {
+            Ok(key) => Ok(Some(key)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List all API keys (newest first)
+    pub async fn list_api_keys(&self) -> SqliteResult<Vec<crate::models::ApiKey>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, name, key_prefix, key_hash, scope, created_at, last_used_at, revoked_at
+            FROM api_keys ORDER BY created_at DESC
+            "#,
+        )?;
+        let keys = stmt.query_map([], Self::row_to_api_key)?;
+        keys.collect()
+    }
+
+    /// Revoke an API key; returns false if it does not exist or is already revoked
+    pub async fn revoke_api_key(&self, id: &str, revoked_at: chrono::DateTime<chrono::Utc>) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "UPDATE api_keys SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL",
+            rusqlite::params![id, revoked_at.to_rfc3339()],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Record the last time an API key was used
+    pub async fn touch_api_key(&self, id: &str, used_at: chrono::DateTime<chrono::Utc>) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE api_keys SET last_used_at = ?2 WHERE id = ?1",
+            rusqlite::params![id, used_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    fn row_to_api_key(row: &rusqlite::Row) -> rusqlite::Result<crate::models::ApiKey> {
+        let scope: String = row.get(4)?;
+        let parse_time = |value: Option<String>| {
+            value.and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        };
+        Ok(crate::models::ApiKey {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            key_prefix: row.get(2)?,
+            key_hash: row.get(3)?,
+            // Unknown scopes degrade to read-only rather than failing the lookup
+            scope: crate::models::ApiKeyScope::from_str(&scope).unwrap_or(crate::models::ApiKeyScope::Read),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            last_used_at: parse_time(row.get(6)?),
+            revoked_at: parse_time(row.get(7)?),
+        })
+    }
+
+    // ==================== Query Shares ====================
+
+    /// Store a new share link with the frozen result of a snapshot share
+    pub async fn create_query_share(&self, share: &crate::models::QueryShare, snapshot: Option<&crate::models::Query>) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO query_shares (id, domain_id, saved_query_id, token_prefix, token_hash, parameters, snapshot, snapshot_at, created_by, created_at, expires_at, revoked_at, query_hash)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            "#,
+            rusqlite::params![
+                share.id,
+                share.domain_id,
+                share.saved_query_id,
+                share.token_prefix,
+                share.token_hash,
+                serde_json::to_string(&share.parameters).unwrap_or_else(|_| "{}".to_string()),
+                snapshot.and_then(|query| serde_json::to_string(query).ok()),
+                share.snapshot_at.map(|t| t.to_rfc3339()),
+                share.created_by,
+                share.created_at.to_rfc3339(),
+                share.expires_at.map(|t| t.to_rfc3339()),
+                share.revoked_at.map(|t| t.to_rfc3339()),
+                share.query_hash,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Look up a share by the hash of its token (revoked and expired shares
+    /// included), with its frozen result
+    pub async fn get_query_share_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> SqliteResult<Option<(crate::models::QueryShare, Option<crate::models::Query>)>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT id, domain_id, saved_query_id, token_prefix, token_hash, parameters, snapshot_at, created_by, created_at, expires_at, revoked_at, query_hash, snapshot
+            FROM query_shares WHERE token_hash = ?1
+            "#,
+            rusqlite::params![token_hash],
+            |row| Ok((Self::row_to_query_share(row)?, Self::json_column(row, 12)?)),
+        );
+
+        match result {
+            Ok(share) => Ok(Some(share)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List the share links of a saved query (newest first)
+    pub async fn list_query_shares(&self, saved_query_id: &str) -> SqliteResult<Vec<crate::models::QueryShare>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, domain_id, saved_query_id, token_prefix, token_hash, parameters, snapshot_at, created_by, created_at, expires_at, revoked_at, query_hash
+            FROM query_shares WHERE saved_query_id = ?1 ORDER BY created_at DESC
+            "#,
+        )?;
+        let shares = stmt.query_map(rusqlite::params![saved_query_id], Self::row_to_query_share)?;
+        shares.collect()
+    }
+
+    /// Revoke a share link of a saved query; returns false when there is no
+    /// active share with that id
+    pub async fn revoke_query_share(
+        &self,
+        id: &str,
+        saved_query_id: &str,
+        revoked_at: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "UPDATE query_shares SET revoked_at = ?3, snapshot = NULL WHERE id = ?1 AND saved_query_id = ?2 AND revoked_at IS NULL",
+            rusqlite::params![id, saved_query_id, revoked_at.to_rfc3339()],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    fn row_to_query_share(row: &rusqlite::Row) -> rusqlite::Result<crate::models::QueryShare> {
+        let parse_time = |value: Option<String>| {
+            value.and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        };
+        Ok(crate::models::QueryShare {
+            id: row.get(0)?,
+            domain_id: row.get(1)?,
+            saved_query_id: row.get(2)?,
+            token_prefix: row.get(3)?,
+            token_hash: row.get(4)?,
+            parameters: Self::json_column(row, 5)?.unwrap_or_default(),
+            snapshot_at: parse_time(row.get(6)?),
+            created_by: row.get(7)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            expires_at: parse_time(row.get(9)?),
+            revoked_at: parse_time(row.get(10)?),
+            query_hash: row.get(11)?,
+        })
+    }
+
+    // ==================== Favorites ====================
+
+    /// Mark a connection or saved query as a favorite of `owner` (no-op if it
+    /// already is one)
+    pub async fn add_favorite(
+        &self,
+        owner: &str,
+        resource_type: crate::models::FavoriteType,
+        resource_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT OR IGNORE INTO favorites (owner, resource_type, resource_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![owner, resource_type.as_str(), resource_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a favorite, returning whether it existed
+    pub async fn remove_favorite(
+        &self,
+        owner: &str,
+        resource_type: crate::models::FavoriteType,
+        resource_id: &str,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "DELETE FROM favorites WHERE owner = ?1 AND resource_type = ?2 AND resource_id = ?3",
+            rusqlite::params![owner, resource_type.as_str(), resource_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Favorites of `owner` in a domain, newest first
+    ///
+    /// Resources that were deleted or moved to another domain are left out.
+    pub async fn list_favorites(&self, owner: &str, domain_id: &str) -> SqliteResult<Vec<crate::models::Favorite>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT f.resource_type, f.resource_id, COALESCE(c.domain_id, s.domain_id), COALESCE(c.name, s.name), f.created_at
+            FROM favorites f
+            LEFT JOIN connections c ON f.resource_type = 'connection' AND c.id = f.resource_id AND c.deleted_at IS NULL
+            LEFT JOIN saved_queries s ON f.resource_type = 'saved_query' AND s.id = f.resource_id AND s.deleted_at IS NULL
+            WHERE f.owner = ?1 AND COALESCE(c.domain_id, s.domain_id) = ?2
+            ORDER BY f.created_at DESC, f.rowid DESC
+            "#,
+        )?;
+        let favorites = stmt
+            .query_map(rusqlite::params![owner, domain_id], |row| {
+                let resource_type = crate::models::FavoriteType::from_str(&row.get::<_, String>(0)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+                })?;
+                Ok(crate::models::Favorite {
+                    resource_type,
+                    resource_id: row.get(1)?,
+                    domain_id: row.get(2)?,
+                    name: row.get(3)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                        .map(|t| t.with_timezone(&chrono::Utc))
+                        .unwrap_or_else(|_| chrono::Utc::now()),
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(favorites)
+    }
+
+    /// Queries `executed_by` ran recently in a domain, latest first
+    ///
+    /// Runs of a saved query form one entry, other runs one entry per
+    /// connection and SQL text. `executed_by` None matches the runs recorded
+    /// while authentication was disabled.
+    pub async fn list_recent_queries(
+        &self,
+        domain_id: &str,
+        executed_by: Option<&str>,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::models::RecentQuery>> {
+        let conn = self.conn.lock().await;
+        // With MAX() in the select list, SQLite takes the bare columns from
+        // the row holding the maximum, i.e. the latest run of each group
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT h.connection_id, h.saved_query_id, s.name, h.query_text, h.status, MAX(h.executed_at), COUNT(*)
+            FROM query_history h
+            LEFT JOIN saved_queries s ON s.id = h.saved_query_id AND s.deleted_at IS NULL
+            WHERE h.domain_id = ?1 AND h.executed_by IS ?2
+            GROUP BY CASE WHEN h.saved_query_id IS NOT NULL THEN 'saved:' || h.saved_query_id
+                          ELSE COALESCE(h.connection_id, '') || ':' || h.query_text END
+            ORDER BY MAX(h.executed_at) DESC
+            LIMIT ?3
+            "#,
+        )?;
+        let recent = stmt
+            .query_map(rusqlite::params![domain_id, executed_by, limit as i64], |row| {
+                let last_status = match row.get::<_, String>(4)?.as_str() {
+                    "success" => crate::models::QueryHistoryStatus::Success,
+                    _ => crate::models::QueryHistoryStatus::Failed,
+                };
+                Ok(crate::models::RecentQuery {
+                    connection_id: row.get(0)?,
+                    saved_query_id: row.get(1)?,
+                    saved_query_name: row.get(2)?,
+                    query_text: row.get(3)?,
+                    last_status,
+                    last_executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                        .map(|t| t.with_timezone(&chrono::Utc))
+                        .unwrap_or_else(|_| chrono::Utc::now()),
+                    executions: row.get::<_, i64>(6)? as u64,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(recent)
+    }
+
+    // ==================== Connection Policies ====================
+
+    /// Store a new connection policy
+    pub async fn create_connection_policy(&self, policy: &crate::models::ConnectionPolicy) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO connection_policies (id, name, selector, masked_columns, max_queries_per_hour, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            rusqlite::params![
+                policy.id,
+                policy.name,
+                serde_json::to_string(&policy.selector).unwrap_or_else(|_| "{}".to_string()),
+                serde_json::to_string(&policy.masked_columns).unwrap_or_else(|_| "[]".to_string()),
+                policy.max_queries_per_hour,
+                policy.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// List all connection policies
+    pub async fn list_connection_policies(&self) -> SqliteResult<Vec<crate::models::ConnectionPolicy>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, selector, masked_columns, max_queries_per_hour, created_at FROM connection_policies ORDER BY created_at",
+        )?;
+        let policies = stmt.query_map([], |row| {
+            Ok(crate::models::ConnectionPolicy {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                selector: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+                masked_columns: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+                max_queries_per_hour: row.get(4)?,
+                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            })
+        })?;
+        policies.collect()
+    }
+
+    /// Delete a connection policy
+    pub async fn delete_connection_policy(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute("DELETE FROM connection_policies WHERE id = ?1", rusqlite::params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Count queries recorded for a connection since the given time (policy quotas)
+    pub async fn count_connection_queries_since(
+        &self,
+        connection_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<u64> {
+        let conn = self.conn.lock().await;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM query_history WHERE connection_id = ?1 AND executed_at >= ?2",
+            rusqlite::params![connection_id, since.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    // ==================== Query Jobs ====================
+
+    /// Store a newly submitted query job
+    pub async fn create_query_job(&self, job: &crate::models::QueryJob) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO query_jobs (id, connection_id, domain_id, query_text, status, submitted_by, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            rusqlite::params![
+                job.id,
+                job.connection_id,
+                job.domain_id,
+                job.query_text,
+                job.status.as_str(),
+                job.submitted_by,
+                job.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a query job by ID
+    pub async fn get_query_job(&self, id: &str) -> SqliteResult<Option<crate::models::QueryJob>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, connection_id, domain_id, query_text, status, submitted_by, results, row_count,
+                   execution_time_ms, limit_applied, error_message, created_at, started_at, finished_at, rows_processed
+            FROM query_jobs WHERE id = ?1
+            "#,
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![id], Self::row_to_query_job)?;
+        rows.next().transpose()
+    }
+
+    /// Move a pending job to running; false if it was cancelled in the meantime
+    pub async fn mark_query_job_running(&self, id: &str, started_at: chrono::DateTime<chrono::Utc>) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "UPDATE query_jobs SET status = 'running', started_at = ?1 WHERE id = ?2 AND status = 'pending'",
+            rusqlite::params![started_at.to_rfc3339(), id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Record the rows a running job has processed so far
+    pub async fn update_query_job_progress(&self, id: &str, rows_processed: u64) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE query_jobs SET rows_processed = ?1 WHERE id = ?2 AND status = 'running'",
+            rusqlite::params![rows_processed as i64, id],
+        )?;
+        Ok(())
+    }
+
+    /// Record the final state of a job (completed, failed or cancelled)
+    ///
+    /// Only unfinished jobs are updated, so a job finishing while it is being
+    /// cancelled keeps whichever outcome was written first. Returns whether
+    /// this call won.
+    pub async fn finish_query_job(&self, job: &crate::models::QueryJob) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let results = job
+            .results
+            .as_ref()
+            .map(|rows| serde_json::to_string(rows).unwrap_or_else(|_| "[]".to_string()));
+        let rows_affected = conn.execute(
+            r#"
+            UPDATE query_jobs
+            SET status = ?1, results = ?2, row_count = ?3, execution_time_ms = ?4, limit_applied = ?5,
+                error_message = ?6, finished_at = ?7
+            WHERE id = ?8 AND status IN ('pending', 'running')
+            "#,
+            rusqlite::params![
+                job.status.as_str(),
+                results,
+                job.row_count.map(|n| n as i64),
+                job.execution_time_ms.map(|n| n as i64),
+                job.limit_applied as i32,
+                job.error_message,
+                job.finished_at.map(|t| t.to_rfc3339()),
+                job.id,
+            ],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Fail jobs left pending or running by a previous process (run at startup)
+    pub async fn fail_unfinished_query_jobs(&self) -> SqliteResult<usize> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            UPDATE query_jobs
+            SET status = 'failed', error_message = 'Interrupted by a server restart', finished_at = ?1
+            WHERE status IN ('pending', 'running')
+            "#,
+            rusqlite::params![chrono::Utc::now().to_rfc3339()],
+        )
+    }
+
+    fn row_to_query_job(row: &rusqlite::Row) -> SqliteResult<crate::models::QueryJob> {
+        let parse_time = |value: Option<String>| {
+            value.and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        };
+        let status: String = row.get(4)?;
+        Ok(crate::models::QueryJob {
+            id: row.get(0)?,
+            connection_id: row.get(1)?,
+            domain_id: row.get(2)?,
+            query_text: row.get(3)?,
+            status: crate::models::QueryJobStatus::from_str(&status).unwrap_or(crate::models::QueryJobStatus::Failed),
+            submitted_by: row.get(5)?,
+            results: row
+                .get::<_, Option<String>>(6)?
+                .and_then(|json| serde_json::from_str(&json).ok()),
+            row_count: row.get::<_, Option<i64>>(7)?.map(|n| n as usize),
+            execution_time_ms: row.get::<_, Option<i64>>(8)?.map(|n| n as u64),
+            limit_applied: row.get::<_, i32>(9)? != 0,
+            error_message: row.get(10)?,
+            created_at: parse_time(row.get(11)?).unwrap_or_else(chrono::Utc::now),
+            started_at: parse_time(row.get(12)?),
+            finished_at: parse_time(row.get(13)?),
+            rows_processed: row.get::<_, Option<i64>>(14)?.map(|n| n as u64),
+        })
+    }
+
+    // ==================== Webhooks ====================
+
+    /// Store a new webhook
+    pub async fn create_webhook(&self, webhook: &crate::models::Webhook) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT INTO webhooks (id, domain_id, url, secret, events, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                webhook.id,
+                webhook.domain_id,
+                webhook.url,
+                webhook.secret,
+                serde_json::to_string(&webhook.events).unwrap_or_else(|_| "[]".to_string()),
+                webhook.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// List the webhooks of a domain
+    pub async fn list_webhooks(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::Webhook>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, url, secret, events, created_at FROM webhooks WHERE domain_id = ?1 ORDER BY created_at",
+        )?;
+        let webhooks = stmt.query_map(rusqlite::params![domain_id], Self::row_to_webhook)?;
+        webhooks.collect()
+    }
+
+    /// Get a webhook by ID
+    pub async fn get_webhook(&self, id: &str) -> SqliteResult<Option<crate::models::Webhook>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            "SELECT id, domain_id, url, secret, events, created_at FROM webhooks WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![id], Self::row_to_webhook)?;
+        rows.next().transpose()
+    }
+
+    /// Delete a domain's webhook and its deliveries
+    pub async fn delete_webhook(&self, domain_id: &str, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        conn.execute("DELETE FROM webhook_deliveries WHERE webhook_id = ?1", rusqlite::params![id])?;
+        let rows_affected = conn.execute(
+            "DELETE FROM webhooks WHERE id = ?1 AND domain_id = ?2",
+            rusqlite::params![id, domain_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    fn row_to_webhook(row: &rusqlite::Row) -> SqliteResult<crate::models::Webhook> {
+        Ok(crate::models::Webhook {
+            id: row.get(0)?,
+            domain_id: row.get(1)?,
+            url: row.get(2)?,
+            secret: row.get(3)?,
+            events: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        })
+    }
+
+    /// Queue a webhook delivery
+    pub async fn create_webhook_delivery(&self, delivery: &crate::models::WebhookDelivery) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO webhook_deliveries
+            (id, webhook_id, event, payload, status, attempts, next_attempt_at, last_error, created_at, delivered_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+            rusqlite::params![
+                delivery.id,
+                delivery.webhook_id,
+                delivery.event.as_str(),
+                delivery.payload.to_string(),
+                delivery.status.as_str(),
+                delivery.attempts,
+                delivery.next_attempt_at.to_rfc3339(),
+                delivery.last_error,
+                delivery.created_at.to_rfc3339(),
+                delivery.delivered_at.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record the outcome of a delivery attempt
+    pub async fn update_webhook_delivery(&self, delivery: &crate::models::WebhookDelivery) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = ?1, attempts = ?2, next_attempt_at = ?3, last_error = ?4, delivered_at = ?5
+            WHERE id = ?6
+            "#,
+            rusqlite::params![
+                delivery.status.as_str(),
+                delivery.attempts,
+                delivery.next_attempt_at.to_rfc3339(),
+                delivery.last_error,
+                delivery.delivered_at.map(|t| t.to_rfc3339()),
+                delivery.id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first
+    pub async fn due_webhook_deliveries(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::models::WebhookDelivery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, webhook_id, event, payload, status, attempts, next_attempt_at, last_error, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= ?1
+            ORDER BY next_attempt_at
+            LIMIT ?2
+            "#,
+        )?;
+        let deliveries = stmt.query_map(rusqlite::params![now.to_rfc3339(), limit as i64], Self::row_to_webhook_delivery)?;
+        deliveries.collect()
+    }
+
+    /// Most recent deliveries of a webhook
+    pub async fn list_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::models::WebhookDelivery>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, webhook_id, event, payload, status, attempts, next_attempt_at, last_error, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#,
+        )?;
+        let deliveries = stmt.query_map(rusqlite::params![webhook_id, limit as i64], Self::row_to_webhook_delivery)?;
+        deliveries.collect()
+    }
+
+    fn row_to_webhook_delivery(row: &rusqlite::Row) -> SqliteResult<crate::models::WebhookDelivery> {
+        let parse_time = |value: Option<String>| {
+            value.and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        };
+        let event: String = row.get(2)?;
+        let status: String = row.get(4)?;
+        Ok(crate::models::WebhookDelivery {
+            id: row.get(0)?,
+            webhook_id: row.get(1)?,
+            event: crate::models::WebhookEvent::from_str(&event)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into()))?,
+            payload: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+            status: crate::models::WebhookDeliveryStatus::from_str(&status)
+                .unwrap_or(crate::models::WebhookDeliveryStatus::Failed),
+            attempts: row.get(5)?,
+            next_attempt_at: parse_time(row.get(6)?).unwrap_or_else(chrono::Utc::now),
+            last_error: row.get(7)?,
+            created_at: parse_time(row.get(8)?).unwrap_or_else(chrono::Utc::now),
+            delivered_at: parse_time(row.get(9)?),
+        })
+    }
+
+    // ==================== Query Result Cache ====================
+
+    /// Persist a cached query result (JSON), replacing an entry with the same key
+    ///
+    /// Expired entries are purged on the way.
+    pub async fn save_query_cache_entry(
+        &self,
+        cache_key: &str,
+        connection_id: &str,
+        result: &str,
+        cached_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "DELETE FROM query_cache_entries WHERE expires_at <= ?1",
+            rusqlite::params![chrono::Utc::now().to_rfc3339()],
+        )?;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO query_cache_entries (cache_key, connection_id, result, cached_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            rusqlite::params![cache_key, connection_id, result, cached_at.to_rfc3339(), expires_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Unexpired persisted cache entry: result JSON, cached and expiry time
+    pub async fn get_query_cache_entry(
+        &self,
+        cache_key: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<Option<(String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT result, cached_at, expires_at FROM query_cache_entries WHERE cache_key = ?1 AND expires_at > ?2",
+            rusqlite::params![cache_key, now.to_rfc3339()],
+            |row| {
+                let parse_time = |value: String| {
+                    chrono::DateTime::parse_from_rfc3339(&value)
+                        .map(|t| t.with_timezone(&chrono::Utc))
+                        .unwrap_or(now)
+                };
+                Ok((row.get::<_, String>(0)?, parse_time(row.get(1)?), parse_time(row.get(2)?)))
+            },
+        );
+
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete a connection's persisted cache entries, returning their keys
+    pub async fn delete_query_cache_entries(&self, connection_id: &str) -> SqliteResult<Vec<String>> {
+        let conn = self.conn.lock().await;
+        let keys = conn
+            .prepare("SELECT cache_key FROM query_cache_entries WHERE connection_id = ?1")?
+            .query_map(rusqlite::params![connection_id], |row| row.get::<_, String>(0))?
+            .collect::<SqliteResult<Vec<String>>>()?;
+        conn.execute(
+            "DELETE FROM query_cache_entries WHERE connection_id = ?1",
+            rusqlite::params![connection_id],
+        )?;
+        Ok(keys)
+    }
+
+    // ==================== View Materializations ====================
+
+    /// Store a view materialization, replacing the one of the same saved query
+    pub async fn save_view_materialization(&self, materialization: &crate::models::ViewMaterialization) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO view_materializations
+                (saved_query_id, domain_id, refresh_interval_secs, status, row_count, size_bytes, refreshed_at,
+                 attempted_at, last_error, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+            rusqlite::params![
+                materialization.saved_query_id,
+                materialization.domain_id,
+                materialization.refresh_interval_secs.map(|n| n as i64),
+                materialization.status.as_str(),
+                materialization.row_count.map(|n| n as i64),
+                materialization.size_bytes.map(|n| n as i64),
+                materialization.refreshed_at.map(|t| t.to_rfc3339()),
+                materialization.attempted_at.map(|t| t.to_rfc3339()),
+                materialization.last_error,
+                materialization.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the materialization of a saved query
+    pub async fn get_view_materialization(
+        &self,
+        saved_query_id: &str,
+    ) -> SqliteResult<Option<crate::models::ViewMaterialization>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT saved_query_id, domain_id, refresh_interval_secs, status, row_count, size_bytes, refreshed_at,
+                   attempted_at, last_error, created_at
+            FROM view_materializations WHERE saved_query_id = ?1
+            "#,
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![saved_query_id], Self::row_to_view_materialization)?;
+        rows.next().transpose()
+    }
+
+    /// List the materializations of active saved queries, of one domain or of all domains
+    pub async fn list_view_materializations(
+        &self,
+        domain_id: Option<&str>,
+    ) -> SqliteResult<Vec<crate::models::ViewMaterialization>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT m.saved_query_id, m.domain_id, m.refresh_interval_secs, m.status, m.row_count, m.size_bytes,
+                   m.refreshed_at, m.attempted_at, m.last_error, m.created_at
+            FROM view_materializations m
+            JOIN saved_queries q ON q.id = m.saved_query_id AND q.deleted_at IS NULL
+            WHERE ?1 IS NULL OR m.domain_id = ?1
+            ORDER BY m.created_at
+            "#,
+        )?;
+        let rows = stmt.query_map(rusqlite::params![domain_id], Self::row_to_view_materialization)?;
+        rows.collect()
+    }
+
+    /// Delete the materialization of a saved query; false if it had none
+    pub async fn delete_view_materialization(&self, saved_query_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute(
+            "DELETE FROM view_materializations WHERE saved_query_id = ?1",
+            rusqlite::params![saved_query_id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    fn row_to_view_materialization(row: &rusqlite::Row) -> SqliteResult<crate::models::ViewMaterialization> {
+        let parse_time = |value: Option<String>| {
+            value.and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        };
+        let status: String = row.get(3)?;
+        Ok(crate::models::ViewMaterialization {
+            saved_query_id: row.get(0)?,
+            domain_id: row.get(1)?,
+            refresh_interval_secs: row.get::<_, Option<i64>>(2)?.map(|n| n as u64),
+            status: crate::models::MaterializationStatus::from_str(&status)
+                .unwrap_or(crate::models::MaterializationStatus::Failed),
+            row_count: row.get::<_, Option<i64>>(4)?.map(|n| n as u64),
+            size_bytes: row.get::<_, Option<i64>>(5)?.map(|n| n as u64),
+            refreshed_at: parse_time(row.get(6)?),
+            attempted_at: parse_time(row.get(7)?),
+            last_error: row.get(8)?,
+            created_at: parse_time(row.get(9)?).unwrap_or_else(chrono::Utc::now),
+        })
+    }
+
+    // ==================== Chat Sessions ====================
+
+    /// Create a chat session
+    pub async fn create_chat_session(&self, session: &crate::models::ChatSession) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO chat_sessions (id, connection_id, domain_id, created_by, title, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            rusqlite::params![
+                session.id,
+                session.connection_id,
+                session.domain_id,
+                session.created_by,
+                session.title,
+                session.created_at.to_rfc3339(),
+                session.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a chat session by ID
+    pub async fn get_chat_session(&self, id: &str) -> SqliteResult<Option<crate::models::ChatSession>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, connection_id, domain_id, created_by, title, created_at, updated_at
+            FROM chat_sessions WHERE id = ?1
+            "#,
+        )?;
+        let mut rows = stmt.query_map(rusqlite::params![id], |row| {
+            let parse_time = |value: String| {
+                chrono::DateTime::parse_from_rfc3339(&value)
+                    .map(|t| t.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(|_| chrono::Utc::now())
+            };
+            Ok(crate::models::ChatSession {
+                id: row.get(0)?,
+                connection_id: row.get(1)?,
+                domain_id: row.get(2)?,
+                created_by: row.get(3)?,
+                title: row.get(4)?,
+                created_at: parse_time(row.get(5)?),
+                updated_at: parse_time(row.get(6)?),
+            })
+        })?;
+        rows.next().transpose()
+    }
+
+    /// Delete a chat session with its turns, returning whether it existed
+    pub async fn delete_chat_session(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute("DELETE FROM chat_sessions WHERE id = ?1", rusqlite::params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Append a turn to its chat session, updating the session's `updated_at`
+    pub async fn add_chat_turn(&self, turn: &crate::models::ChatTurn) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            r#"
+            INSERT INTO chat_turns (id, session_id, question, generated_sql, result_summary, error_message, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            rusqlite::params![
+                turn.id,
+                turn.session_id,
+                turn.question,
+                turn.generated_sql,
+                turn.result_summary,
+                turn.error_message,
+                turn.created_at.to_rfc3339(),
+            ],
+        )?;
+        tx.execute(
+            "UPDATE chat_sessions SET updated_at = ?1 WHERE id = ?2",
+            rusqlite::params![turn.created_at.to_rfc3339(), turn.session_id],
+        )?;
+        tx.commit()
+    }
+
+    /// Turns of a chat session, oldest first
+    pub async fn list_chat_turns(&self, session_id: &str) -> SqliteResult<Vec<crate::models::ChatTurn>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT id, session_id, question, generated_sql, result_summary, error_message, created_at
+            FROM chat_turns WHERE session_id = ?1
+            ORDER BY created_at, rowid
+            "#,
+        )?;
+        let turns = stmt
+            .query_map(rusqlite::params![session_id], |row| {
+                Ok(crate::models::ChatTurn {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    question: row.get(2)?,
+                    generated_sql: row.get(3)?,
+                    result_summary: row.get(4)?,
+                    error_message: row.get(5)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                        .map(|t| t.with_timezone(&chrono::Utc))
+                        .unwrap_or_else(|_| chrono::Utc::now()),
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(turns)
+    }
+
+    // ==================== SQL Feedback ====================
+
+    /// Columns of `sql_feedback` in the order `row_to_sql_feedback` reads them
+    const SQL_FEEDBACK_COLUMNS: &'static str = "id, domain_id, connection_id, database_type, question, generated_sql, corrected_sql, rating, comment, created_by, created_at";
+
+    /// Store a rating or correction of generated SQL
+    pub async fn add_sql_feedback(&self, feedback: &crate::models::SqlFeedback) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO sql_feedback (id, domain_id, connection_id, database_type, question, generated_sql, corrected_sql, rating, comment, created_by, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            "#,
+            rusqlite::params![
+                feedback.id,
+                feedback.domain_id,
+                feedback.connection_id,
+                feedback.database_type,
+                feedback.question,
+                feedback.generated_sql,
+                feedback.corrected_sql,
+                feedback.rating.as_str(),
+                feedback.comment,
+                feedback.created_by,
+                feedback.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get SQL feedback by ID
+    pub async fn get_sql_feedback(&self, id: &str) -> SqliteResult<Option<crate::models::SqlFeedback>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM sql_feedback WHERE id = ?1", Self::SQL_FEEDBACK_COLUMNS))?;
+        let mut rows = stmt.query_map(rusqlite::params![id], Self::row_to_sql_feedback)?;
+        rows.next().transpose()
+    }
+
+    /// Delete SQL feedback, returning whether it existed
+    pub async fn delete_sql_feedback(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let rows_affected = conn.execute("DELETE FROM sql_feedback WHERE id = ?1", rusqlite::params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Feedback of a domain that makes examples (rated up or corrected) for a
+    /// database type, newest first
+    pub async fn list_sql_examples(
+        &self,
+        domain_id: &str,
+        database_type: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<crate::models::SqlFeedback>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {} FROM sql_feedback
+            WHERE domain_id = ?1 AND database_type = ?2 AND (rating = 'up' OR corrected_sql IS NOT NULL)
+            ORDER BY created_at DESC, rowid DESC
+            LIMIT ?3
+            "#,
+            Self::SQL_FEEDBACK_COLUMNS
+        ))?;
+        let feedback = stmt
+            .query_map(rusqlite::params![domain_id, database_type, limit as i64], Self::row_to_sql_feedback)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(feedback)
+    }
+
+    fn row_to_sql_feedback(row: &rusqlite::Row) -> rusqlite::Result<crate::models::SqlFeedback> {
+        Ok(crate::models::SqlFeedback {
+            id: row.get(0)?,
+            domain_id: row.get(1)?,
+            connection_id: row.get(2)?,
+            database_type: row.get(3)?,
+            question: row.get(4)?,
+            generated_sql: row.get(5)?,
+            corrected_sql: row.get(6)?,
+            rating: crate::models::FeedbackRating::parse(&row.get::<_, String>(7)?),
+            comment: row.get(8)?,
+            created_by: row.get(9)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(10)?)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        })
+    }
+
+    // ==================== LLM Audit Log ====================
+
+    /// Columns of `llm_audit_log` in the order `row_to_llm_audit_entry` reads them
+    const LLM_AUDIT_COLUMNS: &'static str = "id, domain_id, connection_id, principal, purpose, prompt, redactions, created_at";
+
+    /// Record a prompt sent to the LLM gateway
+    pub async fn add_llm_audit_entry(&self, entry: &crate::models::LlmAuditEntry) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO llm_audit_log (id, domain_id, connection_id, principal, purpose, prompt, redactions, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            rusqlite::params![
+                entry.id,
+                entry.domain_id,
+                entry.connection_id,
+                entry.principal,
+                entry.purpose,
+                entry.prompt,
+                serde_json::to_string(&entry.redactions).unwrap_or_else(|_| "{}".to_string()),
+                entry.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// List one page of the prompts sent for a domain
+    pub async fn list_llm_audit_page(
+        &self,
+        domain_id: &str,
+        query: &crate::models::ListQuery,
+    ) -> SqliteResult<(Vec<crate::models::LlmAuditEntry>, u64)> {
+        let mut conditions = vec!["domain_id = ?".to_string()];
+        let mut params = vec![domain_id.to_string()];
+        Self::push_text_filter(&["purpose", "principal", "prompt"], query, &mut conditions, &mut params);
+
+        let conn = self.conn.lock().await;
+        Self::fetch_page(
+            &conn,
+            &format!("SELECT {} FROM llm_audit_log", Self::LLM_AUDIT_COLUMNS),
+            "SELECT COUNT(*) FROM llm_audit_log",
+            &conditions,
+            &params,
+            query,
+            Self::row_to_llm_audit_entry,
+        )
+    }
+
+    fn row_to_llm_audit_entry(row: &rusqlite::Row) -> rusqlite::Result<crate::models::LlmAuditEntry> {
+        Ok(crate::models::LlmAuditEntry {
+            id: row.get(0)?,
+            domain_id: row.get(1)?,
+            connection_id: row.get(2)?,
+            principal: row.get(3)?,
+            purpose: row.get(4)?,
+            prompt: row.get(5)?,
+            redactions: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        })
+    }
+
+    // ==================== Data Dictionary ====================
+
+    /// Add or replace the description and tags of a table or column
+    pub async fn upsert_dictionary_entry(&self, entry: &crate::models::DictionaryEntry) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT INTO data_dictionary (connection_id, schema_name, table_name, column_name, description, tags, updated_by, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(connection_id, schema_name, table_name, column_name) DO UPDATE SET
+                description = excluded.description,
+                tags = excluded.tags,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+            "#,
+            rusqlite::params![
+                entry.connection_id,
+                entry.schema.as_deref().unwrap_or(""),
+                entry.table,
+                entry.column.as_deref().unwrap_or(""),
+                entry.description,
+                serde_json::to_string(&entry.tags).unwrap_or_else(|_| "[]".to_string()),
+                entry.updated_by,
+                entry.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Data dictionary of a connection, by schema, table and column
+    pub async fn list_dictionary_entries(&self, connection_id: &str) -> SqliteResult<Vec<crate::models::DictionaryEntry>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT connection_id, schema_name, table_name, column_name, description, tags, updated_by, updated_at
+            FROM data_dictionary
+            WHERE connection_id = ?1
+            ORDER BY schema_name, table_name, column_name
+            "#,
+        )?;
+        let entries = stmt
+            .query_map(rusqlite::params![connection_id], Self::row_to_dictionary_entry)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Remove the entry of a table (`column` None) or column; returns whether it existed
+    pub async fn delete_dictionary_entry(
+        &self,
+        connection_id: &str,
+        schema: Option<&str>,
+        table: &str,
+        column: Option<&str>,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let deleted = conn.execute(
+            "DELETE FROM data_dictionary WHERE connection_id = ?1 AND schema_name = ?2 AND table_name = ?3 AND column_name = ?4",
+            rusqlite::params![connection_id, schema.unwrap_or(""), table, column.unwrap_or("")],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    fn row_to_dictionary_entry(row: &rusqlite::Row) -> rusqlite::Result<crate::models::DictionaryEntry> {
+        let non_empty = |name: String| (!name.is_empty()).then_some(name);
+        Ok(crate::models::DictionaryEntry {
+            connection_id: row.get(0)?,
+            schema: non_empty(row.get(1)?),
+            table: row.get(2)?,
+            column: non_empty(row.get(3)?),
+            description: row.get(4)?,
+            tags: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+            updated_by: row.get(6)?,
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        })
+    }
+
+    // ==================== Table Profiles ====================
+
+    /// Store a table's profile, replacing the previous one
+    pub async fn save_table_profile(&self, profile: &crate::models::TableProfile) -> SqliteResult<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO table_profiles (connection_id, schema_name, table_name, metadata_version, profile, profiled_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            rusqlite::params![
+                profile.connection_id,
+                profile.schema,
+                profile.table,
+                profile.metadata_version,
+                serde_json::to_string(profile).unwrap_or_default(),
+                profile.profiled_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Latest profile of a table, whatever metadata version it was computed against
+    pub async fn get_table_profile(
+        &self,
+        connection_id: &str,
+        schema: &str,
+        table: &str,
+    ) -> SqliteResult<Option<crate::models::TableProfile>> {
+        let conn = self.conn.lock().await;
+        let profile = match conn.query_row(
+            "SELECT profile FROM table_profiles WHERE connection_id = ?1 AND schema_name = ?2 AND table_name = ?3",
+            rusqlite::params![connection_id, schema, table],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(profile) => profile,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // A profile written by an older release that no longer parses is recomputed
+        Ok(serde_json::from_str(&profile).ok())
+    }
+
+    // ==================== Paginated Listings ====================
+
+    /// Sortable fields of `GET /api/connections`
+    pub const CONNECTION_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("name", "name COLLATE NOCASE"),
+        ("database_type", "database_type"),
+        ("status", "status"),
+        ("created_at", "created_at"),
+        ("last_connected_at", "last_connected_at"),
+    ];
+
+    /// Sortable fields of `GET /api/domains`
+    pub const DOMAIN_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("name", "name COLLATE NOCASE"),
+        ("created_at", "created_at"),
+        ("updated_at", "updated_at"),
+        ("connection_count", "connection_count"),
+    ];
+
+    /// Sortable fields of saved query listings
+    pub const SAVED_QUERY_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("name", "name COLLATE NOCASE"),
+        ("created_at", "created_at"),
+        ("updated_at", "updated_at"),
+    ];
+
+    /// Sortable fields of SQL feedback listings
+    pub const SQL_FEEDBACK_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("created_at", "created_at"),
+        ("rating", "rating"),
+    ];
+
+    /// Sortable fields of LLM audit log listings
+    pub const LLM_AUDIT_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("created_at", "created_at"),
+        ("purpose", "purpose"),
+    ];
+
+    /// Sortable fields of query history listings
+    pub const HISTORY_SORT_FIELDS: &'static [crate::models::SortField] = &[
+        ("executed_at", "executed_at"),
+        ("execution_time_ms", "execution_time_ms"),
+        ("row_count", "row_count"),
+    ];
+
+    /// Run a filtered listing, returning one page and the total number of matches
+    ///
+    /// `select` and `count` are the `SELECT ... FROM ...` heads of the page and
+    /// count queries; both get the same WHERE `conditions` (joined with AND),
+    /// bound to `params` in order.
+    fn fetch_page<T>(
+        conn: &Connection,
+        select: &str,
+        count: &str,
+        conditions: &[String],
+        params: &[String],
+        query: &crate::models::ListQuery,
+        map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+    ) -> SqliteResult<(Vec<T>, u64)> {
+        let where_sql = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let total: i64 = conn.query_row(
+            &format!("{}{}", count, where_sql),
+            rusqlite::params_from_iter(params),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!("{}{}{}", select, where_sql, query.page_clause()))?;
+        let items = stmt
+            .query_map(rusqlite::params_from_iter(params), map)?
+            .collect::<SqliteResult<Vec<T>>>()?;
+        Ok((items, total as u64))
+    }
+
+    /// Add the `column IN (...)` restriction for a set of visible domains
+    ///
+    /// Returns false if no domain is visible, in which case the listing is empty.
+    fn push_domain_scope(
+        column: &str,
+        domain_ids: Option<&[String]>,
+        conditions: &mut Vec<String>,
+        params: &mut Vec<String>,
+    ) -> bool {
+        match domain_ids {
+            None => true,
+            Some([]) => false,
+            Some(ids) => {
+                let placeholders = vec!["?"; ids.len()].join(", ");
+                conditions.push(format!("{} IN ({})", column, placeholders));
+                params.extend(ids.iter().cloned());
+                true
+            }
+        }
+    }
+
+    /// Add a case-insensitive substring match on any of `columns`
+    fn push_text_filter(
+        columns: &[&str],
+        query: &crate::models::ListQuery,
+        conditions: &mut Vec<String>,
+        params: &mut Vec<String>,
+    ) {
+        if let Some(pattern) = query.filter_pattern() {
+            let matches: Vec<String> = columns
+                .iter()
+                .map(|column| format!("{} LIKE ? ESCAPE '\\'", column))
+                .collect();
+            conditions.push(format!("({})", matches.join(" OR ")));
+            params.extend(columns.iter().map(|_| pattern.clone()));
+        }
+    }
+
+    /// List one page of connections, optionally restricted to some domains and labels
+    pub async fn list_connections_page(
+        &self,
+        query: &crate::models::ListQuery,
+        domain_ids: Option<&[String]>,
+        labels: Option<&crate::models::LabelSelector>,
+        tags: &[String],
+    ) -> SqliteResult<(Vec<crate::models::DatabaseConnection>, u64)> {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut params = Vec::new();
+        if !Self::push_domain_scope("domain_id", domain_ids, &mut conditions, &mut params) {
+            return Ok((Vec::new(), 0));
+        }
+        for (key, value) in labels.map(|selector| &selector.0).into_iter().flatten() {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(connections.labels) l WHERE l.key = ? AND l.value = ?)".to_string(),
+            );
+            params.push(key.clone());
+            params.push(value.clone());
+        }
+        for tag in tags {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM connection_tags t WHERE t.connection_id = connections.id AND t.tag = ?)".to_string(),
+            );
+            params.push(tag.clone());
+        }
+        Self::push_text_filter(&["name", "database_type"], query, &mut conditions, &mut params);
+
+        let db_conn = self.conn.lock().await;
+        Self::fetch_page(
+            &db_conn,
+            "SELECT id, name, connection_url, database_type, domain_id, status, created_at, last_connected_at, metadata_cache_id, version, read_only, labels, allow_writes, pool_settings, (SELECT json_group_array(tag) FROM (SELECT tag FROM connection_tags t WHERE t.connection_id = connections.id ORDER BY tag)) FROM connections",
+            "SELECT COUNT(*) FROM connections",
+            &conditions,
+            &params,
+            query,
+            Self::row_to_connection,
+        )
+    }
+
+    /// List one page of domains with connection counts, optionally restricted to some domains
+    pub async fn list_domains_page(
+        &self,
+        query: &crate::models::ListQuery,
+        domain_ids: Option<&[String]>,
+    ) -> SqliteResult<(Vec<crate::models::DomainResponse>, u64)> {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut params = Vec::new();
+        if !Self::push_domain_scope("id", domain_ids, &mut conditions, &mut params) {
+            return Ok((Vec::new(), 0));
+        }
+        Self::push_text_filter(&["name", "description"], query, &mut conditions, &mut params);
+
+        let db_conn = self.conn.lock().await;
+        Self::fetch_page(
+            &db_conn,
+            r#"
+            SELECT
+                id, name, description, created_at, updated_at, version,
+                (SELECT COUNT(*) FROM connections c WHERE c.domain_id = domains.id AND c.deleted_at IS NULL) AS connection_count
+            FROM domains
+            "#,
+            "SELECT COUNT(*) FROM domains",
+            &conditions,
+            &params,
+            query,
+            |row| {
+                Ok(crate::models::DomainResponse {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    version: row.get(5)?,
+                    connection_count: row.get::<_, i64>(6)? as usize,
+                    saved_query_count: 0,
+                    query_history_count: 0,
+                })
+            },
+        )
+    }
+
+    /// List one page of a domain's saved queries
+    pub async fn list_saved_queries_page(
+        &self,
+        domain_id: &str,
+        query: &crate::models::ListQuery,
+    ) -> SqliteResult<(Vec<crate::models::SavedQuery>, u64)> {
+        let mut conditions = vec!["domain_id = ?".to_string(), "deleted_at IS NULL".to_string()];
+        let mut params = vec![domain_id.to_string()];
+        Self::push_text_filter(&["name", "query_text", "description"], query, &mut conditions, &mut params);
+
+        let conn = self.conn.lock().await;
+        Self::fetch_page(
+            &conn,
+            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at, version, transform, parameters, connection_ids, database_aliases, view_name, is_broken, breakage_reason FROM saved_queries",
+            "SELECT COUNT(*) FROM saved_queries",
+            &conditions,
+            &params,
+            query,
+            Self::row_to_saved_query,
+        )
+    }
+
+    /// List one page of a domain's SQL feedback
+    pub async fn list_sql_feedback_page(
+        &self,
+        domain_id: &str,
+        query: &crate::models::ListQuery,
+    ) -> SqliteResult<(Vec<crate::models::SqlFeedback>, u64)> {
+        let mut conditions = vec!["domain_id = ?".to_string()];
+        let mut params = vec![domain_id.to_string()];
+        Self::push_text_filter(&["question", "generated_sql", "corrected_sql"], query, &mut conditions, &mut params);
+
+        let conn = self.conn.lock().await;
+        Self::fetch_page(
+            &conn,
+            &format!("SELECT {} FROM sql_feedback", Self::SQL_FEEDBACK_COLUMNS),
+            "SELECT COUNT(*) FROM sql_feedback",
+            &conditions,
+            &params,
+            query,
+            Self::row_to_sql_feedback,
+        )
+    }
+
+    /// List one page of a domain's query history, optionally for a single connection
+    pub async fn list_query_history_page(
+        &self,
+        domain_id: &str,
+        connection_id: Option<&str>,
+        query: &crate::models::ListQuery,
+        filter: &crate::models::HistoryFilterParams,
+    ) -> SqliteResult<(Vec<crate::models::QueryHistory>, u64)> {
+        let (conditions, params) = Self::history_conditions(domain_id, connection_id, query, filter);
+
+        let conn = self.conn.lock().await;
+        Self::fetch_page(
+            &conn,
+            "SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated, executed_by, kind, connection_ids, database_aliases, auto_repaired, saved_query_id, is_slow, fingerprint, EXISTS (SELECT 1 FROM query_history_results r WHERE r.history_id = query_history.id) FROM query_history",
+            "SELECT COUNT(*) FROM query_history",
+            &conditions,
+            &params,
+            query,
+            |row| {
+                Ok(crate::models::QueryHistory {
+                    id: row.get(0)?,
+                    domain_id: row.get(1)?,
+                    connection_id: row.get(2)?,
+                    query_text: row.get(3)?,
+                    row_count: row.get::<_, i64>(4)? as usize,
+                    execution_time_ms: row.get::<_, i64>(5)? as u64,
+                    status: match row.get::<_, String>(6)?.as_str() {
+                        "success" => crate::models::QueryHistoryStatus::Success,
+                        _ => crate::models::QueryHistoryStatus::Failed,
+                    },
+                    error_message: row.get(7)?,
+                    executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                    is_llm_generated: row.get::<_, i32>(9)? == 1,
+                    executed_by: row.get(10)?,
+                    kind: crate::models::QueryKind::from_str(&row.get::<_, String>(11)?),
+                    connection_ids: Self::json_column(row, 12)?.unwrap_or_default(),
+                    database_aliases: Self::json_column(row, 13)?,
+                    auto_repaired: row.get::<_, i32>(14)? == 1,
+                    saved_query_id: row.get(15)?,
+                    is_slow: row.get::<_, i32>(16)? == 1,
+                    fingerprint: row.get::<_, Option<String>>(17)?.unwrap_or_default(),
+                    has_result: row.get::<_, i32>(18)? == 1,
+                })
+            },
+        )
+    }
+
+    /// WHERE conditions and parameters selecting the history entries of a
+    /// domain (or one of its connections) matching the text and filters
+    fn history_conditions(
+        domain_id: &str,
+        connection_id: Option<&str>,
+        query: &crate::models::ListQuery,
+        filter: &crate::models::HistoryFilterParams,
+    ) -> (Vec<String>, Vec<String>) {
+        let mut conditions = vec!["domain_id = ?".to_string()];
+        let mut params = vec![domain_id.to_string()];
+        if let Some(connection_id) = connection_id {
+            // Cross-database queries are listed with each of their connections
+            conditions.push(
+                "(connection_id = ? OR EXISTS (SELECT 1 FROM json_each(connection_ids) WHERE value = ?))".to_string(),
+            );
+            params.push(connection_id.to_string());
+            params.push(connection_id.to_string());
+        }
+        Self::push_text_filter(&["query_text"], query, &mut conditions, &mut params);
+
+        if let Some(status) = &filter.status {
+            conditions.push("status = ?".to_string());
+            params.push(format!("{:?}", status).to_lowercase());
+        }
+        if let Some(is_llm_generated) = filter.is_llm_generated {
+            conditions.push("is_llm_generated = CAST(? AS INTEGER)".to_string());
+            params.push((is_llm_generated as i32).to_string());
+        }
+        if let Some(since) = filter.since {
+            conditions.push("executed_at >= ?".to_string());
+            params.push(since.to_rfc3339());
+        }
+        if let Some(until) = filter.until {
+            conditions.push("executed_at <= ?".to_string());
+            params.push(until.to_rfc3339());
+        }
+        if let Some(min_execution_time_ms) = filter.min_execution_time_ms {
+            conditions.push("execution_time_ms >= CAST(? AS INTEGER)".to_string());
+            params.push(min_execution_time_ms.to_string());
+        }
+        if let Some(is_slow) = filter.is_slow {
+            conditions.push("is_slow = CAST(? AS INTEGER)".to_string());
+            params.push((is_slow as i32).to_string());
+        }
+        if let Some(fingerprint) = &filter.fingerprint {
+            conditions.push("fingerprint = ?".to_string());
+            params.push(fingerprint.clone());
+        }
+        (conditions, params)
+    }
+
+    /// Per-day counts and latency percentiles of the history entries that
+    /// `list_query_history_page` would list, oldest day first
+    ///
+    /// Percentiles use the nearest-rank method: the value at rank
+    /// ceil(p * n) of the day's execution times.
+    pub async fn history_daily_stats(
+        &self,
+        domain_id: &str,
+        connection_id: Option<&str>,
+        query: &crate::models::ListQuery,
+        filter: &crate::models::HistoryFilterParams,
+    ) -> SqliteResult<Vec<crate::models::HistoryDailyStats>> {
+        let (conditions, params) = Self::history_conditions(domain_id, connection_id, query, filter);
+        let sql = format!(
+            r#"
+            WITH ranked AS (
+                SELECT substr(executed_at, 1, 10) AS day, status, execution_time_ms,
+                       ROW_NUMBER() OVER (PARTITION BY substr(executed_at, 1, 10) ORDER BY execution_time_ms) AS pos,
+                       COUNT(*) OVER (PARTITION BY substr(executed_at, 1, 10)) AS n
+                FROM query_history
+                WHERE {}
+            )
+            SELECT day, COUNT(*), SUM(status = 'failed'), AVG(execution_time_ms),
+                   MAX(CASE WHEN pos = (n + 1) / 2 THEN execution_time_ms END),
+                   MAX(CASE WHEN pos = (95 * n + 99) / 100 THEN execution_time_ms END)
+            FROM ranked
+            GROUP BY day
+            ORDER BY day
+            "#,
+            conditions.join(" AND ")
+        );
+
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(&sql)?;
+        let days = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                let day: String = row.get(0)?;
+                Ok(crate::models::HistoryDailyStats {
+                    day: chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d").map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+                    })?,
+                    queries: row.get::<_, i64>(1)? as u64,
+                    failed: row.get::<_, i64>(2)? as u64,
+                    avg_execution_time_ms: row.get(3)?,
+                    p50_execution_time_ms: row.get::<_, i64>(4)? as u64,
+                    p95_execution_time_ms: row.get::<_, i64>(5)? as u64,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(days)
+    }
+
+    fn row_to_connection(row: &rusqlite::Row) -> rusqlite::Result<crate::models::DatabaseConnection> {
+        Ok(crate::models::DatabaseConnection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            connection_url: row.get(2)?,
+            database_type: row.get(3)?,
+            domain_id: row.get(4)?,
+            status: match row.get::<_, String>(5)?.as_str() {
+                "connected" => crate::models::ConnectionStatus::Connected,
+                "error" => crate::models::ConnectionStatus::Error,
+                _ => crate::models::ConnectionStatus::Disconnected,
+            },
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            last_connected_at: row.get::<_, Option<String>>(7)?
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+            metadata_cache_id: row.get(8)?,
+            version: row.get(9)?,
+            read_only: row.get(10)?,
+            labels: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_default(),
+            allow_writes: row.get(12)?,
+            pool_settings: serde_json::from_str(&row.get::<_, String>(13)?).unwrap_or_default(),
+            tags: serde_json::from_str(&row.get::<_, String>(14)?).unwrap_or_default(),
+        })
+    }
+
+    // ============================================================================
+    // Trash Operations (Soft-Deleted Resources)
+    // ============================================================================
+
+    /// List all soft-deleted domains, connections and saved queries (newest first)
+    pub async fn list_trash(&self) -> SqliteResult<Vec<crate::models::TrashItem>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT 'domain', id, name, NULL, deleted_at FROM domains WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'connection', id, name, domain_id, deleted_at FROM connections WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'saved_query', id, name, domain_id, deleted_at FROM saved_queries WHERE deleted_at IS NOT NULL
+            ORDER BY 5 DESC
+            "#
+        )?;
+
+        let items = stmt.query_map([], Self::row_to_trash_item)?;
+        items.collect()
+    }
+
+    /// Get a single soft-deleted resource
+    pub async fn get_trash_item(
+        &self,
+        resource_type: crate::models::TrashResourceType,
+        id: &str,
+    ) -> SqliteResult<Option<crate::models::TrashItem>> {
+        let conn = self.conn.lock().await;
+        let domain_column = match resource_type {
+            crate::models::TrashResourceType::Domain => "NULL",
+            _ => "domain_id",
+        };
+        let sql = format!(
+            "SELECT ?2, id, name, {}, deleted_at FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL",
+            domain_column,
+            resource_type.table_name()
+        );
+
+        let result = conn.query_row(
+            &sql,
+            rusqlite::params![id, resource_type.as_str()],
+            Self::row_to_trash_item,
+        );
+
+        match result {
+            Ok(item) => Ok(Some(item)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Trashed domain holding a name (domain names stay taken while in the trash)
+    pub async fn trashed_domain_named(&self, name: &str) -> SqliteResult<Option<crate::models::TrashItem>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            "SELECT 'domain', id, name, NULL, deleted_at FROM domains WHERE name = ?1 AND deleted_at IS NOT NULL",
+            rusqlite::params![name],
+            Self::row_to_trash_item,
+        );
+
+        match result {
+            Ok(item) => Ok(Some(item)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Trashed saved query of a domain holding a name
+    pub async fn trashed_saved_query_named(
+        &self,
+        domain_id: &str,
+        name: &str,
+    ) -> SqliteResult<Option<crate::models::TrashItem>> {
+        let conn = self.conn.lock().await;
+        let result = conn.query_row(
+            r#"
+            SELECT 'saved_query', id, name, domain_id, deleted_at FROM saved_queries
+            WHERE domain_id = ?1 AND name = ?2 AND deleted_at IS NOT NULL
+            "#,
+            rusqlite::params![domain_id, name],
+            Self::row_to_trash_item,
+        );
+
+        match result {
+            Ok(item) => Ok(Some(item)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Trashed connections a saved query (trashed or not) runs on
+    pub async fn trashed_connections_of_saved_query(&self, id: &str) -> SqliteResult<Vec<String>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT c.id FROM saved_queries q
+            JOIN connections c
+              ON c.id = q.connection_id OR c.id IN (SELECT value FROM json_each(q.connection_ids))
+            WHERE q.id = ?1 AND c.deleted_at IS NOT NULL
+            ORDER BY c.id
+            "#,
+        )?;
+
+        let ids = stmt.query_map(rusqlite::params![id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    fn row_to_trash_item(row: &rusqlite::Row<'_>) -> SqliteResult<crate::models::TrashItem> {
+        let resource_type = crate::models::TrashResourceType::from_str(&row.get::<_, String>(0)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                e.into(),
+            ))?;
+
+        Ok(crate::models::TrashItem {
+            resource_type,
+            id: row.get(1)?,
+            name: row.get(2)?,
+            domain_id: row.get(3)?,
+            deleted_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            purge_after: None,
+        })
+    }
+
+    /// Restore a soft-deleted resource
+    ///
+    /// Restoring a domain also restores the connections and saved queries that
+    /// were trashed together with it. Returns false if the item is not in the trash.
+    pub async fn restore_trash_item(
+        &self,
+        resource_type: crate::models::TrashResourceType,
+        id: &str,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let tx = conn.unchecked_transaction()?;
+
+        let deleted_at: Option<String> = match tx.query_row(
+            &format!(
+                "SELECT deleted_at FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL",
+                resource_type.table_name()
+            ),
+            rusqlite::params![id],
+            |row| row.get(0),
+        ) {
+            Ok(deleted_at) => Some(deleted_at),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+
+        let Some(deleted_at) = deleted_at else {
+            return Ok(false);
+        };
+
+        tx.execute(
+            &format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1", resource_type.table_name()),
+            rusqlite::params![id],
+        )?;
+
+        if resource_type == crate::models::TrashResourceType::Domain {
+            tx.execute(
+                "UPDATE connections SET deleted_at = NULL WHERE domain_id = ?1 AND deleted_at = ?2",
+                rusqlite::params![id, deleted_at],
+            )?;
+            tx.execute(
+                "UPDATE saved_queries SET deleted_at = NULL WHERE domain_id = ?1 AND deleted_at = ?2",
+                rusqlite::params![id, deleted_at],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Permanently delete a single soft-deleted resource
+    ///
+    /// Returns false if the item is not in the trash.
+    pub async fn purge_trash_item(
+        &self,
+        resource_type: crate::models::TrashResourceType,
+        id: &str,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().await;
+        let tx = conn.unchecked_transaction()?;
+
+        // Rows referencing a purged connection cannot be SET NULL (NOT NULL columns),
+        // so dependent history and saved queries are removed explicitly first.
+        match resource_type {
+            crate::models::TrashResourceType::Domain => {
+                tx.execute(
+                    "DELETE FROM query_history WHERE domain_id = ?1 AND EXISTS (SELECT 1 FROM domains WHERE id = ?1 AND deleted_at IS NOT NULL)",
+                    rusqlite::params![id],
+                )?;
+                tx.execute(
+                    "DELETE FROM saved_queries WHERE domain_id = ?1 AND EXISTS (SELECT 1 FROM domains WHERE id = ?1 AND deleted_at IS NOT NULL)",
+                    rusqlite::params![id],
+                )?;
+            }
+            crate::models::TrashResourceType::Connection => {
+                tx.execute(
+                    "DELETE FROM query_history WHERE connection_id = ?1 AND EXISTS (SELECT 1 FROM connections WHERE id = ?1 AND deleted_at IS NOT NULL)",
+                    rusqlite::params![id],
+                )?;
+                tx.execute(
+                    "DELETE FROM saved_queries WHERE connection_id = ?1 AND EXISTS (SELECT 1 FROM connections WHERE id = ?1 AND deleted_at IS NOT NULL)",
+                    rusqlite::params![id],
+                )?;
+            }
+            crate::models::TrashResourceType::SavedQuery => {}
+        }
+
+        let rows_affected = tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL",
+                resource_type.table_name()
+            ),
+            rusqlite::params![id],
+        )?;
+
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Permanently delete all resources trashed before the cutoff
+    ///
+    /// Returns the number of trashed domains, connections and saved queries removed.
+    pub async fn purge_trash_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> SqliteResult<usize> {
+        let conn = self.conn.lock().await;
+        let cutoff = cutoff.to_rfc3339();
+        let tx = conn.unchecked_transaction()?;
+
+        let saved_queries = tx.execute(
+            "DELETE FROM saved_queries WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            rusqlite::params![cutoff],
+        )?;
+
+        tx.execute(
+            r#"
+            DELETE FROM query_history WHERE connection_id IN
+                (SELECT id FROM connections WHERE deleted_at IS NOT NULL AND deleted_at < ?1)
+            OR domain_id IN
+                (SELECT id FROM domains WHERE deleted_at IS NOT NULL AND deleted_at < ?1)
+            "#,
+            rusqlite::params![cutoff],
+        )?;
+        tx.execute(
+            r#"
+            DELETE FROM saved_queries WHERE connection_id IN
+                (SELECT id FROM connections WHERE deleted_at IS NOT NULL AND deleted_at < ?1)
+            OR domain_id IN
+                (SELECT id FROM domains WHERE deleted_at IS NOT NULL AND deleted_at < ?1)
+            "#,
+            rusqlite::params![cutoff],
+        )?;
+
+        let connections = tx.execute(
+            "DELETE FROM connections WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            rusqlite::params![cutoff],
+        )?;
+        let domains = tx.execute(
+            "DELETE FROM domains WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            rusqlite::params![cutoff],
+        )?;
+
+        tx.commit()?;
+        Ok(saved_queries + connections + domains)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sqlite_storage_creation() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await
+        });
+        assert!(storage.is_ok());
+    }
+
+    #[test]
+    fn test_schema_initialization() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Verify tables exist
+        let conn = rt.block_on(async {
+            storage.conn.lock().await
+        });
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type='table' AND name IN ('domains', 'connections', 'metadata_cache')"
+        ).unwrap();
+
+        let tables: Vec<String> = stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert_eq!(tables.len(), 3);
+        assert!(tables.contains(&"domains".to_string()));
+        assert!(tables.contains(&"connections".to_string()));
+        assert!(tables.contains(&"metadata_cache".to_string()));
+
+        // Verify default domain exists
+        let mut stmt = conn.prepare("SELECT id, name FROM domains WHERE id = 'default-domain-id'").unwrap();
+        let default_domain: Result<(String, String), _> = stmt.query_row([], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        });
+
+        assert!(default_domain.is_ok());
+        let (id, name) = default_domain.unwrap();
+        assert_eq!(id, "default-domain-id");
+        assert_eq!(name, "Default Domain");
+    }
+
+    #[test]
+    fn test_domain_crud_operations() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Create a domain
+        let domain = crate::models::Domain::new(
+            "Production".to_string(),
+            Some("Production environment".to_string())
+        ).unwrap();
+        let domain_id = domain.id.clone();
+
+        rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+        });
+
+        // Get the domain
+        let retrieved = rt.block_on(async {
+            storage.get_domain(&domain_id).await.unwrap()
+        });
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.name, "Production");
+        assert_eq!(retrieved.description, Some("Production environment".to_string()));
+
+        // Update the domain
+        let mut updated_domain = retrieved.clone();
+        updated_domain.name = "Production Env".to_string();
+        updated_domain.touch();
+
+        rt.block_on(async {
+            let result = storage.update_domain(&updated_domain).await.unwrap();
+            assert!(result); // Should return true (rows affected > 0)
+        });
+
+        // Verify update
+        let after_update = rt.block_on(async {
+            storage.get_domain(&domain_id).await.unwrap().unwrap()
+        });
+        assert_eq!(after_update.name, "Production Env");
+
+        // Delete the domain
+        rt.block_on(async {
+            let result = storage.delete_domain(&domain_id).await.unwrap();
+            assert!(result); // Should return true
+        });
+
+        // Verify deletion
+        let after_delete = rt.block_on(async {
+            storage.get_domain(&domain_id).await.unwrap()
+        });
+        assert!(after_delete.is_none());
+    }
+
+    #[test]
+    fn test_update_domain_version_conflict() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        let domain = crate::models::Domain::new("Versioned".to_string(), None).unwrap();
+
+        rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+
+            // First writer succeeds and bumps the version
+            let mut first = storage.get_domain(&domain.id).await.unwrap().unwrap();
+            assert_eq!(first.version, 1);
+            first.name = "Versioned A".to_string();
+            assert!(storage.update_domain(&first).await.unwrap());
+
+            // Second writer still holds version 1 and is rejected
+            let mut second = domain.clone();
+            second.name = "Versioned B".to_string();
+            assert!(!storage.update_domain(&second).await.unwrap());
+
+            let current = storage.get_domain(&domain.id).await.unwrap().unwrap();
+            assert_eq!(current.name, "Versioned A");
+            assert_eq!(current.version, 2);
+        });
+    }
+
+    #[test]
+    fn test_list_domains_with_connection_count() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Create two domains
+        let domain1 = crate::models::Domain::new("Dev".to_string(), None).unwrap();
+        let domain2 = crate::models::Domain::new("Staging".to_string(), None).unwrap();
+
+        rt.block_on(async {
+            storage.create_domain(&domain1).await.unwrap();
+            storage.create_domain(&domain2).await.unwrap();
+        });
+
+        // List domains
+        let domains = rt.block_on(async {
+            storage.list_domains().await.unwrap()
+        });
+
+        // Should have 3 domains (default + 2 created)
+        assert!(domains.len() >= 3);
+
+        // Find our created domains
+        let dev_domain = domains.iter().find(|d| d.name == "Dev");
+        let staging_domain = domains.iter().find(|d| d.name == "Staging");
+        let default_domain = domains.iter().find(|d| d.name == "Default Domain");
+
+        assert!(dev_domain.is_some());
+        assert!(staging_domain.is_some());
+        assert!(default_domain.is_some());
+
+        // All should have 0 connections initially
+        assert_eq!(dev_domain.unwrap().connection_count, 0);
+        assert_eq!(staging_domain.unwrap().connection_count, 0);
+    }
+
+    #[test]
+    fn test_domain_cascade_delete() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Create a domain
+        let domain = crate::models::Domain::new("TestDomain".to_string(), None).unwrap();
+        let domain_id = domain.id.clone();
+
+        rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+        });
+
+        // Create a connection in this domain
+        let mut conn = crate::models::DatabaseConnection {
+            id: "test-conn-123".to_string(),
+            name: Some("Test Connection".to_string()),
+            connection_url: "postgresql://localhost/test".to_string(),
+            database_type: "postgresql".to_string(),
+            status: crate::models::ConnectionStatus::Disconnected,
+            created_at: chrono::Utc::now(),
+            last_connected_at: None,
+            metadata_cache_id: None,
+            version: 1,
+            read_only: false,
+            allow_writes: false,
+            pool_settings: Default::default(),
+            tags: Vec::new(),
+            labels: Default::default(),
+            domain_id: Some(domain_id.clone()),
+        };
+
+        rt.block_on(async {
+            storage.save_connection(&conn).await.unwrap();
+        });
+
+        // Verify connection exists
+        let conn_before = rt.block_on(async {
+            storage.get_connection("test-conn-123").await.unwrap()
+        });
+        assert!(conn_before.is_some());
+
+        // Delete the domain (should CASCADE delete the connection)
+        rt.block_on(async {
+            storage.delete_domain(&domain_id).await.unwrap();
+        });
+
+        // Note: CASCADE delete happens at SQL level, connection should be removed
+        // This test verifies the foreign key constraint is working
+        let domain_after = rt.block_on(async {
+            storage.get_domain(&domain_id).await.unwrap()
+        });
+        assert!(domain_after.is_none());
+    }
+
+    #[test]
+    fn test_get_domain_connection_count() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Use default domain
+        let default_domain_id = "default-domain-id";
+
+        // Initially should have 0 connections
+        let count_before = rt.block_on(async {
+            storage.get_domain_connection_count(default_domain_id).await.unwrap()
+        });
+
+        // Create a connection (will use default domain)
+        let conn = crate::models::DatabaseConnection {
+            id: "test-conn-456".to_string(),
+            name: Some("Another Test".to_string()),
+            connection_url: "mysql://localhost/test".to_string(),
+            database_type: "mysql".to_string(),
+            status: crate::models::ConnectionStatus::Connected,
+            created_at: chrono::Utc::now(),
+            last_connected_at: Some(chrono::Utc::now()),
+            metadata_cache_id: None,
+            version: 1,
+            read_only: false,
+            allow_writes: false,
+            pool_settings: Default::default(),
+            tags: Vec::new(),
+            labels: Default::default(),
+            domain_id: Some(default_domain_id.to_string()),
+        };
+
+        rt.block_on(async {
+            storage.save_connection(&conn).await.unwrap();
+        });
+
+        // Count should increase by 1
+        let count_after = rt.block_on(async {
+            storage.get_domain_connection_count(default_domain_id).await.unwrap()
+        });
+
+        assert_eq!(count_after, count_before + 1);
+    }
+
+    #[test]
+    fn test_list_connections_by_domain() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        // Create a custom domain
+        let domain = crate::models::Domain::new("Custom".to_string(), None).unwrap();
+        let domain_id = domain.id.clone();
+
+        rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+        });
+
+        // Initially should have empty list
+        let conns_before = rt.block_on(async {
+            storage.list_connections_by_domain(&domain_id).await.unwrap()
+        });
+        assert_eq!(conns_before.len(), 0);
+
+        // Note: Without modifying save_connection to accept domain_id,
+        // connections will go to default domain
+        // This test validates the query works correctly
+    }
+
+    #[test]
+    fn test_move_connection() {
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(SqliteStorage::new(&dir.path().join("test.db"))).unwrap();
+        let domain = crate::models::Domain::new("Prod".to_string(), None).unwrap();
+        let conn = crate::models::DatabaseConnection::new(None, "postgresql://localhost/test".to_string(), "postgresql".to_string(), None);
+
+        rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+            storage.save_connection(&conn).await.unwrap();
+
+            // Stale version
+            assert!(!storage.move_connection(&conn.id, 2, &domain.id).await.unwrap());
+            assert!(storage.move_connection(&conn.id, 1, &domain.id).await.unwrap());
+            let moved = storage.get_connection(&conn.id).await.unwrap().unwrap();
+            assert_eq!(moved.domain_id.as_deref(), Some(domain.id.as_str()));
+            assert_eq!(moved.version, 2);
+            assert_eq!(storage.list_connections_by_domain(&domain.id).await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_connection_pool_settings() {
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(SqliteStorage::new(&dir.path().join("test.db"))).unwrap();
+        let mut conn = crate::models::DatabaseConnection::new(None, "postgresql://localhost/test".to_string(), "postgresql".to_string(), None);
+
+        rt.block_on(async {
+            storage.save_connection(&conn).await.unwrap();
+            let saved = storage.get_connection(&conn.id).await.unwrap().unwrap();
+            assert_eq!(saved.pool_settings, crate::models::PoolSettings::default());
+
+            conn.pool_settings = crate::models::PoolSettings {
+                max_size: Some(4),
+                min_idle: Some(1),
+                acquire_timeout_secs: Some(5),
+                max_lifetime_secs: None,
+            };
+            assert!(storage.update_connection(&conn).await.unwrap());
+            let updated = storage.get_connection(&conn.id).await.unwrap().unwrap();
+            assert_eq!(updated.pool_settings, conn.pool_settings);
+        });
+    }
+
+    #[test]
+    fn test_paged_connection_and_domain_listings() {
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let storage = SqliteStorage::new(dir.path().join("test.db")).await.unwrap();
+            let domain = crate::models::Domain::new("Sales_EU".to_string(), None).unwrap();
+            storage.create_domain(&domain).await.unwrap();
+
+            for (i, name) in ["orders-prod", "orders-staging", "billing-prod"].iter().enumerate() {
+                storage
+                    .save_connection(&crate::models::DatabaseConnection {
+                        id: format!("conn-{}", i),
+                        name: Some(name.to_string()),
+                        connection_url: "postgresql://localhost/test".to_string(),
+                        database_type: "postgresql".to_string(),
+                        status: crate::models::ConnectionStatus::Disconnected,
+                        created_at: chrono::Utc::now(),
+                        last_connected_at: None,
+                        metadata_cache_id: None,
+                        version: 1,
+                        read_only: false,
+                        allow_writes: false,
+                        pool_settings: Default::default(),
+                        tags: match i {
+                            0 => vec!["pii".to_string(), "warehouse".to_string()],
+                            2 => vec!["pii".to_string()],
+                            _ => Vec::new(),
+                        },
+                        labels: if i == 2 { [("pii".to_string(), "true".to_string())].into() } else { Default::default() },
+                        domain_id: Some(if i == 2 { domain.id.clone() } else { "default-domain-id".to_string() }),
+                    })
+                    .await
+                    .unwrap();
+            }
+
+            let params = crate::models::ListParams {
+                page: Some(2),
+                page_size: Some(2),
+                sort: Some("name".to_string()),
+                ..Default::default()
+            };
+            let query = params.resolve(SqliteStorage::CONNECTION_SORT_FIELDS, "-created_at").unwrap();
+            let (page, total) = storage.list_connections_page(&query, None, None, &[]).await.unwrap();
+            assert_eq!(total, 3);
+            assert_eq!(page.len(), 1);
+            assert_eq!(page[0].name.as_deref(), Some("orders-staging"));
+
+            let params = crate::models::ListParams { filter: Some("PROD".to_string()), ..Default::default() };
+            let query = params.resolve(SqliteStorage::CONNECTION_SORT_FIELDS, "name").unwrap();
+            let scope = vec![domain.id.clone()];
+            let (page, total) = storage.list_connections_page(&query, Some(&scope), None, &[]).await.unwrap();
+            assert_eq!(total, 1);
+            assert_eq!(page[0].id, "conn-2");
+            assert_eq!(storage.list_connections_page(&query, Some(&[]), None, &[]).await.unwrap().1, 0);
+
+            let selector = crate::models::LabelSelector::parse("pii=true").unwrap();
+            let (page, total) = storage.list_connections_page(&query, None, Some(&selector), &[]).await.unwrap();
+            assert_eq!(total, 1);
+            assert_eq!(page[0].labels.get("pii").map(String::as_str), Some("true"));
+
+            // Every tag must match
+            let tags = ["pii".to_string(), "warehouse".to_string()];
+            let (page, total) = storage.list_connections_page(&query, None, None, &tags[..1]).await.unwrap();
+            assert_eq!(total, 2);
+            let (page_both, total_both) = storage.list_connections_page(&query, None, None, &tags).await.unwrap();
+            assert_eq!(total_both, 1);
+            assert_eq!(page_both[0].tags, tags);
+            assert!(page.iter().all(|connection| connection.tags.contains(&tags[0])));
+
+            // Updates replace the tags
+            let mut tagged = storage.get_connection("conn-0").await.unwrap().unwrap();
+            tagged.tags = vec!["archive".to_string()];
+            assert!(storage.update_connection(&tagged).await.unwrap());
+            assert_eq!(storage.get_connection("conn-0").await.unwrap().unwrap().tags, vec!["archive"]);
+
+            // `_` in the filter is literal, not a LIKE wildcard
+            let params = crate::models::ListParams { filter: Some("s_eu".to_string()), ..Default::default() };
+            let query = params.resolve(SqliteStorage::DOMAIN_SORT_FIELDS, "name").unwrap();
+            let (domains, total) = storage.list_domains_page(&query, None).await.unwrap();
+            assert_eq!(total, 1);
+            assert_eq!(domains[0].connection_count, 1);
+            // Unescaped, "t_d" would match "Default Domain"
+            let params = crate::models::ListParams { filter: Some("t_d".to_string()), ..Default::default() };
+            let query = params.resolve(SqliteStorage::DOMAIN_SORT_FIELDS, "name").unwrap();
+            assert_eq!(storage.list_domains_page(&query, None).await.unwrap().1, 0);
+        });
+    }
+
+    #[test]
+    fn test_query_history_before_cutoff() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+
+        let mut old = crate::models::QueryHistory::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "SELECT 1".to_string(),
+            1,
+            5,
+            false,
+        );
+        old.executed_at = chrono::Utc::now() - chrono::Duration::days(40);
+        let recent = crate::models::QueryHistory::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "SELECT 2".to_string(),
+            1,
+            5,
+            false,
+        );
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+        let (before, deleted, remaining) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.add_query_history(&old).await.unwrap();
+            storage.add_query_history(&recent).await.unwrap();
+
+            let all = crate::models::HistoryScope::All;
+            let before = storage.list_query_history_before(cutoff, &all).await.unwrap();
+            let deleted = storage.delete_query_history_before(cutoff, &all).await.unwrap();
+            let remaining = storage.list_query_history("default-domain-id", 10).await.unwrap();
+            (before, deleted, remaining)
+        });
+
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].id, old.id);
+        assert_eq!(deleted, 1);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, recent.id);
+    }
+
+    #[test]
+    fn test_cross_database_history_and_saved_queries() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        let connection_ids = vec!["conn-a".to_string(), "conn-b".to_string()];
+        let aliases: std::collections::HashMap<String, String> =
+            [("db1".to_string(), "conn-a".to_string()), ("db2".to_string(), "conn-b".to_string())].into();
+        let query = "SELECT * FROM db1.users u JOIN db2.orders o ON u.id = o.user_id".to_string();
+        let saved = crate::models::SavedQuery::new(
+            "default-domain-id".to_string(),
+            None,
+            "Orders by user".to_string(),
+            query.clone(),
+            None,
+        )
+        .with_connections(connection_ids.clone(), Some(aliases.clone()));
+        let history = crate::models::QueryHistory::new("default-domain-id".to_string(), None, query, 4, 20, false)
+            .with_connections(connection_ids.clone(), Some(aliases.clone()))
+            .with_saved_query(Some(saved.id.clone()));
+
+        let (stored_history, by_connection, stored_saved, notnull) = rt.block_on(async {
+            storage.add_query_history(&history).await.unwrap();
+            storage.save_query(&saved).await.unwrap();
+            let list_query = crate::models::ListQuery {
+                page: 1,
+                page_size: 10,
+                sort_column: "executed_at",
+                descending: true,
+                filter: None,
+            };
+            let (by_connection, _) = storage
+                .list_query_history_page("default-domain-id", Some("conn-b"), &list_query, &Default::default())
+                .await
+                .unwrap();
+            let conn = storage.conn.lock().await;
+            let notnull: i64 = conn
+                .query_row(
+                    "SELECT \"notnull\" FROM pragma_table_info('query_history') WHERE name = 'connection_id'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            drop(conn);
+            (
+                storage.get_query_history(&history.id).await.unwrap().unwrap(),
+                by_connection,
+                storage.get_saved_query(&saved.id).await.unwrap().unwrap(),
+                notnull,
+            )
+        });
+
+        assert_eq!(notnull, 0);
+        assert_eq!(stored_history.connection_id, None);
+        assert_eq!(stored_history.connection_ids, connection_ids);
+        assert_eq!(stored_history.database_aliases, Some(aliases.clone()));
+        assert_eq!(stored_history.saved_query_id.as_deref(), Some(saved.id.as_str()));
+        assert_eq!(by_connection.len(), 1);
+        assert_eq!(by_connection[0].saved_query_id, stored_history.saved_query_id);
+        assert!(stored_saved.is_cross_database());
+        assert_eq!(stored_saved.connection_ids, connection_ids);
+        assert_eq!(stored_saved.database_aliases, Some(aliases));
+
+        // Promoting the entry links it to the new saved query
+        let promoted = rt.block_on(async {
+            assert!(storage.set_history_saved_query(&history.id, "promoted-id").await.unwrap());
+            assert!(!storage.set_history_saved_query("missing", "promoted-id").await.unwrap());
+            storage.get_query_history(&history.id).await.unwrap().unwrap()
+        });
+        assert_eq!(promoted.saved_query_id.as_deref(), Some("promoted-id"));
+    }
+
+    #[test]
+    fn test_saved_views() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let saved = |name: &str, view_name: Option<&str>| {
+            crate::models::SavedQuery::new(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                name.to_string(),
+                "SELECT 1".to_string(),
+                None,
+            )
+            .with_view_name(view_name.map(str::to_string))
+        };
+        let view = saved("Revenue", Some("revenue"));
+        let plain = saved("Scratch", None);
 
-    // ============================================================================
-    // Saved Query Operations (Domain-Scoped)
-    // ============================================================================
+        let (views, renamed, removed) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_query(&view).await.unwrap();
+            storage.save_query(&plain).await.unwrap();
+            let views = storage.list_saved_views("default-domain-id").await.unwrap();
 
-    /// Save a query for a domain
-    pub async fn save_query(&self, query: &crate::models::SavedQuery) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
-        conn.execute(
-            r#"
-            INSERT INTO saved_queries (id, domain_id, connection_id, name, query_text, description, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            "#,
-            rusqlite::params![
-                query.id,
-                query.domain_id,
-                query.connection_id,
-                query.name,
-                query.query_text,
-                query.description,
-                query.created_at.to_rfc3339(),
-                query.updated_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+            storage
+                .update_saved_query(&plain.id, None, None, None, None, None, Some(Some("scratch")), 1)
+                .await
+                .unwrap();
+            storage
+                .update_saved_query(&view.id, None, None, None, None, None, Some(None), 1)
+                .await
+                .unwrap();
+            (
+                views,
+                storage.get_saved_query(&plain.id).await.unwrap().unwrap(),
+                storage.get_saved_query(&view.id).await.unwrap().unwrap(),
+            )
+        });
+
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].view_name.as_deref(), Some("revenue"));
+        assert_eq!(renamed.view_name.as_deref(), Some("scratch"));
+        assert_eq!(removed.view_name, None);
     }
 
-    /// Get a saved query by ID
-    pub async fn get_saved_query(&self, id: &str) -> SqliteResult<Option<crate::models::SavedQuery>> {
-        let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(
-            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at
-             FROM saved_queries WHERE id = ?1"
-        )?;
+    #[test]
+    fn test_broken_saved_queries() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
 
-        let result = stmt.query_row([id], |row| {
-            Ok(crate::models::SavedQuery {
-                id: row.get(0)?,
-                domain_id: row.get(1)?,
-                connection_id: row.get(2)?,
-                name: row.get(3)?,
-                query_text: row.get(4)?,
-                description: row.get(5)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let saved = crate::models::SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Orders".to_string(),
+            "SELECT status FROM orders".to_string(),
+            None,
+        );
+
+        let (listed, broken, edited) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_query(&saved).await.unwrap();
+            let listed = storage.list_saved_queries_for_connection(&connection.id).await.unwrap();
+            assert!(storage.mark_saved_query_broken(&saved.id, "Column orders.status was dropped or renamed").await.unwrap());
+            let broken = storage.get_saved_query(&saved.id).await.unwrap().unwrap();
+            storage
+                .update_saved_query(&saved.id, None, Some("SELECT state FROM orders".to_string()), None, None, None, None, 1)
+                .await
+                .unwrap();
+            let edited = storage.get_saved_query(&saved.id).await.unwrap().unwrap();
+            (listed, broken, edited)
         });
 
-        match result {
-            Ok(query) => Ok(Some(query)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
-        }
+        assert_eq!(listed.len(), 1);
+        assert!(!listed[0].is_broken);
+        assert!(broken.is_broken);
+        // Flagging doesn't count as an edit
+        assert_eq!(broken.version, 1);
+        assert_eq!(broken.breakage_reason.as_deref(), Some("Column orders.status was dropped or renamed"));
+        assert!(!edited.is_broken);
+        assert_eq!(edited.breakage_reason, None);
     }
 
-    /// List all saved queries for a domain
-    pub async fn list_saved_queries(&self, domain_id: &str) -> SqliteResult<Vec<crate::models::SavedQuery>> {
-        let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(
-            "SELECT id, domain_id, connection_id, name, query_text, description, created_at, updated_at
-             FROM saved_queries
-             WHERE domain_id = ?1
-             ORDER BY created_at DESC"
-        )?;
+    #[test]
+    fn test_view_materializations() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
 
-        let queries = stmt.query_map([domain_id], |row| {
-            Ok(crate::models::SavedQuery {
-                id: row.get(0)?,
-                domain_id: row.get(1)?,
-                connection_id: row.get(2)?,
-                name: row.get(3)?,
-                query_text: row.get(4)?,
-                description: row.get(5)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(7)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
-        })?;
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let view = crate::models::SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Revenue".to_string(),
+            "SELECT 1".to_string(),
+            None,
+        )
+        .with_view_name(Some("revenue".to_string()));
 
-        queries.collect()
+        let mut materialization =
+            crate::models::ViewMaterialization::new(view.id.clone(), "default-domain-id".to_string(), Some(3600));
+        let (stored, listed, trashed) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_query(&view).await.unwrap();
+            storage.save_view_materialization(&materialization).await.unwrap();
+
+            materialization.status = crate::models::MaterializationStatus::Ready;
+            materialization.row_count = Some(12);
+            materialization.refreshed_at = Some(chrono::Utc::now());
+            storage.save_view_materialization(&materialization).await.unwrap();
+            let stored = storage.get_view_materialization(&view.id).await.unwrap().unwrap();
+            let listed = storage.list_view_materializations(Some("default-domain-id")).await.unwrap();
+
+            // Materializations of trashed queries are not listed
+            storage.delete_saved_query(&view.id).await.unwrap();
+            let trashed = storage.list_view_materializations(None).await.unwrap();
+            (stored, listed, trashed)
+        });
+
+        assert_eq!(stored.status, crate::models::MaterializationStatus::Ready);
+        assert_eq!(stored.row_count, Some(12));
+        assert_eq!(stored.refresh_interval_secs, Some(3600));
+        assert!(stored.has_snapshot());
+        assert_eq!(listed.len(), 1);
+        assert!(trashed.is_empty());
+
+        let deleted = rt.block_on(async { storage.delete_view_materialization(&view.id).await.unwrap() });
+        assert!(deleted);
     }
 
-    /// Update a saved query
-    pub async fn update_saved_query(
-        &self,
-        id: &str,
-        name: Option<String>,
-        query_text: Option<String>,
-        description: Option<String>,
-    ) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
+    #[test]
+    fn test_usage_buckets_updated_incrementally() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
 
-        // Build dynamic UPDATE query based on which fields are provided
-        let mut updates = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
 
-        if let Some(n) = name {
-            updates.push("name = ?");
-            params.push(Box::new(n));
-        }
-        if let Some(q) = query_text {
-            updates.push("query_text = ?");
-            params.push(Box::new(q));
-        }
-        if let Some(d) = description {
-            updates.push("description = ?");
-            params.push(Box::new(d));
-        }
+        let buckets = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.add_query_history(&crate::models::QueryHistory::new(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                "SELECT 1".to_string(),
+                1,
+                20,
+                true,
+            )).await.unwrap();
+            storage.add_query_history(&crate::models::QueryHistory::new_failed(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                "SELECT broken".to_string(),
+                "syntax error".to_string(),
+                false,
+            )).await.unwrap();
 
-        if updates.is_empty() {
-            return Ok(()); // Nothing to update
-        }
+            let since = chrono::Utc::now() - chrono::Duration::days(1);
+            storage.list_usage_buckets("default-domain-id", since).await.unwrap()
+        });
 
-        updates.push("updated_at = ?");
-        params.push(Box::new(chrono::Utc::now().to_rfc3339()));
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].total_count, 2);
+        assert_eq!(buckets[0].success_count, 1);
+        assert_eq!(buckets[0].failed_count, 1);
+        assert_eq!(buckets[0].llm_count, 1);
+        assert_eq!(buckets[0].total_execution_time_ms, 20);
+    }
 
-        params.push(Box::new(id.to_string()));
+    #[test]
+    fn test_domain_stats() {
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async { SqliteStorage::new(&dir.path().join("test.db")).await.unwrap() });
 
-        let query = format!(
-            "UPDATE saved_queries SET {} WHERE id = ?",
-            updates.join(", ")
+        let first = crate::models::DatabaseConnection::new(
+            Some("first".to_string()),
+            "postgresql://localhost/first".to_string(),
+            "postgresql".to_string(),
+            None,
         );
+        let second = crate::models::DatabaseConnection::new(
+            None,
+            "mysql://localhost/second".to_string(),
+            "mysql".to_string(),
+            None,
+        );
+        let domain = "default-domain-id".to_string();
 
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-        conn.execute(&query, params_refs.as_slice())?;
+        let stats = rt.block_on(async {
+            storage.save_connection(&first).await.unwrap();
+            storage.save_connection(&second).await.unwrap();
+            for rows in [10, 30] {
+                storage
+                    .add_query_history(
+                        &crate::models::QueryHistory::new(domain.clone(), first.id.clone(), "SELECT 1".to_string(), rows, 20, false)
+                            .with_executed_by(Some("alice".to_string())),
+                    )
+                    .await
+                    .unwrap();
+            }
+            storage
+                .add_query_history(
+                    &crate::models::QueryHistory::new_failed(domain.clone(), second.id.clone(), "SELECT x".to_string(), "boom".to_string(), false)
+                        .with_executed_by(Some("bob".to_string())),
+                )
+                .await
+                .unwrap();
+            storage
+                .add_query_history(
+                    &crate::models::QueryHistory::new(domain.clone(), None, "SELECT 2".to_string(), 5, 50, false)
+                        .with_connections(vec![first.id.clone(), second.id.clone()], None),
+                )
+                .await
+                .unwrap();
 
-        Ok(())
-    }
+            let now = chrono::Utc::now();
+            storage
+                .domain_stats(&domain, now - chrono::Duration::days(1), now, 10)
+                .await
+                .unwrap()
+        });
 
-    /// Delete a saved query
-    pub async fn delete_saved_query(&self, id: &str) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
-        conn.execute("DELETE FROM saved_queries WHERE id = ?1", [id])?;
-        Ok(())
-    }
+        assert_eq!(stats.totals.queries, 4);
+        assert_eq!(stats.totals.failed, 1);
+        assert_eq!(stats.totals.failure_rate, 0.25);
+        assert_eq!(stats.totals.rows_scanned, 45);
+        assert_eq!(stats.totals.avg_execution_time_ms, 22.5);
 
-    // ============================================================================
-    // Query History Operations (Domain-Scoped)
-    // ============================================================================
+        // The cross-database query counts for both connections
+        assert_eq!(stats.top_connections.len(), 2);
+        assert_eq!(stats.top_connections[0].connection_id, first.id);
+        assert_eq!(stats.top_connections[0].name.as_deref(), Some("first"));
+        assert_eq!(stats.top_connections[0].totals.queries, 3);
+        assert_eq!(stats.top_connections[1].totals.queries, 2);
+        assert_eq!(stats.top_connections[1].totals.failed, 1);
 
-    /// Add a query execution to history
-    pub async fn add_query_history(&self, history: &crate::models::QueryHistory) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
-        conn.execute(
-            r#"
-            INSERT INTO query_history
-            (id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
-            "#,
-            rusqlite::params![
-                history.id,
-                history.domain_id,
-                history.connection_id,
-                history.query_text,
-                history.row_count as i64,
-                history.execution_time_ms as i64,
-                format!("{:?}", history.status).to_lowercase(),
-                history.error_message,
-                history.executed_at.to_rfc3339(),
-                if history.is_llm_generated { 1 } else { 0 },
-            ],
-        )?;
-        Ok(())
+        assert_eq!(stats.top_users.len(), 2);
+        assert_eq!(stats.top_users[0].executed_by, "alice");
+        assert_eq!(stats.top_users[0].totals.queries, 2);
+        assert_eq!(stats.top_users[0].totals.avg_execution_time_ms, 20.0);
+
+        // Runs differing only in literals share a fingerprint
+        assert_eq!(stats.top_queries.len(), 2);
+        assert_eq!(stats.top_queries[0].fingerprint, "SELECT ?");
+        assert_eq!(stats.top_queries[0].totals.queries, 3);
+        assert_eq!(stats.top_queries[1].totals.failed, 1);
     }
 
-    /// List query history for a domain (with limit)
-    pub async fn list_query_history(
-        &self,
-        domain_id: &str,
-        limit: usize,
-    ) -> SqliteResult<Vec<crate::models::QueryHistory>> {
-        let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated
-            FROM query_history
-            WHERE domain_id = ?1
-            ORDER BY executed_at DESC
-            LIMIT ?2
-            "#
-        )?;
+    #[test]
+    fn test_history_filters_and_daily_stats() {
+        use crate::models::{HistoryFilterParams, ListQuery, QueryHistory, QueryHistoryStatus};
+        use chrono::TimeZone;
 
-        let histories = stmt.query_map(rusqlite::params![domain_id, limit as i64], |row| {
-            let status_str: String = row.get(6)?;
-            let status = match status_str.as_str() {
-                "success" => crate::models::QueryHistoryStatus::Success,
-                "failed" => crate::models::QueryHistoryStatus::Failed,
-                _ => crate::models::QueryHistoryStatus::Failed,
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async { SqliteStorage::new(&dir.path().join("test.db")).await.unwrap() });
+        let domain = "default-domain-id".to_string();
+        let day1 = chrono::Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let day2 = chrono::Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let list_query = ListQuery {
+            page: 1,
+            page_size: 50,
+            sort_column: "executed_at",
+            descending: true,
+            filter: None,
+        };
+
+        let (slow, failed, llm, days) = rt.block_on(async {
+            for (i, ms) in [30u64, 10, 40, 20].into_iter().enumerate() {
+                let mut entry = QueryHistory::new(domain.clone(), None, format!("SELECT {}", i), 1, ms, i == 0);
+                entry.executed_at = day1 + chrono::Duration::minutes(i as i64);
+                storage.add_query_history(&entry).await.unwrap();
+            }
+            let mut entry = QueryHistory::new_failed(domain.clone(), None, "SELECT x".to_string(), "boom".to_string(), false);
+            entry.execution_time_ms = 100;
+            entry.executed_at = day2;
+            storage.add_query_history(&entry).await.unwrap();
+
+            let slow = HistoryFilterParams {
+                min_execution_time_ms: Some(30),
+                until: Some(day1 + chrono::Duration::hours(1)),
+                ..Default::default()
+            };
+            let failed = HistoryFilterParams {
+                status: Some(QueryHistoryStatus::Failed),
+                ..Default::default()
             };
+            let llm = HistoryFilterParams {
+                is_llm_generated: Some(true),
+                ..Default::default()
+            };
+            (
+                storage.list_query_history_page(&domain, None, &list_query, &slow).await.unwrap(),
+                storage.list_query_history_page(&domain, None, &list_query, &failed).await.unwrap(),
+                storage.list_query_history_page(&domain, None, &list_query, &llm).await.unwrap(),
+                storage
+                    .history_daily_stats(&domain, None, &list_query, &HistoryFilterParams::default())
+                    .await
+                    .unwrap(),
+            )
+        });
 
-            Ok(crate::models::QueryHistory {
-                id: row.get(0)?,
-                domain_id: row.get(1)?,
-                connection_id: row.get(2)?,
-                query_text: row.get(3)?,
-                row_count: row.get::<_, i64>(4)? as usize,
-                execution_time_ms: row.get::<_, i64>(5)? as u64,
-                status,
-                error_message: row.get(7)?,
-                executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                is_llm_generated: row.get::<_, i32>(9)? == 1,
-            })
-        })?;
+        assert_eq!(slow.1, 2);
+        assert!(slow.0.iter().all(|entry| entry.execution_time_ms >= 30));
+        assert_eq!(failed.1, 1);
+        assert_eq!(failed.0[0].query_text, "SELECT x");
+        assert_eq!(llm.1, 1);
+        assert_eq!(llm.0[0].query_text, "SELECT 0");
 
-        histories.collect()
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].day, day1.date_naive());
+        assert_eq!(days[0].queries, 4);
+        assert_eq!(days[0].failed, 0);
+        assert_eq!(days[0].avg_execution_time_ms, 25.0);
+        assert_eq!(days[0].p50_execution_time_ms, 20);
+        assert_eq!(days[0].p95_execution_time_ms, 40);
+        assert_eq!(days[1].failed, 1);
+        assert_eq!(days[1].p95_execution_time_ms, 100);
     }
 
-    /// List query history for a specific connection (with limit)
-    pub async fn list_query_history_by_connection(
-        &self,
-        connection_id: &str,
-        limit: usize,
-    ) -> SqliteResult<Vec<crate::models::QueryHistory>> {
-        let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT id, domain_id, connection_id, query_text, row_count, execution_time_ms, status, error_message, executed_at, is_llm_generated
-            FROM query_history
-            WHERE connection_id = ?1
-            ORDER BY executed_at DESC
-            LIMIT ?2
-            "#
-        )?;
-
-        let histories = stmt.query_map(rusqlite::params![connection_id, limit as i64], |row| {
-            let status_str: String = row.get(6)?;
-            let status = match status_str.as_str() {
-                "success" => crate::models::QueryHistoryStatus::Success,
-                "failed" => crate::models::QueryHistoryStatus::Failed,
-                _ => crate::models::QueryHistoryStatus::Failed,
-            };
+    #[test]
+    fn test_favorites_and_recent_queries() {
+        use crate::models::{FavoriteType, QueryHistory, QueryHistoryStatus, SavedQuery};
 
-            Ok(crate::models::QueryHistory {
-                id: row.get(0)?,
-                domain_id: row.get(1)?,
-                connection_id: row.get(2)?,
-                query_text: row.get(3)?,
-                row_count: row.get::<_, i64>(4)? as usize,
-                execution_time_ms: row.get::<_, i64>(5)? as u64,
-                status,
-                error_message: row.get(7)?,
-                executed_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                is_llm_generated: row.get::<_, i32>(9)? == 1,
-            })
-        })?;
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async { SqliteStorage::new(&dir.path().join("test.db")).await.unwrap() });
+        let domain = "default-domain-id".to_string();
+        let connection = crate::models::DatabaseConnection::new(
+            Some("orders".to_string()),
+            "postgresql://localhost/orders".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let saved = SavedQuery::new(
+            domain.clone(),
+            connection.id.clone(),
+            "Open orders".to_string(),
+            "SELECT * FROM orders".to_string(),
+            None,
+        );
 
-        histories.collect()
+        rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_query(&saved).await.unwrap();
+
+            storage.add_favorite("alice", FavoriteType::Connection, &connection.id).await.unwrap();
+            storage.add_favorite("alice", FavoriteType::SavedQuery, &saved.id).await.unwrap();
+            storage.add_favorite("alice", FavoriteType::SavedQuery, &saved.id).await.unwrap();
+            storage.add_favorite("bob", FavoriteType::Connection, &connection.id).await.unwrap();
+            let favorites = storage.list_favorites("alice", &domain).await.unwrap();
+            assert_eq!(favorites.len(), 2);
+            assert!(favorites.iter().any(|f| f.resource_type == FavoriteType::SavedQuery && f.name.as_deref() == Some("Open orders")));
+            assert!(storage.list_favorites("alice", "other-domain").await.unwrap().is_empty());
+
+            // Deleted resources are left out
+            storage.delete_saved_query(&saved.id).await.unwrap();
+            assert_eq!(storage.list_favorites("alice", &domain).await.unwrap().len(), 1);
+            assert!(storage.remove_favorite("alice", FavoriteType::Connection, &connection.id).await.unwrap());
+            assert!(!storage.remove_favorite("alice", FavoriteType::Connection, &connection.id).await.unwrap());
+
+            let now = chrono::Utc::now();
+            let runs = [
+                ("SELECT 1", None, 3, false),
+                ("SELECT 1", None, 2, true),
+                ("SELECT * FROM orders", Some(saved.id.clone()), 1, false),
+                ("SELECT * FROM orders WHERE id = 2", Some(saved.id.clone()), 0, false),
+            ];
+            for (sql, saved_query_id, minutes_ago, failed) in runs {
+                let mut entry = if failed {
+                    QueryHistory::new_failed(domain.clone(), connection.id.clone(), sql.to_string(), "boom".to_string(), false)
+                } else {
+                    QueryHistory::new(domain.clone(), connection.id.clone(), sql.to_string(), 1, 5, false)
+                }
+                .with_executed_by(Some("alice".to_string()))
+                .with_saved_query(saved_query_id);
+                entry.executed_at = now - chrono::Duration::minutes(minutes_ago);
+                storage.add_query_history(&entry).await.unwrap();
+            }
+            storage
+                .add_query_history(&QueryHistory::new(domain.clone(), connection.id.clone(), "SELECT 2".to_string(), 1, 5, false))
+                .await
+                .unwrap();
+
+            let recent = storage.list_recent_queries(&domain, Some("alice"), 10).await.unwrap();
+            assert_eq!(recent.len(), 2);
+            assert_eq!(recent[0].saved_query_id.as_deref(), Some(saved.id.as_str()));
+            assert_eq!(recent[0].query_text, "SELECT * FROM orders WHERE id = 2");
+            assert_eq!(recent[0].executions, 2);
+            assert_eq!(recent[0].saved_query_name, None);
+            assert_eq!(recent[1].query_text, "SELECT 1");
+            assert_eq!(recent[1].last_status, QueryHistoryStatus::Failed);
+
+            // Runs recorded without a user
+            let anonymous = storage.list_recent_queries(&domain, None, 10).await.unwrap();
+            assert_eq!(anonymous.len(), 1);
+            assert_eq!(anonymous[0].query_text, "SELECT 2");
+            assert_eq!(storage.list_recent_queries(&domain, Some("alice"), 1).await.unwrap().len(), 1);
+        });
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    #[test]
+    fn test_api_usage_daily_rollup() {
+        let dir = tempdir().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let storage = SqliteStorage::new(dir.path().join("test.db")).await.unwrap();
+            let connection = crate::models::DatabaseConnection::new(
+                None,
+                "postgresql://localhost/test".to_string(),
+                "postgresql".to_string(),
+                None,
+            );
+            storage.save_connection(&connection).await.unwrap();
+
+            storage.add_query_history(&crate::models::QueryHistory::new(
+                "default-domain-id".to_string(),
+                connection.id.clone(),
+                "SELECT 1".to_string(),
+                7,
+                20,
+                false,
+            )).await.unwrap();
+            storage.record_api_call("default-domain-id", false, 300).await.unwrap();
+            storage.record_api_call("default-domain-id", true, 100).await.unwrap();
+            let tokens = crate::models::LlmTokenUsage { prompt_tokens: 120, completion_tokens: 30 };
+            storage.record_llm_request("default-domain-id", Some("alice"), tokens, true).await.unwrap();
+            storage.record_llm_request("default-domain-id", Some("alice"), tokens, false).await.unwrap();
+            storage.record_llm_request("default-domain-id", None, tokens, false).await.unwrap();
+            storage.record_llm_request("missing-domain", None, tokens, false).await.unwrap();
+            // Unknown domains are ignored instead of violating the foreign key
+            storage.record_api_call("missing-domain", false, 10).await.unwrap();
+
+            let today = chrono::Utc::now().date_naive();
+            let days = storage
+                .list_api_usage_days("default-domain-id", today, today + chrono::Duration::days(1))
+                .await
+                .unwrap();
+            assert_eq!(days.len(), 1);
+            assert_eq!(days[0].api_calls, 2);
+            assert_eq!(days[0].api_errors, 1);
+            assert_eq!(days[0].bytes_out, 400);
+            assert_eq!(days[0].queries, 1);
+            assert_eq!(days[0].rows_returned, 7);
+            assert_eq!(days[0].llm_requests, 3);
+            assert_eq!(days[0].llm_failures, 1);
+
+            let usage = storage
+                .list_llm_usage("default-domain-id", today, today + chrono::Duration::days(1))
+                .await
+                .unwrap();
+            assert_eq!(usage.len(), 2);
+            assert_eq!(usage[0].principal, None);
+            assert_eq!(usage[1].principal.as_deref(), Some("alice"));
+            assert_eq!(usage[1].requests, 2);
+            assert_eq!(usage[1].failed_requests, 1);
+            assert_eq!(usage[1].tokens.prompt_tokens, 240);
+            assert_eq!(storage.llm_tokens_used("default-domain-id", today).await.unwrap(), 450);
+            assert_eq!(storage.llm_tokens_used("missing-domain", today).await.unwrap(), 0);
+        });
+    }
 
     #[test]
-    fn test_sqlite_storage_creation() {
+    fn test_metadata_versions_retained() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
         let storage = rt.block_on(async {
-            SqliteStorage::new(&db_path).await
+            SqliteStorage::new(&db_path).await.unwrap()
         });
-        assert!(storage.is_ok());
+
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+
+        let (assigned, versions, latest, pruned) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            let mut assigned = Vec::new();
+            for _ in 0..4 {
+                let metadata = crate::models::DatabaseMetadata::new(connection.id.clone(), vec![], vec![], vec![]);
+                assigned.push(storage.save_metadata_cache(&metadata, 3).await.unwrap());
+            }
+            (
+                assigned,
+                storage.list_metadata_versions(&connection.id).await.unwrap(),
+                storage.get_metadata_cache(&connection.id).await.unwrap().unwrap(),
+                storage.get_metadata_cache_version(&connection.id, 1).await.unwrap(),
+            )
+        });
+
+        assert_eq!(assigned, vec![1, 2, 3, 4]);
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![4, 3, 2]);
+        assert_eq!(latest.version, 4);
+        assert!(pruned.is_none());
     }
 
     #[test]
-    fn test_schema_initialization() {
+    fn test_domain_settings_and_scoped_history_prune() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -802,38 +5834,71 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Verify tables exist
-        let conn = rt.block_on(async {
-            storage.conn.lock().await
-        });
-        let mut stmt = conn.prepare(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name IN ('domains', 'connections', 'metadata_cache')"
-        ).unwrap();
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let other_domain = crate::models::Domain::new("Other".to_string(), None).unwrap();
+        let mut settings = crate::models::DomainSettings::defaults(other_domain.id.clone());
+        settings.default_row_limit = 250;
+        settings.max_row_limit = 5000;
+        settings.history_retention_days = Some(7);
+        settings.allowed_database_types = vec!["postgresql".to_string()];
+        settings.llm_denied_identifiers = vec!["customers.email".to_string()];
+        settings.metadata_refresh_interval_secs = Some(3600);
+        settings.slow_query_threshold_ms = 250;
 
-        let tables: Vec<String> = stmt.query_map([], |row| row.get(0))
-            .unwrap()
-            .map(|r| r.unwrap())
-            .collect();
+        let mut old_default = crate::models::QueryHistory::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "SELECT 1".to_string(),
+            1,
+            5,
+            false,
+        );
+        old_default.executed_at = chrono::Utc::now() - chrono::Duration::days(40);
+        let mut old_other = old_default.clone();
+        old_other.id = uuid::Uuid::new_v4().to_string();
+        old_other.domain_id = other_domain.id.clone();
 
-        assert_eq!(tables.len(), 3);
-        assert!(tables.contains(&"domains".to_string()));
-        assert!(tables.contains(&"connections".to_string()));
-        assert!(tables.contains(&"metadata_cache".to_string()));
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+        let (loaded, listed, excluded, deleted) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.create_domain(&other_domain).await.unwrap();
+            storage.save_domain_settings(&settings).await.unwrap();
+            storage.add_query_history(&old_default).await.unwrap();
+            storage.add_query_history(&old_other).await.unwrap();
 
-        // Verify default domain exists
-        let mut stmt = conn.prepare("SELECT id, name FROM domains WHERE id = 'default-domain-id'").unwrap();
-        let default_domain: Result<(String, String), _> = stmt.query_row([], |row| {
-            Ok((row.get(0)?, row.get(1)?))
+            let scope = crate::models::HistoryScope::AllExcept(vec![other_domain.id.clone()]);
+            (
+                storage.get_domain_settings(&other_domain.id).await.unwrap(),
+                storage.list_domain_settings().await.unwrap(),
+                storage.list_query_history_before(cutoff, &scope).await.unwrap(),
+                storage
+                    .delete_query_history_before(cutoff, &crate::models::HistoryScope::Domain(other_domain.id.clone()))
+                    .await
+                    .unwrap(),
+            )
         });
 
-        assert!(default_domain.is_ok());
-        let (id, name) = default_domain.unwrap();
-        assert_eq!(id, "default-domain-id");
-        assert_eq!(name, "Default Domain");
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.default_row_limit, 250);
+        assert_eq!(loaded.max_row_limit, 5000);
+        assert_eq!(loaded.history_retention_days, Some(7));
+        assert_eq!(loaded.allowed_database_types, vec!["postgresql"]);
+        assert_eq!(loaded.llm_denied_identifiers, vec!["customers.email"]);
+        assert_eq!(loaded.metadata_refresh_interval_secs, Some(3600));
+        assert_eq!(loaded.slow_query_threshold_ms, 250);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].id, old_default.id);
+        assert_eq!(deleted, 1);
     }
 
     #[test]
-    fn test_domain_crud_operations() {
+    fn test_domain_metrics_round_trip() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -841,57 +5906,81 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Create a domain
-        let domain = crate::models::Domain::new(
-            "Production".to_string(),
-            Some("Production environment".to_string())
-        ).unwrap();
-        let domain_id = domain.id.clone();
-
-        rt.block_on(async {
-            storage.create_domain(&domain).await.unwrap();
-        });
+        let mut metrics = crate::models::DomainMetrics {
+            domain_id: "default-domain-id".to_string(),
+            definition: serde_yaml::from_str("entities: [{name: orders, table: orders}]").unwrap(),
+            updated_at: chrono::Utc::now(),
+        };
+        let (missing, first, replaced) = rt.block_on(async {
+            let missing = storage.get_domain_metrics("default-domain-id").await.unwrap();
+            storage.save_domain_metrics(&metrics).await.unwrap();
+            let first = storage.get_domain_metrics("default-domain-id").await.unwrap();
 
-        // Get the domain
-        let retrieved = rt.block_on(async {
-            storage.get_domain(&domain_id).await.unwrap()
+            metrics.definition.entities[0].table = "public.orders".to_string();
+            storage.save_domain_metrics(&metrics).await.unwrap();
+            (missing, first, storage.get_domain_metrics("default-domain-id").await.unwrap())
         });
-        assert!(retrieved.is_some());
-        let retrieved = retrieved.unwrap();
-        assert_eq!(retrieved.name, "Production");
-        assert_eq!(retrieved.description, Some("Production environment".to_string()));
 
-        // Update the domain
-        let mut updated_domain = retrieved.clone();
-        updated_domain.name = "Production Env".to_string();
-        updated_domain.touch();
+        assert!(missing.is_none());
+        assert_eq!(first.unwrap().definition.entities[0].table, "orders");
+        assert_eq!(replaced.unwrap().definition, metrics.definition);
+    }
 
-        rt.block_on(async {
-            let result = storage.update_domain(&updated_domain).await.unwrap();
-            assert!(result); // Should return true (rows affected > 0)
+    #[test]
+    fn test_chat_sessions() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Verify update
-        let after_update = rt.block_on(async {
-            storage.get_domain(&domain_id).await.unwrap().unwrap()
-        });
-        assert_eq!(after_update.name, "Production Env");
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let session = crate::models::ChatSession::new(
+            connection.id.clone(),
+            None,
+            Some("alice".to_string()),
+            "Revenue by region".to_string(),
+        );
+        let first = crate::models::ChatTurn::new(
+            session.id.clone(),
+            "Revenue by region".to_string(),
+            "SELECT region, SUM(total) FROM sales GROUP BY region".to_string(),
+        );
+        let second = crate::models::ChatTurn::new(
+            session.id.clone(),
+            "now only for last month".to_string(),
+            "SELECT region, SUM(total) FROM salez".to_string(),
+        )
+        .with_error("relation \"salez\" does not exist".to_string());
 
-        // Delete the domain
-        rt.block_on(async {
-            let result = storage.delete_domain(&domain_id).await.unwrap();
-            assert!(result); // Should return true
+        let (loaded, turns, deleted, missing) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.create_chat_session(&session).await.unwrap();
+            storage.add_chat_turn(&first).await.unwrap();
+            storage.add_chat_turn(&second).await.unwrap();
+            let loaded = storage.get_chat_session(&session.id).await.unwrap().unwrap();
+            let turns = storage.list_chat_turns(&session.id).await.unwrap();
+            let deleted = storage.delete_chat_session(&session.id).await.unwrap();
+            (loaded, turns, deleted, storage.list_chat_turns(&session.id).await.unwrap())
         });
 
-        // Verify deletion
-        let after_delete = rt.block_on(async {
-            storage.get_domain(&domain_id).await.unwrap()
-        });
-        assert!(after_delete.is_none());
+        assert_eq!(loaded.created_by.as_deref(), Some("alice"));
+        assert!(loaded.updated_at >= session.updated_at);
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].question, "Revenue by region");
+        assert!(turns[1].error_message.is_some());
+        assert!(deleted);
+        assert!(missing.is_empty());
     }
 
     #[test]
-    fn test_list_domains_with_connection_count() {
+    fn test_sql_feedback() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -899,39 +5988,110 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Create two domains
-        let domain1 = crate::models::Domain::new("Dev".to_string(), None).unwrap();
-        let domain2 = crate::models::Domain::new("Staging".to_string(), None).unwrap();
+        let domain = crate::models::Domain::new("Sales".to_string(), None).unwrap();
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            Some(domain.id.clone()),
+        );
+        let feedback = |rating, corrected_sql: Option<&str>, database_type: &str| {
+            crate::models::SqlFeedback::new(
+                domain.id.clone(),
+                connection.id.clone(),
+                database_type.to_string(),
+                crate::models::SqlFeedbackRequest {
+                    question: "Orders per region".to_string(),
+                    generated_sql: "SELECT region FROM orders".to_string(),
+                    rating,
+                    corrected_sql: corrected_sql.map(str::to_string),
+                    comment: None,
+                },
+                Some("alice".to_string()),
+            )
+        };
+        let up = feedback(crate::models::FeedbackRating::Up, None, "postgresql");
+        let down = feedback(crate::models::FeedbackRating::Down, None, "postgresql");
+        let corrected = feedback(
+            crate::models::FeedbackRating::Down,
+            Some("SELECT region, COUNT(*) FROM orders GROUP BY region"),
+            "postgresql",
+        );
+        let mysql = feedback(crate::models::FeedbackRating::Up, None, "mysql");
 
-        rt.block_on(async {
-            storage.create_domain(&domain1).await.unwrap();
-            storage.create_domain(&domain2).await.unwrap();
+        let (examples, page, deleted, loaded) = rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
+            storage.save_connection(&connection).await.unwrap();
+            for feedback in [&up, &down, &corrected, &mysql] {
+                storage.add_sql_feedback(feedback).await.unwrap();
+            }
+            let examples = storage.list_sql_examples(&domain.id, "postgresql", 10).await.unwrap();
+            let list_query = crate::models::ListParams::default()
+                .resolve(SqliteStorage::SQL_FEEDBACK_SORT_FIELDS, "-created_at")
+                .unwrap();
+            let page = storage.list_sql_feedback_page(&domain.id, &list_query).await.unwrap();
+            let deleted = storage.delete_sql_feedback(&down.id).await.unwrap();
+            (examples, page, deleted, storage.get_sql_feedback(&down.id).await.unwrap())
         });
 
-        // List domains
-        let domains = rt.block_on(async {
-            storage.list_domains().await.unwrap()
-        });
+        let ids: Vec<&str> = examples.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec![corrected.id.as_str(), up.id.as_str()]);
+        assert_eq!(examples[1].created_by.as_deref(), Some("alice"));
+        assert_eq!(page.1, 4);
+        assert!(deleted);
+        assert!(loaded.is_none());
+    }
 
-        // Should have 3 domains (default + 2 created)
-        assert!(domains.len() >= 3);
+    #[test]
+    fn test_llm_audit_log() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
 
-        // Find our created domains
-        let dev_domain = domains.iter().find(|d| d.name == "Dev");
-        let staging_domain = domains.iter().find(|d| d.name == "Staging");
-        let default_domain = domains.iter().find(|d| d.name == "Default Domain");
+        let context = crate::models::LlmAuditContext {
+            domain_id: Some("default-domain-id".to_string()),
+            connection_id: None,
+            principal: Some("alice".to_string()),
+        };
+        let redactions = crate::models::PromptRedactions {
+            withheld: vec!["salaries".to_string()],
+            masked_columns: vec!["email".to_string()],
+        };
+        let generate = crate::models::LlmAuditEntry::new(&context, "generate_sql", "Question: orders", redactions.clone());
+        let summarize = crate::models::LlmAuditEntry::new(&context, "summarize_result", "Sample of 2 rows", Default::default());
+        let outside = crate::models::LlmAuditEntry::new(&Default::default(), "generate_sql", "Question: users", Default::default());
 
-        assert!(dev_domain.is_some());
-        assert!(staging_domain.is_some());
-        assert!(default_domain.is_some());
+        let (all, filtered) = rt.block_on(async {
+            for entry in [&generate, &summarize, &outside] {
+                storage.add_llm_audit_entry(entry).await.unwrap();
+            }
+            let list_query = crate::models::ListParams::default()
+                .resolve(SqliteStorage::LLM_AUDIT_SORT_FIELDS, "-created_at")
+                .unwrap();
+            let filter_query = crate::models::ListParams {
+                filter: Some("generate".to_string()),
+                ..Default::default()
+            }
+            .resolve(SqliteStorage::LLM_AUDIT_SORT_FIELDS, "-created_at")
+            .unwrap();
+            (
+                storage.list_llm_audit_page("default-domain-id", &list_query).await.unwrap(),
+                storage.list_llm_audit_page("default-domain-id", &filter_query).await.unwrap(),
+            )
+        });
 
-        // All should have 0 connections initially
-        assert_eq!(dev_domain.unwrap().connection_count, 0);
-        assert_eq!(staging_domain.unwrap().connection_count, 0);
+        assert_eq!(all.1, 2);
+        assert_eq!(filtered.1, 1);
+        assert_eq!(filtered.0[0].prompt, "Question: orders");
+        assert_eq!(filtered.0[0].redactions, redactions);
+        assert_eq!(filtered.0[0].principal.as_deref(), Some("alice"));
     }
 
     #[test]
-    fn test_domain_cascade_delete() {
+    fn test_data_dictionary() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -939,52 +6099,100 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Create a domain
-        let domain = crate::models::Domain::new("TestDomain".to_string(), None).unwrap();
-        let domain_id = domain.id.clone();
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let entry = |column: Option<&str>, description: &str| {
+            crate::models::DictionaryEntry::new(
+                connection.id.clone(),
+                crate::models::DictionaryEntryRequest {
+                    schema: Some("sales".to_string()),
+                    table: "orders".to_string(),
+                    column: column.map(str::to_string),
+                    description: Some(description.to_string()),
+                    tags: vec!["finance".to_string()],
+                },
+                Some("alice".to_string()),
+            )
+            .unwrap()
+        };
 
-        rt.block_on(async {
-            storage.create_domain(&domain).await.unwrap();
+        let (entries, deleted, missing) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.upsert_dictionary_entry(&entry(None, "Orders")).await.unwrap();
+            storage.upsert_dictionary_entry(&entry(Some("status"), "State")).await.unwrap();
+            // Replaces the table's entry
+            storage.upsert_dictionary_entry(&entry(None, "Customer orders")).await.unwrap();
+            let entries = storage.list_dictionary_entries(&connection.id).await.unwrap();
+            let deleted = storage
+                .delete_dictionary_entry(&connection.id, Some("sales"), "orders", Some("status"))
+                .await
+                .unwrap();
+            let missing = storage
+                .delete_dictionary_entry(&connection.id, None, "orders", Some("status"))
+                .await
+                .unwrap();
+            (entries, deleted, missing)
         });
 
-        // Create a connection in this domain
-        let mut conn = crate::models::DatabaseConnection {
-            id: "test-conn-123".to_string(),
-            name: Some("Test Connection".to_string()),
-            connection_url: "postgresql://localhost/test".to_string(),
-            database_type: "postgresql".to_string(),
-            status: crate::models::ConnectionStatus::Disconnected,
-            created_at: chrono::Utc::now(),
-            last_connected_at: None,
-            metadata_cache_id: None,
-            domain_id: Some(domain_id.clone()),
-        };
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].column, None);
+        assert_eq!(entries[0].description.as_deref(), Some("Customer orders"));
+        assert_eq!(entries[1].column.as_deref(), Some("status"));
+        assert_eq!(entries[1].tags, vec!["finance"]);
+        assert_eq!(entries[1].updated_by.as_deref(), Some("alice"));
+        assert!(deleted);
+        assert!(!missing);
+    }
 
-        rt.block_on(async {
-            storage.save_connection(&conn).await.unwrap();
+    #[test]
+    fn test_table_profiles() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Verify connection exists
-        let conn_before = rt.block_on(async {
-            storage.get_connection("test-conn-123").await.unwrap()
-        });
-        assert!(conn_before.is_some());
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let profile = |metadata_version: i32, row_count: u64| crate::models::TableProfile {
+            connection_id: connection.id.clone(),
+            schema: "sales".to_string(),
+            table: "orders".to_string(),
+            metadata_version,
+            row_count,
+            columns: Vec::new(),
+            skipped_columns: Vec::new(),
+            execution_time_ms: 5,
+            profiled_at: chrono::Utc::now(),
+        };
 
-        // Delete the domain (should CASCADE delete the connection)
-        rt.block_on(async {
-            storage.delete_domain(&domain_id).await.unwrap();
+        let (saved, missing) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_table_profile(&profile(1, 10)).await.unwrap();
+            // Replaces the first profile
+            storage.save_table_profile(&profile(2, 12)).await.unwrap();
+            let saved = storage.get_table_profile(&connection.id, "sales", "orders").await.unwrap();
+            let missing = storage.get_table_profile(&connection.id, "sales", "customers").await.unwrap();
+            (saved, missing)
         });
 
-        // Note: CASCADE delete happens at SQL level, connection should be removed
-        // This test verifies the foreign key constraint is working
-        let domain_after = rt.block_on(async {
-            storage.get_domain(&domain_id).await.unwrap()
-        });
-        assert!(domain_after.is_none());
+        let saved = saved.unwrap();
+        assert_eq!(saved.metadata_version, 2);
+        assert_eq!(saved.row_count, 12);
+        assert!(missing.is_none());
     }
 
     #[test]
-    fn test_get_domain_connection_count() {
+    fn test_result_contracts() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -992,41 +6200,111 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Use default domain
-        let default_domain_id = "default-domain-id";
+        let connection = crate::models::DatabaseConnection::new(
+            None,
+            "postgresql://localhost/test".to_string(),
+            "postgresql".to_string(),
+            None,
+        );
+        let saved = crate::models::SavedQuery::new(
+            "default-domain-id".to_string(),
+            connection.id.clone(),
+            "Revenue".to_string(),
+            "SELECT region, total FROM revenue".to_string(),
+            None,
+        );
+        let contract = crate::models::ResultContract::new(
+            saved.id.clone(),
+            "default-domain-id".to_string(),
+            vec![crate::models::ContractColumn {
+                name: "total".to_string(),
+                data_type: Some(crate::models::ContractType::Float),
+            }],
+        );
 
-        // Initially should have 0 connections
-        let count_before = rt.block_on(async {
-            storage.get_domain_connection_count(default_domain_id).await.unwrap()
+        let (stored, checked, deleted) = rt.block_on(async {
+            storage.save_connection(&connection).await.unwrap();
+            storage.save_query(&saved).await.unwrap();
+            storage.save_result_contract(&contract).await.unwrap();
+            let stored = storage.get_result_contract(&saved.id).await.unwrap().unwrap();
+
+            let drift = vec!["unexpected column region".to_string()];
+            storage
+                .record_result_contract_check(&saved.id, &drift, chrono::Utc::now())
+                .await
+                .unwrap();
+            let checked = storage.get_result_contract(&saved.id).await.unwrap().unwrap();
+            let deleted = storage.delete_result_contract(&saved.id).await.unwrap();
+            (stored, checked, deleted)
         });
 
-        // Create a connection (will use default domain)
+        assert_eq!(stored.columns, contract.columns);
+        assert!(stored.drift.is_empty());
+        assert!(stored.checked_at.is_none());
+        assert_eq!(checked.drift, vec!["unexpected column region"]);
+        assert!(checked.checked_at.is_some());
+        assert!(deleted);
+    }
+
+    #[test]
+    fn test_soft_delete_and_restore_domain() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let storage = rt.block_on(async {
+            SqliteStorage::new(&db_path).await.unwrap()
+        });
+
+        let domain = crate::models::Domain::new("Trashable".to_string(), None).unwrap();
+        let domain_id = domain.id.clone();
         let conn = crate::models::DatabaseConnection {
-            id: "test-conn-456".to_string(),
-            name: Some("Another Test".to_string()),
-            connection_url: "mysql://localhost/test".to_string(),
-            database_type: "mysql".to_string(),
-            status: crate::models::ConnectionStatus::Connected,
+            id: "trash-conn-1".to_string(),
+            name: Some("Trash Connection".to_string()),
+            connection_url: "postgresql://localhost/test".to_string(),
+            database_type: "postgresql".to_string(),
+            status: crate::models::ConnectionStatus::Disconnected,
             created_at: chrono::Utc::now(),
-            last_connected_at: Some(chrono::Utc::now()),
+            last_connected_at: None,
             metadata_cache_id: None,
-            domain_id: Some(default_domain_id.to_string()),
+            version: 1,
+            read_only: false,
+            allow_writes: false,
+            pool_settings: Default::default(),
+            tags: Vec::new(),
+            labels: Default::default(),
+            domain_id: Some(domain_id.clone()),
         };
 
         rt.block_on(async {
+            storage.create_domain(&domain).await.unwrap();
             storage.save_connection(&conn).await.unwrap();
-        });
+            // save_connection does not persist domain_id, so attach it explicitly
+            storage.conn.lock().await.execute(
+                "UPDATE connections SET domain_id = ?1 WHERE id = ?2",
+                rusqlite::params![domain_id, conn.id],
+            ).unwrap();
 
-        // Count should increase by 1
-        let count_after = rt.block_on(async {
-            storage.get_domain_connection_count(default_domain_id).await.unwrap()
-        });
+            assert!(storage.delete_domain(&domain_id).await.unwrap());
+            assert!(storage.get_domain(&domain_id).await.unwrap().is_none());
+            assert!(storage.get_connection(&conn.id).await.unwrap().is_none());
 
-        assert_eq!(count_after, count_before + 1);
+            let trash = storage.list_trash().await.unwrap();
+            assert_eq!(trash.len(), 2);
+            assert!(trash.iter().any(|item| item.resource_type == crate::models::TrashResourceType::Domain));
+
+            // Restoring the domain brings back the connection trashed with it
+            assert!(storage
+                .restore_trash_item(crate::models::TrashResourceType::Domain, &domain_id)
+                .await
+                .unwrap());
+            assert!(storage.get_domain(&domain_id).await.unwrap().is_some());
+            assert!(storage.get_connection(&conn.id).await.unwrap().is_some());
+            assert!(storage.list_trash().await.unwrap().is_empty());
+        });
     }
 
     #[test]
-    fn test_list_connections_by_domain() {
+    fn test_purge_trash_before() {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.db");
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -1034,23 +6312,25 @@ mod tests {
             SqliteStorage::new(&db_path).await.unwrap()
         });
 
-        // Create a custom domain
-        let domain = crate::models::Domain::new("Custom".to_string(), None).unwrap();
+        let domain = crate::models::Domain::new("Purgeable".to_string(), None).unwrap();
         let domain_id = domain.id.clone();
 
         rt.block_on(async {
             storage.create_domain(&domain).await.unwrap();
-        });
+            storage.delete_domain(&domain_id).await.unwrap();
 
-        // Initially should have empty list
-        let conns_before = rt.block_on(async {
-            storage.list_connections_by_domain(&domain_id).await.unwrap()
-        });
-        assert_eq!(conns_before.len(), 0);
+            // Nothing is old enough yet
+            let past = chrono::Utc::now() - chrono::Duration::days(1);
+            assert_eq!(storage.purge_trash_before(past).await.unwrap(), 0);
 
-        // Note: Without modifying save_connection to accept domain_id,
-        // connections will go to default domain
-        // This test validates the query works correctly
+            let future = chrono::Utc::now() + chrono::Duration::seconds(1);
+            assert_eq!(storage.purge_trash_before(future).await.unwrap(), 1);
+            assert!(storage
+                .get_trash_item(crate::models::TrashResourceType::Domain, &domain_id)
+                .await
+                .unwrap()
+                .is_none());
+        });
     }
 }
 
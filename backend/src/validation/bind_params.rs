@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::api::middleware::AppError;
+
+/// How a database driver expects positional bind placeholders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    /// `$1, $2, ...`, a name used twice binds the same value (PostgreSQL)
+    Numbered,
+    /// `?` per occurrence (MySQL, Druid)
+    QuestionMark,
+}
+
+/// Rewrites `:name` placeholders to the driver's positional placeholders
+///
+/// Placeholders inside string literals, quoted identifiers and comments are
+/// left alone, as are PostgreSQL `::type` casts. Values are never spliced into
+/// the SQL; they are returned in binding order for the adapter to bind natively.
+pub struct BindParams;
+
+impl BindParams {
+    /// Rewrite `sql` and return it with the values to bind
+    ///
+    /// Every placeholder needs a value; values must be scalars (null, boolean,
+    /// number or string). Parameters the SQL does not use are ignored.
+    pub fn bind(
+        sql: &str,
+        parameters: &BTreeMap<String, Value>,
+        style: PlaceholderStyle,
+    ) -> Result<(String, Vec<Value>), AppError> {
+        let chars: Vec<char> = sql.chars().collect();
+        let mut rewritten = String::with_capacity(sql.len());
+        let mut values: Vec<Value> = Vec::new();
+        let mut numbered: Vec<&str> = Vec::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                '\'' | '"' | '`' => {
+                    let end = Self::quoted_end(&chars, i, c);
+                    rewritten.extend(&chars[i..end]);
+                    i = end;
+                }
+                '-' if chars.get(i + 1) == Some(&'-') => {
+                    let end = chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |p| i + p);
+                    rewritten.extend(&chars[i..end]);
+                    i = end;
+                }
+                '/' if chars.get(i + 1) == Some(&'*') => {
+                    let end = (i + 2..chars.len().saturating_sub(1))
+                        .find(|&p| chars[p] == '*' && chars[p + 1] == '/')
+                        .map_or(chars.len(), |p| p + 2);
+                    rewritten.extend(&chars[i..end]);
+                    i = end;
+                }
+                ':' if chars.get(i + 1) == Some(&':') => {
+                    rewritten.push_str("::");
+                    i += 2;
+                }
+                ':' if chars.get(i + 1).is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') => {
+                    let start = i + 1;
+                    let end = (start..chars.len())
+                        .find(|&p| !(chars[p].is_ascii_alphanumeric() || chars[p] == '_'))
+                        .unwrap_or(chars.len());
+                    let name: String = chars[start..end].iter().collect();
+                    let (key, value) = parameters
+                        .get_key_value(name.as_str())
+                        .ok_or_else(|| AppError::Validation(format!("Missing value for parameter :{}", name)))?;
+                    Self::ensure_scalar(key, value)?;
+
+                    match style {
+                        PlaceholderStyle::Numbered => {
+                            let position = match numbered.iter().position(|n| *n == key.as_str()) {
+                                Some(position) => position,
+                                None => {
+                                    numbered.push(key);
+                                    values.push(value.clone());
+                                    numbered.len() - 1
+                                }
+                            };
+                            rewritten.push_str(&format!("${}", position + 1));
+                        }
+                        PlaceholderStyle::QuestionMark => {
+                            values.push(value.clone());
+                            rewritten.push('?');
+                        }
+                    }
+                    i = end;
+                }
+                _ => {
+                    rewritten.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        Ok((rewritten, values))
+    }
+
+    /// Reject values that cannot be bound (arrays and objects)
+    pub fn validate(parameters: &BTreeMap<String, Value>) -> Result<(), AppError> {
+        parameters.iter().try_for_each(|(name, value)| Self::ensure_scalar(name, value))
+    }
+
+    fn ensure_scalar(name: &str, value: &Value) -> Result<(), AppError> {
+        if value.is_array() || value.is_object() {
+            return Err(AppError::Validation(format!(
+                "Parameter :{} must be a string, number, boolean or null",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Index just past a quoted section starting at `start` (doubled quotes escape)
+    fn quoted_end(chars: &[char], start: usize, quote: char) -> usize {
+        let mut i = start + 1;
+        while i < chars.len() {
+            if chars[i] == quote {
+                if chars.get(i + 1) == Some(&quote) {
+                    i += 2;
+                    continue;
+                }
+                return i + 1;
+            }
+            if chars[i] == '\\' && quote == '\'' {
+                i += 2;
+                continue;
+            }
+            i += 1;
+        }
+        chars.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(value: Value) -> BTreeMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_bind_numbered_and_question_mark() {
+        let parameters = params(json!({"status": "active", "min": 10, "unused": true}));
+        let sql = "SELECT id::text FROM t WHERE status = :status AND n > :min OR s = :status";
+
+        let (pg, values) = BindParams::bind(sql, &parameters, PlaceholderStyle::Numbered).unwrap();
+        assert_eq!(pg, "SELECT id::text FROM t WHERE status = $1 AND n > $2 OR s = $1");
+        assert_eq!(values, vec![json!("active"), json!(10)]);
+
+        let (mysql, values) = BindParams::bind(sql, &parameters, PlaceholderStyle::QuestionMark).unwrap();
+        assert_eq!(mysql, "SELECT id::text FROM t WHERE status = ? AND n > ? OR s = ?");
+        assert_eq!(values, vec![json!("active"), json!(10), json!("active")]);
+
+        // Named placeholders pass SELECT validation before they are rewritten
+        assert!(crate::validation::SqlValidator::validate_select_only(sql).is_ok());
+    }
+
+    #[test]
+    fn test_quoted_text_and_comments_are_not_placeholders() {
+        let sql = "SELECT ':a', \"x:b\" FROM t -- :c\nWHERE s = 'it''s :d' /* :e */ AND v = :f";
+        let (rewritten, values) =
+            BindParams::bind(sql, &params(json!({"f": null})), PlaceholderStyle::QuestionMark).unwrap();
+        assert_eq!(rewritten, "SELECT ':a', \"x:b\" FROM t -- :c\nWHERE s = 'it''s :d' /* :e */ AND v = ?");
+        assert_eq!(values, vec![Value::Null]);
+
+        let err = BindParams::bind("SELECT :missing", &BTreeMap::new(), PlaceholderStyle::Numbered).unwrap_err();
+        assert!(err.to_string().contains("Missing value for parameter :missing"));
+        let err = BindParams::bind("SELECT :a", &params(json!({"a": [1]})), PlaceholderStyle::Numbered).unwrap_err();
+        assert!(err.to_string().contains("must be a string, number, boolean or null"));
+        assert!(BindParams::validate(&params(json!({"a": {"b": 1}}))).is_err());
+    }
+}
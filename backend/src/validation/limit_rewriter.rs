@@ -0,0 +1,160 @@
+use sqlparser::ast::{
+    Expr, Fetch, LimitClause, Query, SetExpr, Statement, Top, TopQuantity, Value,
+};
+use sqlparser::dialect::Dialect;
+use sqlparser::parser::Parser;
+
+use crate::api::middleware::AppError;
+
+/// How a database expresses a row limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitStyle {
+    /// `... LIMIT n` (PostgreSQL, MySQL, Doris, Druid, DataFusion)
+    Limit,
+    /// `... FETCH FIRST n ROWS ONLY` (ANSI, Oracle, DB2)
+    FetchFirst,
+    /// `SELECT TOP n ...` (SQL Server)
+    Top,
+}
+
+/// Dialect-aware LIMIT injection on the parsed AST
+///
+/// Only the outermost query is inspected and rewritten, so limits inside
+/// subqueries or CTEs are neither mistaken for a top-level limit nor
+/// duplicated.
+pub struct LimitRewriter;
+
+impl LimitRewriter {
+    /// Inject a row limit into the outermost query if it has none
+    ///
+    /// Returns the SQL to execute and whether a limit was added. SQL that
+    /// already has a top-level limit is returned unchanged.
+    pub fn apply(
+        sql: &str,
+        dialect: &dyn Dialect,
+        style: LimitStyle,
+        limit: u64,
+    ) -> Result<(String, bool), AppError> {
+        let mut statements = Parser::parse_sql(dialect, sql)
+            .map_err(|e| AppError::InvalidSql(format!("SQL parsing error: {}", e)))?;
+
+        if statements.len() != 1 {
+            return Err(AppError::InvalidSql(
+                "Exactly one SQL statement is allowed per query".to_string(),
+            ));
+        }
+
+        let Statement::Query(query) = &mut statements[0] else {
+            return Err(AppError::InvalidSql("Only SELECT queries are permitted".to_string()));
+        };
+
+        if Self::has_outer_limit(query) {
+            return Ok((sql.to_string(), false));
+        }
+
+        let rewritten = match style {
+            LimitStyle::Limit => {
+                query.limit_clause = Some(LimitClause::LimitOffset {
+                    limit: Some(Self::number(limit)),
+                    offset: None,
+                    limit_by: vec![],
+                });
+                query.to_string()
+            }
+            LimitStyle::FetchFirst => {
+                query.fetch = Some(Fetch {
+                    with_ties: false,
+                    percent: false,
+                    quantity: Some(Self::number(limit)),
+                });
+                query.to_string()
+            }
+            LimitStyle::Top => match query.body.as_mut() {
+                SetExpr::Select(select) => {
+                    select.top = Some(Top {
+                        with_ties: false,
+                        percent: false,
+                        quantity: Some(TopQuantity::Constant(limit)),
+                    });
+                    query.to_string()
+                }
+                // TOP cannot be attached to a set operation; limit the combined result instead
+                _ => format!("SELECT TOP {} * FROM ({}) AS limited_result", limit, query),
+            },
+        };
+
+        Ok((rewritten, true))
+    }
+
+    /// Whether the outermost query already limits its rows (LIMIT, FETCH or TOP)
+    pub fn has_outer_limit(query: &Query) -> bool {
+        if query.limit_clause.is_some() || query.fetch.is_some() {
+            return true;
+        }
+        matches!(query.body.as_ref(), SetExpr::Select(select) if select.top.is_some())
+    }
+
+    fn number(value: u64) -> Expr {
+        Expr::value(Value::Number(value.to_string(), false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::{GenericDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect};
+
+    #[test]
+    fn test_limit_only_on_outer_query() {
+        let sql = "SELECT * FROM (SELECT id FROM users LIMIT 5) AS u";
+        let (rewritten, applied) = LimitRewriter::apply(sql, &PostgreSqlDialect {}, LimitStyle::Limit, 100).unwrap();
+        assert!(applied);
+        assert_eq!(rewritten, "SELECT * FROM (SELECT id FROM users LIMIT 5) AS u LIMIT 100");
+
+        let sql = "SELECT id FROM users ORDER BY id LIMIT 10";
+        let (rewritten, applied) = LimitRewriter::apply(sql, &MySqlDialect {}, LimitStyle::Limit, 100).unwrap();
+        assert!(!applied);
+        assert_eq!(rewritten, sql);
+    }
+
+    #[test]
+    fn test_fetch_first_style() {
+        let (rewritten, applied) =
+            LimitRewriter::apply("SELECT id FROM users ORDER BY id", &GenericDialect {}, LimitStyle::FetchFirst, 50)
+                .unwrap();
+        assert!(applied);
+        assert_eq!(rewritten, "SELECT id FROM users ORDER BY id FETCH FIRST 50 ROWS ONLY");
+
+        let (_, applied) = LimitRewriter::apply(
+            "SELECT id FROM users FETCH FIRST 5 ROWS ONLY",
+            &GenericDialect {},
+            LimitStyle::FetchFirst,
+            50,
+        )
+        .unwrap();
+        assert!(!applied);
+    }
+
+    #[test]
+    fn test_top_style() {
+        let (rewritten, _) = LimitRewriter::apply("SELECT id FROM users", &MsSqlDialect {}, LimitStyle::Top, 10).unwrap();
+        assert_eq!(rewritten, "SELECT TOP 10 id FROM users");
+
+        let (rewritten, _) = LimitRewriter::apply(
+            "SELECT id FROM a UNION SELECT id FROM b",
+            &MsSqlDialect {},
+            LimitStyle::Top,
+            10,
+        )
+        .unwrap();
+        assert!(rewritten.starts_with("SELECT TOP 10 * FROM (SELECT id FROM a UNION SELECT id FROM b)"));
+
+        let (_, applied) = LimitRewriter::apply("SELECT TOP 3 id FROM users", &MsSqlDialect {}, LimitStyle::Top, 10).unwrap();
+        assert!(!applied);
+    }
+
+    #[test]
+    fn test_rejects_multiple_statements() {
+        assert!(LimitRewriter::apply("SELECT 1; SELECT 2", &GenericDialect {}, LimitStyle::Limit, 10).is_err());
+    }
+}
@@ -1,4 +1,15 @@
+pub mod bind_params;
+pub mod limit_rewriter;
+pub mod sql_comments;
+pub mod sql_fingerprint;
+pub mod sql_format;
+pub mod sql_lint;
 pub mod sql_validator;
 
+pub use bind_params::*;
+pub use limit_rewriter::*;
+pub use sql_comments::*;
+pub use sql_fingerprint::*;
+pub use sql_format::*;
+pub use sql_lint::*;
 pub use sql_validator::*;
-
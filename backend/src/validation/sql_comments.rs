@@ -0,0 +1,175 @@
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+/// Leading comments and optimizer hints of a SQL statement
+///
+/// AST round-tripping (limit injection, dialect translation) drops comments,
+/// so they are captured from the original SQL and restored on the rewritten
+/// statement: leading comments (`-- ticket:123`) are prepended, optimizer
+/// hints (`/*+ ... */`) are re-inserted right after the first SELECT.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SqlComments {
+    /// Comments before the first token, rendered as in the original SQL
+    pub leading: Vec<String>,
+    /// Hint comments directly following the first SELECT keyword
+    pub hints: Vec<String>,
+}
+
+impl SqlComments {
+    /// Capture the leading comments and SELECT hints of a statement
+    pub fn extract(sql: &str) -> Self {
+        let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+            return Self::default();
+        };
+
+        let mut comments = Self::default();
+        let mut iter = tokens.into_iter().peekable();
+
+        // Comments before the first real token
+        while let Some(Token::Whitespace(ws)) = iter.peek() {
+            match ws {
+                Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_) => {
+                    comments.leading.push(ws.to_string().trim_end().to_string());
+                }
+                _ => {}
+            }
+            iter.next();
+        }
+
+        // Skip to the first SELECT (a WITH clause may come first), then collect hints
+        let mut in_select = false;
+        for token in iter {
+            match token {
+                Token::Word(word) if !in_select && word.keyword == Keyword::SELECT => in_select = true,
+                _ if !in_select => {}
+                Token::Whitespace(Whitespace::MultiLineComment(text)) if text.starts_with('+') => {
+                    comments.hints.push(format!("/*{}*/", text));
+                }
+                Token::Whitespace(_) => {}
+                _ => break,
+            }
+        }
+
+        comments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.hints.is_empty()
+    }
+
+    /// Put the captured comments back into a rewritten statement
+    ///
+    /// SQL that still carries its comments (e.g. returned unchanged by the
+    /// limit rewriter) is left alone, so comments are never duplicated.
+    pub fn restore(&self, sql: &str) -> String {
+        if self.is_empty() || !Self::extract(sql).is_empty() {
+            return sql.to_string();
+        }
+
+        let body = if self.hints.is_empty() {
+            sql.to_string()
+        } else {
+            Self::insert_hints(sql, &self.hints.join(" "))
+        };
+
+        if self.leading.is_empty() {
+            body
+        } else {
+            format!("{}\n{}", self.leading.join("\n"), body)
+        }
+    }
+
+    /// Insert hints after the first SELECT keyword
+    fn insert_hints(sql: &str, hints: &str) -> String {
+        let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+            return sql.to_string();
+        };
+
+        let mut rendered = String::with_capacity(sql.len() + hints.len() + 1);
+        let mut inserted = false;
+        for token in tokens {
+            let is_select = matches!(&token, Token::Word(word) if word.keyword == Keyword::SELECT);
+            rendered.push_str(&token.to_string());
+            if is_select && !inserted {
+                rendered.push(' ');
+                rendered.push_str(hints);
+                inserted = true;
+            }
+        }
+        rendered
+    }
+}
+
+/// Who issued a query, appended to outgoing SQL as an audit comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlAuditContext {
+    pub request_id: String,
+    pub user: Option<String>,
+}
+
+impl SqlAuditContext {
+    /// Audit comment, e.g. `/* db-query request_id=abc user=ci */`
+    pub fn comment(&self) -> String {
+        let mut comment = format!("/* db-query request_id={}", sanitize(&self.request_id));
+        if let Some(user) = &self.user {
+            comment.push_str(&format!(" user={}", sanitize(user)));
+        }
+        comment.push_str(" */");
+        comment
+    }
+
+    /// Append the audit comment on its own line (a trailing `--` comment cannot swallow it)
+    pub fn append_to(&self, sql: &str) -> String {
+        format!("{}\n{}", sql.trim_end(), self.comment())
+    }
+}
+
+/// Keep audit values from closing the comment or spanning lines
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | ':'))
+        .take(64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{LimitRewriter, LimitStyle};
+    use sqlparser::dialect::MySqlDialect;
+
+    #[test]
+    fn test_extract_and_restore_comments() {
+        let sql = "-- ticket:123\n/* audit */ SELECT /*+ MAX_EXECUTION_TIME(1000) */ id FROM users";
+        let comments = SqlComments::extract(sql);
+        assert_eq!(comments.leading, vec!["-- ticket:123", "/* audit */"]);
+        assert_eq!(comments.hints, vec!["/*+ MAX_EXECUTION_TIME(1000) */"]);
+
+        let (rewritten, applied) = LimitRewriter::apply(sql, &MySqlDialect {}, LimitStyle::Limit, 100).unwrap();
+        assert!(applied);
+        assert_eq!(
+            comments.restore(&rewritten),
+            "-- ticket:123\n/* audit */\nSELECT /*+ MAX_EXECUTION_TIME(1000) */ id FROM users LIMIT 100"
+        );
+
+        // SQL that kept its comments is not changed
+        assert_eq!(comments.restore(sql), sql);
+        // Plain comments after SELECT are not hints
+        assert!(SqlComments::extract("SELECT /* note */ 1").is_empty());
+    }
+
+    #[test]
+    fn test_audit_comment() {
+        let audit = SqlAuditContext {
+            request_id: "req-1".to_string(),
+            user: Some("ci */ DROP".to_string()),
+        };
+        assert_eq!(audit.comment(), "/* db-query request_id=req-1 user=ciDROP */");
+        assert_eq!(
+            audit.append_to("SELECT 1 -- trailing"),
+            "SELECT 1 -- trailing\n/* db-query request_id=req-1 user=ciDROP */"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+/// Shape of a SQL statement, shared by runs that differ only in literals
+///
+/// String and numeric literals and placeholders become `?` (a list of them,
+/// as in `IN (1, 2, 3)`, becomes a single `?`), comments are dropped,
+/// keywords are uppercased and whitespace collapsed to single spaces.
+/// Identifiers keep their case, since some databases compare table names
+/// case-sensitively. SQL that cannot be tokenized only has its whitespace
+/// collapsed.
+pub struct SqlFingerprint;
+
+/// Output piece of a fingerprint: a token, or a run of whitespace
+#[derive(PartialEq)]
+enum Piece {
+    Text(String),
+    Space,
+}
+
+const LITERAL: &str = "?";
+
+impl SqlFingerprint {
+    pub fn of(sql: &str) -> String {
+        Self::with_literals(sql).0
+    }
+
+    /// Fingerprint of a statement with the literals it replaced, in order
+    ///
+    /// Literals keep their quoting, so `'1'` and `1` stay distinct. The pair
+    /// identifies the statement as well as its text does.
+    pub fn with_literals(sql: &str) -> (String, Vec<String>) {
+        let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+            return (sql.split_whitespace().collect::<Vec<_>>().join(" "), Vec::new());
+        };
+
+        let mut pieces: Vec<Piece> = Vec::new();
+        let mut literals = Vec::new();
+        for token in tokens {
+            let text = match token {
+                Token::Whitespace(_) => {
+                    if !pieces.is_empty() && pieces.last() != Some(&Piece::Space) {
+                        pieces.push(Piece::Space);
+                    }
+                    continue;
+                }
+                Token::EOF => continue,
+                Token::Number(_, _)
+                | Token::SingleQuotedString(_)
+                | Token::NationalStringLiteral(_)
+                | Token::EscapedStringLiteral(_)
+                | Token::HexStringLiteral(_)
+                | Token::DollarQuotedString(_)
+                | Token::Placeholder(_) => {
+                    literals.push(token.to_string());
+                    Self::push_literal(&mut pieces);
+                    continue;
+                }
+                Token::Word(word) if word.quote_style.is_none() && word.keyword != Keyword::NoKeyword => {
+                    word.value.to_uppercase()
+                }
+                other => other.to_string(),
+            };
+            pieces.push(Piece::Text(text));
+        }
+
+        // A trailing semicolon does not change the statement
+        while matches!(pieces.last(), Some(Piece::Space)) || pieces.last() == Some(&Piece::Text(";".to_string())) {
+            pieces.pop();
+        }
+        let fingerprint = pieces
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => text,
+                Piece::Space => " ".to_string(),
+            })
+            .collect();
+        (fingerprint, literals)
+    }
+
+    /// Push a literal, folding `?, ?` lists into one `?`
+    fn push_literal(pieces: &mut Vec<Piece>) {
+        let significant: Vec<usize> = pieces
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, piece)| **piece != Piece::Space)
+            .map(|(i, _)| i)
+            .take(2)
+            .collect();
+        if let [comma, literal] = significant[..] {
+            if pieces[comma] == Piece::Text(",".to_string()) && pieces[literal] == Piece::Text(LITERAL.to_string()) {
+                pieces.truncate(literal + 1);
+                return;
+            }
+        }
+        pieces.push(Piece::Text(LITERAL.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_strips_literals_and_whitespace() {
+        let a = SqlFingerprint::of("select *  from orders\n where customer_id = 42 and region = 'eu';");
+        let b = SqlFingerprint::of("SELECT * FROM orders WHERE customer_id = 7 AND region = 'us' -- retry");
+        assert_eq!(a, "SELECT * FROM orders WHERE customer_id = ? AND region = ?");
+        assert_eq!(a, b);
+
+        assert_eq!(
+            SqlFingerprint::of("SELECT email FROM customers WHERE customer_id IN (1, 2, 3)"),
+            SqlFingerprint::of("SELECT email FROM customers WHERE customer_id IN (4)")
+        );
+        assert_eq!(SqlFingerprint::of("SELECT * FROM t WHERE x = $1"), "SELECT * FROM t WHERE x = ?");
+        // Identifiers keep their case
+        assert_eq!(SqlFingerprint::of("select \"Email\" from Customers"), "SELECT \"Email\" FROM Customers");
+        assert_ne!(
+            SqlFingerprint::of("SELECT * FROM orders"),
+            SqlFingerprint::of("SELECT * FROM customers")
+        );
+
+        let (fingerprint, literals) = SqlFingerprint::with_literals("SELECT * FROM t WHERE x IN (1, '1') LIMIT 10");
+        assert_eq!(fingerprint, "SELECT * FROM t WHERE x IN (?) LIMIT ?");
+        assert_eq!(literals, vec!["1", "'1'", "10"]);
+    }
+}
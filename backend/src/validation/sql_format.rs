@@ -0,0 +1,138 @@
+use sqlparser::ast::Statement;
+use sqlparser::dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+use crate::api::middleware::AppError;
+use crate::models::FormatStyle;
+
+use super::sql_comments::SqlComments;
+
+/// SQL pretty-printing by AST round-tripping
+///
+/// The SQL is parsed and printed back, so formatting never changes what a
+/// statement means. Leading comments and optimizer hints are kept (see
+/// [`SqlComments`]); other comments are dropped by the parser.
+pub struct SqlFormatter;
+
+impl SqlFormatter {
+    /// sqlparser dialect for a dialect or database type name (`None` for the default)
+    pub fn dialect(name: Option<&str>) -> Result<Option<Box<dyn Dialect>>, AppError> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let dialect: Box<dyn Dialect> = match name.to_lowercase().as_str() {
+            "postgresql" | "postgres" => Box::new(PostgreSqlDialect {}),
+            "mysql" | "doris" => Box::new(MySqlDialect {}),
+            "druid" | "generic" | "datafusion" => Box::new(GenericDialect {}),
+            _ => {
+                return Err(AppError::Validation(format!(
+                    "Unknown SQL dialect: {} (expected postgresql, mysql, doris, druid or generic)",
+                    name
+                )))
+            }
+        };
+        Ok(Some(dialect))
+    }
+
+    /// Parse with the given dialect, or PostgreSQL falling back to generic
+    pub fn parse(sql: &str, dialect: Option<&dyn Dialect>) -> Result<Vec<Statement>, AppError> {
+        let statements = match dialect {
+            Some(dialect) => Parser::parse_sql(dialect, sql),
+            None => Parser::parse_sql(&PostgreSqlDialect {}, sql).or_else(|_| Parser::parse_sql(&GenericDialect {}, sql)),
+        }
+        .map_err(|e| AppError::InvalidSql(format!("SQL parsing error: {}", e)))?;
+
+        if statements.is_empty() {
+            return Err(AppError::InvalidSql("Empty SQL query".to_string()));
+        }
+        Ok(statements)
+    }
+
+    /// Format one or more statements (separated by `;`)
+    pub fn format(sql: &str, dialect: Option<&dyn Dialect>, style: &FormatStyle) -> Result<String, AppError> {
+        let statements = Self::parse(sql, dialect)?;
+        let rendered: Vec<String> = statements
+            .iter()
+            .map(|statement| if style.pretty { format!("{:#}", statement) } else { statement.to_string() })
+            .collect();
+        let separator = if style.pretty { ";\n\n" } else { "; " };
+        let body = Self::restyle(&rendered.join(separator), style);
+        Ok(SqlComments::extract(sql).restore(&body))
+    }
+
+    /// Apply keyword case and indentation width to printed SQL
+    ///
+    /// Works on tokens, so string literals and quoted identifiers are never
+    /// touched. Only keywords the printer upper-cased are lower-cased;
+    /// identifiers keep their case.
+    fn restyle(sql: &str, style: &FormatStyle) -> String {
+        const PRINTER_INDENT: usize = 2;
+        if style.uppercase_keywords && (!style.pretty || style.indent == PRINTER_INDENT) {
+            return sql.to_string();
+        }
+        let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+            return sql.to_string();
+        };
+
+        let mut rendered = String::with_capacity(sql.len());
+        let mut line_start = true;
+        let mut pending_spaces = 0;
+        for token in tokens {
+            if line_start {
+                if let Token::Whitespace(Whitespace::Space) = token {
+                    pending_spaces += 1;
+                    continue;
+                }
+                let levels = pending_spaces / PRINTER_INDENT;
+                rendered.push_str(&" ".repeat(levels * style.indent + pending_spaces % PRINTER_INDENT));
+                pending_spaces = 0;
+                line_start = false;
+            }
+
+            match &token {
+                Token::Word(word)
+                    if !style.uppercase_keywords
+                        && word.quote_style.is_none()
+                        && word.keyword != Keyword::NoKeyword
+                        && word.value.chars().all(|c| !c.is_lowercase()) =>
+                {
+                    rendered.push_str(&word.value.to_lowercase());
+                }
+                Token::Whitespace(Whitespace::Newline) => {
+                    rendered.push('\n');
+                    line_start = true;
+                }
+                _ => rendered.push_str(&token.to_string()),
+            }
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_round_trips_and_restyles() {
+        let sql = "-- daily report\nselect id, name from users u join orders o on o.user_id = u.id where o.total > 10";
+        let pretty = SqlFormatter::format(sql, None, &FormatStyle::default()).unwrap();
+        assert!(pretty.starts_with("-- daily report\nSELECT"));
+        assert!(pretty.contains('\n'));
+        // Formatting never changes the statement
+        assert_eq!(SqlFormatter::parse(&pretty, None).unwrap(), SqlFormatter::parse(sql, None).unwrap());
+
+        let style = FormatStyle { pretty: false, uppercase_keywords: false, indent: 2 };
+        let compact = SqlFormatter::format("SELECT Name, 'SELECT' FROM Users WHERE id IN (1,2)", None, &style).unwrap();
+        assert_eq!(compact, "select Name, 'SELECT' from Users where id in (1, 2)");
+
+        let style = FormatStyle { indent: 4, ..FormatStyle::default() };
+        let wide = SqlFormatter::format("SELECT a, b FROM t", None, &style).unwrap();
+        assert!(wide.lines().skip(1).any(|line| line.starts_with("    ") && !line.starts_with("     ")));
+
+        assert!(SqlFormatter::format("SELEC 1", None, &FormatStyle::default()).is_err());
+        assert!(SqlFormatter::dialect(Some("oracle")).is_err());
+    }
+}
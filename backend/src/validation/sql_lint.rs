@@ -0,0 +1,313 @@
+use std::fmt::Display;
+
+use sqlparser::ast::{
+    BinaryOperator, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Join, JoinConstraint, JoinOperator, Query,
+    Select, SelectItem, SetExpr, Spanned, Statement, TableFactor, TableWithJoins, UnaryOperator, Value,
+};
+use sqlparser::dialect::Dialect;
+
+use crate::api::middleware::AppError;
+use crate::models::{LintIssue, LintRule, LintSeverity};
+
+use super::limit_rewriter::LimitRewriter;
+use super::sql_format::SqlFormatter;
+
+/// Static checks on parsed SQL for editor warnings
+///
+/// Every query block is visited, including CTEs, set operations, derived
+/// tables and subqueries in predicates. Only non-query statements are
+/// skipped.
+#[derive(Default)]
+pub struct SqlLinter {
+    issues: Vec<LintIssue>,
+    /// Inside `EXISTS (...)`, where `SELECT *` is idiomatic
+    in_exists: bool,
+}
+
+impl SqlLinter {
+    pub fn lint(sql: &str, dialect: Option<&dyn Dialect>) -> Result<Vec<LintIssue>, AppError> {
+        let statements = SqlFormatter::parse(sql, dialect)?;
+        let mut linter = Self::default();
+        for statement in &statements {
+            let Statement::Query(query) = statement else {
+                continue;
+            };
+            let reads_tables = !matches!(query.body.as_ref(), SetExpr::Select(select) if select.from.is_empty());
+            if reads_tables && !LimitRewriter::has_outer_limit(query) {
+                linter.push(
+                    LintRule::MissingLimit,
+                    LintSeverity::Info,
+                    "Query has no LIMIT and may return every row",
+                    None::<&Expr>,
+                );
+            }
+            linter.query(query);
+        }
+        Ok(linter.issues)
+    }
+
+    fn push<T: Spanned + Display>(&mut self, rule: LintRule, severity: LintSeverity, message: &str, node: Option<&T>) {
+        let start = node.map(|node| node.span().start).filter(|start| start.line > 0);
+        self.issues.push(LintIssue {
+            rule,
+            severity,
+            message: message.to_string(),
+            fragment: node.map(|node| node.to_string()),
+            line: start.map(|start| start.line),
+            column: start.map(|start| start.column),
+        });
+    }
+
+    fn query(&mut self, query: &Query) {
+        if let Some(with) = &query.with {
+            for cte in &with.cte_tables {
+                self.query(&cte.query);
+            }
+        }
+        self.set_expr(&query.body);
+    }
+
+    fn set_expr(&mut self, body: &SetExpr) {
+        match body {
+            SetExpr::Select(select) => self.select(select),
+            SetExpr::Query(query) => self.query(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.set_expr(left);
+                self.set_expr(right);
+            }
+            _ => {}
+        }
+    }
+
+    fn select(&mut self, select: &Select) {
+        if !self.in_exists {
+            for item in &select.projection {
+                if matches!(item, SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)) {
+                    self.push(
+                        LintRule::SelectStar,
+                        LintSeverity::Info,
+                        "List the needed columns instead of selecting all of them",
+                        Some(item),
+                    );
+                }
+            }
+        }
+
+        if select.from.len() > 1 && select.selection.is_none() {
+            self.push(
+                LintRule::CartesianJoin,
+                LintSeverity::Warning,
+                "Tables listed in FROM without a WHERE condition produce a cartesian product",
+                Some(&select.from[1]),
+            );
+        }
+        for table in &select.from {
+            self.table_with_joins(table);
+        }
+
+        for predicate in select.selection.iter().chain(select.having.iter()) {
+            self.predicate(predicate);
+        }
+    }
+
+    fn table_with_joins(&mut self, table: &TableWithJoins) {
+        self.table_factor(&table.relation);
+        for join in &table.joins {
+            self.table_factor(&join.relation);
+            match Self::join_constraint(join) {
+                Some(JoinConstraint::On(on)) => self.predicate(on),
+                Some(JoinConstraint::None) => self.push(
+                    LintRule::CartesianJoin,
+                    LintSeverity::Warning,
+                    "JOIN without an ON or USING condition produces a cartesian product",
+                    Some(&join.relation),
+                ),
+                Some(_) => {}
+                None if join.to_string().trim_start().starts_with("CROSS JOIN") => self.push(
+                    LintRule::CartesianJoin,
+                    LintSeverity::Warning,
+                    "CROSS JOIN produces a cartesian product",
+                    Some(&join.relation),
+                ),
+                None => {}
+            }
+        }
+    }
+
+    /// Constraint of the plain, inner and outer joins
+    fn join_constraint(join: &Join) -> Option<&JoinConstraint> {
+        match &join.join_operator {
+            JoinOperator::Join(constraint)
+            | JoinOperator::Inner(constraint)
+            | JoinOperator::Left(constraint)
+            | JoinOperator::LeftOuter(constraint)
+            | JoinOperator::Right(constraint)
+            | JoinOperator::RightOuter(constraint)
+            | JoinOperator::FullOuter(constraint) => Some(constraint),
+            _ => None,
+        }
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor) {
+        match factor {
+            TableFactor::Derived { subquery, .. } => self.query(subquery),
+            TableFactor::NestedJoin { table_with_joins, .. } => self.table_with_joins(table_with_joins),
+            _ => {}
+        }
+    }
+
+    fn predicate(&mut self, expr: &Expr) {
+        match expr {
+            Expr::BinaryOp { left, op: BinaryOperator::And | BinaryOperator::Or, right } => {
+                self.predicate(left);
+                self.predicate(right);
+            }
+            Expr::Nested(inner) | Expr::UnaryOp { op: UnaryOperator::Not, expr: inner } => self.predicate(inner),
+            Expr::BinaryOp {
+                left,
+                op:
+                    BinaryOperator::Eq
+                    | BinaryOperator::NotEq
+                    | BinaryOperator::Lt
+                    | BinaryOperator::LtEq
+                    | BinaryOperator::Gt
+                    | BinaryOperator::GtEq,
+                right,
+            } => {
+                for side in [left, right] {
+                    match side.as_ref() {
+                        Expr::Subquery(query) => self.query(query),
+                        side => self.sargable(side),
+                    }
+                }
+            }
+            Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+                self.sargable(expr);
+                if let Expr::Value(value) = pattern.as_ref() {
+                    if matches!(&value.value, Value::SingleQuotedString(text) if text.starts_with('%')) {
+                        self.push(
+                            LintRule::NonSargablePredicate,
+                            LintSeverity::Warning,
+                            "A LIKE pattern starting with a wildcard cannot use an index",
+                            Some(expr.as_ref()),
+                        );
+                    }
+                }
+            }
+            Expr::Between { expr, .. } | Expr::InList { expr, .. } | Expr::IsNull(expr) | Expr::IsNotNull(expr) => {
+                self.sargable(expr)
+            }
+            Expr::InSubquery { expr, subquery, .. } => {
+                self.sargable(expr);
+                self.query(subquery);
+            }
+            Expr::Exists { subquery, .. } => {
+                let in_exists = std::mem::replace(&mut self.in_exists, true);
+                self.query(subquery);
+                self.in_exists = in_exists;
+            }
+            _ => {}
+        }
+    }
+
+    /// Flag a compared expression that wraps a column
+    fn sargable(&mut self, expr: &Expr) {
+        if Self::wraps_column(expr) {
+            self.push(
+                LintRule::NonSargablePredicate,
+                LintSeverity::Warning,
+                "Comparing a function, cast or arithmetic on a column prevents index use; compare the bare column",
+                Some(expr),
+            );
+        }
+    }
+
+    fn wraps_column(expr: &Expr) -> bool {
+        match expr {
+            Expr::Nested(inner) => Self::wraps_column(inner),
+            Expr::Function(_) | Expr::Cast { .. } | Expr::Extract { .. } | Expr::Substring { .. } => {
+                Self::references_column(expr)
+            }
+            Expr::BinaryOp {
+                op:
+                    BinaryOperator::Plus
+                    | BinaryOperator::Minus
+                    | BinaryOperator::Multiply
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Modulo
+                    | BinaryOperator::StringConcat,
+                ..
+            } => Self::references_column(expr),
+            _ => false,
+        }
+    }
+
+    fn references_column(expr: &Expr) -> bool {
+        match expr {
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => true,
+            Expr::Nested(inner)
+            | Expr::UnaryOp { expr: inner, .. }
+            | Expr::Cast { expr: inner, .. }
+            | Expr::Extract { expr: inner, .. }
+            | Expr::Substring { expr: inner, .. } => Self::references_column(inner),
+            Expr::BinaryOp { left, right, .. } => Self::references_column(left) || Self::references_column(right),
+            Expr::Function(function) => match &function.args {
+                FunctionArguments::List(list) => list.args.iter().any(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))
+                    | FunctionArg::Named { arg: FunctionArgExpr::Expr(arg), .. } => Self::references_column(arg),
+                    _ => false,
+                }),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(sql: &str) -> Vec<LintRule> {
+        SqlLinter::lint(sql, None).unwrap().into_iter().map(|issue| issue.rule).collect()
+    }
+
+    #[test]
+    fn test_select_star_and_missing_limit() {
+        let issues = SqlLinter::lint("SELECT *\nFROM users", None).unwrap();
+        assert_eq!(issues[0].rule, LintRule::MissingLimit);
+        assert_eq!(issues[1].rule, LintRule::SelectStar);
+        assert_eq!(issues[1].fragment.as_deref(), Some("*"));
+
+        assert!(rules("SELECT id FROM users LIMIT 10").is_empty());
+        assert!(rules("SELECT 1").is_empty());
+        // Limits in subqueries do not count; EXISTS (SELECT * ...) is fine
+        assert_eq!(
+            rules("SELECT id FROM (SELECT id FROM users LIMIT 5) u WHERE EXISTS (SELECT * FROM orders o WHERE o.user_id = u.id)"),
+            vec![LintRule::MissingLimit]
+        );
+    }
+
+    #[test]
+    fn test_cartesian_joins() {
+        assert_eq!(rules("SELECT a.id FROM a, b LIMIT 1"), vec![LintRule::CartesianJoin]);
+        assert!(rules("SELECT a.id FROM a, b WHERE a.id = b.a_id LIMIT 1").is_empty());
+        assert_eq!(rules("SELECT a.id FROM a CROSS JOIN b LIMIT 1"), vec![LintRule::CartesianJoin]);
+        assert!(rules("SELECT a.id FROM a JOIN b ON a.id = b.a_id LEFT JOIN c USING (id) LIMIT 1").is_empty());
+    }
+
+    #[test]
+    fn test_non_sargable_predicates() {
+        let issues = SqlLinter::lint(
+            "SELECT id FROM orders WHERE DATE(created_at) = '2024-01-01' AND total * 2 > 10 AND name LIKE '%smith' LIMIT 5",
+            None,
+        )
+        .unwrap();
+        let fragments: Vec<_> = issues.iter().map(|issue| issue.fragment.as_deref().unwrap()).collect();
+        assert!(issues.iter().all(|issue| issue.rule == LintRule::NonSargablePredicate));
+        assert_eq!(fragments, vec!["DATE(created_at)", "total * 2", "name"]);
+        assert_eq!((issues[0].line, issues[0].column), (Some(1), Some(29)));
+
+        assert!(rules("SELECT id FROM orders WHERE created_at >= CAST('2024-01-01' AS DATE) AND name LIKE 'smith%' LIMIT 5").is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 use sqlparser::ast::Statement;
 use sqlparser::parser::Parser;
-use sqlparser::dialect::{PostgreSqlDialect, GenericDialect};
+use sqlparser::dialect::{PostgreSqlDialect, GenericDialect, MySqlDialect};
 use crate::api::middleware::AppError;
+use super::limit_rewriter::{LimitRewriter, LimitStyle};
 
 /// SQL validation service for ensuring queries are safe and valid
 pub struct SqlValidator;
@@ -71,111 +72,78 @@ impl SqlValidator {
         Ok(sql.to_string())
     }
 
-    /// Check if query has LIMIT clause and append if missing
-    /// Uses AST parsing to properly detect LIMIT clauses, avoiding false positives
-    /// Supports both PostgreSQL and DataFusion SQL syntax
-    pub fn ensure_limit(sql: &str, default_limit: u64) -> Result<String, AppError> {
-        // Try PostgreSQL dialect first
-        let postgres_result = Self::parse_for_limit(sql, &PostgreSqlDialect {}, default_limit);
-
-        // Fallback to GenericDialect if PostgreSQL parsing fails
-        match postgres_result {
-            Ok(result) => Ok(result),
-            Err(_) => Self::parse_for_limit(sql, &GenericDialect {}, default_limit)
-        }
-    }
-
-    /// Internal helper to parse and check LIMIT with specific dialect
-    fn parse_for_limit<D: sqlparser::dialect::Dialect>(
-        sql: &str,
-        dialect: &D,
-        default_limit: u64,
-    ) -> Result<String, AppError> {
-        let mut parser = Parser::new(dialect).try_with_sql(sql)
+    /// Validate a write statement for the write-mode endpoint
+    ///
+    /// Exactly one INSERT, UPDATE, DELETE, MERGE, TRUNCATE or DDL statement
+    /// (CREATE/ALTER/DROP of tables, views, indexes and schemas) is accepted;
+    /// SELECT belongs on the query endpoint, and permission, session and
+    /// transaction statements are rejected. Returns the statement kind
+    /// (e.g. `INSERT`).
+    pub fn validate_write(sql: &str) -> Result<&'static str, AppError> {
+        let statements = Parser::parse_sql(&PostgreSqlDialect {}, sql)
+            .or_else(|_| Parser::parse_sql(&MySqlDialect {}, sql))
+            .or_else(|_| Parser::parse_sql(&GenericDialect {}, sql))
             .map_err(|e| AppError::InvalidSql(format!("SQL parsing error: {}", e)))?;
 
-        // Parse SQL statement
-        let ast = parser.parse_statements()
-            .map_err(|e| AppError::InvalidSql(format!("SQL parsing error: {}", e)))?;
-
-        if ast.is_empty() {
-            return Err(AppError::InvalidSql("Empty SQL query".to_string()));
-        }
-
-        // Get the first statement (we already validated it's a Query in validate_select_only)
-        let stmt = &ast[0];
-
-        // Check if LIMIT exists by parsing the AST
-        let has_limit = Self::check_limit_in_statement(stmt);
+        let statement = match statements.as_slice() {
+            [statement] => statement,
+            [] => return Err(AppError::InvalidSql("Empty SQL statement".to_string())),
+            _ => return Err(AppError::InvalidSql("Send one write statement per request".to_string())),
+        };
 
-        if has_limit {
-            // LIMIT already exists, return original SQL
-            Ok(sql.to_string())
-        } else {
-            // Append LIMIT clause
-            let trimmed_sql = sql.trim_end_matches(';').trim();
-            Ok(format!("{} LIMIT {}", trimmed_sql, default_limit))
-        }
+        let kind = match statement {
+            Statement::Insert { .. } => "INSERT",
+            Statement::Update { .. } => "UPDATE",
+            Statement::Delete { .. } => "DELETE",
+            Statement::Merge { .. } => "MERGE",
+            Statement::Truncate { .. } => "TRUNCATE",
+            Statement::CreateTable { .. } => "CREATE TABLE",
+            Statement::CreateView { .. } => "CREATE VIEW",
+            Statement::CreateIndex { .. } => "CREATE INDEX",
+            Statement::CreateSchema { .. } => "CREATE SCHEMA",
+            Statement::AlterTable { .. } => "ALTER TABLE",
+            Statement::AlterView { .. } => "ALTER VIEW",
+            Statement::AlterIndex { .. } => "ALTER INDEX",
+            Statement::Drop { .. } => "DROP",
+            Statement::Query(_) => {
+                return Err(AppError::InvalidSql(
+                    "SELECT queries run on the query endpoint, not as write statements".to_string(),
+                ))
+            }
+            other => {
+                return Err(AppError::InvalidSql(format!(
+                    "Statement is not allowed in write mode: {}",
+                    other
+                )))
+            }
+        };
+        Ok(kind)
     }
 
-    /// Check if a statement has a LIMIT clause using AST analysis
-    fn check_limit_in_statement(stmt: &Statement) -> bool {
-        match stmt {
-            Statement::Query(query) => {
-                // Check if the query has a LIMIT clause
-                // Query has body, order_by, limit_clause, offset, etc
-                // The limit_clause field is Option<LimitClause>
-                query.limit_clause.is_some()
-            }
-            _ => false,
-        }
+    /// Check if the outermost query has a LIMIT clause and inject one if missing
+    /// Uses AST rewriting so limits inside subqueries or comments are not mistaken
+    /// for a top-level limit. Supports both PostgreSQL and DataFusion SQL syntax
+    pub fn ensure_limit(sql: &str, default_limit: u64) -> Result<String, AppError> {
+        Self::apply_limit(sql, default_limit).map(|(sql, _)| sql)
     }
 
     /// Validate and prepare SQL query (validate SELECT-only and ensure LIMIT)
     pub fn validate_and_prepare(sql: &str, default_limit: u64) -> Result<(String, bool), AppError> {
         // First validate it's SELECT-only
         let validated_sql = Self::validate_select_only(sql)?;
-        
-        // Check if LIMIT was already present
-        let original_has_limit = Self::has_limit(&validated_sql);
-        
-        // Ensure LIMIT exists
-        let final_sql = Self::ensure_limit(&validated_sql, default_limit)?;
-        
-        let limit_applied = !original_has_limit;
-        
-        Ok((final_sql, limit_applied))
-    }
-
-    /// Check if SQL has LIMIT clause using AST parsing
-    /// Supports both PostgreSQL and DataFusion SQL syntax
-    fn has_limit(sql: &str) -> bool {
-        // Try PostgreSQL dialect first
-        if Self::has_limit_with_dialect(sql, &PostgreSqlDialect {}) {
-            return true;
-        }
 
-        // Fallback to GenericDialect
-        Self::has_limit_with_dialect(sql, &GenericDialect {})
+        // Ensure LIMIT exists; the flag reports whether one had to be injected
+        Self::apply_limit(&validated_sql, default_limit)
     }
 
-    /// Internal helper to check LIMIT with specific dialect
-    fn has_limit_with_dialect<D: sqlparser::dialect::Dialect>(sql: &str, dialect: &D) -> bool {
-        let mut parser = match Parser::new(dialect).try_with_sql(sql) {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
-
-        let ast = match parser.parse_statements() {
-            Ok(statements) => statements,
-            Err(_) => return false,
-        };
-
-        if ast.is_empty() {
-            return false;
+    /// Inject a generic `LIMIT n` into the outermost query
+    /// Tries PostgreSQL dialect first, then GenericDialect for DataFusion syntax.
+    /// Adapters needing another limit syntax use `DatabaseAdapter::apply_row_limit`
+    fn apply_limit(sql: &str, default_limit: u64) -> Result<(String, bool), AppError> {
+        match LimitRewriter::apply(sql, &PostgreSqlDialect {}, LimitStyle::Limit, default_limit) {
+            Ok(result) => Ok(result),
+            Err(_) => LimitRewriter::apply(sql, &GenericDialect {}, LimitStyle::Limit, default_limit),
         }
-
-        Self::check_limit_in_statement(&ast[0])
     }
 }
 
@@ -198,6 +166,20 @@ mod tests {
         assert!(SqlValidator::validate_select_only("DELETE FROM users").is_err());
     }
 
+    #[test]
+    fn test_validate_write() {
+        assert_eq!(SqlValidator::validate_write("INSERT INTO users (name) VALUES ('a')").unwrap(), "INSERT");
+        assert_eq!(SqlValidator::validate_write("UPDATE users SET name = 'b' WHERE id = 1").unwrap(), "UPDATE");
+        assert_eq!(SqlValidator::validate_write("DELETE FROM users WHERE id = 1").unwrap(), "DELETE");
+        assert_eq!(SqlValidator::validate_write("CREATE TABLE t (id INT)").unwrap(), "CREATE TABLE");
+        assert_eq!(SqlValidator::validate_write("DROP TABLE t").unwrap(), "DROP");
+
+        // Reads, multiple statements and permission changes are rejected
+        assert!(SqlValidator::validate_write("SELECT * FROM users").is_err());
+        assert!(SqlValidator::validate_write("DELETE FROM a; DELETE FROM b").is_err());
+        assert!(SqlValidator::validate_write("GRANT ALL ON users TO bob").is_err());
+    }
+
     #[test]
     fn test_ensure_limit() {
         // Query without LIMIT
@@ -211,6 +193,15 @@ mod tests {
         assert_eq!(result, sql);
     }
 
+    #[test]
+    fn test_ensure_limit_ignores_subquery_limit() {
+        let sql = "SELECT * FROM (SELECT * FROM users LIMIT 10) AS u";
+        let (result, limit_applied) = SqlValidator::validate_and_prepare(sql, 1000).unwrap();
+        assert!(limit_applied);
+        assert!(result.ends_with("AS u LIMIT 1000"));
+        assert_eq!(result.matches("LIMIT").count(), 2);
+    }
+
     #[test]
     fn test_validate_and_prepare() {
         // Valid query without LIMIT
@@ -81,7 +81,10 @@ export const DomainManager: React.FC<DomainManagerProps> = ({ onDomainSelect })
     try {
       if (editingDomain) {
         // Update existing domain
-        await domainService.updateDomain(editingDomain.id, values as UpdateDomainRequest);
+        await domainService.updateDomain(editingDomain.id, {
+          ...(values as UpdateDomainRequest),
+          expected_version: editingDomain.version,
+        });
         message.success('Domain更新成功');
       } else {
         // Create new domain
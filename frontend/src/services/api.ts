@@ -16,7 +16,11 @@ const apiClient: AxiosInstance = axios.create({
 // Request interceptor
 apiClient.interceptors.request.use(
   (config) => {
-    // Add any auth tokens or headers here if needed
+    // Send the API key when the backend requires authentication (AUTH_ENABLED)
+    const apiKey = import.meta.env.VITE_API_KEY || localStorage.getItem('apiKey');
+    if (apiKey) {
+      config.headers.Authorization = `Bearer ${apiKey}`;
+    }
     return config;
   },
   (error) => {
@@ -40,6 +44,12 @@ apiClient.interceptors.response.use(
         case 400:
           console.error('Bad Request:', data.error?.message || error.message);
           break;
+        case 401:
+          console.error('Unauthorized:', data.error?.message || error.message);
+          break;
+        case 403:
+          console.error('Forbidden:', data.error?.message || error.message);
+          break;
         case 404:
           console.error('Not Found:', data.error?.message || error.message);
           break;
@@ -65,10 +75,39 @@ export const axiosInstance = apiClient;
 export default apiClient;
 
 // Export error types for use in components
+
+/** Machine-readable error codes returned by the backend (`error.code`) */
+export type ErrorCode =
+  | 'DATABASE_ERROR'
+  | 'TABLE_NOT_FOUND'
+  | 'COLUMN_NOT_FOUND'
+  | 'SQL_SYNTAX_ERROR'
+  | 'PERMISSION_DENIED'
+  | 'QUERY_TIMEOUT'
+  | 'QUERY_CANCELLED'
+  | 'CONNECTION_ERROR'
+  | 'INVALID_SQL'
+  | 'VALIDATION_ERROR'
+  | 'LLM_SERVICE_ERROR'
+  | 'NOT_FOUND'
+  | 'NOT_IMPLEMENTED'
+  | 'CONFLICT'
+  | 'CONFIRMATION_REQUIRED'
+  | 'PRECONDITION_REQUIRED'
+  | 'PAYLOAD_TOO_LARGE'
+  | 'INPUT_TOO_LONG'
+  | 'UNAUTHORIZED'
+  | 'FORBIDDEN'
+  | 'QUOTA_EXCEEDED'
+  | 'CIRCUIT_OPEN'
+  | 'INTERNAL_ERROR';
+
 export interface ApiError {
-  code: string;
+  code: ErrorCode | 'UNKNOWN_ERROR';
   message: string;
   details?: string;
+  data?: unknown;
+  request_id?: string;
 }
 
 export const extractApiError = (error: unknown): ApiError => {
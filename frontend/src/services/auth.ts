@@ -0,0 +1,32 @@
+import api from './api';
+import type { DomainRole, DomainRoleAssignment, LoginResponse } from '../types';
+
+// Auth API functions
+export const login = async (username: string, password: string): Promise<LoginResponse> => {
+  const response = await api.post('/auth/login', { username, password });
+  // The API client sends the stored token as a Bearer credential
+  localStorage.setItem('apiKey', response.data.token);
+  return response.data;
+};
+
+export const logout = (): void => {
+  localStorage.removeItem('apiKey');
+};
+
+export const listDomainRoles = async (domainId: string): Promise<DomainRoleAssignment[]> => {
+  const response = await api.get(`/domains/${domainId}/roles`);
+  return response.data;
+};
+
+export const assignDomainRole = async (
+  domainId: string,
+  userId: string,
+  role: DomainRole
+): Promise<DomainRoleAssignment> => {
+  const response = await api.put(`/domains/${domainId}/roles`, { user_id: userId, role });
+  return response.data;
+};
+
+export const removeDomainRole = async (domainId: string, userId: string): Promise<void> => {
+  await api.delete(`/domains/${domainId}/roles/${userId}`);
+};
@@ -1,12 +1,24 @@
 import { axiosInstance } from './api';
-import { DatabaseConnection, CreateConnectionRequest } from '../types';
+import {
+  ConnectionTemplate,
+  ConnectionUrlFields,
+  ConnectionUrlResponse,
+  DatabaseConnection,
+  CreateConnectionRequest,
+  DuplicateConnectionRequest,
+  ListParams,
+  MoveConnectionRequest,
+  PoolStats,
+} from '../types';
 
 export const connectionService = {
   /**
    * List all database connections
    */
-  async listConnections(): Promise<DatabaseConnection[]> {
-    const response = await axiosInstance.get<{ connections: DatabaseConnection[] }>('/connections');
+  async listConnections(
+    params: ListParams & { labels?: string; tag?: string } = { page_size: 500 }
+  ): Promise<DatabaseConnection[]> {
+    const response = await axiosInstance.get<{ connections: DatabaseConnection[] }>('/connections', { params });
     return response.data.connections;
   },
 
@@ -21,6 +33,25 @@ export const connectionService = {
     return response.data;
   },
 
+  /**
+   * List the connection URL templates of the supported database types
+   */
+  async listTemplates(): Promise<ConnectionTemplate[]> {
+    const response = await axiosInstance.get<{ templates: ConnectionTemplate[] }>('/connections/templates');
+    return response.data.templates;
+  },
+
+  /**
+   * Assemble a connection URL from structured fields
+   */
+  async buildUrl(databaseType: string, fields: ConnectionUrlFields): Promise<ConnectionUrlResponse> {
+    const response = await axiosInstance.post<ConnectionUrlResponse>('/connections/url', {
+      database_type: databaseType,
+      fields,
+    });
+    return response.data;
+  },
+
   /**
    * Get connection details by ID
    */
@@ -29,6 +60,30 @@ export const connectionService = {
     return response.data;
   },
 
+  /**
+   * Copy a connection, optionally into another domain
+   */
+  async duplicateConnection(id: string, data: DuplicateConnectionRequest = {}): Promise<DatabaseConnection> {
+    const response = await axiosInstance.post<DatabaseConnection>(`/connections/${id}/duplicate`, data);
+    return response.data;
+  },
+
+  /**
+   * Move a connection to another domain
+   */
+  async moveConnection(id: string, data: MoveConnectionRequest): Promise<DatabaseConnection> {
+    const response = await axiosInstance.post<DatabaseConnection>(`/connections/${id}/move`, data);
+    return response.data;
+  },
+
+  /**
+   * Get the pool limits and usage of a connection
+   */
+  async getPoolStats(id: string): Promise<PoolStats> {
+    const response = await axiosInstance.get<PoolStats>(`/connections/${id}/pool-stats`);
+    return response.data;
+  },
+
   /**
    * Delete a connection
    */
@@ -1,5 +1,7 @@
 import apiClient from './api';
 import type {
+  CrossDatabaseNaturalLanguageRequest,
+  CrossDatabaseNaturalLanguageResponse,
   CrossDatabaseQueryRequest,
   CrossDatabaseQueryResponse,
 } from '../types/cross-database';
@@ -18,6 +20,20 @@ export const crossDatabaseQueryService = {
     return response.data;
   },
 
+  /**
+   * Answer a natural language question over the connections of a domain
+   */
+  async askQuestion(
+    domainId: string,
+    request: CrossDatabaseNaturalLanguageRequest
+  ): Promise<CrossDatabaseNaturalLanguageResponse> {
+    const response = await apiClient.post<CrossDatabaseNaturalLanguageResponse>(
+      `/domains/${domainId}/nl-query`,
+      request
+    );
+    return response.data;
+  },
+
   /**
    * Validate cross-database query syntax
    */
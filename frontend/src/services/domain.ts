@@ -3,15 +3,32 @@ import {
   DomainResponse,
   CreateDomainRequest,
   UpdateDomainRequest,
-  DatabaseConnection
+  DatabaseConnection,
+  DomainSettings,
+  DomainStats,
+  Favorite,
+  FavoriteType,
+  RecentQuery,
+  SlowQueryReport,
+  SlowQuerySort,
+  UpdateDomainSettingsRequest,
+  DomainBundle,
+  ImportDomainRequest,
+  ImportDomainResponse,
+  ImportConnectionsRequest,
+  ImportConnectionsResponse,
+  ListParams,
+  LlmUsageReport,
+  LlmAuditEntry,
+  PageInfo
 } from '../types';
 
 export const domainService = {
   /**
    * List all domains with resource counts
    */
-  async listDomains(): Promise<DomainResponse[]> {
-    const response = await axiosInstance.get<{ domains: DomainResponse[] }>('/domains');
+  async listDomains(params: ListParams = { page_size: 500 }): Promise<DomainResponse[]> {
+    const response = await axiosInstance.get<{ domains: DomainResponse[] }>('/domains', { params });
     return response.data.domains;
   },
 
@@ -49,8 +66,121 @@ export const domainService = {
   /**
    * List connections for a specific domain
    */
-  async listDomainConnections(id: string): Promise<DatabaseConnection[]> {
-    const response = await axiosInstance.get<{ connections: DatabaseConnection[] }>(`/domains/${id}/connections`);
+  async listDomainConnections(id: string, params: { labels?: string; tag?: string } = {}): Promise<DatabaseConnection[]> {
+    const response = await axiosInstance.get<{ connections: DatabaseConnection[] }>(`/domains/${id}/connections`, {
+      params,
+    });
     return response.data.connections;
   },
+
+  /**
+   * Get domain settings (row limit, timeout, retention, allowed database types)
+   */
+  async getDomainSettings(id: string): Promise<DomainSettings> {
+    const response = await axiosInstance.get<DomainSettings>(`/domains/${id}/settings`);
+    return response.data;
+  },
+
+  /**
+   * Update domain settings (omitted fields are unchanged)
+   */
+  async updateDomainSettings(id: string, data: UpdateDomainSettingsRequest): Promise<DomainSettings> {
+    const response = await axiosInstance.put<DomainSettings>(`/domains/${id}/settings`, data);
+    return response.data;
+  },
+
+  /**
+   * Get a domain's query counts, failure rate, execution time, rows scanned, top connections and users
+   */
+  async getStats(id: string, params?: { days?: number; top?: number }): Promise<DomainStats> {
+    const response = await axiosInstance.get<DomainStats>(`/domains/${id}/stats`, { params });
+    return response.data;
+  },
+
+  /**
+   * Get a domain's slow queries grouped by SQL fingerprint, ranked by total or average time
+   */
+  async getSlowQueries(
+    id: string,
+    params?: { days?: number; top?: number; sort?: SlowQuerySort }
+  ): Promise<SlowQueryReport> {
+    const response = await axiosInstance.get<SlowQueryReport>(`/domains/${id}/slow-queries`, { params });
+    return response.data;
+  },
+
+  /**
+   * List the caller's favorite connections and saved queries in a domain
+   */
+  async listFavorites(id: string): Promise<Favorite[]> {
+    const response = await axiosInstance.get<{ favorites: Favorite[] }>(`/domains/${id}/favorites`);
+    return response.data.favorites;
+  },
+
+  async addFavorite(id: string, type: FavoriteType, resourceId: string): Promise<void> {
+    await axiosInstance.put(`/domains/${id}/favorites/${type}/${resourceId}`);
+  },
+
+  async removeFavorite(id: string, type: FavoriteType, resourceId: string): Promise<void> {
+    await axiosInstance.delete(`/domains/${id}/favorites/${type}/${resourceId}`);
+  },
+
+  /**
+   * List the queries the caller ran recently in a domain, latest first
+   */
+  async listRecentQueries(id: string, limit?: number): Promise<RecentQuery[]> {
+    const response = await axiosInstance.get<{ recent: RecentQuery[] }>(`/domains/${id}/recent`, {
+      params: limit ? { limit } : undefined,
+    });
+    return response.data.recent;
+  },
+
+  /**
+   * Get a domain's LLM requests and tokens for a month (`YYYY-MM`, default: current) with its budget
+   */
+  async getLlmUsage(id: string, month?: string): Promise<LlmUsageReport> {
+    const response = await axiosInstance.get<LlmUsageReport>(`/domains/${id}/llm-usage`, {
+      params: month ? { month } : undefined,
+    });
+    return response.data;
+  },
+
+  /**
+   * List the prompts sent to the LLM for a domain, as sent, with their redactions (Admin)
+   */
+  async listLlmAudit(
+    id: string,
+    params: ListParams = {}
+  ): Promise<{ entries: LlmAuditEntry[] } & PageInfo> {
+    const response = await axiosInstance.get<{ entries: LlmAuditEntry[] } & PageInfo>(
+      `/domains/${id}/llm-audit`,
+      { params }
+    );
+    return response.data;
+  },
+
+  /**
+   * Export a domain as a portable bundle (connection URLs are templated)
+   */
+  async exportDomain(id: string): Promise<DomainBundle> {
+    const response = await axiosInstance.get<DomainBundle>(`/domains/${id}/export`, {
+      params: { format: 'json' },
+    });
+    return response.data;
+  },
+
+  /**
+   * Import a bundle as a new domain, filling in connection URL variables
+   */
+  async importDomain(data: ImportDomainRequest): Promise<ImportDomainResponse> {
+    const response = await axiosInstance.post<ImportDomainResponse>('/domains/import', data);
+    return response.data;
+  },
+
+  /**
+   * Import connection definitions into a domain
+   */
+  async importConnections(id: string, data: ImportConnectionsRequest): Promise<ImportConnectionsResponse> {
+    const response = await axiosInstance.post<ImportConnectionsResponse>(`/domains/${id}/connections/import`, data);
+    return response.data;
+  },
 };
@@ -1,5 +1,5 @@
 import { axiosInstance } from './api';
-import { DatabaseMetadata } from '../types';
+import { DatabaseMetadata, DictionaryEntry, DictionaryEntryRequest, TableProfile } from '../types';
 
 export const metadataService = {
   /**
@@ -15,5 +15,72 @@ export const metadataService = {
     );
     return response.data;
   },
-};
 
+  /**
+   * Re-read the schema of a connection, saving it as a new metadata version
+   * @param connectionId - The connection ID
+   * @param full - Read every table, not only new or changed ones
+   */
+  async refreshMetadata(
+    connectionId: string,
+    full: boolean = false
+  ): Promise<{ metadata: DatabaseMetadata; changed: boolean; diff: Record<string, unknown> | null }> {
+    const response = await axiosInstance.post<{
+      metadata: DatabaseMetadata;
+      changed: boolean;
+      diff: Record<string, unknown> | null;
+    }>(`/connections/${connectionId}/metadata/refresh`, null, { params: full ? { full: 'true' } : {} });
+    return response.data;
+  },
+
+  /**
+   * Profile the columns of a table: null ratios, distinct counts, min/max,
+   * most frequent values and histograms
+   * @param connectionId - The connection ID
+   * @param table - Table or view name
+   * @param schema - Schema of the table, needed when the name is not unique
+   * @param refresh - Recompute instead of returning the cached profile
+   */
+  async profileTable(connectionId: string, table: string, schema?: string, refresh: boolean = false): Promise<TableProfile> {
+    const response = await axiosInstance.post<TableProfile>(
+      `/connections/${connectionId}/tables/${encodeURIComponent(table)}/profile`,
+      null,
+      { params: { schema, refresh: refresh ? 'true' : undefined } }
+    );
+    return response.data;
+  },
+
+  /**
+   * List the data dictionary of a connection
+   * @param connectionId - The connection ID
+   */
+  async listDictionary(connectionId: string): Promise<DictionaryEntry[]> {
+    const response = await axiosInstance.get<{ entries: DictionaryEntry[] }>(
+      `/connections/${connectionId}/dictionary`
+    );
+    return response.data.entries;
+  },
+
+  /**
+   * Describe or tag a table or column, replacing its previous entry
+   * @param connectionId - The connection ID
+   * @param entry - Table (and column) with its description and tags
+   */
+  async saveDictionaryEntry(connectionId: string, entry: DictionaryEntryRequest): Promise<DictionaryEntry> {
+    const response = await axiosInstance.put<DictionaryEntry>(`/connections/${connectionId}/dictionary`, entry);
+    return response.data;
+  },
+
+  /**
+   * Remove the data dictionary entry of a table or column
+   * @param connectionId - The connection ID
+   * @param table - Table of the entry
+   * @param column - Column of the entry, omitted for the table's own entry
+   * @param schema - Schema of the entry, omitted for entries of any schema
+   */
+  async deleteDictionaryEntry(connectionId: string, table: string, column?: string, schema?: string): Promise<void> {
+    await axiosInstance.delete(`/connections/${connectionId}/dictionary`, {
+      params: { table, column, schema },
+    });
+  },
+};
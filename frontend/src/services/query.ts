@@ -1,27 +1,123 @@
 import { axiosInstance } from './api';
-import { QueryResult } from '../types';
+import {
+  CacheStatus,
+  ChartSpec,
+  ColumnarResult,
+  HistoryResultFormat,
+  QueryParameters,
+  QueryResult,
+  TransformStep,
+} from '../types';
 
 export interface QueryRequest {
   query: string;
+  confirm_large_result?: boolean;
+  /** Apply this saved query's result transformation */
+  saved_query_id?: string;
+  transform?: TransformStep[];
+  /** Values for `:name` placeholders (override the saved query's defaults) */
+  parameters?: QueryParameters;
+  /** Timeout override, capped at the domain's max_timeout_secs */
+  timeout_secs?: number;
+  /** Row limit override for queries without LIMIT, capped at the domain's max_row_limit */
+  limit_value?: number;
+  /** Keep the result with the history entry */
+  save_result?: HistoryResultFormat;
 }
 
 export interface NaturalLanguageQueryRequest {
   question: string;
+  confirm_large_result?: boolean;
+  /** Ask the LLM even if SQL for the same question is cached */
+  force_regenerate?: boolean;
+}
+
+/** Server-Sent Event of `POST /connections/{id}/nl-query/stream` (SSE event name = `event`) */
+export type NaturalLanguageQueryEvent =
+  | { event: 'sql-token'; token: string }
+  | { event: 'sql-generated'; sql: string; cache: CacheStatus }
+  | { event: 'execution-started' }
+  | { event: 'sql-repaired'; attempt: number; sql: string; error_message: string }
+  | { event: 'completed'; query: QueryResult }
+  | { event: 'failed'; error_message: string };
+
+/** Estimate returned (409 CONFIRMATION_REQUIRED) for large queries without LIMIT */
+export interface RowEstimate {
+  estimated_rows: number;
+  threshold: number;
 }
 
 export interface QueryResponse {
   query: QueryResult;
   generated_sql?: string;
+  /** Whether generated_sql was cached for the same question (natural language queries) */
+  generation_cache?: CacheStatus;
+  /** Set when requested with `?result_format=columnar` (query.results is then null) */
+  columnar?: ColumnarResult;
+  /** Suggested chart of the result (absent when no chart fits) */
+  chart?: ChartSpec;
+}
+
+/** Conversation of natural language questions about one connection */
+export interface ChatSession {
+  id: string;
+  connection_id: string;
+  domain_id?: string | null;
+  created_by?: string | null;
+  title: string;
+  created_at: string;
+  updated_at: string;
+}
+
+/** One question of a chat session with the SQL it produced */
+export interface ChatTurn {
+  id: string;
+  session_id: string;
+  question: string;
+  generated_sql?: string | null;
+  /** Row count and columns of the result */
+  result_summary?: string | null;
+  error_message?: string | null;
+  created_at: string;
+}
+
+export interface ChatResponse {
+  session: ChatSession;
+  turn: ChatTurn;
+  query: QueryResult;
+}
+
+/** Rating or correction of generated SQL; rated-up and corrected questions become prompt examples */
+export interface SqlFeedbackRequest {
+  question: string;
+  generated_sql: string;
+  rating: 'up' | 'down';
+  /** SQL that answers the question, when the generated SQL doesn't */
+  corrected_sql?: string;
+  comment?: string;
+}
+
+export interface SqlFeedback extends SqlFeedbackRequest {
+  id: string;
+  domain_id: string;
+  connection_id?: string | null;
+  database_type: string;
+  created_by?: string | null;
+  created_at: string;
 }
 
 export const queryService = {
   /**
    * Execute SQL query
    */
-  async executeQuery(connectionId: string, query: string): Promise<QueryResponse> {
+  async executeQuery(
+    connectionId: string,
+    query: string,
+    confirmLargeResult = false
+  ): Promise<QueryResponse> {
     const response = await axiosInstance.post<QueryResponse>(
       `/connections/${connectionId}/query`,
-      { query }
+      { query, confirm_large_result: confirmLargeResult }
     );
     return response.data;
   },
@@ -31,11 +127,40 @@ export const queryService = {
    */
   async executeNaturalLanguageQuery(
     connectionId: string,
-    question: string
+    question: string,
+    confirmLargeResult = false,
+    forceRegenerate = false
   ): Promise<QueryResponse> {
     const response = await axiosInstance.post<QueryResponse>(
       `/connections/${connectionId}/nl-query`,
-      { question }
+      { question, confirm_large_result: confirmLargeResult, force_regenerate: forceRegenerate }
+    );
+    return response.data;
+  },
+
+  /**
+   * Ask a question in a chat session (omit sessionId to start one)
+   */
+  async chat(
+    connectionId: string,
+    question: string,
+    sessionId?: string,
+    confirmLargeResult = false
+  ): Promise<ChatResponse> {
+    const response = await axiosInstance.post<ChatResponse>(
+      `/connections/${connectionId}/chat`,
+      { session_id: sessionId, question, confirm_large_result: confirmLargeResult }
+    );
+    return response.data;
+  },
+
+  /**
+   * Rate or correct the SQL generated for a question
+   */
+  async submitFeedback(connectionId: string, feedback: SqlFeedbackRequest): Promise<SqlFeedback> {
+    const response = await axiosInstance.post<SqlFeedback>(
+      `/connections/${connectionId}/nl-feedback`,
+      feedback
     );
     return response.data;
   },
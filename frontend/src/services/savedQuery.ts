@@ -4,12 +4,43 @@ import type {
   CreateSavedQueryRequest,
   UpdateSavedQueryRequest,
   QueryHistory,
+  ListParams,
+  PageInfo,
+  QueryParameters,
+  TransformStep,
+  QueryShare,
+  CreateQueryShareRequest,
+  CreateQueryShareResponse,
+  SharedResult,
+  HistoryFilterParams,
+  HistoryDailyStats,
+  HistoryResult,
+  HistoryResultFormat,
 } from '../types';
+import type { QueryResponse } from './query';
+
+export interface ExecuteSavedQueryRequest {
+  /** Values for `:name` placeholders (override the saved query's defaults) */
+  parameters?: QueryParameters;
+  confirm_large_result?: boolean;
+  /** Steps applied after the saved query's transformation */
+  transform?: TransformStep[];
+  timeout_secs?: number;
+  limit_value?: number;
+  /** Keep the result with the history entry */
+  save_result?: HistoryResultFormat;
+}
 
 // Saved Query API functions
-export const listSavedQueries = async (domainId: string): Promise<SavedQuery[]> => {
-  const response = await api.get(`/domains/${domainId}/queries/saved`);
-  return response.data;
+export const listSavedQueries = async (
+  domainId: string,
+  params: ListParams = { page_size: 500 }
+): Promise<SavedQuery[]> => {
+  const response = await api.get<{ saved_queries: SavedQuery[] } & PageInfo>(
+    `/domains/${domainId}/queries/saved`,
+    { params }
+  );
+  return response.data.saved_queries;
 };
 
 export const getSavedQuery = async (domainId: string, queryId: string): Promise<SavedQuery> => {
@@ -34,18 +65,103 @@ export const updateSavedQuery = async (
   return response.data;
 };
 
+// Runs the stored SQL on the saved query's connection (not cross-database queries)
+export const executeSavedQuery = async (
+  domainId: string,
+  queryId: string,
+  request: ExecuteSavedQueryRequest = {}
+): Promise<QueryResponse> => {
+  const response = await api.post<QueryResponse>(`/domains/${domainId}/queries/saved/${queryId}/execute`, request);
+  return response.data;
+};
+
 export const deleteSavedQuery = async (domainId: string, queryId: string): Promise<void> => {
   await api.delete(`/domains/${domainId}/queries/saved/${queryId}`);
 };
 
+// Share links of saved queries
+export const createQueryShare = async (
+  domainId: string,
+  queryId: string,
+  request: CreateQueryShareRequest = {}
+): Promise<CreateQueryShareResponse> => {
+  const response = await api.post<CreateQueryShareResponse>(
+    `/domains/${domainId}/queries/saved/${queryId}/shares`,
+    request
+  );
+  return response.data;
+};
+
+export const listQueryShares = async (domainId: string, queryId: string): Promise<QueryShare[]> => {
+  const response = await api.get<{ shares: QueryShare[] }>(`/domains/${domainId}/queries/saved/${queryId}/shares`);
+  return response.data.shares;
+};
+
+export const revokeQueryShare = async (domainId: string, queryId: string, shareId: string): Promise<void> => {
+  await api.delete(`/domains/${domainId}/queries/saved/${queryId}/shares/${shareId}`);
+};
+
+export const getSharedResult = async (token: string): Promise<SharedResult> => {
+  const response = await api.get<SharedResult>(`/share/${token}`);
+  return response.data;
+};
+
 // Query History API functions
 export const listQueryHistory = async (
   domainId: string,
-  limit: number = 50
+  limit: number = 50,
+  filters: HistoryFilterParams = {}
 ): Promise<QueryHistory[]> => {
-  const response = await api.get(`/domains/${domainId}/queries/history`, {
-    params: { limit },
+  const response = await api.get<{ history: QueryHistory[] } & PageInfo>(`/domains/${domainId}/queries/history`, {
+    params: { page_size: limit, ...filters },
+  });
+  return response.data.history;
+};
+
+// Per-day counts and p50/p95 execution times of the matching history entries
+export const getQueryHistoryDailyStats = async (
+  domainId: string,
+  filters: HistoryFilterParams = {}
+): Promise<HistoryDailyStats[]> => {
+  const response = await api.get<{ days: HistoryDailyStats[] }>(`/domains/${domainId}/queries/history`, {
+    params: { ...filters, aggregate: 'daily' },
   });
+  return response.data.days;
+};
+
+export interface RerunHistoryRequest {
+  /** Values for `:name` placeholders (override the saved query's defaults) */
+  parameters?: QueryParameters;
+  confirm_large_result?: boolean;
+  timeout_secs?: number;
+  limit_value?: number;
+  /** Keep the result with the history entry */
+  save_result?: HistoryResultFormat;
+}
+
+// Runs the entry's SQL again on its connection (not writes or cross-database queries)
+export const rerunQueryHistory = async (
+  domainId: string,
+  historyId: string,
+  request: RerunHistoryRequest = {}
+): Promise<QueryResponse> => {
+  const response = await api.post<QueryResponse>(`/domains/${domainId}/queries/history/${historyId}/rerun`, request);
+  return response.data;
+};
+
+// Result saved with the entry (queries run with `save_result`)
+export const getQueryHistoryResult = async (domainId: string, historyId: string): Promise<HistoryResult> => {
+  const response = await api.get<HistoryResult>(`/domains/${domainId}/queries/history/${historyId}/result`);
+  return response.data;
+};
+
+// Saves the entry's SQL as a saved query; the entry then references it
+export const promoteQueryHistory = async (
+  domainId: string,
+  historyId: string,
+  request: { name: string; description?: string; parameters?: QueryParameters }
+): Promise<SavedQuery> => {
+  const response = await api.post<SavedQuery>(`/domains/${domainId}/queries/history/${historyId}/promote`, request);
   return response.data;
 };
 
@@ -54,11 +170,11 @@ export const listConnectionQueryHistory = async (
   connectionId: string,
   limit: number = 50
 ): Promise<QueryHistory[]> => {
-  const response = await api.get(
+  const response = await api.get<{ history: QueryHistory[] } & PageInfo>(
     `/domains/${domainId}/connections/${connectionId}/history`,
     {
-      params: { limit },
+      params: { page_size: limit },
     }
   );
-  return response.data;
+  return response.data.history;
 };
@@ -19,11 +19,11 @@ export interface UnifiedQueryRequest {
   query: string;
   /** Target database type */
   database_type: DatabaseType;
-  /** Query timeout in seconds (default: 30) */
+  /** Query timeout in seconds (default and cap from the domain settings) */
   timeout_secs?: number;
   /** Whether to automatically apply LIMIT if not present (default: true) */
   apply_limit?: boolean;
-  /** LIMIT value to apply (default: 1000) */
+  /** LIMIT value to apply (default and cap from the domain settings) */
   limit_value?: number;
 }
 
@@ -2,7 +2,8 @@
 
 export interface CrossDatabaseQueryRequest {
   query: string;
-  connection_ids: string[];
+  // Omit (with database_aliases) to resolve `alias.schema.table` from the catalog
+  connection_ids?: string[];
   database_aliases?: Record<string, string>;
   timeout_secs?: number;
   apply_limit?: boolean;
@@ -15,6 +16,10 @@ export interface SubQueryResult {
   query: string;
   row_count: number;
   execution_time_ms: number;
+  pushed_predicates?: string[];
+  estimated_rows?: number;
+  broadcast_keys?: number;
+  cache?: { hit: boolean; cached_at?: string };
 }
 
 export interface CrossDatabaseQueryResponse {
@@ -25,6 +30,7 @@ export interface CrossDatabaseQueryResponse {
   execution_time_ms: number;
   limit_applied: boolean;
   executed_at: string;
+  warnings?: string[];
 }
 
 export interface DatabaseAlias {
@@ -34,6 +40,39 @@ export interface DatabaseAlias {
   databaseType?: string;
 }
 
+export interface CatalogSchema {
+  name: string;
+  tables: string[];
+}
+
+export interface CatalogConnection {
+  alias: string;
+  connection_id: string;
+  name?: string;
+  database_type: string;
+  schemas: CatalogSchema[];
+}
+
+export interface FederatedCatalog {
+  connections: CatalogConnection[];
+}
+
+// Natural language question over the connections of a domain (POST /domains/{id}/nl-query)
+export interface CrossDatabaseNaturalLanguageRequest {
+  question: string;
+  // Default: every connected connection of the domain
+  connection_ids?: string[];
+  timeout_secs?: number;
+  apply_limit?: boolean;
+  limit_value?: number;
+}
+
+export interface CrossDatabaseNaturalLanguageResponse {
+  // SQL over `alias.schema.table` tables
+  generated_sql: string;
+  query: CrossDatabaseQueryResponse;
+}
+
 export interface CrossDatabaseQueryError {
   code: string;
   message: string;
@@ -8,13 +8,113 @@ export interface DatabaseConnection {
   created_at: string;
   last_connected_at?: string;
   metadata_cache_id?: string;
+  version: number;
+  read_only: boolean;
+  /** Write statements may run through POST /api/connections/{id}/execute */
+  allow_writes: boolean;
+  /** Free-form labels such as env=prod, used for filtering and policies */
+  labels: Record<string, string>;
+  /** Pool sizes and timeouts overriding the server defaults */
+  pool_settings: PoolSettings;
+  /** Free-form tags such as pii or warehouse, for grouping and list filters */
+  tags: string[];
+}
+
+// POST /api/connections/{id}/duplicate
+export interface DuplicateConnectionRequest {
+  name?: string;
+  domain_id?: string;
+}
+
+// POST /api/connections/{id}/move
+export interface MoveConnectionRequest {
+  domain_id: string;
+  expected_version?: number;
+}
+
+export interface PoolSettings {
+  max_size?: number;
+  min_idle?: number;
+  acquire_timeout_secs?: number;
+  /** 0 = no limit */
+  max_lifetime_secs?: number;
+}
+
+export type CircuitState = 'closed' | 'open' | 'half_open';
+
+export interface CircuitStatus {
+  state: CircuitState;
+  consecutive_failures: number;
+  opened_at?: string;
+  /** When an open circuit lets the next request probe the database */
+  retry_at?: string;
+  last_error?: string;
+  trips: number;
+}
+
+// GET /api/connections/{id}/pool-stats
+export interface PoolStats {
+  connection_id: string;
+  database_type: string;
+  limits: Required<PoolSettings>;
+  /** Whether the pool has been opened yet */
+  open: boolean;
+  /** Reported for PostgreSQL only */
+  size?: number;
+  in_use?: number;
+  idle?: number;
+  waiting: number;
+  acquired: number;
+  acquire_timeouts: number;
+  acquire_failures: number;
+  avg_wait_ms: number;
+  max_wait_ms: number;
+  /** Sessions are opened read-only by the database (PostgreSQL, MySQL) */
+  session_read_only: boolean;
+  /** Circuit breaker of the pool (not used for Druid) */
+  circuit: CircuitStatus;
 }
 
 export interface CreateConnectionRequest {
   name?: string;
-  connection_url: string;
+  /** Required unless url_fields is given */
+  connection_url?: string;
   database_type?: string;
   domain_id?: string;
+  read_only?: boolean;
+  /** Check that the credentials of a read-only connection lack write privileges */
+  verify_read_only?: boolean;
+  allow_writes?: boolean;
+  labels?: Record<string, string>;
+  tags?: string[];
+  pool_settings?: PoolSettings;
+  /** The URL as separate fields, instead of connection_url */
+  url_fields?: ConnectionUrlFields;
+}
+
+export interface ConnectionUrlFields {
+  host: string;
+  port?: number;
+  user?: string;
+  password?: string;
+  database?: string;
+  options?: Record<string, string>;
+}
+
+// GET /api/connections/templates
+export interface ConnectionTemplate {
+  database_type: string;
+  scheme: string;
+  default_port: number;
+  fields: { name: string; required: boolean; description: string }[];
+  options: { name: string; description: string; example: string }[];
+  example_urls: string[];
+}
+
+// POST /api/connections/url
+export interface ConnectionUrlResponse {
+  connection_url: string;
+  redacted_url: string;
 }
 
 export interface DatabaseMetadata {
@@ -33,7 +133,33 @@ export interface Table {
   schema?: string;
   columns: Column[];
   row_count?: number;
+  size_bytes?: number;
   description?: string;
+  tags?: string[];
+  /** Partitioning and distribution of Druid datasources and Doris tables */
+  engine_metadata?: EngineMetadata;
+}
+
+export interface PartitionInfo {
+  name: string;
+  values?: string;
+  row_count?: number;
+}
+
+export interface EngineMetadata {
+  engine: 'druid' | 'doris';
+  partition_method?: string;
+  partition_keys?: string[];
+  partition_count?: number;
+  range_start?: string;
+  range_end?: string;
+  /** The first partition and the most recent ones */
+  partitions?: PartitionInfo[];
+  distribution_type?: string;
+  distribution_keys?: string[];
+  buckets?: number;
+  table_model?: string;
+  key_columns?: string[];
 }
 
 export interface View {
@@ -42,6 +168,7 @@ export interface View {
   columns: Column[];
   definition?: string;
   description?: string;
+  tags?: string[];
 }
 
 export interface Column {
@@ -53,6 +180,91 @@ export interface Column {
   default_value?: string;
   max_length?: number;
   description?: string;
+  distinct_count?: number;
+  tags?: string[];
+}
+
+// Data dictionary: description and tags of a table or column (/api/connections/{id}/dictionary)
+export interface DictionaryEntry {
+  connection_id: string;
+  schema?: string | null;
+  table: string;
+  column?: string | null;
+  description?: string | null;
+  tags: string[];
+  updated_by?: string | null;
+  updated_at: string;
+}
+
+export interface DictionaryEntryRequest {
+  schema?: string;
+  table: string;
+  column?: string;
+  description?: string;
+  tags?: string[];
+}
+
+// POST /api/connections/{id}/autocomplete; cursor is a character offset (default: end)
+export interface AutocompleteRequest {
+  sql: string;
+  cursor?: number;
+  limit?: number;
+}
+
+export interface Completion {
+  label: string;
+  kind: 'column' | 'table' | 'view' | 'schema' | 'keyword';
+  detail?: string;
+}
+
+export interface AutocompleteResponse {
+  context: 'table' | 'column' | 'qualified' | 'keyword';
+  prefix: string;
+  suggestions: Completion[];
+  schema_cached: boolean;
+}
+
+export interface TableSampleResponse {
+  schema: string;
+  table: string;
+  rows: any[];
+  row_count: number;
+  execution_time_ms: number;
+}
+
+export interface TableCountResponse {
+  schema: string;
+  table: string;
+  row_count: number;
+}
+
+export type ColumnKind = 'numeric' | 'temporal' | 'text' | 'boolean' | 'other';
+
+export interface ColumnStatistics {
+  name: string;
+  data_type: string;
+  kind: ColumnKind;
+  null_count: number;
+  null_ratio: number;
+  distinct_count: number | null;
+  min: any | null;
+  max: any | null;
+  top_values: { value: any; count: number }[];
+  histogram: { lower: number; upper: number; count: number }[];
+  masked?: boolean;
+}
+
+export interface TableProfile {
+  connection_id: string;
+  schema: string;
+  table: string;
+  metadata_version: number;
+  row_count: number;
+  columns: ColumnStatistics[];
+  skipped_columns?: string[];
+  execution_time_ms: number;
+  profiled_at: string;
+  cached: boolean;
 }
 
 export interface QueryResult {
@@ -67,6 +279,12 @@ export interface QueryResult {
   error_message?: string;
   executed_at?: string;
   limit_applied: boolean;
+  cache?: CacheStatus;
+}
+
+export interface CacheStatus {
+  hit: boolean;
+  cached_at?: string;
 }
 
 // Domain types
@@ -76,6 +294,7 @@ export interface Domain {
   description?: string;
   created_at: string;
   updated_at: string;
+  version: number;
 }
 
 export interface DomainResponse {
@@ -84,6 +303,7 @@ export interface DomainResponse {
   description?: string;
   created_at: string;
   updated_at: string;
+  version: number;
   connection_count: number;
   saved_query_count: number;
   query_history_count: number;
@@ -97,38 +317,435 @@ export interface CreateDomainRequest {
 export interface UpdateDomainRequest {
   name?: string;
   description?: string;
+  expected_version?: number;
 }
 
+export interface DomainSettings {
+  domain_id: string;
+  default_row_limit: number;
+  default_timeout_secs: number;
+  max_row_limit: number;
+  max_timeout_secs: number;
+  history_retention_days: number | null;
+  allowed_database_types: string[];
+  llm_monthly_token_budget: number | null;
+  /** Tables and columns (`table`, `column` or `table.column`) kept out of LLM prompts */
+  llm_denied_identifiers: string[];
+  /** Scheduled metadata refresh override (null: server setting, 0: disabled) */
+  metadata_refresh_interval_secs: number | null;
+  /** Execution time (ms) from which queries are flagged as slow (0: disabled) */
+  slow_query_threshold_ms: number;
+  updated_at: string;
+}
+
+export interface UpdateDomainSettingsRequest {
+  default_row_limit?: number;
+  default_timeout_secs?: number;
+  max_row_limit?: number;
+  max_timeout_secs?: number;
+  history_retention_days?: number | null;
+  allowed_database_types?: string[];
+  llm_monthly_token_budget?: number | null;
+  llm_denied_identifiers?: string[];
+  metadata_refresh_interval_secs?: number | null;
+  slow_query_threshold_ms?: number;
+}
+
+// GET /api/domains/{id}/stats
+export interface QueryTotals {
+  queries: number;
+  failed: number;
+  /** 0.0 to 1.0 */
+  failure_rate: number;
+  avg_execution_time_ms: number;
+  /** Rows returned, or affected by write statements */
+  rows_scanned: number;
+}
+
+export interface DomainStats extends QueryTotals {
+  domain_id: string;
+  since: string;
+  until: string;
+  /** Cross-database queries count once for each connection they used */
+  top_connections: Array<QueryTotals & { connection_id: string; name: string | null }>;
+  top_users: Array<QueryTotals & { executed_by: string }>;
+  /** Most-run query shapes (history grouped by SQL fingerprint) */
+  top_queries: Array<QueryTotals & { fingerprint: string }>;
+}
+
+// GET /api/domains/{id}/slow-queries
+export type SlowQuerySort = 'total' | 'avg';
+
+/** Slow runs of one query shape (SQL with literals replaced by `?`) */
+export interface SlowQueryGroup {
+  fingerprint: string;
+  /** SQL of the latest slow run */
+  example_query: string;
+  executions: number;
+  total_execution_time_ms: number;
+  avg_execution_time_ms: number;
+  max_execution_time_ms: number;
+  last_executed_at: string;
+}
+
+export interface SlowQueryReport {
+  domain_id: string;
+  since: string;
+  until: string;
+  /** Current threshold of the domain (0: flagging disabled) */
+  threshold_ms: number;
+  sort: SlowQuerySort;
+  queries: SlowQueryGroup[];
+}
+
+// Filters of the query history listings
+export interface HistoryFilterParams {
+  status?: 'success' | 'failed';
+  is_llm_generated?: boolean;
+  /** RFC 3339 */
+  since?: string;
+  until?: string;
+  min_execution_time_ms?: number;
+  is_slow?: boolean;
+  fingerprint?: string;
+}
+
+export interface HistoryDailyStats {
+  /** UTC day (`YYYY-MM-DD`) */
+  day: string;
+  queries: number;
+  failed: number;
+  avg_execution_time_ms: number;
+  p50_execution_time_ms: number;
+  p95_execution_time_ms: number;
+}
+
+// Favorites and recently run queries (per user)
+export type FavoriteType = 'connection' | 'saved_query';
+
+export interface Favorite {
+  resource_type: FavoriteType;
+  resource_id: string;
+  domain_id: string;
+  name: string | null;
+  created_at: string;
+}
+
+export interface RecentQuery {
+  /** Null for cross-database queries */
+  connection_id: string | null;
+  saved_query_id: string | null;
+  /** Null for ad-hoc SQL or when the saved query was deleted */
+  saved_query_name: string | null;
+  /** SQL of the latest run */
+  query_text: string;
+  last_status: 'success' | 'failed';
+  last_executed_at: string;
+  executions: number;
+}
+
+// LLM usage and token budget types
+export interface LlmUsageTotals {
+  requests: number;
+  failed_requests: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
+}
+
+export interface LlmBudgetStatus {
+  monthly_token_budget: number | null;
+  used_tokens: number;
+  remaining_tokens: number | null;
+  exceeded: boolean;
+  exceeded_action: 'reject' | 'degrade';
+}
+
+export interface LlmUsageReport {
+  domain_id: string;
+  month: string;
+  days: Array<LlmUsageTotals & { date: string }>;
+  principals: Array<LlmUsageTotals & { principal: string | null }>;
+  totals: LlmUsageTotals;
+  budget: LlmBudgetStatus;
+}
+
+// Prompts sent to the LLM, as recorded in a domain's audit log
+export interface PromptRedactions {
+  /** Deny-listed tables and columns left out of the schema */
+  withheld: string[];
+  /** Columns whose values were masked in sample data */
+  masked_columns: string[];
+}
+
+export interface LlmAuditEntry {
+  id: string;
+  domain_id: string | null;
+  connection_id: string | null;
+  principal: string | null;
+  purpose: string;
+  prompt: string;
+  redactions: PromptRedactions;
+  created_at: string;
+}
+
+// Domain bundle (export/import) types
+export interface DomainBundle {
+  format_version: number;
+  exported_at: string;
+  domain: { name: string; description?: string | null };
+  settings: {
+    default_row_limit: number;
+    default_timeout_secs: number;
+    max_row_limit?: number;
+    max_timeout_secs?: number;
+    history_retention_days: number | null;
+    allowed_database_types: string[];
+    llm_monthly_token_budget?: number | null;
+    llm_denied_identifiers?: string[];
+    metadata_refresh_interval_secs?: number | null;
+    slow_query_threshold_ms?: number | null;
+  };
+  connections: Array<{
+    key: string;
+    name?: string | null;
+    database_type: string;
+    connection_url: string;
+    variables: string[];
+  }>;
+  saved_queries: Array<{
+    name: string;
+    connection: string;
+    query_text: string;
+    description?: string | null;
+  }>;
+}
+
+export interface ImportDomainRequest {
+  bundle: DomainBundle;
+  variables?: Record<string, string>;
+  name?: string;
+}
+
+export interface ImportDomainResponse {
+  domain: DomainResponse;
+  connection_count: number;
+  saved_query_count: number;
+}
+
+export interface ConnectionDefinition {
+  name: string;
+  database_type?: string;
+  /** Connection URL with `${VARIABLE}` placeholders for secrets */
+  connection_url: string;
+  read_only?: boolean;
+  allow_writes?: boolean;
+  labels?: Record<string, string>;
+  tags?: string[];
+  pool_settings?: PoolSettings;
+}
+
+export type ImportConflictMode = 'fail' | 'skip' | 'update';
+
+export interface ImportConnectionsRequest {
+  connections: ConnectionDefinition[];
+  variables?: Record<string, string>;
+  on_conflict?: ImportConflictMode;
+  dry_run?: boolean;
+}
+
+export interface ImportedConnection {
+  name: string;
+  connection_id: string | null;
+  outcome: 'created' | 'updated' | 'skipped';
+}
+
+export interface ImportConnectionsResponse {
+  domain_id: string;
+  dry_run: boolean;
+  created: number;
+  updated: number;
+  skipped: number;
+  connections: ImportedConnection[];
+}
+
+// Result transformation steps (applied to query results on the server)
+export type CastType = 'integer' | 'float' | 'string' | 'boolean' | 'date' | 'timestamp';
+
+export type TransformStep =
+  | { op: 'rename'; column: string; to: string }
+  | { op: 'cast'; column: string; type: CastType }
+  | { op: 'derive'; column: string; expr: string }
+  | { op: 'filter'; predicate: string };
+
+// Column-oriented results (`result_format=columnar`): data[i] holds the values of columns[i]
+export interface ColumnarResult {
+  columns: string[];
+  types: string[];
+  data: any[][];
+}
+
+// Suggested chart of a query result (`chart` of query responses)
+export interface ChartEncoding {
+  field: string;
+  kind: 'temporal' | 'quantitative' | 'nominal';
+}
+
+export interface ChartSpec {
+  chart_type: 'line' | 'bar' | 'pie' | 'scatter';
+  x: ChartEncoding;
+  /** One series per column; empty for count charts */
+  y: ChartEncoding[];
+  /** Set when x values repeat, or for count charts */
+  aggregation?: 'sum' | 'avg' | 'count';
+  reason: string;
+}
+
+// Values for the `:name` placeholders of a query
+export type QueryParameters = Record<string, string | number | boolean | null>;
+
 // Saved Query types
 export interface SavedQuery {
   id: string;
   domain_id: string;
-  connection_id: string;
+  connection_id: string | null;
+  /** Connections of a cross-database query (connection_id is then null) */
+  connection_ids?: string[];
+  database_aliases?: Record<string, string>;
   name: string;
   query_text: string;
   description?: string;
   created_at: string;
   updated_at: string;
+  version: number;
+  transform: TransformStep[];
+  parameters: QueryParameters;
+  /** Exposed to cross-database queries as the view `<domain name>.<view_name>` */
+  view_name?: string;
+  /** A table or column the query uses was dropped or renamed; cleared by editing the query */
+  is_broken: boolean;
+  breakage_reason?: string;
+}
+
+// Link to a saved query's result, readable without authentication
+export interface QueryShare {
+  id: string;
+  domain_id: string;
+  saved_query_id: string;
+  /** First characters of the token */
+  token_prefix: string;
+  parameters: QueryParameters;
+  /** Set for links serving a frozen result; null when every visit runs the query */
+  snapshot_at: string | null;
+  created_by: string | null;
+  created_at: string;
+  expires_at: string | null;
+  revoked_at: string | null;
+}
+
+export interface CreateQueryShareRequest {
+  snapshot?: boolean;
+  expires_in_secs?: number;
+  parameters?: QueryParameters;
+}
+
+export interface CreateQueryShareResponse {
+  share: QueryShare;
+  /** Only returned here */
+  token: string;
+  path: string;
+}
+
+// Result served by GET /api/share/{token} (no SQL or connection)
+export interface SharedResult {
+  name: string;
+  description: string | null;
+  snapshot: boolean;
+  executed_at: string | null;
+  expires_at: string | null;
+  results: Record<string, unknown>[];
+  row_count: number;
+  limit_applied: boolean;
+  chart?: ChartSpec;
 }
 
 export interface CreateSavedQueryRequest {
-  connection_id: string;
+  /** Either a single connection, or connection_ids for a cross-database query */
+  connection_id?: string;
+  connection_ids?: string[];
+  database_aliases?: Record<string, string>;
   name: string;
   query_text: string;
   description?: string;
+  transform?: TransformStep[];
+  parameters?: QueryParameters;
+  view_name?: string;
 }
 
 export interface UpdateSavedQueryRequest {
   name?: string;
   query_text?: string;
   description?: string;
+  transform?: TransformStep[];
+  parameters?: QueryParameters;
+  /** An empty string stops exposing the query as a view */
+  view_name?: string;
+  expected_version?: number;
+}
+
+// Result contracts (/api/domains/{domain_id}/queries/saved/{query_id}/contract)
+export type ContractType = 'integer' | 'float' | 'string' | 'boolean' | 'date' | 'timestamp';
+
+export interface ContractColumn {
+  name: string;
+  /** Omit to only require the column */
+  data_type?: ContractType;
+}
+
+export interface ResultContract {
+  saved_query_id: string;
+  domain_id: string;
+  columns: ContractColumn[];
+  /** How the latest checked result differed from columns (empty when it matched) */
+  drift: string[];
+  checked_at: string | null;
+  updated_at: string;
+}
+
+export interface SetResultContractRequest {
+  columns: ContractColumn[];
+}
+
+// Materialized views (/api/domains/{domain_id}/queries/saved/{query_id}/materialization)
+export type MaterializationStatus = 'pending' | 'refreshing' | 'ready' | 'failed';
+
+export interface ViewMaterialization {
+  saved_query_id: string;
+  domain_id: string;
+  /** Seconds between scheduled refreshes (null refreshes on demand only) */
+  refresh_interval_secs: number | null;
+  status: MaterializationStatus;
+  row_count: number | null;
+  size_bytes: number | null;
+  /** When the snapshot queries read was taken */
+  refreshed_at: string | null;
+  attempted_at: string | null;
+  last_error: string | null;
+  created_at: string;
+}
+
+export interface MaterializeViewRequest {
+  refresh_interval_secs?: number;
 }
 
 // Query History types
 export interface QueryHistory {
   id: string;
   domain_id: string;
-  connection_id: string;
+  connection_id: string | null;
+  /** Connections of a cross-database query (connection_id is then null) */
+  connection_ids?: string[];
+  database_aliases?: Record<string, string>;
   query_text: string;
   row_count: number;
   execution_time_ms: number;
@@ -136,6 +753,306 @@ export interface QueryHistory {
   error_message?: string;
   executed_at: string;
   is_llm_generated: boolean;
+  executed_by?: string;
+  kind: 'read' | 'write';
+  /** SQL the LLM rewrote after an earlier generated statement failed */
+  auto_repaired: boolean;
+  /** Saved query the entry ran, or the one it was promoted to (`POST .../history/{history_id}/promote`) */
+  saved_query_id?: string;
+  /** Execution time reached the domain's `slow_query_threshold_ms` */
+  is_slow: boolean;
+  /** SQL with its literals replaced by `?`, shared by runs of the same query shape */
+  fingerprint: string;
+  /** A result was saved with the entry (`GET .../history/{history_id}/result`) */
+  has_result: boolean;
+}
+
+/** `rows` keeps the first HISTORY_RESULT_MAX_ROWS rows, `parquet` the whole result */
+export type HistoryResultFormat = 'rows' | 'parquet';
+
+export interface HistoryResult {
+  history_id: string;
+  /** `rows` when the Parquet file would have been empty or too large */
+  format: HistoryResultFormat;
+  total_row_count: number;
+  row_count: number;
+  truncated: boolean;
+  size_bytes: number;
+  captured_at: string;
+  results: Record<string, unknown>[];
+}
+
+// Query plans (POST /api/connections/{id}/query/explain)
+export interface PlanNode {
+  operation: string;
+  relation?: string;
+  estimated_rows?: number;
+  estimated_cost?: number;
+  details: Record<string, unknown>;
+  children: PlanNode[];
+}
+
+export interface QueryPlan {
+  format: 'json' | 'text';
+  root: PlanNode;
+  raw: unknown;
+}
+
+export interface ExplainResponse {
+  query: string;
+  translated_sql?: string;
+  estimated_rows?: number;
+  plan: QueryPlan;
+}
+
+// Optimization advice (POST /api/connections/{id}/query/advise)
+export interface OptimizationSuggestion {
+  kind: 'missing_index' | 'rewrite' | 'statistics' | 'other';
+  impact: 'high' | 'medium' | 'low';
+  title: string;
+  description: string;
+  table?: string | null;
+  columns: string[];
+  /** CREATE INDEX statement or rewritten query; never executed by the server */
+  sql?: string | null;
+  source: 'llm' | 'heuristic';
+}
+
+export interface AdviseResponse extends ExplainResponse {
+  suggestions: OptimizationSuggestion[];
+  /** Set when the suggestions are heuristic only */
+  llm_error?: string;
+}
+
+// Result summaries (POST /api/connections/{id}/query/summarize)
+export interface SummarizeResultRequest {
+  query: string;
+  question?: string;
+  results: Record<string, unknown>[];
+  /** Rows of the full result, when results holds only part of it */
+  row_count?: number;
+}
+
+export interface ResultSummary {
+  summary: string;
+  insights: string[];
+  /** Usable as natural language queries */
+  follow_up_questions: string[];
+  sampled_rows: number;
+  row_count: number;
+  source: 'llm' | 'statistics';
+  /** Set when the summary comes from column statistics */
+  llm_error?: string;
+}
+
+// SQL formatting (POST /api/sql/format) and linting (POST /api/sql/lint)
+export type SqlDialect = 'postgresql' | 'mysql' | 'doris' | 'druid' | 'generic';
+
+export interface FormatStyle {
+  pretty?: boolean;
+  uppercase_keywords?: boolean;
+  indent?: number;
+}
+
+export interface FormatSqlRequest {
+  sql: string;
+  dialect?: SqlDialect;
+  style?: FormatStyle;
+}
+
+export interface LintSqlRequest {
+  sql: string;
+  dialect?: SqlDialect;
+}
+
+export type LintRule = 'select_star' | 'missing_limit' | 'cartesian_join' | 'non_sargable_predicate';
+
+export interface LintIssue {
+  rule: LintRule;
+  severity: 'warning' | 'info';
+  message: string;
+  fragment?: string;
+  line?: number;
+  column?: number;
+}
+
+// Unified SQL translation preview (POST /api/sql/translate)
+export interface TranslateSqlRequest {
+  sql: string;
+  dialect: Exclude<SqlDialect, 'generic'>;
+}
+
+export interface SqlRewrite {
+  kind: 'identifier_quoting' | 'function' | 'type_name' | 'interval';
+  original: string;
+  translated: string;
+  line: number;
+  column: number;
+}
+
+export interface TranslatedSqlResponse {
+  dialect: string;
+  translated_sql: string;
+  rewrites: SqlRewrite[];
+}
+
+// Semantic metrics (GET/PUT /api/domains/{id}/metrics)
+export type MeasureAggregation = 'sum' | 'count' | 'count_distinct' | 'avg' | 'min' | 'max';
+export type TimeGrain = 'day' | 'week' | 'month' | 'quarter' | 'year';
+
+export interface MetricDimension {
+  name: string;
+  expr?: string;
+  description?: string;
+}
+
+export interface MetricMeasure {
+  name: string;
+  agg: MeasureAggregation;
+  expr?: string;
+  description?: string;
+}
+
+export interface MetricEntity {
+  name: string;
+  table: string;
+  time_dimension?: string;
+  dimensions: MetricDimension[];
+  measures: MetricMeasure[];
+}
+
+export interface MetricJoin {
+  from: string;
+  to: string;
+  on: { from: string; to: string }[];
+}
+
+export interface MetricsDefinition {
+  entities: MetricEntity[];
+  joins: MetricJoin[];
+}
+
+export interface DomainMetrics extends MetricsDefinition {
+  domain_id: string;
+  updated_at: string;
+}
+
+export interface CompileMetricRequest {
+  metric: string;
+  group_by?: string[];
+  grain?: TimeGrain;
+  connection_id: string;
+  limit?: number;
+}
+
+export interface CompiledMetric {
+  connection_id: string;
+  dialect: string;
+  sql: string;
+  columns: string[];
+}
+
+// Running synchronous query (POST /api/queries/{id}/cancel); id is the request's X-Request-Id
+export interface RunningQuery {
+  id: string;
+  connection_id: string;
+  domain_id?: string;
+  started_by?: string;
+  started_at: string;
+}
+
+// Write-mode transaction sessions (/api/sessions/{id})
+export interface TransactionSession {
+  id: string;
+  connection_id: string;
+  domain_id?: string;
+  opened_by?: string;
+  status: 'open' | 'committed' | 'rolled_back';
+  statement_count: number;
+  affected_rows: number;
+  opened_at: string;
+  last_used_at: string;
+  expires_at: string;
+}
+
+// Domain webhooks (/api/domains/{id}/webhooks)
+export type WebhookEvent =
+  | 'query_job.completed'
+  | 'query_job.failed'
+  | 'query.failed'
+  | 'connection.status_changed'
+  | 'metadata.changed'
+  | 'saved_query.broken'
+  | 'saved_query.schema_drift';
+
+export interface Webhook {
+  id: string;
+  domain_id: string;
+  url: string;
+  events: WebhookEvent[];
+  created_at: string;
+}
+
+export interface WebhookDelivery {
+  id: string;
+  webhook_id: string;
+  event: WebhookEvent;
+  payload: Record<string, unknown>;
+  status: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  next_attempt_at: string;
+  last_error?: string;
+  created_at: string;
+  delivered_at?: string;
+}
+
+// Auth types
+export type DomainRole = 'viewer' | 'editor' | 'admin';
+
+export interface User {
+  id: string;
+  username: string;
+  is_admin: boolean;
+  created_at: string;
+}
+
+export interface LoginResponse {
+  token: string;
+  token_type: string;
+  expires_at: string;
+  user: User;
+}
+
+export interface DomainRoleAssignment {
+  user_id: string;
+  domain_id: string;
+  role: DomainRole;
+  assigned_at: string;
+}
+
+// List endpoints: ?page=&page_size=&sort=&filter=
+export interface ListParams {
+  page?: number;
+  page_size?: number;
+  /** Field name, prefixed with '-' for descending order */
+  sort?: string;
+  filter?: string;
+}
+
+export interface PageInfo {
+  total: number;
+  page: number;
+  page_size: number;
+}
+
+// Policy applied to connections whose labels match every selector entry
+export interface ConnectionPolicy {
+  id: string;
+  name: string;
+  selector: Record<string, string>;
+  masked_columns: string[];
+  max_queries_per_hour?: number;
+  created_at: string;
 }
 
 // Re-export cross-database types
@@ -144,6 +1061,11 @@ export type {
   CrossDatabaseQueryResponse,
   SubQueryResult,
   DatabaseAlias,
+  CatalogSchema,
+  CatalogConnection,
+  FederatedCatalog,
+  CrossDatabaseNaturalLanguageRequest,
+  CrossDatabaseNaturalLanguageResponse,
   CrossDatabaseQueryError,
 } from './cross-database';
 